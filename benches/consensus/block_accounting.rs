@@ -0,0 +1,151 @@
+//! Block Accounting Benchmarks
+//! Weight, stripped size and sigop cost of real blocks
+//!
+//! Every block is weighed and sigop-counted against the consensus limits before
+//! a script runs, and the accounting differential does it all again per block,
+//! so these hot paths are timed on their own:
+//!
+//! | bench                 | work                                        | Core `bench_bitcoin` |
+//! |-----------------------|---------------------------------------------|----------------------|
+//! | deserialize           | decode (baseline for the rows below)        | DeserializeBlockTest |
+//! | deserialize_and_check | decode + check_block                        | DeserializeAndCheckBlockTest |
+//! | weight                | calculate_block_weight                      | part of ContextualCheckBlock |
+//! | stripped_size         | size without witness data                   | part of CheckBlock |
+//! | legacy_sigops         | GetLegacySigOpCount over the block          | part of CheckBlock |
+//! | sigop_cost            | legacy + P2SH + witness cost, from the wire | part of ConnectBlock |
+//!
+//! BLVM doesn't expose its sigop counter outside block connection, so the sigop
+//! rows time the Core-rule counter of `accounting_differential`. `sigop_cost`
+//! needs prevouts and only runs on blocks with a UTXO pre-state.
+//!
+//! Blocks come from the extracted corpus (`blvm-bench corpus-extract`,
+//! `BLVM_BLOCK_CORPUS`) and the notable-block fixtures (`blvm-bench
+//! build-fixtures`, `BLVM_BLOCK_FIXTURES`), at most a bench config's
+//! `corpus_limit` of them. With neither, a synthetic block of signed spends
+//! stands in. Core's two block benches decode block 413567; extract it with
+//! `corpus-extract --heights 413567` and `compare-core` lines the rows up (see
+//! `core_bench_compare::MAPPINGS`). Throughput is in block bytes.
+
+use blvm_bench::accounting_differential::{block_sigop_cost, legacy_sigop_cost, stripped_size};
+use blvm_bench::bench_config::{BenchConfig, GroupSettings};
+use blvm_bench::bench_fixtures::{build_block, serialize_block, signed_spends, SpendKind, POST_TAPROOT_HEIGHT};
+use blvm_bench::block_corpus::{default_corpus_dir, load_corpus};
+use blvm_bench::block_fixtures::{default_fixtures_dir, load_fixture, NOTABLE_BLOCKS};
+use blvm_consensus::block::check_block;
+use blvm_consensus::segwit::calculate_block_weight;
+use blvm_consensus::serialization::block::deserialize_block_with_witnesses;
+use blvm_consensus::UtxoSet;
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+
+/// Spends of each kind in the synthetic stand-in block
+const SYNTHETIC_SPENDS_PER_KIND: usize = 100;
+
+struct AccountingBlock {
+    /// Height for corpus blocks, fixture name otherwise
+    id: String,
+    height: u64,
+    bytes: Vec<u8>,
+    pre_state: Option<UtxoSet>,
+}
+
+fn load_blocks(limit: usize) -> Vec<AccountingBlock> {
+    let corpus_dir = default_corpus_dir();
+    let mut blocks: Vec<AccountingBlock> = load_corpus(&corpus_dir)
+        .expect("failed to load block corpus")
+        .into_iter()
+        .take(limit)
+        .map(|(entry, bytes)| AccountingBlock {
+            id: entry.height.to_string(),
+            height: entry.height,
+            bytes,
+            pre_state: None,
+        })
+        .collect();
+    if blocks.is_empty() {
+        eprintln!(
+            "⚠️  No block corpus in {} (run `blvm-bench corpus-extract`)",
+            corpus_dir.display()
+        );
+    }
+
+    let fixtures_dir = default_fixtures_dir();
+    for (name, height) in NOTABLE_BLOCKS.iter().take(limit.saturating_sub(blocks.len())) {
+        match load_fixture(&fixtures_dir, name, *height).expect("failed to load fixture") {
+            Some(fixture) => blocks.push(AccountingBlock {
+                id: fixture.name,
+                height: fixture.height,
+                bytes: fixture.block_bytes,
+                pre_state: Some(fixture.pre_state),
+            }),
+            None => eprintln!(
+                "⚠️  Skipping {}: no fixture in {} (run `blvm-bench build-fixtures`)",
+                name,
+                fixtures_dir.display()
+            ),
+        }
+    }
+
+    if blocks.is_empty() {
+        let spends = SpendKind::ALL
+            .iter()
+            .flat_map(|kind| signed_spends(*kind, SYNTHETIC_SPENDS_PER_KIND))
+            .collect();
+        let (block, witnesses, utxo_set) = build_block(spends, POST_TAPROOT_HEIGHT);
+        blocks.push(AccountingBlock {
+            id: "synthetic".to_string(),
+            height: POST_TAPROOT_HEIGHT,
+            bytes: serialize_block(&block, &witnesses),
+            pre_state: Some(utxo_set),
+        });
+    }
+    blocks
+}
+
+fn benchmark_block_accounting(c: &mut Criterion) {
+    let config = BenchConfig::get();
+    let defaults = GroupSettings { sample_size: Some(20), ..Default::default() };
+    let Some(mut group) = config.group(c, "block_accounting", defaults) else {
+        return;
+    };
+
+    for block in load_blocks(config.corpus_limit("block_accounting", usize::MAX)) {
+        let (decoded, witnesses) = deserialize_block_with_witnesses(&block.bytes).expect("block deserializes");
+        group.throughput(Throughput::Bytes(block.bytes.len() as u64));
+
+        group.bench_function(BenchmarkId::new("deserialize", &block.id), |b| {
+            b.iter(|| black_box(deserialize_block_with_witnesses(black_box(&block.bytes))))
+        });
+        group.bench_function(BenchmarkId::new("deserialize_and_check", &block.id), |b| {
+            b.iter(|| {
+                let (decoded, _) =
+                    deserialize_block_with_witnesses(black_box(&block.bytes)).expect("block deserializes");
+                black_box(check_block(&decoded))
+            })
+        });
+        group.bench_function(BenchmarkId::new("weight", &block.id), |b| {
+            b.iter(|| black_box(calculate_block_weight(black_box(&decoded), black_box(&witnesses))))
+        });
+        group.bench_function(BenchmarkId::new("stripped_size", &block.id), |b| {
+            b.iter(|| black_box(stripped_size(black_box(&decoded))))
+        });
+        group.bench_function(BenchmarkId::new("legacy_sigops", &block.id), |b| {
+            b.iter(|| black_box(legacy_sigop_cost(black_box(&decoded))))
+        });
+
+        let Some(pre_state) = &block.pre_state else {
+            continue;
+        };
+        // A missing prevout returns early, which would time nothing
+        if block_sigop_cost(&block.bytes, block.height, pre_state).expect("block parses").is_none() {
+            eprintln!("⚠️  Skipping sigop_cost for {}: pre-state is missing prevouts", block.id);
+            continue;
+        }
+        group.bench_function(BenchmarkId::new("sigop_cost", &block.id), |b| {
+            b.iter(|| black_box(block_sigop_cost(black_box(&block.bytes), block.height, black_box(pre_state))))
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, benchmark_block_accounting);
+criterion_main!(benches);
@@ -0,0 +1,87 @@
+//! Recorded Input Bundle Benchmarks
+//! Replays blocks recorded by a differential run (each with the exact UTXOs it
+//! spends) through connect_block, giving offline, repeatable end-to-end
+//! validation numbers over any stretch of the chain
+//!
+//! Record bundles by running the parallel differential with
+//! `BLVM_INPUT_BUNDLES=<dir>`, then run this bench with the same variable set.
+//! `BLVM_BUNDLE_LIMIT` (or the bench config's `corpus_limit`) caps how many
+//! bundles are loaded (lowest heights first).
+
+use blvm_bench::bench_config::{BenchConfig, GroupSettings};
+use blvm_bench::input_bundles::{input_bundle_dir_from_env, list_bundles, load_bundle};
+use blvm_consensus::block::connect_block;
+use blvm_consensus::serialization::block::deserialize_block_with_witnesses;
+use blvm_consensus::types::{Network, ValidationResult};
+use criterion::{black_box, criterion_group, criterion_main, Criterion, Throughput};
+
+fn benchmark_connect_block_bundles(c: &mut Criterion) {
+    let config = BenchConfig::get();
+    let defaults = GroupSettings { sample_size: Some(10), ..Default::default() };
+    let Some(mut group) = config.group(c, "connect_block_bundles", defaults) else {
+        return;
+    };
+    let dir = match input_bundle_dir_from_env() {
+        Some(dir) => dir,
+        None => {
+            eprintln!("⚠️  Skipping: set BLVM_INPUT_BUNDLES to a directory of recorded input bundles");
+            return;
+        }
+    };
+    let limit = std::env::var("BLVM_BUNDLE_LIMIT")
+        .ok()
+        .map(|n| n.parse::<usize>().expect("invalid BLVM_BUNDLE_LIMIT"))
+        .unwrap_or_else(|| config.corpus_limit("connect_block_bundles", usize::MAX));
+
+    let mut blocks = Vec::new();
+    let mut total_bytes = 0u64;
+    let mut total_txs = 0u64;
+    for (height, path) in list_bundles(&dir).expect("failed to list bundles").into_iter().take(limit) {
+        let bundle = load_bundle(&path).expect("failed to load bundle");
+        let (block, witnesses) =
+            deserialize_block_with_witnesses(&bundle.block_bytes).expect("bundle block deserializes");
+
+        // A rejected bundle would time the early-exit path, not validation
+        let (result, _, _) = connect_block(&block, &witnesses, bundle.pre_state.clone(), height, None, Network::Mainnet)
+            .expect("connect_block failed");
+        assert!(matches!(result, ValidationResult::Valid), "bundle at height {} rejected: {:?}", height, result);
+
+        total_bytes += bundle.block_bytes.len() as u64;
+        total_txs += block.transactions.len() as u64;
+        blocks.push((height, block, witnesses, bundle.pre_state));
+    }
+    if blocks.is_empty() {
+        eprintln!("⚠️  Skipping: no input bundles in {}", dir.display());
+        return;
+    }
+    eprintln!(
+        "Replaying {} bundles (heights {}-{}, {} transactions, {:.1} MB)",
+        blocks.len(),
+        blocks[0].0,
+        blocks[blocks.len() - 1].0,
+        total_txs,
+        total_bytes as f64 / 1e6
+    );
+
+    // Blocks/sec; bytes and transactions per block are printed above for scale
+    group.throughput(Throughput::Elements(blocks.len() as u64));
+    group.bench_function("replay", |b| {
+        b.iter(|| {
+            for (height, block, witnesses, pre_state) in &blocks {
+                black_box(connect_block(
+                    black_box(block),
+                    black_box(witnesses),
+                    black_box(pre_state.clone()),
+                    black_box(*height),
+                    black_box(None),
+                    black_box(Network::Mainnet),
+                ))
+                .ok();
+            }
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(benches, benchmark_connect_block_bundles);
+criterion_main!(benches);
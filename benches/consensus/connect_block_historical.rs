@@ -0,0 +1,79 @@
+//! Historical Block Connect Benchmarks
+//! Replays notable mainnet blocks (block 1, the 2015 spam cleanup, a max-size
+//! segwit block, a taproot-heavy block) against their pre-built UTXO fixtures,
+//! giving end-to-end connect_block throughput a stable regression baseline
+//!
+//! Fixtures are not committed; build them once with
+//! `blvm-bench build-fixtures` (needs a Core node with RPC) or point
+//! `BLVM_BLOCK_FIXTURES` at an existing set. Missing fixtures are skipped;
+//! a bench config `corpus_limit` replays only the first blocks.
+
+use blvm_bench::bench_config::{BenchConfig, GroupSettings};
+use blvm_bench::block_fixtures::{default_fixtures_dir, load_fixture, NOTABLE_BLOCKS};
+use blvm_consensus::block::connect_block;
+use blvm_consensus::serialization::block::deserialize_block_with_witnesses;
+use blvm_consensus::types::{Network, ValidationResult};
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+
+fn benchmark_connect_block_historical(c: &mut Criterion) {
+    let config = BenchConfig::get();
+    let defaults = GroupSettings { sample_size: Some(10), ..Default::default() };
+    let Some(mut group) = config.group(c, "connect_block_historical", defaults) else {
+        return;
+    };
+    let fixtures_dir = default_fixtures_dir();
+    let limit = config.corpus_limit("connect_block_historical", NOTABLE_BLOCKS.len());
+
+    for (name, height) in NOTABLE_BLOCKS.iter().take(limit) {
+        let fixture = match load_fixture(&fixtures_dir, name, *height).expect("failed to load fixture") {
+            Some(f) => f,
+            None => {
+                eprintln!(
+                    "⚠️  Skipping {}: no fixture in {} (run `blvm-bench build-fixtures`)",
+                    name,
+                    fixtures_dir.display()
+                );
+                continue;
+            }
+        };
+
+        let (block, witnesses) =
+            deserialize_block_with_witnesses(&fixture.block_bytes).expect("fixture block deserializes");
+
+        // A rejected fixture would time the early-exit path, not validation
+        let (result, _, _) = connect_block(
+            &block,
+            &witnesses,
+            fixture.pre_state.clone(),
+            fixture.height,
+            None,
+            Network::Mainnet,
+        )
+        .expect("connect_block failed");
+        assert!(
+            matches!(result, ValidationResult::Valid),
+            "{} (height {}) rejected: {:?}",
+            name,
+            height,
+            result
+        );
+
+        group.throughput(Throughput::Bytes(fixture.block_bytes.len() as u64));
+        group.bench_function(BenchmarkId::new(*name, height), |b| {
+            b.iter(|| {
+                black_box(connect_block(
+                    black_box(&block),
+                    black_box(&witnesses),
+                    black_box(fixture.pre_state.clone()),
+                    black_box(fixture.height),
+                    black_box(None),
+                    black_box(Network::Mainnet),
+                ))
+            })
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, benchmark_connect_block_historical);
+criterion_main!(benches);
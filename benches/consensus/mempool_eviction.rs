@@ -0,0 +1,592 @@
+//! Mempool Eviction and Fee-Rate Ordering at Scale
+//!
+//! Matches the scope of Core's MempoolEviction bench, at the size Core
+//! actually runs: a mempool filled to the default `-maxmempool` (300 MB of
+//! memory usage) with realistic packages - singletons, CPFP chains and
+//! fan-outs, some hanging off earlier mempool transactions - then measures:
+//!
+//! - trimming back to the limit after a block's worth of new packages arrives
+//!   (lowest descendant score first, like `TrimToSize`)
+//! - ordering the whole pool by ancestor fee-rate (block template order)
+//! - ancestor/descendant limit enforcement for new children
+//! - parent+child package acceptance (`accept_to_memory_pool` per member,
+//!   limits, and the aggregate package fee-rate against the rolling minimum)
+//!
+//! `Mempool` in blvm-consensus is only a txid set, so fees, sizes and the
+//! ancestor/descendant bookkeeping live in the `Pool` model below.
+
+use blvm_bench::bench_fixtures::splitmix64;
+use blvm_consensus::block::calculate_tx_id;
+use blvm_consensus::mempool::{accept_to_memory_pool, Mempool, MempoolResult};
+use blvm_consensus::serialization::transaction::serialize_transaction;
+use blvm_consensus::{OutPoint, Transaction, TransactionInput, TransactionOutput, UtxoSet, UTXO};
+use criterion::{black_box, criterion_group, criterion_main, BatchSize, Criterion};
+use std::cmp::Ordering;
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::time::{Duration, Instant};
+
+/// Core's default -maxmempool
+const MAX_MEMPOOL_USAGE: u64 = 300_000_000;
+/// Per-entry memory beyond the transaction itself (entry, index nodes, links)
+const ENTRY_OVERHEAD: u64 = 320;
+/// Core's DEFAULT_ANCESTOR_LIMIT / DEFAULT_DESCENDANT_LIMIT
+const MAX_CHAIN_COUNT: u64 = 25;
+/// Core's DEFAULT_ANCESTOR_SIZE_LIMIT_KVB / DEFAULT_DESCENDANT_SIZE_LIMIT_KVB
+const MAX_CHAIN_VSIZE: u64 = 101_000;
+/// Core's DEFAULT_INCREMENTAL_RELAY_FEE (sat/vB)
+const INCREMENTAL_RELAY_FEE: u64 = 1;
+/// Arrivals before each trim: one block's worth of vsize
+const INCOMING_VSIZE: u64 = 1_000_000;
+/// Packages per acceptance measurement
+const PACKAGES_PER_ITER: usize = 1_000;
+
+/// Fee-rate as an exact fraction (compared by cross-multiplication)
+#[derive(Debug, Clone, Copy)]
+struct FeeRate {
+    fee: u64,
+    vsize: u64,
+}
+
+impl Ord for FeeRate {
+    fn cmp(&self, other: &Self) -> Ordering {
+        (self.fee as u128 * other.vsize as u128).cmp(&(other.fee as u128 * self.vsize as u128))
+    }
+}
+
+impl PartialOrd for FeeRate {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for FeeRate {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for FeeRate {}
+
+/// Count, vsize and fees of an entry plus its ancestors (or descendants)
+#[derive(Debug, Clone, Copy, Default)]
+struct Aggregate {
+    count: u64,
+    vsize: u64,
+    fees: u64,
+}
+
+#[derive(Debug, Clone)]
+struct PoolEntry {
+    txid: [u8; 32],
+    fee: u64,
+    vsize: u64,
+    usage: u64,
+    parents: Vec<usize>,
+    children: Vec<usize>,
+    ancestors: Aggregate,
+    descendants: Aggregate,
+    removed: bool,
+}
+
+impl PoolEntry {
+    /// Eviction key: the better of the entry's own and its package's fee-rate
+    fn descendant_score(&self) -> FeeRate {
+        let own = FeeRate { fee: self.fee, vsize: self.vsize };
+        let package = FeeRate { fee: self.descendants.fees, vsize: self.descendants.vsize };
+        own.max(package)
+    }
+
+    /// Mining key: the worse of the entry's own and its ancestors' fee-rate
+    fn ancestor_score(&self) -> FeeRate {
+        let own = FeeRate { fee: self.fee, vsize: self.vsize };
+        let package = FeeRate { fee: self.ancestors.fees, vsize: self.ancestors.vsize };
+        own.min(package)
+    }
+}
+
+/// Mempool with Core's ancestor/descendant bookkeeping and eviction index
+#[derive(Debug, Clone, Default)]
+struct Pool {
+    entries: Vec<PoolEntry>,
+    by_txid: HashMap<[u8; 32], usize>,
+    eviction: BTreeSet<(FeeRate, usize)>,
+    usage: u64,
+    /// Rolling minimum fee-rate in sat/vB, bumped by evictions
+    min_fee_rate: u64,
+}
+
+impl Pool {
+    /// All in-pool ancestors of a transaction with the given parents
+    fn ancestors_of(&self, parents: &[usize]) -> Vec<usize> {
+        let mut seen: HashSet<usize> = parents.iter().copied().collect();
+        let mut stack: Vec<usize> = parents.to_vec();
+        while let Some(idx) = stack.pop() {
+            for &parent in &self.entries[idx].parents {
+                if seen.insert(parent) {
+                    stack.push(parent);
+                }
+            }
+        }
+        seen.into_iter().collect()
+    }
+
+    /// `idx` and everything that descends from it
+    fn descendants_of(&self, idx: usize) -> Vec<usize> {
+        let mut seen = HashSet::from([idx]);
+        let mut stack = vec![idx];
+        while let Some(idx) = stack.pop() {
+            for &child in &self.entries[idx].children {
+                if seen.insert(child) {
+                    stack.push(child);
+                }
+            }
+        }
+        seen.into_iter().collect()
+    }
+
+    /// Core's ancestor/descendant count and size limits for `count` new transactions
+    /// (a single transaction or a package) of total `vsize` attached below `parents`
+    fn check_limits(&self, parents: &[usize], count: u64, vsize: u64) -> Result<Vec<usize>, &'static str> {
+        let ancestors = self.ancestors_of(parents);
+        if ancestors.len() as u64 + count > MAX_CHAIN_COUNT {
+            return Err("too-long-mempool-chain: too many ancestors");
+        }
+        let ancestor_vsize: u64 = ancestors.iter().map(|&a| self.entries[a].vsize).sum();
+        if ancestor_vsize + vsize > MAX_CHAIN_VSIZE {
+            return Err("too-long-mempool-chain: exceeds ancestor size limit");
+        }
+        for &a in &ancestors {
+            let descendants = &self.entries[a].descendants;
+            if descendants.count + count > MAX_CHAIN_COUNT {
+                return Err("too-long-mempool-chain: too many descendants");
+            }
+            if descendants.vsize + vsize > MAX_CHAIN_VSIZE {
+                return Err("too-long-mempool-chain: exceeds descendant size limit");
+            }
+        }
+        Ok(ancestors)
+    }
+
+    fn add(&mut self, txid: [u8; 32], fee: u64, vsize: u64, parents: Vec<usize>) -> usize {
+        let idx = self.entries.len();
+        let ancestors = self.ancestors_of(&parents);
+        let mut ancestor_agg = Aggregate { count: 1, vsize, fees: fee };
+        for &a in &ancestors {
+            ancestor_agg.count += 1;
+            ancestor_agg.vsize += self.entries[a].vsize;
+            ancestor_agg.fees += self.entries[a].fee;
+            self.eviction.remove(&(self.entries[a].descendant_score(), a));
+            let entry = &mut self.entries[a];
+            entry.descendants.count += 1;
+            entry.descendants.vsize += vsize;
+            entry.descendants.fees += fee;
+            self.eviction.insert((entry.descendant_score(), a));
+        }
+        for &parent in &parents {
+            self.entries[parent].children.push(idx);
+        }
+        let entry = PoolEntry {
+            txid,
+            fee,
+            vsize,
+            usage: ENTRY_OVERHEAD + 2 * vsize,
+            parents,
+            children: Vec::new(),
+            ancestors: ancestor_agg,
+            descendants: Aggregate { count: 1, vsize, fees: fee },
+            removed: false,
+        };
+        self.usage += entry.usage;
+        self.eviction.insert((entry.descendant_score(), idx));
+        self.by_txid.insert(txid, idx);
+        self.entries.push(entry);
+        idx
+    }
+
+    /// Evict lowest-descendant-score packages until usage fits `limit`; returns the number of evicted entries
+    fn trim_to_size(&mut self, limit: u64) -> usize {
+        let mut evicted = 0;
+        while self.usage > limit {
+            let Some((score, idx)) = self.eviction.pop_first() else { break };
+            // The next transaction must beat what was just evicted
+            let rate = score.fee.div_ceil(score.vsize) + INCREMENTAL_RELAY_FEE;
+            self.min_fee_rate = self.min_fee_rate.max(rate);
+
+            let package = self.descendants_of(idx);
+            for &r in &package {
+                if r != idx {
+                    self.eviction.remove(&(self.entries[r].descendant_score(), r));
+                }
+                self.entries[r].removed = true;
+            }
+            for &r in &package {
+                let (fee, vsize) = (self.entries[r].fee, self.entries[r].vsize);
+                let parents = self.entries[r].parents.clone();
+                for a in self.ancestors_of(&parents) {
+                    if self.entries[a].removed {
+                        continue;
+                    }
+                    self.eviction.remove(&(self.entries[a].descendant_score(), a));
+                    let entry = &mut self.entries[a];
+                    entry.descendants.count -= 1;
+                    entry.descendants.vsize -= vsize;
+                    entry.descendants.fees -= fee;
+                    self.eviction.insert((entry.descendant_score(), a));
+                }
+                self.usage -= self.entries[r].usage;
+                self.by_txid.remove(&self.entries[r].txid);
+            }
+            for &r in &package {
+                let parents = std::mem::take(&mut self.entries[r].parents);
+                for parent in parents {
+                    self.entries[parent].children.retain(|&c| c != r);
+                }
+            }
+            evicted += package.len();
+        }
+        evicted
+    }
+
+    /// Live entries, best ancestor fee-rate first
+    fn ancestor_score_order(&self) -> Vec<usize> {
+        let mut order: Vec<usize> = (0..self.entries.len()).filter(|&i| !self.entries[i].removed).collect();
+        order.sort_unstable_by(|&a, &b| self.entries[b].ancestor_score().cmp(&self.entries[a].ancestor_score()));
+        order
+    }
+}
+
+/// Height the acceptance benchmark validates at
+const TIP_HEIGHT: u64 = 900_000;
+
+fn confirmed_outpoint(id: u64) -> OutPoint {
+    let mut hash = [0u8; 32];
+    for (i, word) in hash.chunks_mut(8).enumerate() {
+        word.copy_from_slice(&splitmix64(id.wrapping_mul(4).wrapping_add(i as u64)).to_le_bytes());
+    }
+    OutPoint {
+        hash,
+        index: (id % 4) as _,
+    }
+}
+
+fn confirmed_value(id: u64) -> u64 {
+    1_000_000 + splitmix64(id ^ 0xc0ffee) % 9_000_000
+}
+
+/// Signature + pubkey sized pushes (a P2PKH spend's 107 bytes); the OP_1 output script ignores them
+fn p2pkh_sized_script_sig() -> Vec<u8> {
+    let mut script_sig = vec![72];
+    script_sig.extend_from_slice(&[0x30; 72]);
+    script_sig.push(33);
+    script_sig.extend_from_slice(&[0x02; 33]);
+    script_sig
+}
+
+/// A generated transaction with what the pool needs to know about it
+#[derive(Debug, Clone)]
+struct GeneratedTx {
+    tx: Transaction,
+    txid: [u8; 32],
+    fee: u64,
+    vsize: u64,
+    /// In-pool parents (pool indices)
+    parents: Vec<usize>,
+}
+
+impl GeneratedTx {
+    fn output(&self, index: usize) -> (OutPoint, u64) {
+        (
+            OutPoint { hash: self.txid, index: index as _ },
+            self.tx.outputs[index].value as u64,
+        )
+    }
+}
+
+/// Deterministic package generator over an endless supply of confirmed coins
+#[derive(Debug, Clone, Default)]
+struct Generator {
+    counter: u64,
+    /// Confirmed coins handed out so far (ids 1..=next_coin)
+    next_coin: u64,
+    /// Unspent change outputs of pool entries: (pool index, outpoint, value)
+    change: Vec<(usize, OutPoint, u64)>,
+}
+
+impl Generator {
+    fn next(&mut self) -> u64 {
+        self.counter += 1;
+        splitmix64(self.counter)
+    }
+
+    /// 1..~200 sat/vB, most of the pool near the bottom like a real backlog
+    fn fee_rate(&mut self) -> u64 {
+        let r = self.next() % 1_000;
+        1 + r * r / 5_000
+    }
+
+    fn confirmed_coin(&mut self) -> (OutPoint, u64) {
+        self.next_coin += 1;
+        (confirmed_outpoint(self.next_coin), confirmed_value(self.next_coin))
+    }
+
+    /// Spend a fresh confirmed coin or, one time in ten, the change of an in-pool entry
+    fn root_input(&mut self, pool: &Pool) -> ((OutPoint, u64), Option<usize>) {
+        if !self.change.is_empty() && self.next() % 10 == 0 {
+            let pick = self.next() as usize % self.change.len();
+            let (idx, outpoint, value) = self.change.swap_remove(pick);
+            if !pool.entries[idx].removed {
+                return ((outpoint, value), Some(idx));
+            }
+        }
+        (self.confirmed_coin(), None)
+    }
+
+    /// A transaction spending `inputs` into `outputs` outputs plus a change output
+    fn transaction(&mut self, inputs: &[(OutPoint, u64)], outputs: usize, fee_rate: u64, parents: Vec<usize>) -> GeneratedTx {
+        let input_value: u64 = inputs.iter().map(|(_, value)| value).sum();
+        let mut tx = Transaction {
+            version: 2,
+            inputs: inputs
+                .iter()
+                .map(|(prevout, _)| TransactionInput {
+                    prevout: prevout.clone(),
+                    script_sig: p2pkh_sized_script_sig(),
+                    sequence: 0xffff_fffd,
+                })
+                .collect::<Vec<_>>()
+                .into(),
+            outputs: (0..=outputs)
+                .map(|_| TransactionOutput {
+                    value: 0,
+                    script_pubkey: vec![0x51],
+                })
+                .collect::<Vec<_>>()
+                .into(),
+            lock_time: 0,
+        };
+        let vsize = serialize_transaction(&tx).len() as u64;
+        let fee = (fee_rate * vsize).min(input_value / 2);
+        let each = (input_value - fee) / (outputs as u64 + 1);
+        for output in tx.outputs.iter_mut() {
+            output.value = each as _;
+        }
+        let txid = calculate_tx_id(&tx);
+        GeneratedTx { tx, txid, fee, vsize, parents }
+    }
+
+    /// One package, parents first; in-package parents are the pool indices they will get when added in order
+    fn package(&mut self, pool: &Pool) -> Vec<GeneratedTx> {
+        let base = pool.entries.len();
+        let (input, anchor) = self.root_input(pool);
+        let parents: Vec<usize> = anchor.into_iter().collect();
+        let rate = self.fee_rate();
+        let kind = self.next() % 100;
+        let mut package = Vec::new();
+        if kind < 60 {
+            // Singleton, sometimes consolidating a second coin
+            let mut inputs = vec![input];
+            if self.next() % 3 == 0 {
+                inputs.push(self.confirmed_coin());
+            }
+            package.push(self.transaction(&inputs, 1, rate, parents));
+        } else if kind < 85 {
+            // CPFP chain: each child spends its parent's first output at a higher fee-rate
+            let depth = 2 + self.next() % 3;
+            let mut tx = self.transaction(&[input], 1, rate, parents);
+            for level in 1..depth {
+                let child = self.transaction(&[tx.output(0)], 1, (rate * (level + 2)).min(500), vec![base + package.len()]);
+                package.push(tx);
+                tx = child;
+            }
+            package.push(tx);
+        } else {
+            // Fan-out: one parent paying several recipients who each spend onwards
+            let fanout = 2 + self.next() as usize % 4;
+            let parent = self.transaction(&[input], fanout, rate, parents);
+            let spends: Vec<_> = (0..fanout).map(|i| parent.output(i)).collect();
+            package.push(parent);
+            for spend in spends {
+                let child_rate = self.fee_rate().max(rate);
+                package.push(self.transaction(&[spend], 1, child_rate, vec![base]));
+            }
+        }
+        package
+    }
+
+    /// A low-fee parent and the child that pays for it
+    fn parent_child(&mut self, pool: &Pool) -> Vec<GeneratedTx> {
+        let base = pool.entries.len();
+        let (input, anchor) = self.root_input(pool);
+        let parent = self.transaction(&[input], 1, 1, anchor.into_iter().collect());
+        let child_rate = self.fee_rate() * 4;
+        let child = self.transaction(&[parent.output(0)], 1, child_rate, vec![base]);
+        vec![parent, child]
+    }
+
+    /// Add packages that pass the chain limits until `done`; returns everything added
+    fn fill(&mut self, pool: &mut Pool, done: impl Fn(&Pool, u64) -> bool) -> Vec<GeneratedTx> {
+        let mut added = Vec::new();
+        let mut added_vsize = 0;
+        while !done(pool, added_vsize) {
+            let package = self.package(pool);
+            let vsize: u64 = package.iter().map(|g| g.vsize).sum();
+            if pool.check_limits(&package[0].parents, package.len() as u64, vsize).is_err() {
+                continue;
+            }
+            for g in &package {
+                let idx = pool.add(g.txid, g.fee, g.vsize, g.parents.clone());
+                let change = g.tx.outputs.len() - 1;
+                let (outpoint, value) = g.output(change);
+                self.change.push((idx, outpoint, value));
+            }
+            added_vsize += vsize;
+            added.extend(package);
+        }
+        added
+    }
+}
+
+/// Package acceptance: fee-rate floor, chain limits, then each member through `accept_to_memory_pool`
+fn accept_package(
+    package: &[GeneratedTx],
+    pool: &Pool,
+    utxo_set: &mut UtxoSet,
+    mempool: &mut Mempool,
+) -> Result<(), String> {
+    let fees: u64 = package.iter().map(|g| g.fee).sum();
+    let vsize: u64 = package.iter().map(|g| g.vsize).sum();
+    // CPFP: only the aggregate has to clear the rolling minimum
+    if fees < pool.min_fee_rate.max(INCREMENTAL_RELAY_FEE) * vsize {
+        return Err("package-mempool-min-fee-not-met".to_string());
+    }
+    pool.check_limits(&package[0].parents, package.len() as u64, vsize)?;
+    for g in package {
+        match accept_to_memory_pool(&g.tx, None, utxo_set, mempool, TIP_HEIGHT) {
+            Ok(MempoolResult::Accepted) => {}
+            Ok(result) => return Err(format!("{:?}", result)),
+            Err(e) => return Err(format!("error: {}", e)),
+        }
+        mempool.insert(g.txid);
+        for (index, output) in g.tx.outputs.iter().enumerate() {
+            utxo_set.insert(
+                OutPoint { hash: g.txid, index: index as _ },
+                UTXO {
+                    value: output.value,
+                    script_pubkey: output.script_pubkey.clone(),
+                    height: TIP_HEIGHT as _,
+                    is_coinbase: false,
+                },
+            );
+        }
+    }
+    Ok(())
+}
+
+fn benchmark_mempool_at_scale(c: &mut Criterion) {
+    let mut generator = Generator::default();
+    let mut pool = Pool::default();
+    let pool_txs = generator.fill(&mut pool, |pool, _| pool.usage >= MAX_MEMPOOL_USAGE);
+
+    // A block's worth of arrivals, replayed onto a fresh copy of the full pool before each trim
+    let incoming = generator
+        .clone()
+        .fill(&mut pool.clone(), |_, added_vsize| added_vsize >= INCOMING_VSIZE);
+
+    let mut group = c.benchmark_group("mempool_eviction");
+    group.sample_size(10);
+    group.bench_function("trim_to_size_after_block", |b| {
+        b.iter_batched(
+            || {
+                let mut pool = pool.clone();
+                for g in &incoming {
+                    pool.add(g.txid, g.fee, g.vsize, g.parents.clone());
+                }
+                pool
+            },
+            |mut pool| black_box(pool.trim_to_size(MAX_MEMPOOL_USAGE)),
+            BatchSize::LargeInput,
+        )
+    });
+    group.finish();
+
+    let mut group = c.benchmark_group("mempool_fee_rate_ordering");
+    group.sample_size(10);
+    group.bench_function("ancestor_score_sort", |b| b.iter(|| black_box(pool.ancestor_score_order().len())));
+    group.bench_function("lowest_descendant_score_1k", |b| {
+        b.iter(|| black_box(pool.eviction.iter().take(1_000).map(|(_, idx)| *idx).sum::<usize>()))
+    });
+    group.finish();
+
+    // New children of random in-pool entries, deep chains included
+    let candidates: Vec<usize> = (0..1_000u64)
+        .map(|i| splitmix64(i ^ 0x1117) as usize % pool.entries.len())
+        .collect();
+    let mut group = c.benchmark_group("mempool_limits");
+    group.bench_function("descendant_limits_1k", |b| {
+        b.iter(|| {
+            candidates
+                .iter()
+                .filter(|&&idx| pool.check_limits(black_box(&[idx]), 1, 150).is_ok())
+                .count()
+        })
+    });
+    group.finish();
+
+    // Evict once so the rolling minimum fee is live, as it is on a full node
+    pool.trim_to_size(MAX_MEMPOOL_USAGE - INCOMING_VSIZE);
+    let packages: Vec<Vec<GeneratedTx>> = (0..PACKAGES_PER_ITER).map(|_| generator.parent_child(&pool)).collect();
+    let mut utxo_set = UtxoSet::new();
+    for id in 1..=generator.next_coin {
+        utxo_set.insert(
+            confirmed_outpoint(id),
+            UTXO {
+                value: confirmed_value(id) as _,
+                script_pubkey: vec![0x51],
+                height: (TIP_HEIGHT - 100) as _,
+                is_coinbase: false,
+            },
+        );
+    }
+    let mut mempool: Mempool = HashSet::new();
+    for g in pool_txs.iter().filter(|g| pool.by_txid.contains_key(&g.txid)) {
+        mempool.insert(g.txid);
+        for (index, output) in g.tx.outputs.iter().enumerate() {
+            utxo_set.insert(
+                OutPoint { hash: g.txid, index: index as _ },
+                UTXO {
+                    value: output.value,
+                    script_pubkey: output.script_pubkey.clone(),
+                    height: TIP_HEIGHT as _,
+                    is_coinbase: false,
+                },
+            );
+        }
+    }
+
+    let mut group = c.benchmark_group("mempool_package_acceptance");
+    group.sample_size(10);
+    group.bench_function("parent_child_1k", |b| {
+        b.iter_custom(|iters| {
+            let mut total = Duration::ZERO;
+            for _ in 0..iters {
+                let start = Instant::now();
+                for package in &packages {
+                    let _ = black_box(accept_package(package, &pool, &mut utxo_set, &mut mempool));
+                }
+                total += start.elapsed();
+                // Undo so the next iteration sees the same mempool
+                for g in packages.iter().flatten() {
+                    mempool.remove(&g.txid);
+                    for index in 0..g.tx.outputs.len() {
+                        utxo_set.remove(&OutPoint { hash: g.txid, index: index as _ });
+                    }
+                }
+            }
+            total
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(benches, benchmark_mempool_at_scale);
+criterion_main!(benches);
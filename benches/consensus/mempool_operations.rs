@@ -153,35 +153,6 @@ fn benchmark_replacement_checks(c: &mut Criterion) {
     });
 }
 
-fn benchmark_mempool_eviction(c: &mut Criterion) {
-    // Create a mempool with many transactions to test eviction logic
-    let mut mempool: Mempool = HashSet::new();
-    let mut utxo_set = UtxoSet::new();
-
-    // Add many transactions to mempool (simulate full mempool)
-    for i in 0..1000 {
-        let mut tx = create_test_transaction();
-        // Make each transaction unique
-        tx.inputs[0].prevout.hash[0] = (i % 256) as u8;
-        let tx_id = bllvm_consensus::block::calculate_tx_id(&tx);
-        mempool.insert(tx_id);
-    }
-
-    // Create a new transaction that would cause eviction
-    let new_tx = create_test_transaction();
-
-    c.bench_function("mempool_eviction", |b| {
-        b.iter(|| {
-            // Simulate eviction: check if mempool is full and would need eviction
-            // In real scenario, this would involve checking mempool size limits
-            // and removing lowest fee transactions
-            let mempool_size = black_box(mempool.len());
-            let _would_evict = black_box(mempool_size > 500); // Simulate size limit
-            black_box(mempool_size)
-        })
-    });
-}
-
 fn benchmark_accept_to_memory_pool_400tx(c: &mut Criterion) {
     // Create 400 transactions and accept them all (matches Core's MempoolCheck scale)
     let mut transactions = Vec::new();
@@ -358,7 +329,6 @@ criterion_group!(
     benchmark_mempool_acceptance_complex,
     benchmark_is_standard_tx,
     benchmark_replacement_checks,
-    benchmark_mempool_eviction,
     benchmark_accept_to_memory_pool_400tx,
     benchmark_is_standard_tx_400tx,
     benchmark_replacement_checks_mempool
@@ -0,0 +1,170 @@
+//! Merkle Root and Witness Commitment Benchmark
+//!
+//! Both run once per block on every connect: `calculate_merkle_root` (txids
+//! plus the tree) against the header, and the BIP141 witness merkle root
+//! (wtxids plus the tree) against the coinbase commitment. Blocks of 1, 100,
+//! 1000 and 4000 transactions cover an empty block up to a full block of
+//! small segwit spends. Transactions are P2WPKH spends with 1-3 inputs and
+//! 1-3 outputs drawn from a fixed SplitMix64 stream, so txid hashing sees
+//! realistic sizes and every run hashes the same bytes.
+//!
+//! `merkle_tree_precomputed` covers the tree alone from ready-made hashes.
+
+use blvm_bench::bench_fixtures::SplitMix64;
+use blvm_consensus::mining::calculate_merkle_root;
+use blvm_consensus::segwit::{compute_witness_merkle_root, validate_witness_commitment, Witness};
+use blvm_consensus::{
+    tx_inputs, tx_outputs, Block, BlockHeader, OutPoint, Transaction, TransactionInput,
+    TransactionOutput,
+};
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use sha2::{Digest, Sha256};
+
+const TX_COUNTS: [usize; 4] = [1, 100, 1000, 4000];
+
+/// BIP141 commitment header: OP_RETURN, push 36, 0xaa21a9ed
+const COMMITMENT_HEADER: [u8; 6] = [0x6a, 0x24, 0xaa, 0x21, 0xa9, 0xed];
+
+fn p2wpkh(rng: &mut SplitMix64) -> Vec<u8> {
+    [&[0x00, 0x14][..], &rng.bytes::<20>()].concat()
+}
+
+/// P2WPKH spend: empty scriptSig, signature + pubkey per input in the witness
+fn create_segwit_transaction(rng: &mut SplitMix64) -> (Transaction, Witness) {
+    let num_inputs = 1 + (rng.next_u64() % 3) as usize;
+    let num_outputs = 1 + (rng.next_u64() % 3) as usize;
+    let mut inputs = tx_inputs![];
+    let mut witness: Witness = Vec::with_capacity(num_inputs * 2);
+    for _ in 0..num_inputs {
+        inputs.push(TransactionInput {
+            prevout: OutPoint {
+                hash: rng.bytes::<32>(),
+                index: rng.next_u64() % 4,
+            },
+            script_sig: vec![],
+            sequence: 0xfffffffd,
+        });
+        witness.push([&[0x30][..], &rng.bytes::<70>(), &[0x01]].concat());
+        witness.push([&[0x02][..], &rng.bytes::<32>()].concat());
+    }
+    let mut outputs = tx_outputs![];
+    for _ in 0..num_outputs {
+        outputs.push(TransactionOutput {
+            value: 10_000 + rng.next_u64() % 1_000_000,
+            script_pubkey: p2wpkh(rng),
+        });
+    }
+    let tx = Transaction {
+        version: 2,
+        inputs,
+        outputs,
+        lock_time: 0,
+    };
+    (tx, witness)
+}
+
+fn create_coinbase(height: u64) -> Transaction {
+    Transaction {
+        version: 2,
+        inputs: tx_inputs![TransactionInput {
+            prevout: OutPoint {
+                hash: [0; 32],
+                index: 0xffffffff,
+            },
+            script_sig: [&[0x03][..], &height.to_le_bytes()[..3]].concat(),
+            sequence: 0xffffffff,
+        }],
+        outputs: tx_outputs![TransactionOutput {
+            value: 312_500_000,
+            script_pubkey: vec![0x51],
+        }],
+        lock_time: 0,
+    }
+}
+
+fn double_sha256(data: &[u8]) -> [u8; 32] {
+    Sha256::digest(Sha256::digest(data)).into()
+}
+
+/// Block of `tx_count` transactions (coinbase included) whose coinbase commits to its witness root
+fn create_block(tx_count: usize) -> (Block, Vec<Witness>, [u8; 32]) {
+    let mut rng = SplitMix64(tx_count as u64);
+    let mut transactions = vec![create_coinbase(800_000)];
+    // Coinbase witness is the 32-byte reserved value
+    let mut witnesses: Vec<Witness> = vec![vec![vec![0u8; 32]]];
+    for _ in 1..tx_count {
+        let (tx, witness) = create_segwit_transaction(&mut rng);
+        transactions.push(tx);
+        witnesses.push(witness);
+    }
+    let mut block = Block {
+        header: BlockHeader {
+            version: 0x20000000,
+            prev_block_hash: rng.bytes::<32>(),
+            merkle_root: [0; 32],
+            timestamp: 1_700_000_000,
+            bits: 0x17034219,
+            nonce: 0,
+        },
+        transactions: transactions.into_boxed_slice(),
+    };
+
+    // The coinbase's wtxid is defined as zero, so adding the commitment doesn't change the root
+    let witness_root = compute_witness_merkle_root(&block, &witnesses).expect("witness merkle root");
+    let commitment = double_sha256(&[witness_root, [0u8; 32]].concat());
+    let mut coinbase = block.transactions[0].clone();
+    coinbase.outputs.push(TransactionOutput {
+        value: 0,
+        script_pubkey: [&COMMITMENT_HEADER[..], &commitment].concat(),
+    });
+    block.transactions[0] = coinbase;
+    block.header.merkle_root = calculate_merkle_root(&block.transactions).expect("merkle root");
+    (block, witnesses, witness_root)
+}
+
+fn benchmark_merkle_root(c: &mut Criterion) {
+    let blocks: Vec<(usize, (Block, Vec<Witness>, [u8; 32]))> =
+        TX_COUNTS.iter().map(|&n| (n, create_block(n))).collect();
+
+    let mut group = c.benchmark_group("merkle_root");
+    for (tx_count, (block, _, _)) in &blocks {
+        group.throughput(Throughput::Elements(*tx_count as u64));
+        group.bench_with_input(
+            BenchmarkId::new("calculate_merkle_root", format!("{}tx", tx_count)),
+            &block.transactions,
+            |b, transactions| b.iter(|| black_box(calculate_merkle_root(black_box(transactions)).unwrap())),
+        );
+    }
+    group.finish();
+
+    let mut group = c.benchmark_group("witness_commitment");
+    for (tx_count, (block, witnesses, witness_root)) in &blocks {
+        assert!(
+            validate_witness_commitment(&block.transactions[0], witness_root).unwrap(),
+            "fixture commitment must validate"
+        );
+        group.throughput(Throughput::Elements(*tx_count as u64));
+        group.bench_with_input(
+            BenchmarkId::new("compute_witness_merkle_root", format!("{}tx", tx_count)),
+            &(block, witnesses),
+            |b, (block, witnesses)| {
+                b.iter(|| black_box(compute_witness_merkle_root(black_box(block), black_box(witnesses)).unwrap()))
+            },
+        );
+        // Root plus the coinbase commitment check, as done per block on connect
+        group.bench_with_input(
+            BenchmarkId::new("root_and_validate", format!("{}tx", tx_count)),
+            &(block, witnesses),
+            |b, (block, witnesses)| {
+                b.iter(|| {
+                    let root = compute_witness_merkle_root(black_box(block), black_box(witnesses)).unwrap();
+                    black_box(validate_witness_commitment(&block.transactions[0], &root).unwrap())
+                })
+            },
+        );
+    }
+    group.finish();
+}
+
+criterion_group!(benches, benchmark_merkle_root);
+criterion_main!(benches);
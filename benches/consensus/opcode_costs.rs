@@ -0,0 +1,448 @@
+//! Script Interpreter Opcode Microcosts
+//! Per-opcode execution cost, one opcode family at a time
+//!
+//! Each case runs one opcode `reps` times, together with the pushes that feed
+//! it, and a baseline runs the same script without the opcode, so
+//! (case - baseline) / reps is what the opcode itself costs. Stack, arithmetic
+//! and hashing opcodes go through `eval_script`. CHECKSIG, CHECKMULTISIG, CLTV
+//! and CSV need the spending transaction, so they go through
+//! `verify_script_with_context`, and their baselines drop the operands instead.
+//!
+//! A `cargo bench` run ends by reading criterion's estimates back, printing the
+//! per-opcode table and writing it to `BLVM_OPCODE_COSTS` (default
+//! `target/opcode_costs.json`). Set `BLVM_OPCODE_COSTS_BASELINE` to a table from
+//! another BLVM release, or one made from Core's bench numbers, to print each
+//! opcode's ratio against it.
+
+use blvm_bench::bench_config::{BenchConfig, GroupSettings};
+use blvm_bench::bench_fixtures::{ecdsa_sig, legacy_sighash, push_data, secret_key};
+use blvm_consensus::script::{eval_script, verify_script_with_context};
+use blvm_consensus::types::Network;
+use blvm_consensus::{tx_inputs, tx_outputs, OutPoint, Transaction, TransactionInput, TransactionOutput};
+use criterion::{black_box, criterion_group, BenchmarkId, Criterion, Throughput};
+use secp256k1::{PublicKey, Secp256k1};
+use serde_json::{json, Value};
+use std::path::{Path, PathBuf};
+
+/// Repetitions of context-free opcodes (stays under the 201 non-push opcode limit)
+const REPS: usize = 100;
+/// Repetitions of signature checks (a full ECDSA verification each)
+const SIG_REPS: usize = 20;
+
+// Core's script verification flag bits
+const SCRIPT_VERIFY_P2SH: u32 = 1 << 0;
+const SCRIPT_VERIFY_STRICTENC: u32 = 1 << 1;
+const SCRIPT_VERIFY_DERSIG: u32 = 1 << 2;
+const SCRIPT_VERIFY_LOW_S: u32 = 1 << 3;
+const SCRIPT_VERIFY_NULLDUMMY: u32 = 1 << 4;
+const SCRIPT_VERIFY_CHECKLOCKTIMEVERIFY: u32 = 1 << 9;
+const SCRIPT_VERIFY_CHECKSEQUENCEVERIFY: u32 = 1 << 10;
+const FLAGS: u32 = SCRIPT_VERIFY_P2SH
+    | SCRIPT_VERIFY_STRICTENC
+    | SCRIPT_VERIFY_DERSIG
+    | SCRIPT_VERIFY_LOW_S
+    | SCRIPT_VERIFY_NULLDUMMY
+    | SCRIPT_VERIFY_CHECKLOCKTIMEVERIFY
+    | SCRIPT_VERIFY_CHECKSEQUENCEVERIFY;
+
+const OP_0: u8 = 0x00;
+const OP_1: u8 = 0x51;
+const OP_2: u8 = 0x52;
+const OP_3: u8 = 0x53;
+const OP_DROP: u8 = 0x75;
+const OP_2DROP: u8 = 0x6d;
+const OP_DUP: u8 = 0x76;
+const OP_CHECKSIGVERIFY: u8 = 0xad;
+const OP_CHECKMULTISIGVERIFY: u8 = 0xaf;
+const OP_CHECKLOCKTIMEVERIFY: u8 = 0xb1;
+const OP_CHECKSEQUENCEVERIFY: u8 = 0xb2;
+
+/// Spending transaction's lock time and input sequence (CLTV/CSV cases check against these)
+const LOCK_TIME: u32 = 500_000;
+const SEQUENCE: u32 = 10;
+
+/// Minimal CScriptNum push
+fn push_num(script: &mut Vec<u8>, n: i64) {
+    match n {
+        0 => script.push(OP_0),
+        1..=16 => script.push(OP_1 + (n as u8 - 1)),
+        _ => {
+            let mut bytes = Vec::new();
+            let mut abs = n.unsigned_abs();
+            while abs > 0 {
+                bytes.push(abs as u8);
+                abs >>= 8;
+            }
+            if bytes.last().is_some_and(|b| b & 0x80 != 0) {
+                bytes.push(if n < 0 { 0x80 } else { 0x00 });
+            } else if n < 0 {
+                *bytes.last_mut().unwrap() |= 0x80;
+            }
+            push_data(script, &bytes);
+        }
+    }
+}
+
+/// `count` stack items dropped
+fn drops(count: usize) -> Vec<u8> {
+    let mut ops = vec![OP_2DROP; count / 2];
+    if count % 2 == 1 {
+        ops.push(OP_DROP);
+    }
+    ops
+}
+
+/// How a case's script is run
+enum Run {
+    /// `eval_script` on an empty stack
+    Eval,
+    /// `verify_script_with_context` with `script_sig` against input 0 of the fixture transaction
+    Verify { script_sig: Vec<u8> },
+}
+
+/// One opcode measurement: `prelude` once, then `reps` × (`setup` `op`)
+struct Case {
+    family: &'static str,
+    name: String,
+    prelude: Vec<u8>,
+    setup: Vec<u8>,
+    op: Vec<u8>,
+    /// Replaces `op` in the baseline (operands the opcode would have consumed)
+    baseline_op: Vec<u8>,
+    /// Appended to both scripts (leaves a true stack top for `Verify`)
+    epilogue: Vec<u8>,
+    reps: usize,
+    run: Run,
+}
+
+impl Case {
+    fn eval(family: &'static str, name: impl Into<String>, setup: impl Into<Vec<u8>>, op: u8) -> Self {
+        Self {
+            family,
+            name: name.into(),
+            prelude: Vec::new(),
+            setup: setup.into(),
+            op: vec![op],
+            baseline_op: Vec::new(),
+            epilogue: Vec::new(),
+            reps: REPS,
+            run: Run::Eval,
+        }
+    }
+
+    fn script(&self, op: &[u8]) -> Vec<u8> {
+        let mut script = self.prelude.clone();
+        for _ in 0..self.reps {
+            script.extend_from_slice(&self.setup);
+            script.extend_from_slice(op);
+        }
+        script.extend_from_slice(&self.epilogue);
+        script
+    }
+}
+
+/// `count` pushes of 4-byte numbers (arithmetic operands that aren't small-int opcodes)
+fn operands(count: usize) -> Vec<u8> {
+    let mut setup = Vec::new();
+    for i in 0..count as i64 {
+        push_num(&mut setup, 0x0102_0304 + i * 0x1111);
+    }
+    setup
+}
+
+fn stack_cases() -> Vec<Case> {
+    let mut size_setup = Vec::new();
+    push_data(&mut size_setup, &[0x42; 32]);
+    vec![
+        Case::eval("stack", "OP_DUP", [OP_1], OP_DUP),
+        Case::eval("stack", "OP_DROP", [OP_1, OP_1], OP_DROP),
+        Case::eval("stack", "OP_SWAP", [OP_1, OP_2], 0x7c),
+        Case::eval("stack", "OP_OVER", [OP_1, OP_2], 0x78),
+        Case::eval("stack", "OP_ROT", [OP_1, OP_2, OP_3], 0x7b),
+        Case::eval("stack", "OP_PICK", [OP_1, OP_2, OP_1], 0x79),
+        Case::eval("stack", "OP_ROLL", [OP_1, OP_2, OP_1], 0x7a),
+        Case::eval("stack", "OP_2DUP", [OP_1, OP_2], 0x6e),
+        Case::eval("stack", "OP_TOALTSTACK", [OP_1], 0x6b),
+        Case::eval("stack", "OP_SIZE", size_setup, 0x82),
+    ]
+}
+
+fn arithmetic_cases() -> Vec<Case> {
+    vec![
+        Case::eval("arithmetic", "OP_1ADD", operands(1), 0x8b),
+        Case::eval("arithmetic", "OP_NEGATE", operands(1), 0x8f),
+        Case::eval("arithmetic", "OP_ABS", operands(1), 0x90),
+        Case::eval("arithmetic", "OP_NOT", operands(1), 0x91),
+        Case::eval("arithmetic", "OP_ADD", operands(2), 0x93),
+        Case::eval("arithmetic", "OP_SUB", operands(2), 0x94),
+        Case::eval("arithmetic", "OP_BOOLAND", operands(2), 0x9a),
+        Case::eval("arithmetic", "OP_NUMEQUAL", operands(2), 0x9c),
+        Case::eval("arithmetic", "OP_LESSTHAN", operands(2), 0x9f),
+        Case::eval("arithmetic", "OP_MIN", operands(2), 0xa3),
+        Case::eval("arithmetic", "OP_MAX", operands(2), 0xa4),
+        Case::eval("arithmetic", "OP_WITHIN", operands(3), 0xa5),
+    ]
+}
+
+fn hashing_cases() -> Vec<Case> {
+    let mut cases = Vec::new();
+    for (size, suffix) in [(32, "32B"), (520, "520B")] {
+        let mut prelude = Vec::new();
+        push_data(&mut prelude, &vec![0x42; size]);
+        for (name, op) in [
+            ("OP_RIPEMD160", 0xa6),
+            ("OP_SHA1", 0xa7),
+            ("OP_SHA256", 0xa8),
+            ("OP_HASH160", 0xa9),
+            ("OP_HASH256", 0xaa),
+        ] {
+            // Each rep hashes a copy of the preimage, so the stack holds `reps` digests
+            let mut case = Case::eval("hashing", format!("{}_{}", name, suffix), [OP_DUP], op);
+            case.prelude = prelude.clone();
+            cases.push(case);
+        }
+    }
+    cases
+}
+
+/// Spending transaction with one input and the coin it spends
+fn spending_tx(script_pubkey: &[u8]) -> (Transaction, Vec<TransactionOutput>) {
+    let tx = Transaction {
+        version: 2,
+        inputs: tx_inputs![TransactionInput {
+            prevout: OutPoint { hash: [0x11; 32], index: 0 },
+            script_sig: Vec::new(),
+            sequence: SEQUENCE as u64,
+        }],
+        outputs: tx_outputs![TransactionOutput {
+            value: 90_000,
+            script_pubkey: vec![OP_1],
+        }],
+        lock_time: LOCK_TIME as u64,
+    };
+    let prevouts = vec![TransactionOutput {
+        value: 100_000,
+        script_pubkey: script_pubkey.to_vec(),
+    }];
+    (tx, prevouts)
+}
+
+/// CHECKSIG (`multisig` false, one key) or `m`-of-`n` CHECKMULTISIG, signatures in the scriptSig
+fn signature_case(name: &str, multisig: bool, m: usize, n: usize) -> Case {
+    let secp = Secp256k1::new();
+    let keys: Vec<_> = (0..n as u64).map(secret_key).collect();
+    let mut setup = Vec::new();
+    let (op, baseline_op) = if !multisig {
+        push_data(&mut setup, &PublicKey::from_secret_key(&secp, &keys[0]).serialize());
+        (vec![OP_CHECKSIGVERIFY], drops(2))
+    } else {
+        push_num(&mut setup, m as i64);
+        for key in &keys {
+            push_data(&mut setup, &PublicKey::from_secret_key(&secp, key).serialize());
+        }
+        push_num(&mut setup, n as i64);
+        // dummy, m signatures, m, n keys, n
+        (vec![OP_CHECKMULTISIGVERIFY], drops(m + n + 3))
+    };
+    let mut case = Case {
+        family: "signature",
+        name: name.to_string(),
+        prelude: Vec::new(),
+        setup,
+        op,
+        baseline_op,
+        epilogue: vec![OP_1],
+        reps: SIG_REPS,
+        run: Run::Eval,
+    };
+
+    // Every check signs the same scriptCode (the whole scriptPubKey), so one signature per key serves all reps
+    let script_pubkey = case.script(&case.op);
+    let (tx, _) = spending_tx(&script_pubkey);
+    let sighash = legacy_sighash(&tx, 0, &script_pubkey);
+    let mut one_check = Vec::new();
+    if multisig {
+        one_check.push(OP_0); // CHECKMULTISIG dummy
+    }
+    for key in &keys[..m] {
+        push_data(&mut one_check, &ecdsa_sig(&secp, &sighash, key));
+    }
+    case.run = Run::Verify {
+        script_sig: one_check.repeat(case.reps),
+    };
+    case
+}
+
+fn locktime_cases() -> Vec<Case> {
+    let case = |name: &str, lock: i64, op| {
+        let mut setup = Vec::new();
+        push_num(&mut setup, lock);
+        Case {
+            family: "locktime",
+            name: name.to_string(),
+            prelude: Vec::new(),
+            setup,
+            op: vec![op, OP_DROP],
+            baseline_op: vec![OP_DROP],
+            epilogue: vec![OP_1],
+            reps: REPS,
+            run: Run::Verify { script_sig: Vec::new() },
+        }
+    };
+    vec![
+        case("OP_CHECKLOCKTIMEVERIFY", LOCK_TIME as i64 - 100_000, OP_CHECKLOCKTIMEVERIFY),
+        case("OP_CHECKSEQUENCEVERIFY", SEQUENCE as i64 / 2, OP_CHECKSEQUENCEVERIFY),
+    ]
+}
+
+fn all_cases() -> Vec<Case> {
+    let mut cases = stack_cases();
+    cases.extend(arithmetic_cases());
+    cases.extend(hashing_cases());
+    cases.push(signature_case("OP_CHECKSIG", false, 1, 1));
+    // 1-of-1 next to OP_CHECKSIG shows CHECKMULTISIG's own overhead
+    cases.push(signature_case("OP_CHECKMULTISIG_1of1", true, 1, 1));
+    cases.push(signature_case("OP_CHECKMULTISIG_2of3", true, 2, 3));
+    cases.extend(locktime_cases());
+    cases
+}
+
+/// Run `script`; `Ok(true)` expected from `Verify` cases, no error from `Eval` ones
+fn run_script(run: &Run, script: &[u8], (tx, prevouts): &(Transaction, Vec<TransactionOutput>)) -> Result<bool, String> {
+    match run {
+        Run::Eval => {
+            let mut stack = Vec::new();
+            eval_script(black_box(script), black_box(&mut stack), black_box(FLAGS)).map_err(|e| e.to_string())
+        }
+        Run::Verify { script_sig } => verify_script_with_context(
+            black_box(script_sig),
+            black_box(script),
+            None,
+            black_box(FLAGS),
+            tx,
+            0,
+            prevouts,
+            Network::Mainnet,
+        )
+        .map_err(|e| e.to_string()),
+    }
+}
+
+fn benchmark_opcode_costs(c: &mut Criterion) {
+    let config = BenchConfig::get();
+    let cases = all_cases();
+    let mut families: Vec<&str> = cases.iter().map(|case| case.family).collect();
+    families.dedup();
+    for family in families {
+        let Some(mut group) = config.group(c, &format!("opcode_costs_{}", family), GroupSettings::default()) else {
+            continue;
+        };
+        for case in cases.iter().filter(|case| case.family == family) {
+            group.throughput(Throughput::Elements(case.reps as u64));
+            // Legacy signature hashing reads no prevout script, so one fixture serves both scripts
+            let fixture = spending_tx(&case.script(&case.op));
+            for (variant, op) in [("op", &case.op), ("baseline", &case.baseline_op)] {
+                let script = case.script(op);
+                // A failing script stops early, which would make the opcode look free
+                match run_script(&case.run, &script, &fixture) {
+                    Ok(true) => {}
+                    Ok(false) if matches!(case.run, Run::Eval) => {}
+                    outcome => panic!("{} {} script failed: {:?}", case.name, variant, outcome),
+                }
+                group.bench_function(BenchmarkId::new(case.name.as_str(), variant), |b| {
+                    b.iter(|| black_box(run_script(&case.run, &script, &fixture)))
+                });
+            }
+        }
+        group.finish();
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Cost table
+// ---------------------------------------------------------------------------
+
+/// Where criterion keeps its estimates
+fn criterion_home() -> PathBuf {
+    if let Ok(home) = std::env::var("CRITERION_HOME") {
+        return PathBuf::from(home);
+    }
+    let target = std::env::var("CARGO_TARGET_DIR").unwrap_or_else(|_| "target".to_string());
+    Path::new(&target).join("criterion")
+}
+
+/// Median nanoseconds per iteration of one benchmark, if it ran
+fn median_ns(home: &Path, group: &str, function: &str, variant: &str) -> Option<f64> {
+    let path = home.join(group).join(function).join(variant).join("new").join("estimates.json");
+    let estimates: Value = serde_json::from_str(&std::fs::read_to_string(path).ok()?).ok()?;
+    estimates["median"]["point_estimate"].as_f64()
+}
+
+/// Per-opcode nanoseconds from this run's estimates, in case order
+fn cost_table(home: &Path) -> Vec<(&'static str, String, f64)> {
+    all_cases()
+        .iter()
+        .filter_map(|case| {
+            let group = format!("opcode_costs_{}", case.family);
+            // Estimates of a deselected family are left over from an earlier run
+            if !BenchConfig::get().enabled(&group) {
+                return None;
+            }
+            let op = median_ns(home, &group, &case.name, "op")?;
+            let baseline = median_ns(home, &group, &case.name, "baseline")?;
+            Some((case.family, case.name.clone(), ((op - baseline) / case.reps as f64).max(0.0)))
+        })
+        .collect()
+}
+
+fn write_cost_table() {
+    let table = cost_table(&criterion_home());
+    if table.is_empty() {
+        return;
+    }
+    let checksig = table.iter().find(|(_, name, _)| *name == "OP_CHECKSIG").map(|(_, _, ns)| *ns);
+    let baseline: Option<Value> = std::env::var("BLVM_OPCODE_COSTS_BASELINE")
+        .ok()
+        .and_then(|path| std::fs::read_to_string(path).ok())
+        .and_then(|content| serde_json::from_str(&content).ok());
+
+    println!("\n📊 Opcode costs (ns per opcode, net of its operands):");
+    println!("   {:<12} {:<26} {:>10} {:>12} {:>10}", "family", "opcode", "ns", "/CHECKSIG", "vs base");
+    for (family, name, ns) in &table {
+        let relative = checksig.map(|c| format!("{:.4}", ns / c)).unwrap_or_default();
+        let versus = baseline
+            .as_ref()
+            .and_then(|b| b["opcodes"][name.as_str()]["ns"].as_f64())
+            .filter(|base| *base > 0.0)
+            .map(|base| format!("{:.2}x", ns / base))
+            .unwrap_or_default();
+        println!("   {:<12} {:<26} {:>10.1} {:>12} {:>10}", family, name, ns, relative, versus);
+    }
+
+    let opcodes: serde_json::Map<String, Value> = table
+        .iter()
+        .map(|(family, name, ns)| {
+            let relative = checksig.map(|c| ns / c);
+            (name.clone(), json!({ "family": family, "ns": ns, "relative_to_checksig": relative }))
+        })
+        .collect();
+    let out = std::env::var("BLVM_OPCODE_COSTS")
+        .map(PathBuf::from)
+        .unwrap_or_else(|_| Path::new(&std::env::var("CARGO_TARGET_DIR").unwrap_or_else(|_| "target".to_string())).join("opcode_costs.json"));
+    let document = json!({ "reps": REPS, "sig_reps": SIG_REPS, "opcodes": opcodes });
+    match std::fs::write(&out, serde_json::to_string_pretty(&document).expect("serializable table")) {
+        Ok(()) => println!("   Written to {}", out.display()),
+        Err(e) => eprintln!("⚠️  Failed to write {}: {}", out.display(), e),
+    }
+}
+
+criterion_group!(benches, benchmark_opcode_costs);
+
+fn main() {
+    benches();
+    Criterion::default().configure_from_args().final_summary();
+    // `cargo test --benches` runs each benchmark once without estimates
+    if std::env::args().any(|arg| arg == "--bench") {
+        write_cost_table();
+    }
+}
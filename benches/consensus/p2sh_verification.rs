@@ -0,0 +1,78 @@
+//! P2SH and Nested SegWit Verification Benchmarks
+//! Per-input `verify_script_with_context` on correctly signed spends
+//!
+//! `script_verification` only runs trivial OP_1 scripts, which never reach a
+//! signature check. Here every input carries real ECDSA signatures: P2SH
+//! redeem scripts (2-of-3 and 15-of-15 multisig, the largest redeem script that
+//! fits in a push), P2WPKH and 2-of-3 P2WSH wrapped in P2SH, and their bare or
+//! native counterparts, so each wrapping's overhead reads off directly.
+//! Throughput is in signatures checked.
+
+use blvm_bench::bench_config::{BenchConfig, GroupSettings};
+use blvm_bench::bench_fixtures::{signed_spends, SignedSpend, SpendKind};
+use blvm_consensus::script::verify_script_with_context;
+use blvm_consensus::types::Network;
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+
+// Consensus flags in force after Taproot (Core's bit layout)
+const SCRIPT_VERIFY_P2SH: u32 = 1 << 0;
+const SCRIPT_VERIFY_DERSIG: u32 = 1 << 2;
+const SCRIPT_VERIFY_NULLDUMMY: u32 = 1 << 4;
+const SCRIPT_VERIFY_CHECKLOCKTIMEVERIFY: u32 = 1 << 9;
+const SCRIPT_VERIFY_CHECKSEQUENCEVERIFY: u32 = 1 << 10;
+const SCRIPT_VERIFY_WITNESS: u32 = 1 << 11;
+const SCRIPT_VERIFY_TAPROOT: u32 = 1 << 17;
+const FLAGS: u32 = SCRIPT_VERIFY_P2SH
+    | SCRIPT_VERIFY_DERSIG
+    | SCRIPT_VERIFY_NULLDUMMY
+    | SCRIPT_VERIFY_CHECKLOCKTIMEVERIFY
+    | SCRIPT_VERIFY_CHECKSEQUENCEVERIFY
+    | SCRIPT_VERIFY_WITNESS
+    | SCRIPT_VERIFY_TAPROOT;
+
+/// Each P2SH kind next to what it wraps
+const KINDS: [SpendKind; 7] = [
+    SpendKind::P2pkh,
+    SpendKind::P2shMultisig,
+    SpendKind::P2shMultisigMax,
+    SpendKind::P2wpkh,
+    SpendKind::P2shP2wpkh,
+    SpendKind::P2wshMultisig,
+    SpendKind::P2shP2wshMultisig,
+];
+
+fn verify(spend: &SignedSpend) -> Result<bool, String> {
+    let witness = Some(&spend.witness).filter(|w| !w.is_empty());
+    verify_script_with_context(
+        &spend.tx.inputs[0].script_sig,
+        &spend.spent.script_pubkey,
+        witness,
+        FLAGS,
+        &spend.tx,
+        0,
+        std::slice::from_ref(&spend.spent),
+        Network::Mainnet,
+    )
+    .map_err(|e| e.to_string())
+}
+
+fn benchmark_p2sh_verification(c: &mut Criterion) {
+    let Some(mut group) = BenchConfig::get().group(c, "p2sh_verification", GroupSettings::default()) else {
+        return;
+    };
+
+    for kind in KINDS {
+        let spend = signed_spends(kind, 1).pop().expect("one spend");
+        // Fixtures must verify, otherwise we'd be timing an early rejection
+        assert_eq!(verify(&spend), Ok(true), "{} fixture rejected", kind.name());
+
+        group.throughput(Throughput::Elements(kind.signatures() as u64));
+        group.bench_function(BenchmarkId::new("verify_script", kind.name()), |b| {
+            b.iter(|| black_box(verify(black_box(&spend))))
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, benchmark_p2sh_verification);
+criterion_main!(benches);
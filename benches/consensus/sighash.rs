@@ -0,0 +1,180 @@
+//! Sighash Computation Benchmarks
+//! Legacy SIGHASH_ALL, BIP143 (segwit v0) and BIP341 (taproot) on transactions
+//! with 1, 10 and 500 inputs, hashing every input as a verifier would
+//!
+//! Legacy hashing goes through blvm_consensus (`calculate_transaction_sighash`
+//! per input, plus `batch_compute_sighashes` which is where its caching lives).
+//! BIP143/BIP341 are only reachable inside script verification, so they are
+//! measured with the reference implementation from `bench_fixtures`, uncached
+//! vs. midstate-cached: a cached run that stops scaling linearly with the input
+//! count is the quadratic-hashing regression this suite exists to catch.
+
+use blvm_bench::bench_config::{BenchConfig, GroupSettings};
+use blvm_bench::bench_fixtures::{
+    bip143_sighash, bip341_sighash, hash160, push_data, SighashMidstate,
+};
+use blvm_consensus::transaction_hash::{calculate_transaction_sighash, SighashType};
+use blvm_consensus::{OutPoint, Transaction, TransactionInput, TransactionOutput};
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+
+const INPUT_COUNTS: [usize; 3] = [1, 10, 500];
+
+/// Transaction with `input_count` inputs spending outputs with `script_pubkey`
+fn create_transaction(input_count: usize, script_pubkey: &[u8]) -> (Transaction, Vec<TransactionOutput>) {
+    let tx = Transaction {
+        version: 2,
+        inputs: (0..input_count)
+            .map(|i| TransactionInput {
+                prevout: OutPoint {
+                    hash: {
+                        let mut h = [0u8; 32];
+                        h[..8].copy_from_slice(&(i as u64).to_le_bytes());
+                        h
+                    },
+                    index: (i % 3) as u64,
+                },
+                script_sig: Vec::new(),
+                sequence: 0xfffffffd,
+            })
+            .collect::<Vec<_>>()
+            .into(),
+        outputs: (0..2)
+            .map(|i| TransactionOutput {
+                value: 40_000 * input_count as i64,
+                script_pubkey: {
+                    let mut spk = vec![0x00];
+                    push_data(&mut spk, &hash160(&[i as u8]));
+                    spk
+                },
+            })
+            .collect::<Vec<_>>()
+            .into(),
+        lock_time: 0,
+    };
+    let prevouts = (0..input_count)
+        .map(|_| TransactionOutput {
+            value: 100_000,
+            script_pubkey: script_pubkey.to_vec(),
+        })
+        .collect();
+    (tx, prevouts)
+}
+
+fn p2pkh_script() -> Vec<u8> {
+    let mut spk = vec![0x76, 0xa9];
+    push_data(&mut spk, &[0x89; 20]);
+    spk.extend_from_slice(&[0x88, 0xac]);
+    spk
+}
+
+fn benchmark_legacy_sighash(c: &mut Criterion) {
+    let Some(mut group) = BenchConfig::get().group(c, "sighash_legacy", GroupSettings::default()) else {
+        return;
+    };
+    for input_count in INPUT_COUNTS {
+        let (tx, prevouts) = create_transaction(input_count, &p2pkh_script());
+        group.throughput(Throughput::Elements(input_count as u64));
+        group.bench_with_input(BenchmarkId::new("all_inputs", input_count), &input_count, |b, &n| {
+            b.iter(|| {
+                for i in 0..n {
+                    black_box(
+                        calculate_transaction_sighash(black_box(&tx), i, black_box(&prevouts), SighashType::All)
+                            .unwrap(),
+                    );
+                }
+            })
+        });
+    }
+    group.finish();
+}
+
+#[cfg(feature = "production")]
+fn benchmark_legacy_sighash_batch(c: &mut Criterion) {
+    use blvm_consensus::transaction_hash::batch_compute_sighashes;
+
+    let Some(mut group) = BenchConfig::get().group(c, "sighash_legacy", GroupSettings::default()) else {
+        return;
+    };
+    for input_count in INPUT_COUNTS {
+        let (tx, prevouts) = create_transaction(input_count, &p2pkh_script());
+        group.throughput(Throughput::Elements(input_count as u64));
+        group.bench_with_input(BenchmarkId::new("batch", input_count), &input_count, |b, _| {
+            b.iter(|| {
+                black_box(batch_compute_sighashes(black_box(&tx), black_box(&prevouts), SighashType::All).unwrap())
+            })
+        });
+    }
+    group.finish();
+}
+
+#[cfg(not(feature = "production"))]
+fn benchmark_legacy_sighash_batch(_c: &mut Criterion) {}
+
+fn benchmark_bip143_sighash(c: &mut Criterion) {
+    // P2WPKH script code
+    let script_code = p2pkh_script();
+    let mut spk = vec![0x00];
+    push_data(&mut spk, &[0x89; 20]);
+
+    let Some(mut group) = BenchConfig::get().group(c, "sighash_bip143", GroupSettings::default()) else {
+        return;
+    };
+    for input_count in INPUT_COUNTS {
+        let (tx, prevouts) = create_transaction(input_count, &spk);
+        group.throughput(Throughput::Elements(input_count as u64));
+        group.bench_with_input(BenchmarkId::new("uncached", input_count), &input_count, |b, &n| {
+            b.iter(|| {
+                for i in 0..n {
+                    black_box(bip143_sighash(black_box(&tx), i, &script_code, prevouts[i].value as i64));
+                }
+            })
+        });
+        group.bench_with_input(BenchmarkId::new("cached", input_count), &input_count, |b, &n| {
+            b.iter(|| {
+                let midstate = SighashMidstate::new(black_box(&tx), black_box(&prevouts));
+                for i in 0..n {
+                    black_box(midstate.bip143(&tx, i, &script_code, prevouts[i].value as i64));
+                }
+            })
+        });
+    }
+    group.finish();
+}
+
+fn benchmark_bip341_sighash(c: &mut Criterion) {
+    let mut spk = vec![0x51];
+    push_data(&mut spk, &[0x42; 32]);
+
+    let Some(mut group) = BenchConfig::get().group(c, "sighash_bip341", GroupSettings::default()) else {
+        return;
+    };
+    for input_count in INPUT_COUNTS {
+        let (tx, prevouts) = create_transaction(input_count, &spk);
+        group.throughput(Throughput::Elements(input_count as u64));
+        group.bench_with_input(BenchmarkId::new("uncached", input_count), &input_count, |b, &n| {
+            b.iter(|| {
+                for i in 0..n {
+                    black_box(bip341_sighash(black_box(&tx), i, black_box(&prevouts), None));
+                }
+            })
+        });
+        group.bench_with_input(BenchmarkId::new("cached", input_count), &input_count, |b, &n| {
+            b.iter(|| {
+                let midstate = SighashMidstate::new(black_box(&tx), black_box(&prevouts));
+                for i in 0..n {
+                    black_box(midstate.bip341(&tx, i, None));
+                }
+            })
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(
+    benches,
+    benchmark_legacy_sighash,
+    benchmark_legacy_sighash_batch,
+    benchmark_bip143_sighash,
+    benchmark_bip341_sighash
+);
+criterion_main!(benches);
@@ -0,0 +1,86 @@
+//! Signature Verification Benchmarks
+//! Measures full script + signature verification per spend type, comparable to
+//! Core's VerifyScriptP2WPKH / VerifyScriptP2TR benches
+//!
+//! Each benchmark connects a post-Taproot block containing 100 correctly signed
+//! spends of one type (P2PKH, P2WPKH, P2WSH 2-of-3, Taproot key path, Taproot
+//! script path), so the per-input cost is the reported time / 100. Raw
+//! secp256k1 verification is measured alongside as the floor.
+
+use blvm_bench::bench_config::{BenchConfig, GroupSettings};
+use blvm_bench::bench_fixtures::{build_block, signed_spends, SpendKind, POST_TAPROOT_HEIGHT};
+use blvm_consensus::block::connect_block;
+use blvm_consensus::types::Network;
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use secp256k1::{Keypair, Message, PublicKey, Secp256k1, SecretKey};
+
+const SPENDS_PER_BLOCK: usize = 100;
+
+fn benchmark_signature_verification(c: &mut Criterion) {
+    let Some(mut group) = BenchConfig::get().group(c, "signature_verification", GroupSettings::default()) else {
+        return;
+    };
+    group.throughput(Throughput::Elements(SPENDS_PER_BLOCK as u64));
+
+    for kind in SpendKind::ALL {
+        let (block, witnesses, utxo_set) =
+            build_block(signed_spends(kind, SPENDS_PER_BLOCK), POST_TAPROOT_HEIGHT);
+
+        // Fixtures must validate, otherwise we'd be timing an early rejection
+        let (result, _, _) = connect_block(
+            &block,
+            &witnesses,
+            utxo_set.clone(),
+            POST_TAPROOT_HEIGHT,
+            None,
+            Network::Mainnet,
+        )
+        .expect("connect_block failed");
+        assert!(
+            matches!(result, blvm_consensus::types::ValidationResult::Valid),
+            "{} fixture block rejected: {:?}",
+            kind.name(),
+            result
+        );
+
+        group.bench_function(BenchmarkId::new("connect_block", kind.name()), |b| {
+            b.iter(|| {
+                black_box(connect_block(
+                    black_box(&block),
+                    black_box(&witnesses),
+                    black_box(utxo_set.clone()),
+                    black_box(POST_TAPROOT_HEIGHT),
+                    black_box(None),
+                    black_box(Network::Mainnet),
+                ))
+            })
+        });
+    }
+    group.finish();
+}
+
+fn benchmark_raw_secp256k1(c: &mut Criterion) {
+    let secp = Secp256k1::new();
+    let secret_key = SecretKey::from_slice(&[0x42; 32]).expect("valid key");
+    let msg = Message::from_digest_slice(&[0x17; 32]).expect("32-byte digest");
+
+    let pubkey = PublicKey::from_secret_key(&secp, &secret_key);
+    let ecdsa_sig = secp.sign_ecdsa(&msg, &secret_key);
+    c.bench_function("secp256k1_verify_ecdsa", |b| {
+        b.iter(|| black_box(secp.verify_ecdsa(black_box(&msg), black_box(&ecdsa_sig), black_box(&pubkey))))
+    });
+
+    let keypair = Keypair::from_secret_key(&secp, &secret_key);
+    let (xonly, _) = keypair.x_only_public_key();
+    let schnorr_sig = secp.sign_schnorr_no_aux_rand(&msg, &keypair);
+    c.bench_function("secp256k1_verify_schnorr", |b| {
+        b.iter(|| black_box(secp.verify_schnorr(black_box(&schnorr_sig), black_box(&msg), black_box(&xonly))))
+    });
+}
+
+criterion_group!(
+    benches,
+    benchmark_signature_verification,
+    benchmark_raw_secp256k1
+);
+criterion_main!(benches);
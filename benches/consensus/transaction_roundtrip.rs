@@ -0,0 +1,62 @@
+//! Transaction Serialization Round-Trip Benchmarks
+//! BLVM decode, re-encode and both, for legacy and segwit encodings
+//!
+//! Every block this crate touches is deserialized first, so decoding cost is
+//! paid on every block in every mode. Transactions with 1, 100 and 2,500
+//! inputs cover a typical payment up to a large consolidation; throughput is
+//! in serialized bytes. The unusual encodings of `tx_roundtrip::weird_encodings`
+//! (including rejected ones, which must fail fast rather than allocate for a
+//! huge declared input count) are timed as a group too.
+
+use blvm_bench::bench_config::{BenchConfig, GroupSettings};
+use blvm_bench::tx_roundtrip::{decode_tx, sample_tx, weird_encodings};
+use blvm_consensus::serialization::transaction::serialize_transaction;
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+
+const INPUT_COUNTS: [usize; 3] = [1, 100, 2_500];
+
+fn benchmark_transaction_roundtrip(c: &mut Criterion) {
+    let Some(mut group) = BenchConfig::get().group(c, "transaction_roundtrip", GroupSettings::default()) else {
+        return;
+    };
+
+    for segwit in [false, true] {
+        let encoding = if segwit { "segwit" } else { "legacy" };
+        for inputs in INPUT_COUNTS {
+            let bytes = sample_tx(inputs, segwit);
+            let (tx, _) = decode_tx(&bytes).expect("sample transaction decodes");
+            let id = format!("{}_{}in", encoding, inputs);
+            group.throughput(Throughput::Bytes(bytes.len() as u64));
+
+            group.bench_function(BenchmarkId::new("deserialize", &id), |b| {
+                b.iter(|| black_box(decode_tx(black_box(&bytes))))
+            });
+            group.bench_function(BenchmarkId::new("serialize", &id), |b| {
+                b.iter(|| black_box(serialize_transaction(black_box(&tx))))
+            });
+            group.bench_function(BenchmarkId::new("roundtrip", &id), |b| {
+                b.iter(|| {
+                    let (tx, witness) = decode_tx(black_box(&bytes)).expect("sample transaction decodes");
+                    black_box((serialize_transaction(&tx), witness))
+                })
+            });
+        }
+    }
+    group.finish();
+}
+
+fn benchmark_weird_encodings(c: &mut Criterion) {
+    let Some(mut group) = BenchConfig::get().group(c, "transaction_weird_encodings", GroupSettings::default()) else {
+        return;
+    };
+    for (name, bytes, _) in weird_encodings() {
+        group.throughput(Throughput::Bytes(bytes.len() as u64));
+        group.bench_function(BenchmarkId::new("deserialize", name), |b| {
+            b.iter(|| black_box(decode_tx(black_box(&bytes)).is_ok()))
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, benchmark_transaction_roundtrip, benchmark_weird_encodings);
+criterion_main!(benches);
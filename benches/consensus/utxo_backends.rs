@@ -0,0 +1,160 @@
+//! UTXO Backend Comparison Benchmark
+//!
+//! Connects the same synthetic block range through each `UtxoBackend`
+//! (memory, cow, disk) on top of a pre-populated UTXO set, so the cost of
+//! the storage layer is measured with identical consensus work.
+//!
+//! Tunables: BLVM_BENCH_UTXO_BASE (initial set size, default 100000),
+//! BLVM_BENCH_UTXO_BLOCKS (blocks in range, default 20).
+
+use blvm_bench::bench_config::{BenchConfig, GroupSettings};
+use blvm_bench::utxo_backend::UtxoBackend;
+use blvm_consensus::block::calculate_tx_id;
+use blvm_consensus::mining::calculate_merkle_root;
+use blvm_consensus::segwit::Witness;
+use blvm_consensus::types::Network;
+use blvm_consensus::{
+    tx_inputs, tx_outputs, Block, BlockHeader, OutPoint, Transaction, TransactionInput,
+    TransactionOutput, UtxoSet, UTXO,
+};
+use criterion::{black_box, criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion};
+
+const TXS_PER_BLOCK: usize = 100;
+const START_HEIGHT: u64 = 1_000;
+
+fn env_usize(name: &str, default: usize) -> usize {
+    std::env::var(name)
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(default)
+}
+
+/// Base UTXO set plus a chain of blocks where each block spends the previous block's outputs
+fn create_range(base_size: usize, num_blocks: usize) -> (UtxoSet, Vec<(Block, Vec<Witness>)>) {
+    let mut utxo_set = UtxoSet::new();
+    for i in 0..base_size {
+        let mut hash = [0u8; 32];
+        hash[..8].copy_from_slice(&(i as u64).to_le_bytes());
+        utxo_set.insert(
+            OutPoint { hash, index: 0 },
+            UTXO {
+                value: 10_000_000,
+                script_pubkey: vec![0x51],
+                height: 0,
+                is_coinbase: false,
+            },
+        );
+    }
+
+    // The first block spends from the base set; later blocks spend the previous block
+    let mut spendable: Vec<OutPoint> = utxo_set.iter().map(|(op, _)| op.clone()).take(TXS_PER_BLOCK).collect();
+    let mut blocks = Vec::with_capacity(num_blocks);
+    let mut prev_block_hash = [0u8; 32];
+
+    for b in 0..num_blocks {
+        let height = START_HEIGHT + b as u64;
+        let mut transactions = vec![Transaction {
+            version: 1,
+            inputs: tx_inputs![TransactionInput {
+                prevout: OutPoint {
+                    hash: [0; 32],
+                    index: 0xffffffff,
+                },
+                // Height in script_sig keeps coinbase txids unique
+                script_sig: height.to_le_bytes().to_vec(),
+                sequence: 0xffffffff,
+            }],
+            outputs: tx_outputs![TransactionOutput {
+                value: 5_000_000_000,
+                script_pubkey: vec![0x51],
+            }],
+            lock_time: 0,
+        }];
+
+        let mut next_spendable = Vec::with_capacity(spendable.len());
+        for prevout in spendable.drain(..) {
+            let tx = Transaction {
+                version: 1,
+                inputs: tx_inputs![TransactionInput {
+                    prevout,
+                    script_sig: vec![0x51],
+                    sequence: 0xffffffff,
+                }],
+                outputs: tx_outputs![TransactionOutput {
+                    value: 5_000_000,
+                    script_pubkey: vec![0x51],
+                }],
+                lock_time: 0,
+            };
+            next_spendable.push(OutPoint {
+                hash: calculate_tx_id(&tx),
+                index: 0,
+            });
+            transactions.push(tx);
+        }
+        spendable = next_spendable;
+
+        let merkle_root = calculate_merkle_root(&transactions).unwrap_or([0; 32]);
+        let block = Block {
+            header: BlockHeader {
+                version: 1,
+                prev_block_hash,
+                merkle_root,
+                timestamp: 1234567890 + b as u64 * 600,
+                bits: 0x1d00ffff,
+                nonce: 0,
+            },
+            transactions: transactions.into_boxed_slice(),
+        };
+        prev_block_hash[..8].copy_from_slice(&height.to_le_bytes());
+        let witnesses: Vec<Witness> = block.transactions.iter().map(|_| Vec::new()).collect();
+        blocks.push((block, witnesses));
+    }
+
+    (utxo_set, blocks)
+}
+
+fn benchmark_utxo_backends(c: &mut Criterion) {
+    let defaults = GroupSettings { sample_size: Some(10), ..Default::default() };
+    let Some(mut group) = BenchConfig::get().group(c, "utxo_backends", defaults) else {
+        return;
+    };
+    let base_size = env_usize("BLVM_BENCH_UTXO_BASE", 100_000);
+    let num_blocks = env_usize("BLVM_BENCH_UTXO_BLOCKS", 20);
+    let (base, blocks) = create_range(base_size, num_blocks);
+    let disk_dir = tempfile::tempdir().expect("Failed to create temp dir");
+
+    let backends = [
+        UtxoBackend::Memory,
+        UtxoBackend::Cow,
+        UtxoBackend::Disk(disk_dir.path().to_path_buf()),
+    ];
+
+    for backend in backends.iter() {
+        group.bench_with_input(
+            BenchmarkId::new("connect_range", backend.name()),
+            backend,
+            |b, backend| {
+                b.iter_batched(
+                    || backend.create(base.clone()).expect("Failed to create UTXO store"),
+                    |mut store| {
+                        for (i, (block, witnesses)) in blocks.iter().enumerate() {
+                            let _result = store.connect_block(
+                                black_box(block),
+                                black_box(witnesses),
+                                START_HEIGHT + i as u64,
+                                Network::Mainnet,
+                            );
+                        }
+                        store
+                    },
+                    BatchSize::LargeInput,
+                )
+            },
+        );
+    }
+    group.finish();
+}
+
+criterion_group!(benches, benchmark_utxo_backends);
+criterion_main!(benches);
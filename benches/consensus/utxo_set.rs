@@ -0,0 +1,174 @@
+//! UTXO Set Operations at Realistic Scale
+//!
+//! Benchmarks insert / lookup / remove / iterate on `UtxoSet` populated with
+//! 1M and 10M synthetic entries, plus applying a full block's worth of changes.
+//! Mainnet scale (80M entries, tens of GB of RAM) is opt-in via
+//! BLVM_BENCH_UTXO_FULL_SCALE=1.
+//!
+//! Mutating benchmarks use `iter_custom` so only the measured operation is
+//! timed; the set is restored afterwards to keep every iteration at the same size.
+
+use blvm_bench::bench_fixtures::splitmix64;
+use blvm_consensus::{OutPoint, UtxoSet, UTXO};
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+use std::time::{Duration, Instant};
+
+/// Spent inputs in a typical full mainnet block
+const BLOCK_SPENDS: usize = 5_000;
+/// Created outputs in a typical full mainnet block
+const BLOCK_CREATES: usize = 7_000;
+/// Keys per lookup/insert/remove measurement
+const OPS_PER_ITER: usize = 1_000;
+
+fn scales() -> Vec<usize> {
+    let mut scales = vec![1_000_000, 10_000_000];
+    if std::env::var("BLVM_BENCH_UTXO_FULL_SCALE").is_ok() {
+        scales.push(80_000_000);
+    }
+    scales
+}
+
+fn outpoint(id: u64) -> OutPoint {
+    let mut hash = [0u8; 32];
+    for (i, word) in hash.chunks_mut(8).enumerate() {
+        word.copy_from_slice(&splitmix64(id.wrapping_mul(4).wrapping_add(i as u64)).to_le_bytes());
+    }
+    OutPoint {
+        hash,
+        index: (id % 4) as _,
+    }
+}
+
+fn utxo(id: u64) -> UTXO {
+    // P2WPKH-sized script (OP_0 <20 bytes>)
+    let mut script_pubkey = vec![0x00, 0x14];
+    script_pubkey.extend_from_slice(&outpoint(id).hash[..20]);
+    UTXO {
+        value: (id % 100_000_000) as _,
+        script_pubkey: script_pubkey.into(),
+        height: (id % 800_000) as _,
+        is_coinbase: id % 2_000 == 0,
+    }
+}
+
+fn populate(size: usize) -> UtxoSet {
+    let mut utxo_set = UtxoSet::new();
+    for id in 0..size as u64 {
+        utxo_set.insert(outpoint(id), utxo(id));
+    }
+    utxo_set
+}
+
+fn benchmark_utxo_set(c: &mut Criterion) {
+    for size in scales() {
+        let mut utxo_set = populate(size);
+        let label = format!("{}M", size / 1_000_000);
+        let size = size as u64;
+
+        let mut group = c.benchmark_group("utxo_set");
+        group.sample_size(10);
+
+        // Existing keys, spread across the set
+        let hits: Vec<OutPoint> = (0..OPS_PER_ITER as u64)
+            .map(|i| outpoint(splitmix64(i) % size))
+            .collect();
+        let misses: Vec<OutPoint> = (0..OPS_PER_ITER as u64).map(|i| outpoint(size + i)).collect();
+
+        group.bench_function(BenchmarkId::new("lookup_hit_1k", &label), |b| {
+            b.iter(|| {
+                for op in &hits {
+                    black_box(utxo_set.get(black_box(op)));
+                }
+            })
+        });
+
+        group.bench_function(BenchmarkId::new("lookup_miss_1k", &label), |b| {
+            b.iter(|| {
+                for op in &misses {
+                    black_box(utxo_set.get(black_box(op)));
+                }
+            })
+        });
+
+        group.bench_function(BenchmarkId::new("insert_1k", &label), |b| {
+            b.iter_custom(|iters| {
+                let mut total = Duration::ZERO;
+                for _ in 0..iters {
+                    let start = Instant::now();
+                    for (i, op) in misses.iter().enumerate() {
+                        utxo_set.insert(op.clone(), utxo(size + i as u64));
+                    }
+                    total += start.elapsed();
+                    for op in &misses {
+                        utxo_set.remove(op);
+                    }
+                }
+                total
+            })
+        });
+
+        group.bench_function(BenchmarkId::new("remove_1k", &label), |b| {
+            b.iter_custom(|iters| {
+                let mut total = Duration::ZERO;
+                for _ in 0..iters {
+                    let start = Instant::now();
+                    let removed: Vec<_> = hits.iter().map(|op| utxo_set.remove(op)).collect();
+                    total += start.elapsed();
+                    for (op, entry) in hits.iter().zip(removed) {
+                        if let Some(entry) = entry {
+                            utxo_set.insert(op.clone(), entry);
+                        }
+                    }
+                }
+                total
+            })
+        });
+
+        // One block: spend BLOCK_SPENDS existing coins, create BLOCK_CREATES new ones
+        let spends: Vec<OutPoint> = (0..BLOCK_SPENDS as u64)
+            .map(|i| outpoint(splitmix64(i ^ 0x5555) % size))
+            .collect();
+        let creates: Vec<(OutPoint, UTXO)> = (0..BLOCK_CREATES as u64)
+            .map(|i| (outpoint(size + OPS_PER_ITER as u64 + i), utxo(size + i)))
+            .collect();
+
+        group.bench_function(BenchmarkId::new("apply_block", &label), |b| {
+            b.iter_custom(|iters| {
+                let mut total = Duration::ZERO;
+                for _ in 0..iters {
+                    let start = Instant::now();
+                    let spent: Vec<_> = spends.iter().map(|op| (op, utxo_set.remove(op))).collect();
+                    for (op, entry) in &creates {
+                        utxo_set.insert(op.clone(), entry.clone());
+                    }
+                    total += start.elapsed();
+                    // Undo so the next iteration sees the same set
+                    for (op, _) in &creates {
+                        utxo_set.remove(op);
+                    }
+                    for (op, entry) in spent {
+                        if let Some(entry) = entry {
+                            utxo_set.insert(op.clone(), entry);
+                        }
+                    }
+                }
+                total
+            })
+        });
+
+        group.bench_function(BenchmarkId::new("iterate", &label), |b| {
+            b.iter(|| {
+                let mut total_value: u64 = 0;
+                for (_, entry) in utxo_set.iter() {
+                    total_value = total_value.wrapping_add(entry.value as u64);
+                }
+                black_box(total_value)
+            })
+        });
+
+        group.finish();
+    }
+}
+
+criterion_group!(benches, benchmark_utxo_set);
+criterion_main!(benches);
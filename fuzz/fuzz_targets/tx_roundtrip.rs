@@ -0,0 +1,15 @@
+//! Transaction decode/re-encode round-trip against the reference wire parser
+//!
+//! Run from the repository root with `cargo +nightly fuzz run tx_roundtrip`.
+//! A crash is an encoding BLVM and the reference disagree on (or one that made
+//! either panic); `tx_roundtrip::weird_encodings()` holds the known edge cases.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    if let Err(e) = blvm_bench::tx_roundtrip::check_roundtrip(data) {
+        panic!("{:#}", e);
+    }
+});
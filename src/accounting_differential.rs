@@ -0,0 +1,624 @@
+//! Block Accounting Differential
+//!
+//! Accounting bugs (a weight off by one witness byte, a miscounted sigop)
+//! don't change any verdict until a block lands right on a limit, so a replay
+//! of the main chain can pass with them. This compares, per block, BLVM's
+//! weight, size, stripped size, per-transaction weight and witness commitment
+//! with the fields of Core's `getblock` (verbosity 3).
+//!
+//! Core doesn't report a block's sigop cost over RPC, so a reference count is
+//! taken instead: Core's `GetTransactionSigOpCost` rules (legacy, P2SH and
+//! witness sigops) applied to Core's own transaction and prevout data. It
+//! needs the verbosity-3 prevouts (Core 23+); blocks close to the sigop or
+//! weight limit are listed as boundary candidates for fixtures and benches.
+//! `block_sigop_cost` applies the same rules to a serialized block and a UTXO
+//! pre-state, for fixtures that come without Core's JSON.
+
+use anyhow::{Context, Result};
+use blvm_consensus::segwit::{calculate_block_weight, calculate_transaction_weight};
+use blvm_consensus::serialization::block::deserialize_block_with_witnesses;
+use blvm_consensus::serialization::transaction::serialize_transaction;
+use blvm_consensus::{Block, OutPoint, UtxoSet};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::time::Instant;
+
+use crate::core_rpc_client::CoreRpcClient;
+use crate::mutation_differential::raw_transactions;
+
+/// Consensus limits (BIP141)
+pub const MAX_BLOCK_WEIGHT: u64 = 4_000_000;
+pub const MAX_BLOCK_SIGOPS_COST: u64 = 80_000;
+/// Fraction of a limit above which a block counts as a boundary candidate
+const BOUNDARY_FRACTION: f64 = 0.95;
+/// The one mainnet block validated without P2SH (BIP16 exception, 00000000000002dc...9c22)
+const BIP16_EXCEPTION_HEIGHT: u64 = 170_060;
+/// Witness commitment output prefix: OP_RETURN, push 36, 0xaa21a9ed
+const WITNESS_COMMITMENT_PREFIX: [u8; 6] = [0x6a, 0x24, 0xaa, 0x21, 0xa9, 0xed];
+/// Stop collecting mismatch details after this many
+const MAX_REPORTED_MISMATCHES: usize = 100;
+
+/// Size and weight figures of one block
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BlockAccounting {
+    pub weight: u64,
+    pub size: u64,
+    pub stripped_size: u64,
+    pub tx_weights: Vec<u64>,
+    /// Hex of the last coinbase output carrying a witness commitment
+    pub witness_commitment: Option<String>,
+}
+
+fn commitment_of<'a>(scripts: impl Iterator<Item = &'a [u8]>) -> Option<String> {
+    scripts
+        .filter(|s| s.len() >= 38 && s.starts_with(&WITNESS_COMMITMENT_PREFIX))
+        .last()
+        .map(hex::encode)
+}
+
+fn compact_size_len(n: u64) -> u64 {
+    match n {
+        0..=0xfc => 1,
+        0xfd..=0xffff => 3,
+        0x1_0000..=0xffff_ffff => 5,
+        _ => 9,
+    }
+}
+
+/// Size of a block serialized without witness data
+pub fn stripped_size(block: &Block) -> u64 {
+    80 + compact_size_len(block.transactions.len() as u64)
+        + block
+            .transactions
+            .iter()
+            .map(|tx| serialize_transaction(tx).len() as u64)
+            .sum::<u64>()
+}
+
+/// BLVM's figures for a serialized block
+pub fn blvm_accounting(block_bytes: &[u8]) -> Result<BlockAccounting> {
+    let (block, witnesses) = deserialize_block_with_witnesses(block_bytes)
+        .map_err(|e| anyhow::anyhow!("Failed to deserialize block: {}", e))?;
+    let weight = calculate_block_weight(&block, &witnesses).map_err(|e| anyhow::anyhow!("Block weight: {:?}", e))?;
+    let tx_weights = block
+        .transactions
+        .iter()
+        .zip(witnesses.iter())
+        .map(|(tx, witness)| {
+            calculate_transaction_weight(tx, Some(witness))
+                .map_err(|e| anyhow::anyhow!("Transaction weight: {:?}", e))
+        })
+        .collect::<Result<Vec<u64>>>()?;
+    let stripped_size = stripped_size(&block);
+    let witness_commitment = block
+        .transactions
+        .first()
+        .and_then(|coinbase| commitment_of(coinbase.outputs.iter().map(|o| &o.script_pubkey[..])));
+    Ok(BlockAccounting {
+        weight,
+        size: block_bytes.len() as u64,
+        stripped_size,
+        tx_weights,
+        witness_commitment,
+    })
+}
+
+fn field_u64(v: &Value, key: &str) -> Result<u64> {
+    v.get(key).and_then(|x| x.as_u64()).with_context(|| format!("getblock missing {}", key))
+}
+
+fn script_hex(v: &Value, key: &str) -> Result<Vec<u8>> {
+    let hex_str = v
+        .get(key)
+        .and_then(|s| s.get("hex"))
+        .and_then(|h| h.as_str())
+        .unwrap_or_default();
+    Ok(hex::decode(hex_str)?)
+}
+
+/// Core's figures from `getblock` verbosity 2 or 3
+pub fn core_accounting(block: &Value) -> Result<BlockAccounting> {
+    let txs = block.get("tx").and_then(|t| t.as_array()).context("getblock missing tx")?;
+    let tx_weights = txs.iter().map(|tx| field_u64(tx, "weight")).collect::<Result<Vec<u64>>>()?;
+    let coinbase_scripts = txs
+        .first()
+        .and_then(|tx| tx.get("vout"))
+        .and_then(|v| v.as_array())
+        .map(|vouts| vouts.iter().map(|o| script_hex(o, "scriptPubKey")).collect::<Result<Vec<_>>>())
+        .transpose()?
+        .unwrap_or_default();
+    Ok(BlockAccounting {
+        weight: field_u64(block, "weight")?,
+        size: field_u64(block, "size")?,
+        stripped_size: field_u64(block, "strippedsize")?,
+        tx_weights,
+        witness_commitment: commitment_of(coinbase_scripts.iter().map(|s| &s[..])),
+    })
+}
+
+/// One accounting figure BLVM and Core disagree on
+#[derive(Debug, Clone)]
+pub struct AccountingMismatch {
+    pub height: u64,
+    pub field: String,
+    pub blvm: String,
+    pub core: String,
+}
+
+/// Field-by-field differences (per-transaction weights report the first differing tx)
+pub fn compare_accounting(height: u64, blvm: &BlockAccounting, core: &BlockAccounting) -> Vec<AccountingMismatch> {
+    let mut mismatches = Vec::new();
+    let mut check = |field: String, blvm: String, core: String| {
+        if blvm != core {
+            mismatches.push(AccountingMismatch { height, field, blvm, core });
+        }
+    };
+    check("weight".into(), blvm.weight.to_string(), core.weight.to_string());
+    check("size".into(), blvm.size.to_string(), core.size.to_string());
+    check("strippedsize".into(), blvm.stripped_size.to_string(), core.stripped_size.to_string());
+    if blvm.tx_weights.len() != core.tx_weights.len() {
+        check("tx count".into(), blvm.tx_weights.len().to_string(), core.tx_weights.len().to_string());
+    } else if let Some(index) = blvm.tx_weights.iter().zip(&core.tx_weights).position(|(b, c)| b != c) {
+        check(
+            format!("tx {} weight", index),
+            blvm.tx_weights[index].to_string(),
+            core.tx_weights[index].to_string(),
+        );
+    }
+    let none = || "none".to_string();
+    check(
+        "witness commitment".into(),
+        blvm.witness_commitment.clone().unwrap_or_else(none),
+        core.witness_commitment.clone().unwrap_or_else(none),
+    );
+    mismatches
+}
+
+// ---------------------------------------------------------------------------
+// Reference sigop cost (Core's GetTransactionSigOpCost)
+// ---------------------------------------------------------------------------
+
+const OP_PUSHDATA1: u8 = 0x4c;
+const OP_PUSHDATA2: u8 = 0x4d;
+const OP_PUSHDATA4: u8 = 0x4e;
+const OP_1: u8 = 0x51;
+const OP_16: u8 = 0x60;
+const OP_CHECKSIG: u8 = 0xac;
+const OP_CHECKSIGVERIFY: u8 = 0xad;
+const OP_CHECKMULTISIG: u8 = 0xae;
+const OP_CHECKMULTISIGVERIFY: u8 = 0xaf;
+const WITNESS_SCALE_FACTOR: u64 = 4;
+
+/// Next (opcode, push data) of a script; None at the end or on a truncated push
+fn next_op<'a>(script: &'a [u8], pos: &mut usize) -> Option<(u8, &'a [u8])> {
+    let opcode = *script.get(*pos)?;
+    *pos += 1;
+    let len = match opcode {
+        0x01..=0x4b => opcode as usize,
+        OP_PUSHDATA1 => {
+            let len = *script.get(*pos)? as usize;
+            *pos += 1;
+            len
+        }
+        OP_PUSHDATA2 => {
+            let len = u16::from_le_bytes(script.get(*pos..*pos + 2)?.try_into().ok()?) as usize;
+            *pos += 2;
+            len
+        }
+        OP_PUSHDATA4 => {
+            let len = u32::from_le_bytes(script.get(*pos..*pos + 4)?.try_into().ok()?) as usize;
+            *pos += 4;
+            len
+        }
+        _ => 0,
+    };
+    let data = script.get(*pos..pos.checked_add(len)?)?;
+    *pos += len;
+    Some((opcode, data))
+}
+
+/// CScript::GetSigOpCount
+fn script_sigops(script: &[u8], accurate: bool) -> u64 {
+    let mut count = 0;
+    let mut last_opcode = 0xff;
+    let mut pos = 0;
+    while let Some((opcode, _)) = next_op(script, &mut pos) {
+        match opcode {
+            OP_CHECKSIG | OP_CHECKSIGVERIFY => count += 1,
+            OP_CHECKMULTISIG | OP_CHECKMULTISIGVERIFY => {
+                count += if accurate && (OP_1..=OP_16).contains(&last_opcode) {
+                    (last_opcode - OP_1 + 1) as u64
+                } else {
+                    20
+                };
+            }
+            _ => {}
+        }
+        last_opcode = opcode;
+    }
+    count
+}
+
+/// Last push of a push-only script (the P2SH redeem script)
+fn last_push(script_sig: &[u8]) -> Option<&[u8]> {
+    let mut pos = 0;
+    let mut last = None;
+    while pos < script_sig.len() {
+        let (opcode, data) = next_op(script_sig, &mut pos)?;
+        if opcode > OP_16 {
+            return None;
+        }
+        last = Some(data);
+    }
+    last
+}
+
+fn is_p2sh(script: &[u8]) -> bool {
+    script.len() == 23 && script[0] == 0xa9 && script[1] == 0x14 && script[22] == 0x87
+}
+
+/// (version, program) of a witness program
+fn witness_program(script: &[u8]) -> Option<(u8, &[u8])> {
+    if !(4..=42).contains(&script.len()) || script[1] as usize + 2 != script.len() {
+        return None;
+    }
+    match script[0] {
+        0x00 => Some((0, &script[2..])),
+        op @ OP_1..=OP_16 => Some((op - OP_1 + 1, &script[2..])),
+        _ => None,
+    }
+}
+
+fn witness_sigops(version: u8, program: &[u8], witness: &[Vec<u8>]) -> u64 {
+    match (version, program.len()) {
+        (0, 20) => 1,
+        (0, 32) => witness.last().map(|script| script_sigops(script, true)).unwrap_or(0),
+        _ => 0,
+    }
+}
+
+/// P2SH and witness sigop cost of spending `prev_script`, the part that needs the prevout
+fn input_sigop_cost(script_sig: &[u8], prev_script: &[u8], witness: &[Vec<u8>]) -> u64 {
+    let mut cost = 0;
+    let redeem_script = is_p2sh(prev_script).then(|| last_push(script_sig)).flatten();
+    if let Some(redeem) = redeem_script {
+        cost += script_sigops(redeem, true) * WITNESS_SCALE_FACTOR;
+    }
+    // Native witness program, or one nested in P2SH
+    if let Some((version, program)) = witness_program(prev_script).or_else(|| redeem_script.and_then(witness_program)) {
+        cost += witness_sigops(version, program, witness);
+    }
+    cost
+}
+
+/// Sigop cost of one transaction from getblock verbosity-3 JSON; None without prevouts
+fn tx_sigop_cost(tx: &Value, p2sh_and_witness: bool) -> Result<Option<u64>> {
+    let vin = tx.get("vin").and_then(|v| v.as_array()).context("tx missing vin")?;
+    let vout = tx.get("vout").and_then(|v| v.as_array()).context("tx missing vout")?;
+    let is_coinbase = vin.first().is_some_and(|i| i.get("coinbase").is_some());
+
+    let mut legacy = 0;
+    for input in vin {
+        // Core counts the coinbase scriptSig too, which getblock shows as `coinbase`
+        let script_sig = match input.get("coinbase").and_then(|c| c.as_str()) {
+            Some(coinbase) => hex::decode(coinbase)?,
+            None => script_hex(input, "scriptSig")?,
+        };
+        legacy += script_sigops(&script_sig, false);
+    }
+    for output in vout {
+        legacy += script_sigops(&script_hex(output, "scriptPubKey")?, false);
+    }
+    let mut cost = legacy * WITNESS_SCALE_FACTOR;
+    if is_coinbase || !p2sh_and_witness {
+        return Ok(Some(cost));
+    }
+
+    for input in vin {
+        let Some(prevout) = input.get("prevout") else {
+            return Ok(None);
+        };
+        let prev_script = script_hex(prevout, "scriptPubKey")?;
+        let script_sig = script_hex(input, "scriptSig")?;
+        let witness: Vec<Vec<u8>> = input
+            .get("txinwitness")
+            .and_then(|w| w.as_array())
+            .map(|items| items.iter().map(|i| hex::decode(i.as_str().unwrap_or_default())).collect())
+            .transpose()?
+            .unwrap_or_default();
+        cost += input_sigop_cost(&script_sig, &prev_script, &witness);
+    }
+    Ok(Some(cost))
+}
+
+/// Reference sigop cost of a block (None if Core didn't include prevouts)
+pub fn reference_sigop_cost(block: &Value, height: u64) -> Result<Option<u64>> {
+    let txs = block.get("tx").and_then(|t| t.as_array()).context("getblock missing tx")?;
+    let p2sh_and_witness = height != BIP16_EXCEPTION_HEIGHT;
+    let mut total = 0;
+    for tx in txs {
+        match tx_sigop_cost(tx, p2sh_and_witness)? {
+            Some(cost) => total += cost,
+            None => return Ok(None),
+        }
+    }
+    Ok(Some(total))
+}
+
+/// Legacy sigop cost of a block (Core's GetLegacySigOpCount, the count CheckBlock limits)
+pub fn legacy_sigop_cost(block: &Block) -> u64 {
+    block
+        .transactions
+        .iter()
+        .map(|tx| {
+            let script_sigs = tx.inputs.iter().map(|i| script_sigops(&i.script_sig, false));
+            let script_pubkeys = tx.outputs.iter().map(|o| script_sigops(&o.script_pubkey, false));
+            script_sigs.chain(script_pubkeys).sum::<u64>()
+        })
+        .sum::<u64>()
+        * WITNESS_SCALE_FACTOR
+}
+
+/// Reference sigop cost of a serialized block, prevouts from `pre_state` or the block itself
+/// (None if one is missing)
+///
+/// Works on the raw wire format so witness stacks are kept per input.
+pub fn block_sigop_cost(block_bytes: &[u8], height: u64, pre_state: &UtxoSet) -> Result<Option<u64>> {
+    let transactions = raw_transactions(block_bytes)?;
+    let p2sh_and_witness = height != BIP16_EXCEPTION_HEIGHT;
+    let mut created: HashMap<OutPoint, &[u8]> = HashMap::new();
+    let mut total = 0;
+    for (index, tx) in transactions.iter().enumerate() {
+        let is_coinbase = index == 0;
+        let legacy = tx
+            .inputs
+            .iter()
+            .map(|input| script_sigops(&input.script_sig, false))
+            .chain(tx.outputs.iter().map(|(_, script)| script_sigops(script, false)))
+            .sum::<u64>();
+        total += legacy * WITNESS_SCALE_FACTOR;
+        if !is_coinbase && p2sh_and_witness {
+            for input in &tx.inputs {
+                let prevout = OutPoint {
+                    hash: input.prevout[..32].try_into()?,
+                    index: u32::from_le_bytes(input.prevout[32..].try_into()?) as _,
+                };
+                let prev_script = created
+                    .get(&prevout)
+                    .copied()
+                    .or_else(|| pre_state.get(&prevout).map(|utxo| &utxo.script_pubkey[..]));
+                let Some(prev_script) = prev_script else {
+                    return Ok(None);
+                };
+                total += input_sigop_cost(&input.script_sig, prev_script, &input.witness);
+            }
+        }
+        let txid = tx.txid();
+        for (vout, (_, script_pubkey)) in tx.outputs.iter().enumerate() {
+            created.insert(OutPoint { hash: txid, index: vout as _ }, script_pubkey);
+        }
+    }
+    Ok(Some(total))
+}
+
+// ---------------------------------------------------------------------------
+// Run
+// ---------------------------------------------------------------------------
+
+/// A block close to the weight or sigop limit
+#[derive(Debug, Clone)]
+pub struct BoundaryBlock {
+    pub height: u64,
+    pub weight: u64,
+    pub sigop_cost: Option<u64>,
+}
+
+/// Result of an accounting run
+#[derive(Debug, Default)]
+pub struct AccountingReport {
+    pub blocks_checked: u64,
+    pub mismatch_count: u64,
+    /// First `MAX_REPORTED_MISMATCHES` mismatches, in height order
+    pub mismatches: Vec<AccountingMismatch>,
+    /// (height, cost) of the block with the highest reference sigop cost
+    pub max_sigop_cost: Option<(u64, u64)>,
+    pub boundary_blocks: Vec<BoundaryBlock>,
+    pub elapsed_secs: f64,
+}
+
+/// Compare accounting for blocks `start_height..=end_height` (end defaults to Core's tip)
+pub async fn run_accounting_differential(
+    client: &CoreRpcClient,
+    start_height: u64,
+    end_height: Option<u64>,
+) -> Result<AccountingReport> {
+    let tip = client.getblockcount().await?;
+    let end_height = end_height.unwrap_or(tip).min(tip);
+    println!("⚖️  Accounting differential: blocks {}..={}", start_height, end_height);
+
+    let start = Instant::now();
+    let mut report = AccountingReport::default();
+    let mut warned_no_prevouts = false;
+    for height in start_height..=end_height {
+        let hash = client.getblockhash(height).await?;
+        let block_bytes = hex::decode(client.getblock_raw(&hash).await?)?;
+        let core_block = client.getblock(&hash, 3).await?;
+
+        let blvm = blvm_accounting(&block_bytes).with_context(|| format!("BLVM accounting for block {}", height))?;
+        let core = core_accounting(&core_block).with_context(|| format!("Core accounting for block {}", height))?;
+        for mismatch in compare_accounting(height, &blvm, &core) {
+            report.mismatch_count += 1;
+            if report.mismatches.len() < MAX_REPORTED_MISMATCHES {
+                eprintln!(
+                    "❌ Block {} {}: BLVM={}, Core={}",
+                    height, mismatch.field, mismatch.blvm, mismatch.core
+                );
+                report.mismatches.push(mismatch);
+            }
+        }
+
+        let sigop_cost = reference_sigop_cost(&core_block, height)?;
+        match sigop_cost {
+            Some(cost) if report.max_sigop_cost.is_none_or(|(_, max)| cost > max) => {
+                report.max_sigop_cost = Some((height, cost));
+            }
+            None if !warned_no_prevouts => {
+                eprintln!("⚠️  Core's getblock has no prevouts (needs Core 23+); sigop cost not counted");
+                warned_no_prevouts = true;
+            }
+            _ => {}
+        }
+        let near_weight = core.weight as f64 >= BOUNDARY_FRACTION * MAX_BLOCK_WEIGHT as f64;
+        let near_sigops = sigop_cost.is_some_and(|c| c as f64 >= BOUNDARY_FRACTION * MAX_BLOCK_SIGOPS_COST as f64);
+        if near_weight || near_sigops {
+            report.boundary_blocks.push(BoundaryBlock {
+                height,
+                weight: core.weight,
+                sigop_cost,
+            });
+        }
+
+        report.blocks_checked += 1;
+        if report.blocks_checked % 10_000 == 0 {
+            println!(
+                "   {} blocks checked ({:.0}/s)",
+                report.blocks_checked,
+                report.blocks_checked as f64 / start.elapsed().as_secs_f64()
+            );
+        }
+    }
+
+    report.elapsed_secs = start.elapsed().as_secs_f64();
+    println!(
+        "{} {} blocks checked in {:.1}s, {} accounting mismatches",
+        if report.mismatch_count == 0 { "✅" } else { "❌" },
+        report.blocks_checked,
+        report.elapsed_secs,
+        report.mismatch_count
+    );
+    if let Some((height, cost)) = report.max_sigop_cost {
+        println!("   Highest sigop cost: {} at height {} (limit {})", cost, height, MAX_BLOCK_SIGOPS_COST);
+    }
+    if !report.boundary_blocks.is_empty() {
+        let heights: Vec<String> = report.boundary_blocks.iter().map(|b| b.height.to_string()).collect();
+        println!(
+            "   {} blocks within {:.0}% of the weight/sigop limit: {}",
+            heights.len(),
+            (1.0 - BOUNDARY_FRACTION) * 100.0,
+            heights.join(",")
+        );
+    }
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_script_sigops() {
+        // Bare 1-of-3 style multisig: accurate counting uses the key count before CHECKMULTISIG
+        let multisig = [vec![0x51, 0x21], vec![0u8; 33], vec![0x53, OP_CHECKMULTISIG]].concat();
+        assert_eq!(script_sigops(&multisig, true), 3);
+        assert_eq!(script_sigops(&multisig, false), 20);
+        assert_eq!(script_sigops(&[OP_CHECKSIG, OP_CHECKSIGVERIFY], false), 2);
+        // Truncated push stops counting
+        assert_eq!(script_sigops(&[OP_CHECKSIG, 0x05, 0x01], false), 1);
+    }
+
+    #[test]
+    fn test_reference_sigop_cost() {
+        let p2pkh = format!("76a914{}88ac", "00".repeat(20));
+        let p2wpkh = format!("0014{}", "11".repeat(20));
+        let block = json!({ "tx": [
+            { "vin": [{ "coinbase": "00" }], "vout": [{ "scriptPubKey": { "hex": p2pkh } }] },
+            { "vin": [{ "scriptSig": { "hex": "" }, "txinwitness": ["30", "02"],
+                        "prevout": { "scriptPubKey": { "hex": p2wpkh } } }],
+              "vout": [{ "scriptPubKey": { "hex": p2pkh } }] },
+        ]});
+        // Coinbase P2PKH output 4, spend: P2PKH output 4 + P2WPKH input 1
+        assert_eq!(reference_sigop_cost(&block, 500_000).unwrap(), Some(9));
+
+        let no_prevouts = json!({ "tx": [
+            { "vin": [{ "scriptSig": { "hex": "" } }], "vout": [] },
+        ]});
+        assert_eq!(reference_sigop_cost(&no_prevouts, 500_000).unwrap(), None);
+
+        // Core counts sigops in the coinbase scriptSig, even though it never runs
+        let coinbase_sigops = json!({ "tx": [
+            { "vin": [{ "coinbase": "03a08601ac" }], "vout": [{ "scriptPubKey": { "hex": p2pkh } }] },
+        ]});
+        assert_eq!(reference_sigop_cost(&coinbase_sigops, 500_000).unwrap(), Some(8));
+    }
+
+    #[test]
+    fn test_block_sigop_cost() {
+        use crate::mutation_differential::{RawInput, RawTx};
+        use blvm_consensus::UTXO;
+
+        let p2pkh = [vec![0x76, 0xa9, 0x14], vec![0u8; 20], vec![0x88, OP_CHECKSIG]].concat();
+        let p2wpkh = [vec![0x00, 0x14], vec![0x11; 20]].concat();
+        let input = |hash: [u8; 32], index: u32, witness: Vec<Vec<u8>>| {
+            let mut prevout = [0u8; 36];
+            prevout[..32].copy_from_slice(&hash);
+            prevout[32..].copy_from_slice(&index.to_le_bytes());
+            RawInput { prevout, script_sig: Vec::new(), sequence: 0xffff_ffff, witness }
+        };
+        let tx = |input: RawInput, script: &[u8]| RawTx {
+            version: 2,
+            inputs: vec![input],
+            outputs: vec![(1_000, script.to_vec())],
+            lock_time: 0,
+        };
+        let coinbase = tx(input([0; 32], u32::MAX, Vec::new()), &p2pkh);
+        let spend = tx(input([7; 32], 0, vec![vec![0x30], vec![0x02]]), &p2wpkh);
+        // Spends an output created earlier in the same block
+        let child = tx(input(spend.txid(), 0, vec![vec![0x30], vec![0x02]]), &p2wpkh);
+        let block = |txs: &[&RawTx]| {
+            let mut bytes = vec![0u8; 80];
+            bytes.push(txs.len() as u8);
+            for tx in txs {
+                bytes.extend_from_slice(&tx.to_bytes());
+            }
+            bytes
+        };
+
+        let mut pre_state = UtxoSet::new();
+        pre_state.insert(
+            OutPoint { hash: [7; 32], index: 0 },
+            UTXO { value: 2_000, script_pubkey: p2wpkh.clone().into(), height: 1, is_coinbase: false },
+        );
+        // Coinbase P2PKH output 4, then one P2WPKH input each
+        let full = block(&[&coinbase, &spend, &child]);
+        assert_eq!(block_sigop_cost(&full, 500_000, &pre_state).unwrap(), Some(6));
+        assert_eq!(block_sigop_cost(&full, 500_000, &UtxoSet::new()).unwrap(), None);
+
+        // Sigop opcodes in the coinbase scriptSig count, as in Core's GetLegacySigOpCount
+        let mut coinbase_input = input([0; 32], u32::MAX, Vec::new());
+        coinbase_input.script_sig = vec![0x03, 0xa0, 0x86, 0x01, OP_CHECKSIG, OP_CHECKMULTISIG];
+        let sigop_coinbase = tx(coinbase_input, &p2pkh);
+        let with_sigops = block(&[&sigop_coinbase, &spend, &child]);
+        // 1 + 20 (inaccurate CHECKMULTISIG) more, times the witness scale factor
+        assert_eq!(block_sigop_cost(&with_sigops, 500_000, &pre_state).unwrap(), Some(6 + 21 * 4));
+        let (decoded, _) = deserialize_block_with_witnesses(&with_sigops).unwrap();
+        assert_eq!(legacy_sigop_cost(&decoded), (21 + 1) * 4);
+    }
+
+    #[test]
+    fn test_compare_accounting() {
+        let core = BlockAccounting {
+            weight: 4000,
+            size: 1000,
+            stripped_size: 1000,
+            tx_weights: vec![800, 3200],
+            witness_commitment: None,
+        };
+        let mut blvm = core.clone();
+        assert!(compare_accounting(1, &blvm, &core).is_empty());
+        blvm.tx_weights[1] = 3201;
+        blvm.weight = 4001;
+        let fields: Vec<String> = compare_accounting(1, &blvm, &core).into_iter().map(|m| m.field).collect();
+        assert_eq!(fields, vec!["weight", "tx 1 weight"]);
+    }
+}
@@ -0,0 +1,176 @@
+//! Soft-Fork Activation Height Verification
+//!
+//! Compares the heights at which BLVM turns on each script-verification rule
+//! (BIP34, BIP66, CLTV, CSV, segwit, taproot) with the activation heights Core
+//! reports via `getdeploymentinfo` (or `getblockchaininfo.softforks` on older
+//! nodes), and reports the first height where the active flag sets differ -
+//! instead of waiting for a downstream validation divergence to reveal it.
+
+use anyhow::{Context, Result};
+use blvm_consensus::constants::{
+    BIP34_ACTIVATION_MAINNET, BIP65_ACTIVATION_MAINNET, BIP66_ACTIVATION_MAINNET, CSV_ACTIVATION_MAINNET,
+    SEGWIT_ACTIVATION_MAINNET, TAPROOT_ACTIVATION_MAINNET,
+};
+use serde_json::Value;
+use std::collections::BTreeSet;
+
+use crate::core_rpc_client::{BitcoinNetwork, CoreRpcClient};
+
+/// Consensus deployments that change script verification flags
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Deployment {
+    Bip34,
+    Bip66,
+    Cltv,
+    Csv,
+    Segwit,
+    Taproot,
+}
+
+impl Deployment {
+    pub const ALL: [Deployment; 6] = [
+        Deployment::Bip34,
+        Deployment::Bip66,
+        Deployment::Cltv,
+        Deployment::Csv,
+        Deployment::Segwit,
+        Deployment::Taproot,
+    ];
+
+    /// Key in Core's deployment RPCs
+    pub fn core_name(&self) -> &'static str {
+        match self {
+            Deployment::Bip34 => "bip34",
+            Deployment::Bip66 => "bip66",
+            Deployment::Cltv => "bip65",
+            Deployment::Csv => "csv",
+            Deployment::Segwit => "segwit",
+            Deployment::Taproot => "taproot",
+        }
+    }
+
+    /// Height at which BLVM activates this deployment (mainnet)
+    pub fn blvm_height(&self) -> u64 {
+        match self {
+            Deployment::Bip34 => BIP34_ACTIVATION_MAINNET,
+            Deployment::Bip66 => BIP66_ACTIVATION_MAINNET,
+            Deployment::Cltv => BIP65_ACTIVATION_MAINNET,
+            Deployment::Csv => CSV_ACTIVATION_MAINNET,
+            Deployment::Segwit => SEGWIT_ACTIVATION_MAINNET,
+            Deployment::Taproot => TAPROOT_ACTIVATION_MAINNET,
+        }
+    }
+}
+
+/// Activation heights for one side; None = not active on that side
+pub type ActivationHeights = Vec<(Deployment, Option<u64>)>;
+
+/// Deployments active at `height`
+pub fn active_set(heights: &ActivationHeights, height: u64) -> BTreeSet<Deployment> {
+    heights
+        .iter()
+        .filter(|(_, h)| h.is_some_and(|h| height >= h))
+        .map(|(d, _)| *d)
+        .collect()
+}
+
+/// First height at which the two sides' active sets differ, with the deployments responsible
+pub fn first_divergence(blvm: &ActivationHeights, core: &ActivationHeights) -> Option<(u64, Vec<Deployment>)> {
+    let mut candidates: Vec<u64> = blvm.iter().chain(core.iter()).filter_map(|(_, h)| *h).collect();
+    candidates.sort_unstable();
+    candidates.dedup();
+
+    candidates.into_iter().find_map(|height| {
+        let b = active_set(blvm, height);
+        let c = active_set(core, height);
+        (b != c).then(|| (height, b.symmetric_difference(&c).copied().collect()))
+    })
+}
+
+/// BLVM's activation heights
+pub fn blvm_activation_heights() -> ActivationHeights {
+    Deployment::ALL.iter().map(|d| (*d, Some(d.blvm_height()))).collect()
+}
+
+/// Parse Core's deployments (`getdeploymentinfo.deployments` or `getblockchaininfo.softforks`)
+pub fn parse_core_deployments(deployments: &Value) -> ActivationHeights {
+    Deployment::ALL
+        .iter()
+        .map(|d| {
+            let entry = deployments.get(d.core_name());
+            let height = entry.and_then(|e| {
+                e.get("height")
+                    .and_then(|h| h.as_u64())
+                    // BIP9 deployments carry their height under bip9.since once active
+                    .or_else(|| {
+                        e.get("bip9")
+                            .filter(|b| b.get("status").and_then(|s| s.as_str()) == Some("active"))
+                            .and_then(|b| b.get("since"))
+                            .and_then(|h| h.as_u64())
+                    })
+            });
+            (*d, height)
+        })
+        .collect()
+}
+
+/// Fetch Core's activation heights, preferring getdeploymentinfo
+pub async fn core_activation_heights(client: &CoreRpcClient) -> Result<ActivationHeights> {
+    let deployments = match client.getdeploymentinfo().await {
+        Ok(info) => info.get("deployments").cloned(),
+        Err(_) => client.getblockchaininfo().await?.get("softforks").cloned(),
+    }
+    .context("Core reported no deployment information")?;
+    Ok(parse_core_deployments(&deployments))
+}
+
+/// Compare activation heights with Core; returns the first diverging height, if any
+pub async fn run_activation_check(client: &CoreRpcClient) -> Result<Option<(u64, Vec<Deployment>)>> {
+    if client.detect_network().await? != BitcoinNetwork::Mainnet {
+        anyhow::bail!("Activation check compares BLVM's mainnet heights; point it at a mainnet node");
+    }
+    let blvm = blvm_activation_heights();
+    let core = core_activation_heights(client).await?;
+
+    println!("🔒 Soft-fork activation heights (BLVM vs Core)");
+    for ((deployment, blvm_h), (_, core_h)) in blvm.iter().zip(core.iter()) {
+        let icon = if blvm_h == core_h { "✅" } else { "❌" };
+        let fmt = |h: &Option<u64>| h.map(|h| h.to_string()).unwrap_or_else(|| "inactive".to_string());
+        println!("   {} {:<8} {:>9} {:>9}", icon, deployment.core_name(), fmt(blvm_h), fmt(core_h));
+    }
+
+    let divergence = first_divergence(&blvm, &core);
+    match &divergence {
+        Some((height, deployments)) => {
+            let names: Vec<_> = deployments.iter().map(|d| d.core_name()).collect();
+            println!("❌ Flag sets first differ at height {} ({})", height, names.join(", "));
+        }
+        None => println!("✅ Flag sets agree at every height"),
+    }
+    Ok(divergence)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_first_divergence() {
+        let blvm = vec![(Deployment::Bip34, Some(100)), (Deployment::Segwit, Some(500))];
+        let core = vec![(Deployment::Bip34, Some(100)), (Deployment::Segwit, Some(481))];
+        assert_eq!(first_divergence(&blvm, &core), Some((481, vec![Deployment::Segwit])));
+        assert_eq!(first_divergence(&blvm, &blvm), None);
+    }
+
+    #[test]
+    fn test_parse_core_deployments() {
+        let deployments = serde_json::json!({
+            "bip34": { "type": "buried", "active": true, "height": 227931 },
+            "taproot": { "type": "bip9", "active": true, "bip9": { "status": "active", "since": 709632 } },
+        });
+        let parsed = parse_core_deployments(&deployments);
+        assert!(parsed.contains(&(Deployment::Bip34, Some(227_931))));
+        assert!(parsed.contains(&(Deployment::Taproot, Some(709_632))));
+        assert!(parsed.contains(&(Deployment::Csv, None)));
+    }
+}
@@ -0,0 +1,160 @@
+//! Divergence Alerts
+//!
+//! POSTs a JSON payload to a webhook the moment a divergence is recorded,
+//! instead of leaving it for the end-of-run summary. Slack and Matrix
+//! (hookshot-style) incoming webhooks get a `text` message; the generic
+//! format sends the raw alert object.
+//!
+//! Configured with `BLVM_ALERT_WEBHOOK` and `BLVM_ALERT_FORMAT`
+//! (`generic`, `slack` or `matrix`).
+
+use anyhow::Result;
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::block_hash::block_hash_hex;
+
+/// Webhook payload flavour
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum WebhookFormat {
+    #[default]
+    Generic,
+    Slack,
+    Matrix,
+}
+
+impl WebhookFormat {
+    pub fn parse(value: &str) -> Result<Self> {
+        match value.trim().to_lowercase().as_str() {
+            "generic" | "json" => Ok(WebhookFormat::Generic),
+            "slack" => Ok(WebhookFormat::Slack),
+            "matrix" => Ok(WebhookFormat::Matrix),
+            other => anyhow::bail!("Unknown alert format '{}' (expected generic, slack or matrix)", other),
+        }
+    }
+}
+
+/// Where and how to send alerts
+#[derive(Debug, Clone)]
+pub struct AlertConfig {
+    pub webhook_url: String,
+    pub format: WebhookFormat,
+    /// Identifies the run in every alert
+    pub run_id: String,
+}
+
+impl AlertConfig {
+    pub fn new(webhook_url: impl Into<String>, format: WebhookFormat) -> Self {
+        Self {
+            webhook_url: webhook_url.into(),
+            format,
+            run_id: new_run_id(),
+        }
+    }
+
+    /// From `BLVM_ALERT_WEBHOOK` / `BLVM_ALERT_FORMAT`; None if no webhook is set
+    pub fn from_env() -> Result<Option<Self>> {
+        let Ok(url) = std::env::var("BLVM_ALERT_WEBHOOK") else {
+            return Ok(None);
+        };
+        let format = match std::env::var("BLVM_ALERT_FORMAT") {
+            Ok(value) => WebhookFormat::parse(&value)?,
+            Err(_) => WebhookFormat::default(),
+        };
+        Ok(Some(Self::new(url, format)))
+    }
+}
+
+/// `<unix seconds>-<pid>`, unique enough to tell concurrent runs apart
+fn new_run_id() -> String {
+    let secs = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default();
+    format!("{}-{}", secs, std::process::id())
+}
+
+/// One divergence, as sent to the webhook
+#[derive(Debug, Clone, Serialize)]
+pub struct DivergenceAlert {
+    pub run_id: String,
+    pub height: u64,
+    pub hash: String,
+    pub blvm_result: String,
+    pub core_result: String,
+}
+
+impl DivergenceAlert {
+    fn summary(&self) -> String {
+        format!(
+            "❌ BLVM consensus divergence at height {} ({}): BLVM={}, Core={} [run {}]",
+            self.height, self.hash, self.blvm_result, self.core_result, self.run_id
+        )
+    }
+
+    /// Request body for the given webhook format
+    pub fn payload(&self, format: WebhookFormat) -> Value {
+        match format {
+            WebhookFormat::Generic => serde_json::to_value(self).unwrap_or(Value::Null),
+            WebhookFormat::Slack => serde_json::json!({ "text": self.summary() }),
+            WebhookFormat::Matrix => serde_json::json!({
+                "text": self.summary(),
+                "msgtype": "m.text",
+            }),
+        }
+    }
+}
+
+/// Send a divergence alert (failures are logged, not fatal - the run goes on)
+pub async fn notify_divergence(
+    config: Option<&AlertConfig>,
+    height: u64,
+    block_bytes: &[u8],
+    blvm_result: &str,
+    core_result: &str,
+) {
+    let Some(config) = config else {
+        return;
+    };
+    let alert = DivergenceAlert {
+        run_id: config.run_id.clone(),
+        height,
+        hash: block_hash_hex(block_bytes).unwrap_or_default(),
+        blvm_result: blvm_result.to_string(),
+        core_result: core_result.to_string(),
+    };
+    let sent = reqwest::Client::new()
+        .post(&config.webhook_url)
+        .timeout(std::time::Duration::from_secs(10))
+        .json(&alert.payload(config.format))
+        .send()
+        .await;
+    match sent {
+        Ok(response) if response.status().is_success() => println!("   📣 Divergence alert sent"),
+        Ok(response) => eprintln!("   ⚠️  Alert webhook returned {}", response.status()),
+        Err(e) => eprintln!("   ⚠️  Failed to send divergence alert: {}", e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_payload_formats() {
+        let alert = DivergenceAlert {
+            run_id: "1-2".to_string(),
+            height: 100,
+            hash: "00ab".to_string(),
+            blvm_result: "Invalid(x)".to_string(),
+            core_result: "Valid".to_string(),
+        };
+        let generic = alert.payload(WebhookFormat::Generic);
+        assert_eq!(generic["height"], 100);
+        assert_eq!(generic["run_id"], "1-2");
+        let slack = alert.payload(WebhookFormat::Slack);
+        assert!(slack["text"].as_str().unwrap().contains("height 100"));
+        assert!(WebhookFormat::parse("Slack").is_ok());
+        assert!(WebhookFormat::parse("irc").is_err());
+    }
+}
@@ -0,0 +1,258 @@
+//! Bench Configuration
+//!
+//! The full consensus suite takes hours on a large corpus, which is far too
+//! slow for a pre-commit check. A TOML file (`BLVM_BENCH_CONFIG`, or
+//! `bench-config.toml` in the crate root if present) selects which criterion
+//! groups run and overrides their sample size, timing and corpus size:
+//!
+//! ```toml
+//! # Only these groups run (default: all); a trailing `*` matches a prefix
+//! groups = ["block_accounting", "p2sh_verification", "opcode_costs_*"]
+//! skip = ["opcode_costs_signature"]
+//!
+//! # Defaults for every group
+//! sample_size = 10
+//! measurement_time_secs = 2.0
+//!
+//! [group.block_accounting]
+//! corpus_limit = 2
+//! ```
+//!
+//! `BLVM_BENCH_GROUPS`, `BLVM_BENCH_SKIP` (comma-separated),
+//! `BLVM_BENCH_SAMPLE_SIZE`, `BLVM_BENCH_MEASUREMENT_SECS` and
+//! `BLVM_BENCH_CORPUS_LIMIT` override the file for a single run. Settings
+//! nobody configured keep each bench's own defaults. `benches/quick.toml` is a
+//! ready-made pre-commit selection.
+
+use anyhow::{Context, Result};
+use criterion::measurement::Measurement;
+use criterion::{BenchmarkGroup, Criterion};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use std::time::Duration;
+
+/// Criterion refuses fewer samples than this
+const MIN_SAMPLE_SIZE: usize = 10;
+
+/// Per-group overrides; unset fields fall through to the file defaults, then the bench's own
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct GroupSettings {
+    pub sample_size: Option<usize>,
+    pub measurement_time_secs: Option<f64>,
+    pub warm_up_time_secs: Option<f64>,
+    /// Most corpus blocks or fixtures a group loads
+    pub corpus_limit: Option<usize>,
+}
+
+impl GroupSettings {
+    /// `self`, with unset fields taken from `fallback`
+    fn or(self, fallback: GroupSettings) -> GroupSettings {
+        GroupSettings {
+            sample_size: self.sample_size.or(fallback.sample_size),
+            measurement_time_secs: self.measurement_time_secs.or(fallback.measurement_time_secs),
+            warm_up_time_secs: self.warm_up_time_secs.or(fallback.warm_up_time_secs),
+            corpus_limit: self.corpus_limit.or(fallback.corpus_limit),
+        }
+    }
+
+    fn validate(&self, context: &str) -> Result<()> {
+        if let Some(size) = self.sample_size.filter(|size| *size < MIN_SAMPLE_SIZE) {
+            anyhow::bail!("{}: sample_size {} is below criterion's minimum of {}", context, size, MIN_SAMPLE_SIZE);
+        }
+        for (name, secs) in [
+            ("measurement_time_secs", self.measurement_time_secs),
+            ("warm_up_time_secs", self.warm_up_time_secs),
+        ] {
+            if secs.is_some_and(|secs| !(secs > 0.0 && secs.is_finite())) {
+                anyhow::bail!("{}: {} must be a positive number of seconds", context, name);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Which groups run, and with what settings
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct BenchConfig {
+    /// Groups to run (None runs all)
+    #[serde(default)]
+    pub groups: Option<Vec<String>>,
+    /// Groups never to run, even if selected
+    #[serde(default)]
+    pub skip: Vec<String>,
+    #[serde(flatten)]
+    pub defaults: GroupSettings,
+    #[serde(default, rename = "group")]
+    pub group_settings: BTreeMap<String, GroupSettings>,
+}
+
+/// `pattern` is a group name, or a prefix ending in `*`
+fn matches(pattern: &str, group: &str) -> bool {
+    match pattern.strip_suffix('*') {
+        Some(prefix) => group.starts_with(prefix),
+        None => pattern == group,
+    }
+}
+
+fn env_list(key: &str) -> Option<Vec<String>> {
+    let value = std::env::var(key).ok()?;
+    Some(value.split(',').map(str::trim).filter(|s| !s.is_empty()).map(String::from).collect())
+}
+
+fn env_parse<T: std::str::FromStr>(key: &str) -> Result<Option<T>> {
+    match std::env::var(key) {
+        Ok(value) => value
+            .trim()
+            .parse()
+            .map(Some)
+            .map_err(|_| anyhow::anyhow!("{}={} is not a valid value", key, value)),
+        Err(_) => Ok(None),
+    }
+}
+
+/// Config file used when `BLVM_BENCH_CONFIG` is unset (only if it exists)
+fn default_config_path() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("bench-config.toml")
+}
+
+impl BenchConfig {
+    /// Parse a config file's contents
+    pub fn from_toml(content: &str) -> Result<Self> {
+        let config: BenchConfig = toml::from_str(content)?;
+        config.defaults.validate("defaults")?;
+        for (name, settings) in &config.group_settings {
+            settings.validate(&format!("[group.{}]", name))?;
+        }
+        Ok(config)
+    }
+
+    /// Load a config file
+    pub fn load(path: &Path) -> Result<Self> {
+        let content = std::fs::read_to_string(path).with_context(|| format!("Failed to read {}", path.display()))?;
+        Self::from_toml(&content).with_context(|| format!("Failed to parse {}", path.display()))
+    }
+
+    /// The config file (if any) with the environment overrides applied
+    pub fn from_env() -> Result<Self> {
+        let mut config = match std::env::var("BLVM_BENCH_CONFIG") {
+            Ok(path) => Self::load(Path::new(&path))?,
+            Err(_) if default_config_path().exists() => Self::load(&default_config_path())?,
+            Err(_) => Self::default(),
+        };
+        if let Some(groups) = env_list("BLVM_BENCH_GROUPS") {
+            config.groups = Some(groups);
+        }
+        if let Some(skip) = env_list("BLVM_BENCH_SKIP") {
+            config.skip.extend(skip);
+        }
+        let overrides = GroupSettings {
+            sample_size: env_parse("BLVM_BENCH_SAMPLE_SIZE")?,
+            measurement_time_secs: env_parse("BLVM_BENCH_MEASUREMENT_SECS")?,
+            warm_up_time_secs: None,
+            corpus_limit: env_parse("BLVM_BENCH_CORPUS_LIMIT")?,
+        };
+        overrides.validate("environment")?;
+        // The environment wins over the file, per-group sections included
+        config.defaults = overrides.or(config.defaults);
+        for settings in config.group_settings.values_mut() {
+            *settings = overrides.or(*settings);
+        }
+        Ok(config)
+    }
+
+    /// The process-wide config, loaded once (panics on an invalid config, failing the bench run)
+    pub fn get() -> &'static BenchConfig {
+        static CONFIG: OnceLock<BenchConfig> = OnceLock::new();
+        CONFIG.get_or_init(|| match Self::from_env() {
+            Ok(config) => config,
+            Err(e) => panic!("Invalid bench configuration: {:#}", e),
+        })
+    }
+
+    /// Whether `group` is selected
+    pub fn enabled(&self, group: &str) -> bool {
+        let selected = self.groups.as_ref().is_none_or(|groups| groups.iter().any(|p| matches(p, group)));
+        selected && !self.skip.iter().any(|p| matches(p, group))
+    }
+
+    /// Configured settings of `group` (a `[group.<name>]` section over the defaults)
+    pub fn settings(&self, group: &str) -> GroupSettings {
+        self.group_settings
+            .get(group)
+            .copied()
+            .unwrap_or_default()
+            .or(self.defaults)
+    }
+
+    /// Most corpus entries `group` should load, or `default` if unconfigured
+    pub fn corpus_limit(&self, group: &str, default: usize) -> usize {
+        self.settings(group).corpus_limit.unwrap_or(default)
+    }
+
+    /// Start `group` with the configured settings over the bench's own `defaults`;
+    /// None if the group is deselected
+    pub fn group<'a, M: Measurement>(
+        &self,
+        c: &'a mut Criterion<M>,
+        group: &str,
+        defaults: GroupSettings,
+    ) -> Option<BenchmarkGroup<'a, M>> {
+        if !self.enabled(group) {
+            return None;
+        }
+        let settings = self.settings(group).or(defaults);
+        let mut benchmark_group = c.benchmark_group(group);
+        if let Some(size) = settings.sample_size {
+            benchmark_group.sample_size(size);
+        }
+        if let Some(secs) = settings.measurement_time_secs {
+            benchmark_group.measurement_time(Duration::from_secs_f64(secs));
+        }
+        if let Some(secs) = settings.warm_up_time_secs {
+            benchmark_group.warm_up_time(Duration::from_secs_f64(secs));
+        }
+        Some(benchmark_group)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_group_selection_and_settings() {
+        let config = BenchConfig::from_toml(
+            r#"
+            groups = ["block_accounting", "opcode_costs_*"]
+            skip = ["opcode_costs_signature"]
+            sample_size = 20
+
+            [group.block_accounting]
+            sample_size = 10
+            corpus_limit = 2
+            "#,
+        )
+        .unwrap();
+        assert!(config.enabled("block_accounting"));
+        assert!(config.enabled("opcode_costs_stack"));
+        assert!(!config.enabled("opcode_costs_signature"));
+        assert!(!config.enabled("p2sh_verification"));
+
+        assert_eq!(config.settings("block_accounting").sample_size, Some(10));
+        assert_eq!(config.corpus_limit("block_accounting", 100), 2);
+        assert_eq!(config.settings("opcode_costs_stack").sample_size, Some(20));
+        assert_eq!(config.corpus_limit("opcode_costs_stack", 100), 100);
+        assert!(BenchConfig::default().enabled("anything"));
+    }
+
+    #[test]
+    fn test_rejects_invalid_settings() {
+        assert!(BenchConfig::from_toml("sample_size = 5").is_err());
+        assert!(BenchConfig::from_toml("[group.x]\nmeasurement_time_secs = 0.0").is_err());
+        assert!(BenchConfig::from_toml("sample_sizes = 20").is_err());
+    }
+}
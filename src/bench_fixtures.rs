@@ -0,0 +1,784 @@
+//! Signed Transaction Fixtures
+//!
+//! Builders for benchmark inputs that must pass full validation: correctly
+//! signed P2PKH / P2WPKH / P2WSH multisig / Taproot spends, P2SH multisig and
+//! P2SH-wrapped P2WPKH / P2WSH spends, with reference legacy, BIP143 and
+//! BIP341 sighash implementations, and blocks at a post-Taproot height (BIP34
+//! coinbase, witness commitment, valid merkle root).
+//!
+//! The sighash code here is a straightforward spec transcription used to sign
+//! fixtures - it is deliberately independent of blvm_consensus.
+
+use blvm_consensus::block::calculate_tx_id;
+use blvm_consensus::mining::calculate_merkle_root;
+use blvm_consensus::segwit::Witness;
+use blvm_consensus::{
+    tx_inputs, Block, BlockHeader, OutPoint, Transaction, TransactionInput, TransactionOutput,
+    UtxoSet, UTXO,
+};
+use secp256k1::{schnorr, All, Keypair, Message, PublicKey, Scalar, Secp256k1, SecretKey, XOnlyPublicKey};
+use sha2::{Digest, Sha256};
+
+/// Mainnet height where all soft forks through Taproot are active
+pub const POST_TAPROOT_HEIGHT: u64 = 800_000;
+
+/// Value of each funding UTXO (sats)
+const FUNDING_VALUE: i64 = 100_000;
+/// Fee paid by each spend (sats)
+const SPEND_FEE: i64 = 1_000;
+
+/// Output type being spent
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SpendKind {
+    /// Legacy pay-to-pubkey-hash (ECDSA, legacy sighash)
+    P2pkh,
+    /// Native segwit v0 pay-to-witness-pubkey-hash (ECDSA, BIP143)
+    P2wpkh,
+    /// Native segwit v0 2-of-3 multisig (ECDSA, BIP143)
+    P2wshMultisig,
+    /// Taproot key-path spend (Schnorr, BIP341)
+    TaprootKeyPath,
+    /// Taproot script-path spend of `<key> OP_CHECKSIG` (Schnorr, BIP341/342)
+    TaprootScriptPath,
+    /// P2SH 2-of-3 multisig redeem script (ECDSA, legacy sighash)
+    P2shMultisig,
+    /// P2SH 15-of-15 multisig, the largest redeem script that fits in a push (ECDSA, legacy sighash)
+    P2shMultisigMax,
+    /// P2WPKH wrapped in P2SH (ECDSA, BIP143)
+    P2shP2wpkh,
+    /// 2-of-3 P2WSH wrapped in P2SH (ECDSA, BIP143)
+    P2shP2wshMultisig,
+}
+
+impl SpendKind {
+    pub const ALL: [SpendKind; 5] = [
+        SpendKind::P2pkh,
+        SpendKind::P2wpkh,
+        SpendKind::P2wshMultisig,
+        SpendKind::TaprootKeyPath,
+        SpendKind::TaprootScriptPath,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            SpendKind::P2pkh => "p2pkh",
+            SpendKind::P2wpkh => "p2wpkh",
+            SpendKind::P2wshMultisig => "p2wsh_multisig_2of3",
+            SpendKind::TaprootKeyPath => "taproot_keypath",
+            SpendKind::TaprootScriptPath => "taproot_scriptpath",
+            SpendKind::P2shMultisig => "p2sh_multisig_2of3",
+            SpendKind::P2shMultisigMax => "p2sh_multisig_15of15",
+            SpendKind::P2shP2wpkh => "p2sh_p2wpkh",
+            SpendKind::P2shP2wshMultisig => "p2sh_p2wsh_multisig_2of3",
+        }
+    }
+
+    /// Kinds whose output is P2SH
+    pub const P2SH: [SpendKind; 4] = [
+        SpendKind::P2shMultisig,
+        SpendKind::P2shMultisigMax,
+        SpendKind::P2shP2wpkh,
+        SpendKind::P2shP2wshMultisig,
+    ];
+
+    /// Multisig threshold and key count (1-of-1 for single-key kinds)
+    fn multisig(&self) -> (usize, usize) {
+        match self {
+            SpendKind::P2wshMultisig | SpendKind::P2shMultisig | SpendKind::P2shP2wshMultisig => (2, 3),
+            SpendKind::P2shMultisigMax => (MAX_PUBKEYS_PER_MULTISIG, MAX_PUBKEYS_PER_MULTISIG),
+            _ => (1, 1),
+        }
+    }
+
+    /// Signatures checked to spend one output
+    pub fn signatures(&self) -> usize {
+        self.multisig().0
+    }
+}
+
+/// CHECKMULTISIG key limit that still fits a P2SH redeem script in 520 bytes
+const MAX_PUBKEYS_PER_MULTISIG: usize = 15;
+
+/// `m`-of-`n` bare multisig script over the first `n` keys
+fn multisig_script(m: usize, keys: &[PublicKey]) -> Vec<u8> {
+    let mut script = vec![0x50 + m as u8]; // OP_m
+    for key in keys {
+        push_data(&mut script, &key.serialize());
+    }
+    script.extend_from_slice(&[0x50 + keys.len() as u8, 0xae]); // OP_n OP_CHECKMULTISIG
+    script
+}
+
+/// `OP_HASH160 <hash160(redeem_script)> OP_EQUAL`
+pub fn p2sh_script_pubkey(redeem_script: &[u8]) -> Vec<u8> {
+    let mut spk = vec![0xa9];
+    push_data(&mut spk, &hash160(redeem_script));
+    spk.push(0x87);
+    spk
+}
+
+// ---------------------------------------------------------------------------
+// Hash and script helpers
+// ---------------------------------------------------------------------------
+
+pub fn sha256(data: &[u8]) -> [u8; 32] {
+    Sha256::digest(data).into()
+}
+
+pub fn sha256d(data: &[u8]) -> [u8; 32] {
+    Sha256::digest(Sha256::digest(data)).into()
+}
+
+pub fn hash160(data: &[u8]) -> [u8; 20] {
+    use ripemd::Ripemd160;
+    Ripemd160::digest(Sha256::digest(data)).into()
+}
+
+/// BIP340 tagged hash
+pub fn tagged_hash(tag: &str, data: &[u8]) -> [u8; 32] {
+    let tag_hash = Sha256::digest(tag.as_bytes());
+    let mut hasher = Sha256::new();
+    hasher.update(tag_hash);
+    hasher.update(tag_hash);
+    hasher.update(data);
+    hasher.finalize().into()
+}
+
+pub fn write_compact_size(buf: &mut Vec<u8>, n: u64) {
+    match n {
+        0..=0xfc => buf.push(n as u8),
+        0xfd..=0xffff => {
+            buf.push(0xfd);
+            buf.extend_from_slice(&(n as u16).to_le_bytes());
+        }
+        0x10000..=0xffff_ffff => {
+            buf.push(0xfe);
+            buf.extend_from_slice(&(n as u32).to_le_bytes());
+        }
+        _ => {
+            buf.push(0xff);
+            buf.extend_from_slice(&n.to_le_bytes());
+        }
+    }
+}
+
+/// Append a minimal data push
+pub fn push_data(script: &mut Vec<u8>, data: &[u8]) {
+    match data.len() {
+        0..=75 => script.push(data.len() as u8),
+        76..=255 => {
+            script.push(0x4c);
+            script.push(data.len() as u8);
+        }
+        _ => {
+            script.push(0x4d);
+            script.extend_from_slice(&(data.len() as u16).to_le_bytes());
+        }
+    }
+    script.extend_from_slice(data);
+}
+
+fn write_var_bytes(buf: &mut Vec<u8>, data: &[u8]) {
+    write_compact_size(buf, data.len() as u64);
+    buf.extend_from_slice(data);
+}
+
+fn write_outpoint(buf: &mut Vec<u8>, outpoint: &OutPoint) {
+    buf.extend_from_slice(&outpoint.hash);
+    buf.extend_from_slice(&(outpoint.index as u32).to_le_bytes());
+}
+
+fn write_output(buf: &mut Vec<u8>, output: &TransactionOutput) {
+    buf.extend_from_slice(&(output.value as i64).to_le_bytes());
+    write_var_bytes(buf, &output.script_pubkey);
+}
+
+/// Serialize a transaction, optionally with witness data (BIP144)
+pub fn serialize_tx(tx: &Transaction, witness: Option<&[Vec<u8>]>) -> Vec<u8> {
+    let mut buf = Vec::new();
+    buf.extend_from_slice(&(tx.version as u32).to_le_bytes());
+    if witness.is_some() {
+        buf.extend_from_slice(&[0x00, 0x01]);
+    }
+    write_compact_size(&mut buf, tx.inputs.len() as u64);
+    for input in tx.inputs.iter() {
+        write_outpoint(&mut buf, &input.prevout);
+        write_var_bytes(&mut buf, &input.script_sig);
+        buf.extend_from_slice(&(input.sequence as u32).to_le_bytes());
+    }
+    write_compact_size(&mut buf, tx.outputs.len() as u64);
+    for output in tx.outputs.iter() {
+        write_output(&mut buf, output);
+    }
+    if let Some(stack) = witness {
+        // Single-input fixtures: one witness stack
+        write_compact_size(&mut buf, stack.len() as u64);
+        for item in stack {
+            write_var_bytes(&mut buf, item);
+        }
+    }
+    buf.extend_from_slice(&(tx.lock_time as u32).to_le_bytes());
+    buf
+}
+
+// ---------------------------------------------------------------------------
+// Deterministic fixture data
+// ---------------------------------------------------------------------------
+
+const SPLITMIX64_GAMMA: u64 = 0x9e37_79b9_7f4a_7c15;
+
+/// SplitMix64 output for state `x`: spreads sequential ids across the whole key space like real txids
+pub fn splitmix64(x: u64) -> u64 {
+    let mut z = x.wrapping_add(SPLITMIX64_GAMMA);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+/// SplitMix64 stream: deterministic fixture data without a rand dependency, and
+/// stable across dependency upgrades (unlike `StdRng`), so a seed keeps its values
+pub struct SplitMix64(pub u64);
+
+impl SplitMix64 {
+    pub fn next_u64(&mut self) -> u64 {
+        let out = splitmix64(self.0);
+        self.0 = self.0.wrapping_add(SPLITMIX64_GAMMA);
+        out
+    }
+
+    pub fn bytes<const N: usize>(&mut self) -> [u8; N] {
+        let mut out = [0u8; N];
+        for chunk in out.chunks_mut(8) {
+            chunk.copy_from_slice(&self.next_u64().to_le_bytes()[..chunk.len()]);
+        }
+        out
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Sighash reference implementations
+// ---------------------------------------------------------------------------
+
+/// Legacy SIGHASH_ALL
+pub fn legacy_sighash(tx: &Transaction, input_index: usize, script_code: &[u8]) -> [u8; 32] {
+    let mut tx = tx.clone();
+    for (i, input) in tx.inputs.iter_mut().enumerate() {
+        input.script_sig = if i == input_index {
+            script_code.to_vec()
+        } else {
+            Vec::new()
+        };
+    }
+    let mut buf = serialize_tx(&tx, None);
+    buf.extend_from_slice(&1u32.to_le_bytes());
+    sha256d(&buf)
+}
+
+/// Per-transaction hashes shared by every input's BIP143/BIP341 sighash
+///
+/// Computing these once per transaction is what keeps segwit signature hashing
+/// linear in the number of inputs.
+pub struct SighashMidstate {
+    // BIP143 (double SHA256)
+    hash_prevouts: [u8; 32],
+    hash_sequence: [u8; 32],
+    hash_outputs: [u8; 32],
+    // BIP341 (single SHA256)
+    sha_prevouts: [u8; 32],
+    sha_amounts: [u8; 32],
+    sha_script_pubkeys: [u8; 32],
+    sha_sequences: [u8; 32],
+    sha_outputs: [u8; 32],
+}
+
+impl SighashMidstate {
+    pub fn new(tx: &Transaction, spent_outputs: &[TransactionOutput]) -> Self {
+        let mut prevouts = Vec::new();
+        let mut sequences = Vec::new();
+        for input in tx.inputs.iter() {
+            write_outpoint(&mut prevouts, &input.prevout);
+            sequences.extend_from_slice(&(input.sequence as u32).to_le_bytes());
+        }
+        let mut amounts = Vec::new();
+        let mut script_pubkeys = Vec::new();
+        for spent in spent_outputs {
+            amounts.extend_from_slice(&(spent.value as i64).to_le_bytes());
+            write_var_bytes(&mut script_pubkeys, &spent.script_pubkey);
+        }
+        let mut outputs = Vec::new();
+        for output in tx.outputs.iter() {
+            write_output(&mut outputs, output);
+        }
+
+        Self {
+            hash_prevouts: sha256d(&prevouts),
+            hash_sequence: sha256d(&sequences),
+            hash_outputs: sha256d(&outputs),
+            sha_prevouts: sha256(&prevouts),
+            sha_amounts: sha256(&amounts),
+            sha_script_pubkeys: sha256(&script_pubkeys),
+            sha_sequences: sha256(&sequences),
+            sha_outputs: sha256(&outputs),
+        }
+    }
+
+    /// BIP143 SIGHASH_ALL (segwit v0)
+    pub fn bip143(&self, tx: &Transaction, input_index: usize, script_code: &[u8], amount: i64) -> [u8; 32] {
+        let input = &tx.inputs[input_index];
+        let mut buf = Vec::with_capacity(160 + script_code.len());
+        buf.extend_from_slice(&(tx.version as u32).to_le_bytes());
+        buf.extend_from_slice(&self.hash_prevouts);
+        buf.extend_from_slice(&self.hash_sequence);
+        write_outpoint(&mut buf, &input.prevout);
+        write_var_bytes(&mut buf, script_code);
+        buf.extend_from_slice(&amount.to_le_bytes());
+        buf.extend_from_slice(&(input.sequence as u32).to_le_bytes());
+        buf.extend_from_slice(&self.hash_outputs);
+        buf.extend_from_slice(&(tx.lock_time as u32).to_le_bytes());
+        buf.extend_from_slice(&1u32.to_le_bytes());
+        sha256d(&buf)
+    }
+
+    /// BIP341 SIGHASH_DEFAULT; `leaf_hash` selects a script-path spend
+    pub fn bip341(&self, tx: &Transaction, input_index: usize, leaf_hash: Option<[u8; 32]>) -> [u8; 32] {
+        self.bip341_with_annex(tx, input_index, leaf_hash, None)
+    }
+
+    /// BIP341 SIGHASH_DEFAULT committing to `annex` (including its 0x50 prefix)
+    pub fn bip341_with_annex(
+        &self,
+        tx: &Transaction,
+        input_index: usize,
+        leaf_hash: Option<[u8; 32]>,
+        annex: Option<&[u8]>,
+    ) -> [u8; 32] {
+        let mut msg = Vec::with_capacity(256);
+        msg.extend_from_slice(&[0x00, 0x00]); // epoch, hash_type = SIGHASH_DEFAULT
+        msg.extend_from_slice(&(tx.version as u32).to_le_bytes());
+        msg.extend_from_slice(&(tx.lock_time as u32).to_le_bytes());
+        msg.extend_from_slice(&self.sha_prevouts);
+        msg.extend_from_slice(&self.sha_amounts);
+        msg.extend_from_slice(&self.sha_script_pubkeys);
+        msg.extend_from_slice(&self.sha_sequences);
+        msg.extend_from_slice(&self.sha_outputs);
+        let ext_flag: u8 = if leaf_hash.is_some() { 0x02 } else { 0x00 };
+        msg.push(ext_flag | u8::from(annex.is_some())); // spend_type
+        msg.extend_from_slice(&(input_index as u32).to_le_bytes());
+        if let Some(annex) = annex {
+            let mut data = Vec::with_capacity(annex.len() + 9);
+            write_var_bytes(&mut data, annex);
+            msg.extend_from_slice(&sha256(&data));
+        }
+        if let Some(leaf_hash) = leaf_hash {
+            msg.extend_from_slice(&leaf_hash);
+            msg.push(0x00); // key_version
+            msg.extend_from_slice(&0xffff_ffffu32.to_le_bytes()); // codesep_pos
+        }
+        tagged_hash("TapSighash", &msg)
+    }
+}
+
+/// BIP143 SIGHASH_ALL (segwit v0), uncached
+pub fn bip143_sighash(tx: &Transaction, input_index: usize, script_code: &[u8], amount: i64) -> [u8; 32] {
+    SighashMidstate::new(tx, &[]).bip143(tx, input_index, script_code, amount)
+}
+
+/// BIP341 SIGHASH_DEFAULT, uncached; `leaf_hash` selects a script-path spend
+pub fn bip341_sighash(
+    tx: &Transaction,
+    input_index: usize,
+    spent_outputs: &[TransactionOutput],
+    leaf_hash: Option<[u8; 32]>,
+) -> [u8; 32] {
+    SighashMidstate::new(tx, spent_outputs).bip341(tx, input_index, leaf_hash)
+}
+
+/// BIP341 leaf hash for a tapscript (leaf version 0xc0)
+pub fn tapleaf_hash(script: &[u8]) -> [u8; 32] {
+    let mut data = vec![0xc0];
+    write_var_bytes(&mut data, script);
+    tagged_hash("TapLeaf", &data)
+}
+
+// ---------------------------------------------------------------------------
+// Keys and signatures
+// ---------------------------------------------------------------------------
+
+/// Deterministic secret key #n
+pub fn secret_key(n: u64) -> SecretKey {
+    SecretKey::from_slice(&sha256(&n.to_le_bytes())).expect("sha256 output is a valid key")
+}
+
+pub fn ecdsa_sig(secp: &Secp256k1<All>, sighash: &[u8; 32], key: &SecretKey) -> Vec<u8> {
+    let msg = Message::from_digest_slice(sighash).expect("32-byte sighash");
+    let mut sig = secp.sign_ecdsa(&msg, key).serialize_der().to_vec();
+    sig.push(0x01); // SIGHASH_ALL
+    sig
+}
+
+pub fn schnorr_sig(secp: &Secp256k1<All>, sighash: &[u8; 32], keypair: &Keypair) -> Vec<u8> {
+    let msg = Message::from_digest_slice(sighash).expect("32-byte sighash");
+    let sig: schnorr::Signature = secp.sign_schnorr_no_aux_rand(&msg, keypair);
+    sig.as_ref().to_vec() // 64 bytes = SIGHASH_DEFAULT
+}
+
+pub fn taptweak(internal: &XOnlyPublicKey, merkle_root: Option<[u8; 32]>) -> Scalar {
+    let mut data = internal.serialize().to_vec();
+    if let Some(root) = merkle_root {
+        data.extend_from_slice(&root);
+    }
+    Scalar::from_be_bytes(tagged_hash("TapTweak", &data)).expect("tweak below curve order")
+}
+
+/// Keys and scripts for spending one output of a given kind
+struct SpendTemplate {
+    kind: SpendKind,
+    keys: Vec<SecretKey>,
+    script_pubkey: Vec<u8>,
+    /// P2SH redeem script / P2WSH witness script / tapscript leaf
+    script: Vec<u8>,
+    /// Taproot output tweak parity (script path control block)
+    parity: u8,
+    internal_key: Option<XOnlyPublicKey>,
+}
+
+impl SpendTemplate {
+    fn new(secp: &Secp256k1<All>, kind: SpendKind, seed: u64) -> Self {
+        let key_count = kind.multisig().1.max(3) as u64;
+        let keys: Vec<SecretKey> = (0..key_count)
+            .map(|i| secret_key(seed * MAX_PUBKEYS_PER_MULTISIG as u64 + i))
+            .collect();
+        let pubkey = |i: usize| PublicKey::from_secret_key(secp, &keys[i]);
+        let mut template = SpendTemplate {
+            kind,
+            keys: keys.clone(),
+            script_pubkey: Vec::new(),
+            script: Vec::new(),
+            parity: 0,
+            internal_key: None,
+        };
+
+        match kind {
+            SpendKind::P2pkh => {
+                let mut spk = vec![0x76, 0xa9];
+                push_data(&mut spk, &hash160(&pubkey(0).serialize()));
+                spk.extend_from_slice(&[0x88, 0xac]);
+                template.script_pubkey = spk;
+            }
+            SpendKind::P2wpkh => {
+                let mut spk = vec![0x00];
+                push_data(&mut spk, &hash160(&pubkey(0).serialize()));
+                template.script_pubkey = spk;
+            }
+            SpendKind::P2wshMultisig => {
+                let ws = multisig_script(2, &(0..3).map(pubkey).collect::<Vec<_>>());
+                let mut spk = vec![0x00];
+                push_data(&mut spk, &sha256(&ws));
+                template.script_pubkey = spk;
+                template.script = ws;
+            }
+            SpendKind::TaprootKeyPath | SpendKind::TaprootScriptPath => {
+                let (internal, _) = Keypair::from_secret_key(secp, &keys[0]).x_only_public_key();
+                let merkle_root = if kind == SpendKind::TaprootScriptPath {
+                    let (leaf_key, _) = Keypair::from_secret_key(secp, &keys[1]).x_only_public_key();
+                    let mut leaf = Vec::new();
+                    push_data(&mut leaf, &leaf_key.serialize());
+                    leaf.push(0xac); // OP_CHECKSIG
+                    let root = tapleaf_hash(&leaf);
+                    template.script = leaf;
+                    Some(root)
+                } else {
+                    None
+                };
+                let (output_key, parity) = internal
+                    .add_tweak(secp, &taptweak(&internal, merkle_root))
+                    .expect("valid taproot tweak");
+                let mut spk = vec![0x51];
+                push_data(&mut spk, &output_key.serialize());
+                template.script_pubkey = spk;
+                template.parity = parity.to_u8();
+                template.internal_key = Some(internal);
+            }
+            SpendKind::P2shMultisig | SpendKind::P2shMultisigMax => {
+                let (m, n) = kind.multisig();
+                let redeem = multisig_script(m, &(0..n).map(pubkey).collect::<Vec<_>>());
+                template.script_pubkey = p2sh_script_pubkey(&redeem);
+                template.script = redeem;
+            }
+            SpendKind::P2shP2wpkh => {
+                let mut redeem = vec![0x00];
+                push_data(&mut redeem, &hash160(&pubkey(0).serialize()));
+                template.script_pubkey = p2sh_script_pubkey(&redeem);
+            }
+            SpendKind::P2shP2wshMultisig => {
+                let ws = multisig_script(2, &(0..3).map(pubkey).collect::<Vec<_>>());
+                let mut redeem = vec![0x00];
+                push_data(&mut redeem, &sha256(&ws));
+                template.script_pubkey = p2sh_script_pubkey(&redeem);
+                template.script = ws;
+            }
+        }
+        template
+    }
+
+    /// Fill in script_sig / witness for input 0 of `tx`
+    fn sign(&self, secp: &Secp256k1<All>, tx: &mut Transaction, spent: &TransactionOutput) -> Vec<Vec<u8>> {
+        let pubkey = |i: usize| PublicKey::from_secret_key(secp, &self.keys[i]).serialize().to_vec();
+        match self.kind {
+            SpendKind::P2pkh => {
+                let sighash = legacy_sighash(tx, 0, &self.script_pubkey);
+                let mut script_sig = Vec::new();
+                push_data(&mut script_sig, &ecdsa_sig(secp, &sighash, &self.keys[0]));
+                push_data(&mut script_sig, &pubkey(0));
+                tx.inputs[0].script_sig = script_sig;
+                Vec::new()
+            }
+            SpendKind::P2wpkh | SpendKind::P2shP2wpkh => {
+                if self.kind == SpendKind::P2shP2wpkh {
+                    let mut redeem = vec![0x00];
+                    push_data(&mut redeem, &hash160(&pubkey(0)));
+                    let mut script_sig = Vec::new();
+                    push_data(&mut script_sig, &redeem);
+                    tx.inputs[0].script_sig = script_sig;
+                }
+                let mut script_code = vec![0x76, 0xa9];
+                push_data(&mut script_code, &hash160(&pubkey(0)));
+                script_code.extend_from_slice(&[0x88, 0xac]);
+                let sighash = bip143_sighash(tx, 0, &script_code, spent.value as i64);
+                vec![ecdsa_sig(secp, &sighash, &self.keys[0]), pubkey(0)]
+            }
+            SpendKind::P2wshMultisig | SpendKind::P2shP2wshMultisig => {
+                if self.kind == SpendKind::P2shP2wshMultisig {
+                    let mut redeem = vec![0x00];
+                    push_data(&mut redeem, &sha256(&self.script));
+                    let mut script_sig = Vec::new();
+                    push_data(&mut script_sig, &redeem);
+                    tx.inputs[0].script_sig = script_sig;
+                }
+                let sighash = bip143_sighash(tx, 0, &self.script, spent.value as i64);
+                vec![
+                    Vec::new(), // CHECKMULTISIG dummy
+                    ecdsa_sig(secp, &sighash, &self.keys[0]),
+                    ecdsa_sig(secp, &sighash, &self.keys[1]),
+                    self.script.clone(),
+                ]
+            }
+            SpendKind::TaprootKeyPath => {
+                let internal = self.internal_key.expect("taproot template");
+                let keypair = Keypair::from_secret_key(secp, &self.keys[0])
+                    .add_xonly_tweak(secp, &taptweak(&internal, None))
+                    .expect("valid taproot tweak");
+                let sighash = bip341_sighash(tx, 0, std::slice::from_ref(spent), None);
+                vec![schnorr_sig(secp, &sighash, &keypair)]
+            }
+            SpendKind::TaprootScriptPath => {
+                let internal = self.internal_key.expect("taproot template");
+                let keypair = Keypair::from_secret_key(secp, &self.keys[1]);
+                let sighash = bip341_sighash(tx, 0, std::slice::from_ref(spent), Some(tapleaf_hash(&self.script)));
+                let mut control_block = vec![0xc0 | self.parity];
+                control_block.extend_from_slice(&internal.serialize());
+                vec![schnorr_sig(secp, &sighash, &keypair), self.script.clone(), control_block]
+            }
+            SpendKind::P2shMultisig | SpendKind::P2shMultisigMax => {
+                let sighash = legacy_sighash(tx, 0, &self.script);
+                let mut script_sig = vec![0x00]; // CHECKMULTISIG dummy
+                for key in &self.keys[..self.kind.signatures()] {
+                    push_data(&mut script_sig, &ecdsa_sig(secp, &sighash, key));
+                }
+                push_data(&mut script_sig, &self.script);
+                tx.inputs[0].script_sig = script_sig;
+                Vec::new()
+            }
+        }
+    }
+}
+
+/// A single signed spend: (transaction, witness stack, spent outpoint, spent coin)
+pub struct SignedSpend {
+    pub tx: Transaction,
+    pub witness: Vec<Vec<u8>>,
+    pub prevout: OutPoint,
+    pub spent: TransactionOutput,
+}
+
+/// Build `count` independent single-input spends of `kind`
+pub fn signed_spends(kind: SpendKind, count: usize) -> Vec<SignedSpend> {
+    let secp = Secp256k1::new();
+    (0..count as u64)
+        .map(|i| {
+            let template = SpendTemplate::new(&secp, kind, i);
+            let mut hash = sha256(&[kind as u8]);
+            hash[..8].copy_from_slice(&i.to_le_bytes());
+            let prevout = OutPoint { hash, index: 0 };
+            let spent = TransactionOutput {
+                value: FUNDING_VALUE as _,
+                script_pubkey: template.script_pubkey.clone(),
+            };
+
+            // Pay to a fresh P2WPKH
+            let mut dest = vec![0x00];
+            push_data(&mut dest, &hash160(&i.to_le_bytes()));
+            let mut tx = Transaction {
+                version: 2,
+                inputs: tx_inputs![TransactionInput {
+                    prevout: prevout.clone(),
+                    script_sig: Vec::new(),
+                    sequence: 0xffff_fffd,
+                }],
+                outputs: vec![TransactionOutput {
+                    value: (FUNDING_VALUE - SPEND_FEE) as _,
+                    script_pubkey: dest,
+                }]
+                .into(),
+                lock_time: 0,
+            };
+            let witness = template.sign(&secp, &mut tx, &spent);
+            SignedSpend { tx, witness, prevout, spent }
+        })
+        .collect()
+}
+
+/// BIP34 height push for the coinbase script_sig
+fn coinbase_height_push(height: u64) -> Vec<u8> {
+    let mut n = height;
+    let mut bytes = Vec::new();
+    while n > 0 {
+        bytes.push((n & 0xff) as u8);
+        n >>= 8;
+    }
+    if bytes.last().map_or(false, |b| b & 0x80 != 0) {
+        bytes.push(0);
+    }
+    let mut script = Vec::new();
+    push_data(&mut script, &bytes);
+    script
+}
+
+/// Merkle root of txids or wtxids (internal byte order), odd levels padded
+/// by repeating the last hash; all zeros for an empty list
+pub fn merkle_root(mut hashes: Vec<[u8; 32]>) -> [u8; 32] {
+    while hashes.len() > 1 {
+        if hashes.len() % 2 == 1 {
+            hashes.push(*hashes.last().expect("non-empty"));
+        }
+        hashes = hashes
+            .chunks(2)
+            .map(|pair| sha256d(&[pair[0], pair[1]].concat()))
+            .collect();
+    }
+    hashes.first().copied().unwrap_or([0; 32])
+}
+
+/// Block subsidy at `height` (mainnet schedule)
+pub fn block_subsidy(height: u64) -> i64 {
+    let halvings = height / 210_000;
+    if halvings >= 64 {
+        0
+    } else {
+        (50 * 100_000_000i64) >> halvings
+    }
+}
+
+/// Assemble a valid block at `height` from signed spends
+///
+/// Returns the block, per-transaction witnesses and the UTXO set the spends need.
+pub fn build_block(spends: Vec<SignedSpend>, height: u64) -> (Block, Vec<Witness>, UtxoSet) {
+    let mut utxo_set = UtxoSet::new();
+    let mut transactions = Vec::with_capacity(spends.len() + 1);
+    let mut witnesses: Vec<Witness> = Vec::with_capacity(spends.len() + 1);
+    let mut wtxids = vec![[0u8; 32]]; // coinbase wtxid is zero
+    let fees = SPEND_FEE * spends.len() as i64;
+
+    let mut spend_txs = Vec::with_capacity(spends.len());
+    for spend in spends {
+        utxo_set.insert(
+            spend.prevout.clone(),
+            UTXO {
+                value: spend.spent.value,
+                script_pubkey: spend.spent.script_pubkey.clone(),
+                height: height.saturating_sub(200) as _,
+                is_coinbase: false,
+            },
+        );
+        let has_witness = !spend.witness.is_empty();
+        wtxids.push(if has_witness {
+            sha256d(&serialize_tx(&spend.tx, Some(&spend.witness)))
+        } else {
+            calculate_tx_id(&spend.tx)
+        });
+        spend_txs.push((spend.tx, spend.witness));
+    }
+
+    // Coinbase: BIP34 height, subsidy + fees, BIP141 witness commitment
+    let commitment = {
+        let mut data = merkle_root(wtxids).to_vec();
+        data.extend_from_slice(&[0u8; 32]); // witness reserved value
+        sha256d(&data)
+    };
+    let mut commitment_script = vec![0x6a, 0x24, 0xaa, 0x21, 0xa9, 0xed];
+    commitment_script.extend_from_slice(&commitment);
+    let mut coinbase_script = coinbase_height_push(height);
+    coinbase_script.extend_from_slice(b"blvm-bench");
+    let coinbase = Transaction {
+        version: 2,
+        inputs: tx_inputs![TransactionInput {
+            prevout: OutPoint {
+                hash: [0; 32],
+                index: 0xffffffff,
+            },
+            script_sig: coinbase_script,
+            sequence: 0xffffffff,
+        }],
+        outputs: vec![
+            TransactionOutput {
+                value: (block_subsidy(height) + fees) as _,
+                script_pubkey: vec![0x51],
+            },
+            TransactionOutput {
+                value: 0,
+                script_pubkey: commitment_script,
+            },
+        ]
+        .into(),
+        lock_time: 0,
+    };
+    transactions.push(coinbase);
+    witnesses.push(vec![vec![0u8; 32]]);
+    for (tx, witness) in spend_txs {
+        transactions.push(tx);
+        witnesses.push(witness);
+    }
+
+    let merkle_root = calculate_merkle_root(&transactions).unwrap_or([0; 32]);
+    let block = Block {
+        header: BlockHeader {
+            version: 0x2000_0000,
+            prev_block_hash: [0; 32],
+            merkle_root,
+            timestamp: 1_690_000_000,
+            bits: 0x1703_6e3a,
+            nonce: 0,
+        },
+        transactions: transactions.into_boxed_slice(),
+    };
+
+    (block, witnesses, utxo_set)
+}
+
+/// Serialize a block built by `build_block` (single-input fixtures: one witness stack per transaction)
+pub fn serialize_block(block: &Block, witnesses: &[Witness]) -> Vec<u8> {
+    let header = &block.header;
+    let mut buf = Vec::new();
+    buf.extend_from_slice(&(header.version as u32).to_le_bytes());
+    buf.extend_from_slice(&header.prev_block_hash);
+    buf.extend_from_slice(&header.merkle_root);
+    buf.extend_from_slice(&(header.timestamp as u32).to_le_bytes());
+    buf.extend_from_slice(&(header.bits as u32).to_le_bytes());
+    buf.extend_from_slice(&(header.nonce as u32).to_le_bytes());
+    write_compact_size(&mut buf, block.transactions.len() as u64);
+    for (index, tx) in block.transactions.iter().enumerate() {
+        let witness = witnesses.get(index).map(|w| &w[..]).filter(|w| !w.is_empty());
+        buf.extend_from_slice(&serialize_tx(tx, witness));
+    }
+    buf
+}
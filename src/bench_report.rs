@@ -0,0 +1,260 @@
+//! Criterion Result Export and Regression Gate
+//!
+//! Collects every `new/estimates.json` criterion wrote under its output
+//! directory into one `bench_results.json`, and compares that against a stored
+//! baseline so unattended perf runs can fail on a slowdown.
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+/// Default regression tolerance (fraction of the baseline time)
+pub const DEFAULT_TOLERANCE: f64 = 0.05;
+
+/// Default criterion output directory: `CRITERION_HOME` or `target/criterion`
+pub fn default_criterion_dir() -> PathBuf {
+    std::env::var("CRITERION_HOME")
+        .map(PathBuf::from)
+        .unwrap_or_else(|_| PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("target/criterion"))
+}
+
+/// Summary of one criterion benchmark (times in nanoseconds)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BenchEstimate {
+    pub mean_ns: f64,
+    pub mean_lower_ns: f64,
+    pub mean_upper_ns: f64,
+    pub median_ns: f64,
+    pub std_dev_ns: f64,
+}
+
+/// Consolidated results of one run (`bench_results.json`)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BenchReport {
+    pub timestamp: u64,
+    pub production: bool,
+    /// Keyed by criterion's full id, e.g. `sighash_bip143/cached/500`
+    pub benchmarks: BTreeMap<String, BenchEstimate>,
+}
+
+impl BenchReport {
+    /// Collect all benchmarks under a criterion output directory
+    pub fn from_criterion_dir(dir: &Path) -> Result<Self> {
+        let mut benchmarks = BTreeMap::new();
+        collect_estimates(dir, dir, &mut benchmarks)?;
+        let timestamp = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+        Ok(Self {
+            timestamp,
+            production: crate::utils::is_production_mode(),
+            benchmarks,
+        })
+    }
+
+    pub fn load(path: &Path) -> Result<Self> {
+        let contents =
+            std::fs::read_to_string(path).with_context(|| format!("Failed to read {}", path.display()))?;
+        serde_json::from_str(&contents).with_context(|| format!("Failed to parse {}", path.display()))
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        std::fs::write(path, serde_json::to_string_pretty(self)?)
+            .with_context(|| format!("Failed to write {}", path.display()))
+    }
+}
+
+fn collect_estimates(root: &Path, dir: &Path, out: &mut BTreeMap<String, BenchEstimate>) -> Result<()> {
+    let estimates_path = dir.join("new").join("estimates.json");
+    if estimates_path.exists() {
+        let id = benchmark_id(root, dir);
+        out.insert(id, parse_estimates(&estimates_path)?);
+        return Ok(());
+    }
+
+    for entry in std::fs::read_dir(dir).with_context(|| format!("Failed to read {}", dir.display()))? {
+        let path = entry?.path();
+        // `report` holds criterion's HTML output only
+        if path.is_dir() && path.file_name().is_some_and(|n| n != "report") {
+            collect_estimates(root, &path, out)?;
+        }
+    }
+    Ok(())
+}
+
+/// Prefer criterion's own `full_id`, fall back to the directory path
+fn benchmark_id(root: &Path, dir: &Path) -> String {
+    let from_meta = std::fs::read_to_string(dir.join("new").join("benchmark.json"))
+        .ok()
+        .and_then(|s| serde_json::from_str::<serde_json::Value>(&s).ok())
+        .and_then(|v| v.get("full_id").and_then(|id| id.as_str()).map(String::from));
+
+    from_meta.unwrap_or_else(|| {
+        dir.strip_prefix(root)
+            .unwrap_or(dir)
+            .components()
+            .map(|c| c.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/")
+    })
+}
+
+fn parse_estimates(path: &Path) -> Result<BenchEstimate> {
+    let contents = std::fs::read_to_string(path)?;
+    let v: serde_json::Value =
+        serde_json::from_str(&contents).with_context(|| format!("Failed to parse {}", path.display()))?;
+    let point = |key: &str| v.get(key).and_then(|e| e.get("point_estimate")).and_then(|p| p.as_f64());
+    let bound = |which: &str| {
+        v.get("mean")
+            .and_then(|m| m.get("confidence_interval"))
+            .and_then(|ci| ci.get(which))
+            .and_then(|b| b.as_f64())
+    };
+
+    let mean_ns = point("mean").with_context(|| format!("{} missing mean estimate", path.display()))?;
+    Ok(BenchEstimate {
+        mean_ns,
+        mean_lower_ns: bound("lower_bound").unwrap_or(mean_ns),
+        mean_upper_ns: bound("upper_bound").unwrap_or(mean_ns),
+        median_ns: point("median").unwrap_or(mean_ns),
+        std_dev_ns: point("std_dev").unwrap_or(0.0),
+    })
+}
+
+/// Result of comparing one benchmark against the baseline
+#[derive(Debug, Clone, PartialEq)]
+pub struct Comparison {
+    pub id: String,
+    pub baseline_ns: f64,
+    pub current_ns: f64,
+    /// (current - baseline) / baseline; positive is slower
+    pub change: f64,
+}
+
+/// Comparison outcome across all benchmarks present in both reports
+#[derive(Debug, Default)]
+pub struct RegressionSummary {
+    pub regressions: Vec<Comparison>,
+    pub improvements: Vec<Comparison>,
+    pub unchanged: Vec<Comparison>,
+    /// In the current run but not the baseline
+    pub new: Vec<String>,
+    /// In the baseline but not the current run
+    pub missing: Vec<String>,
+}
+
+impl RegressionSummary {
+    pub fn has_regressions(&self) -> bool {
+        !self.regressions.is_empty()
+    }
+}
+
+/// Compare mean times; a change beyond `tolerance` either way is flagged
+pub fn compare(baseline: &BenchReport, current: &BenchReport, tolerance: f64) -> RegressionSummary {
+    let mut summary = RegressionSummary::default();
+
+    for (id, cur) in &current.benchmarks {
+        let Some(base) = baseline.benchmarks.get(id) else {
+            summary.new.push(id.clone());
+            continue;
+        };
+        if base.mean_ns <= 0.0 {
+            continue;
+        }
+        let comparison = Comparison {
+            id: id.clone(),
+            baseline_ns: base.mean_ns,
+            current_ns: cur.mean_ns,
+            change: (cur.mean_ns - base.mean_ns) / base.mean_ns,
+        };
+        if comparison.change > tolerance {
+            summary.regressions.push(comparison);
+        } else if comparison.change < -tolerance {
+            summary.improvements.push(comparison);
+        } else {
+            summary.unchanged.push(comparison);
+        }
+    }
+    summary.missing = baseline
+        .benchmarks
+        .keys()
+        .filter(|id| !current.benchmarks.contains_key(*id))
+        .cloned()
+        .collect();
+
+    summary
+}
+
+/// Print a human-readable comparison
+pub fn print_summary(summary: &RegressionSummary, tolerance: f64) {
+    println!("📊 Benchmark comparison (tolerance {:.1}%)", tolerance * 100.0);
+    for c in &summary.regressions {
+        println!(
+            "   ❌ {}: {:.0}ns -> {:.0}ns ({:+.1}%)",
+            c.id,
+            c.baseline_ns,
+            c.current_ns,
+            c.change * 100.0
+        );
+    }
+    for c in &summary.improvements {
+        println!(
+            "   ✅ {}: {:.0}ns -> {:.0}ns ({:+.1}%)",
+            c.id,
+            c.baseline_ns,
+            c.current_ns,
+            c.change * 100.0
+        );
+    }
+    println!(
+        "   {} regressed, {} improved, {} unchanged, {} new, {} missing",
+        summary.regressions.len(),
+        summary.improvements.len(),
+        summary.unchanged.len(),
+        summary.new.len(),
+        summary.missing.len()
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn report(entries: &[(&str, f64)]) -> BenchReport {
+        BenchReport {
+            timestamp: 0,
+            production: false,
+            benchmarks: entries
+                .iter()
+                .map(|(id, mean)| {
+                    (
+                        id.to_string(),
+                        BenchEstimate {
+                            mean_ns: *mean,
+                            mean_lower_ns: *mean,
+                            mean_upper_ns: *mean,
+                            median_ns: *mean,
+                            std_dev_ns: 0.0,
+                        },
+                    )
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn test_compare_with_tolerance() {
+        let baseline = report(&[("a", 100.0), ("b", 100.0), ("c", 100.0), ("gone", 1.0)]);
+        let current = report(&[("a", 104.0), ("b", 110.0), ("c", 80.0), ("fresh", 1.0)]);
+
+        let summary = compare(&baseline, &current, 0.05);
+        assert_eq!(summary.unchanged.len(), 1);
+        assert_eq!(summary.regressions[0].id, "b");
+        assert_eq!(summary.improvements[0].id, "c");
+        assert_eq!(summary.new, vec!["fresh".to_string()]);
+        assert_eq!(summary.missing, vec!["gone".to_string()]);
+        assert!(summary.has_regressions());
+    }
+}
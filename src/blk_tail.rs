@@ -0,0 +1,511 @@
+//! blk*.dat Tail-Follow Mode
+//!
+//! Core appends every block it accepts to the newest blk file (moving on to the
+//! next file when one fills up), and closes the file after each write, so new
+//! blocks become visible to other readers straight away. `BlkTail` remembers
+//! where the data ended and polls for records appended after that point;
+//! `FollowedBlocks` first reads the best chain from a start height, then turns
+//! the tail into a stream of new best-chain blocks - continuous DirectFile tip
+//! validation with neither ZMQ nor RPC.
+//!
+//! A block in the blk files was stored, not necessarily connected: Core writes
+//! blocks before fully validating them, and stale blocks stay in the files. New
+//! blocks are linked by previous hash, a branch that grows longer than the
+//! current chain replaces it (`TailEvent::Reorg`), and consumers that need
+//! certainty should lag a few blocks behind the tip.
+//!
+//! Standard (unencrypted) files only; Start9's XOR-encrypted mounts aren't supported.
+//!
+//! Configuration:
+//! - `BLVM_FOLLOW_POLL_MS` - how often the newest blk file is checked (default: 500)
+
+use anyhow::{Context, Result};
+use std::collections::{HashMap, VecDeque};
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use crate::block_file_reader::BlockIterator;
+use crate::block_hash::block_hash;
+
+/// Poll interval by default
+pub const DEFAULT_POLL_MS: u64 = 500;
+
+/// Blocks below the tip kept for linking forks (deeper reorgs are an error)
+const REORG_WINDOW: u64 = 100;
+
+/// Blocks held while waiting for their parent before the buffer is reset
+const MAX_ORPHANS: usize = 1000;
+
+/// Poll interval from `BLVM_FOLLOW_POLL_MS`
+pub fn poll_interval_from_env() -> Result<Duration> {
+    match std::env::var("BLVM_FOLLOW_POLL_MS") {
+        Ok(ms) => Ok(Duration::from_millis(
+            ms.parse().with_context(|| format!("Invalid BLVM_FOLLOW_POLL_MS '{}'", ms))?,
+        )),
+        Err(_) => Ok(Duration::from_millis(DEFAULT_POLL_MS)),
+    }
+}
+
+fn blk_path(blocks_dir: &Path, file_num: u32) -> PathBuf {
+    blocks_dir.join(format!("blk{:05}.dat", file_num))
+}
+
+/// Number of the newest blkNNNNN.dat in `blocks_dir`
+fn newest_file_num(blocks_dir: &Path) -> Result<u32> {
+    std::fs::read_dir(blocks_dir)
+        .with_context(|| format!("Cannot read blocks directory {}", blocks_dir.display()))?
+        .flatten()
+        .filter_map(|entry| {
+            let name = entry.file_name();
+            name.to_str()?.strip_prefix("blk")?.strip_suffix(".dat")?.parse().ok()
+        })
+        .max()
+        .with_context(|| format!("No block files found in {}", blocks_dir.display()))
+}
+
+/// New records appended to the blk files since a starting point
+pub struct BlkTail {
+    blocks_dir: PathBuf,
+    magic: [u8; 4],
+    file_num: u32,
+    /// Offset of the next unread record in `file_num`
+    pos: u64,
+    /// Last record at `pos` with nothing after it yet: accepted once it reads
+    /// the same on the next poll
+    candidate: Option<Vec<u8>>,
+}
+
+impl BlkTail {
+    /// Start after the last complete record of the newest blk file
+    pub fn at_end(blocks_dir: impl AsRef<Path>, magic: &[u8; 4]) -> Result<Self> {
+        let blocks_dir = blocks_dir.as_ref().to_path_buf();
+        let file_num = newest_file_num(&blocks_dir)?;
+        let mut tail = Self {
+            blocks_dir,
+            magic: *magic,
+            file_num,
+            pos: 0,
+            candidate: None,
+        };
+        let mut file = File::open(tail.path()).with_context(|| format!("Failed to open {}", tail.path().display()))?;
+        let file_len = file.metadata()?.len();
+        while let Some(len) = tail.record_len(&mut file, file_len)? {
+            if tail.pos + 8 + len as u64 > file_len {
+                break; // Still being written: the tail picks it up
+            }
+            tail.pos += 8 + len as u64;
+        }
+        Ok(tail)
+    }
+
+    fn path(&self) -> PathBuf {
+        blk_path(&self.blocks_dir, self.file_num)
+    }
+
+    /// File and offset the next record is expected at
+    pub fn position(&self) -> (PathBuf, u64) {
+        (self.path(), self.pos)
+    }
+
+    /// Block length of the record at `pos`, None if nothing has been written there yet
+    fn record_len(&self, file: &mut File, file_len: u64) -> Result<Option<u32>> {
+        if self.pos + 8 > file_len {
+            return Ok(None);
+        }
+        let mut prefix = [0u8; 8];
+        file.seek(SeekFrom::Start(self.pos))?;
+        file.read_exact(&mut prefix)?;
+        // Core preallocates blk files with zeros
+        if prefix == [0u8; 8] {
+            return Ok(None);
+        }
+        if prefix[..4] != self.magic {
+            anyhow::bail!(
+                "Unexpected data at {}:{} (not block magic); is this the right network?",
+                self.path().display(),
+                self.pos
+            );
+        }
+        Ok(Some(u32::from_le_bytes(prefix[4..].try_into()?)))
+    }
+
+    /// Whether the next blk file has been started (Core moved on from the current one)
+    fn next_file_started(&self) -> bool {
+        let mut magic = [0u8; 4];
+        File::open(blk_path(&self.blocks_dir, self.file_num + 1))
+            .and_then(|mut f| f.read_exact(&mut magic))
+            .is_ok()
+            && magic == self.magic
+    }
+
+    /// Complete blocks appended since the last poll, in file order
+    pub fn poll(&mut self) -> Result<Vec<Vec<u8>>> {
+        let mut blocks = Vec::new();
+        loop {
+            let path = self.path();
+            let mut file = File::open(&path).with_context(|| format!("Failed to open {}", path.display()))?;
+            let file_len = file.metadata()?.len();
+            let Some(len) = self.record_len(&mut file, file_len)? else {
+                if self.next_file_started() {
+                    self.file_num += 1;
+                    self.pos = 0;
+                    self.candidate = None;
+                    continue;
+                }
+                return Ok(blocks);
+            };
+            let end = self.pos + 8 + len as u64;
+            if len < 80 || end > file_len {
+                return Ok(blocks); // Partially written
+            }
+            let mut block = vec![0u8; len as usize];
+            file.read_exact(&mut block)?;
+
+            // A record is complete once the next one starts after it, or it
+            // hasn't changed since the previous poll
+            let mut next = [0u8; 4];
+            let followed = end + 4 <= file_len && file.read_exact(&mut next).is_ok() && next == self.magic;
+            if !followed && self.candidate.as_ref() != Some(&block) {
+                self.candidate = Some(block);
+                return Ok(blocks);
+            }
+            self.candidate = None;
+            self.pos = end;
+            blocks.push(block);
+        }
+    }
+}
+
+/// What following the blk files produces
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TailEvent {
+    /// The next block of the best chain
+    Block { height: u64, bytes: Vec<u8> },
+    /// Blocks above `fork_height` left the best chain; the new branch follows
+    /// as `Block` events from `fork_height + 1`
+    Reorg { fork_height: u64 },
+}
+
+/// A recently seen block
+struct Node {
+    height: u64,
+    prev: [u8; 32],
+    /// Kept for blocks off the current chain, to replay them if their branch wins
+    bytes: Option<Vec<u8>>,
+}
+
+/// Links stored blocks into the best chain (by length) near the tip
+#[derive(Default)]
+struct TipTracker {
+    nodes: HashMap<[u8; 32], Node>,
+    /// Current chain, `chain[0]` at `base_height`
+    chain: VecDeque<[u8; 32]>,
+    base_height: u64,
+    /// Blocks waiting for their parent, by parent hash
+    orphans: HashMap<[u8; 32], Vec<Vec<u8>>>,
+    orphan_count: usize,
+}
+
+impl TipTracker {
+    fn tip_height(&self) -> Option<u64> {
+        (self.base_height + self.chain.len() as u64).checked_sub(1)
+    }
+
+    fn on_chain(&self, hash: &[u8; 32], height: u64) -> bool {
+        height >= self.base_height && self.chain.get((height - self.base_height) as usize) == Some(hash)
+    }
+
+    /// Record a best-chain block from the catch-up read
+    fn push_known(&mut self, height: u64, bytes: &[u8]) -> Result<()> {
+        let hash = block_hash(bytes).with_context(|| format!("Block at height {} is too short", height))?;
+        if self.chain.is_empty() {
+            self.base_height = height;
+        }
+        self.nodes.insert(hash, Node { height, prev: bytes[4..36].try_into()?, bytes: None });
+        self.chain.push_back(hash);
+        self.prune();
+        Ok(())
+    }
+
+    /// Add a block from the tail, appending what it changes about the best chain to `events`
+    fn add(&mut self, bytes: Vec<u8>, events: &mut VecDeque<TailEvent>) -> Result<()> {
+        let mut queue = vec![bytes];
+        while let Some(bytes) = queue.pop() {
+            let hash = block_hash(&bytes).context("Block in the blk files is too short")?;
+            let prev: [u8; 32] = bytes[4..36].try_into()?;
+            if self.nodes.contains_key(&hash) {
+                continue; // Already read during catch-up, or written twice
+            }
+            let Some(parent) = self.nodes.get(&prev) else {
+                if self.orphan_count >= MAX_ORPHANS {
+                    eprintln!("⚠️  {} blocks without a known parent in the blk files, dropping them", self.orphan_count);
+                    self.orphans.clear();
+                    self.orphan_count = 0;
+                }
+                self.orphans.entry(prev).or_default().push(bytes);
+                self.orphan_count += 1;
+                continue;
+            };
+            let height = parent.height + 1;
+            let extends_tip = self.chain.back() == Some(&prev);
+            self.nodes.insert(hash, Node { height, prev, bytes: None });
+
+            if extends_tip {
+                self.chain.push_back(hash);
+                events.push_back(TailEvent::Block { height, bytes });
+            } else if self.tip_height().is_some_and(|tip| height > tip) {
+                self.nodes.get_mut(&hash).expect("just inserted").bytes = Some(bytes);
+                self.reorg_to(hash, events)?;
+            } else {
+                // Side branch for now
+                self.nodes.get_mut(&hash).expect("just inserted").bytes = Some(bytes);
+            }
+
+            if let Some(children) = self.orphans.remove(&hash) {
+                self.orphan_count -= children.len();
+                queue.extend(children);
+            }
+        }
+        self.prune();
+        Ok(())
+    }
+
+    /// Switch the chain to the branch ending at `tip`
+    fn reorg_to(&mut self, tip: [u8; 32], events: &mut VecDeque<TailEvent>) -> Result<()> {
+        let mut branch = Vec::new();
+        let mut hash = tip;
+        loop {
+            let node = &self.nodes[&hash];
+            if self.on_chain(&hash, node.height) {
+                break;
+            }
+            branch.push(hash);
+            hash = node.prev;
+            if !self.nodes.contains_key(&hash) {
+                anyhow::bail!("Reorg deeper than the {} blocks kept for linking forks", REORG_WINDOW);
+            }
+        }
+        let fork_height = self.nodes[&hash].height;
+        let old_tip = self.tip_height().unwrap_or(fork_height);
+        println!(
+            "🔀 Reorg in the blk files: {} block(s) above height {} replaced by {}",
+            old_tip - fork_height,
+            fork_height,
+            branch.len()
+        );
+        events.push_back(TailEvent::Reorg { fork_height });
+
+        // Blocks leaving the chain can only come back if they were kept in memory,
+        // which they aren't: a branch that flips back fails below
+        self.chain.truncate((fork_height + 1 - self.base_height) as usize);
+        for hash in branch.into_iter().rev() {
+            let node = self.nodes.get_mut(&hash).expect("branch blocks are known");
+            let bytes = node
+                .bytes
+                .take()
+                .context("Reorg back to blocks that are no longer in memory")?;
+            self.chain.push_back(hash);
+            events.push_back(TailEvent::Block { height: node.height, bytes });
+        }
+        Ok(())
+    }
+
+    /// Forget blocks too far below the tip to matter for forks
+    fn prune(&mut self) {
+        let Some(tip) = self.tip_height() else {
+            return;
+        };
+        let floor = tip.saturating_sub(REORG_WINDOW);
+        while self.base_height < floor {
+            self.chain.pop_front();
+            self.base_height += 1;
+        }
+        if self.nodes.len() > 2 * (REORG_WINDOW as usize + 1) {
+            self.nodes.retain(|_, node| node.height >= floor);
+        }
+    }
+}
+
+/// The best chain from a start height, then every block Core adds to the blk files
+///
+/// Blocking iterator: once caught up, `next` sleeps between polls until a new
+/// block arrives. Created by `BlockFileReader::follow`.
+pub struct FollowedBlocks {
+    catch_up: Option<BlockIterator>,
+    next_height: u64,
+    /// The first catch-up block only seeds the chain (the parent of the start height)
+    skip_first: bool,
+    tail: BlkTail,
+    tracker: TipTracker,
+    ready: VecDeque<TailEvent>,
+    poll_interval: Duration,
+}
+
+impl FollowedBlocks {
+    /// `catch_up` yields the best chain from `catch_up_height`; `tail` must have
+    /// been positioned before that read started, so nothing falls in between
+    pub(crate) fn new(
+        catch_up: BlockIterator,
+        catch_up_height: u64,
+        skip_first: bool,
+        tail: BlkTail,
+        poll_interval: Duration,
+    ) -> Self {
+        Self {
+            catch_up: Some(catch_up),
+            next_height: catch_up_height,
+            skip_first,
+            tail,
+            tracker: TipTracker::default(),
+            ready: VecDeque::new(),
+            poll_interval,
+        }
+    }
+
+    /// Height of the last block of the current best chain (None before any)
+    pub fn tip_height(&self) -> Option<u64> {
+        self.tracker.tip_height()
+    }
+
+    fn next_event(&mut self) -> Result<TailEvent> {
+        if let Some(catch_up) = &mut self.catch_up {
+            for block in catch_up.by_ref() {
+                let block = block?;
+                let height = self.next_height;
+                self.next_height += 1;
+                self.tracker.push_known(height, &block)?;
+                if std::mem::take(&mut self.skip_first) {
+                    continue;
+                }
+                return Ok(TailEvent::Block { height, bytes: block });
+            }
+            self.catch_up = None;
+            if self.tracker.tip_height().is_none() {
+                anyhow::bail!("Nothing to follow: the catch-up read returned no blocks");
+            }
+            let (path, pos) = self.tail.position();
+            println!("👀 Caught up at height {}; following {} from offset {}", self.next_height - 1, path.display(), pos);
+        }
+        loop {
+            if let Some(event) = self.ready.pop_front() {
+                return Ok(event);
+            }
+            for block in self.tail.poll()? {
+                if block.len() >= 80 {
+                    self.tracker.add(block, &mut self.ready)?;
+                }
+            }
+            if self.ready.is_empty() {
+                std::thread::sleep(self.poll_interval);
+            }
+        }
+    }
+}
+
+impl Iterator for FollowedBlocks {
+    type Item = Result<TailEvent>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        Some(self.next_event())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    const MAGIC: [u8; 4] = [0xfa, 0xbf, 0xb5, 0xda];
+
+    /// A header-only "block" whose previous hash is `prev`
+    fn block(prev: [u8; 32], nonce: u32) -> Vec<u8> {
+        let mut header = vec![0u8; 80];
+        header[4..36].copy_from_slice(&prev);
+        header[76..80].copy_from_slice(&nonce.to_le_bytes());
+        header
+    }
+
+    fn hash(block: &[u8]) -> [u8; 32] {
+        block_hash(block).unwrap()
+    }
+
+    fn record(block: &[u8]) -> Vec<u8> {
+        let mut record = MAGIC.to_vec();
+        record.extend_from_slice(&(block.len() as u32).to_le_bytes());
+        record.extend_from_slice(block);
+        record
+    }
+
+    fn append(path: &Path, bytes: &[u8]) {
+        std::fs::OpenOptions::new().create(true).append(true).open(path).unwrap().write_all(bytes).unwrap();
+    }
+
+    #[test]
+    fn test_tail_reads_appended_records_and_next_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let first = block([0u8; 32], 0);
+        append(&dir.path().join("blk00000.dat"), &record(&first));
+
+        let mut tail = BlkTail::at_end(dir.path(), &MAGIC).unwrap();
+        assert_eq!(tail.position().1, 88);
+        assert!(tail.poll().unwrap().is_empty());
+
+        // Followed by another record: complete at once; the last one needs a second look
+        let (b1, b2) = (block(hash(&first), 1), block([1u8; 32], 2));
+        append(&dir.path().join("blk00000.dat"), &[record(&b1), record(&b2)].concat());
+        assert_eq!(tail.poll().unwrap(), vec![b1.clone()]);
+        assert_eq!(tail.poll().unwrap(), vec![b2.clone()]);
+
+        // Preallocated zeros, then Core moves on to the next file
+        append(&dir.path().join("blk00000.dat"), &[0u8; 64]);
+        assert!(tail.poll().unwrap().is_empty());
+        let b3 = block([2u8; 32], 3);
+        append(&dir.path().join("blk00001.dat"), &[record(&b3), record(&b3)].concat());
+        assert_eq!(tail.poll().unwrap(), vec![b3]);
+        assert_eq!(tail.position(), (dir.path().join("blk00001.dat"), 88));
+
+        append(&dir.path().join("blk00001.dat"), b"garbage!");
+        assert!(tail.poll().is_err());
+    }
+
+    #[test]
+    fn test_tracker_links_orphans_and_reorgs() {
+        let genesis = block([0u8; 32], 0);
+        let mut tracker = TipTracker::default();
+        tracker.push_known(0, &genesis).unwrap();
+        let mut events = VecDeque::new();
+
+        let a1 = block(hash(&genesis), 1);
+        let a2 = block(hash(&a1), 2);
+        // Child before parent: held until the parent arrives
+        tracker.add(a2.clone(), &mut events).unwrap();
+        assert!(events.is_empty());
+        tracker.add(a1.clone(), &mut events).unwrap();
+        assert_eq!(
+            events.drain(..).collect::<Vec<_>>(),
+            vec![
+                TailEvent::Block { height: 1, bytes: a1.clone() },
+                TailEvent::Block { height: 2, bytes: a2 },
+            ]
+        );
+
+        // A competing branch from height 1 only wins once it's longer
+        let b2 = block(hash(&a1), 20);
+        let b3 = block(hash(&b2), 30);
+        tracker.add(b2.clone(), &mut events).unwrap();
+        tracker.add(a1.clone(), &mut events).unwrap();
+        assert!(events.is_empty());
+        tracker.add(b3.clone(), &mut events).unwrap();
+        assert_eq!(
+            events.drain(..).collect::<Vec<_>>(),
+            vec![
+                TailEvent::Reorg { fork_height: 1 },
+                TailEvent::Block { height: 2, bytes: b2 },
+                TailEvent::Block { height: 3, bytes: b3 },
+            ]
+        );
+        assert_eq!(tracker.tip_height(), Some(3));
+    }
+}
@@ -0,0 +1,273 @@
+//! Block Corpus Extraction
+//!
+//! Pulls blocks out of any `BlockDataSource` into a directory of standalone
+//! fixture files, so criterion benches and unit tests can run on real mainnet
+//! blocks without a node at bench time:
+//!
+//! ```text
+//! <dir>/
+//!   <height>.bin     raw block bytes
+//!   <height>.hex     same, hex encoded (optional)
+//!   manifest.json    height, hash, size, tx count and why each block was picked
+//! ```
+//!
+//! Blocks are chosen by explicit height, or as the N largest / N most
+//! script-diverse blocks in a scanned range. Unlike `block_fixtures`, no UTXO
+//! pre-state is captured - these are for parsing, hashing and check_block
+//! style benches.
+
+use anyhow::{Context, Result};
+use blvm_consensus::serialization::block::deserialize_block_with_witnesses;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Path, PathBuf};
+
+use crate::block_hash::block_hash_hex;
+use crate::block_source::{for_each_block, BlockSource};
+use crate::parallel_differential::BlockDataSource;
+
+const MANIFEST: &str = "manifest.json";
+
+/// Which blocks to extract
+#[derive(Debug, Clone)]
+pub enum CorpusSelection {
+    Heights(Vec<u64>),
+    /// The `count` largest blocks in `start..=end`
+    Largest { count: usize, start: u64, end: u64 },
+    /// The `count` blocks in `start..=end` with the most distinct output script types
+    ScriptDiverse { count: usize, start: u64, end: u64 },
+}
+
+impl CorpusSelection {
+    fn reason(&self) -> &'static str {
+        match self {
+            CorpusSelection::Heights(_) => "requested",
+            CorpusSelection::Largest { .. } => "largest",
+            CorpusSelection::ScriptDiverse { .. } => "script-diverse",
+        }
+    }
+}
+
+/// One extracted block
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CorpusEntry {
+    pub height: u64,
+    pub hash: String,
+    pub size: usize,
+    pub tx_count: usize,
+    /// Distinct output script types in the block
+    pub script_types: usize,
+    pub reason: String,
+    /// File name of the raw block, relative to the corpus directory
+    pub file: String,
+}
+
+/// The corpus manifest
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CorpusManifest {
+    pub entries: Vec<CorpusEntry>,
+}
+
+/// Output script template, for diversity scoring
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ScriptType {
+    P2pk,
+    P2pkh,
+    P2sh,
+    Multisig,
+    NullData,
+    P2wpkh,
+    P2wsh,
+    P2tr,
+    /// Witness program of an unassigned version/length
+    WitnessUnknown,
+    NonStandard,
+}
+
+/// Classify an output script
+pub fn classify_script(script: &[u8]) -> ScriptType {
+    match script {
+        [0x76, 0xa9, 0x14, .., 0x88, 0xac] if script.len() == 25 => ScriptType::P2pkh,
+        [0xa9, 0x14, .., 0x87] if script.len() == 23 => ScriptType::P2sh,
+        [0x00, 0x14, ..] if script.len() == 22 => ScriptType::P2wpkh,
+        [0x00, 0x20, ..] if script.len() == 34 => ScriptType::P2wsh,
+        [0x51, 0x20, ..] if script.len() == 34 => ScriptType::P2tr,
+        [0x21, .., 0xac] if script.len() == 35 => ScriptType::P2pk,
+        [0x41, .., 0xac] if script.len() == 67 => ScriptType::P2pk,
+        [0x6a, ..] => ScriptType::NullData,
+        [version, push, ..]
+            if (*version == 0x00 || (0x51..=0x60).contains(version))
+                && (2..=40).contains(push)
+                && script.len() == *push as usize + 2 =>
+        {
+            ScriptType::WitnessUnknown
+        }
+        [.., 0xae] => ScriptType::Multisig,
+        _ => ScriptType::NonStandard,
+    }
+}
+
+/// (tx count, distinct output script types) of a serialized block
+fn block_stats(block_bytes: &[u8]) -> Result<(usize, usize)> {
+    let (block, _) = deserialize_block_with_witnesses(block_bytes)
+        .map_err(|e| anyhow::anyhow!("Failed to deserialize block: {}", e))?;
+    let types: BTreeSet<ScriptType> = block
+        .transactions
+        .iter()
+        .flat_map(|tx| tx.outputs.iter())
+        .map(|output| classify_script(&output.script_pubkey))
+        .collect();
+    Ok((block.transactions.len(), types.len()))
+}
+
+/// Keeps the `count` highest-scoring heights seen so far
+struct TopN {
+    count: usize,
+    /// (score, height) -> block bytes; smallest score is evicted first
+    best: BTreeMap<(u64, u64), Vec<u8>>,
+}
+
+impl TopN {
+    fn offer(&mut self, score: u64, height: u64, block_bytes: Vec<u8>) {
+        if self.best.len() == self.count {
+            match self.best.keys().next() {
+                Some(&lowest) if lowest < (score, height) => {
+                    self.best.remove(&lowest);
+                }
+                _ => return,
+            }
+        }
+        self.best.insert((score, height), block_bytes);
+    }
+}
+
+/// Select blocks from `source` and write them (plus the manifest) into `dir`
+pub async fn extract_corpus(
+    source: &BlockDataSource,
+    selection: &CorpusSelection,
+    dir: &Path,
+    write_hex: bool,
+) -> Result<CorpusManifest> {
+    let selected: Vec<(u64, Vec<u8>)> = match selection {
+        CorpusSelection::Heights(heights) => {
+            let mut blocks = Vec::with_capacity(heights.len());
+            for &height in heights {
+                blocks.push((height, source.get_block(height).await?));
+            }
+            blocks
+        }
+        CorpusSelection::Largest { count, start, end } | CorpusSelection::ScriptDiverse { count, start, end } => {
+            let by_size = matches!(selection, CorpusSelection::Largest { .. });
+            println!("🔎 Scanning heights {}-{} for the {} {} blocks", start, end, count, selection.reason());
+            let mut top = TopN { count: *count, best: BTreeMap::new() };
+            for_each_block(source, *start, *end, |height, block_bytes| {
+                let score = if by_size {
+                    block_bytes.len() as u64
+                } else {
+                    // Ties go to the block with more transactions
+                    let (tx_count, script_types) = block_stats(&block_bytes)?;
+                    ((script_types as u64) << 32) | tx_count as u64
+                };
+                top.offer(score, height, block_bytes);
+                if height % 10_000 == 0 {
+                    println!("   📊 Scanned to height {}", height);
+                }
+                Ok(())
+            })
+            .await?;
+            let mut blocks: Vec<(u64, Vec<u8>)> = top.best.into_iter().map(|((_, h), b)| (h, b)).collect();
+            blocks.sort_by_key(|(height, _)| *height);
+            blocks
+        }
+    };
+
+    std::fs::create_dir_all(dir).with_context(|| format!("Failed to create corpus directory {}", dir.display()))?;
+    let mut manifest = load_manifest(dir)?.unwrap_or_default();
+    for (height, block_bytes) in selected {
+        let (tx_count, script_types) = block_stats(&block_bytes)
+            .with_context(|| format!("Block {} is not a valid serialized block", height))?;
+        let file = format!("{}.bin", height);
+        std::fs::write(dir.join(&file), &block_bytes)?;
+        if write_hex {
+            std::fs::write(dir.join(format!("{}.hex", height)), hex::encode(&block_bytes))?;
+        }
+        let entry = CorpusEntry {
+            height,
+            hash: block_hash_hex(&block_bytes).unwrap_or_default(),
+            size: block_bytes.len(),
+            tx_count,
+            script_types,
+            reason: selection.reason().to_string(),
+            file,
+        };
+        println!("✅ Block {} ({} bytes, {} txs, {} script types)", height, entry.size, tx_count, script_types);
+        manifest.entries.retain(|e| e.height != height);
+        manifest.entries.push(entry);
+    }
+    manifest.entries.sort_by_key(|e| e.height);
+    std::fs::write(dir.join(MANIFEST), serde_json::to_string_pretty(&manifest)?)?;
+    Ok(manifest)
+}
+
+/// Default corpus directory: `BLVM_BLOCK_CORPUS` or `benches/fixtures/corpus`
+pub fn default_corpus_dir() -> PathBuf {
+    std::env::var("BLVM_BLOCK_CORPUS")
+        .map(PathBuf::from)
+        .unwrap_or_else(|_| PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("benches/fixtures/corpus"))
+}
+
+/// Read a corpus manifest (None if `dir` holds no corpus)
+pub fn load_manifest(dir: &Path) -> Result<Option<CorpusManifest>> {
+    let path = dir.join(MANIFEST);
+    if !path.exists() {
+        return Ok(None);
+    }
+    let content = std::fs::read_to_string(&path).with_context(|| format!("Failed to read {}", path.display()))?;
+    Ok(Some(serde_json::from_str(&content).with_context(|| format!("Failed to parse {}", path.display()))?))
+}
+
+/// Load every block of a corpus (empty if `dir` holds no corpus)
+pub fn load_corpus(dir: &Path) -> Result<Vec<(CorpusEntry, Vec<u8>)>> {
+    let Some(manifest) = load_manifest(dir)? else {
+        return Ok(Vec::new());
+    };
+    manifest
+        .entries
+        .into_iter()
+        .map(|entry| {
+            let path = dir.join(&entry.file);
+            let bytes = std::fs::read(&path).with_context(|| format!("Failed to read {}", path.display()))?;
+            if bytes.len() != entry.size {
+                anyhow::bail!("{} is {} bytes, manifest says {}", path.display(), bytes.len(), entry.size);
+            }
+            Ok((entry, bytes))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_classify_script() {
+        let p2pkh = [&[0x76, 0xa9, 0x14][..], &[0u8; 20], &[0x88, 0xac]].concat();
+        assert_eq!(classify_script(&p2pkh), ScriptType::P2pkh);
+        assert_eq!(classify_script(&[&[0x51, 0x20][..], &[1u8; 32]].concat()), ScriptType::P2tr);
+        assert_eq!(classify_script(&[&[0x00, 0x20][..], &[1u8; 32]].concat()), ScriptType::P2wsh);
+        assert_eq!(classify_script(&[&[0x52, 0x02][..], &[1u8; 2]].concat()), ScriptType::WitnessUnknown);
+        assert_eq!(classify_script(&[0x6a, 0x04, 1, 2, 3, 4]), ScriptType::NullData);
+        assert_eq!(classify_script(&[0x51, 0x21, 2, 0x51, 0xae]), ScriptType::Multisig);
+        assert_eq!(classify_script(&[0x01]), ScriptType::NonStandard);
+    }
+
+    #[test]
+    fn test_top_n_keeps_highest_scores() {
+        let mut top = TopN { count: 2, best: BTreeMap::new() };
+        for (score, height) in [(5, 1), (9, 2), (1, 3), (7, 4)] {
+            top.offer(score, height, Vec::new());
+        }
+        let heights: Vec<u64> = top.best.keys().map(|(_, h)| *h).collect();
+        assert_eq!(heights, vec![4, 2]);
+    }
+}
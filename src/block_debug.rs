@@ -0,0 +1,174 @@
+//! Targeted Block Debugging
+//!
+//! Detailed dumps of a block's header, transactions and the coins each input
+//! spends, for heights picked at run time instead of `if height == 15` blocks
+//! compiled into the runner. Heights come from `BLVM_DEBUG_HEIGHTS` (or the
+//! global `--debug-heights` flag) as a comma-separated list that may include
+//! ranges, e.g. `15,16,91722` or `100-110,91842`. Checkpoint generation, chunk
+//! validation and the block cache dump a listed block before connecting it.
+
+use anyhow::{Context, Result};
+use blvm_consensus::block::calculate_tx_id;
+use blvm_consensus::transaction::is_coinbase;
+use blvm_consensus::Block;
+use std::ops::RangeInclusive;
+use std::sync::OnceLock;
+
+use crate::block_hash::display_hex;
+use crate::utxo_backend::UtxoStore;
+
+/// Heights to dump, kept as inclusive ranges rather than expanded
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DebugHeights(Vec<RangeInclusive<u64>>);
+
+impl DebugHeights {
+    /// Parse `15,16,100-110`
+    pub fn parse(spec: &str) -> Result<Self> {
+        let mut heights = Vec::new();
+        for part in spec.split(',').map(str::trim).filter(|p| !p.is_empty()) {
+            let parse = |s: &str| s.trim().parse::<u64>().with_context(|| format!("Invalid debug height '{}'", part));
+            match part.split_once('-') {
+                Some((lo, hi)) => {
+                    let (lo, hi) = (parse(lo)?, parse(hi)?);
+                    if lo > hi {
+                        anyhow::bail!("Invalid debug height range '{}'", part);
+                    }
+                    heights.push(lo..=hi);
+                }
+                None => {
+                    let height = parse(part)?;
+                    heights.push(height..=height);
+                }
+            }
+        }
+        Ok(Self(heights))
+    }
+
+    /// From `BLVM_DEBUG_HEIGHTS` (empty when unset)
+    pub fn from_env() -> Result<Self> {
+        match std::env::var("BLVM_DEBUG_HEIGHTS") {
+            Ok(spec) => Self::parse(&spec).context("Invalid BLVM_DEBUG_HEIGHTS"),
+            Err(_) => Ok(Self::default()),
+        }
+    }
+
+    pub fn contains(&self, height: u64) -> bool {
+        self.0.iter().any(|range| range.contains(&height))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+/// The process's debug heights, read from the environment once
+pub fn debug_heights() -> &'static DebugHeights {
+    static HEIGHTS: OnceLock<DebugHeights> = OnceLock::new();
+    HEIGHTS.get_or_init(|| {
+        DebugHeights::from_env().unwrap_or_else(|e| {
+            eprintln!("⚠️  {:#}, not dumping any blocks", e);
+            DebugHeights::default()
+        })
+    })
+}
+
+/// Whether the block at `height` should be dumped
+pub fn enabled(height: u64) -> bool {
+    debug_heights().contains(height)
+}
+
+/// Header, transactions and the coins every input spends, as seen by `utxo_store` before the block
+pub fn dump_block(height: u64, block_bytes: &[u8], block: &Block, utxo_store: &dyn UtxoStore) {
+    eprintln!("🔍 Block {} ({} bytes, {} transactions)", height, block_bytes.len(), block.transactions.len());
+    if let Some(hash) = crate::block_hash::block_hash_hex(block_bytes) {
+        eprintln!("   hash: {}", hash);
+    }
+    eprintln!("   prev: {}", display_hex(&block.header.prev_block_hash));
+    eprintln!("   UTXO set before the block: {} coins ({} backend)", utxo_store.len(), utxo_store.name());
+
+    for (tx_idx, tx) in block.transactions.iter().enumerate() {
+        let coinbase = is_coinbase(tx);
+        eprintln!(
+            "   TX {} {}{}: {} inputs, {} outputs",
+            tx_idx,
+            display_hex(&calculate_tx_id(tx)),
+            if coinbase { " (coinbase)" } else { "" },
+            tx.inputs.len(),
+            tx.outputs.len()
+        );
+        if coinbase {
+            let script_sig: &[u8] = &tx.inputs[0].script_sig;
+            eprintln!("      script_sig: {}", hex::encode(script_sig));
+        } else {
+            for (input_idx, input) in tx.inputs.iter().enumerate() {
+                let prevout = format!("{}:{}", display_hex(&input.prevout.hash), input.prevout.index);
+                match utxo_store.get(&input.prevout) {
+                    Ok(Some(coin)) => eprintln!(
+                        "      in {} spends {} (value={}, height={}, coinbase={})",
+                        input_idx, prevout, coin.value, coin.height, coin.is_coinbase
+                    ),
+                    Ok(None) => {
+                        eprintln!("      in {} spends {} - MISSING", input_idx, prevout);
+                        explain_missing(&input.prevout, utxo_store);
+                    }
+                    Err(e) => eprintln!("      in {} spends {} - lookup failed: {}", input_idx, prevout, e),
+                }
+            }
+        }
+        for (output_idx, output) in tx.outputs.iter().enumerate() {
+            let script: &[u8] = &output.script_pubkey;
+            eprintln!("      out {}: value={} script={}", output_idx, output.value, hex::encode(script));
+        }
+    }
+}
+
+/// Whether other outputs of a missing coin's transaction are in the set (a wrong index rather than a missing tx)
+fn explain_missing(prevout: &blvm_consensus::OutPoint, utxo_store: &dyn UtxoStore) {
+    let utxo_set = match utxo_store.to_utxo_set() {
+        Ok(set) => set,
+        Err(e) => {
+            eprintln!("         (couldn't read the UTXO set: {})", e);
+            return;
+        }
+    };
+    let siblings: Vec<String> = utxo_set
+        .iter()
+        .filter(|(outpoint, _)| outpoint.hash == prevout.hash)
+        .map(|(outpoint, coin)| format!("{} (height {})", outpoint.index, coin.height))
+        .collect();
+    if siblings.is_empty() {
+        eprintln!("         no unspent output of that transaction - it was never created or is fully spent");
+    } else {
+        eprintln!("         that transaction still has outputs {} - wrong output index?", siblings.join(", "));
+    }
+}
+
+/// A cached block about to be served: size and hash
+pub fn dump_cached_block(height: u64, block_bytes: &[u8]) {
+    eprintln!(
+        "🔍 Block {} from cache: {} bytes, hash {}",
+        height,
+        block_bytes.len(),
+        crate::block_hash::block_hash_hex(block_bytes).unwrap_or_else(|| "(shorter than a header)".to_string())
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_heights() {
+        let heights = DebugHeights::parse("15, 16,91722,100-102").unwrap();
+        for height in [15, 16, 91_722, 100, 101, 102] {
+            assert!(heights.contains(height), "{}", height);
+        }
+        assert!(!heights.contains(17));
+        assert!(DebugHeights::parse("").unwrap().is_empty());
+        assert!(DebugHeights::parse("15,x").is_err());
+        assert!(DebugHeights::parse("20-10").is_err());
+
+        let all = DebugHeights::parse("0-18446744073709551615").unwrap();
+        assert!(all.contains(0) && all.contains(u64::MAX));
+    }
+}
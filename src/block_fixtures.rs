@@ -0,0 +1,150 @@
+//! Historical Block Fixtures
+//!
+//! Notable mainnet blocks plus the exact UTXOs they spend, stored in the same
+//! `block.bin` / `utxos.bin` layout as reproducer bundles, so `connect_block`
+//! can be benchmarked end-to-end on real data without a node.
+//!
+//! Fixtures are built once from a Core node (`getblock` verbosity 3 carries the
+//! spent prevouts) with `blvm-bench build-fixtures`.
+
+use anyhow::{Context, Result};
+use blvm_consensus::{OutPoint, UtxoSet, UTXO};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+use crate::core_rpc_client::CoreRpcClient;
+
+/// Notable blocks tracked by the historical connect_block bench: (name, height)
+pub const NOTABLE_BLOCKS: &[(&str, u64)] = &[
+    // First block after genesis - trivial baseline
+    ("block_1", 1),
+    // 2015 single 1MB transaction cleaning up spam outputs (quadratic legacy sighash)
+    ("spam_2015", 364_292),
+    // ~4MB-weight block full of witness data
+    ("max_size_segwit", 774_628),
+    // Halving block, dense with taproot commit/reveal transactions
+    ("taproot_heavy", 840_000),
+];
+
+/// Default fixture directory: `BLVM_BLOCK_FIXTURES` or `benches/fixtures/blocks`
+pub fn default_fixtures_dir() -> PathBuf {
+    std::env::var("BLVM_BLOCK_FIXTURES")
+        .map(PathBuf::from)
+        .unwrap_or_else(|_| PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("benches/fixtures/blocks"))
+}
+
+/// A loaded fixture
+pub struct BlockFixture {
+    pub name: String,
+    pub height: u64,
+    pub block_bytes: Vec<u8>,
+    pub pre_state: UtxoSet,
+}
+
+/// Load fixture `name` from `dir` (None if it hasn't been built)
+pub fn load_fixture(dir: &Path, name: &str, height: u64) -> Result<Option<BlockFixture>> {
+    let fixture_dir = dir.join(name);
+    if !fixture_dir.join("block.bin").exists() {
+        return Ok(None);
+    }
+    let (block_bytes, pre_state) = crate::reproducer::load_reproducer(&fixture_dir)?;
+    Ok(Some(BlockFixture {
+        name: name.to_string(),
+        height,
+        block_bytes,
+        pre_state,
+    }))
+}
+
+/// Fetch a block and the coins it spends from Core: (hash, block bytes, pre-state)
+pub async fn fetch_block_with_pre_state(client: &CoreRpcClient, height: u64) -> Result<(String, Vec<u8>, UtxoSet)> {
+    let block_hash = client.getblockhash(height).await?;
+    let block_bytes = hex::decode(client.getblock_raw(&block_hash).await?)?;
+    let verbose = client
+        .getblock(&block_hash, 3)
+        .await
+        .context("getblock verbosity 3 requires Bitcoin Core 23.0+")?;
+    Ok((block_hash, block_bytes, pre_state_from_verbose(&verbose)?))
+}
+
+/// Coins a block spends, from its `getblock` verbosity-3 JSON
+pub fn pre_state_from_verbose(verbose: &serde_json::Value) -> Result<UtxoSet> {
+    let txs = verbose
+        .get("tx")
+        .and_then(|t| t.as_array())
+        .context("getblock response missing tx array")?;
+
+    // Outputs created inside the block are not part of the pre-state
+    let created_in_block: HashSet<&str> = txs
+        .iter()
+        .filter_map(|tx| tx.get("txid").and_then(|t| t.as_str()))
+        .collect();
+
+    let mut pre_state = UtxoSet::new();
+    for tx in txs {
+        let vins = tx.get("vin").and_then(|v| v.as_array()).context("tx missing vin")?;
+        for vin in vins {
+            if vin.get("coinbase").is_some() {
+                continue;
+            }
+            let txid = vin.get("txid").and_then(|t| t.as_str()).context("vin missing txid")?;
+            if created_in_block.contains(txid) {
+                continue;
+            }
+            let vout = vin.get("vout").and_then(|v| v.as_u64()).context("vin missing vout")?;
+            let prevout = vin.get("prevout").context("vin missing prevout (need verbosity 3)")?;
+
+            let mut hash: [u8; 32] = hex::decode(txid)?
+                .try_into()
+                .map_err(|_| anyhow::anyhow!("Invalid txid length"))?;
+            hash.reverse(); // display order -> internal order
+            let value_btc = prevout.get("value").and_then(|v| v.as_f64()).context("prevout missing value")?;
+            let script_hex = prevout
+                .get("scriptPubKey")
+                .and_then(|s| s.get("hex"))
+                .and_then(|h| h.as_str())
+                .context("prevout missing scriptPubKey")?;
+
+            pre_state.insert(
+                OutPoint { hash, index: vout as _ },
+                UTXO {
+                    value: (value_btc * 100_000_000.0).round() as i64 as _,
+                    script_pubkey: hex::decode(script_hex)?.into(),
+                    height: prevout.get("height").and_then(|h| h.as_u64()).unwrap_or(0) as _,
+                    is_coinbase: prevout.get("generated").and_then(|g| g.as_bool()).unwrap_or(false),
+                },
+            );
+        }
+    }
+
+    Ok(pre_state)
+}
+
+/// Fetch a block and the coins it spends from Core and write it as fixture `name`
+pub async fn build_fixture(client: &CoreRpcClient, dir: &Path, name: &str, height: u64) -> Result<PathBuf> {
+    let (block_hash, block_bytes, pre_state) = fetch_block_with_pre_state(client, height).await?;
+    let fixture_dir = dir.join(name);
+    std::fs::create_dir_all(&fixture_dir)
+        .with_context(|| format!("Failed to create fixture directory {}", fixture_dir.display()))?;
+    std::fs::write(fixture_dir.join("block.bin"), &block_bytes)?;
+    std::fs::write(fixture_dir.join("utxos.bin"), crate::reproducer::encode_utxo_set(&pre_state))?;
+    let meta = serde_json::json!({
+        "name": name,
+        "height": height,
+        "block_hash": block_hash,
+        "block_size": block_bytes.len(),
+        "pre_state_utxos": pre_state.len(),
+    });
+    std::fs::write(fixture_dir.join("meta.json"), serde_json::to_string_pretty(&meta)?)?;
+
+    println!("✅ Fixture {} (height {}): {} bytes, {} spent coins", name, height, block_bytes.len(), pre_state.len());
+    Ok(fixture_dir)
+}
+
+/// Build every fixture in `NOTABLE_BLOCKS`
+pub async fn build_notable_fixtures(client: &CoreRpcClient, dir: &Path) -> Result<()> {
+    for (name, height) in NOTABLE_BLOCKS {
+        build_fixture(client, dir, name, *height).await?;
+    }
+    Ok(())
+}
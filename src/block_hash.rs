@@ -0,0 +1,140 @@
+//! Block header hashing
+//!
+//! A block's hash is the double-SHA256 of its 80-byte header. It's used in
+//! two byte orders: internal order (what `prev_block_hash` fields, the block
+//! index and chunk caches store) and display order (reversed, what Core's RPC
+//! and explorers print). These helpers replace the ad-hoc hashing and
+//! reversing that had spread across the differential runner and readers, as
+//! `compact_target` does the decoders of a header's `nBits`.
+
+use sha2::{Digest, Sha256};
+use std::cell::OnceCell;
+
+/// Length of a serialized block header
+pub const HEADER_LEN: usize = 80;
+
+/// Double-SHA256 of an 80-byte header, internal byte order
+pub fn header_hash(header: &[u8; HEADER_LEN]) -> [u8; 32] {
+    Sha256::digest(Sha256::digest(header)).into()
+}
+
+/// Hash of a serialized block (or bare header), internal byte order; `None` if it's shorter than a header
+pub fn block_hash(block: &[u8]) -> Option<[u8; 32]> {
+    block.get(..HEADER_LEN)?.try_into().ok().map(header_hash)
+}
+
+/// Internal-order hash reversed into display order
+pub fn to_display_order(mut hash: [u8; 32]) -> [u8; 32] {
+    hash.reverse();
+    hash
+}
+
+/// Hex in display order, as Core's RPC prints it
+pub fn display_hex(hash: &[u8; 32]) -> String {
+    hex::encode(to_display_order(*hash))
+}
+
+/// Display-order hash of a serialized block; `None` if it's shorter than a header
+pub fn block_hash_hex(block: &[u8]) -> Option<String> {
+    block_hash(block).map(|hash| display_hex(&hash))
+}
+
+/// Parse a display-order hex hash (as returned by Core's RPC) into internal order
+pub fn from_display_hex(hex_hash: &str) -> anyhow::Result<[u8; 32]> {
+    let bytes: [u8; 32] = hex::decode(hex_hash)?
+        .try_into()
+        .map_err(|_| anyhow::anyhow!("Block hash {} is not 32 bytes", hex_hash))?;
+    Ok(to_display_order(bytes))
+}
+
+/// A serialized block whose hash is computed on first use and reused afterwards
+pub struct CachedBlockHash<'a> {
+    block: &'a [u8],
+    hash: OnceCell<Option<[u8; 32]>>,
+}
+
+impl<'a> CachedBlockHash<'a> {
+    pub fn new(block: &'a [u8]) -> Self {
+        Self {
+            block,
+            hash: OnceCell::new(),
+        }
+    }
+
+    pub fn bytes(&self) -> &'a [u8] {
+        self.block
+    }
+
+    /// Internal byte order; `None` if the block is shorter than a header
+    pub fn internal(&self) -> Option<[u8; 32]> {
+        *self.hash.get_or_init(|| block_hash(self.block))
+    }
+
+    /// Display byte order; `None` if the block is shorter than a header
+    pub fn display(&self) -> Option<[u8; 32]> {
+        self.internal().map(to_display_order)
+    }
+
+    /// Display-order hex; `None` if the block is shorter than a header
+    pub fn display_hex(&self) -> Option<String> {
+        self.internal().map(|hash| display_hex(&hash))
+    }
+}
+
+/// Big-endian target of compact `nBits`; None if it is negative, zero or overflows 256 bits
+pub fn compact_target(bits: u32) -> Option<[u8; 32]> {
+    let exponent = (bits >> 24) as usize;
+    if bits & 0x0080_0000 != 0 || exponent > 34 {
+        return None;
+    }
+    let mantissa = match exponent {
+        0..=2 => (bits & 0x007f_ffff) >> (8 * (3 - exponent)),
+        _ => bits & 0x007f_ffff,
+    };
+    if mantissa == 0 {
+        return None;
+    }
+    // Three spare leading bytes catch a mantissa shifted past 256 bits
+    let mut wide = [0u8; 35];
+    let end = 35 - exponent.saturating_sub(3);
+    wide[end - 3..end].copy_from_slice(&mantissa.to_be_bytes()[1..]);
+    if wide[..3] != [0, 0, 0] {
+        return None;
+    }
+    wide[3..].try_into().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Mainnet genesis header
+    const GENESIS_HEADER: &str = "0100000000000000000000000000000000000000000000000000000000000000000000003ba3edfd7a7b12b27ac72c3e67768f617fc81bc3888a51323a9fb8aa4b1e5e4a29ab5f49ffff001d1dac2b7c";
+    const GENESIS_HASH: &str = "000000000019d6689c085ae165831e934ff763ae46a2a6c172b3f1b60a8ce26f";
+
+    #[test]
+    fn test_genesis_hash_both_orders() {
+        let header = hex::decode(GENESIS_HEADER).unwrap();
+        assert_eq!(block_hash_hex(&header).unwrap(), GENESIS_HASH);
+        let internal = block_hash(&header).unwrap();
+        assert_eq!(internal[31], 0x00);
+        assert_eq!(from_display_hex(GENESIS_HASH).unwrap(), internal);
+        assert!(block_hash(&header[..79]).is_none());
+
+        let cached = CachedBlockHash::new(&header);
+        assert_eq!(cached.internal(), Some(internal));
+        assert_eq!(cached.display_hex().as_deref(), Some(GENESIS_HASH));
+    }
+
+    #[test]
+    fn test_compact_target() {
+        let mut genesis_target = [0u8; 32];
+        genesis_target[4..6].copy_from_slice(&[0xff, 0xff]);
+        assert_eq!(compact_target(0x1d00_ffff), Some(genesis_target));
+        assert_eq!(compact_target(0x0300_0001).map(|t| t[31]), Some(1));
+        assert_eq!(compact_target(0x0200_8000).map(|t| t[31]), Some(0x80)); // Exponent below 3 shifts right
+        assert_eq!(compact_target(0x1d80_ffff), None); // Negative
+        assert_eq!(compact_target(0x2301_0000), None); // Overflows
+        assert_eq!(compact_target(0x1d00_0000), None); // Zero
+    }
+}
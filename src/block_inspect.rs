@@ -0,0 +1,308 @@
+//! Single-Block Investigation
+//!
+//! `inspect-block <height|hash>` is the manual workflow that used to mean
+//! editing debug prints into the runner: fetch one block from the configured
+//! source, print its header and transactions, validate it with BLVM on top of
+//! the nearest stored checkpoint (replaying the blocks in between), ask Core
+//! for its view of the same block, and show the two side by side. Without a
+//! checkpoint below the block, BLVM starts from the coins Core says the block
+//! spends (`getblock` verbosity 3).
+
+use anyhow::{Context, Result};
+use blvm_consensus::block::calculate_tx_id;
+use blvm_consensus::serialization::block::deserialize_block_with_witnesses;
+use blvm_consensus::transaction::is_coinbase;
+use blvm_consensus::types::{Network, ValidationResult};
+use blvm_consensus::UtxoSet;
+
+use crate::block_hash::display_hex;
+use crate::checkpoint_store::{utxo_store_before, CheckpointStore};
+use crate::core_rpc_client::CoreRpcClient;
+use crate::parallel_differential::{get_block_data, BlockDataSource};
+use crate::utxo_backend::UtxoBackend;
+
+/// Transactions listed in full before the rest are summarized
+const TX_LIST_LIMIT: usize = 25;
+
+/// A block named by height or by display-order hash
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BlockRef {
+    Height(u64),
+    Hash(String),
+}
+
+impl BlockRef {
+    pub fn parse(spec: &str) -> Result<Self> {
+        let spec = spec.trim();
+        if spec.len() == 64 && hex::decode(spec).is_ok() {
+            return Ok(BlockRef::Hash(spec.to_ascii_lowercase()));
+        }
+        spec.parse()
+            .map(BlockRef::Height)
+            .with_context(|| format!("'{}' is neither a block height nor a 64-character block hash", spec))
+    }
+}
+
+/// One transaction of the inspected block
+#[derive(Debug, Clone)]
+pub struct TxSummary {
+    pub txid: String,
+    pub coinbase: bool,
+    pub inputs: usize,
+    pub outputs: usize,
+    pub output_value: i64,
+}
+
+/// Core's view of the block
+#[derive(Debug, Clone)]
+pub struct CoreView {
+    /// -1 when the block isn't in Core's active chain
+    pub confirmations: i64,
+    pub tx_count: Option<u64>,
+    pub size: Option<u64>,
+    pub weight: Option<u64>,
+}
+
+impl CoreView {
+    pub fn in_active_chain(&self) -> bool {
+        self.confirmations >= 0
+    }
+
+    fn verdict(&self) -> String {
+        if self.in_active_chain() {
+            format!("Valid (active chain, {} confirmations)", self.confirmations)
+        } else {
+            "Not in active chain".to_string()
+        }
+    }
+}
+
+/// Everything `inspect-block` found out
+#[derive(Debug, Clone)]
+pub struct Inspection {
+    pub height: u64,
+    pub hash: String,
+    pub size: usize,
+    pub version: i64,
+    pub prev_hash: String,
+    pub merkle_root: String,
+    pub timestamp: u64,
+    pub bits: u32,
+    pub nonce: u64,
+    pub txs: Vec<TxSummary>,
+    /// What BLVM's UTXO set was built from
+    pub base: String,
+    /// `Valid`, `Invalid(..)` or `Panic(..)`
+    pub blvm: String,
+    pub blvm_secs: f64,
+    /// None if Core couldn't be asked (with why in `core_error`)
+    pub core: Option<CoreView>,
+    pub core_error: Option<String>,
+}
+
+impl Inspection {
+    /// Whether BLVM and Core agree (None if Core's view is unknown)
+    pub fn agrees(&self) -> Option<bool> {
+        self.core
+            .as_ref()
+            .map(|core| (self.blvm == "Valid") == core.in_active_chain())
+    }
+
+    pub fn print(&self) {
+        println!("🧱 Block {} ({})", self.height, self.hash);
+        println!("   size:        {} bytes", self.size);
+        println!("   version:     0x{:08x}", self.version);
+        println!("   prev:        {}", self.prev_hash);
+        println!("   merkle root: {}", self.merkle_root);
+        println!("   time:        {}", self.timestamp);
+        println!("   bits:        {:08x}", self.bits);
+        println!("   nonce:       {}", self.nonce);
+
+        let inputs: usize = self.txs.iter().filter(|tx| !tx.coinbase).map(|tx| tx.inputs).sum();
+        let outputs: usize = self.txs.iter().map(|tx| tx.outputs).sum();
+        println!("\n📜 {} transactions, {} inputs, {} outputs", self.txs.len(), inputs, outputs);
+        for (idx, tx) in self.txs.iter().take(TX_LIST_LIMIT).enumerate() {
+            println!(
+                "   {:>4} {}{} {} in / {} out, {} sat",
+                idx,
+                tx.txid,
+                if tx.coinbase { " coinbase" } else { "" },
+                tx.inputs,
+                tx.outputs,
+                tx.output_value
+            );
+        }
+        if self.txs.len() > TX_LIST_LIMIT {
+            println!("   ... {} more (--verbose lists every input)", self.txs.len() - TX_LIST_LIMIT);
+        }
+
+        println!("\n⚖️  BLVM (from {}) vs Core", self.base);
+        let (core_verdict, core_txs, core_size, core_weight) = match &self.core {
+            Some(core) => (
+                core.verdict(),
+                core.tx_count.map(|n| n.to_string()),
+                core.size.map(|n| n.to_string()),
+                core.weight.map(|n| n.to_string()),
+            ),
+            None => (
+                format!("unknown ({})", self.core_error.as_deref().unwrap_or("no RPC")),
+                None,
+                None,
+                None,
+            ),
+        };
+        let unknown = || "-".to_string();
+        println!("   {:<14} {:<40} {}", "", "BLVM", "Core");
+        println!("   {:<14} {:<40} {}", "verdict", self.blvm, core_verdict);
+        println!("   {:<14} {:<40} {}", "transactions", self.txs.len(), core_txs.unwrap_or_else(unknown));
+        println!("   {:<14} {:<40} {}", "size", self.size, core_size.unwrap_or_else(unknown));
+        println!("   {:<14} {:<40} {}", "weight", "-", core_weight.unwrap_or_else(unknown));
+        println!("   {:<14} {:.1}ms", "connect time", self.blvm_secs * 1000.0);
+        match self.agrees() {
+            Some(true) => println!("\n✅ BLVM and Core agree"),
+            Some(false) => println!("\n❌ BLVM and Core DISAGREE"),
+            None => println!("\n❔ Core's view unknown"),
+        }
+    }
+}
+
+/// Inspect one block (see module docs); `verbose` also dumps every input's spent coin
+pub async fn inspect_block(
+    block_ref: &BlockRef,
+    block_source: &BlockDataSource,
+    client: Option<&CoreRpcClient>,
+    checkpoints: &CheckpointStore,
+    utxo_backend: &UtxoBackend,
+    verbose: bool,
+) -> Result<Inspection> {
+    let height = match block_ref {
+        BlockRef::Height(height) => *height,
+        BlockRef::Hash(hash) => {
+            let client = client.context("Looking a block up by hash needs Core's RPC")?;
+            let header = client.getblockheader(hash).await.with_context(|| format!("Core doesn't know block {}", hash))?;
+            header["height"].as_u64().context("getblockheader returned no height")?
+        }
+    };
+    let block_bytes = get_block_data(block_source, height).await?;
+    let hash = crate::block_hash::block_hash_hex(&block_bytes)
+        .with_context(|| format!("Block {} is shorter than a header", height))?;
+    if let BlockRef::Hash(wanted) = block_ref {
+        if *wanted != hash {
+            anyhow::bail!(
+                "{} has block {} at height {}, not {} (is {} on Core's active chain?)",
+                block_source.name(),
+                hash,
+                height,
+                wanted,
+                wanted
+            );
+        }
+    }
+    let (block, witnesses) = deserialize_block_with_witnesses(&block_bytes)
+        .map_err(|e| anyhow::anyhow!("Failed to deserialize block {}: {}", height, e))?;
+
+    let txs = block
+        .transactions
+        .iter()
+        .map(|tx| TxSummary {
+            txid: display_hex(&calculate_tx_id(tx)),
+            coinbase: is_coinbase(tx),
+            inputs: tx.inputs.len(),
+            outputs: tx.outputs.len(),
+            output_value: tx.outputs.iter().map(|o| o.value as i64).sum(),
+        })
+        .collect();
+
+    let (base_set, base) = base_utxo_set(height, block_source, client, checkpoints, utxo_backend).await?;
+    let mut utxo_store = utxo_backend.create(base_set)?;
+    if verbose {
+        crate::block_debug::dump_block(height, &block_bytes, &block, utxo_store.as_ref());
+    }
+    let started = std::time::Instant::now();
+    let connected = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+        utxo_store.connect_block(&block, &witnesses, height, Network::Mainnet)
+    }));
+    let blvm_secs = started.elapsed().as_secs_f64();
+    let blvm = match connected {
+        Ok(Ok(ValidationResult::Valid)) => "Valid".to_string(),
+        Ok(Ok(ValidationResult::Invalid(msg))) => format!("Invalid({})", msg),
+        Ok(Err(e)) => format!("Invalid({:?})", e),
+        Err(payload) => format!("Panic({})", crate::quarantine::panic_message(payload.as_ref())),
+    };
+
+    let (core, core_error) = match client {
+        Some(client) => match core_view(client, &hash).await {
+            Ok(view) => (Some(view), None),
+            Err(e) => (None, Some(format!("{:#}", e))),
+        },
+        None => (None, Some("no Core RPC configured".to_string())),
+    };
+
+    Ok(Inspection {
+        height,
+        hash,
+        size: block_bytes.len(),
+        version: block.header.version as i64,
+        prev_hash: display_hex(&block.header.prev_block_hash),
+        merkle_root: display_hex(&block.header.merkle_root),
+        timestamp: block.header.timestamp as u64,
+        bits: block.header.bits as u32,
+        nonce: block.header.nonce as u64,
+        txs,
+        base,
+        blvm,
+        blvm_secs,
+        core,
+        core_error,
+    })
+}
+
+/// UTXO set just before `height`, and a description of where it came from
+async fn base_utxo_set(
+    height: u64,
+    block_source: &BlockDataSource,
+    client: Option<&CoreRpcClient>,
+    checkpoints: &CheckpointStore,
+    utxo_backend: &UtxoBackend,
+) -> Result<(UtxoSet, String)> {
+    if height == 0 {
+        return Ok((UtxoSet::new(), "an empty UTXO set".to_string()));
+    }
+    if let Some((utxo_store, checkpoint)) = utxo_store_before(height, block_source, checkpoints, utxo_backend).await? {
+        return Ok((
+            utxo_store.to_utxo_set()?,
+            format!("checkpoint {} + {} replayed blocks", checkpoint, height - 1 - checkpoint),
+        ));
+    }
+    let client = client.context("No checkpoint below this block and no Core RPC to fetch the coins it spends")?;
+    let (_, _, pre_state) = crate::block_fixtures::fetch_block_with_pre_state(client, height).await?;
+    Ok((pre_state, "the coins Core says it spends".to_string()))
+}
+
+async fn core_view(client: &CoreRpcClient, hash: &str) -> Result<CoreView> {
+    let header = client.getblockheader(hash).await.context("Core doesn't know this block")?;
+    let confirmations = header["confirmations"].as_i64().context("getblockheader returned no confirmations")?;
+    // Block data may be pruned or missing for stale blocks; the header is enough for the verdict
+    let block = client.getblock(hash, 1).await.ok();
+    let field = |name: &str| block.as_ref().and_then(|b| b[name].as_u64());
+    Ok(CoreView {
+        confirmations,
+        tx_count: field("nTx"),
+        size: field("size"),
+        weight: field("weight"),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_block_ref() {
+        assert_eq!(BlockRef::parse("91722").unwrap(), BlockRef::Height(91_722));
+        let hash = "000000000019D6689C085AE165831E934FF763AE46A2A6C172B3F1B60A8CE26F";
+        assert_eq!(BlockRef::parse(hash).unwrap(), BlockRef::Hash(hash.to_ascii_lowercase()));
+        assert!(BlockRef::parse("tip").is_err());
+        assert!(BlockRef::parse(&hash[1..]).is_err());
+    }
+}
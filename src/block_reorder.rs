@@ -0,0 +1,249 @@
+//! Streaming Block Reordering
+//!
+//! Blocks in blk*.dat files are not strictly height-ordered even on the main
+//! chain (headers-first sync downloads in parallel). `ReorderedBlocks` puts
+//! them in order on the fly, without the up-front header scan of `best_chain`:
+//! each block is parked in a buffer keyed by its previous-block hash until its
+//! parent has been emitted.
+//!
+//! A block is only emitted once a child of it has been seen (or the input has
+//! ended), so a stale sibling that arrived first loses to the block that was
+//! actually built on. Stale blocks whose parent was already passed are dropped.
+//! Buffered bytes are capped (`BLVM_REORDER_BUFFER_MB`, default 512); a run
+//! that needs more fails instead of growing without bound.
+
+use anyhow::{Context, Result};
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use crate::block_hash::block_hash;
+
+/// Default cap on buffered block bytes
+pub const DEFAULT_REORDER_BUFFER_MB: usize = 512;
+
+/// Deepest descendant chain looked at when choosing between siblings
+const MAX_LOOKAHEAD: usize = 16;
+
+/// Buffer cap in bytes from `BLVM_REORDER_BUFFER_MB`
+pub fn reorder_cap_from_env() -> Result<usize> {
+    let mb = match std::env::var("BLVM_REORDER_BUFFER_MB") {
+        Ok(mb) => mb
+            .parse()
+            .with_context(|| format!("Invalid BLVM_REORDER_BUFFER_MB '{}'", mb))?,
+        Err(_) => DEFAULT_REORDER_BUFFER_MB,
+    };
+    Ok(mb * 1024 * 1024)
+}
+
+struct Parked {
+    hash: [u8; 32],
+    bytes: Vec<u8>,
+}
+
+/// Orders blocks by prev-hash linkage, starting from genesis
+pub struct ReorderBuffer {
+    cap_bytes: usize,
+    buffered_bytes: usize,
+    /// Hash of the last emitted block (zero before genesis)
+    tip: [u8; 32],
+    emitted: HashSet<[u8; 32]>,
+    /// Parked blocks by previous-block hash
+    parked: HashMap<[u8; 32], Vec<Parked>>,
+    ready: VecDeque<Vec<u8>>,
+    /// Stale blocks dropped so far
+    pub dropped: usize,
+}
+
+impl ReorderBuffer {
+    pub fn new(cap_bytes: usize) -> Self {
+        Self {
+            cap_bytes,
+            buffered_bytes: 0,
+            tip: [0u8; 32],
+            emitted: HashSet::new(),
+            parked: HashMap::new(),
+            ready: VecDeque::new(),
+            dropped: 0,
+        }
+    }
+
+    /// Add the next block in file order
+    pub fn push(&mut self, block: Vec<u8>) -> Result<()> {
+        if block.len() < 80 {
+            anyhow::bail!("Block of {} bytes has no header", block.len());
+        }
+        let hash = block_hash(&block).expect("length checked above");
+        let prev: [u8; 32] = block[4..36].try_into()?;
+        if self.emitted.contains(&hash) || (prev != self.tip && self.emitted.contains(&prev)) {
+            // Duplicate, or a sibling of a block already emitted
+            self.dropped += 1;
+            return Ok(());
+        }
+        self.buffered_bytes += block.len();
+        self.parked.entry(prev).or_default().push(Parked { hash, bytes: block });
+        self.advance(false);
+        if self.buffered_bytes > self.cap_bytes {
+            anyhow::bail!(
+                "Reorder buffer exceeded {} MB after {} blocks (gap after height {}); raise BLVM_REORDER_BUFFER_MB or use BLVM_BLOCK_ORDER=best-chain",
+                self.cap_bytes / (1024 * 1024),
+                self.emitted.len(),
+                self.emitted.len() as i64 - 1
+            );
+        }
+        Ok(())
+    }
+
+    /// No more input: emit whatever still links to the tip
+    pub fn finish(&mut self) {
+        self.advance(true);
+    }
+
+    pub fn pop(&mut self) -> Option<Vec<u8>> {
+        self.ready.pop_front()
+    }
+
+    /// Length of the longest parked chain starting at `hash` (bounded)
+    fn depth(&self, hash: &[u8; 32], limit: usize) -> usize {
+        if limit == 0 {
+            return 0;
+        }
+        self.parked
+            .get(hash)
+            .map(|children| {
+                children
+                    .iter()
+                    .map(|c| 1 + self.depth(&c.hash, limit - 1))
+                    .max()
+                    .unwrap_or(0)
+            })
+            .unwrap_or(0)
+    }
+
+    fn advance(&mut self, finishing: bool) {
+        while let Some(candidates) = self.parked.get(&self.tip) {
+            let depths: Vec<usize> = candidates.iter().map(|c| self.depth(&c.hash, MAX_LOOKAHEAD)).collect();
+            let best = *depths.iter().max().expect("non-empty candidate list");
+            let leaders = depths.iter().filter(|&&d| d == best).count();
+            // Wait for a child (or a clear winner among siblings) unless input ended
+            if !finishing && (best == 0 || leaders > 1) {
+                return;
+            }
+            let chosen = depths.iter().position(|&d| d == best).expect("max is present");
+            let mut candidates = self.parked.remove(&self.tip).expect("looked up above");
+            let block = candidates.swap_remove(chosen);
+            for sibling in candidates {
+                self.discard(sibling);
+            }
+            self.buffered_bytes -= block.bytes.len();
+            self.emitted.insert(block.hash);
+            self.tip = block.hash;
+            self.ready.push_back(block.bytes);
+        }
+    }
+
+    /// Drop a losing sibling and everything parked on top of it
+    fn discard(&mut self, block: Parked) {
+        self.buffered_bytes -= block.bytes.len();
+        self.dropped += 1;
+        for child in self.parked.remove(&block.hash).unwrap_or_default() {
+            self.discard(child);
+        }
+    }
+}
+
+/// Blocks from a file-order iterator, in height order from `skip` on
+pub struct ReorderedBlocks<I> {
+    raw: I,
+    buffer: ReorderBuffer,
+    exhausted: bool,
+    /// Leading blocks still to skip (heights below the requested start)
+    skip: u64,
+}
+
+impl<I: Iterator<Item = Result<Vec<u8>>>> ReorderedBlocks<I> {
+    pub fn new(raw: I, start_height: u64, cap_bytes: usize) -> Self {
+        Self {
+            raw,
+            buffer: ReorderBuffer::new(cap_bytes),
+            exhausted: false,
+            skip: start_height,
+        }
+    }
+}
+
+impl<I: Iterator<Item = Result<Vec<u8>>>> Iterator for ReorderedBlocks<I> {
+    type Item = Result<Vec<u8>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(block) = self.buffer.pop() {
+                if self.skip > 0 {
+                    self.skip -= 1;
+                    continue;
+                }
+                return Some(Ok(block));
+            }
+            if self.exhausted {
+                return None;
+            }
+            match self.raw.next() {
+                Some(Ok(block)) => {
+                    if let Err(e) = self.buffer.push(block) {
+                        self.exhausted = true;
+                        return Some(Err(e));
+                    }
+                }
+                Some(Err(e)) => return Some(Err(e)),
+                None => {
+                    self.exhausted = true;
+                    self.buffer.finish();
+                    if self.buffer.dropped > 0 {
+                        println!("   🧹 Reorder buffer dropped {} stale blocks", self.buffer.dropped);
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn block(prev: &[u8], nonce: u8) -> Vec<u8> {
+        let mut block = vec![0u8; 81];
+        block[4..36].copy_from_slice(prev);
+        block[76] = nonce;
+        block
+    }
+
+    fn hash(block: &[u8]) -> Vec<u8> {
+        block_hash(block).unwrap().to_vec()
+    }
+
+    #[test]
+    fn test_reorders_and_drops_stale() {
+        let genesis = block(&[0; 32], 0);
+        let a = block(&hash(&genesis), 1);
+        let stale = block(&hash(&genesis), 2);
+        let b = block(&hash(&a), 3);
+        let c = block(&hash(&b), 4);
+        let late_stale = block(&hash(&a), 5);
+
+        // Stale sibling first, child before parent, a stale block after its height passed
+        let raw = vec![genesis.clone(), stale, c.clone(), b.clone(), a.clone(), late_stale];
+        let ordered: Vec<Vec<u8>> = ReorderedBlocks::new(raw.into_iter().map(Ok), 0, 1 << 20)
+            .collect::<Result<_>>()
+            .unwrap();
+        assert_eq!(ordered, vec![genesis.clone(), a.clone(), b.clone(), c.clone()]);
+
+        let from_two: Vec<Vec<u8>> = ReorderedBlocks::new(vec![genesis.clone(), a.clone(), b.clone(), c].into_iter().map(Ok), 2, 1 << 20)
+            .collect::<Result<_>>()
+            .unwrap();
+        assert_eq!(from_two.len(), 2);
+        assert_eq!(from_two[0], b);
+
+        // Cap exceeded while waiting for a missing parent
+        let gap = vec![genesis, b.clone(), b].into_iter().map(Ok);
+        assert!(ReorderedBlocks::new(gap, 0, 100).any(|r| r.is_err()));
+    }
+}
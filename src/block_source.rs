@@ -0,0 +1,244 @@
+//! Pluggable Block Sources
+//!
+//! `BlockSource` is everything the differential runners need from wherever
+//! blocks come from: a block by height, the tip height, and optionally a faster
+//! sequential read. Each built-in source implements it, `BlockDataSource`
+//! dispatches to them, and `BlockDataSource::Custom` wraps any other
+//! implementation (an S3 bucket, an Esplora server, a private archive) so it
+//! plugs into checkpoint generation, chunk validation, corpus extraction and the
+//! cache builders without forking the crate.
+
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+
+use crate::block_file_reader::BlockFileReader;
+use crate::core_rpc_client::CoreRpcClient;
+use crate::mmap_cache::MmapBlockCache;
+use crate::p2p_client::P2pClient;
+use crate::parallel_differential::BlockDataSource;
+use crate::start9_session::Start9Session;
+
+/// Raw blocks in height order, from a source's sequential reader
+pub type SequentialBlocks = Box<dyn Iterator<Item = Result<Vec<u8>>> + Send>;
+
+/// A source of raw (serialized) blocks by height
+#[async_trait]
+pub trait BlockSource: Send + Sync {
+    /// Short name for logs
+    fn name(&self) -> &str;
+
+    /// Raw block at `height` in the source's best chain
+    async fn get_block(&self, height: u64) -> Result<Vec<u8>>;
+
+    /// Highest block the source can serve (None if it can't tell)
+    async fn get_tip_height(&self) -> Result<Option<u64>>;
+
+    /// Blocks `start..start + count` in height order, for sources that read
+    /// sequentially much faster than by height
+    ///
+    /// Returns None (the default) when `get_block` per height is the way to read
+    /// this source; callers fall back to that.
+    fn iter_sequential(&self, _start: u64, _count: usize) -> Result<Option<SequentialBlocks>> {
+        Ok(None)
+    }
+}
+
+#[async_trait]
+impl BlockSource for BlockFileReader {
+    fn name(&self) -> &str {
+        "direct file"
+    }
+
+    async fn get_block(&self, height: u64) -> Result<Vec<u8>> {
+        self.read_blocks_sequential(Some(height), Some(1))?
+            .next()
+            .with_context(|| format!("Block {} not found in block files", height))?
+    }
+
+    async fn get_tip_height(&self) -> Result<Option<u64>> {
+        Ok(Some(self.tip_height()?))
+    }
+
+    fn iter_sequential(&self, start: u64, count: usize) -> Result<Option<SequentialBlocks>> {
+        Ok(Some(Box::new(self.read_blocks_sequential(Some(start), Some(count))?)))
+    }
+}
+
+#[async_trait]
+impl BlockSource for CoreRpcClient {
+    fn name(&self) -> &str {
+        "RPC"
+    }
+
+    async fn get_block(&self, height: u64) -> Result<Vec<u8>> {
+        let block_hash = self.getblockhash(height).await?;
+        let block_hex = self.getblock_raw(&block_hash).await?;
+        Ok(hex::decode(&block_hex)?)
+    }
+
+    async fn get_tip_height(&self) -> Result<Option<u64>> {
+        Ok(Some(self.getblockcount().await?))
+    }
+}
+
+#[async_trait]
+impl BlockSource for Start9Session {
+    fn name(&self) -> &str {
+        "Start9 RPC"
+    }
+
+    async fn get_block(&self, height: u64) -> Result<Vec<u8>> {
+        let block_hash = self.get_block_hash(height).await?;
+        let block_hex = self.get_block_hex(&block_hash).await?;
+        Ok(hex::decode(&block_hex)?)
+    }
+
+    async fn get_tip_height(&self) -> Result<Option<u64>> {
+        Ok(Some(self.get_block_count().await?))
+    }
+}
+
+#[async_trait]
+impl BlockSource for P2pClient {
+    fn name(&self) -> &str {
+        "P2P"
+    }
+
+    async fn get_block(&self, height: u64) -> Result<Vec<u8>> {
+        P2pClient::get_block(self, height).await
+    }
+
+    async fn get_tip_height(&self) -> Result<Option<u64>> {
+        Ok(Some(self.sync_headers().await?))
+    }
+}
+
+#[async_trait]
+impl BlockSource for MmapBlockCache {
+    fn name(&self) -> &str {
+        "mmap cache"
+    }
+
+    async fn get_block(&self, height: u64) -> Result<Vec<u8>> {
+        self.get(height)
+            .map(|bytes| bytes.to_vec())
+            .with_context(|| format!("Block {} not in mmap cache", height))
+    }
+
+    async fn get_tip_height(&self) -> Result<Option<u64>> {
+        Ok(Some(self.end_height()))
+    }
+}
+
+#[async_trait]
+impl BlockSource for BlockDataSource {
+    fn name(&self) -> &str {
+        BlockDataSource::name(self)
+    }
+
+    async fn get_block(&self, height: u64) -> Result<Vec<u8>> {
+        match self {
+            // Fine for single blocks; ranges should go through `iter_sequential`
+            BlockDataSource::DirectFile(reader) => reader.get_block(height).await,
+            BlockDataSource::SharedCache(cache, rpc_client) => {
+                cache.get_or_fetch_block(height, rpc_client.as_deref()).await
+            }
+            BlockDataSource::Rpc(client) => client.get_block(height).await,
+            BlockDataSource::Start9Rpc(session) => session.get_block(height).await,
+            BlockDataSource::P2p(client) => BlockSource::get_block(client.as_ref(), height).await,
+            BlockDataSource::MmapCache(cache) => cache.get_block(height).await,
+            BlockDataSource::Custom(source) => source.get_block(height).await,
+        }
+    }
+
+    async fn get_tip_height(&self) -> Result<Option<u64>> {
+        match self {
+            BlockDataSource::DirectFile(reader) => reader.get_tip_height().await,
+            // Cache without RPC: the chain height is unknown
+            BlockDataSource::SharedCache(_, None) => Ok(None),
+            BlockDataSource::SharedCache(_, Some(client)) | BlockDataSource::Rpc(client) => client.get_tip_height().await,
+            BlockDataSource::Start9Rpc(session) => session.get_tip_height().await,
+            BlockDataSource::P2p(client) => client.get_tip_height().await,
+            BlockDataSource::MmapCache(cache) => cache.get_tip_height().await,
+            BlockDataSource::Custom(source) => source.get_tip_height().await,
+        }
+    }
+
+    fn iter_sequential(&self, start: u64, count: usize) -> Result<Option<SequentialBlocks>> {
+        match self {
+            BlockDataSource::DirectFile(reader) => reader.iter_sequential(start, count),
+            BlockDataSource::Custom(source) => source.iter_sequential(start, count),
+            _ => Ok(None),
+        }
+    }
+}
+
+/// Call `f` with every block in `start..=end`, reading sequentially where the source can
+pub async fn for_each_block(
+    source: &dyn BlockSource,
+    start: u64,
+    end: u64,
+    mut f: impl FnMut(u64, Vec<u8>) -> Result<()>,
+) -> Result<()> {
+    match source.iter_sequential(start, (end - start + 1) as usize)? {
+        Some(blocks) => {
+            for (height, block) in (start..).zip(blocks) {
+                f(height, block?)?;
+            }
+        }
+        None => {
+            for height in start..=end {
+                f(height, source.get_block(height).await?)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+
+    /// Blocks held in memory, one byte each (the height)
+    struct VecSource(Vec<Vec<u8>>);
+
+    #[async_trait]
+    impl BlockSource for VecSource {
+        fn name(&self) -> &str {
+            "in-memory"
+        }
+
+        async fn get_block(&self, height: u64) -> Result<Vec<u8>> {
+            self.0.get(height as usize).cloned().with_context(|| format!("No block {}", height))
+        }
+
+        async fn get_tip_height(&self) -> Result<Option<u64>> {
+            Ok(self.0.len().checked_sub(1).map(|tip| tip as u64))
+        }
+    }
+
+    #[test]
+    fn test_custom_source_through_block_data_source() {
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let source = BlockDataSource::Custom(Arc::new(VecSource((0..10u8).map(|h| vec![h]).collect())));
+        assert_eq!(source.name(), "in-memory");
+        assert!(source.iter_sequential(0, 10).unwrap().is_none());
+
+        runtime.block_on(async {
+            assert_eq!(source.get_tip_height().await.unwrap(), Some(9));
+            assert_eq!(source.get_block(4).await.unwrap(), vec![4]);
+
+            let mut seen = Vec::new();
+            for_each_block(&source, 3, 6, |height, block| {
+                seen.push((height, block[0]));
+                Ok(())
+            })
+            .await
+            .unwrap();
+            assert_eq!(seen, vec![(3, 3), (4, 4), (5, 5), (6, 6)]);
+
+            assert!(source.get_block(10).await.is_err());
+        });
+    }
+}
@@ -0,0 +1,315 @@
+//! Chunked Cache Archive in Object Storage
+//!
+//! A prepared chunked block cache is ~600GB and takes hours to read out of a
+//! node. Pushing it once to S3-compatible object storage (AWS, MinIO, R2, ...)
+//! lets CI runners and distributed workers pull it instead, optionally only the
+//! chunks covering the heights they need.
+//!
+//! Transfers go through the `aws` CLI, which brings multipart uploads, retries
+//! and the usual credential chain (`AWS_PROFILE`, `AWS_ACCESS_KEY_ID`, instance
+//! roles). Files whose remote size matches the local one are skipped, and
+//! `chunks.meta` is always written last, so a reader never sees metadata
+//! pointing at chunks that aren't there yet. A signed `manifest.json` travels
+//! with the chunks; when trusted keys are configured, `pull` checks its
+//! signature before downloading and every chunk's hash after.
+//!
+//! Configuration:
+//! - `BLVM_CACHE_ARCHIVE` - default archive, `s3://bucket/prefix`
+//! - `BLVM_S3_ENDPOINT` - endpoint URL for non-AWS object storage
+
+use anyhow::{Context, Result};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use crate::cache_manifest::{trusted_keys_from_env, CacheManifest, MANIFEST_FILE};
+use crate::chunked_cache::{chunk_path, expected_hashes_path, load_chunk_metadata};
+
+const META_FILE: &str = "chunks.meta";
+
+/// Where a chunked cache lives in object storage
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CacheArchive {
+    pub bucket: String,
+    /// Key prefix without leading or trailing slashes ("" = bucket root)
+    pub prefix: String,
+    /// Endpoint URL for S3-compatible storage (None = AWS)
+    pub endpoint: Option<String>,
+}
+
+impl CacheArchive {
+    /// Parse `s3://bucket/prefix`
+    pub fn parse(url: &str) -> Result<Self> {
+        let rest = url
+            .trim()
+            .strip_prefix("s3://")
+            .with_context(|| format!("Cache archive '{}' must look like s3://bucket/prefix", url))?;
+        let (bucket, prefix) = rest.split_once('/').unwrap_or((rest, ""));
+        if bucket.is_empty() {
+            anyhow::bail!("Cache archive '{}' has no bucket", url);
+        }
+        Ok(Self {
+            bucket: bucket.to_string(),
+            prefix: prefix.trim_matches('/').to_string(),
+            endpoint: std::env::var("BLVM_S3_ENDPOINT").ok().filter(|e| !e.is_empty()),
+        })
+    }
+
+    /// `url`, or `BLVM_CACHE_ARCHIVE` when not given
+    pub fn from_arg_or_env(url: Option<&str>) -> Result<Self> {
+        match url {
+            Some(url) => Self::parse(url),
+            None => Self::parse(
+                &std::env::var("BLVM_CACHE_ARCHIVE").context("No cache archive given (--archive or BLVM_CACHE_ARCHIVE)")?,
+            ),
+        }
+    }
+
+    fn key(&self, file_name: &str) -> String {
+        if self.prefix.is_empty() {
+            file_name.to_string()
+        } else {
+            format!("{}/{}", self.prefix, file_name)
+        }
+    }
+
+    fn s3_url(&self, file_name: &str) -> String {
+        format!("s3://{}/{}", self.bucket, self.key(file_name))
+    }
+
+    /// Run `aws <args>` against this archive's endpoint
+    fn aws(&self, args: &[&str]) -> Result<Vec<u8>> {
+        let mut command = Command::new("aws");
+        if let Some(endpoint) = &self.endpoint {
+            command.args(["--endpoint-url", endpoint]);
+        }
+        let output = command
+            .args(args)
+            .output()
+            .context("Failed to run the aws CLI (is it installed?)")?;
+        if !output.status.success() {
+            anyhow::bail!("aws {} failed: {}", args.join(" "), String::from_utf8_lossy(&output.stderr).trim());
+        }
+        Ok(output.stdout)
+    }
+
+    /// Sizes of the archive's files, by file name
+    pub fn list(&self) -> Result<HashMap<String, u64>> {
+        let prefix = if self.prefix.is_empty() { String::new() } else { format!("{}/", self.prefix) };
+        let stdout = self.aws(&[
+            "s3api", "list-objects-v2", "--bucket", &self.bucket, "--prefix", &prefix, "--output", "json",
+        ])?;
+        parse_listing(&stdout, &prefix)
+    }
+
+    fn upload(&self, local: &Path, file_name: &str) -> Result<()> {
+        let local = local.to_string_lossy();
+        self.aws(&["s3", "cp", "--only-show-errors", &local, &self.s3_url(file_name)])?;
+        Ok(())
+    }
+
+    /// Download to a temporary name and rename, so an interrupted pull never leaves a torn file
+    fn download(&self, file_name: &str, local: &Path) -> Result<()> {
+        let tmp = local.with_extension("part");
+        let tmp_str = tmp.to_string_lossy();
+        self.aws(&["s3", "cp", "--only-show-errors", &self.s3_url(file_name), &tmp_str])?;
+        std::fs::rename(&tmp, local).with_context(|| format!("Failed to move {} into place", tmp.display()))
+    }
+
+    /// Upload the chunked cache in `chunks_dir`, skipping files already there
+    pub fn push(&self, chunks_dir: &Path) -> Result<TransferReport> {
+        let metadata = load_chunk_metadata(chunks_dir)?
+            .with_context(|| format!("No chunks.meta in {}", chunks_dir.display()))?;
+        let remote = self.list()?;
+        println!("📤 Pushing {} chunks from {} to {}", metadata.num_chunks, chunks_dir.display(), self.s3_url(""));
+
+        let mut files: Vec<PathBuf> = (0..metadata.num_chunks).map(|n| chunk_path(chunks_dir, n)).collect();
+        files.push(expected_hashes_path(chunks_dir));
+        let mut report = TransferReport::default();
+        for path in files.iter().filter(|p| p.exists()) {
+            let name = file_name(path)?;
+            let size = std::fs::metadata(path)?.len();
+            if remote.get(name) == Some(&size) {
+                report.skipped += 1;
+                continue;
+            }
+            println!("   📤 {} ({:.1} MB)", name, size as f64 / 1_048_576.0);
+            self.upload(path, name)?;
+            report.record(size);
+        }
+        // Last, so the archive's metadata never describes chunks it doesn't have
+        let manifest = CacheManifest::path(chunks_dir);
+        if manifest.exists() {
+            self.upload(&manifest, MANIFEST_FILE)?;
+        } else {
+            println!("   ⚠️  No {} - consumers won't be able to verify this cache (see sign-cache)", MANIFEST_FILE);
+        }
+        self.upload(&chunks_dir.join(META_FILE), META_FILE)?;
+        Ok(report)
+    }
+
+    /// Download the chunks covering `heights` (all if None) into `chunks_dir`
+    ///
+    /// Chunks already present locally with the archive's size are kept. With
+    /// `BLVM_CACHE_TRUSTED_KEYS` set, the archive must carry a manifest signed by
+    /// one of them, and every chunk must match its hash in it.
+    pub fn pull(&self, chunks_dir: &Path, heights: Option<(u64, u64)>) -> Result<TransferReport> {
+        std::fs::create_dir_all(chunks_dir).with_context(|| format!("Failed to create {}", chunks_dir.display()))?;
+        let remote = self.list()?;
+        if !remote.contains_key(META_FILE) {
+            anyhow::bail!("{} has no chunks.meta", self.s3_url(""));
+        }
+        // Stage the metadata beside the chunks until they're all in place
+        let staging = chunks_dir.join("archive-meta");
+        std::fs::create_dir_all(&staging)?;
+        self.download(META_FILE, &staging.join(META_FILE))?;
+        let metadata = load_chunk_metadata(&staging)?.context("Archive chunks.meta is incomplete")?;
+        let trusted = trusted_keys_from_env()?;
+        let manifest = if remote.contains_key(MANIFEST_FILE) {
+            self.download(MANIFEST_FILE, &staging.join(MANIFEST_FILE))?;
+            CacheManifest::load(&staging)?
+        } else {
+            None
+        };
+        if !trusted.is_empty() {
+            let manifest = manifest.as_ref().with_context(|| format!("{} has no signed {}", self.s3_url(""), MANIFEST_FILE))?;
+            manifest.verify_signatures(&trusted)?;
+            if manifest.body.total_blocks != metadata.total_blocks {
+                anyhow::bail!("Archive manifest covers {} blocks but chunks.meta says {}", manifest.body.total_blocks, metadata.total_blocks);
+            }
+            println!("🔏 Archive manifest is signed by a trusted key");
+        }
+        // Only enforced when the manifest's signature has been checked
+        let verify_with = manifest.as_ref().filter(|_| !trusted.is_empty());
+
+        let chunks = chunk_range(metadata.num_chunks, metadata.blocks_per_chunk, heights);
+        println!(
+            "📥 Pulling chunks {}-{} of {} from {} into {}",
+            chunks.start,
+            chunks.end.saturating_sub(1),
+            metadata.num_chunks,
+            self.s3_url(""),
+            chunks_dir.display()
+        );
+        let mut files: Vec<PathBuf> = chunks.map(|n| chunk_path(chunks_dir, n)).collect();
+        files.push(expected_hashes_path(chunks_dir));
+        let mut report = TransferReport::default();
+        for path in &files {
+            let name = file_name(path)?;
+            let Some(&size) = remote.get(name) else {
+                if name.starts_with("chunk_") {
+                    anyhow::bail!("{} is missing {}", self.s3_url(""), name);
+                }
+                continue;
+            };
+            if std::fs::metadata(path).map(|m| m.len()).ok() == Some(size) {
+                report.skipped += 1;
+            } else {
+                println!("   📥 {} ({:.1} MB)", name, size as f64 / 1_048_576.0);
+                self.download(name, path)?;
+                report.record(size);
+            }
+            if let Some(mismatch) = verify_with.and_then(|m| m.check_file(path, false)) {
+                let _ = std::fs::remove_file(path);
+                anyhow::bail!("{} doesn't match the signed manifest: {} (removed)", mismatch.file, mismatch.reason);
+            }
+        }
+        if manifest.is_some() {
+            std::fs::rename(staging.join(MANIFEST_FILE), CacheManifest::path(chunks_dir))?;
+        }
+        std::fs::rename(staging.join(META_FILE), chunks_dir.join(META_FILE))?;
+        let _ = std::fs::remove_dir(&staging);
+        Ok(report)
+    }
+}
+
+/// What a push or pull moved
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TransferReport {
+    pub transferred: usize,
+    pub skipped: usize,
+    pub bytes: u64,
+}
+
+impl TransferReport {
+    fn record(&mut self, bytes: u64) {
+        self.transferred += 1;
+        self.bytes += bytes;
+    }
+}
+
+fn file_name(path: &Path) -> Result<&str> {
+    path.file_name()
+        .and_then(|n| n.to_str())
+        .with_context(|| format!("Bad cache file name {}", path.display()))
+}
+
+/// Chunk numbers holding `heights` (inclusive), or every chunk
+fn chunk_range(num_chunks: usize, blocks_per_chunk: u64, heights: Option<(u64, u64)>) -> std::ops::Range<usize> {
+    match heights {
+        None => 0..num_chunks,
+        Some((start, end)) => {
+            let first = (start / blocks_per_chunk) as usize;
+            let last = (end / blocks_per_chunk) as usize;
+            first.min(num_chunks)..(last + 1).min(num_chunks)
+        }
+    }
+}
+
+/// File sizes from `aws s3api list-objects-v2` output, keyed by name below `prefix`
+fn parse_listing(stdout: &[u8], prefix: &str) -> Result<HashMap<String, u64>> {
+    if stdout.iter().all(u8::is_ascii_whitespace) {
+        // Nothing under the prefix
+        return Ok(HashMap::new());
+    }
+    let listing: serde_json::Value = serde_json::from_slice(stdout).context("Unexpected aws s3api output")?;
+    Ok(listing
+        .get("Contents")
+        .and_then(|c| c.as_array())
+        .into_iter()
+        .flatten()
+        .filter_map(|object| {
+            let name = object.get("Key")?.as_str()?.strip_prefix(prefix)?;
+            // Only files directly under the prefix belong to this archive
+            if name.is_empty() || name.contains('/') {
+                return None;
+            }
+            Some((name.to_string(), object.get("Size")?.as_u64()?))
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_archive_url_and_keys() {
+        let archive = CacheArchive::parse("s3://blocks/mainnet/chunks/").unwrap();
+        assert_eq!(archive.bucket, "blocks");
+        assert_eq!(archive.prefix, "mainnet/chunks");
+        assert_eq!(archive.s3_url("chunks.meta"), "s3://blocks/mainnet/chunks/chunks.meta");
+
+        let root = CacheArchive::parse("s3://blocks").unwrap();
+        assert_eq!(root.key("chunk_0.bin.zst"), "chunk_0.bin.zst");
+        assert!(CacheArchive::parse("https://blocks").is_err());
+        assert!(CacheArchive::parse("s3:///chunks").is_err());
+    }
+
+    #[test]
+    fn test_listing_and_chunk_range() {
+        let stdout = br#"{"Contents": [
+            {"Key": "cache/chunks.meta", "Size": 120},
+            {"Key": "cache/chunk_0.bin.zst", "Size": 5000},
+            {"Key": "cache/old/chunk_0.bin.zst", "Size": 1}
+        ]}"#;
+        let listing = parse_listing(stdout, "cache/").unwrap();
+        assert_eq!(listing.len(), 2);
+        assert_eq!(listing["chunk_0.bin.zst"], 5000);
+        assert!(parse_listing(b"\n", "cache/").unwrap().is_empty());
+
+        assert_eq!(chunk_range(9, 100_000, None), 0..9);
+        assert_eq!(chunk_range(9, 100_000, Some((150_000, 420_000))), 1..5);
+        assert_eq!(chunk_range(9, 100_000, Some((850_000, 2_000_000))), 8..9);
+    }
+}
@@ -0,0 +1,393 @@
+//! Signed Cache Manifests
+//!
+//! A prepared chunked cache is only a useful differential input if it holds
+//! exactly the blocks it claims to. `manifest.json` lists every chunk file with
+//! the heights it covers, its size and its SHA-256, and carries BIP340 Schnorr
+//! signatures over a tagged hash of that listing. A team signs the manifest once
+//! when it publishes a cache; consumers check a signature from a key they trust,
+//! then check each chunk against its listed hash before using it.
+//!
+//! Configuration:
+//! - `BLVM_CACHE_SIGNING_KEY` - hex secret key used by `sign-cache`
+//! - `BLVM_CACHE_TRUSTED_KEYS` - comma-separated hex x-only public keys a
+//!   manifest must be signed by before a cache is trusted
+
+use anyhow::{Context, Result};
+use secp256k1::{schnorr, Keypair, Message, Secp256k1, SecretKey, XOnlyPublicKey};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+use crate::bench_fixtures::tagged_hash;
+use crate::chunked_cache::{chunk_path, expected_hashes_path, load_chunk_metadata};
+
+/// Manifest file name inside a chunks directory
+pub const MANIFEST_FILE: &str = "manifest.json";
+
+/// BIP340 tag for the signed digest
+const SIGNATURE_TAG: &str = "BLVM/CacheManifest";
+
+const MANIFEST_VERSION: u32 = 1;
+
+/// One file of the cache and what it must hash to
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ManifestEntry {
+    pub file: String,
+    /// Heights held by the file (inclusive); None for non-chunk files
+    pub heights: Option<(u64, u64)>,
+    pub size: u64,
+    /// Hex SHA-256 of the file contents
+    pub sha256: String,
+}
+
+/// The signed part of a manifest
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ManifestBody {
+    pub version: u32,
+    pub total_blocks: u64,
+    pub blocks_per_chunk: u64,
+    pub files: Vec<ManifestEntry>,
+}
+
+impl ManifestBody {
+    /// Digest the signatures commit to
+    fn digest(&self) -> Result<[u8; 32]> {
+        Ok(tagged_hash(SIGNATURE_TAG, &serde_json::to_vec(self)?))
+    }
+
+    pub fn entry(&self, file: &str) -> Option<&ManifestEntry> {
+        self.files.iter().find(|e| e.file == file)
+    }
+}
+
+/// A signer's x-only public key and Schnorr signature, both hex
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ManifestSignature {
+    pub pubkey: String,
+    pub signature: String,
+}
+
+/// `manifest.json`: the listing plus any number of signatures over it
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CacheManifest {
+    pub body: ManifestBody,
+    #[serde(default)]
+    pub signatures: Vec<ManifestSignature>,
+}
+
+/// A file that doesn't match the manifest
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ManifestMismatch {
+    pub file: String,
+    pub reason: String,
+}
+
+/// Hex SHA-256 of a file, streamed
+pub fn file_sha256(path: &Path) -> Result<String> {
+    let mut file = std::fs::File::open(path).with_context(|| format!("Failed to open {}", path.display()))?;
+    let mut hasher = Sha256::new();
+    let mut buf = vec![0u8; 4 * 1024 * 1024];
+    loop {
+        let n = file.read(&mut buf)?;
+        if n == 0 {
+            break;
+        }
+        hasher.update(&buf[..n]);
+    }
+    Ok(hex::encode(hasher.finalize()))
+}
+
+/// Keys from `BLVM_CACHE_TRUSTED_KEYS` (empty if unset)
+pub fn trusted_keys_from_env() -> Result<Vec<XOnlyPublicKey>> {
+    match std::env::var("BLVM_CACHE_TRUSTED_KEYS") {
+        Ok(keys) => parse_keys(&keys),
+        Err(_) => Ok(Vec::new()),
+    }
+}
+
+/// Comma-separated hex x-only public keys
+pub fn parse_keys(keys: &str) -> Result<Vec<XOnlyPublicKey>> {
+    keys.split(',')
+        .map(str::trim)
+        .filter(|k| !k.is_empty())
+        .map(|k| {
+            let bytes = hex::decode(k).with_context(|| format!("Invalid public key '{}'", k))?;
+            XOnlyPublicKey::from_slice(&bytes).with_context(|| format!("Invalid x-only public key '{}'", k))
+        })
+        .collect()
+}
+
+/// Secret key from a hex string (file contents or `BLVM_CACHE_SIGNING_KEY`)
+pub fn parse_secret_key(hex_key: &str) -> Result<SecretKey> {
+    let bytes = hex::decode(hex_key.trim()).context("Signing key is not hex")?;
+    SecretKey::from_slice(&bytes).context("Invalid signing key")
+}
+
+impl CacheManifest {
+    /// Hash every file of the chunked cache in `chunks_dir` (unsigned)
+    pub fn build(chunks_dir: &Path) -> Result<Self> {
+        let metadata = load_chunk_metadata(chunks_dir)?
+            .with_context(|| format!("No chunks.meta in {}", chunks_dir.display()))?;
+        let mut files = Vec::with_capacity(metadata.num_chunks + 1);
+        for chunk_num in 0..metadata.num_chunks {
+            let path = chunk_path(chunks_dir, chunk_num);
+            let start = chunk_num as u64 * metadata.blocks_per_chunk;
+            let end = (start + metadata.blocks_per_chunk).min(metadata.total_blocks) - 1;
+            println!("   🔏 Hashing chunk {} (heights {}-{})", chunk_num, start, end);
+            files.push(Self::entry_for(&path, Some((start, end)))?);
+        }
+        let hashes = expected_hashes_path(chunks_dir);
+        if hashes.exists() {
+            files.push(Self::entry_for(&hashes, None)?);
+        }
+        Ok(Self {
+            body: ManifestBody {
+                version: MANIFEST_VERSION,
+                total_blocks: metadata.total_blocks,
+                blocks_per_chunk: metadata.blocks_per_chunk,
+                files,
+            },
+            signatures: Vec::new(),
+        })
+    }
+
+    fn entry_for(path: &Path, heights: Option<(u64, u64)>) -> Result<ManifestEntry> {
+        Ok(ManifestEntry {
+            file: path
+                .file_name()
+                .and_then(|n| n.to_str())
+                .with_context(|| format!("Bad cache file name {}", path.display()))?
+                .to_string(),
+            heights,
+            size: std::fs::metadata(path).with_context(|| format!("Missing {}", path.display()))?.len(),
+            sha256: file_sha256(path)?,
+        })
+    }
+
+    pub fn path(chunks_dir: &Path) -> PathBuf {
+        chunks_dir.join(MANIFEST_FILE)
+    }
+
+    /// Load `manifest.json` from `chunks_dir` (None if there isn't one)
+    pub fn load(chunks_dir: &Path) -> Result<Option<Self>> {
+        let path = Self::path(chunks_dir);
+        if !path.exists() {
+            return Ok(None);
+        }
+        let content = std::fs::read_to_string(&path).with_context(|| format!("Failed to read {}", path.display()))?;
+        Ok(Some(serde_json::from_str(&content).with_context(|| format!("Failed to parse {}", path.display()))?))
+    }
+
+    pub fn save(&self, chunks_dir: &Path) -> Result<()> {
+        let path = Self::path(chunks_dir);
+        std::fs::write(&path, serde_json::to_string_pretty(self)?)
+            .with_context(|| format!("Failed to write {}", path.display()))
+    }
+
+    /// Add (or replace) `secret_key`'s signature
+    pub fn sign(&mut self, secret_key: &SecretKey) -> Result<XOnlyPublicKey> {
+        let secp = Secp256k1::new();
+        let keypair = Keypair::from_secret_key(&secp, secret_key);
+        let (pubkey, _) = keypair.x_only_public_key();
+        let msg = Message::from_digest_slice(&self.body.digest()?)?;
+        let signature = secp.sign_schnorr_no_aux_rand(&msg, &keypair);
+        let pubkey_hex = hex::encode(pubkey.serialize());
+        self.signatures.retain(|s| s.pubkey != pubkey_hex);
+        self.signatures.push(ManifestSignature {
+            pubkey: pubkey_hex,
+            signature: hex::encode(signature.as_ref().to_vec()),
+        });
+        Ok(pubkey)
+    }
+
+    /// The trusted keys with a valid signature on this manifest; errors if there are none
+    pub fn verify_signatures(&self, trusted: &[XOnlyPublicKey]) -> Result<Vec<XOnlyPublicKey>> {
+        if trusted.is_empty() {
+            anyhow::bail!("No trusted keys to verify the cache manifest against");
+        }
+        let secp = Secp256k1::verification_only();
+        let msg = Message::from_digest_slice(&self.body.digest()?)?;
+        let valid: Vec<XOnlyPublicKey> = trusted
+            .iter()
+            .filter(|key| {
+                let key_hex = hex::encode(key.serialize());
+                self.signatures.iter().filter(|s| s.pubkey == key_hex).any(|s| {
+                    hex::decode(&s.signature)
+                        .ok()
+                        .and_then(|sig| schnorr::Signature::from_slice(&sig).ok())
+                        .is_some_and(|sig| secp.verify_schnorr(&sig, &msg, key).is_ok())
+                })
+            })
+            .copied()
+            .collect();
+        if valid.is_empty() {
+            anyhow::bail!(
+                "Cache manifest has no valid signature from a trusted key ({} signatures present)",
+                self.signatures.len()
+            );
+        }
+        Ok(valid)
+    }
+
+    /// Compare a file against its entry (size, then SHA-256 unless `size_only`)
+    pub fn check_file(&self, path: &Path, size_only: bool) -> Option<ManifestMismatch> {
+        let name = path.file_name()?.to_str()?.to_string();
+        let mismatch = |reason: String| Some(ManifestMismatch { file: name.clone(), reason });
+        let Some(entry) = self.body.entry(&name) else {
+            return mismatch("not listed in the manifest".to_string());
+        };
+        let size = match std::fs::metadata(path) {
+            Ok(m) => m.len(),
+            Err(_) => return mismatch("missing".to_string()),
+        };
+        if size != entry.size {
+            return mismatch(format!("size {} but manifest says {}", size, entry.size));
+        }
+        if size_only {
+            return None;
+        }
+        match file_sha256(path) {
+            Ok(hash) if hash == entry.sha256 => None,
+            Ok(hash) => mismatch(format!("sha256 {} but manifest says {}", hash, entry.sha256)),
+            Err(e) => mismatch(format!("unreadable: {}", e)),
+        }
+    }
+
+    /// Check the cache in `chunks_dir` against the manifest: chunks.meta agrees,
+    /// every listed file present matches (`size_only` skips hashing) and no
+    /// chunk or expected-hashes file is there that the manifest doesn't list
+    pub fn verify_files(&self, chunks_dir: &Path, size_only: bool) -> Result<Vec<ManifestMismatch>> {
+        let metadata = load_chunk_metadata(chunks_dir)?
+            .with_context(|| format!("No chunks.meta in {}", chunks_dir.display()))?;
+        if metadata.total_blocks != self.body.total_blocks || metadata.blocks_per_chunk != self.body.blocks_per_chunk {
+            anyhow::bail!(
+                "chunks.meta ({} blocks, {} per chunk) doesn't match the manifest ({} blocks, {} per chunk)",
+                metadata.total_blocks,
+                metadata.blocks_per_chunk,
+                self.body.total_blocks,
+                self.body.blocks_per_chunk
+            );
+        }
+        let mut mismatches: Vec<ManifestMismatch> = self
+            .body
+            .files
+            .iter()
+            .map(|entry| chunks_dir.join(&entry.file))
+            // Partial pulls only hold some chunks; absent ones are reported by the reader
+            .filter(|path| path.exists())
+            .filter_map(|path| self.check_file(&path, size_only))
+            .collect();
+        mismatches.extend(self.unlisted_files(chunks_dir)?);
+        Ok(mismatches)
+    }
+
+    /// Chunk and expected-hashes files in `chunks_dir` the manifest doesn't list
+    /// (the reader would use them unverified)
+    fn unlisted_files(&self, chunks_dir: &Path) -> Result<Vec<ManifestMismatch>> {
+        let hashes_file = expected_hashes_path(chunks_dir);
+        let mut unlisted = Vec::new();
+        for dir_entry in std::fs::read_dir(chunks_dir).with_context(|| format!("Failed to list {}", chunks_dir.display()))? {
+            let path = dir_entry?.path();
+            let Some(name) = path.file_name().and_then(|n| n.to_str()) else {
+                continue;
+            };
+            let is_cache_file = path == hashes_file || (name.starts_with("chunk_") && name.ends_with(".bin.zst"));
+            if is_cache_file && self.body.entry(name).is_none() {
+                unlisted.push(ManifestMismatch {
+                    file: name.to_string(),
+                    reason: "not listed in the manifest".to_string(),
+                });
+            }
+        }
+        unlisted.sort_by(|a, b| a.file.cmp(&b.file));
+        Ok(unlisted)
+    }
+}
+
+/// Require a trusted signature on `chunks_dir`'s manifest and files whose
+/// size and SHA-256 match it
+///
+/// No-op when no trusted keys are configured.
+pub fn verify_trusted_cache(chunks_dir: &Path, trusted: &[XOnlyPublicKey]) -> Result<()> {
+    if trusted.is_empty() {
+        return Ok(());
+    }
+    let manifest = CacheManifest::load(chunks_dir)?
+        .with_context(|| format!("Trusted keys are configured but {} has no {}", chunks_dir.display(), MANIFEST_FILE))?;
+    manifest.verify_signatures(trusted)?;
+    println!("🔐 Hashing {} cache files against the signed manifest...", manifest.body.files.len());
+    let mismatches = manifest.verify_files(chunks_dir, false)?;
+    if let Some(first) = mismatches.first() {
+        anyhow::bail!(
+            "{} cache files don't match the signed manifest (first: {}: {})",
+            mismatches.len(),
+            first.file,
+            first.reason
+        );
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn manifest() -> CacheManifest {
+        CacheManifest {
+            body: ManifestBody {
+                version: MANIFEST_VERSION,
+                total_blocks: 150,
+                blocks_per_chunk: 100,
+                files: vec![
+                    ManifestEntry { file: "chunk_0.bin.zst".into(), heights: Some((0, 99)), size: 10, sha256: "aa".into() },
+                    ManifestEntry { file: "chunk_1.bin.zst".into(), heights: Some((100, 149)), size: 5, sha256: "bb".into() },
+                ],
+            },
+            signatures: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_sign_and_verify() {
+        let key = crate::bench_fixtures::secret_key(7);
+        let other = crate::bench_fixtures::secret_key(8);
+        let mut manifest = manifest();
+        let pubkey = manifest.sign(&key).unwrap();
+        let (other_pubkey, _) = Keypair::from_secret_key(&Secp256k1::new(), &other).x_only_public_key();
+
+        assert_eq!(manifest.verify_signatures(&[other_pubkey, pubkey]).unwrap(), vec![pubkey]);
+        assert!(manifest.verify_signatures(&[other_pubkey]).is_err());
+        assert!(manifest.verify_signatures(&[]).is_err());
+
+        // Round-trips through JSON, and re-signing replaces rather than duplicates
+        let parsed: CacheManifest = serde_json::from_str(&serde_json::to_string(&manifest).unwrap()).unwrap();
+        assert!(parsed.verify_signatures(&[pubkey]).is_ok());
+        manifest.sign(&key).unwrap();
+        assert_eq!(manifest.signatures.len(), 1);
+
+        // Any change to the listing invalidates the signature
+        manifest.body.files[1].sha256 = "cc".into();
+        assert!(manifest.verify_signatures(&[pubkey]).is_err());
+    }
+
+    #[test]
+    fn test_unlisted_files_are_reported() {
+        let dir = tempfile::tempdir().unwrap();
+        for name in ["chunk_0.bin.zst", "chunk_2.bin.zst", "block_hashes.bin", "notes.txt", MANIFEST_FILE] {
+            std::fs::write(dir.path().join(name), b"x").unwrap();
+        }
+        let unlisted: Vec<String> = manifest().unlisted_files(dir.path()).unwrap().into_iter().map(|m| m.file).collect();
+        assert_eq!(unlisted, vec!["block_hashes.bin", "chunk_2.bin.zst"]);
+    }
+
+    #[test]
+    fn test_parse_keys() {
+        let key = crate::bench_fixtures::secret_key(1);
+        let (pubkey, _) = Keypair::from_secret_key(&Secp256k1::new(), &key).x_only_public_key();
+        let hex_key = hex::encode(pubkey.serialize());
+        assert_eq!(parse_keys(&format!(" {}, ", hex_key)).unwrap(), vec![pubkey]);
+        assert!(parse_keys("zz").is_err());
+        assert_eq!(parse_secret_key(&hex::encode(key.secret_bytes())).unwrap(), key);
+    }
+}
@@ -0,0 +1,279 @@
+//! Shared Block Cache Warming
+//!
+//! Fills a `SharedBlockCache` directory (`block_<height>.bin` files) for a height
+//! range from the best available block source, so later differential runs -
+//! and other machines sharing the directory - read blocks from local disk
+//! instead of RPC. Sequential sources (block files, chunked caches) are read in
+//! one pass; the rest block by block, skipping heights already cached.
+//!
+//! Progress is written to `warm-state.json` in the cache directory as it goes:
+//! re-running the same range continues where the last run stopped, and blocks
+//! already present are never fetched again. Block files are written to a
+//! temporary name and renamed, so an interrupted run leaves no torn blocks.
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use crate::block_file_reader::SharedBlockCache;
+use crate::block_source::BlockSource;
+
+/// Name of the resumption state file inside the cache directory
+pub const STATE_FILE: &str = "warm-state.json";
+
+/// How often progress is printed and the state file refreshed
+const REPORT_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Where an interrupted warm-up stopped
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WarmState {
+    pub start_height: u64,
+    pub end_height: u64,
+    /// Every height below this (from `start_height`) is cached
+    pub next_height: u64,
+}
+
+impl WarmState {
+    fn path(cache_dir: &Path) -> PathBuf {
+        cache_dir.join(STATE_FILE)
+    }
+
+    /// The saved state, if there is a readable one
+    pub fn load(cache_dir: &Path) -> Option<Self> {
+        let data = std::fs::read(Self::path(cache_dir)).ok()?;
+        serde_json::from_slice(&data).ok()
+    }
+
+    pub fn save(&self, cache_dir: &Path) -> Result<()> {
+        let path = Self::path(cache_dir);
+        let tmp = path.with_extension("json.tmp");
+        std::fs::write(&tmp, serde_json::to_vec_pretty(self)?)?;
+        std::fs::rename(&tmp, &path).with_context(|| format!("Failed to save {}", path.display()))
+    }
+
+    /// Height to resume `start..=end` from: the saved position when it's the same range
+    fn resume_height(saved: Option<&Self>, start: u64, end: u64) -> u64 {
+        match saved {
+            Some(state) if state.start_height == start && state.end_height == end => state.next_height.max(start),
+            _ => start,
+        }
+    }
+}
+
+/// What a warm-up did
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct WarmReport {
+    /// Blocks fetched and written
+    pub written: u64,
+    /// Blocks that were already cached
+    pub skipped: u64,
+    pub bytes: u64,
+}
+
+/// "1h 05m", "12m 30s" or "42s"
+pub fn format_eta(secs: f64) -> String {
+    if !secs.is_finite() {
+        return "?".to_string();
+    }
+    let secs = secs.round() as u64;
+    match (secs / 3600, secs % 3600 / 60, secs % 60) {
+        (0, 0, s) => format!("{}s", s),
+        (0, m, s) => format!("{}m {:02}s", m, s),
+        (h, m, _) => format!("{}h {:02}m", h, m),
+    }
+}
+
+/// Rate, ETA and periodic state saves over one warm-up
+struct WarmProgress<'a> {
+    cache_dir: &'a Path,
+    state: WarmState,
+    total: u64,
+    started: Instant,
+    last_report: Instant,
+    report: WarmReport,
+}
+
+impl WarmProgress<'_> {
+    fn record(&mut self, height: u64, written: Option<usize>) -> Result<()> {
+        match written {
+            Some(bytes) => {
+                self.report.written += 1;
+                self.report.bytes += bytes as u64;
+            }
+            None => self.report.skipped += 1,
+        }
+        self.state.next_height = height + 1;
+        if self.last_report.elapsed() >= REPORT_INTERVAL {
+            self.last_report = Instant::now();
+            self.state.save(self.cache_dir)?;
+            let done = self.report.written + self.report.skipped;
+            let rate = done as f64 / self.started.elapsed().as_secs_f64().max(1e-9);
+            println!(
+                "   📊 Height {} ({:.1}%), {:.1} blocks/sec, {:.2} GB written, ETA {}",
+                height,
+                100.0 * done as f64 / self.total as f64,
+                rate,
+                self.report.bytes as f64 / 1e9,
+                format_eta(self.total.saturating_sub(done) as f64 / rate)
+            );
+        }
+        Ok(())
+    }
+}
+
+/// Write `bytes` as the cached block at `height` (temporary file, then rename)
+fn write_block(cache: &SharedBlockCache, height: u64, bytes: &[u8]) -> Result<()> {
+    let path = cache.block_path(height);
+    let tmp = path.with_extension("bin.tmp");
+    std::fs::write(&tmp, bytes).with_context(|| format!("Failed to write {}", tmp.display()))?;
+    std::fs::rename(&tmp, &path).with_context(|| format!("Failed to write {}", path.display()))
+}
+
+/// Cache every block in `start..=end` (end defaults to the source's tip)
+pub async fn warm_cache(
+    cache: &SharedBlockCache,
+    source: &dyn BlockSource,
+    start: u64,
+    end: Option<u64>,
+) -> Result<WarmReport> {
+    let end = match end {
+        Some(end) => end,
+        None => source
+            .get_tip_height()
+            .await?
+            .with_context(|| format!("{} can't tell its tip height; pass --end", source.name()))?,
+    };
+    if end < start {
+        anyhow::bail!("Nothing to warm: end height {} is below start height {}", end, start);
+    }
+    let cache_dir = cache.cache_dir();
+
+    // Skip what a previous run (or anything else) already cached at the front of the range
+    let saved = WarmState::load(cache_dir);
+    let mut from = WarmState::resume_height(saved.as_ref(), start, end);
+    while from <= end && cache.block_path(from).exists() {
+        from += 1;
+    }
+    if from > start {
+        println!("⏩ Heights {}-{} already cached", start, from - 1);
+    }
+    let state = WarmState { start_height: start, end_height: end, next_height: from };
+    if from > end {
+        state.save(cache_dir)?;
+        println!("✅ Cache already warm for heights {}-{}", start, end);
+        return Ok(WarmReport { skipped: end - start + 1, ..Default::default() });
+    }
+
+    println!("🔥 Warming {} with heights {}-{} from {}", cache_dir.display(), from, end, source.name());
+    let mut progress = WarmProgress {
+        cache_dir,
+        state,
+        total: end - from + 1,
+        started: Instant::now(),
+        last_report: Instant::now(),
+        report: WarmReport::default(),
+    };
+    match source.iter_sequential(from, (end - from + 1) as usize)? {
+        Some(blocks) => {
+            for (height, block) in (from..=end).zip(blocks) {
+                let block = block.with_context(|| format!("Failed to read block {}", height))?;
+                let written = if cache.block_path(height).exists() {
+                    None
+                } else {
+                    write_block(cache, height, &block)?;
+                    Some(block.len())
+                };
+                progress.record(height, written)?;
+            }
+        }
+        None => {
+            for height in from..=end {
+                let written = if cache.block_path(height).exists() {
+                    None
+                } else {
+                    let block = source.get_block(height).await.with_context(|| format!("Failed to fetch block {}", height))?;
+                    write_block(cache, height, &block)?;
+                    Some(block.len())
+                };
+                progress.record(height, written)?;
+            }
+        }
+    }
+    if progress.state.next_height <= end {
+        progress.state.save(cache_dir)?;
+        anyhow::bail!("{} ended at height {} before {}", source.name(), progress.state.next_height - 1, end);
+    }
+    progress.state.save(cache_dir)?;
+
+    let report = progress.report;
+    let elapsed = progress.started.elapsed().as_secs_f64();
+    println!(
+        "✅ Warmed heights {}-{}: {} blocks written ({:.2} GB), {} already cached, in {}",
+        start,
+        end,
+        report.written,
+        report.bytes as f64 / 1e9,
+        report.skipped + (from - start),
+        format_eta(elapsed)
+    );
+    Ok(WarmReport { skipped: report.skipped + (from - start), ..report })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use async_trait::async_trait;
+
+    /// Blocks held in memory, one byte each (the height)
+    struct VecSource(Vec<Vec<u8>>);
+
+    #[async_trait]
+    impl BlockSource for VecSource {
+        fn name(&self) -> &str {
+            "in-memory"
+        }
+
+        async fn get_block(&self, height: u64) -> Result<Vec<u8>> {
+            self.0.get(height as usize).cloned().with_context(|| format!("No block {}", height))
+        }
+
+        async fn get_tip_height(&self) -> Result<Option<u64>> {
+            Ok(self.0.len().checked_sub(1).map(|tip| tip as u64))
+        }
+    }
+
+    #[test]
+    fn test_warm_resumes_and_skips_cached_blocks() {
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let dir = tempfile::tempdir().unwrap();
+        let cache = SharedBlockCache::new(dir.path()).unwrap();
+        let source = VecSource((0..20u8).map(|h| vec![h]).collect());
+
+        // A previous run got through height 4; height 7 was cached some other way
+        WarmState { start_height: 0, end_height: 9, next_height: 5 }.save(dir.path()).unwrap();
+        for height in 0..5 {
+            std::fs::write(cache.block_path(height), [height as u8]).unwrap();
+        }
+        std::fs::write(cache.block_path(7), [7]).unwrap();
+
+        let report = runtime.block_on(warm_cache(&cache, &source, 0, Some(9))).unwrap();
+        assert_eq!(report, WarmReport { written: 4, skipped: 6, bytes: 4 });
+        assert_eq!(std::fs::read(cache.block_path(9)).unwrap(), vec![9]);
+        assert_eq!(WarmState::load(dir.path()).unwrap().next_height, 10);
+
+        // Warm again: nothing to fetch; the default end is the source's tip
+        let report = runtime.block_on(warm_cache(&cache, &source, 0, Some(9))).unwrap();
+        assert_eq!(report.written, 0);
+        let report = runtime.block_on(warm_cache(&cache, &source, 5, None)).unwrap();
+        assert_eq!(report, WarmReport { written: 10, skipped: 5, bytes: 10 });
+    }
+
+    #[test]
+    fn test_format_eta() {
+        assert_eq!(format_eta(42.4), "42s");
+        assert_eq!(format_eta(750.0), "12m 30s");
+        assert_eq!(format_eta(3900.0), "1h 05m");
+        assert_eq!(format_eta(f64::INFINITY), "?");
+    }
+}
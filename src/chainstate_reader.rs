@@ -0,0 +1,136 @@
+//! Core Chainstate Reader
+//!
+//! Reads the UTXO set straight out of Core's `chainstate` LevelDB, so a run
+//! can start at Core's tip instead of replaying from genesis.
+//!
+//! Format (Core 0.15+): coins are stored per output under
+//! `'C' | txid | VARINT(vout)`, values are `VARINT(height * 2 + coinbase)`
+//! followed by a compressed amount and script, and every value is XORed with
+//! the key stored under `0x0e 0x00 "obfuscate_key"`. `'B'` holds the hash of
+//! the block the set is current at.
+//!
+//! LevelDB is locked while Core runs: stop Core, or point this at a copy of
+//! the directory taken while it was stopped.
+
+use anyhow::{Context, Result};
+use blvm_consensus::{OutPoint, UtxoSet};
+use rusty_leveldb::{LdbIterator, Options, DB};
+use std::path::Path;
+
+use crate::utxo_snapshot::{decode_coin, read_varint};
+
+const DB_COIN: u8 = b'C';
+const DB_BEST_BLOCK: u8 = b'B';
+const OBFUSCATE_KEY_KEY: &[u8] = b"\x0e\x00obfuscate_key";
+
+/// An open chainstate database
+pub struct ChainstateReader {
+    db: DB,
+    obfuscate_key: Vec<u8>,
+}
+
+impl ChainstateReader {
+    /// Open `<datadir>/chainstate` (or a copy of it)
+    pub fn open(dir: &Path) -> Result<Self> {
+        let options = Options {
+            create_if_missing: false,
+            ..Options::default()
+        };
+        let mut db = DB::open(dir, options).map_err(|e| {
+            anyhow::anyhow!(
+                "Failed to open chainstate {}: {} (stop Core or use a copy - LevelDB is locked while it runs)",
+                dir.display(),
+                e
+            )
+        })?;
+        // Stored as a length-prefixed byte vector; absent in very old datadirs
+        let obfuscate_key = match db.get(OBFUSCATE_KEY_KEY) {
+            Some(value) if !value.is_empty() => value[1..].to_vec(),
+            _ => Vec::new(),
+        };
+        Ok(Self { db, obfuscate_key })
+    }
+
+    fn deobfuscate(&self, value: &mut [u8]) {
+        if self.obfuscate_key.is_empty() {
+            return;
+        }
+        for (i, byte) in value.iter_mut().enumerate() {
+            *byte ^= self.obfuscate_key[i % self.obfuscate_key.len()];
+        }
+    }
+
+    /// Hash of the block the UTXO set is current at (internal byte order)
+    pub fn best_block(&mut self) -> Result<[u8; 32]> {
+        let mut value = self
+            .db
+            .get(&[DB_BEST_BLOCK])
+            .context("Chainstate has no best block (is this a chainstate directory?)")?;
+        self.deobfuscate(&mut value);
+        value
+            .as_slice()
+            .try_into()
+            .map_err(|_| anyhow::anyhow!("Best block entry is {} bytes, expected 32", value.len()))
+    }
+
+    /// Read every coin into a `UtxoSet`
+    pub fn read_utxo_set(&mut self) -> Result<UtxoSet> {
+        let mut iter = self
+            .db
+            .new_iter()
+            .map_err(|e| anyhow::anyhow!("Failed to iterate chainstate: {}", e))?;
+        iter.seek(&[DB_COIN]);
+
+        let mut utxo_set = UtxoSet::new();
+        let (mut key, mut value) = (Vec::new(), Vec::new());
+        while iter.valid() && iter.current(&mut key, &mut value) && key.first() == Some(&DB_COIN) {
+            let outpoint = decode_coin_key(&key)?;
+            self.deobfuscate(&mut value);
+            let utxo = decode_coin(&mut value.as_slice())
+                .with_context(|| format!("Corrupt coin {}:{}", hex::encode(outpoint.hash), outpoint.index))?;
+            utxo_set.insert(outpoint, utxo);
+            if utxo_set.len() % 10_000_000 == 0 {
+                println!("   📊 Read {} coins from chainstate", utxo_set.len());
+            }
+            iter.advance();
+        }
+        if utxo_set.is_empty() {
+            anyhow::bail!("No coins found in chainstate (pre-0.15 per-transaction format is not supported)");
+        }
+        Ok(utxo_set)
+    }
+}
+
+fn decode_coin_key(key: &[u8]) -> Result<OutPoint> {
+    let hash: [u8; 32] = key.get(1..33).context("Coin key too short")?.try_into()?;
+    let index = read_varint(&mut &key[33..])?;
+    Ok(OutPoint {
+        hash,
+        index: index as _,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decode_coin() {
+        // Genesis-era coinbase P2PKH coin at height 1, 50 BTC
+        let mut value = vec![0x03, 0x32, 0x00];
+        value.extend_from_slice(&[0xab; 20]);
+        let utxo = decode_coin(&mut value.as_slice()).unwrap();
+        assert!(utxo.is_coinbase);
+        assert_eq!(utxo.height as u64, 1);
+        assert_eq!(utxo.value as u64, 5_000_000_000);
+        let script: &[u8] = &utxo.script_pubkey;
+        assert_eq!(script.len(), 25);
+        assert_eq!(&script[..3], &[0x76, 0xa9, 0x14]);
+
+        assert_eq!(crate::utxo_snapshot::decompress_amount(0), 0);
+        assert_eq!(crate::utxo_snapshot::decompress_amount(1), 1);
+        assert_eq!(crate::utxo_snapshot::decompress_amount(0x09), 100_000_000);
+        assert_eq!(read_varint(&mut &[0x80, 0x00][..]).unwrap(), 128);
+        assert_eq!(decode_coin_key(&[&[b'C'][..], &[7u8; 32], &[0x81, 0x00]].concat()).unwrap().index as u64, 256);
+    }
+}
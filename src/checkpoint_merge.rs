@@ -0,0 +1,294 @@
+//! Two-Phase Checkpoint Generation
+//!
+//! The sequential pass connects every block in order, so generating
+//! checkpoints takes as long as validating the whole range on one core. The
+//! coins a range of blocks creates and spends don't depend on anything before
+//! it, though. Phase 1 scans every checkpoint segment on its own worker and
+//! records its delta: the outputs it leaves unspent and the older outpoints it
+//! spends. Phase 2 folds the deltas onto the running set in height order and
+//! saves a checkpoint after each one, a map merge per segment instead of a
+//! block-by-block replay. Wall time is about one segment's scan plus the merges.
+//!
+//! Blocks aren't run through BLVM on the way, so these checkpoints are the
+//! chain's own UTXO sets (with Core's rule of leaving provably unspendable
+//! outputs out). The chunks starting from them still validate every block, and
+//! a spend of a coin that doesn't exist at merge time fails generation.
+//! Chosen with `BLVM_CHECKPOINT_GENERATION` (`sequential`, the default, or
+//! `merged`).
+
+use anyhow::{Context, Result};
+use blvm_consensus::block::calculate_tx_id;
+use blvm_consensus::serialization::block::deserialize_block_with_witnesses;
+use blvm_consensus::transaction::is_coinbase;
+use blvm_consensus::{OutPoint, Transaction, UtxoSet, UTXO};
+use std::collections::{HashSet, VecDeque};
+use std::sync::Arc;
+
+use crate::checkpoint_store::CheckpointStore;
+use crate::parallel_differential::{source_tip, BlockDataSource};
+
+/// How UTXO checkpoints are produced
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum CheckpointGeneration {
+    /// One pass connecting every block with BLVM
+    #[default]
+    Sequential,
+    /// Per-segment deltas scanned in parallel, then folded in order
+    Merged,
+}
+
+impl CheckpointGeneration {
+    /// From `BLVM_CHECKPOINT_GENERATION` (`sequential` or `merged`)
+    pub fn from_env() -> Result<Self> {
+        match std::env::var("BLVM_CHECKPOINT_GENERATION").as_deref() {
+            Err(_) | Ok("sequential") => Ok(CheckpointGeneration::Sequential),
+            Ok("merged") => Ok(CheckpointGeneration::Merged),
+            Ok(other) => anyhow::bail!(
+                "Invalid BLVM_CHECKPOINT_GENERATION '{}' (expected sequential or merged)",
+                other
+            ),
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            CheckpointGeneration::Sequential => "sequential",
+            CheckpointGeneration::Merged => "merged",
+        }
+    }
+}
+
+/// Coins a range of blocks creates and consumes, independent of what came before it
+#[derive(Debug, Default)]
+pub struct UtxoDelta {
+    /// Outputs created in the range and still unspent at its end
+    pub created: UtxoSet,
+    /// Outpoints created before the range and spent in it
+    pub spent: HashSet<OutPoint>,
+}
+
+impl UtxoDelta {
+    /// Record one block's transactions (in block order)
+    pub fn add_transactions(&mut self, transactions: &[Transaction], height: u64) {
+        for tx in transactions {
+            let coinbase = is_coinbase(tx);
+            if !coinbase {
+                for input in tx.inputs.iter() {
+                    // A coin created earlier in the range just disappears
+                    if self.created.remove(&input.prevout).is_none() {
+                        self.spent.insert(input.prevout.clone());
+                    }
+                }
+            }
+            let txid = calculate_tx_id(tx);
+            for (index, output) in tx.outputs.iter().enumerate() {
+                if is_unspendable(&output.script_pubkey) {
+                    continue;
+                }
+                self.created.insert(
+                    OutPoint {
+                        hash: txid,
+                        index: index as _,
+                    },
+                    UTXO {
+                        value: output.value,
+                        script_pubkey: output.script_pubkey.clone(),
+                        height: height as _,
+                        is_coinbase: coinbase,
+                    },
+                );
+            }
+        }
+    }
+
+    /// Fold onto the UTXO set at the end of the previous range
+    pub fn apply(self, utxo_set: &mut UtxoSet) -> Result<()> {
+        for outpoint in &self.spent {
+            if utxo_set.remove(outpoint).is_none() {
+                let mut txid = outpoint.hash;
+                txid.reverse();
+                anyhow::bail!("Spends {}:{}, which no earlier block created", hex::encode(txid), outpoint.index);
+            }
+        }
+        // Inserting last lets a BIP30 duplicate coinbase overwrite the older coin, as in Core
+        for (outpoint, utxo) in self.created {
+            utxo_set.insert(outpoint, utxo);
+        }
+        Ok(())
+    }
+}
+
+/// Core's `MAX_SCRIPT_SIZE`: longer scripts are unspendable
+pub const MAX_SCRIPT_SIZE: usize = 10_000;
+
+/// Core's `IsUnspendable`: OP_RETURN outputs and scripts over the size limit never enter the set
+pub fn is_unspendable(script_pubkey: &[u8]) -> bool {
+    script_pubkey.first() == Some(&0x6a) || script_pubkey.len() > MAX_SCRIPT_SIZE
+}
+
+/// Ranges between consecutive checkpoints in `start..=end`, the last ending at `end`
+pub fn segments(start: u64, end: u64, checkpoint_heights: &[u64]) -> Vec<(u64, u64)> {
+    let mut segments = Vec::new();
+    let mut current = start;
+    for &height in checkpoint_heights.iter().filter(|h| (start..end).contains(*h)) {
+        segments.push((current, height));
+        current = height + 1;
+    }
+    if current <= end {
+        segments.push((current, end));
+    }
+    segments
+}
+
+/// Phase 1 for one segment
+async fn scan_segment(block_source: Arc<BlockDataSource>, start: u64, end: u64) -> Result<UtxoDelta> {
+    let mut delta = UtxoDelta::default();
+    crate::block_source::for_each_block(block_source.as_ref(), start, end, |height, block_bytes| {
+        let (block, _) = deserialize_block_with_witnesses(&block_bytes)
+            .with_context(|| format!("Failed to deserialize block {}", height))?;
+        delta.add_transactions(&block.transactions, height);
+        Ok(())
+    })
+    .await?;
+    Ok(delta)
+}
+
+/// Generate checkpoints at `checkpoint_heights` (and the end of the range) from merged deltas
+///
+/// Up to `workers` segments are scanned at once; deltas are folded as soon as
+/// every segment before them is, so at most `workers` are held in memory.
+pub async fn generate_checkpoints_merged(
+    start_height: u64,
+    end_height: u64,
+    checkpoint_heights: &[u64],
+    block_source: &Arc<BlockDataSource>,
+    workers: usize,
+    store: &mut CheckpointStore,
+) -> Result<Vec<u64>> {
+    let chain_height = source_tip(block_source, end_height).await?;
+    let actual_end = end_height.min(chain_height);
+    let segments = segments(start_height, actual_end, checkpoint_heights);
+    store.reset(start_height)?;
+    println!(
+        "🔧 Generating {} UTXO checkpoints from {} to {} ({} segments scanned {} at a time, then merged)",
+        checkpoint_heights.len(),
+        start_height,
+        actual_end,
+        segments.len(),
+        workers.max(1)
+    );
+
+    let started = std::time::Instant::now();
+    let mut queued = segments.into_iter();
+    let mut scanning = VecDeque::new();
+    let mut utxo_set = UtxoSet::new();
+    let mut checkpoints = Vec::new();
+    loop {
+        while scanning.len() < workers.max(1) {
+            let Some((start, end)) = queued.next() else { break };
+            let handle = tokio::spawn(scan_segment(block_source.clone(), start, end));
+            scanning.push_back((start, end, handle));
+        }
+        let Some((start, end, handle)) = scanning.pop_front() else { break };
+        let delta = handle
+            .await
+            .with_context(|| format!("Scan of blocks {}-{} panicked", start, end))?
+            .with_context(|| format!("Failed to scan blocks {}-{}", start, end))?;
+        let (created, spent) = (delta.created.len(), delta.spent.len());
+        delta
+            .apply(&mut utxo_set)
+            .with_context(|| format!("Failed to merge blocks {}-{}", start, end))?;
+        println!(
+            "✅ Checkpoint at height {} (UTXO count: {}, +{} -{})",
+            end,
+            utxo_set.len(),
+            created,
+            spent
+        );
+        store.save(end, utxo_set.clone())?;
+        checkpoints.push(end);
+    }
+    println!("⏱️  Merged checkpoints in {:.1}s", started.elapsed().as_secs_f64());
+    Ok(checkpoints)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use blvm_consensus::{TransactionInput, TransactionOutput};
+
+    fn tx(spends: &[OutPoint], outputs: &[&[u8]]) -> Transaction {
+        let coinbase_input = [OutPoint {
+            hash: [0; 32],
+            index: 0xffffffff,
+        }];
+        let spends = if spends.is_empty() { &coinbase_input[..] } else { spends };
+        Transaction {
+            version: 1,
+            inputs: spends
+                .iter()
+                .map(|prevout| TransactionInput {
+                    prevout: prevout.clone(),
+                    script_sig: vec![0x51],
+                    sequence: 0xffffffff,
+                })
+                .collect(),
+            outputs: outputs
+                .iter()
+                .map(|script| TransactionOutput {
+                    value: 1_000,
+                    script_pubkey: script.to_vec(),
+                })
+                .collect(),
+            lock_time: 0,
+        }
+    }
+
+    fn outpoint(tx: &Transaction, index: u32) -> OutPoint {
+        OutPoint {
+            hash: calculate_tx_id(tx),
+            index: index as _,
+        }
+    }
+
+    #[test]
+    fn test_segments_end_at_each_checkpoint() {
+        assert_eq!(segments(0, 250, &[99, 199]), vec![(0, 99), (100, 199), (200, 250)]);
+        assert_eq!(segments(0, 199, &[99, 199]), vec![(0, 99), (100, 199)]);
+        assert_eq!(segments(100, 150, &[99, 199]), vec![(100, 150)]);
+    }
+
+    #[test]
+    fn test_merged_deltas_match_sequential_replay() {
+        let coinbase_a = tx(&[], &[&[0x51], &[0x6a, 0x01]]);
+        let coinbase_b = tx(&[], &[&[0x52]]);
+        let spend_b = tx(&[outpoint(&coinbase_b, 0)], &[&[0x53], &[0x54]]);
+        let spend_a = tx(&[outpoint(&coinbase_a, 0), outpoint(&spend_b, 0)], &[&[0x55]]);
+
+        // Block 1 in the first segment; blocks 2 and 3 in the second
+        let mut first = UtxoDelta::default();
+        first.add_transactions(std::slice::from_ref(&coinbase_a), 1);
+        let mut second = UtxoDelta::default();
+        second.add_transactions(&[coinbase_b.clone(), spend_b.clone()], 2);
+        second.add_transactions(&[spend_a.clone()], 3);
+
+        // The OP_RETURN output never exists; spend_b's first output lives and dies in the segment
+        assert_eq!(first.created.len(), 1);
+        assert_eq!(second.spent, HashSet::from([outpoint(&coinbase_a, 0)]));
+
+        let mut utxo_set = UtxoSet::new();
+        first.apply(&mut utxo_set).unwrap();
+        second.apply(&mut utxo_set).unwrap();
+        let mut coins: Vec<OutPoint> = utxo_set.iter().map(|(outpoint, _)| outpoint.clone()).collect();
+        coins.sort_by_key(|o| (o.hash, o.index));
+        let mut expected = vec![outpoint(&spend_b, 1), outpoint(&spend_a, 0)];
+        expected.sort_by_key(|o| (o.hash, o.index));
+        assert_eq!(coins, expected);
+        assert_eq!(utxo_set.get(&outpoint(&spend_a, 0)).unwrap().height, 3);
+
+        // Merging out of order spends a coin that doesn't exist yet
+        let mut orphan = UtxoDelta::default();
+        orphan.add_transactions(&[spend_a], 3);
+        assert!(orphan.apply(&mut UtxoSet::new()).is_err());
+    }
+}
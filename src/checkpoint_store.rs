@@ -0,0 +1,419 @@
+//! On-Disk UTXO Checkpoints
+//!
+//! Checkpoint generation used to keep a full `UtxoSet` per chunk boundary,
+//! which near the tip means tens of GB for a handful of checkpoints.
+//! `CheckpointStore` writes a zstd-compressed base snapshot followed by
+//! per-checkpoint deltas (spent outpoints plus new coins); a fresh base is
+//! written every `base_interval` checkpoints so loading a mid-chain
+//! checkpoint replays at most that many deltas.
+//!
+//! The store is kept between runs: a run with the same start height whose
+//! chunk boundaries are all covered skips the sequential checkpoint pass.
+//!
+//! Layout: `manifest.json`, `base-<height>.zst`, `delta-<height>.zst`.
+
+use crate::utxo_backend::{decode_utxo, encode_utxo};
+use anyhow::{Context, Result};
+use blvm_consensus::{OutPoint, UtxoSet};
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+
+/// Checkpoints between full snapshots by default
+pub const DEFAULT_BASE_INTERVAL: usize = 10;
+
+/// zstd level: fast enough to keep up with generation, ~3-4x on UTXO data
+const ZSTD_LEVEL: i32 = 3;
+
+/// Checkpoint directory from `BLVM_CHECKPOINT_DIR`
+pub fn default_checkpoint_dir() -> PathBuf {
+    std::env::var("BLVM_CHECKPOINT_DIR")
+        .map(PathBuf::from)
+        .unwrap_or_else(|_| PathBuf::from("checkpoints"))
+}
+
+/// Base snapshot interval from `BLVM_CHECKPOINT_BASE_INTERVAL`
+pub fn base_interval_from_env() -> Result<usize> {
+    match std::env::var("BLVM_CHECKPOINT_BASE_INTERVAL") {
+        Ok(interval) => match interval.parse() {
+            Ok(0) | Err(_) => anyhow::bail!("Invalid BLVM_CHECKPOINT_BASE_INTERVAL '{}'", interval),
+            Ok(interval) => Ok(interval),
+        },
+        Err(_) => Ok(DEFAULT_BASE_INTERVAL),
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CheckpointKind {
+    Base,
+    Delta,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CheckpointEntry {
+    pub height: u64,
+    pub kind: CheckpointKind,
+    pub utxo_count: usize,
+    /// Compressed size on disk
+    pub bytes: u64,
+}
+
+impl CheckpointEntry {
+    fn file_name(&self) -> String {
+        match self.kind {
+            CheckpointKind::Base => format!("base-{}.zst", self.height),
+            CheckpointKind::Delta => format!("delta-{}.zst", self.height),
+        }
+    }
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct Manifest {
+    /// Height generation started from (with an empty UTXO set)
+    start_height: u64,
+    entries: Vec<CheckpointEntry>,
+}
+
+/// Delta-encoded, compressed checkpoint store
+pub struct CheckpointStore {
+    dir: PathBuf,
+    base_interval: usize,
+    manifest: Manifest,
+    /// Set at the last saved checkpoint, to diff the next one against
+    last: Option<UtxoSet>,
+}
+
+impl CheckpointStore {
+    /// Open an existing store (or an empty one if `dir` has no manifest)
+    pub fn open(dir: &Path, base_interval: usize) -> Result<Self> {
+        let manifest_path = dir.join("manifest.json");
+        let manifest = if manifest_path.exists() {
+            let data = std::fs::read(&manifest_path)
+                .with_context(|| format!("Failed to read {}", manifest_path.display()))?;
+            serde_json::from_slice(&data).with_context(|| format!("Corrupt checkpoint manifest {}", manifest_path.display()))?
+        } else {
+            Manifest::default()
+        };
+        Ok(Self {
+            dir: dir.to_path_buf(),
+            base_interval: base_interval.max(1),
+            manifest,
+            last: None,
+        })
+    }
+
+    /// Discard any stored checkpoints and start a new generation at `start_height`
+    pub fn reset(&mut self, start_height: u64) -> Result<()> {
+        std::fs::create_dir_all(&self.dir)
+            .with_context(|| format!("Failed to create checkpoint directory {}", self.dir.display()))?;
+        for entry in &self.manifest.entries {
+            let _ = std::fs::remove_file(self.dir.join(entry.file_name()));
+        }
+        self.manifest = Manifest {
+            start_height,
+            entries: Vec::new(),
+        };
+        self.last = None;
+        self.write_manifest()
+    }
+
+    /// Height the stored generation started from
+    pub fn start_height(&self) -> u64 {
+        self.manifest.start_height
+    }
+
+    pub fn entries(&self) -> &[CheckpointEntry] {
+        &self.manifest.entries
+    }
+
+    /// Whether a generation from `start_height` stored all of `heights`
+    pub fn covers(&self, start_height: u64, heights: &[u64]) -> bool {
+        !self.manifest.entries.is_empty()
+            && self.manifest.start_height == start_height
+            && heights
+                .iter()
+                .all(|h| self.manifest.entries.iter().any(|e| e.height == *h))
+    }
+
+    /// Compressed bytes on disk
+    pub fn disk_usage(&self) -> u64 {
+        self.manifest.entries.iter().map(|e| e.bytes).sum()
+    }
+
+    /// Store the UTXO set at `height` (heights must increase)
+    pub fn save(&mut self, height: u64, utxo_set: UtxoSet) -> Result<()> {
+        if let Some(last) = self.manifest.entries.last() {
+            if height <= last.height {
+                anyhow::bail!("Checkpoint at height {} is not after {}", height, last.height);
+            }
+        }
+        let since_base = self
+            .manifest
+            .entries
+            .iter()
+            .rev()
+            .take_while(|e| e.kind == CheckpointKind::Delta)
+            .count();
+        let kind = match &self.last {
+            Some(_) if since_base + 1 < self.base_interval => CheckpointKind::Delta,
+            _ => CheckpointKind::Base,
+        };
+        let mut entry = CheckpointEntry {
+            height,
+            kind,
+            utxo_count: utxo_set.len(),
+            bytes: 0,
+        };
+        let path = self.dir.join(entry.file_name());
+        let file = File::create(&path).with_context(|| format!("Failed to create {}", path.display()))?;
+        let mut out = zstd::stream::Encoder::new(BufWriter::new(file), ZSTD_LEVEL)?;
+        match (kind, &self.last) {
+            (CheckpointKind::Delta, Some(last)) => write_delta(&mut out, last, &utxo_set)?,
+            _ => write_coins(&mut out, utxo_set.len(), utxo_set.iter())?,
+        }
+        out.finish()?.flush()?;
+        entry.bytes = std::fs::metadata(&path)?.len();
+
+        self.manifest.entries.push(entry);
+        self.last = Some(utxo_set);
+        self.write_manifest()
+    }
+
+    /// Reconstruct the UTXO set at `height` (last base plus deltas)
+    pub fn load(&self, height: u64) -> Result<UtxoSet> {
+        self.replay().advance_to(height)
+    }
+
+    /// Sequential loader: reconstructs increasing heights without re-reading the base
+    pub fn replay(&self) -> Replay<'_> {
+        Replay {
+            store: self,
+            next: 0,
+            current: UtxoSet::new(),
+        }
+    }
+
+    fn write_manifest(&self) -> Result<()> {
+        let path = self.dir.join("manifest.json");
+        std::fs::write(&path, serde_json::to_vec_pretty(&self.manifest)?)
+            .with_context(|| format!("Failed to write {}", path.display()))
+    }
+
+    fn open_entry(&self, entry: &CheckpointEntry) -> Result<impl Read> {
+        let path = self.dir.join(entry.file_name());
+        let file = File::open(&path).with_context(|| format!("Failed to open {}", path.display()))?;
+        Ok(zstd::stream::Decoder::new(file)?)
+    }
+}
+
+/// Walks the manifest forward, applying bases and deltas
+pub struct Replay<'a> {
+    store: &'a CheckpointStore,
+    next: usize,
+    current: UtxoSet,
+}
+
+impl Replay<'_> {
+    /// UTXO set at `height`, which must not be before the last one returned
+    pub fn advance_to(&mut self, height: u64) -> Result<UtxoSet> {
+        let entries = &self.store.manifest.entries;
+        let target = entries
+            .iter()
+            .position(|e| e.height == height)
+            .with_context(|| format!("No checkpoint at height {}", height))?;
+        if target + 1 < self.next {
+            anyhow::bail!("Checkpoint replay is past height {}", height);
+        }
+        // Jump to the newest base at or before the target
+        if let Some(base) = entries[self.next..=target].iter().rposition(|e| e.kind == CheckpointKind::Base) {
+            self.next += base;
+        }
+        while self.next <= target {
+            let entry = &entries[self.next];
+            let mut reader = BufReader::new(self.store.open_entry(entry)?);
+            match entry.kind {
+                CheckpointKind::Base => {
+                    self.current = UtxoSet::new();
+                    read_coins(&mut reader, &mut self.current)?;
+                }
+                CheckpointKind::Delta => {
+                    for _ in 0..read_u64(&mut reader)? {
+                        self.current.remove(&read_outpoint(&mut reader)?);
+                    }
+                    read_coins(&mut reader, &mut self.current)?;
+                }
+            }
+            if self.current.len() != entry.utxo_count {
+                anyhow::bail!(
+                    "Checkpoint {} has {} coins, expected {}",
+                    entry.height,
+                    self.current.len(),
+                    entry.utxo_count
+                );
+            }
+            self.next += 1;
+        }
+        Ok(self.current.clone())
+    }
+}
+
+/// BLVM's UTXO state just before `height`: the nearest checkpoint below it, plus the blocks in between
+///
+/// Returns the store and the checkpoint height, or None if no checkpoint is
+/// usable (only a store generated from genesis holds every older coin).
+pub async fn utxo_store_before(
+    height: u64,
+    block_source: &crate::parallel_differential::BlockDataSource,
+    checkpoints: &CheckpointStore,
+    utxo_backend: &crate::utxo_backend::UtxoBackend,
+) -> Result<Option<(Box<dyn crate::utxo_backend::UtxoStore>, u64)>> {
+    use blvm_consensus::serialization::block::deserialize_block_with_witnesses;
+    use blvm_consensus::types::{Network, ValidationResult};
+
+    if checkpoints.start_height() != 0 {
+        return Ok(None);
+    }
+    let Some(checkpoint) = checkpoints.entries().iter().map(|e| e.height).filter(|&h| h < height).max() else {
+        return Ok(None);
+    };
+    let mut utxo_store = utxo_backend.create(checkpoints.load(checkpoint)?)?;
+    if checkpoint + 1 < height {
+        println!("🔧 Replaying blocks {} to {} on top of checkpoint {}", checkpoint + 1, height - 1, checkpoint);
+        crate::block_source::for_each_block(block_source, checkpoint + 1, height - 1, |replay_height, block_bytes| {
+            let (block, witnesses) = deserialize_block_with_witnesses(&block_bytes)
+                .map_err(|e| anyhow::anyhow!("Failed to deserialize block {}: {}", replay_height, e))?;
+            match utxo_store.connect_block(&block, &witnesses, replay_height, Network::Mainnet)? {
+                ValidationResult::Valid => Ok(()),
+                ValidationResult::Invalid(msg) => {
+                    anyhow::bail!("BLVM rejected block {} while replaying up to {}: {}", replay_height, height, msg)
+                }
+            }
+        })
+        .await?;
+    }
+    Ok(Some((utxo_store, checkpoint)))
+}
+
+/// Delta layout: spent count (u64) | outpoints, then coins as in a base
+fn write_delta(out: &mut impl Write, previous: &UtxoSet, current: &UtxoSet) -> Result<()> {
+    let spent: Vec<&OutPoint> = previous
+        .iter()
+        .filter(|(outpoint, _)| current.get(outpoint).is_none())
+        .map(|(outpoint, _)| outpoint)
+        .collect();
+    out.write_all(&(spent.len() as u64).to_le_bytes())?;
+    for outpoint in spent {
+        write_outpoint(out, outpoint)?;
+    }
+    let added: Vec<_> = current
+        .iter()
+        .filter(|(outpoint, utxo)| match previous.get(outpoint) {
+            Some(old) => encode_utxo(old) != encode_utxo(utxo),
+            None => true,
+        })
+        .collect();
+    write_coins(out, added.len(), added.into_iter())
+}
+
+/// Coin list layout: count (u64) | outpoint, record length (u32), `encode_utxo` record
+fn write_coins<'a>(
+    out: &mut impl Write,
+    count: usize,
+    coins: impl Iterator<Item = (&'a OutPoint, &'a blvm_consensus::UTXO)>,
+) -> Result<()> {
+    out.write_all(&(count as u64).to_le_bytes())?;
+    for (outpoint, utxo) in coins {
+        write_outpoint(out, outpoint)?;
+        let record = encode_utxo(utxo);
+        out.write_all(&(record.len() as u32).to_le_bytes())?;
+        out.write_all(&record)?;
+    }
+    Ok(())
+}
+
+fn read_coins(reader: &mut impl Read, set: &mut UtxoSet) -> Result<()> {
+    let mut record = Vec::new();
+    for _ in 0..read_u64(reader)? {
+        let outpoint = read_outpoint(reader)?;
+        let mut len = [0u8; 4];
+        reader.read_exact(&mut len)?;
+        record.resize(u32::from_le_bytes(len) as usize, 0);
+        reader.read_exact(&mut record)?;
+        set.insert(outpoint, decode_utxo(&record)?);
+    }
+    Ok(())
+}
+
+fn write_outpoint(out: &mut impl Write, outpoint: &OutPoint) -> Result<()> {
+    out.write_all(&outpoint.hash)?;
+    out.write_all(&(outpoint.index as u64).to_le_bytes())?;
+    Ok(())
+}
+
+fn read_outpoint(reader: &mut impl Read) -> Result<OutPoint> {
+    let mut hash = [0u8; 32];
+    reader.read_exact(&mut hash)?;
+    Ok(OutPoint {
+        hash,
+        index: read_u64(reader)? as _,
+    })
+}
+
+fn read_u64(reader: &mut impl Read) -> Result<u64> {
+    let mut buf = [0u8; 8];
+    reader.read_exact(&mut buf)?;
+    Ok(u64::from_le_bytes(buf))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use blvm_consensus::UTXO;
+
+    fn coin(value: i64) -> UTXO {
+        UTXO {
+            value: value as _,
+            script_pubkey: vec![0x51].into(),
+            height: 1,
+            is_coinbase: false,
+        }
+    }
+
+    fn outpoint(n: u8) -> OutPoint {
+        OutPoint { hash: [n; 32], index: 0 }
+    }
+
+    #[test]
+    fn test_base_and_delta_round_trip() {
+        let dir = std::env::temp_dir().join(format!("blvm-checkpoints-{}", std::process::id()));
+        let mut store = CheckpointStore::open(&dir, 2).unwrap();
+        store.reset(0).unwrap();
+
+        let mut set = UtxoSet::new();
+        set.insert(outpoint(1), coin(10));
+        set.insert(outpoint(2), coin(20));
+        store.save(99, set.clone()).unwrap();
+        set.remove(&outpoint(1));
+        set.insert(outpoint(3), coin(30));
+        store.save(199, set.clone()).unwrap();
+        set.insert(outpoint(4), coin(40));
+        store.save(299, set.clone()).unwrap();
+
+        let kinds: Vec<CheckpointKind> = store.entries().iter().map(|e| e.kind).collect();
+        assert_eq!(kinds, vec![CheckpointKind::Base, CheckpointKind::Delta, CheckpointKind::Base]);
+
+        let reopened = CheckpointStore::open(&dir, 2).unwrap();
+        assert!(reopened.covers(0, &[99, 199]));
+        assert!(!reopened.covers(5, &[99]));
+        let at_199 = reopened.load(199).unwrap();
+        assert_eq!(at_199.len(), 2);
+        assert!(at_199.get(&outpoint(1)).is_none());
+        assert!(at_199.get(&outpoint(3)).is_some());
+        let mut replay = reopened.replay();
+        assert_eq!(replay.advance_to(99).unwrap().len(), 2);
+        assert_eq!(replay.advance_to(299).unwrap().len(), 3);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
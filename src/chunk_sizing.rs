@@ -0,0 +1,198 @@
+//! Per-Era Chunk Sizing
+//!
+//! Early blocks validate thousands per second while post-segwit blocks take
+//! orders of magnitude longer, so equal-height chunks leave most workers idle
+//! while the last few grind through modern blocks. Weighted sizing keeps the
+//! number of chunks an equal split would give but places the boundaries so
+//! each chunk carries about the same estimated work: long chunks early,
+//! shorter after segwit, shorter still after taproot.
+//!
+//! Work per block comes from a cost model: per-era blocks/sec of the latest
+//! revision in `BLVM_RESULTS_DB` when it has history (`results-db` feature),
+//! else built-in ballpark rates. Chosen with `BLVM_CHUNK_SIZING`
+//! (`equal`, the default, or `weighted`). Stored checkpoints are per chunk
+//! boundary, so switching sizing regenerates them.
+
+use anyhow::Result;
+
+use crate::sampler::Era;
+
+/// Ballpark per-worker blocks/sec by era, when there is no history to go on
+pub const DEFAULT_BLOCKS_PER_SEC: [(Era, f64); 4] = [
+    (Era::PreBip34, 1_500.0),
+    (Era::PreSegwit, 60.0),
+    (Era::Segwit, 15.0),
+    (Era::Taproot, 10.0),
+];
+
+/// Estimated per-worker validation speed by era
+#[derive(Debug, Clone, PartialEq)]
+pub struct CostModel {
+    /// Blocks/sec for every era
+    pub rates: Vec<(Era, f64)>,
+    /// Where the rates came from
+    pub source: String,
+}
+
+impl CostModel {
+    pub fn builtin() -> Self {
+        Self {
+            rates: DEFAULT_BLOCKS_PER_SEC.to_vec(),
+            source: "built-in ballpark".to_string(),
+        }
+    }
+
+    /// Rates of the latest revision in `BLVM_RESULTS_DB` (eras it never ran keep the built-in rate)
+    #[cfg(feature = "results-db")]
+    pub fn measured() -> Option<Self> {
+        let path = std::env::var("BLVM_RESULTS_DB").ok()?;
+        let history = crate::results_db::ResultsDb::open(&path).ok()?.perf_history(None).ok()?;
+        let latest = history.last()?;
+        let rates = DEFAULT_BLOCKS_PER_SEC
+            .iter()
+            .map(|(era, default)| {
+                let bps = latest.eras.iter().find(|(name, _)| name == era.name()).map(|(_, bps)| *bps);
+                (*era, bps.filter(|bps| *bps > 0.0).unwrap_or(*default))
+            })
+            .collect();
+        Some(Self {
+            rates,
+            source: format!("history of {} in {}", latest.revision, path),
+        })
+    }
+
+    #[cfg(not(feature = "results-db"))]
+    pub fn measured() -> Option<Self> {
+        None
+    }
+
+    /// Measured rates if there are any, else built-in
+    pub fn best_available() -> Self {
+        Self::measured().unwrap_or_else(Self::builtin)
+    }
+
+    pub fn blocks_per_sec(&self, era: Era) -> f64 {
+        self.rates
+            .iter()
+            .find(|(e, _)| *e == era)
+            .map(|(_, bps)| *bps)
+            .unwrap_or(1.0)
+            .max(f64::EPSILON)
+    }
+
+    /// Estimated single-worker seconds to validate `start..=end`
+    pub fn range_secs(&self, start: u64, end: u64) -> f64 {
+        Era::ALL
+            .iter()
+            .map(|era| {
+                let (lo, hi) = era.heights();
+                let (lo, hi) = (lo.max(start), hi.min(end));
+                if lo > hi {
+                    0.0
+                } else {
+                    (hi - lo + 1) as f64 / self.blocks_per_sec(*era)
+                }
+            })
+            .sum()
+    }
+}
+
+/// How a height range is cut into chunks
+#[derive(Debug, Clone, PartialEq, Default)]
+pub enum ChunkSizing {
+    /// Every chunk `chunk_size` blocks (the last may be shorter)
+    #[default]
+    Equal,
+    /// As many chunks as `Equal` would make, of about equal estimated work
+    Weighted(CostModel),
+}
+
+impl ChunkSizing {
+    /// From `BLVM_CHUNK_SIZING` (`equal` or `weighted`)
+    pub fn from_env() -> Result<Self> {
+        match std::env::var("BLVM_CHUNK_SIZING").as_deref() {
+            Err(_) | Ok("equal") => Ok(ChunkSizing::Equal),
+            Ok("weighted") => Ok(ChunkSizing::Weighted(CostModel::best_available())),
+            Ok(other) => anyhow::bail!("Invalid BLVM_CHUNK_SIZING '{}' (expected equal or weighted)", other),
+        }
+    }
+
+    pub fn describe(&self) -> String {
+        match self {
+            ChunkSizing::Equal => "equal heights".to_string(),
+            ChunkSizing::Weighted(model) => format!("weighted by era cost ({})", model.source),
+        }
+    }
+}
+
+/// Split `start..=end` into chunk ranges
+pub fn chunk_ranges(start: u64, end: u64, chunk_size: u64, sizing: &ChunkSizing) -> Vec<(u64, u64)> {
+    let chunk_size = chunk_size.max(1);
+    if end < start {
+        return Vec::new();
+    }
+    let mut ranges = Vec::new();
+    match sizing {
+        ChunkSizing::Equal => {
+            let mut current = start;
+            while current <= end {
+                let chunk_end = current.saturating_add(chunk_size - 1).min(end);
+                ranges.push((current, chunk_end));
+                current = chunk_end + 1;
+            }
+        }
+        ChunkSizing::Weighted(model) => {
+            let count = (end - start) / chunk_size + 1;
+            let target = model.range_secs(start, end) / count as f64;
+            let mut current = start;
+            let mut cost = 0.0;
+            for height in start..=end {
+                cost += 1.0 / model.blocks_per_sec(Era::of(height));
+                // Leave the remainder to the final chunk once the others are cut
+                if cost >= target && height < end && (ranges.len() as u64) < count - 1 {
+                    ranges.push((current, height));
+                    current = height + 1;
+                    cost = 0.0;
+                }
+            }
+            ranges.push((current, end));
+        }
+    }
+    ranges
+}
+
+/// Heights whose UTXO set the chunks after the first start from (each chunk's predecessor's end)
+pub fn checkpoint_heights(ranges: &[(u64, u64)]) -> Vec<u64> {
+    ranges.iter().skip(1).map(|(start, _)| start - 1).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_equal_ranges() {
+        assert_eq!(chunk_ranges(0, 250, 100, &ChunkSizing::Equal), vec![(0, 99), (100, 199), (200, 250)]);
+        assert_eq!(checkpoint_heights(&chunk_ranges(0, 250, 100, &ChunkSizing::Equal)), vec![99, 199]);
+    }
+
+    #[test]
+    fn test_weighted_ranges_balance_cost() {
+        let model = CostModel::builtin();
+        let (taproot_start, _) = Era::Taproot.heights();
+        let end = taproot_start + 99_999;
+        let ranges = chunk_ranges(0, end, 100_000, &ChunkSizing::Weighted(model.clone()));
+
+        // Same chunk count as an equal split, contiguous and covering the range
+        assert_eq!(ranges.len(), chunk_ranges(0, end, 100_000, &ChunkSizing::Equal).len());
+        assert_eq!((ranges[0].0, ranges.last().unwrap().1), (0, end));
+        assert!(ranges.windows(2).all(|pair| pair[1].0 == pair[0].1 + 1));
+
+        // Early chunks are long, taproot-era chunks short, and costs are close
+        let lengths: Vec<u64> = ranges.iter().map(|(s, e)| e - s + 1).collect();
+        assert!(lengths[0] > 100_000 && *lengths.last().unwrap() < 100_000, "{:?}", lengths);
+        let costs: Vec<f64> = ranges.iter().map(|(s, e)| model.range_secs(*s, *e)).collect();
+        let target = model.range_secs(0, end) / ranges.len() as f64;
+        assert!(costs.iter().all(|c| (c - target).abs() / target < 0.05), "{:?}", costs);
+    }
+}
@@ -1,11 +1,18 @@
 //! Chunked and compressed cache support
 //!
-//! Handles reading from chunked, compressed cache files created by split_and_compress_cache.sh
-//! Format: Multiple files like chunk_0.bin.zst, chunk_1.bin.zst, etc.
+//! Handles reading and writing chunked, compressed cache files (`ChunkedCacheWriter`,
+//! formerly split_and_compress_cache.sh)
+//! Format: Multiple files like chunk_0.bin.zst, chunk_1.bin.zst, etc., each a sequence
+//! of zstd frames over u32 LE length-prefixed blocks, plus a chunks.meta file
 
 use anyhow::{Context, Result};
 use std::path::{Path, PathBuf};
 
+use crate::block_hash::block_hash;
+
+/// zstd level for chunks written by repair/update
+pub const DEFAULT_ZSTD_LEVEL: i32 = 3;
+
 /// Chunk metadata
 #[derive(Debug, Clone)]
 pub struct ChunkMetadata {
@@ -169,94 +176,155 @@ pub fn load_chunked_cache(
     };
 
     let start_chunk = start_idx / metadata.blocks_per_chunk as usize;
-    let end_chunk = (end_idx - 1) / metadata.blocks_per_chunk as usize;
+    let end_chunk = end_idx.saturating_sub(1) / metadata.blocks_per_chunk as usize;
 
     println!("   Loading chunks {}-{} (blocks {}-{})", 
              start_chunk, end_chunk, start_idx, end_idx);
 
-    // OPTIMIZATION: Stream blocks from chunks instead of loading entire chunks into memory
-    // For 50-60GB compressed chunks, this prevents loading 200GB+ into RAM
-    let mut all_blocks = Vec::new();
-    for chunk_num in start_chunk..=end_chunk.min(metadata.num_chunks - 1) {
-        let chunk_file = chunks_dir.join(format!("chunk_{}.bin.zst", chunk_num));
-        
-        if !chunk_file.exists() {
-            eprintln!("   ⚠️  Chunk {} not found: {}", chunk_num, chunk_file.display());
-            continue;
+    // OPTIMIZATION: Stream blocks from chunks instead of loading entire chunks into memory,
+    // decompressing the next chunks on background threads while this one is consumed
+    let wanted = end_idx.saturating_sub(start_idx);
+    let mut all_blocks = Vec::with_capacity(wanted);
+    let stream = ChunkBlockStream::new(
+        chunks_dir,
+        &metadata,
+        start_idx as u64,
+        end_idx as u64,
+        chunk_decompress_threads(),
+    );
+    for item in stream {
+        let (_, block_data) = item?;
+        all_blocks.push(block_data);
+
+        // OPTIMIZATION: Reduce progress reporting frequency (less I/O overhead)
+        if all_blocks.len() % 25000 == 0 {
+            println!("     Loaded {}/{} blocks...", all_blocks.len(), wanted);
         }
+    }
 
-        println!("   📦 Streaming blocks from chunk {}...", chunk_num);
-        
-        // OPTIMIZATION: Stream decompression instead of loading entire chunk
-        use std::io::{BufReader, Read};
-        use std::process::{Command, Stdio};
-        
-        let mut zstd_proc = Command::new("zstd")
-            .arg("-d")
-            .arg("--stdout")
-            .arg(&chunk_file)
-            .stdout(Stdio::piped())
-            .stderr(Stdio::piped())
-            .spawn()
-            .with_context(|| format!("Failed to start zstd for chunk {}", chunk_num))?;
-        
-        let mut reader = BufReader::with_capacity(128 * 1024 * 1024, // 128MB buffer
-            zstd_proc.stdout.take()
-                .ok_or_else(|| anyhow::anyhow!("Failed to get zstd stdout"))?);
-        
-        // Read blocks one at a time (streaming)
-        let mut blocks_in_chunk = 0;
-        loop {
-            let mut len_buf = [0u8; 4];
-            match reader.read_exact(&mut len_buf) {
-                Ok(_) => {},
-                Err(e) if e.kind() == std::io::ErrorKind::UnexpectedEof => break,
-                Err(e) => {
-                    let _ = zstd_proc.wait(); // Clean up
-                    return Err(e.into());
-                }
-            }
-            
-            let block_len = u32::from_le_bytes(len_buf) as usize;
-            
-            // Validate block size
-            if block_len > 10 * 1024 * 1024 || block_len < 88 {
-                let _ = zstd_proc.wait();
-                anyhow::bail!("Invalid block size in chunk {}: {} bytes", chunk_num, block_len);
-            }
-            
-            // Read block data
-            let mut block_data = vec![0u8; block_len];
-            reader.read_exact(&mut block_data)?;
-            
-            all_blocks.push(block_data);
-            blocks_in_chunk += 1;
-            
-            // OPTIMIZATION: Reduce progress reporting frequency (less I/O overhead)
-            if blocks_in_chunk % 25000 == 0 {
-                println!("     Loaded {}/{} blocks from chunk {}...", 
-                        blocks_in_chunk, metadata.blocks_per_chunk, chunk_num);
-            }
+    println!("   ✅ Loaded {} blocks", all_blocks.len());
+    Ok(Some(all_blocks))
+}
+
+/// Blocks each background decoder may buffer ahead of the consumer
+const PIPELINE_BUFFER_BLOCKS: usize = 1024;
+
+/// Chunks decompressed concurrently: `BLVM_CHUNK_DECOMPRESS_THREADS` or half the cores
+pub fn chunk_decompress_threads() -> usize {
+    std::env::var("BLVM_CHUNK_DECOMPRESS_THREADS")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or_else(|| num_cpus::get() / 2)
+        .max(1)
+}
+
+/// Chunk being decoded on a background thread
+struct InFlightChunk {
+    next_height: u64,
+    rx: std::sync::mpsc::Receiver<Result<Vec<u8>>>,
+}
+
+/// Ordered stream of `(height, block)` for `start..end`, with up to
+/// `parallelism` chunks decoding ahead on background threads
+///
+/// Each decoder feeds a bounded channel, so memory stays at roughly
+/// `parallelism * PIPELINE_BUFFER_BLOCKS` blocks. Missing chunk files are
+/// skipped with a warning, as in the serial loader.
+pub struct ChunkBlockStream {
+    chunks_dir: PathBuf,
+    blocks_per_chunk: u64,
+    next_chunk: usize,
+    last_chunk: usize,
+    start_height: u64,
+    end_height: u64,
+    parallelism: usize,
+    in_flight: std::collections::VecDeque<InFlightChunk>,
+}
+
+impl ChunkBlockStream {
+    pub fn new(chunks_dir: &Path, metadata: &ChunkMetadata, start_height: u64, end_height: u64, parallelism: usize) -> Self {
+        let end_height = end_height.min(metadata.total_blocks);
+        let mut stream = Self {
+            chunks_dir: chunks_dir.to_path_buf(),
+            blocks_per_chunk: metadata.blocks_per_chunk,
+            next_chunk: (start_height / metadata.blocks_per_chunk) as usize,
+            last_chunk: (end_height.saturating_sub(1) / metadata.blocks_per_chunk) as usize,
+            start_height,
+            end_height,
+            parallelism: parallelism.max(1),
+            in_flight: std::collections::VecDeque::new(),
+        };
+        if start_height >= end_height || metadata.num_chunks == 0 {
+            stream.next_chunk = stream.last_chunk + 1;
+        } else {
+            stream.last_chunk = stream.last_chunk.min(metadata.num_chunks - 1);
         }
-        
-        // Wait for zstd to finish
-        let status = zstd_proc.wait()?;
-        if !status.success() {
-            anyhow::bail!("zstd decompression failed for chunk {}", chunk_num);
+        stream.refill();
+        stream
+    }
+
+    /// Start decoders until `parallelism` chunks are in flight
+    fn refill(&mut self) {
+        while self.in_flight.len() < self.parallelism && self.next_chunk <= self.last_chunk {
+            let chunk_num = self.next_chunk;
+            self.next_chunk += 1;
+
+            let chunk_file = chunk_path(&self.chunks_dir, chunk_num);
+            if !chunk_file.exists() {
+                eprintln!("   ⚠️  Chunk {} not found: {}", chunk_num, chunk_file.display());
+                continue;
+            }
+            println!("   📦 Streaming blocks from chunk {}...", chunk_num);
+
+            let (tx, rx) = std::sync::mpsc::sync_channel(PIPELINE_BUFFER_BLOCKS);
+            std::thread::spawn(move || {
+                let result = for_each_chunk_block(&chunk_file, |_, block| {
+                    // A send error means the consumer stopped early; stop decoding
+                    tx.send(Ok(block)).map_err(|_| anyhow::anyhow!("chunk stream dropped"))
+                });
+                if let Err(e) = result {
+                    let _ = tx.send(Err(e.context(format!("chunk {}", chunk_num))));
+                }
+            });
+            self.in_flight.push_back(InFlightChunk {
+                next_height: chunk_num as u64 * self.blocks_per_chunk,
+                rx,
+            });
         }
-        
-        println!("   ✅ Loaded {} blocks from chunk {}", blocks_in_chunk, chunk_num);
     }
+}
 
-    // Filter to requested range
-    if start_idx > 0 || end_idx < all_blocks.len() {
-        let filtered: Vec<_> = all_blocks.into_iter()
-            .skip(start_idx)
-            .take(end_idx - start_idx)
-            .collect();
-        Ok(Some(filtered))
-    } else {
-        Ok(Some(all_blocks))
+impl Iterator for ChunkBlockStream {
+    type Item = Result<(u64, Vec<u8>)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let front = self.in_flight.front_mut()?;
+            match front.rx.recv() {
+                Ok(Ok(block)) => {
+                    let height = front.next_height;
+                    front.next_height += 1;
+                    if height < self.start_height {
+                        continue;
+                    }
+                    if height >= self.end_height {
+                        // Dropping the receivers stops the remaining decoders
+                        self.in_flight.clear();
+                        return None;
+                    }
+                    return Some(Ok((height, block)));
+                }
+                Ok(Err(e)) => {
+                    self.in_flight.clear();
+                    return Some(Err(e));
+                }
+                Err(_) => {
+                    // Decoder finished this chunk
+                    self.in_flight.pop_front();
+                    self.refill();
+                }
+            }
+        }
     }
 }
 
@@ -275,3 +343,764 @@ pub fn chunked_cache_exists() -> bool {
         false
     }
 }
+
+/// Stream every block of one chunk file to `on_block(index_in_chunk, bytes)`
+///
+/// Returns the number of blocks read. Decompression is streamed, so memory
+/// stays bounded regardless of chunk size.
+pub fn for_each_chunk_block(
+    chunk_file: &Path,
+    mut on_block: impl FnMut(usize, Vec<u8>) -> Result<()>,
+) -> Result<usize> {
+    use std::io::{BufReader, Read};
+
+    let file = std::fs::File::open(chunk_file)
+        .with_context(|| format!("Failed to open chunk {}", chunk_file.display()))?;
+    let mut reader = BufReader::with_capacity(
+        16 * 1024 * 1024,
+        zstd::stream::read::Decoder::new(file)
+            .with_context(|| format!("Failed to start zstd decompression: {}", chunk_file.display()))?,
+    );
+
+    let mut count = 0usize;
+    let result = (|| -> Result<()> {
+        loop {
+            // Only a clean end of stream reads zero bytes; a cut-off frame is an UnexpectedEof error
+            let mut len_buf = [0u8; 4];
+            if reader.read(&mut len_buf[..1])? == 0 {
+                return Ok(());
+            }
+            reader
+                .read_exact(&mut len_buf[1..])
+                .with_context(|| format!("Truncated length of block {} in {}", count, chunk_file.display()))?;
+            let block_len = u32::from_le_bytes(len_buf) as usize;
+            if block_len > 10 * 1024 * 1024 || block_len < 88 {
+                anyhow::bail!("Invalid block size {} at block {} of {}", block_len, count, chunk_file.display());
+            }
+            let mut block_data = vec![0u8; block_len];
+            reader
+                .read_exact(&mut block_data)
+                .with_context(|| format!("Truncated block {} in {}", count, chunk_file.display()))?;
+            on_block(count, block_data)?;
+            count += 1;
+        }
+    })();
+
+    result.with_context(|| format!("Failed to read {}", chunk_file.display()))?;
+    Ok(count)
+}
+
+/// Path of a chunk file
+pub fn chunk_path(chunks_dir: &Path, chunk_num: usize) -> PathBuf {
+    chunks_dir.join(format!("chunk_{}.bin.zst", chunk_num))
+}
+
+/// Expected block hashes by height, stored alongside the chunks (32 bytes per height)
+pub fn expected_hashes_path(chunks_dir: &Path) -> PathBuf {
+    chunks_dir.join("block_hashes.bin")
+}
+
+pub fn load_expected_hashes(path: &Path) -> Result<Vec<[u8; 32]>> {
+    let bytes = std::fs::read(path).with_context(|| format!("Failed to read {}", path.display()))?;
+    Ok(bytes
+        .chunks_exact(32)
+        .map(|h| h.try_into().expect("32-byte chunk"))
+        .collect())
+}
+
+pub fn save_expected_hashes(path: &Path, hashes: &[[u8; 32]]) -> Result<()> {
+    std::fs::write(path, hashes.concat()).with_context(|| format!("Failed to write {}", path.display()))
+}
+
+/// Fetch hashes for heights `0..count` via RPC, extending an existing list
+pub async fn fetch_expected_hashes(
+    client: &crate::core_rpc_client::CoreRpcClient,
+    mut hashes: Vec<[u8; 32]>,
+    count: u64,
+) -> Result<Vec<[u8; 32]>> {
+    for height in hashes.len() as u64..count {
+        let mut hash: [u8; 32] = hex::decode(client.getblockhash(height).await?)?
+            .try_into()
+            .map_err(|_| anyhow::anyhow!("Invalid block hash length at height {}", height))?;
+        hash.reverse();
+        hashes.push(hash);
+        if height > 0 && height % 100_000 == 0 {
+            println!("   📥 Fetched {} block hashes", height);
+        }
+    }
+    Ok(hashes)
+}
+
+/// Load the stored expected-hash index, topping it up from Core to `count` heights
+pub async fn load_or_fetch_expected_hashes(
+    chunks_dir: &Path,
+    client: &crate::core_rpc_client::CoreRpcClient,
+    count: u64,
+) -> Result<Vec<[u8; 32]>> {
+    let path = expected_hashes_path(chunks_dir);
+    let mut hashes = if path.exists() { load_expected_hashes(&path)? } else { Vec::new() };
+    if (hashes.len() as u64) < count {
+        println!("📥 Fetching {} block hashes from Core", count - hashes.len() as u64);
+        hashes = fetch_expected_hashes(client, hashes, count).await?;
+        save_expected_hashes(&path, &hashes)?;
+    }
+    Ok(hashes)
+}
+
+/// A chunk that failed verification
+#[derive(Debug, Clone)]
+pub struct CorruptChunk {
+    pub chunk_num: usize,
+    pub start_height: u64,
+    pub expected_blocks: u64,
+    pub blocks_found: u64,
+    /// Heights whose header hash doesn't match the expected hash
+    pub bad_heights: Vec<u64>,
+    /// Missing file, decompression failure or malformed framing
+    pub error: Option<String>,
+}
+
+impl CorruptChunk {
+    /// Height ranges that need repair (bad blocks plus anything missing at the end)
+    pub fn damaged_ranges(&self) -> Vec<(u64, u64)> {
+        if self.error.is_some() {
+            return vec![(self.start_height, self.start_height + self.expected_blocks - 1)];
+        }
+        let mut ranges: Vec<(u64, u64)> = Vec::new();
+        for &h in &self.bad_heights {
+            match ranges.last_mut() {
+                Some((_, end)) if *end + 1 == h => *end = h,
+                _ => ranges.push((h, h)),
+            }
+        }
+        if self.blocks_found < self.expected_blocks {
+            ranges.push((
+                self.start_height + self.blocks_found,
+                self.start_height + self.expected_blocks - 1,
+            ));
+        }
+        ranges
+    }
+}
+
+/// Result of `verify_cache`
+#[derive(Debug, Default)]
+pub struct CacheVerifyReport {
+    pub blocks_checked: u64,
+    pub corrupted: Vec<CorruptChunk>,
+}
+
+impl CacheVerifyReport {
+    pub fn is_clean(&self) -> bool {
+        self.corrupted.is_empty()
+    }
+}
+
+/// Verify one chunk against the expected hashes
+pub fn verify_chunk(
+    chunks_dir: &Path,
+    metadata: &ChunkMetadata,
+    chunk_num: usize,
+    expected_hashes: &[[u8; 32]],
+) -> (u64, Option<CorruptChunk>) {
+    let start_height = chunk_num as u64 * metadata.blocks_per_chunk;
+    let expected_blocks = metadata
+        .blocks_per_chunk
+        .min(metadata.total_blocks.saturating_sub(start_height));
+    let mut corrupt = CorruptChunk {
+        chunk_num,
+        start_height,
+        expected_blocks,
+        blocks_found: 0,
+        bad_heights: Vec::new(),
+        error: None,
+    };
+
+    let chunk_file = chunk_path(chunks_dir, chunk_num);
+    if !chunk_file.exists() {
+        corrupt.error = Some(format!("missing {}", chunk_file.display()));
+        return (0, Some(corrupt));
+    }
+
+    let result = for_each_chunk_block(&chunk_file, |idx, block| {
+        let height = start_height + idx as u64;
+        match (block_hash(&block), expected_hashes.get(height as usize)) {
+            (Some(actual), Some(expected)) if actual == *expected => {}
+            (_, None) => {} // No reference hash (beyond the fetched index)
+            _ => corrupt.bad_heights.push(height),
+        }
+        Ok(())
+    });
+    match result {
+        Ok(found) => corrupt.blocks_found = found as u64,
+        Err(e) => corrupt.error = Some(e.to_string()),
+    }
+
+    let checked = corrupt.blocks_found;
+    let damaged = corrupt.error.is_some() || !corrupt.bad_heights.is_empty() || checked < expected_blocks;
+    (checked, damaged.then_some(corrupt))
+}
+
+/// Walk every chunk and check each block's hash against `expected_hashes`
+pub fn verify_cache(chunks_dir: &Path, expected_hashes: &[[u8; 32]]) -> Result<CacheVerifyReport> {
+    let metadata = load_chunk_metadata(chunks_dir)?
+        .with_context(|| format!("No chunks.meta in {}", chunks_dir.display()))?;
+    println!(
+        "🔍 Verifying {} chunks ({} blocks) against {} expected hashes",
+        metadata.num_chunks,
+        metadata.total_blocks,
+        expected_hashes.len()
+    );
+
+    let mut report = CacheVerifyReport::default();
+    for chunk_num in 0..metadata.num_chunks {
+        let (checked, corrupt) = verify_chunk(chunks_dir, &metadata, chunk_num, expected_hashes);
+        report.blocks_checked += checked;
+        match corrupt {
+            Some(c) => {
+                eprintln!(
+                    "   ❌ Chunk {} (heights {}-{}): {} bad blocks, {}/{} present{}",
+                    c.chunk_num,
+                    c.start_height,
+                    c.start_height + c.expected_blocks.saturating_sub(1),
+                    c.bad_heights.len(),
+                    c.blocks_found,
+                    c.expected_blocks,
+                    c.error.as_ref().map(|e| format!(" ({})", e)).unwrap_or_default()
+                );
+                report.corrupted.push(c);
+            }
+            None => println!("   ✅ Chunk {} ok ({} blocks)", chunk_num, checked),
+        }
+    }
+    Ok(report)
+}
+
+/// Writes length-prefixed blocks as a zstd frame, compressed in-process
+///
+/// `create` writes a temporary file that is renamed over the target on
+/// `finish`; `append` adds a new zstd frame to the end of an existing chunk
+/// (concatenated frames decompress as one stream).
+struct ChunkFileWriter {
+    encoder: zstd::stream::write::Encoder<'static, std::io::BufWriter<std::fs::File>>,
+    /// Temp file to rename on finish, None when appending
+    tmp_path: Option<PathBuf>,
+    final_path: PathBuf,
+    /// Length of the chunk before appending, to roll back a failed append
+    original_len: u64,
+    blocks: u64,
+}
+
+impl ChunkFileWriter {
+    fn encoder(
+        file: std::fs::File,
+        level: i32,
+    ) -> Result<zstd::stream::write::Encoder<'static, std::io::BufWriter<std::fs::File>>> {
+        let output = std::io::BufWriter::with_capacity(16 * 1024 * 1024, file);
+        zstd::stream::write::Encoder::new(output, level).context("Failed to start zstd compression")
+    }
+
+    fn create(final_path: &Path, level: i32) -> Result<Self> {
+        let tmp_path = final_path.with_extension("zst.tmp");
+        let file = std::fs::File::create(&tmp_path)
+            .with_context(|| format!("Failed to create {}", tmp_path.display()))?;
+        Ok(Self {
+            encoder: Self::encoder(file, level)?,
+            tmp_path: Some(tmp_path),
+            final_path: final_path.to_path_buf(),
+            original_len: 0,
+            blocks: 0,
+        })
+    }
+
+    fn append(final_path: &Path, level: i32) -> Result<Self> {
+        let file = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(final_path)
+            .with_context(|| format!("Failed to open {} for append", final_path.display()))?;
+        let original_len = file.metadata()?.len();
+        Ok(Self {
+            encoder: Self::encoder(file, level)?,
+            tmp_path: None,
+            final_path: final_path.to_path_buf(),
+            original_len,
+            blocks: 0,
+        })
+    }
+
+    fn write_block(&mut self, block: &[u8]) -> Result<()> {
+        use std::io::Write;
+        self.encoder.write_all(&(block.len() as u32).to_le_bytes())?;
+        self.encoder.write_all(block)?;
+        self.blocks += 1;
+        Ok(())
+    }
+
+    fn finish(self) -> Result<u64> {
+        let Self {
+            encoder,
+            tmp_path,
+            final_path,
+            original_len,
+            blocks,
+        } = self;
+        // Close the frame, then flush the buffered tail to the file
+        let written = encoder
+            .finish()
+            .and_then(|output| output.into_inner().map_err(|e| e.into_error()))
+            .and_then(|file| file.sync_all());
+        let success = written.is_ok();
+        match tmp_path {
+            Some(tmp) if success => std::fs::rename(&tmp, &final_path)?,
+            Some(tmp) => {
+                let _ = std::fs::remove_file(&tmp);
+            }
+            // Cut off a partial frame so the chunk stays decodable
+            None if !success => std::fs::OpenOptions::new()
+                .write(true)
+                .open(&final_path)?
+                .set_len(original_len)?,
+            None => {}
+        }
+        written.with_context(|| format!("zstd compression failed for {}", final_path.display()))?;
+        Ok(blocks)
+    }
+}
+
+/// Fetch a block from `source` and check it against the expected hash
+async fn fetch_verified_block(
+    source: &crate::parallel_differential::BlockDataSource,
+    height: u64,
+    expected_hashes: &[[u8; 32]],
+) -> Result<Vec<u8>> {
+    let block = crate::parallel_differential::get_block_data(source, height).await?;
+    if let Some(expected) = expected_hashes.get(height as usize) {
+        if block_hash(&block).as_ref() != Some(expected) {
+            anyhow::bail!("Block source returned the wrong block for height {}", height);
+        }
+    }
+    Ok(block)
+}
+
+/// Rewrite one corrupted chunk, re-fetching only its damaged heights
+///
+/// Readable chunks are streamed through with the bad blocks substituted;
+/// unreadable ones are rebuilt from the source entirely.
+pub async fn repair_chunk(
+    chunks_dir: &Path,
+    corrupt: &CorruptChunk,
+    source: &crate::parallel_differential::BlockDataSource,
+    expected_hashes: &[[u8; 32]],
+) -> Result<u64> {
+    let chunk_file = chunk_path(chunks_dir, corrupt.chunk_num);
+    let end_height = corrupt.start_height + corrupt.expected_blocks;
+    let mut writer = ChunkFileWriter::create(&chunk_file, DEFAULT_ZSTD_LEVEL)?;
+    let mut refetched = 0u64;
+
+    if corrupt.error.is_some() {
+        for height in corrupt.start_height..end_height {
+            writer.write_block(&fetch_verified_block(source, height, expected_hashes).await?)?;
+            refetched += 1;
+        }
+    } else {
+        // Damaged blocks are few; fetch them up front so the old chunk can be streamed
+        let mut replacements = std::collections::HashMap::new();
+        for &height in &corrupt.bad_heights {
+            replacements.insert(height, fetch_verified_block(source, height, expected_hashes).await?);
+        }
+        // The rewrite goes to a temp file, so the old chunk can be streamed in place
+        for_each_chunk_block(&chunk_file, |idx, block| {
+            let height = corrupt.start_height + idx as u64;
+            if height >= end_height {
+                return Ok(());
+            }
+            match replacements.get(&height) {
+                Some(fixed) => writer.write_block(fixed),
+                None => writer.write_block(&block),
+            }
+        })?;
+        refetched += replacements.len() as u64;
+
+        // Append whatever was missing from the end of the chunk
+        for height in corrupt.start_height + corrupt.blocks_found..end_height {
+            writer.write_block(&fetch_verified_block(source, height, expected_hashes).await?)?;
+            refetched += 1;
+        }
+    }
+
+    writer.finish()?;
+    Ok(refetched)
+}
+
+/// Repair every chunk listed in a verification report; returns blocks re-fetched
+pub async fn repair_cache(
+    chunks_dir: &Path,
+    report: &CacheVerifyReport,
+    source: &crate::parallel_differential::BlockDataSource,
+    expected_hashes: &[[u8; 32]],
+) -> Result<u64> {
+    let mut total = 0u64;
+    for corrupt in &report.corrupted {
+        println!(
+            "🔧 Repairing chunk {} (heights {}-{})",
+            corrupt.chunk_num,
+            corrupt.start_height,
+            corrupt.start_height + corrupt.expected_blocks.saturating_sub(1)
+        );
+        let refetched = repair_chunk(chunks_dir, corrupt, source, expected_hashes)
+            .await
+            .with_context(|| format!("Failed to repair chunk {}", corrupt.chunk_num))?;
+        println!("   ✅ Chunk {} rewritten ({} blocks re-fetched)", corrupt.chunk_num, refetched);
+        total += refetched;
+    }
+    Ok(total)
+}
+
+/// Write chunks.meta (via a temp file, so readers never see a partial one)
+pub fn save_chunk_metadata(chunks_dir: &Path, metadata: &ChunkMetadata) -> Result<()> {
+    let content = format!(
+        "# Chunked block cache metadata\ntotal_blocks={}\nnum_chunks={}\nblocks_per_chunk={}\ncompression={}\n",
+        metadata.total_blocks, metadata.num_chunks, metadata.blocks_per_chunk, metadata.compression
+    );
+    let tmp = chunks_dir.join("chunks.meta.tmp");
+    std::fs::write(&tmp, content)?;
+    std::fs::rename(&tmp, chunks_dir.join("chunks.meta"))?;
+    Ok(())
+}
+
+/// Heights `start..=end` split at chunk boundaries: (chunk_num, start, end)
+fn chunk_segments(start: u64, end: u64, blocks_per_chunk: u64) -> Vec<(usize, u64, u64)> {
+    let mut segments = Vec::new();
+    let mut height = start;
+    while height <= end {
+        let chunk_num = height / blocks_per_chunk;
+        let segment_end = ((chunk_num + 1) * blocks_per_chunk - 1).min(end);
+        segments.push((chunk_num as usize, height, segment_end));
+        height = segment_end + 1;
+    }
+    segments
+}
+
+/// Extend the cache up to `tip_height`, appending to the active chunk and
+/// rolling new chunks at `blocks_per_chunk`; returns blocks appended
+///
+/// chunks.meta is rewritten after every chunk segment, so an interrupted
+/// update resumes from the last completed segment.
+pub async fn update_cache(
+    chunks_dir: &Path,
+    source: &crate::parallel_differential::BlockDataSource,
+    tip_height: u64,
+) -> Result<u64> {
+    use crate::block_source::BlockSource;
+    use crate::parallel_differential::get_block_data;
+
+    let mut metadata = load_chunk_metadata(chunks_dir)?
+        .with_context(|| format!("No chunks.meta in {}", chunks_dir.display()))?;
+    let next_height = metadata.total_blocks;
+    if next_height > tip_height {
+        println!("✅ Chunked cache already at height {}", next_height.saturating_sub(1));
+        return Ok(0);
+    }
+    println!(
+        "📦 Updating chunked cache: heights {}-{} ({} blocks)",
+        next_height,
+        tip_height,
+        tip_height - next_height + 1
+    );
+
+    let mut appended = 0u64;
+    for (chunk_num, start, end) in chunk_segments(next_height, tip_height, metadata.blocks_per_chunk) {
+        let mut writer = ChunkFileWriter::append(&chunk_path(chunks_dir, chunk_num), DEFAULT_ZSTD_LEVEL)?;
+        let count = (end - start + 1) as usize;
+        match source.iter_sequential(start, count)? {
+            Some(blocks) => {
+                for block in blocks {
+                    writer.write_block(&block?)?;
+                }
+            }
+            None => {
+                for height in start..=end {
+                    writer.write_block(&get_block_data(source, height).await?)?;
+                }
+            }
+        }
+        let written = writer.finish()?;
+        if written != count as u64 {
+            anyhow::bail!("Expected {} blocks for chunk {}, source returned {}", count, chunk_num, written);
+        }
+
+        metadata.total_blocks = end + 1;
+        metadata.num_chunks = metadata.num_chunks.max(chunk_num + 1);
+        save_chunk_metadata(chunks_dir, &metadata)?;
+        appended += written;
+        println!("   ✅ Chunk {}: heights {}-{} appended", chunk_num, start, end);
+    }
+    Ok(appended)
+}
+
+/// Options for `ChunkedCacheWriter`
+#[derive(Debug, Clone)]
+pub struct ChunkedCacheOptions {
+    pub blocks_per_chunk: u64,
+    /// zstd compression level (1-22)
+    pub level: i32,
+    /// Uncompressed bytes per zstd frame; smaller frames bound the damage of a corrupt byte
+    pub frame_size: u64,
+}
+
+impl Default for ChunkedCacheOptions {
+    fn default() -> Self {
+        Self {
+            blocks_per_chunk: 100_000,
+            level: DEFAULT_ZSTD_LEVEL,
+            frame_size: 256 * 1024 * 1024,
+        }
+    }
+}
+
+/// Builds a chunked cache from genesis: length-prefixed blocks, zstd-compressed
+/// into `chunk_N.bin.zst` files, with chunks.meta written on `finish`
+pub struct ChunkedCacheWriter {
+    dir: PathBuf,
+    options: ChunkedCacheOptions,
+    current: Option<ChunkFileWriter>,
+    frame_bytes: u64,
+    total_blocks: u64,
+}
+
+impl ChunkedCacheWriter {
+    pub fn create(dir: impl AsRef<Path>, options: ChunkedCacheOptions) -> Result<Self> {
+        let dir = dir.as_ref().to_path_buf();
+        if options.blocks_per_chunk == 0 || !(1..=22).contains(&options.level) {
+            anyhow::bail!("Invalid chunked cache options: {:?}", options);
+        }
+        if dir.join("chunks.meta").exists() {
+            anyhow::bail!("{} already holds a chunked cache (use update-cache to extend it)", dir.display());
+        }
+        std::fs::create_dir_all(&dir)
+            .with_context(|| format!("Failed to create chunks directory {}", dir.display()))?;
+        Ok(Self {
+            dir,
+            options,
+            current: None,
+            frame_bytes: 0,
+            total_blocks: 0,
+        })
+    }
+
+    /// Append the block at the next height
+    pub fn push_block(&mut self, block: &[u8]) -> Result<()> {
+        let chunk_num = (self.total_blocks / self.options.blocks_per_chunk) as usize;
+        let chunk_start = self.total_blocks % self.options.blocks_per_chunk == 0;
+        if chunk_start || self.frame_bytes >= self.options.frame_size {
+            if let Some(writer) = self.current.take() {
+                writer.finish()?;
+            }
+            let path = chunk_path(&self.dir, chunk_num);
+            if chunk_start {
+                // Frames are appended, so start each chunk from an empty file
+                std::fs::File::create(&path)?;
+                println!("   📦 Writing chunk {}", chunk_num);
+            }
+            self.current = Some(ChunkFileWriter::append(&path, self.options.level)?);
+            self.frame_bytes = 0;
+        }
+
+        self.current
+            .as_mut()
+            .expect("chunk writer opened above")
+            .write_block(block)?;
+        self.frame_bytes += 4 + block.len() as u64;
+        self.total_blocks += 1;
+        Ok(())
+    }
+
+    /// Append every block from an iterator
+    pub fn write_blocks(&mut self, blocks: impl IntoIterator<Item = Result<Vec<u8>>>) -> Result<()> {
+        for block in blocks {
+            self.push_block(&block?)?;
+        }
+        Ok(())
+    }
+
+    /// Close the last frame and write chunks.meta
+    pub fn finish(mut self) -> Result<ChunkMetadata> {
+        if let Some(writer) = self.current.take() {
+            writer.finish()?;
+        }
+        let metadata = ChunkMetadata {
+            total_blocks: self.total_blocks,
+            num_chunks: self.total_blocks.div_ceil(self.options.blocks_per_chunk) as usize,
+            blocks_per_chunk: self.options.blocks_per_chunk,
+            compression: "zstd".to_string(),
+        };
+        save_chunk_metadata(&self.dir, &metadata)?;
+        Ok(metadata)
+    }
+}
+
+/// Build a chunked cache for heights `0..=end_height` from any block data source
+pub async fn build_chunked_cache(
+    dir: &Path,
+    source: &crate::parallel_differential::BlockDataSource,
+    end_height: u64,
+    options: ChunkedCacheOptions,
+) -> Result<ChunkMetadata> {
+    use crate::block_source::BlockSource;
+    use crate::parallel_differential::get_block_data;
+
+    println!("📦 Building chunked cache 0-{} in {}", end_height, dir.display());
+    let mut writer = ChunkedCacheWriter::create(dir, options)?;
+    match source.iter_sequential(0, end_height as usize + 1)? {
+        Some(blocks) => writer.write_blocks(blocks)?,
+        None => {
+            for height in 0..=end_height {
+                writer.push_block(&get_block_data(source, height).await?)?;
+            }
+        }
+    }
+    let metadata = writer.finish()?;
+    println!(
+        "✅ Chunked cache ready: {} blocks in {} chunks",
+        metadata.total_blocks, metadata.num_chunks
+    );
+    Ok(metadata)
+}
+
+/// A chunked cache as a block source (e.g. one pulled from a cache archive,
+/// on a machine with no node)
+///
+/// Ranges stream through `ChunkBlockStream`; single blocks decompress their
+/// chunk up to the block, so random access is slow.
+pub struct ChunkedCacheSource {
+    chunks_dir: PathBuf,
+    metadata: ChunkMetadata,
+}
+
+impl ChunkedCacheSource {
+    /// Open the cache in `chunks_dir` (None if it has no chunks.meta)
+    pub fn open(chunks_dir: &Path) -> Result<Option<Self>> {
+        Ok(load_chunk_metadata(chunks_dir)?.map(|metadata| Self {
+            chunks_dir: chunks_dir.to_path_buf(),
+            metadata,
+        }))
+    }
+
+    pub fn metadata(&self) -> &ChunkMetadata {
+        &self.metadata
+    }
+
+    /// Blocks `start..end` in order; a missing chunk is an error, not a gap
+    fn stream(&self, start: u64, end: u64, parallelism: usize) -> impl Iterator<Item = Result<Vec<u8>>> + Send {
+        let stream = ChunkBlockStream::new(&self.chunks_dir, &self.metadata, start, end, parallelism);
+        stream.zip(start..).map(|(item, expected)| {
+            let (height, block) = item?;
+            if height != expected {
+                anyhow::bail!("Chunked cache has no block {} (chunk file missing?)", expected);
+            }
+            Ok(block)
+        })
+    }
+}
+
+#[async_trait::async_trait]
+impl crate::block_source::BlockSource for ChunkedCacheSource {
+    fn name(&self) -> &str {
+        "chunked cache"
+    }
+
+    async fn get_block(&self, height: u64) -> Result<Vec<u8>> {
+        self.stream(height, height + 1, 1)
+            .next()
+            .with_context(|| format!("Block {} not in chunked cache", height))?
+    }
+
+    async fn get_tip_height(&self) -> Result<Option<u64>> {
+        Ok(self.metadata.total_blocks.checked_sub(1))
+    }
+
+    fn iter_sequential(&self, start: u64, count: usize) -> Result<Option<crate::block_source::SequentialBlocks>> {
+        let end = start + count as u64;
+        if end > self.metadata.total_blocks {
+            anyhow::bail!(
+                "Chunked cache ends at height {}, {} blocks from {} requested",
+                self.metadata.total_blocks.saturating_sub(1),
+                count,
+                start
+            );
+        }
+        Ok(Some(Box::new(self.stream(start, end, chunk_decompress_threads()))))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_damaged_ranges() {
+        let chunk = CorruptChunk {
+            chunk_num: 1,
+            start_height: 100,
+            expected_blocks: 10,
+            blocks_found: 8,
+            bad_heights: vec![101, 102, 105],
+            error: None,
+        };
+        assert_eq!(chunk.damaged_ranges(), vec![(101, 102), (105, 105), (108, 109)]);
+
+        let unreadable = CorruptChunk {
+            error: Some("zstd failed".to_string()),
+            ..chunk
+        };
+        assert_eq!(unreadable.damaged_ranges(), vec![(100, 109)]);
+    }
+
+    #[test]
+    fn test_chunk_segments() {
+        assert_eq!(chunk_segments(95, 215, 100), vec![(0, 95, 99), (1, 100, 199), (2, 200, 215)]);
+        assert_eq!(chunk_segments(100, 100, 100), vec![(1, 100, 100)]);
+    }
+
+    #[test]
+    fn test_writer_roundtrip() {
+        let dir = tempfile::tempdir().unwrap();
+        let options = ChunkedCacheOptions {
+            blocks_per_chunk: 3,
+            level: 1,
+            frame_size: 200,
+        };
+        let blocks: Vec<Vec<u8>> = (0..7u8).map(|i| vec![i; 100]).collect();
+        let mut writer = ChunkedCacheWriter::create(dir.path(), options).unwrap();
+        writer.write_blocks(blocks.iter().cloned().map(Ok)).unwrap();
+        let metadata = writer.finish().unwrap();
+        assert_eq!((metadata.total_blocks, metadata.num_chunks), (7, 3));
+
+        let reloaded = load_chunk_metadata(dir.path()).unwrap().unwrap();
+        assert_eq!(reloaded.blocks_per_chunk, 3);
+        let mut read_back = Vec::new();
+        for chunk in 0..reloaded.num_chunks {
+            for_each_chunk_block(&chunk_path(dir.path(), chunk), |_, b| {
+                read_back.push(b);
+                Ok(())
+            })
+            .unwrap();
+        }
+        assert_eq!(read_back, blocks);
+
+        // Pipelined stream crosses chunk boundaries in height order
+        let streamed: Vec<(u64, Vec<u8>)> = ChunkBlockStream::new(dir.path(), &reloaded, 2, 6, 2)
+            .collect::<Result<_>>()
+            .unwrap();
+        let heights: Vec<u64> = streamed.iter().map(|(h, _)| *h).collect();
+        assert_eq!(heights, vec![2, 3, 4, 5]);
+        assert_eq!(streamed[0].1, blocks[2]);
+
+        // A cut-off frame is an error, not a short chunk
+        let chunk_0 = chunk_path(dir.path(), 0);
+        let len = std::fs::metadata(&chunk_0).unwrap().len();
+        std::fs::OpenOptions::new().write(true).open(&chunk_0).unwrap().set_len(len - 3).unwrap();
+        assert!(for_each_chunk_block(&chunk_0, |_, _| Ok(())).is_err());
+    }
+}
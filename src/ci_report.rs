@@ -0,0 +1,393 @@
+//! CI Output
+//!
+//! Machine-readable results of a differential run, so CI can gate merges on
+//! consensus equivalence without parsing console output.
+//!
+//! The `differential` subcommand exits with a fixed code:
+//! - 0 (`EXIT_MATCH`): every tested block matched (waived divergences allowed)
+//! - 1 (`EXIT_DIVERGENCE`): an unwaived divergence, or BLVM panicked on a block
+//! - 2 (`EXIT_INFRASTRUCTURE`): the run itself failed (node unreachable, bad
+//!   block source, ...), or a chunk failed or was cancelled so part of the
+//!   range went untested, and equivalence over the range is unknown
+//!
+//! and writes a `RunSummary` as JSON to `--summary` (or `BLVM_SUMMARY_JSON`)
+//! in all three cases.
+//!
+//! With `BLVM_JUNIT_XML` set, the run is also written as a JUnit report -
+//! one test case per chunk (in sample mode, per sampled block), unwaived
+//! divergences as failures and BLVM panics as errors - for the test UIs of
+//! GitHub Actions, GitLab and Jenkins.
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::fmt::Write as _;
+use std::path::Path;
+
+use crate::html_report::RunRecord;
+use crate::parallel_differential::ChunkResult;
+
+/// Exit code: BLVM and Core agreed on every block
+pub const EXIT_MATCH: i32 = 0;
+/// Exit code: at least one unwaived divergence (or BLVM panic)
+pub const EXIT_DIVERGENCE: i32 = 1;
+/// Exit code: the run failed, or left heights untested, before reaching a verdict
+pub const EXIT_INFRASTRUCTURE: i32 = 2;
+
+/// Overall outcome of a run
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RunStatus {
+    Match,
+    Divergence,
+    Error,
+}
+
+impl RunStatus {
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            RunStatus::Match => EXIT_MATCH,
+            RunStatus::Divergence => EXIT_DIVERGENCE,
+            RunStatus::Error => EXIT_INFRASTRUCTURE,
+        }
+    }
+}
+
+/// Totals of a run (`summary.json`)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RunSummary {
+    pub status: RunStatus,
+    pub exit_code: i32,
+    pub start_height: u64,
+    pub end_height: u64,
+    pub chunks: usize,
+    pub blocks_tested: usize,
+    pub matched: usize,
+    pub divergences: usize,
+    /// Divergences covered by a waiver (included in `divergences`)
+    pub waived: usize,
+    pub version_divergences: usize,
+    /// Chunks stopped by a BLVM panic
+    pub poisoned_chunks: usize,
+    pub chunk_retries: usize,
+    /// Height ranges failed or cancelled chunks never compared
+    #[serde(default)]
+    pub untested_ranges: Vec<(u64, u64)>,
+    /// Heights of unwaived divergences
+    pub divergent_heights: Vec<u64>,
+    pub wall_secs: f64,
+    pub blocks_per_sec: f64,
+    /// Why the run failed or what it left untested
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl RunSummary {
+    /// Summary of a run that finished
+    ///
+    /// Every planned chunk has a result, failed ones included, so a failed
+    /// chunk (or no chunk at all) means part of the range is uncovered: the
+    /// status is then `Error`, unless a divergence or panic was found anyway.
+    pub fn from_results(start_height: u64, end_height: u64, results: &[ChunkResult], wall_secs: f64) -> Self {
+        let blocks_tested = results.iter().map(|r| r.tested).sum();
+        let mut divergent_heights: Vec<u64> = results
+            .iter()
+            .flat_map(|r| r.unwaived_divergences().map(|(height, _, _)| *height))
+            .collect();
+        divergent_heights.sort_unstable();
+        let poisoned_chunks = results.iter().filter(|r| r.poisoned.is_some()).count();
+        let mut untested_ranges: Vec<(u64, u64)> = results.iter().filter_map(|r| r.untested).collect();
+        if results.is_empty() && start_height <= end_height {
+            untested_ranges.push((start_height, end_height));
+        }
+        let status = if !divergent_heights.is_empty() || poisoned_chunks > 0 {
+            RunStatus::Divergence
+        } else if !untested_ranges.is_empty() {
+            RunStatus::Error
+        } else {
+            RunStatus::Match
+        };
+        let error = (!untested_ranges.is_empty()).then(|| {
+            let ranges: Vec<String> = untested_ranges.iter().map(|(start, end)| format!("{}-{}", start, end)).collect();
+            format!("Heights {} were not tested (chunk failed or was cancelled)", ranges.join(", "))
+        });
+        Self {
+            status,
+            exit_code: status.exit_code(),
+            start_height,
+            end_height,
+            chunks: results.len(),
+            blocks_tested,
+            matched: results.iter().map(|r| r.matched).sum(),
+            divergences: results.iter().map(|r| r.divergences.len()).sum(),
+            waived: results.iter().map(|r| r.waived.len()).sum(),
+            version_divergences: results.iter().map(|r| r.version_divergences.len()).sum(),
+            poisoned_chunks,
+            chunk_retries: results.iter().map(|r| r.retries).sum(),
+            untested_ranges,
+            divergent_heights,
+            wall_secs,
+            blocks_per_sec: blocks_tested as f64 / wall_secs.max(f64::EPSILON),
+            error,
+        }
+    }
+
+    /// Summary of a run that failed before reaching a verdict
+    pub fn failed(start_height: u64, end_height: u64, error: &anyhow::Error, wall_secs: f64) -> Self {
+        Self {
+            status: RunStatus::Error,
+            exit_code: EXIT_INFRASTRUCTURE,
+            start_height,
+            end_height,
+            chunks: 0,
+            blocks_tested: 0,
+            matched: 0,
+            divergences: 0,
+            waived: 0,
+            version_divergences: 0,
+            poisoned_chunks: 0,
+            chunk_retries: 0,
+            untested_ranges: vec![(start_height, end_height)],
+            divergent_heights: Vec::new(),
+            wall_secs,
+            blocks_per_sec: 0.0,
+            error: Some(format!("{:#}", error)),
+        }
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        std::fs::write(path, serde_json::to_string_pretty(self)?)
+            .with_context(|| format!("Failed to write {}", path.display()))
+    }
+}
+
+/// Escape text for XML content and attributes (dropping characters XML 1.0 can't carry)
+fn xml_escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            '\n' | '\t' => escaped.push(c),
+            c if c.is_control() => {}
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Render a run as a JUnit XML report
+pub fn junit_xml(record: &RunRecord) -> String {
+    let failures = record
+        .chunks
+        .iter()
+        .filter(|c| c.poisoned.is_none() && c.divergences.iter().any(|d| d.waiver.is_none()))
+        .count();
+    let errors = record.chunks.iter().filter(|c| c.poisoned.is_some()).count();
+    let time: f64 = record.chunks.iter().map(|c| c.duration_secs).sum();
+    let suite = format!("BLVM differential {}-{}", record.start_height, record.end_height);
+
+    let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    let name = xml_escape(&suite);
+    let tests = record.chunks.len();
+    let _ = writeln!(
+        xml,
+        "<testsuites name=\"{name}\" tests=\"{tests}\" failures=\"{failures}\" errors=\"{errors}\" time=\"{time:.3}\">"
+    );
+    let _ = writeln!(
+        xml,
+        "  <testsuite name=\"{name}\" tests=\"{tests}\" failures=\"{failures}\" errors=\"{errors}\" skipped=\"0\" time=\"{time:.3}\">"
+    );
+    for chunk in &record.chunks {
+        let name = if chunk.start_height == chunk.end_height {
+            format!("block {}", chunk.start_height)
+        } else {
+            format!("heights {}-{}", chunk.start_height, chunk.end_height)
+        };
+        let _ = write!(
+            xml,
+            "    <testcase classname=\"blvm_differential.{}\" name=\"{}\" time=\"{:.3}\"",
+            crate::sampler::Era::of(chunk.start_height).name(),
+            xml_escape(&name),
+            chunk.duration_secs
+        );
+
+        let (unwaived, waived): (Vec<_>, Vec<_>) = chunk.divergences.iter().partition(|d| d.waiver.is_none());
+        if chunk.poisoned.is_none() && unwaived.is_empty() && waived.is_empty() {
+            xml.push_str("/>\n");
+            continue;
+        }
+        xml.push_str(">\n");
+        if let Some(panic) = &chunk.poisoned {
+            let _ = writeln!(
+                xml,
+                "      <error type=\"panic\" message=\"BLVM panicked\">{}</error>",
+                xml_escape(panic)
+            );
+        } else if let Some(first) = unwaived.first() {
+            let details: Vec<String> = unwaived
+                .iter()
+                .map(|d| format!("Height {}: BLVM={}, Core={}", d.height, d.blvm_result, d.core_result))
+                .collect();
+            let _ = writeln!(
+                xml,
+                "      <failure type=\"divergence\" message=\"{} divergence(s), first at height {}\">{}</failure>",
+                unwaived.len(),
+                first.height,
+                xml_escape(&details.join("\n"))
+            );
+        }
+        if !waived.is_empty() {
+            let notes: Vec<String> = waived
+                .iter()
+                .map(|d| format!("Waived divergence at height {}: {}", d.height, d.waiver.as_deref().unwrap_or_default()))
+                .collect();
+            let _ = writeln!(xml, "      <system-out>{}</system-out>", xml_escape(&notes.join("\n")));
+        }
+        xml.push_str("    </testcase>\n");
+    }
+    xml.push_str("  </testsuite>\n</testsuites>\n");
+    xml
+}
+
+/// Write a run's JUnit report to `path`
+pub fn write_junit(record: &RunRecord, path: &Path) -> Result<()> {
+    std::fs::write(path, junit_xml(record)).with_context(|| format!("Failed to write {}", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chunk(start: u64, divergences: &[u64], waived: &[u64]) -> ChunkResult {
+        ChunkResult {
+            start_height: start,
+            end_height: start + 99,
+            tested: 100,
+            matched: 100 - divergences.len(),
+            divergences: divergences
+                .iter()
+                .map(|&h| (h, "Invalid(x)".to_string(), "Valid".to_string()))
+                .collect(),
+            waived: waived.iter().map(|&h| (h, "known".to_string())).collect(),
+            version_divergences: Vec::new(),
+            duration_secs: 1.0,
+            utxo_count: 0,
+            finished_at: std::time::SystemTime::UNIX_EPOCH,
+            poisoned: None,
+            stage_timings: Default::default(),
+            era_timings: Default::default(),
+            slow_blocks: Vec::new(),
+            slowest: Default::default(),
+            memory: Default::default(),
+            retries: 0,
+            assumed_utxo: None,
+            end_utxo: None,
+            failed: None,
+            untested: None,
+        }
+    }
+
+    #[test]
+    fn test_status_and_exit_codes() {
+        let summary = RunSummary::from_results(0, 199, &[chunk(0, &[], &[]), chunk(100, &[142], &[142])], 2.0);
+        assert_eq!((summary.status, summary.exit_code), (RunStatus::Match, 0));
+        assert_eq!((summary.divergences, summary.waived, summary.blocks_per_sec), (1, 1, 100.0));
+
+        let summary = RunSummary::from_results(0, 199, &[chunk(0, &[7, 42], &[42]), chunk(100, &[], &[])], 1.0);
+        assert_eq!((summary.status, summary.exit_code), (RunStatus::Divergence, 1));
+        assert_eq!(summary.divergent_heights, vec![7]);
+
+        let summary = RunSummary::failed(0, 199, &anyhow::anyhow!("node unreachable"), 0.5);
+        assert_eq!(summary.exit_code, 2);
+        let json = serde_json::to_string(&summary).unwrap();
+        assert!(json.contains(r#""status":"error""#) && json.contains("node unreachable"));
+    }
+
+    #[test]
+    fn test_failed_chunk_is_not_a_match() {
+        let failed = ChunkResult::incomplete(100, 199, Some("node unreachable".to_string()), None);
+        let summary = RunSummary::from_results(0, 199, &[chunk(0, &[], &[]), failed], 1.0);
+        assert_eq!((summary.status, summary.exit_code), (RunStatus::Error, 2));
+        assert_eq!(summary.untested_ranges, vec![(100, 199)]);
+        assert!(summary.error.unwrap().contains("100-199"));
+
+        // A panic outside block processing is a poisoned chunk
+        let panic = crate::quarantine::BlockPanic { height: None, message: "boom".to_string(), artifact_dir: None };
+        let panicked = ChunkResult::incomplete(100, 199, None, Some(panic));
+        let summary = RunSummary::from_results(0, 199, &[chunk(0, &[], &[]), panicked], 1.0);
+        assert_eq!((summary.status, summary.poisoned_chunks), (RunStatus::Divergence, 1));
+
+        let summary = RunSummary::from_results(0, 199, &[], 1.0);
+        assert_eq!(summary.status, RunStatus::Error);
+    }
+
+    #[test]
+    fn test_divergence_survives_a_chunk_that_gives_up() {
+        use crate::parallel_differential::give_up;
+
+        // Blocks 100-142 compared (142 diverged), then the source failed for good at 143
+        let mut partial = chunk(100, &[142], &[]);
+        partial.end_height = 142;
+        partial.tested = 43;
+        let result = give_up(Some(partial), 100, 199, 143, 2, anyhow::anyhow!("source gone")).unwrap();
+        assert_eq!((result.end_height, result.untested, result.retries), (142, Some((143, 199)), 2));
+
+        let summary = RunSummary::from_results(0, 199, &[chunk(0, &[], &[]), result], 1.0);
+        assert_eq!((summary.status, summary.exit_code), (RunStatus::Divergence, 1));
+        assert_eq!(summary.divergent_heights, vec![142]);
+        assert_eq!(summary.untested_ranges, vec![(143, 199)]);
+
+        // Nothing compared: nothing to keep
+        assert!(give_up(None, 100, 199, 100, 0, anyhow::anyhow!("source gone")).is_err());
+    }
+
+    #[test]
+    fn test_junit_one_case_per_chunk() {
+        use crate::html_report::{ChunkRecord, DivergenceRecord};
+
+        let chunk = |start: u64, end: u64, divergences: Vec<DivergenceRecord>, poisoned: Option<&str>| ChunkRecord {
+            start_height: start,
+            end_height: end,
+            tested: (end - start + 1) as usize,
+            matched: 0,
+            duration_secs: 1.5,
+            finished_after_secs: 0.0,
+            utxo_count: 0,
+            divergences,
+            poisoned: poisoned.map(str::to_string),
+            memory: Default::default(),
+        };
+        let divergence = |height, waiver: Option<&str>| DivergenceRecord {
+            height,
+            blvm_result: "Invalid(<bad-txns>)".to_string(),
+            core_result: "Valid".to_string(),
+            waiver: waiver.map(str::to_string),
+        };
+        let record = RunRecord {
+            started_at: 0,
+            start_height: 0,
+            end_height: 300,
+            chunk_size: 100,
+            num_workers: 2,
+            utxo_backend: "memory".to_string(),
+            sample_seed: None,
+            chunks: vec![
+                chunk(0, 99, Vec::new(), None),
+                chunk(100, 199, vec![divergence(150, None), divergence(160, Some("known"))], None),
+                chunk(200, 299, vec![divergence(250, Some("known"))], None),
+                chunk(300, 300, Vec::new(), Some("panicked at 'boom'")),
+            ],
+        };
+        let xml = junit_xml(&record);
+        assert!(xml.contains(r#"tests="4" failures="1" errors="1""#));
+        assert!(xml.contains(r#"name="heights 0-99" time="1.500"/>"#));
+        assert!(xml.contains(r#"message="1 divergence(s), first at height 150">Height 150: BLVM=Invalid(&lt;bad-txns&gt;)"#));
+        assert!(xml.contains("Waived divergence at height 250: known"));
+        assert!(xml.contains(r#"name="block 300""#));
+        assert!(xml.contains("panicked at &apos;boom&apos;"));
+        assert!(!xml.contains("<bad-txns>"));
+    }
+}
@@ -0,0 +1,438 @@
+//! Rolling UTXO Set Hash (Core's coinstats kernel)
+//!
+//! Comparing whole UTXO sets only happens at checkpoint heights, and a drift
+//! found there could have started anywhere in the blocks before. Core's
+//! coinstatsindex keeps a MuHash3072 of the UTXO set that is updated per block
+//! (multiply in every created coin, divide out every spent one), so its
+//! `gettxoutsetinfo muhash <height>` is available at every height for the
+//! cost of an index lookup. `CoinStats` replicates it: same coin serialization,
+//! same hash-to-group mapping (SHA256 then ChaCha20 keystream as a 3072-bit
+//! number), same skipped outputs (unspendable scripts, the genesis block and
+//! the two coinbases a BIP30 duplicate overwrote), so BLVM's set hash can be compared with
+//! Core's after every block at two modular multiplications per coin touched.
+//!
+//! The comparison runs from the nearest genesis-based checkpoint (see
+//! `checkpoint_store`) and needs Core with `-coinstatsindex=1`.
+
+use anyhow::{Context, Result};
+use blvm_consensus::block::calculate_tx_id;
+use blvm_consensus::serialization::block::deserialize_block_with_witnesses;
+use blvm_consensus::transaction::is_coinbase;
+use blvm_consensus::types::{Network, ValidationResult};
+use blvm_consensus::{Block, OutPoint, UtxoSet, UTXO};
+use chacha20::cipher::{KeyIvInit, StreamCipher};
+use num_bigint::BigUint;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::sync::OnceLock;
+use std::time::{Duration, Instant};
+
+use crate::bench_fixtures::write_compact_size;
+use crate::block_hash::display_hex;
+use crate::checkpoint_merge::is_unspendable;
+use crate::checkpoint_store::{utxo_store_before, CheckpointStore};
+use crate::core_rpc_client::{BitcoinNetwork, CoreRpcClient};
+use crate::parallel_differential::{get_block_data, BlockDataSource};
+use crate::utxo_backend::{UtxoBackend, UtxoStore};
+
+/// Bytes in a MuHash3072 group element
+const NUM3072_BYTES: usize = 384;
+/// The modulus is 2^3072 - this
+const MODULUS_OFFSET: u32 = 1_103_717;
+/// Heights of the coinbases whose txid a later coinbase duplicated (91880 and 91842), overwriting them.
+/// Core's index never counts these (`IsBIP30Unspendable`); the duplicates are counted as normal coins.
+const BIP30_UNSPENDABLE_HEIGHTS: [u64; 2] = [91_722, 91_812];
+
+fn modulus() -> &'static BigUint {
+    static MODULUS: OnceLock<BigUint> = OnceLock::new();
+    MODULUS.get_or_init(|| (BigUint::from(1u8) << (NUM3072_BYTES * 8)) - BigUint::from(MODULUS_OFFSET))
+}
+
+/// `x` mod 2^3072 - 1103717, using 2^3072 = 1103717 (mod p)
+fn reduce(mut x: BigUint) -> BigUint {
+    let bits = NUM3072_BYTES * 8;
+    while x.bits() > bits as u64 {
+        let high = &x >> bits;
+        let mask = (BigUint::from(1u8) << bits) - 1u8;
+        x = (x & mask) + high * MODULUS_OFFSET;
+    }
+    if &x >= modulus() {
+        x -= modulus();
+    }
+    x
+}
+
+/// Core's MuHash3072: a multiset hash with insert and remove in any order
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MuHash3072 {
+    numerator: BigUint,
+    denominator: BigUint,
+}
+
+impl Default for MuHash3072 {
+    fn default() -> Self {
+        Self {
+            numerator: BigUint::from(1u8),
+            denominator: BigUint::from(1u8),
+        }
+    }
+}
+
+impl MuHash3072 {
+    /// SHA256 of `data` keys a ChaCha20 stream (zero nonce); its first 384 bytes are the element, little-endian
+    fn to_num3072(data: &[u8]) -> BigUint {
+        let key: [u8; 32] = Sha256::digest(data).into();
+        let mut stream = [0u8; NUM3072_BYTES];
+        chacha20::ChaCha20::new(&key.into(), &[0u8; 12].into()).apply_keystream(&mut stream);
+        BigUint::from_bytes_le(&stream)
+    }
+
+    pub fn insert(&mut self, data: &[u8]) {
+        self.numerator = reduce(&self.numerator * Self::to_num3072(data));
+    }
+
+    pub fn remove(&mut self, data: &[u8]) {
+        self.denominator = reduce(&self.denominator * Self::to_num3072(data));
+    }
+
+    /// SHA256 of numerator / denominator (384 bytes, little-endian); internal byte order
+    pub fn finalize(&self) -> [u8; 32] {
+        let p = modulus();
+        let inverse = self.denominator.modpow(&(p - 2u8), p);
+        let mut bytes = reduce(&self.numerator * inverse).to_bytes_le();
+        bytes.resize(NUM3072_BYTES, 0);
+        Sha256::digest(&bytes).into()
+    }
+}
+
+/// Core's `TxOutSer`: outpoint, `height * 2 + coinbase` (u32), value (i64), script with its CompactSize length
+pub fn coin_serialization(outpoint: &OutPoint, coin: &UTXO) -> Vec<u8> {
+    let script: &[u8] = &coin.script_pubkey;
+    let mut data = Vec::with_capacity(36 + 4 + 8 + 9 + script.len());
+    data.extend_from_slice(&outpoint.hash);
+    data.extend_from_slice(&(outpoint.index as u32).to_le_bytes());
+    data.extend_from_slice(&(((coin.height as u32) << 1) + coin.is_coinbase as u32).to_le_bytes());
+    data.extend_from_slice(&(coin.value as i64).to_le_bytes());
+    write_compact_size(&mut data, script.len() as u64);
+    data.extend_from_slice(script);
+    data
+}
+
+/// Whether `coin` is an output of a coinbase Core's index skips (one per block, so height identifies it)
+fn is_bip30_unspendable(coin: &UTXO) -> bool {
+    coin.is_coinbase && BIP30_UNSPENDABLE_HEIGHTS.contains(&(coin.height as u64))
+}
+
+/// Coin count, total value and rolling MuHash of a UTXO set, as Core's coinstatsindex keeps them
+#[derive(Debug, Clone, Default)]
+pub struct CoinStats {
+    muhash: MuHash3072,
+    pub txouts: u64,
+    /// Satoshis
+    pub total_amount: i64,
+}
+
+impl CoinStats {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Stats of a whole set (e.g. a checkpoint), one insert per coin
+    pub fn from_utxo_set(utxo_set: &UtxoSet) -> Self {
+        let mut stats = Self::new();
+        for (outpoint, coin) in utxo_set.iter() {
+            stats.add(outpoint, coin);
+        }
+        stats
+    }
+
+    pub fn add(&mut self, outpoint: &OutPoint, coin: &UTXO) {
+        let script: &[u8] = &coin.script_pubkey;
+        if is_unspendable(script) || is_bip30_unspendable(coin) {
+            return;
+        }
+        self.muhash.insert(&coin_serialization(outpoint, coin));
+        self.txouts += 1;
+        self.total_amount += coin.value as i64;
+    }
+
+    pub fn spend(&mut self, outpoint: &OutPoint, coin: &UTXO) {
+        if is_bip30_unspendable(coin) {
+            return;
+        }
+        self.muhash.remove(&coin_serialization(outpoint, coin));
+        self.txouts -= 1;
+        self.total_amount -= coin.value as i64;
+    }
+
+    /// Apply a block; `utxo_store` must hold the set from before it (call before connecting it)
+    pub fn connect_block(&mut self, block: &Block, height: u64, utxo_store: &dyn UtxoStore) -> Result<()> {
+        if height == 0 {
+            return Ok(()); // The genesis coinbase was never added to the set
+        }
+        let mut created: HashMap<OutPoint, UTXO> = HashMap::new();
+        for tx in block.transactions.iter() {
+            let coinbase = is_coinbase(tx);
+            if !coinbase {
+                for input in tx.inputs.iter() {
+                    let coin = match created.get(&input.prevout) {
+                        Some(coin) => coin.clone(),
+                        None => utxo_store.get(&input.prevout)?.with_context(|| {
+                            format!(
+                                "Block {} spends {}:{}, which BLVM's UTXO set doesn't have",
+                                height,
+                                display_hex(&input.prevout.hash),
+                                input.prevout.index
+                            )
+                        })?,
+                    };
+                    self.spend(&input.prevout, &coin);
+                }
+            }
+            let txid = calculate_tx_id(tx);
+            for (index, output) in tx.outputs.iter().enumerate() {
+                let outpoint = OutPoint {
+                    hash: txid,
+                    index: index as _,
+                };
+                let coin = UTXO {
+                    value: output.value,
+                    script_pubkey: output.script_pubkey.clone(),
+                    height: height as _,
+                    is_coinbase: coinbase,
+                };
+                self.add(&outpoint, &coin);
+                created.insert(outpoint, coin);
+            }
+        }
+        Ok(())
+    }
+
+    /// The set hash in display byte order, as `gettxoutsetinfo muhash` prints it
+    pub fn muhash_hex(&self) -> String {
+        display_hex(&self.muhash.finalize())
+    }
+}
+
+/// Core's coinstatsindex figures at one height
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CoreCoinStats {
+    pub muhash: String,
+    pub txouts: u64,
+    pub total_amount: i64,
+}
+
+async fn core_coin_stats(client: &CoreRpcClient, height: u64) -> Result<CoreCoinStats> {
+    let info = client
+        .gettxoutsetinfo_at("muhash", height)
+        .await
+        .context("gettxoutsetinfo muhash failed (is Core running with -coinstatsindex=1?)")?;
+    let total = info
+        .get("total_amount")
+        .and_then(|v| v.as_f64())
+        .context("gettxoutsetinfo missing total_amount")?;
+    Ok(CoreCoinStats {
+        muhash: info
+            .get("muhash")
+            .and_then(|v| v.as_str())
+            .context("gettxoutsetinfo missing muhash")?
+            .to_string(),
+        txouts: info.get("txouts").and_then(|v| v.as_u64()).context("gettxoutsetinfo missing txouts")?,
+        total_amount: (total * 100_000_000.0).round() as i64,
+    })
+}
+
+/// The first compared height where BLVM's set hash differs from Core's
+#[derive(Debug, Clone)]
+pub struct CoinStatsMismatch {
+    pub height: u64,
+    /// Last compared height that still matched (the drift started after it)
+    pub last_match: Option<u64>,
+    pub blvm: CoreCoinStats,
+    pub core: CoreCoinStats,
+}
+
+/// Result of a coin stats run
+#[derive(Debug, Default)]
+pub struct CoinStatsReport {
+    pub blocks_connected: u64,
+    pub heights_compared: u64,
+    pub mismatch: Option<CoinStatsMismatch>,
+    /// Time spent updating the hash (excluding the initial checkpoint hash)
+    pub hashing: Duration,
+    pub elapsed_secs: f64,
+}
+
+/// Connect blocks `start_height..=end_height` and compare the set hash with Core's every `every` blocks
+///
+/// Stops at the first mismatch: every later height would differ too. Mainnet
+/// only: the checkpoints, block source and BIP30 exceptions are mainnet's, so
+/// a Core node on another chain is refused rather than checked against them.
+pub async fn run_coin_stats_differential(
+    client: &CoreRpcClient,
+    block_source: &BlockDataSource,
+    checkpoints: &CheckpointStore,
+    utxo_backend: &UtxoBackend,
+    start_height: u64,
+    end_height: Option<u64>,
+    every: u64,
+) -> Result<CoinStatsReport> {
+    let network = client.detect_network().await?;
+    if network != BitcoinNetwork::Mainnet {
+        anyhow::bail!("The coin stats differential only supports mainnet, but Core is on {}", network.as_str());
+    }
+    let tip = client.getblockcount().await?;
+    let end_height = end_height.unwrap_or(tip).min(tip);
+    let every = every.max(1);
+    println!(
+        "🧮 Coin stats differential: blocks {}..={}, compared with Core every {} block(s)",
+        start_height, end_height, every
+    );
+
+    let (mut utxo_store, mut stats) = if start_height == 0 {
+        (utxo_backend.create(UtxoSet::new())?, CoinStats::new())
+    } else {
+        let (utxo_store, _) = utxo_store_before(start_height, block_source, checkpoints, utxo_backend)
+            .await?
+            .with_context(|| {
+                format!(
+                    "No genesis-based checkpoint below height {}; generate checkpoints or start at 0",
+                    start_height
+                )
+            })?;
+        let hashing = Instant::now();
+        let stats = CoinStats::from_utxo_set(&utxo_store.to_utxo_set()?);
+        println!(
+            "   Hashed {} coins at height {} in {:.1}s",
+            stats.txouts,
+            start_height - 1,
+            hashing.elapsed().as_secs_f64()
+        );
+        (utxo_store, stats)
+    };
+
+    let start = Instant::now();
+    let mut report = CoinStatsReport::default();
+    let mut last_match = None;
+    for height in start_height..=end_height {
+        let block_bytes = get_block_data(block_source, height).await?;
+        let (block, witnesses) = deserialize_block_with_witnesses(&block_bytes)
+            .map_err(|e| anyhow::anyhow!("Failed to deserialize block {}: {}", height, e))?;
+        let hashing = Instant::now();
+        stats.connect_block(&block, height, utxo_store.as_ref())?;
+        report.hashing += hashing.elapsed();
+        if let ValidationResult::Invalid(msg) = utxo_store.connect_block(&block, &witnesses, height, Network::Mainnet)? {
+            anyhow::bail!("BLVM rejected block {}: {}", height, msg);
+        }
+        report.blocks_connected += 1;
+
+        if (height - start_height + 1) % every != 0 && height != end_height {
+            continue;
+        }
+        let core = core_coin_stats(client, height).await?;
+        let blvm = CoreCoinStats {
+            muhash: stats.muhash_hex(),
+            txouts: stats.txouts,
+            total_amount: stats.total_amount,
+        };
+        report.heights_compared += 1;
+        if blvm != core {
+            eprintln!(
+                "❌ Block {}: BLVM muhash={} txouts={} total={}, Core muhash={} txouts={} total={}",
+                height, blvm.muhash, blvm.txouts, blvm.total_amount, core.muhash, core.txouts, core.total_amount
+            );
+            match last_match {
+                Some(last) => eprintln!("   The sets diverged in blocks {}..={}", last + 1, height),
+                None => eprintln!("   The sets already differed at or before block {}", height),
+            }
+            report.mismatch = Some(CoinStatsMismatch {
+                height,
+                last_match,
+                blvm,
+                core,
+            });
+            break;
+        }
+        last_match = Some(height);
+        if report.heights_compared % 1_000 == 0 {
+            println!("   {} heights match (up to {})", report.heights_compared, height);
+        }
+    }
+
+    report.elapsed_secs = start.elapsed().as_secs_f64();
+    println!(
+        "{} {} blocks connected in {:.1}s ({:.1}s hashing), {} heights compared",
+        if report.mismatch.is_none() { "✅" } else { "❌" },
+        report.blocks_connected,
+        report.elapsed_secs,
+        report.hashing.as_secs_f64(),
+        report.heights_compared
+    );
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn element(i: u8) -> [u8; 32] {
+        let mut data = [0u8; 32];
+        data[0] = i;
+        data
+    }
+
+    #[test]
+    fn test_muhash_matches_core_vector() {
+        // Core's muhash_tests: FromInt(0) * FromInt(1) / FromInt(2)
+        let mut muhash = MuHash3072::default();
+        muhash.insert(&element(0));
+        muhash.insert(&element(1));
+        muhash.remove(&element(2));
+        assert_eq!(
+            display_hex(&muhash.finalize()),
+            "10d312b100cbd32ada024a6646e40d3482fcff103668d2625f10002a607d5863"
+        );
+
+        // Order doesn't matter, and a removed element cancels its insert
+        let mut other = MuHash3072::default();
+        other.remove(&element(2));
+        other.insert(&element(3));
+        other.insert(&element(1));
+        other.insert(&element(0));
+        other.remove(&element(3));
+        assert_eq!(other.finalize(), muhash.finalize());
+    }
+
+    #[test]
+    fn test_stats_skip_unspendable_and_bip30_overwritten_coinbases() {
+        let outpoint = OutPoint {
+            hash: [7; 32],
+            index: 0,
+        };
+        let coin = |script: Vec<u8>, height: u64| UTXO {
+            value: 5_000_000_000,
+            script_pubkey: script.into(),
+            height: height as _,
+            is_coinbase: true,
+        };
+        let mut stats = CoinStats::new();
+        stats.add(&outpoint, &coin(vec![0x6a, 0x01], 100));
+        assert_eq!(stats.txouts, 0);
+
+        // Core's index never counts the overwritten coinbases, so a checkpoint holding one adds nothing
+        let mut overwritten = CoinStats::new();
+        overwritten.add(&outpoint, &coin(vec![0x51], 91_722));
+        overwritten.add(&outpoint, &coin(vec![0x51], 91_812));
+        assert_eq!((overwritten.txouts, overwritten.muhash_hex()), (0, CoinStats::new().muhash_hex()));
+
+        // The duplicates are normal coins, hashed with their own height
+        let mut duplicate = CoinStats::new();
+        duplicate.add(&outpoint, &coin(vec![0x51], 91_842));
+        assert_eq!((duplicate.txouts, duplicate.total_amount), (1, 5_000_000_000));
+        let mut at_original_height = MuHash3072::default();
+        at_original_height.insert(&coin_serialization(&outpoint, &coin(vec![0x51], 91_812)));
+        assert_ne!(duplicate.muhash.finalize(), at_original_height.finalize());
+        duplicate.spend(&outpoint, &coin(vec![0x51], 91_842));
+        assert_eq!((duplicate.txouts, duplicate.muhash_hex()), (0, CoinStats::new().muhash_hex()));
+    }
+}
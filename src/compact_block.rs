@@ -0,0 +1,405 @@
+//! Compact Block Reconstruction (BIP152)
+//!
+//! A `cmpctblock` carries the header, a few prefilled transactions (at least
+//! the coinbase) and a 6-byte short ID per remaining transaction. The P2P
+//! client keeps the transactions its peer relays, so most of a new block is
+//! rebuilt from them; whatever is missing is asked for with one `getblocktxn`,
+//! and a block that still doesn't add up (merkle root mismatch after a short ID
+//! collision) is fetched in full. Version 2 (segwit) short IDs only: they are
+//! SipHash-2-4 of the wtxid, keyed by SHA256(header || nonce).
+
+use anyhow::{Context, Result};
+use sha2::{Digest, Sha256};
+use std::collections::{HashMap, HashSet};
+use std::hash::Hasher;
+
+use crate::bench_fixtures::{merkle_root, sha256d, write_compact_size};
+
+/// `getdata` inventory type for a compact block
+pub const MSG_CMPCT_BLOCK: u32 = 4;
+
+/// Core answers `getdata(MSG_CMPCT_BLOCK)` with a full block when the block is deeper than this
+pub const MAX_CMPCTBLOCK_DEPTH: u64 = 5;
+
+fn compact_size(buf: &[u8], pos: &mut usize) -> Result<u64> {
+    let first = *buf.get(*pos).context("Truncated CompactSize")?;
+    let width = match first {
+        0xfd => 2,
+        0xfe => 4,
+        0xff => 8,
+        n => {
+            *pos += 1;
+            return Ok(n as u64);
+        }
+    };
+    let bytes = buf.get(*pos + 1..*pos + 1 + width).context("Truncated CompactSize")?;
+    let mut value = [0u8; 8];
+    value[..width].copy_from_slice(bytes);
+    *pos += 1 + width;
+    Ok(u64::from_le_bytes(value))
+}
+
+fn skip(buf: &[u8], pos: &mut usize, n: u64) -> Result<()> {
+    let end = usize::try_from(n).ok().and_then(|n| pos.checked_add(n)).filter(|&end| end <= buf.len());
+    *pos = end.context("Truncated transaction")?;
+    Ok(())
+}
+
+/// Identity and extent of one serialized transaction
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TxInfo {
+    /// Internal byte order
+    pub txid: [u8; 32],
+    pub wtxid: [u8; 32],
+    /// Serialized length including any witness
+    pub len: usize,
+}
+
+/// Parse the transaction starting at `buf[offset]`
+pub fn parse_tx(buf: &[u8], offset: usize) -> Result<TxInfo> {
+    let mut pos = offset;
+    skip(buf, &mut pos, 4)?; // version
+    let segwit = buf.get(pos..pos + 2) == Some(&[0x00, 0x01][..]);
+    if segwit {
+        pos += 2;
+    }
+    let body_start = pos;
+    let inputs = compact_size(buf, &mut pos)?;
+    for _ in 0..inputs {
+        skip(buf, &mut pos, 36)?;
+        let script_len = compact_size(buf, &mut pos)?;
+        skip(buf, &mut pos, script_len + 4)?;
+    }
+    for _ in 0..compact_size(buf, &mut pos)? {
+        skip(buf, &mut pos, 8)?;
+        let script_len = compact_size(buf, &mut pos)?;
+        skip(buf, &mut pos, script_len)?;
+    }
+    let body_end = pos;
+    if segwit {
+        for _ in 0..inputs {
+            for _ in 0..compact_size(buf, &mut pos)? {
+                let item_len = compact_size(buf, &mut pos)?;
+                skip(buf, &mut pos, item_len)?;
+            }
+        }
+    }
+    skip(buf, &mut pos, 4)?; // lock time
+
+    let wtxid = sha256d(&buf[offset..pos]);
+    let txid = if segwit {
+        sha256d(&[&buf[offset..offset + 4], &buf[body_start..body_end], &buf[pos - 4..pos]].concat())
+    } else {
+        wtxid
+    };
+    Ok(TxInfo {
+        txid,
+        wtxid,
+        len: pos - offset,
+    })
+}
+
+/// `count` transactions back to back from `buf[pos]`, as owned byte strings
+fn parse_txs(buf: &[u8], pos: &mut usize, count: u64) -> Result<Vec<Vec<u8>>> {
+    let mut txs = Vec::new();
+    for _ in 0..count {
+        let info = parse_tx(buf, *pos)?;
+        txs.push(buf[*pos..*pos + info.len].to_vec());
+        *pos += info.len;
+    }
+    Ok(txs)
+}
+
+/// A parsed `cmpctblock` message
+#[derive(Debug, Clone)]
+pub struct CompactBlock {
+    pub header: [u8; 80],
+    pub nonce: u64,
+    pub short_ids: Vec<u64>,
+    /// (index in the block, transaction)
+    pub prefilled: Vec<(usize, Vec<u8>)>,
+}
+
+impl CompactBlock {
+    pub fn parse(payload: &[u8]) -> Result<Self> {
+        let header: [u8; 80] = payload.get(..80).context("Truncated cmpctblock header")?.try_into()?;
+        let nonce = u64::from_le_bytes(payload.get(80..88).context("Truncated cmpctblock nonce")?.try_into()?);
+        let mut pos = 88;
+        let mut short_ids = Vec::new();
+        for _ in 0..compact_size(payload, &mut pos)? {
+            let bytes = payload.get(pos..pos + 6).context("Truncated short IDs")?;
+            let mut id = [0u8; 8];
+            id[..6].copy_from_slice(bytes);
+            short_ids.push(u64::from_le_bytes(id));
+            pos += 6;
+        }
+        let mut prefilled = Vec::new();
+        let mut next_index = 0u64;
+        for _ in 0..compact_size(payload, &mut pos)? {
+            // Indexes are differentially encoded
+            let index = next_index
+                .checked_add(compact_size(payload, &mut pos)?)
+                .filter(|&i| i <= u16::MAX as u64)
+                .context("Prefilled transaction index out of range")?;
+            let tx = parse_txs(payload, &mut pos, 1)?.remove(0);
+            prefilled.push((index as usize, tx));
+            next_index = index + 1;
+        }
+        if pos != payload.len() {
+            anyhow::bail!("{} trailing bytes after cmpctblock", payload.len() - pos);
+        }
+        Ok(Self {
+            header,
+            nonce,
+            short_ids,
+            prefilled,
+        })
+    }
+
+    /// Internal byte order
+    pub fn block_hash(&self) -> [u8; 32] {
+        sha256d(&self.header)
+    }
+
+    pub fn tx_count(&self) -> usize {
+        self.short_ids.len() + self.prefilled.len()
+    }
+
+    /// SipHash keys for this block's short IDs
+    fn short_id_keys(&self) -> (u64, u64) {
+        let mut data = self.header.to_vec();
+        data.extend_from_slice(&self.nonce.to_le_bytes());
+        let hash = Sha256::digest(&data);
+        (
+            u64::from_le_bytes(hash[0..8].try_into().expect("8 bytes")),
+            u64::from_le_bytes(hash[8..16].try_into().expect("8 bytes")),
+        )
+    }
+
+    /// Short ID of a transaction in this block (48 bits of SipHash of its wtxid)
+    pub fn short_id(&self, wtxid: &[u8; 32]) -> u64 {
+        let (k0, k1) = self.short_id_keys();
+        short_id(k0, k1, wtxid)
+    }
+
+    /// Place the prefilled transactions and whatever `pool` (wtxid -> transaction) has
+    pub fn reconstruct<'a>(&self, pool: impl IntoIterator<Item = (&'a [u8; 32], &'a Vec<u8>)>) -> Result<PartialBlock> {
+        let mut slots: Vec<Option<Vec<u8>>> = vec![None; self.tx_count()];
+        for (index, tx) in &self.prefilled {
+            let slot = slots.get_mut(*index).context("Prefilled transaction index past the end of the block")?;
+            *slot = Some(tx.clone());
+        }
+        // The remaining slots take the short IDs in order
+        let mut by_short_id: HashMap<u64, usize> = HashMap::new();
+        let mut short_ids = self.short_ids.iter();
+        for (index, slot) in slots.iter().enumerate() {
+            if slot.is_none() {
+                let id = short_ids.next().context("Fewer short IDs than empty slots")?;
+                if by_short_id.insert(*id, index).is_some() {
+                    // Core gives up on such blocks too and asks for the full block
+                    anyhow::bail!("Duplicate short ID in compact block");
+                }
+            }
+        }
+
+        let (k0, k1) = self.short_id_keys();
+        let mut collided = HashSet::new();
+        let mut from_pool = 0;
+        for (wtxid, tx) in pool {
+            let Some(&index) = by_short_id.get(&short_id(k0, k1, wtxid)) else {
+                continue;
+            };
+            if slots[index].is_some() {
+                // Two pool transactions share the short ID: ask the peer which one
+                collided.insert(index);
+                continue;
+            }
+            slots[index] = Some(tx.clone());
+            from_pool += 1;
+        }
+        for index in &collided {
+            slots[*index] = None;
+        }
+        Ok(PartialBlock {
+            header: self.header,
+            from_pool: from_pool - collided.len(),
+            slots,
+        })
+    }
+}
+
+fn short_id(k0: u64, k1: u64, wtxid: &[u8; 32]) -> u64 {
+    let mut hasher = siphasher::sip::SipHasher24::new_with_keys(k0, k1);
+    hasher.write(wtxid);
+    hasher.finish() & 0xffff_ffff_ffff
+}
+
+/// A block being rebuilt from a compact block
+#[derive(Debug, Clone)]
+pub struct PartialBlock {
+    header: [u8; 80],
+    slots: Vec<Option<Vec<u8>>>,
+    /// Transactions taken from the pool
+    pub from_pool: usize,
+}
+
+impl PartialBlock {
+    /// Indexes still missing, in block order
+    pub fn missing(&self) -> Vec<usize> {
+        self.slots
+            .iter()
+            .enumerate()
+            .filter(|(_, slot)| slot.is_none())
+            .map(|(index, _)| index)
+            .collect()
+    }
+
+    /// Fill the missing slots, in `missing()` order, with a `blocktxn` answer
+    pub fn fill(&mut self, txs: Vec<Vec<u8>>) -> Result<()> {
+        let missing = self.missing();
+        if txs.len() != missing.len() {
+            anyhow::bail!("blocktxn has {} transactions for {} missing", txs.len(), missing.len());
+        }
+        for (index, tx) in missing.into_iter().zip(txs) {
+            self.slots[index] = Some(tx);
+        }
+        Ok(())
+    }
+
+    /// The serialized block, once every slot is filled and the merkle root matches the header
+    pub fn into_block(self) -> Result<Vec<u8>> {
+        let txs: Vec<Vec<u8>> = self
+            .slots
+            .into_iter()
+            .collect::<Option<_>>()
+            .context("Compact block still has missing transactions")?;
+        let txids = txs.iter().map(|tx| parse_tx(tx, 0).map(|info| info.txid)).collect::<Result<Vec<_>>>()?;
+        if merkle_root(txids)[..] != self.header[36..68] {
+            anyhow::bail!("Reconstructed block doesn't match its merkle root (short ID collision?)");
+        }
+        let mut block = self.header.to_vec();
+        write_compact_size(&mut block, txs.len() as u64);
+        for tx in txs {
+            block.extend_from_slice(&tx);
+        }
+        Ok(block)
+    }
+}
+
+/// `getblocktxn` payload asking for `indexes` (ascending) of `block_hash`
+pub fn getblocktxn_payload(block_hash: &[u8; 32], indexes: &[usize]) -> Vec<u8> {
+    let mut payload = block_hash.to_vec();
+    write_compact_size(&mut payload, indexes.len() as u64);
+    let mut next = 0;
+    for &index in indexes {
+        write_compact_size(&mut payload, (index - next) as u64);
+        next = index + 1;
+    }
+    payload
+}
+
+/// Block hash and transactions of a `blocktxn` message
+pub fn parse_blocktxn(payload: &[u8]) -> Result<([u8; 32], Vec<Vec<u8>>)> {
+    let hash: [u8; 32] = payload.get(..32).context("Truncated blocktxn")?.try_into()?;
+    let mut pos = 32;
+    let count = compact_size(payload, &mut pos)?;
+    let txs = parse_txs(payload, &mut pos, count)?;
+    Ok((hash, txs))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// One input, one output; `witness` adds the segwit marker and a single-item witness
+    fn tx(tag: u8, witness: bool) -> Vec<u8> {
+        let mut tx = 2u32.to_le_bytes().to_vec();
+        if witness {
+            tx.extend_from_slice(&[0x00, 0x01]);
+        }
+        tx.push(1);
+        tx.extend_from_slice(&[tag; 32]);
+        tx.extend_from_slice(&0u32.to_le_bytes());
+        tx.push(0); // scriptSig
+        tx.extend_from_slice(&u32::MAX.to_le_bytes());
+        tx.push(1);
+        tx.extend_from_slice(&1000u64.to_le_bytes());
+        tx.extend_from_slice(&[1, 0x51]);
+        if witness {
+            tx.extend_from_slice(&[1, 2, tag, tag]);
+        }
+        tx.extend_from_slice(&0u32.to_le_bytes());
+        tx
+    }
+
+    fn cmpctblock(header: &[u8; 80], nonce: u64, short_ids: &[u64], prefilled: &[(u64, &[u8])]) -> Vec<u8> {
+        let mut payload = header.to_vec();
+        payload.extend_from_slice(&nonce.to_le_bytes());
+        write_compact_size(&mut payload, short_ids.len() as u64);
+        for id in short_ids {
+            payload.extend_from_slice(&id.to_le_bytes()[..6]);
+        }
+        write_compact_size(&mut payload, prefilled.len() as u64);
+        for (diff, tx) in prefilled {
+            write_compact_size(&mut payload, *diff);
+            payload.extend_from_slice(tx);
+        }
+        payload
+    }
+
+    #[test]
+    fn test_parse_tx_strips_witness_for_txid() {
+        let (legacy, segwit) = (tx(7, false), tx(7, true));
+        let legacy_info = parse_tx(&legacy, 0).unwrap();
+        let segwit_info = parse_tx(&segwit, 0).unwrap();
+        assert_eq!(legacy_info.txid, segwit_info.txid);
+        assert_eq!(legacy_info.txid, legacy_info.wtxid);
+        assert_ne!(segwit_info.txid, segwit_info.wtxid);
+        assert_eq!(segwit_info.len, segwit.len());
+        assert!(parse_tx(&segwit[..segwit.len() - 1], 0).is_err());
+    }
+
+    #[test]
+    fn test_reconstruct_from_pool_and_blocktxn() {
+        let txs = [tx(0, false), tx(1, true), tx(2, true), tx(3, false)];
+        let infos: Vec<TxInfo> = txs.iter().map(|t| parse_tx(t, 0).unwrap()).collect();
+        let mut header = [0u8; 80];
+        header[36..68].copy_from_slice(&merkle_root(infos.iter().map(|i| i.txid).collect()));
+
+        let keys = CompactBlock {
+            header,
+            nonce: 42,
+            short_ids: Vec::new(),
+            prefilled: Vec::new(),
+        };
+        let ids: Vec<u64> = infos[1..].iter().map(|i| keys.short_id(&i.wtxid)).collect();
+        let compact = CompactBlock::parse(&cmpctblock(&header, 42, &ids, &[(0, &txs[0])])).unwrap();
+        assert_eq!((compact.tx_count(), compact.prefilled[0].0), (4, 0));
+
+        // The pool has txs 1 and 3 plus an unrelated one
+        let pool: HashMap<[u8; 32], Vec<u8>> = [&txs[1], &txs[3], &tx(9, true)]
+            .into_iter()
+            .map(|t| (parse_tx(t, 0).unwrap().wtxid, t.clone()))
+            .collect();
+        let mut partial = compact.reconstruct(&pool).unwrap();
+        assert_eq!((partial.missing(), partial.from_pool), (vec![2], 2));
+
+        let request = getblocktxn_payload(&compact.block_hash(), &partial.missing());
+        assert_eq!(request[32..], [1, 2]);
+        let mut answer = compact.block_hash().to_vec();
+        answer.push(1);
+        answer.extend_from_slice(&txs[2]);
+        let (hash, fetched) = parse_blocktxn(&answer).unwrap();
+        assert_eq!(hash, compact.block_hash());
+        partial.fill(fetched).unwrap();
+
+        let block = partial.into_block().unwrap();
+        assert_eq!(block[..80], header);
+        assert_eq!(block[81..], txs.concat());
+
+        // A wrong transaction in a slot fails the merkle check
+        let mut wrong = compact.reconstruct(&pool).unwrap();
+        wrong.fill(vec![tx(8, true)]).unwrap();
+        assert!(wrong.into_block().is_err());
+    }
+}
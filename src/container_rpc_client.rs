@@ -0,0 +1,205 @@
+//! Container RPC Client (podman/docker exec)
+//!
+//! `Start9Session` reaches bitcoind's in-container RPC port by entering the
+//! container's namespaces with `nsenter`, which needs root on the host and
+//! bitcoind's host PID. `ContainerRpcClient` opens the same long-lived shell
+//! with `podman exec -i <container> sh` (or `docker exec`) instead, which only
+//! needs access to the container runtime: rootless podman, membership of the
+//! `docker` group, or a sudo rule for the runtime alone
+//! (`BLVM_CONTAINER_RUNTIME="sudo -n podman"`). Batching and everything else is
+//! the session's, so it is used as a `Start9Rpc` source.
+//!
+//! Configuration (plus the session's `BLVM_START9_RPC_URL`, `BLVM_START9_COOKIE`, `BLVM_START9_BATCH`):
+//! - `BLVM_CONTAINER` - container name or ID (default: the one running container whose name or image mentions bitcoin)
+//! - `BLVM_CONTAINER_RUNTIME` - runtime command (default: `podman`, then `docker`, whichever lists the container)
+
+use anyhow::{Context, Result};
+use std::sync::Arc;
+
+use crate::start9_session::{Start9Config, Start9Session};
+
+/// Runtimes tried in order when `BLVM_CONTAINER_RUNTIME` isn't set
+const DEFAULT_RUNTIMES: [&str; 2] = ["podman", "docker"];
+
+/// Where the node runs: a container runtime command and a container in it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ContainerTarget {
+    /// Program and leading arguments, e.g. `["podman"]` or `["sudo", "docker"]`
+    pub runtime: Vec<String>,
+    pub container: String,
+}
+
+impl ContainerTarget {
+    /// Whether the container was chosen explicitly (`BLVM_CONTAINER`)
+    pub fn configured() -> bool {
+        std::env::var("BLVM_CONTAINER").is_ok()
+    }
+
+    /// The target from `BLVM_CONTAINER`/`BLVM_CONTAINER_RUNTIME`, detecting what isn't set
+    pub fn from_env() -> Result<Self> {
+        let runtimes: Vec<Vec<String>> = match std::env::var("BLVM_CONTAINER_RUNTIME") {
+            Ok(command) => {
+                let runtime: Vec<String> = command.split_whitespace().map(String::from).collect();
+                if runtime.is_empty() {
+                    anyhow::bail!("BLVM_CONTAINER_RUNTIME is empty");
+                }
+                vec![runtime]
+            }
+            Err(_) => DEFAULT_RUNTIMES.iter().map(|r| vec![r.to_string()]).collect(),
+        };
+        let wanted = std::env::var("BLVM_CONTAINER").ok();
+
+        let mut tried = Vec::new();
+        for runtime in runtimes {
+            let listing = match list_containers(&runtime) {
+                Ok(listing) => listing,
+                Err(e) => {
+                    tried.push(format!("{}: {:#}", runtime.join(" "), e));
+                    continue;
+                }
+            };
+            let container = match &wanted {
+                Some(wanted) if lists_container(&listing, wanted) => Ok(wanted.clone()),
+                Some(wanted) => Err(anyhow::anyhow!("no running container {}", wanted)),
+                None => pick_bitcoind_container(&listing),
+            };
+            match container {
+                Ok(container) => return Ok(Self { runtime, container }),
+                Err(e) => tried.push(format!("{}: {:#}", runtime.join(" "), e)),
+            }
+        }
+        anyhow::bail!(
+            "No bitcoind container found (set BLVM_CONTAINER / BLVM_CONTAINER_RUNTIME). Tried:\n  {}",
+            tried.join("\n  ")
+        )
+    }
+
+    /// Command that opens a shell in the container with stdin attached
+    pub fn exec_shell(&self) -> Vec<String> {
+        let mut shell = self.runtime.clone();
+        shell.extend(["exec", "-i", &self.container, "sh"].map(String::from));
+        shell
+    }
+}
+
+/// `<runtime> ps` as `name<TAB>image<TAB>id` lines (the same format flag works for podman and docker)
+fn list_containers(runtime: &[String]) -> Result<String> {
+    let output = std::process::Command::new(&runtime[0])
+        .args(&runtime[1..])
+        .args(["ps", "--format", "{{.Names}}\t{{.Image}}\t{{.ID}}"])
+        .output()
+        .with_context(|| format!("Failed to run {}", runtime[0]))?;
+    if !output.status.success() {
+        anyhow::bail!("`ps` failed: {}", String::from_utf8_lossy(&output.stderr).trim());
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// Whether a `ps` listing has a container with this name or (abbreviated) ID
+fn lists_container(listing: &str, wanted: &str) -> bool {
+    listing.lines().any(|line| {
+        let fields: Vec<&str> = line.split('\t').map(str::trim).collect();
+        fields.first() == Some(&wanted) || fields.get(2).is_some_and(|id| !wanted.is_empty() && id.starts_with(wanted))
+    })
+}
+
+/// The bitcoind container in a `ps` listing
+///
+/// A name mentioning bitcoind wins over one only mentioning bitcoin (StartOS also
+/// runs e.g. `btc-rpc-proxy` and electrs containers next to the node).
+pub fn pick_bitcoind_container(listing: &str) -> Result<String> {
+    let containers: Vec<(&str, String)> = listing
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split('\t');
+            let name = fields.next()?.trim();
+            let image = fields.next().unwrap_or("").to_ascii_lowercase();
+            (!name.is_empty()).then_some((name, image))
+        })
+        .collect();
+    let matching = |pattern: &str| -> Vec<&str> {
+        containers
+            .iter()
+            .filter(|(name, image)| name.to_ascii_lowercase().contains(pattern) || image.contains(pattern))
+            .map(|(name, _)| *name)
+            .collect()
+    };
+    let mut candidates = matching("bitcoind");
+    if candidates.is_empty() {
+        candidates = matching("bitcoin");
+    }
+    match candidates.as_slice() {
+        [name] => Ok(name.to_string()),
+        [] => anyhow::bail!("none of {} running containers looks like bitcoind", containers.len()),
+        names => anyhow::bail!("several containers look like bitcoind ({}); set BLVM_CONTAINER", names.join(", ")),
+    }
+}
+
+/// JSON-RPC to bitcoind through `<runtime> exec` into its container
+pub struct ContainerRpcClient {
+    target: ContainerTarget,
+    session: Arc<Start9Session>,
+}
+
+impl ContainerRpcClient {
+    pub fn new(target: ContainerTarget) -> Result<Self> {
+        let config = Start9Config::with_shell(target.exec_shell())?;
+        Ok(Self {
+            target,
+            session: Arc::new(Start9Session::new(config)),
+        })
+    }
+
+    pub fn from_env() -> Result<Self> {
+        Self::new(ContainerTarget::from_env()?)
+    }
+
+    pub fn target(&self) -> &ContainerTarget {
+        &self.target
+    }
+
+    /// The RPC session over the exec'd shell (a `Start9Rpc` block source)
+    pub fn session(&self) -> Arc<Start9Session> {
+        self.session.clone()
+    }
+
+    /// Check the shell opens and bitcoind answers; returns its block count
+    pub async fn probe(&self) -> Result<u64> {
+        self.session
+            .get_block_count()
+            .await
+            .with_context(|| format!("No RPC answer through `{}`", self.target.exec_shell().join(" ")))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pick_bitcoind_container() {
+        let listing = "electrs.embassy\tstart9/electrs/main:0.10.1\n\
+                       btc-rpc-proxy.embassy\tstart9/btc-rpc-proxy/main:0.3.2\n\
+                       bitcoind.embassy\tstart9/bitcoind/main:25.0.0\n";
+        assert_eq!(pick_bitcoind_container(listing).unwrap(), "bitcoind.embassy");
+
+        // Plain docker setups: only the image says what it is
+        assert_eq!(pick_bitcoind_container("node\tlncm/bitcoind:v26.0\n").unwrap(), "node");
+        assert!(pick_bitcoind_container("a\tbitcoin/bitcoin:27\nb\tbitcoin/bitcoin:28\n").is_err());
+        assert!(pick_bitcoind_container("web\tnginx\n").is_err());
+        assert!(pick_bitcoind_container("").is_err());
+
+        assert!(lists_container("node\tlncm/bitcoind:v26.0\t3f2a9c01d4e5\n", "node"));
+        assert!(lists_container("node\tlncm/bitcoind:v26.0\t3f2a9c01d4e5\n", "3f2a9c"));
+        assert!(!lists_container("node\tlncm/bitcoind:v26.0\t3f2a9c01d4e5\n", "bitcoind"));
+    }
+
+    #[test]
+    fn test_exec_shell() {
+        let target = ContainerTarget {
+            runtime: vec!["sudo".to_string(), "podman".to_string()],
+            container: "bitcoind.embassy".to_string(),
+        };
+        assert_eq!(target.exec_shell().join(" "), "sudo podman exec -i bitcoind.embassy sh");
+    }
+}
@@ -0,0 +1,231 @@
+//! Side-by-side comparison with Bitcoin Core's bench_bitcoin
+//!
+//! Parses `bench_bitcoin` output (the default markdown table or `-output-csv`),
+//! maps Core benchmark names onto equivalent blvm-bench criterion ids, and
+//! renders a relative performance table per operation.
+
+use anyhow::{Context, Result};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use crate::bench_report::BenchReport;
+
+/// One Core benchmark result, normalized to nanoseconds per unit
+#[derive(Debug, Clone, PartialEq)]
+pub struct CoreBench {
+    pub ns_per_unit: f64,
+    /// Unit from the table header (`op`, `byte`, `block`, ...)
+    pub unit: String,
+}
+
+/// Core benchmark -> blvm-bench equivalent
+pub struct BenchMapping {
+    pub core_name: &'static str,
+    pub blvm_id: &'static str,
+    /// Operations a single blvm iteration performs (e.g. 100 spends per block)
+    pub blvm_ops_per_iter: f64,
+}
+
+/// Known equivalences; only pairs measuring the same work belong here
+pub const MAPPINGS: &[BenchMapping] = &[
+    BenchMapping {
+        core_name: "VerifyScriptP2WPKH",
+        blvm_id: "signature_verification/connect_block/p2wpkh",
+        blvm_ops_per_iter: 100.0,
+    },
+    BenchMapping {
+        core_name: "VerifyScriptP2TR_KeyPath",
+        blvm_id: "signature_verification/connect_block/taproot_keypath",
+        blvm_ops_per_iter: 100.0,
+    },
+    BenchMapping {
+        core_name: "VerifyScriptP2TR_ScriptPath",
+        blvm_id: "signature_verification/connect_block/taproot_scriptpath",
+        blvm_ops_per_iter: 100.0,
+    },
+    BenchMapping {
+        core_name: "ConnectBlockAllEcdsa",
+        blvm_id: "connect_block_realistic_1000tx",
+        blvm_ops_per_iter: 1.0,
+    },
+    BenchMapping {
+        core_name: "TransactionIdCalculation",
+        blvm_id: "transaction_id_calculation",
+        blvm_ops_per_iter: 1.0,
+    },
+    BenchMapping {
+        core_name: "TransactionSerialization",
+        blvm_id: "transaction_serialization",
+        blvm_ops_per_iter: 1.0,
+    },
+    BenchMapping {
+        core_name: "TransactionSighashCalculation",
+        blvm_id: "sighash_legacy/all_inputs/1",
+        blvm_ops_per_iter: 1.0,
+    },
+    // Core's block benches decode mainnet block 413567 (extract it into the corpus)
+    BenchMapping {
+        core_name: "DeserializeBlockTest",
+        blvm_id: "block_accounting/deserialize/413567",
+        blvm_ops_per_iter: 1.0,
+    },
+    BenchMapping {
+        core_name: "DeserializeAndCheckBlockTest",
+        blvm_id: "block_accounting/deserialize_and_check/413567",
+        blvm_ops_per_iter: 1.0,
+    },
+];
+
+/// Parse bench_bitcoin output, auto-detecting table vs. CSV format
+pub fn parse_bench_bitcoin(output: &str) -> BTreeMap<String, CoreBench> {
+    if output.lines().any(|l| l.trim_start().starts_with("# Benchmark")) {
+        parse_csv(output)
+    } else {
+        parse_table(output)
+    }
+}
+
+/// `|   ns/op |   op/s |  err% |  total | benchmark` followed by rows whose last
+/// column is the backquoted name; extra perf-counter columns are ignored
+fn parse_table(output: &str) -> BTreeMap<String, CoreBench> {
+    let mut results = BTreeMap::new();
+    let mut unit = "op".to_string();
+
+    for line in output.lines() {
+        let line = line.trim();
+        if !line.starts_with('|') {
+            continue;
+        }
+        let cols: Vec<&str> = line.trim_matches('|').split('|').map(str::trim).collect();
+        let Some(first) = cols.first() else { continue };
+
+        if let Some(header_unit) = first.strip_prefix("ns/") {
+            unit = header_unit.to_string();
+            continue;
+        }
+        let Some(name) = cols.last().map(|c| c.trim_matches('`')) else { continue };
+        if name.is_empty() || name.starts_with('-') || name.starts_with(':') {
+            continue;
+        }
+        if let Ok(ns) = first.replace(',', "").parse::<f64>() {
+            results.insert(
+                name.to_string(),
+                CoreBench {
+                    ns_per_unit: ns,
+                    unit: unit.clone(),
+                },
+            );
+        }
+    }
+    results
+}
+
+/// `# Benchmark, evals, iterations, total, min, max, median` (times in seconds)
+fn parse_csv(output: &str) -> BTreeMap<String, CoreBench> {
+    let mut results = BTreeMap::new();
+    for line in output.lines() {
+        if line.trim_start().starts_with('#') {
+            continue;
+        }
+        let cols: Vec<&str> = line.split(',').map(str::trim).collect();
+        if cols.len() < 7 {
+            continue;
+        }
+        if let Ok(median_secs) = cols[6].parse::<f64>() {
+            results.insert(
+                cols[0].to_string(),
+                CoreBench {
+                    ns_per_unit: median_secs * 1e9,
+                    unit: "op".to_string(),
+                },
+            );
+        }
+    }
+    results
+}
+
+/// One row of the comparison table
+#[derive(Debug, Clone, Serialize)]
+pub struct ComparisonRow {
+    pub core_name: String,
+    pub blvm_id: String,
+    pub unit: String,
+    pub core_ns: f64,
+    pub blvm_ns: f64,
+    /// blvm / core; below 1.0 means BLVM is faster
+    pub ratio: f64,
+}
+
+/// Pair up mapped benchmarks present in both result sets
+pub fn compare_with_core(core: &BTreeMap<String, CoreBench>, blvm: &BenchReport) -> Vec<ComparisonRow> {
+    MAPPINGS
+        .iter()
+        .filter_map(|m| {
+            let core_bench = core.get(m.core_name)?;
+            let blvm_bench = blvm.benchmarks.get(m.blvm_id)?;
+            let blvm_ns = blvm_bench.mean_ns / m.blvm_ops_per_iter;
+            Some(ComparisonRow {
+                core_name: m.core_name.to_string(),
+                blvm_id: m.blvm_id.to_string(),
+                unit: core_bench.unit.clone(),
+                core_ns: core_bench.ns_per_unit,
+                blvm_ns,
+                ratio: blvm_ns / core_bench.ns_per_unit,
+            })
+        })
+        .collect()
+}
+
+/// Render rows as a markdown table
+pub fn to_markdown(rows: &[ComparisonRow]) -> String {
+    let mut out = String::from("| Operation | Core (ns/unit) | BLVM (ns/unit) | BLVM / Core |\n");
+    out.push_str("|---|---:|---:|---:|\n");
+    for row in rows {
+        out.push_str(&format!(
+            "| {} (`{}`) | {:.1} | {:.1} | {:.2}x |\n",
+            row.core_name, row.blvm_id, row.core_ns, row.blvm_ns, row.ratio
+        ));
+    }
+    out
+}
+
+/// Load Core output from a file and compare against a bench_results.json
+pub fn compare_files(core_output: &Path, blvm_results: &Path) -> Result<Vec<ComparisonRow>> {
+    let core_text = std::fs::read_to_string(core_output)
+        .with_context(|| format!("Failed to read {}", core_output.display()))?;
+    let core = parse_bench_bitcoin(&core_text);
+    if core.is_empty() {
+        anyhow::bail!("No benchmarks found in {}", core_output.display());
+    }
+    Ok(compare_with_core(&core, &BenchReport::load(blvm_results)?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_table() {
+        let output = "\
+|               ns/op |                op/s |    err% |     total | benchmark
+|--------------------:|--------------------:|--------:|----------:|:----------
+|            1,234.50 |              810.04 |    0.3% |      0.01 | `VerifyScriptP2WPKH`
+|               42.00 |       23,809,523.81 |    0.1% |      0.01 | `TransactionIdCalculation`
+";
+        let parsed = parse_bench_bitcoin(output);
+        assert_eq!(parsed.len(), 2);
+        assert_eq!(parsed["VerifyScriptP2WPKH"].ns_per_unit, 1234.5);
+        assert_eq!(parsed["TransactionIdCalculation"].unit, "op");
+    }
+
+    #[test]
+    fn test_parse_csv() {
+        let output = "\
+# Benchmark, evals, iterations, total, min, max, median
+VerifyScriptP2WPKH, 5, 1000, 0.0061, 1.2e-06, 1.3e-06, 1.25e-06
+";
+        let parsed = parse_bench_bitcoin(output);
+        assert!((parsed["VerifyScriptP2WPKH"].ns_per_unit - 1250.0).abs() < 1e-6);
+    }
+}
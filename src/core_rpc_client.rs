@@ -6,7 +6,9 @@
 use anyhow::{Context, Result};
 use reqwest::Client;
 use serde_json::Value;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::RwLock;
 use std::time::Duration;
 
 /// RPC client configuration
@@ -20,6 +22,100 @@ pub struct RpcConfig {
     pub pass: String,
     /// Request timeout
     pub timeout: Duration,
+    /// Retry policy for transient failures
+    pub retry: RetryPolicy,
+    /// Core's `.cookie` file; when set, credentials are read from it (and
+    /// re-read on HTTP 401, since Core rewrites it on every restart)
+    pub cookie_file: Option<PathBuf>,
+}
+
+/// Retry with exponential backoff and jitter for transient RPC failures
+/// (connection errors, timeouts, HTTP 502/503/504, -28 warming up)
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    /// Retries after the first attempt (0 = fail immediately)
+    pub max_retries: u32,
+    /// Delay before the first retry; doubles each attempt
+    pub initial_backoff: Duration,
+    /// Upper bound on a single delay
+    pub max_backoff: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_retries: 8,
+            initial_backoff: Duration::from_millis(500),
+            max_backoff: Duration::from_secs(60),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// No retries (used for discovery probes, where failing fast is the point)
+    pub fn none() -> Self {
+        Self {
+            max_retries: 0,
+            ..Self::default()
+        }
+    }
+
+    /// Defaults, overridden by `BLVM_RPC_MAX_RETRIES` / `BLVM_RPC_RETRY_BACKOFF_MS`
+    pub fn from_env() -> Self {
+        Self::from_vars(|name| std::env::var(name).ok())
+    }
+
+    /// `from_env` over an arbitrary variable lookup; an unparseable value is
+    /// reported and its default kept
+    fn from_vars(var: impl Fn(&str) -> Option<String>) -> Self {
+        fn parse<T: std::str::FromStr>(var: &dyn Fn(&str) -> Option<String>, key: &str) -> Option<T> {
+            let value = var(key)?;
+            let parsed = value.trim().parse().ok();
+            if parsed.is_none() {
+                eprintln!("⚠️  {}={} is not a valid value, using the default", key, value);
+            }
+            parsed
+        }
+        let mut policy = Self::default();
+        if let Some(n) = parse(&var, "BLVM_RPC_MAX_RETRIES") {
+            policy.max_retries = n;
+        }
+        if let Some(ms) = parse(&var, "BLVM_RPC_RETRY_BACKOFF_MS") {
+            policy.initial_backoff = Duration::from_millis(ms);
+        }
+        policy
+    }
+
+    /// Upper bound of the delay before retry number `attempt` (0-based)
+    fn backoff_cap(&self, attempt: u32) -> Duration {
+        self.initial_backoff
+            .saturating_mul(2u32.saturating_pow(attempt))
+            .min(self.max_backoff)
+    }
+
+    /// Delay before retry `attempt`, jittered into [cap/2, cap] so parallel
+    /// workers don't hammer a recovering node in lockstep
+    fn backoff(&self, attempt: u32) -> Duration {
+        use rand::Rng;
+        let cap = self.backoff_cap(attempt);
+        cap / 2 + cap.mul_f64(rand::thread_rng().gen_range(0.0..=0.5))
+    }
+}
+
+/// Core's RPC_IN_WARMUP error code
+const RPC_IN_WARMUP: i64 = -28;
+
+/// Whether an RPC failure is worth retrying, from the HTTP status and JSON-RPC error code
+fn is_transient(http_status: u16, rpc_code: Option<i64>) -> bool {
+    rpc_code == Some(RPC_IN_WARMUP) || matches!(http_status, 502..=504)
+}
+
+/// A failed attempt, classified for the retry loop
+enum RpcFailure {
+    Transient(anyhow::Error),
+    Permanent(anyhow::Error),
+    /// HTTP 401 - stale cookie or wrong credentials
+    Unauthorized(anyhow::Error),
 }
 
 impl RpcConfig {
@@ -30,6 +126,8 @@ impl RpcConfig {
             user: node.rpc_user().to_owned(),
             pass: node.rpc_pass().to_owned(),
             timeout: Duration::from_secs(30),
+            retry: RetryPolicy::from_env(),
+            cookie_file: None,
         }
     }
 
@@ -38,34 +136,67 @@ impl RpcConfig {
     /// Environment variables:
     /// - `BITCOIN_RPC_HOST` (default: "127.0.0.1")
     /// - `BITCOIN_RPC_PORT` (default: 8332 for mainnet, 18443 for regtest)
-    /// - `BITCOIN_RPC_USER` (default: "test")
-    /// - `BITCOIN_RPC_PASSWORD` (default: "test")
-    /// - `BITCOIN_NETWORK` (default: "mainnet") - used to determine default port
+    /// - `BITCOIN_RPC_USER` / `BITCOIN_RPC_PASSWORD` (default: Core's `.cookie`
+    ///   if one is found, otherwise "test"/"test")
+    /// - `BITCOIN_RPC_COOKIE` - explicit cookie file path
+    /// - `BITCOIN_DATA_DIR` or `BITCOIN_DATADIR` (default: Core's per-OS datadir) - where to look for the cookie
+    /// - `BITCOIN_NETWORK` (default: "mainnet") - used to determine default port and cookie subdirectory
+    /// - `BLVM_RPC_MAX_RETRIES` / `BLVM_RPC_RETRY_BACKOFF_MS` - see `RetryPolicy`
     pub fn from_env() -> Self {
-        let rpc_host =
-            std::env::var("BITCOIN_RPC_HOST").unwrap_or_else(|_| "127.0.0.1".to_string());
-
-        let rpc_user = std::env::var("BITCOIN_RPC_USER").unwrap_or_else(|_| "test".to_string());
-
-        let rpc_pass = std::env::var("BITCOIN_RPC_PASSWORD").unwrap_or_else(|_| "test".to_string());
-
-        // Determine default port based on network
-        let default_port = match std::env::var("BITCOIN_NETWORK")
+        let network = match std::env::var("BITCOIN_NETWORK")
             .ok()
             .as_ref()
             .map(|s| s.as_str())
         {
-            Some("mainnet") | Some("main") => 8332,
-            Some("testnet") | Some("test") => 18332,
-            Some("regtest") => 18443,
-            Some("signet") => 38332,
-            _ => 8332, // Default to mainnet
+            Some("testnet") | Some("test") => BitcoinNetwork::Testnet,
+            Some("regtest") => BitcoinNetwork::Regtest,
+            Some("signet") => BitcoinNetwork::Signet,
+            _ => BitcoinNetwork::Mainnet, // Default to mainnet
         };
+        Self::from_vars(network, |name| std::env::var(name).ok())
+    }
 
-        let rpc_port = std::env::var("BITCOIN_RPC_PORT")
-            .ok()
+    /// Create for one chain of a multi-network run
+    ///
+    /// Each `BITCOIN_RPC_*` variable is first looked up with the network as a
+    /// suffix (e.g. `BITCOIN_RPC_PORT_TESTNET`). Host and credentials fall back
+    /// to the plain variables; port and cookie, which differ per chain, to the
+    /// network's defaults.
+    pub fn for_network(network: BitcoinNetwork) -> Self {
+        let suffix = network.as_str().to_ascii_uppercase();
+        Self::from_vars(network, |name| {
+            std::env::var(format!("{}_{}", name, suffix)).ok().or_else(|| match name {
+                "BITCOIN_RPC_PORT" | "BITCOIN_RPC_COOKIE" => None,
+                _ => std::env::var(name).ok(),
+            })
+        })
+    }
+
+    fn from_vars(network: BitcoinNetwork, var: impl Fn(&str) -> Option<String>) -> Self {
+        let rpc_host = var("BITCOIN_RPC_HOST").unwrap_or_else(|| "127.0.0.1".to_string());
+
+        let env_user = var("BITCOIN_RPC_USER");
+        let env_pass = var("BITCOIN_RPC_PASSWORD");
+
+        // Explicit credentials win; otherwise fall back to the cookie a default Core setup writes
+        let cookie_file = if env_user.is_none() && env_pass.is_none() {
+            var("BITCOIN_RPC_COOKIE")
+                .map(PathBuf::from)
+                .or_else(|| {
+                    let datadir = crate::datadir::datadir_override().or_else(default_datadir)?;
+                    Some(cookie_path(&datadir, network))
+                })
+                .filter(|p| p.exists())
+        } else {
+            None
+        };
+
+        let rpc_user = env_user.unwrap_or_else(|| "test".to_string());
+        let rpc_pass = env_pass.unwrap_or_else(|| "test".to_string());
+
+        let rpc_port = var("BITCOIN_RPC_PORT")
             .and_then(|p| p.parse::<u16>().ok())
-            .unwrap_or(default_port);
+            .unwrap_or(network.default_rpc_port());
 
         let url = format!("http://{}:{}", rpc_host, rpc_port);
 
@@ -74,6 +205,8 @@ impl RpcConfig {
             user: rpc_user,
             pass: rpc_pass,
             timeout: Duration::from_secs(30),
+            retry: RetryPolicy::from_env(),
+            cookie_file,
         }
     }
 
@@ -84,14 +217,52 @@ impl RpcConfig {
             user,
             pass,
             timeout: Duration::from_secs(30),
+            retry: RetryPolicy::from_env(),
+            cookie_file: None,
         }
     }
+
+    /// Authenticate with a cookie file instead of user/password
+    pub fn with_cookie_file(mut self, path: impl Into<PathBuf>) -> Self {
+        self.cookie_file = Some(path.into());
+        self
+    }
+}
+
+/// Default Core data directory (`~/.bitcoin`, or the macOS/Windows equivalent; see `datadir`)
+pub fn default_datadir() -> Option<PathBuf> {
+    crate::datadir::default_datadir()
+}
+
+/// Cookie location for `network` under a datadir (`<datadir>/<network subdir>/.cookie`)
+pub fn cookie_path(datadir: &Path, network: BitcoinNetwork) -> PathBuf {
+    match network.data_subdir() {
+        Some(subdir) => datadir.join(subdir).join(".cookie"),
+        None => datadir.join(".cookie"),
+    }
+}
+
+/// Parse `user:password` from a cookie file's contents
+fn parse_cookie(content: &str) -> Option<(String, String)> {
+    let (user, pass) = content.trim().split_once(':')?;
+    Some((user.to_string(), pass.to_string()))
+}
+
+/// Read credentials from a cookie file
+pub fn read_cookie(path: &Path) -> Result<(String, String)> {
+    let content = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read RPC cookie {}", path.display()))?;
+    parse_cookie(&content).with_context(|| format!("Malformed RPC cookie {}", path.display()))
 }
 
 /// Bitcoin Core RPC client
 pub struct CoreRpcClient {
     client: Client,
     config: RpcConfig,
+    /// (user, password) - replaced when the cookie is re-read
+    credentials: RwLock<(String, String)>,
+    /// Calls Core turned away with HTTP 503 (its RPC work queue was full)
+    work_queue_rejections: AtomicU64,
 }
 
 impl CoreRpcClient {
@@ -102,11 +273,89 @@ impl CoreRpcClient {
             .build()
             .expect("Failed to create HTTP client");
 
-        Self { client, config }
+        let credentials = match &config.cookie_file {
+            Some(path) => read_cookie(path).unwrap_or_else(|e| {
+                eprintln!("⚠️  {:#}; using configured user/password", e);
+                (config.user.clone(), config.pass.clone())
+            }),
+            None => (config.user.clone(), config.pass.clone()),
+        };
+
+        Self {
+            client,
+            config,
+            credentials: RwLock::new(credentials),
+            work_queue_rejections: AtomicU64::new(0),
+        }
+    }
+
+    /// RPC endpoint URL
+    pub fn url(&self) -> &str {
+        &self.config.url
+    }
+
+    /// Calls rejected for a full work queue so far, retried ones included
+    pub fn work_queue_rejections(&self) -> u64 {
+        self.work_queue_rejections.load(Ordering::Relaxed)
+    }
+
+    /// Re-read the cookie file; true if the credentials changed
+    fn reload_cookie(&self) -> bool {
+        let Some(path) = &self.config.cookie_file else {
+            return false;
+        };
+        match read_cookie(path) {
+            Ok(fresh) => {
+                let mut credentials = self.credentials.write().expect("credentials lock poisoned");
+                let changed = *credentials != fresh;
+                *credentials = fresh;
+                changed
+            }
+            Err(e) => {
+                eprintln!("⚠️  {:#}", e);
+                false
+            }
+        }
     }
 
-    /// Make an RPC call
+    /// Make an RPC call, retrying transient failures per `config.retry`
     async fn call(&self, method: &str, params: Value) -> Result<Value> {
+        let policy = &self.config.retry;
+        let mut attempt = 0;
+        let mut cookie_reloaded = false;
+        loop {
+            match self.call_once(method, &params).await {
+                Ok(result) => return Ok(result),
+                // Core regenerates its cookie on restart; pick up the new one once
+                Err(RpcFailure::Unauthorized(_)) if !cookie_reloaded && self.reload_cookie() => {
+                    eprintln!("🔑 RPC cookie changed, retrying {} with new credentials", method);
+                    cookie_reloaded = true;
+                }
+                Err(RpcFailure::Transient(e)) if attempt < policy.max_retries => {
+                    let delay = policy.backoff(attempt);
+                    attempt += 1;
+                    eprintln!(
+                        "⚠️  RPC {} failed ({:#}), retry {}/{} in {:.1}s",
+                        method,
+                        e,
+                        attempt,
+                        policy.max_retries,
+                        delay.as_secs_f64()
+                    );
+                    tokio::time::sleep(delay).await;
+                }
+                Err(RpcFailure::Transient(e)) if attempt > 0 => {
+                    return Err(e.context(format!("RPC {} still failing after {} retries", method, attempt)))
+                }
+                Err(RpcFailure::Transient(e)) | Err(RpcFailure::Permanent(e)) | Err(RpcFailure::Unauthorized(e)) => {
+                    return Err(e)
+                }
+            }
+        }
+    }
+
+    /// Single RPC attempt
+    async fn call_once(&self, method: &str, params: &Value) -> std::result::Result<Value, RpcFailure> {
         let body = serde_json::json!({
             "jsonrpc": "2.0",
             "method": method,
@@ -114,34 +363,52 @@ impl CoreRpcClient {
             "id": 1
         });
 
+        let (user, pass) = self.credentials.read().expect("credentials lock poisoned").clone();
+
+        // Connection resets, refusals and timeouts are all worth retrying
         let response = self
             .client
             .post(&self.config.url)
-            .basic_auth(&self.config.user, Some(&self.config.pass))
+            .basic_auth(user, Some(pass))
             .json(&body)
             .send()
             .await
-            .context("RPC request failed")?;
+            .map_err(|e| RpcFailure::Transient(anyhow::Error::new(e).context("RPC request failed")))?;
 
         let status = response.status();
-        if !status.is_success() {
-            anyhow::bail!("RPC request failed with status: {}", status);
+        if status == reqwest::StatusCode::SERVICE_UNAVAILABLE {
+            // "Work queue depth exceeded" - counted so a rate limiter can back off
+            self.work_queue_rejections.fetch_add(1, Ordering::Relaxed);
+        }
+        if status == reqwest::StatusCode::UNAUTHORIZED {
+            return Err(RpcFailure::Unauthorized(anyhow::anyhow!(
+                "RPC authentication failed (check BITCOIN_RPC_USER/PASSWORD or the .cookie file)"
+            )));
         }
 
-        let json: Value = response
-            .json()
-            .await
-            .context("Failed to parse RPC response")?;
-
-        if let Some(error) = json.get("error") {
-            if !error.is_null() {
-                anyhow::bail!("RPC error: {}", error);
+        // Core reports RPC errors with HTTP 500 and a JSON body, so read it either way
+        let json: Option<Value> = response.json().await.ok();
+        let error = json.as_ref().and_then(|j| j.get("error")).filter(|e| !e.is_null());
+        let rpc_code = error.and_then(|e| e.get("code")).and_then(|c| c.as_i64());
+        let classify = |e: anyhow::Error| {
+            if is_transient(status.as_u16(), rpc_code) {
+                RpcFailure::Transient(e)
+            } else {
+                RpcFailure::Permanent(e)
             }
+        };
+
+        if let Some(error) = error {
+            return Err(classify(anyhow::anyhow!("RPC error: {}", error)));
+        }
+        if !status.is_success() {
+            return Err(classify(anyhow::anyhow!("RPC request failed with status: {}", status)));
         }
+        let json = json.ok_or_else(|| RpcFailure::Permanent(anyhow::anyhow!("Failed to parse RPC response")))?;
 
         json.get("result")
             .cloned()
-            .context("RPC response missing result")
+            .ok_or_else(|| RpcFailure::Permanent(anyhow::anyhow!("RPC response missing result")))
     }
 
     /// Test if a transaction would be accepted to mempool
@@ -171,6 +438,12 @@ impl CoreRpcClient {
         anyhow::bail!("Unexpected testmempoolaccept response format")
     }
 
+    /// Submit a child-with-parents package (parents first); returns Core's raw result
+    /// (`package_msg` plus `tx-results` keyed by wtxid)
+    pub async fn submitpackage(&self, tx_hexes: &[String]) -> Result<Value> {
+        self.call("submitpackage", serde_json::json!([tx_hexes])).await
+    }
+
     /// Submit a block
     pub async fn submitblock(&self, block_hex: &str) -> Result<SubmitBlockResult> {
         let params = serde_json::json!([block_hex]);
@@ -192,6 +465,27 @@ impl CoreRpcClient {
         }
     }
 
+    /// Check a block with `getblocktemplate` proposal mode (no PoW check, must build on the tip)
+    ///
+    /// Uses the same SubmitBlockResult shape: null means Core would accept the block.
+    pub async fn propose_block(&self, block_hex: &str) -> Result<SubmitBlockResult> {
+        let params = serde_json::json!([{ "mode": "proposal", "data": block_hex, "rules": ["segwit"] }]);
+        let result = self.call("getblocktemplate", params).await?;
+        if result.is_null() {
+            Ok(SubmitBlockResult {
+                accepted: true,
+                error: None,
+            })
+        } else if let Some(error) = result.as_str() {
+            Ok(SubmitBlockResult {
+                accepted: false,
+                error: Some(error.to_string()),
+            })
+        } else {
+            anyhow::bail!("Unexpected getblocktemplate proposal response format")
+        }
+    }
+
     /// Get block information
     pub async fn getblock(&self, block_hash: &str, verbosity: u8) -> Result<Value> {
         let params = serde_json::json!([block_hash, verbosity]);
@@ -241,6 +535,15 @@ impl CoreRpcClient {
         }
     }
 
+    /// Mine blocks paying their coinbase to `descriptor` (e.g. `raw(<script hex>)`; regtest only)
+    pub async fn generatetodescriptor(&self, nblocks: u64, descriptor: &str) -> Result<Vec<String>> {
+        let result = self.call("generatetodescriptor", serde_json::json!([nblocks, descriptor])).await?;
+        result
+            .as_array()
+            .map(|blocks| blocks.iter().filter_map(|v| v.as_str().map(|s| s.to_string())).collect())
+            .context("Unexpected generatetodescriptor response format")
+    }
+
     /// Get new address
     pub async fn getnewaddress(&self) -> Result<String> {
         let result = self.call("getnewaddress", serde_json::json!([])).await?;
@@ -255,6 +558,139 @@ impl CoreRpcClient {
         self.call("getblockchaininfo", serde_json::json!([])).await
     }
 
+    /// Send `amount_btc` from the node's wallet; returns the txid
+    pub async fn sendtoaddress(&self, address: &str, amount_btc: f64) -> Result<String> {
+        let result = self.call("sendtoaddress", serde_json::json!([address, amount_btc])).await?;
+        result
+            .as_str()
+            .map(|s| s.to_string())
+            .context("Invalid sendtoaddress response")
+    }
+
+    /// Broadcast a raw transaction into the mempool; returns the txid
+    pub async fn sendrawtransaction(&self, tx_hex: &str) -> Result<String> {
+        let result = self.call("sendrawtransaction", serde_json::json!([tx_hex])).await?;
+        result
+            .as_str()
+            .map(|s| s.to_string())
+            .context("Invalid sendrawtransaction response")
+    }
+
+    /// Get mempool contents (verbose=true returns entries keyed by txid)
+    pub async fn getrawmempool(&self, verbose: bool) -> Result<Value> {
+        self.call("getrawmempool", serde_json::json!([verbose])).await
+    }
+
+    /// Get raw transaction hex (mempool or txindex)
+    pub async fn getrawtransaction(&self, txid: &str) -> Result<String> {
+        let result = self.call("getrawtransaction", serde_json::json!([txid, false])).await?;
+        result
+            .as_str()
+            .map(|s| s.to_string())
+            .context("Invalid getrawtransaction response")
+    }
+
+    /// Decoded transaction (txid, hash, size, vsize, weight, hex, ...) from the mempool or txindex
+    pub async fn getrawtransaction_verbose(&self, txid: &str) -> Result<Value> {
+        self.call("getrawtransaction", serde_json::json!([txid, true])).await
+    }
+
+    /// Build an unsigned transaction from (txid, vout, nSequence) inputs and (address, BTC) outputs
+    pub async fn createrawtransaction(
+        &self,
+        inputs: &[(&str, u32, u32)],
+        outputs: &[(&str, f64)],
+        locktime: u32,
+    ) -> Result<String> {
+        let inputs: Vec<Value> = inputs
+            .iter()
+            .map(|(txid, vout, sequence)| serde_json::json!({ "txid": txid, "vout": vout, "sequence": sequence }))
+            .collect();
+        let outputs: serde_json::Map<String, Value> = outputs
+            .iter()
+            .map(|(address, amount)| (address.to_string(), serde_json::json!(amount)))
+            .collect();
+        let result = self
+            .call("createrawtransaction", serde_json::json!([inputs, outputs, locktime]))
+            .await?;
+        result
+            .as_str()
+            .map(|s| s.to_string())
+            .context("Invalid createrawtransaction response")
+    }
+
+    /// Sign a raw transaction with the node's wallet (fails unless fully signed)
+    pub async fn signrawtransactionwithwallet(&self, tx_hex: &str) -> Result<String> {
+        let result = self.call("signrawtransactionwithwallet", serde_json::json!([tx_hex])).await?;
+        if result.get("complete").and_then(|c| c.as_bool()) != Some(true) {
+            anyhow::bail!("Wallet could not fully sign the transaction: {}", result);
+        }
+        result
+            .get("hex")
+            .and_then(|h| h.as_str())
+            .map(|s| s.to_string())
+            .context("Invalid signrawtransactionwithwallet response")
+    }
+
+    /// Get an unspent output (None if spent or unknown)
+    pub async fn gettxout(&self, txid: &str, vout: u32, include_mempool: bool) -> Result<Option<Value>> {
+        let result = self
+            .call("gettxout", serde_json::json!([txid, vout, include_mempool]))
+            .await?;
+        Ok(if result.is_null() { None } else { Some(result) })
+    }
+
+    /// Get block header information (verbose JSON)
+    pub async fn getblockheader(&self, block_hash: &str) -> Result<Value> {
+        self.call("getblockheader", serde_json::json!([block_hash, true])).await
+    }
+
+    /// BIP158 basic filter and filter header of a block (needs `-blockfilterindex=1`)
+    pub async fn getblockfilter(&self, block_hash: &str) -> Result<Value> {
+        self.call("getblockfilter", serde_json::json!([block_hash, "basic"])).await
+    }
+
+    /// Per-block statistics, restricted to `stats` (e.g. totalfee, subsidy)
+    pub async fn getblockstats(&self, height: u64, stats: &[&str]) -> Result<Value> {
+        self.call("getblockstats", serde_json::json!([height, stats])).await
+    }
+
+    /// Get the hash of the active chain tip
+    pub async fn getbestblockhash(&self) -> Result<String> {
+        let result = self.call("getbestblockhash", serde_json::json!([])).await?;
+        result
+            .as_str()
+            .map(|s| s.to_string())
+            .context("Invalid getbestblockhash response")
+    }
+
+    /// Mark a block (and its descendants) invalid, forcing a reorg away from it
+    pub async fn invalidateblock(&self, block_hash: &str) -> Result<()> {
+        self.call("invalidateblock", serde_json::json!([block_hash])).await?;
+        Ok(())
+    }
+
+    /// Undo a previous invalidateblock
+    pub async fn reconsiderblock(&self, block_hash: &str) -> Result<()> {
+        self.call("reconsiderblock", serde_json::json!([block_hash])).await?;
+        Ok(())
+    }
+
+    /// UTXO set statistics (txouts, total_amount, height, bestblock)
+    pub async fn gettxoutsetinfo(&self) -> Result<Value> {
+        self.call("gettxoutsetinfo", serde_json::json!([])).await
+    }
+
+    /// UTXO set statistics at `height` with `hash_type` (`muhash` at past heights needs `-coinstatsindex=1`)
+    pub async fn gettxoutsetinfo_at(&self, hash_type: &str, height: u64) -> Result<Value> {
+        self.call("gettxoutsetinfo", serde_json::json!([hash_type, height])).await
+    }
+
+    /// Get soft-fork deployment state (Core 23.0+)
+    pub async fn getdeploymentinfo(&self) -> Result<Value> {
+        self.call("getdeploymentinfo", serde_json::json!([])).await
+    }
+
     /// Detect network type from running node
     pub async fn detect_network(&self) -> Result<BitcoinNetwork> {
         let info = self.getblockchaininfo().await?;
@@ -317,17 +753,17 @@ impl NodeDiscovery {
         // Common Bitcoin Core config file locations
         let mut config_paths = Vec::new();
         // Mainnet
-        if let Some(home) = dirs::home_dir() {
-            config_paths.push(home.join(".bitcoin/bitcoin.conf"));
+        if let Some(datadir) = default_datadir() {
+            config_paths.push(datadir.join("bitcoin.conf"));
         }
         config_paths.push(PathBuf::from("/etc/bitcoin/bitcoin.conf"));
         // Testnet
-        if let Some(home) = dirs::home_dir() {
-            config_paths.push(home.join(".bitcoin/testnet3/bitcoin.conf"));
+        if let Some(datadir) = default_datadir() {
+            config_paths.push(datadir.join("testnet3").join("bitcoin.conf"));
         }
         // Regtest
-        if let Some(home) = dirs::home_dir() {
-            config_paths.push(home.join(".bitcoin/regtest/bitcoin.conf"));
+        if let Some(datadir) = default_datadir() {
+            config_paths.push(datadir.join("regtest").join("bitcoin.conf"));
         }
 
         for path in config_paths {
@@ -350,6 +786,7 @@ impl NodeDiscovery {
         let mut rpc_user: Option<String> = None;
         let mut rpc_password: Option<String> = None;
         let mut rpc_bind: Option<String> = None;
+        let mut datadir: Option<PathBuf> = None;
         let mut testnet = false;
         let mut regtest = false;
 
@@ -380,6 +817,9 @@ impl NodeDiscovery {
                     "rpcbind" => {
                         rpc_bind = Some(value.to_string());
                     }
+                    "datadir" => {
+                        datadir = Some(PathBuf::from(value));
+                    }
                     "testnet" => {
                         testnet = value == "1" || value == "true";
                     }
@@ -402,6 +842,23 @@ impl NodeDiscovery {
             }
         });
 
+        // No rpcuser/rpcpassword: Core falls back to cookie auth in its datadir
+        let network = if regtest {
+            BitcoinNetwork::Regtest
+        } else if testnet {
+            BitcoinNetwork::Testnet
+        } else {
+            BitcoinNetwork::Mainnet
+        };
+        let cookie_file = if rpc_user.is_none() && rpc_password.is_none() {
+            datadir
+                .or_else(|| path.parent().map(Path::to_path_buf))
+                .map(|d| cookie_path(&d, network))
+                .filter(|p| p.exists())
+        } else {
+            None
+        };
+
         // Default credentials if not set
         let user = rpc_user.unwrap_or_else(|| "test".to_string());
         let password = rpc_password.unwrap_or_else(|| "test".to_string());
@@ -412,6 +869,8 @@ impl NodeDiscovery {
             user,
             pass: password,
             timeout: Duration::from_secs(5),
+            retry: RetryPolicy::none(),
+            cookie_file,
         })
     }
 
@@ -438,6 +897,8 @@ impl NodeDiscovery {
                     user: user.to_string(),
                     pass: pass.to_string(),
                     timeout: Duration::from_secs(2),
+                    retry: RetryPolicy::none(),
+                    cookie_file: None,
                 };
                 let client = CoreRpcClient::new(config.clone());
                 if client.test_connection().await.unwrap_or(false) {
@@ -514,6 +975,16 @@ impl BitcoinNetwork {
         }
     }
 
+    /// Datadir subdirectory Core uses for this network (None for mainnet)
+    pub fn data_subdir(&self) -> Option<&'static str> {
+        match self {
+            BitcoinNetwork::Mainnet => None,
+            BitcoinNetwork::Testnet => Some("testnet3"),
+            BitcoinNetwork::Regtest => Some("regtest"),
+            BitcoinNetwork::Signet => Some("signet"),
+        }
+    }
+
     pub fn as_str(&self) -> &'static str {
         match self {
             BitcoinNetwork::Mainnet => "mainnet",
@@ -541,3 +1012,69 @@ pub struct SubmitBlockResult {
     /// Error message if not accepted
     pub error: Option<String>,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_transient_classification() {
+        assert!(is_transient(500, Some(RPC_IN_WARMUP)));
+        assert!(is_transient(503, None));
+        assert!(!is_transient(500, Some(-5)));
+        assert!(!is_transient(401, None));
+    }
+
+    #[test]
+    fn test_cookie_discovery() {
+        let datadir = Path::new("/data/bitcoin");
+        assert_eq!(cookie_path(datadir, BitcoinNetwork::Mainnet), datadir.join(".cookie"));
+        assert_eq!(
+            cookie_path(datadir, BitcoinNetwork::Testnet),
+            datadir.join("testnet3/.cookie")
+        );
+        assert_eq!(
+            parse_cookie("__cookie__:abc123\n"),
+            Some(("__cookie__".to_string(), "abc123".to_string()))
+        );
+        assert_eq!(parse_cookie("garbage"), None);
+    }
+
+    #[test]
+    fn test_backoff_bounds() {
+        let policy = RetryPolicy {
+            max_retries: 10,
+            initial_backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_secs(1),
+        };
+        assert_eq!(policy.backoff_cap(0), Duration::from_millis(100));
+        assert_eq!(policy.backoff_cap(3), Duration::from_millis(800));
+        assert_eq!(policy.backoff_cap(40), Duration::from_secs(1));
+        for attempt in 0..10 {
+            let delay = policy.backoff(attempt);
+            let cap = policy.backoff_cap(attempt);
+            assert!(delay >= cap / 2 && delay <= cap);
+        }
+    }
+
+    #[test]
+    fn test_retry_policy_from_vars() {
+        let policy = RetryPolicy::from_vars(|name| match name {
+            "BLVM_RPC_MAX_RETRIES" => Some("3".to_string()),
+            "BLVM_RPC_RETRY_BACKOFF_MS" => Some(" 250 ".to_string()),
+            _ => None,
+        });
+        assert_eq!(policy.max_retries, 3);
+        assert_eq!(policy.initial_backoff, Duration::from_millis(250));
+
+        // Invalid values keep the defaults instead of failing the run
+        let policy = RetryPolicy::from_vars(|name| match name {
+            "BLVM_RPC_MAX_RETRIES" => Some("many".to_string()),
+            "BLVM_RPC_RETRY_BACKOFF_MS" => Some("-5".to_string()),
+            _ => None,
+        });
+        let default = RetryPolicy::default();
+        assert_eq!(policy.max_retries, default.max_retries);
+        assert_eq!(policy.initial_backoff, default.initial_backoff);
+    }
+}
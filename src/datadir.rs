@@ -0,0 +1,361 @@
+//! Core Data Directory Detection
+//!
+//! Finds the directory whose `blocks/` holds Core's blk files, in order:
+//!
+//! 1. An explicit override - `--datadir` or `BITCOIN_DATA_DIR` (`BITCOIN_DATADIR`
+//!    is accepted too). When set, nothing else is tried.
+//! 2. Running `bitcoind` processes: their `-datadir`/`-blocksdir` arguments,
+//!    and the `bitcoin.conf` they were started with (`-conf`).
+//! 3. `bitcoin.conf` in the usual places (`datadir=` and `blocksdir=`).
+//! 4. The fixed default locations: Core's per-OS default datadir (`~/.bitcoin`,
+//!    `~/Library/Application Support/Bitcoin` on macOS, `%APPDATA%\Bitcoin` on
+//!    Windows), then on Unix `/var/lib/bitcoind` and the Start9 mounts.
+//!
+//! Every candidate is returned with where it came from, so callers can list
+//! everything that was tried when none of them works.
+
+use crate::block_file_reader::Network;
+use std::path::{Path, PathBuf};
+
+/// A directory that may contain `blocks/blk*.dat`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Candidate {
+    /// Directory containing `blocks/` (network subdirectory already applied)
+    pub dir: PathBuf,
+    /// Where the path came from, for diagnostics
+    pub origin: String,
+}
+
+/// Core's default datadir for `os` (`std::env::consts::OS`), as `GetDefaultDataDir` picks it
+pub fn core_default_datadir(os: &str, home: Option<&Path>, appdata: Option<&Path>) -> Option<PathBuf> {
+    match os {
+        "windows" => appdata
+            .map(Path::to_path_buf)
+            .or_else(|| home.map(|h| h.join("AppData").join("Roaming")))
+            .map(|d| d.join("Bitcoin")),
+        "macos" => home.map(|h| h.join("Library").join("Application Support").join("Bitcoin")),
+        _ => home.map(|h| h.join(".bitcoin")),
+    }
+}
+
+/// Core's default datadir on this machine
+pub fn default_datadir() -> Option<PathBuf> {
+    let appdata = std::env::var_os("APPDATA").map(PathBuf::from);
+    core_default_datadir(std::env::consts::OS, dirs::home_dir().as_deref(), appdata.as_deref())
+}
+
+/// The explicit data directory override, if any
+pub fn datadir_override() -> Option<PathBuf> {
+    std::env::var("BITCOIN_DATA_DIR")
+        .or_else(|_| std::env::var("BITCOIN_DATADIR"))
+        .ok()
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+}
+
+fn network_subdir(network: Network) -> Option<&'static str> {
+    match network {
+        Network::Mainnet => None,
+        Network::Testnet => Some("testnet3"),
+        Network::Regtest => Some("regtest"),
+        Network::Signet => Some("signet"),
+    }
+}
+
+/// Section name for `network` in bitcoin.conf
+fn conf_section(network: Network) -> &'static str {
+    match network {
+        Network::Mainnet => "main",
+        Network::Testnet => "test",
+        Network::Regtest => "regtest",
+        Network::Signet => "signet",
+    }
+}
+
+/// Per-network directory under a datadir or blocksdir
+fn for_network(dir: &Path, network: Network) -> PathBuf {
+    match network_subdir(network) {
+        Some(subdir) => dir.join(subdir),
+        None => dir.to_path_buf(),
+    }
+}
+
+/// Settings from a bitcoin.conf or a bitcoind command line that decide where blocks live
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DatadirSettings {
+    pub datadir: Option<PathBuf>,
+    pub blocksdir: Option<PathBuf>,
+    pub conf: Option<PathBuf>,
+    pub network: Option<Network>,
+}
+
+impl DatadirSettings {
+    fn set(&mut self, key: &str, value: &str) {
+        match key {
+            "datadir" => self.datadir = Some(PathBuf::from(value)),
+            "blocksdir" => self.blocksdir = Some(PathBuf::from(value)),
+            "conf" => self.conf = Some(PathBuf::from(value)),
+            "chain" => {
+                self.network = match value {
+                    "main" => Some(Network::Mainnet),
+                    "test" => Some(Network::Testnet),
+                    "regtest" => Some(Network::Regtest),
+                    "signet" => Some(Network::Signet),
+                    _ => self.network,
+                }
+            }
+            "testnet" if value != "0" => self.network = Some(Network::Testnet),
+            "regtest" if value != "0" => self.network = Some(Network::Regtest),
+            "signet" if value != "0" => self.network = Some(Network::Signet),
+            _ => {}
+        }
+    }
+
+    /// Fill in whatever `other` leaves unset (command line over conf)
+    fn or(self, other: DatadirSettings) -> Self {
+        Self {
+            datadir: self.datadir.or(other.datadir),
+            blocksdir: self.blocksdir.or(other.blocksdir),
+            conf: self.conf.or(other.conf),
+            network: self.network.or(other.network),
+        }
+    }
+
+    /// Directories to look for `blocks/` in; `fallback_datadir` is used when no datadir is set
+    fn dirs(&self, network: Network, fallback_datadir: Option<&Path>) -> Vec<PathBuf> {
+        let datadir = self.datadir.as_deref().or(fallback_datadir);
+        self.blocksdir
+            .as_deref()
+            .into_iter()
+            .chain(datadir)
+            .map(|dir| for_network(dir, network))
+            .collect()
+    }
+}
+
+/// Parse the settings that matter for block location from bitcoin.conf contents
+///
+/// Top-level keys and keys in `network`'s section (`[main]`, `[test]`, `[regtest]`) apply.
+pub fn parse_conf(content: &str, network: Network) -> DatadirSettings {
+    let mut settings = DatadirSettings::default();
+    let mut section: Option<String> = None;
+    for line in content.lines() {
+        let line = line.split('#').next().unwrap_or("").trim();
+        if let Some(name) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+            section = Some(name.trim().to_string());
+            continue;
+        }
+        if section.as_deref().is_some_and(|s| s != conf_section(network)) {
+            continue;
+        }
+        if let Some((key, value)) = line.split_once('=') {
+            settings.set(&key.trim().to_lowercase(), value.trim());
+        }
+    }
+    settings
+}
+
+/// Parse `-datadir=`, `-blocksdir=`, `-conf=` and the chain flags from bitcoind's arguments
+pub fn parse_args<'a>(args: impl IntoIterator<Item = &'a str>) -> DatadirSettings {
+    let mut settings = DatadirSettings::default();
+    for arg in args {
+        let Some(option) = arg.strip_prefix("--").or_else(|| arg.strip_prefix('-')) else {
+            continue;
+        };
+        match option.split_once('=') {
+            Some((key, value)) => settings.set(key, value),
+            None => settings.set(option, "1"),
+        }
+    }
+    settings
+}
+
+/// Command lines of running bitcoind processes (Linux `/proc`; empty elsewhere)
+fn bitcoind_command_lines() -> Vec<(u32, Vec<String>)> {
+    let Ok(entries) = std::fs::read_dir("/proc") else {
+        return Vec::new();
+    };
+    entries
+        .flatten()
+        .filter_map(|entry| {
+            let pid: u32 = entry.file_name().to_str()?.parse().ok()?;
+            let cmdline = std::fs::read(entry.path().join("cmdline")).ok()?;
+            let args: Vec<String> = cmdline
+                .split(|&b| b == 0)
+                .filter(|arg| !arg.is_empty())
+                .map(|arg| String::from_utf8_lossy(arg).into_owned())
+                .collect();
+            let program = Path::new(args.first()?).file_stem()?.to_str()?;
+            (program == "bitcoind").then_some((pid, args))
+        })
+        .collect()
+}
+
+/// bitcoin.conf locations checked when no process points at one
+fn default_conf_files() -> Vec<PathBuf> {
+    let system = [
+        PathBuf::from("/etc/bitcoin/bitcoin.conf"),
+        PathBuf::from("/var/lib/bitcoind/bitcoin.conf"),
+    ];
+    default_datadir()
+        .map(|d| d.join("bitcoin.conf"))
+        .into_iter()
+        .chain(system.into_iter().filter(|_| cfg!(unix)))
+        .collect()
+}
+
+/// Fixed locations tried last (Start9 mounts hold a plain mainnet datadir)
+fn default_dirs(network: Network) -> Vec<PathBuf> {
+    let unix_datadirs = [PathBuf::from("/root/.bitcoin"), PathBuf::from("/var/lib/bitcoind")];
+    let datadirs = default_datadir()
+        .into_iter()
+        .chain(unix_datadirs.into_iter().filter(|_| cfg!(unix)));
+    let start9 = [
+        dirs::home_dir().map(|h| h.join("mnt/bitcoin-start9")),
+        Some(PathBuf::from("/mnt/bitcoin-start9")),
+    ];
+    let start9 = if network == Network::Mainnet && cfg!(unix) {
+        start9.into_iter().flatten().collect()
+    } else {
+        Vec::new()
+    };
+    datadirs.map(|dir| for_network(&dir, network)).chain(start9).collect()
+}
+
+/// Every directory worth trying for `network`, most specific first, without duplicates
+///
+/// `explicit` (a `--datadir` flag) takes precedence over the environment override.
+pub fn candidates(network: Network, explicit: Option<&Path>) -> Vec<Candidate> {
+    let mut found: Vec<Candidate> = Vec::new();
+    let mut push = |dir: PathBuf, origin: String| {
+        if !found.iter().any(|c| c.dir == dir) {
+            found.push(Candidate { dir, origin });
+        }
+    };
+
+    let override_dir = explicit.map(|d| (d.to_path_buf(), "--datadir")).or_else(|| {
+        datadir_override().map(|d| (d, "BITCOIN_DATA_DIR"))
+    });
+    if let Some((dir, origin)) = override_dir {
+        // Explicitly chosen: the network subdirectory still applies, but nothing else is guessed
+        push(for_network(&dir, network), origin.to_string());
+        if network_subdir(network).is_some() {
+            push(dir, format!("{} (as given)", origin));
+        }
+        return found;
+    }
+
+    for (pid, args) in bitcoind_command_lines() {
+        let from_args = parse_args(args.iter().skip(1).map(String::as_str));
+        let conf_file = from_args.conf.clone().or_else(|| {
+            from_args.datadir.as_ref().map(|d| d.join("bitcoin.conf"))
+        });
+        let from_conf = conf_file
+            .as_ref()
+            .and_then(|path| std::fs::read_to_string(path).ok())
+            .map(|content| parse_conf(&content, from_args.network.unwrap_or(network)))
+            .unwrap_or_default();
+        let settings = from_args.or(from_conf);
+        if settings.network.unwrap_or(Network::Mainnet) != network {
+            continue;
+        }
+        let cwd = std::fs::read_link(format!("/proc/{}/cwd", pid)).ok();
+        let fallback = default_datadir();
+        for dir in settings.dirs(network, fallback.as_deref()) {
+            // Relative paths are relative to the process's working directory
+            let dir = match (&cwd, dir.is_relative()) {
+                (Some(cwd), true) => cwd.join(dir),
+                _ => dir,
+            };
+            push(dir, format!("running bitcoind (pid {})", pid));
+        }
+    }
+
+    for conf in default_conf_files() {
+        let Ok(content) = std::fs::read_to_string(&conf) else {
+            continue;
+        };
+        let settings = parse_conf(&content, network);
+        let conf_dir = conf.parent().map(Path::to_path_buf);
+        for dir in settings.dirs(network, conf_dir.as_deref()) {
+            push(dir, conf.display().to_string());
+        }
+    }
+
+    for dir in default_dirs(network) {
+        push(dir, "default location".to_string());
+    }
+    found
+}
+
+/// One line per candidate and what happened, for "nothing worked" diagnostics
+pub fn describe_attempts(attempts: &[(Candidate, String)]) -> String {
+    attempts
+        .iter()
+        .map(|(candidate, outcome)| format!("   - {} ({}): {}", candidate.dir.display(), candidate.origin, outcome))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_conf_sections_and_blocksdir() {
+        let conf = "\
+# node
+datadir=/data/bitcoin
+blocksdir=/hdd/blocks  # big disk
+[test]
+blocksdir=/hdd/testblocks
+[main]
+rpcport=8332
+";
+        let main = parse_conf(conf, Network::Mainnet);
+        assert_eq!(main.datadir, Some(PathBuf::from("/data/bitcoin")));
+        assert_eq!(main.blocksdir, Some(PathBuf::from("/hdd/blocks")));
+        assert_eq!(
+            main.dirs(Network::Mainnet, None),
+            vec![PathBuf::from("/hdd/blocks"), PathBuf::from("/data/bitcoin")]
+        );
+
+        let test = parse_conf(conf, Network::Testnet);
+        assert_eq!(
+            test.dirs(Network::Testnet, None),
+            vec![PathBuf::from("/hdd/testblocks/testnet3"), PathBuf::from("/data/bitcoin/testnet3")]
+        );
+    }
+
+    #[test]
+    fn test_parse_args_overrides_conf() {
+        let args = parse_args(["-datadir=/srv/btc", "--regtest", "-printtoconsole", "-conf=/etc/b.conf"]);
+        assert_eq!(args.datadir, Some(PathBuf::from("/srv/btc")));
+        assert_eq!(args.conf, Some(PathBuf::from("/etc/b.conf")));
+        assert_eq!(args.network, Some(Network::Regtest));
+
+        let conf = parse_conf("datadir=/other\nblocksdir=/hdd\nchain=test\n", Network::Regtest);
+        let merged = args.or(conf);
+        assert_eq!(merged.datadir, Some(PathBuf::from("/srv/btc")));
+        assert_eq!(merged.blocksdir, Some(PathBuf::from("/hdd")));
+        assert_eq!(merged.network, Some(Network::Regtest));
+        assert_eq!(merged.dirs(Network::Regtest, None)[0], PathBuf::from("/hdd/regtest"));
+    }
+
+    #[test]
+    fn test_core_default_datadir_per_os() {
+        let home = Path::new("/home/satoshi");
+        assert_eq!(core_default_datadir("linux", Some(home), None), Some(home.join(".bitcoin")));
+        assert_eq!(
+            core_default_datadir("macos", Some(home), None),
+            Some(home.join("Library").join("Application Support").join("Bitcoin"))
+        );
+        let appdata = Path::new(r"C:\Users\satoshi\AppData\Roaming");
+        assert_eq!(core_default_datadir("windows", Some(home), Some(appdata)), Some(appdata.join("Bitcoin")));
+        assert_eq!(
+            core_default_datadir("windows", Some(home), None),
+            Some(home.join("AppData").join("Roaming").join("Bitcoin"))
+        );
+        assert_eq!(core_default_datadir("linux", None, None), None);
+    }
+}
@@ -0,0 +1,151 @@
+//! Block Decode Pipeline
+//!
+//! `validate_chunk` reads, deserializes and connects blocks one after another,
+//! so parsing sits on the critical path of the single-threaded `connect_block`
+//! loop. `DecodePipeline` runs `deserialize_block_with_witnesses` on the rayon
+//! pool up to `depth` blocks ahead of the consumer and hands the results back
+//! in height order, hiding parse latency behind validation.
+//!
+//! At most `depth + 1` raw and decoded blocks are held at once. Depth 0 decodes
+//! inline (the old serial behaviour). Configured with `BLVM_DECODE_AHEAD`.
+
+use anyhow::{Context, Result};
+use blvm_consensus::segwit::Witness;
+use blvm_consensus::serialization::block::deserialize_block_with_witnesses;
+use blvm_consensus::Block;
+use std::collections::VecDeque;
+use std::sync::mpsc::{sync_channel, Receiver};
+use std::time::{Duration, Instant};
+
+/// Blocks decoded ahead of the consumer by default
+pub const DEFAULT_DECODE_AHEAD: usize = 8;
+
+/// Decode-ahead depth from `BLVM_DECODE_AHEAD` (default `DEFAULT_DECODE_AHEAD`)
+pub fn decode_ahead_from_env() -> Result<usize> {
+    match std::env::var("BLVM_DECODE_AHEAD") {
+        Ok(depth) => depth
+            .parse()
+            .with_context(|| format!("Invalid BLVM_DECODE_AHEAD '{}'", depth)),
+        Err(_) => Ok(DEFAULT_DECODE_AHEAD),
+    }
+}
+
+/// A deserialized block and its per-transaction witnesses
+pub type DecodedBlock = (Block, Vec<Witness>);
+
+/// Deserialize one block
+pub fn decode_block(block_bytes: &[u8], height: u64) -> Result<DecodedBlock> {
+    deserialize_block_with_witnesses(block_bytes)
+        .map_err(|e| anyhow::anyhow!("Failed to deserialize block at height {}: {}", height, e))
+}
+
+/// A block out of the pipeline, with how long reading and decoding it took
+pub struct PipelinedBlock {
+    pub height: u64,
+    pub bytes: Vec<u8>,
+    pub decoded: DecodedBlock,
+    pub read_time: Duration,
+    /// Decode time on the pool (overlaps validation when depth > 0)
+    pub decode_time: Duration,
+}
+
+/// Raw bytes, decode result and decode time of one in-flight block
+type Slot = Receiver<(Vec<u8>, Result<DecodedBlock>, Duration)>;
+
+/// Decode and time one block
+fn timed_decode(bytes: Vec<u8>, height: u64) -> (Vec<u8>, Result<DecodedBlock>, Duration) {
+    let started = Instant::now();
+    let decoded = decode_block(&bytes, height);
+    (bytes, decoded, started.elapsed())
+}
+
+/// Ordered, bounded decode-ahead over a stream of raw blocks
+///
+/// Yields blocks in order with heights counting up from the first block.
+/// Read and decode errors are yielded in order too.
+pub struct DecodePipeline<I> {
+    blocks: I,
+    next_height: u64,
+    depth: usize,
+    pending: VecDeque<(u64, Duration, Slot)>,
+}
+
+impl<I: Iterator<Item = Result<Vec<u8>>>> DecodePipeline<I> {
+    pub fn new(blocks: I, start_height: u64, depth: usize) -> Self {
+        Self {
+            blocks,
+            next_height: start_height,
+            depth,
+            pending: VecDeque::with_capacity(depth + 1),
+        }
+    }
+
+    /// Queue blocks until `depth` are in flight behind the next one
+    fn fill(&mut self) {
+        while self.pending.len() <= self.depth {
+            let read_started = Instant::now();
+            let Some(raw) = self.blocks.next() else {
+                return;
+            };
+            let read_time = read_started.elapsed();
+            let height = self.next_height;
+            self.next_height += 1;
+            let (tx, rx) = sync_channel(1);
+            match raw {
+                Ok(bytes) if self.depth > 0 => rayon::spawn(move || {
+                    // The consumer may have stopped early (poisoned chunk)
+                    let _ = tx.send(timed_decode(bytes, height));
+                }),
+                Ok(bytes) => {
+                    let _ = tx.send(timed_decode(bytes, height));
+                }
+                Err(e) => {
+                    let _ = tx.send((Vec::new(), Err(e), Duration::ZERO));
+                }
+            }
+            self.pending.push_back((height, read_time, rx));
+        }
+    }
+}
+
+impl<I: Iterator<Item = Result<Vec<u8>>>> Iterator for DecodePipeline<I> {
+    type Item = Result<PipelinedBlock>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.fill();
+        let (height, read_time, slot) = self.pending.pop_front()?;
+        Some(match slot.recv() {
+            Ok((bytes, decoded, decode_time)) => decoded.map(|decoded| PipelinedBlock {
+                height,
+                bytes,
+                decoded,
+                read_time,
+                decode_time,
+            }),
+            Err(_) => Err(anyhow::anyhow!("Decode task for block {} panicked", height)),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pipeline_yields_in_order_with_errors() {
+        for depth in [0, 3] {
+            let blocks = vec![
+                Ok(vec![0u8; 4]),
+                Err(anyhow::anyhow!("read failed")),
+                Ok(vec![1u8; 4]),
+            ];
+            let results: Vec<String> = DecodePipeline::new(blocks.into_iter(), 100, depth)
+                .map(|r| r.err().map(|e| e.to_string()).unwrap_or_default())
+                .collect();
+            assert_eq!(results.len(), 3);
+            assert!(results[0].contains("height 100"), "{:?}", results);
+            assert_eq!(results[1], "read failed");
+            assert!(results[2].contains("height 102"), "{:?}", results);
+        }
+    }
+}
@@ -0,0 +1,268 @@
+//! Optimistic Chunks With a Deferred UTXO Check
+//!
+//! A checkpointed run can't start most chunks until phase 1 has built the UTXO
+//! set at every chunk boundary. In optimistic mode there is no phase 1: each
+//! chunk starts from the coins its blocks spend that were created before it,
+//! taken from Core's undo data (the `prevout`s of `getblock` verbosity 3) by
+//! the chunk's own worker. That is everything `connect_block` reads, so every
+//! chunk validates as soon as a worker is free.
+//!
+//! Starting from Core's coins assumes BLVM's own state would have held the
+//! same ones. The deferred check confirms it as chunks finish: BLVM's
+//! end-of-chunk sets are folded in height order into BLVM's sequential state,
+//! and every coin a chunk assumed must be in that state with the same value,
+//! script, height and coinbase flag. A coin that isn't becomes a divergence at
+//! the chunk's start. Coins created before the run's first block can't be
+//! checked. On with `BLVM_OPTIMISTIC_CHUNKS=1`; Core is reached with the usual
+//! `BITCOIN_RPC_*` settings.
+
+use anyhow::{Context, Result};
+use blvm_consensus::{OutPoint, UtxoSet, UTXO};
+use std::sync::Arc;
+
+use crate::core_rpc_client::{CoreRpcClient, RpcConfig};
+
+/// Core node the chunks' start sets come from
+#[derive(Clone)]
+pub struct OptimisticChunks {
+    client: Arc<CoreRpcClient>,
+}
+
+impl std::fmt::Debug for OptimisticChunks {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("OptimisticChunks").field("url", &self.client.url()).finish()
+    }
+}
+
+impl OptimisticChunks {
+    pub fn new(client: Arc<CoreRpcClient>) -> Self {
+        Self { client }
+    }
+
+    /// On when `BLVM_OPTIMISTIC_CHUNKS` is `1` or `true`
+    pub fn from_env() -> Option<Self> {
+        match std::env::var("BLVM_OPTIMISTIC_CHUNKS").as_deref() {
+            Ok("1") | Ok("true") => Some(Self::new(Arc::new(CoreRpcClient::new(RpcConfig::from_env())))),
+            _ => None,
+        }
+    }
+
+    pub fn url(&self) -> &str {
+        self.client.url()
+    }
+
+    /// Coins blocks `start..=end` spend that were created before `start`
+    pub async fn start_set(&self, start: u64, end: u64) -> Result<UtxoSet> {
+        let mut assumed = UtxoSet::new();
+        for height in start..=end {
+            let block_hash = self.client.getblockhash(height).await?;
+            let verbose = self
+                .client
+                .getblock(&block_hash, 3)
+                .await
+                .context("getblock verbosity 3 requires Bitcoin Core 23.0+")?;
+            let spent = crate::block_fixtures::pre_state_from_verbose(&verbose)
+                .with_context(|| format!("Failed to read the coins block {} spends", height))?;
+            for (outpoint, coin) in spent.into_iter() {
+                if (coin.height as u64) < start {
+                    assumed.insert(outpoint, coin);
+                }
+            }
+        }
+        Ok(assumed)
+    }
+}
+
+/// A coin a chunk assumed that BLVM's sequential state doesn't hold as-is
+#[derive(Debug, Clone)]
+pub struct AssumptionMismatch {
+    pub chunk_start: u64,
+    pub outpoint: OutPoint,
+    /// The coin per Core's undo data
+    pub assumed: UTXO,
+    /// The coin in BLVM's state (None if BLVM doesn't have it)
+    pub blvm: Option<UTXO>,
+}
+
+impl AssumptionMismatch {
+    /// (BLVM, Core) strings for the divergence record
+    pub fn describe(&self) -> (String, String) {
+        let outpoint = format_outpoint(&self.outpoint);
+        let blvm = match &self.blvm {
+            Some(coin) => format!("Coin({} {})", outpoint, format_coin(coin)),
+            None => format!("Missing({})", outpoint),
+        };
+        (blvm, format!("Coin({} {})", outpoint, format_coin(&self.assumed)))
+    }
+}
+
+/// BLVM's sequential UTXO state, rebuilt chunk by chunk in height order
+#[derive(Debug)]
+pub struct Reconciler {
+    run_start: u64,
+    state: UtxoSet,
+    next_height: u64,
+    checked: usize,
+    mismatched: usize,
+    stopped_at: Option<u64>,
+}
+
+impl Reconciler {
+    pub fn new(run_start: u64) -> Self {
+        Self {
+            run_start,
+            state: UtxoSet::new(),
+            next_height: run_start,
+            checked: 0,
+            mismatched: 0,
+            stopped_at: None,
+        }
+    }
+
+    /// Check a chunk's assumed coins against the state so far, then apply BLVM's changes over the chunk
+    ///
+    /// `end` is BLVM's set after the chunk's last block, which started out as `assumed`.
+    pub fn fold(&mut self, chunk_start: u64, chunk_end: u64, assumed: &UtxoSet, end: UtxoSet) -> Vec<AssumptionMismatch> {
+        if self.stopped_at.is_some() {
+            return Vec::new();
+        }
+        if chunk_start != self.next_height {
+            self.stopped_at = Some(self.next_height);
+            return Vec::new();
+        }
+        let mut mismatches = Vec::new();
+        for (outpoint, coin) in assumed.iter() {
+            if (coin.height as u64) < self.run_start {
+                continue;
+            }
+            self.checked += 1;
+            match self.state.get(outpoint) {
+                Some(blvm) if same_coin(blvm, coin) => {}
+                blvm => mismatches.push(AssumptionMismatch {
+                    chunk_start,
+                    outpoint: outpoint.clone(),
+                    assumed: coin.clone(),
+                    blvm: blvm.cloned(),
+                }),
+            }
+        }
+        self.mismatched += mismatches.len();
+
+        // BLVM spent the assumed coins it no longer holds and created the rest of what it holds
+        for (outpoint, _) in assumed.iter() {
+            if end.get(outpoint).is_none() {
+                self.state.remove(outpoint);
+            }
+        }
+        for (outpoint, coin) in end.into_iter() {
+            if assumed.get(&outpoint).is_none() {
+                self.state.insert(outpoint, coin);
+            }
+        }
+        self.next_height = chunk_end + 1;
+        mismatches
+    }
+
+    /// A chunk that didn't finish: nothing from it on can be checked
+    pub fn gap(&mut self, chunk_start: u64) {
+        if self.stopped_at.is_none() {
+            self.stopped_at = Some(chunk_start.min(self.next_height));
+        }
+    }
+
+    /// Assumed coins compared with BLVM's state
+    pub fn checked(&self) -> usize {
+        self.checked
+    }
+
+    pub fn mismatched(&self) -> usize {
+        self.mismatched
+    }
+
+    /// Height from which chunks couldn't be checked (None if every chunk was)
+    pub fn stopped_at(&self) -> Option<u64> {
+        self.stopped_at
+    }
+}
+
+pub(crate) fn same_coin(a: &UTXO, b: &UTXO) -> bool {
+    let (a_script, b_script): (&[u8], &[u8]) = (&a.script_pubkey, &b.script_pubkey);
+    a.value as i64 == b.value as i64
+        && a_script == b_script
+        && a.is_coinbase == b.is_coinbase
+        && a.height as u64 == b.height as u64
+}
+
+/// `txid:vout` with the txid in display byte order
+fn format_outpoint(outpoint: &OutPoint) -> String {
+    let mut txid = outpoint.hash;
+    txid.reverse();
+    format!("{}:{}", hex::encode(txid), outpoint.index)
+}
+
+fn format_coin(coin: &UTXO) -> String {
+    let script: &[u8] = &coin.script_pubkey;
+    format!(
+        "value={} height={} coinbase={} script={}",
+        coin.value,
+        coin.height,
+        coin.is_coinbase,
+        hex::encode(script)
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn coin(value: i64, height: u64) -> UTXO {
+        UTXO {
+            value: value as _,
+            script_pubkey: vec![0x51].into(),
+            height: height as _,
+            is_coinbase: false,
+        }
+    }
+
+    fn outpoint(n: u8) -> OutPoint {
+        OutPoint {
+            hash: [n; 32],
+            index: 0,
+        }
+    }
+
+    fn set(coins: &[(u8, UTXO)]) -> UtxoSet {
+        let mut set = UtxoSet::new();
+        for (n, coin) in coins {
+            set.insert(outpoint(*n), coin.clone());
+        }
+        set
+    }
+
+    #[test]
+    fn test_fold_confirms_assumed_coins() {
+        let mut reconciler = Reconciler::new(100);
+
+        // Chunk 100-199 spends a pre-run coin (unchecked) and creates coins 2 and 3
+        let assumed = set(&[(1, coin(50, 10))]);
+        let end = set(&[(2, coin(20, 150)), (3, coin(30, 180))]);
+        assert!(reconciler.fold(100, 199, &assumed, end).is_empty());
+
+        // Chunk 200-299 assumed coin 2 correctly but coin 3 with the wrong value
+        let assumed = set(&[(2, coin(20, 150)), (3, coin(31, 180))]);
+        let mismatches = reconciler.fold(200, 299, &assumed, set(&[]));
+        assert_eq!(mismatches.len(), 1);
+        assert_eq!(mismatches[0].outpoint, outpoint(3));
+        assert_eq!((reconciler.checked(), reconciler.mismatched()), (2, 1));
+
+        // Both were spent; assuming coin 2 again is a missing coin
+        let mismatches = reconciler.fold(300, 399, &set(&[(2, coin(20, 150))]), set(&[]));
+        assert!(mismatches[0].blvm.is_none());
+        assert!(mismatches[0].describe().0.starts_with("Missing("));
+
+        // A chunk that never finished stops the check
+        reconciler.gap(400);
+        assert!(reconciler.fold(500, 599, &set(&[(2, coin(20, 150))]), set(&[])).is_empty());
+        assert_eq!(reconciler.stopped_at(), Some(400));
+    }
+}
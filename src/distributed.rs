@@ -0,0 +1,734 @@
+//! Distributed Differential Execution
+//!
+//! Spreads one parallel differential run across machines. The coordinator
+//! generates (or reuses) the UTXO checkpoints, splits the range into chunks
+//! and serves them over HTTP; workers on other machines claim a chunk, fetch
+//! the checkpoint it starts from, validate it against their own block source
+//! and Core node, and post back a `ChunkRecord`. The coordinator merges the
+//! records into one `RunRecord`, so the usual JSON/HTML reports work as-is.
+//!
+//! Endpoints (JSON unless noted):
+//! - `POST /claim` - next chunk for a worker, `wait` while others are still running, or `done`
+//! - `GET /checkpoints/{height}` - zstd-compressed UTXO set after `height` (binary)
+//! - `POST /chunks/{id}/heartbeat` - extend the worker's lease on a chunk
+//! - `POST /chunks/{id}/report` - finished chunk record, or the error it failed with
+//!
+//! A chunk whose worker stops sending heartbeats is handed to another worker
+//! once its lease expires; a chunk that fails `max_attempts` times is given
+//! up on and listed in the run's report. There is no authentication - run
+//! the coordinator on a trusted network.
+
+use anyhow::{Context, Result};
+use blvm_consensus::UtxoSet;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime};
+
+use crate::checkpoint_store::CheckpointStore;
+use crate::chunk_sizing::{chunk_ranges, ChunkSizing};
+use crate::html_report::{ChunkRecord, RunRecord};
+use crate::parallel_differential::{BlockDataSource, ParallelConfig};
+
+/// Port the coordinator listens on by default
+pub const DEFAULT_PORT: u16 = 8787;
+
+/// Lease on a chunk; workers renew it every third of this
+pub const DEFAULT_LEASE: Duration = Duration::from_secs(120);
+
+/// Attempts (across all workers) before a chunk is given up on
+pub const DEFAULT_MAX_ATTEMPTS: usize = 3;
+
+/// How long idle workers wait before claiming again while chunks are still leased
+const WAIT_SECS: u64 = 10;
+
+/// How long the coordinator keeps answering `done` after the last chunk, so polling workers exit cleanly
+const DONE_GRACE: Duration = Duration::from_secs(2 * WAIT_SECS);
+
+/// Attempts per request before a worker gives up on reaching the coordinator
+const REQUEST_ATTEMPTS: u32 = 5;
+
+/// A chunk handed to a worker
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChunkAssignment {
+    pub id: usize,
+    pub start_height: u64,
+    pub end_height: u64,
+    /// Checkpoint the chunk starts from (`GET /checkpoints/{height}`); None = empty UTXO set
+    pub checkpoint_height: Option<u64>,
+}
+
+/// Split `start_height..=end_height` into chunks, each after the first starting from the checkpoint before it
+pub fn plan_chunks(start_height: u64, end_height: u64, chunk_size: u64, sizing: &ChunkSizing) -> Vec<ChunkAssignment> {
+    chunk_ranges(start_height, end_height, chunk_size, sizing)
+        .into_iter()
+        .enumerate()
+        .map(|(id, (start, end))| ChunkAssignment {
+            id,
+            start_height: start,
+            end_height: end,
+            checkpoint_height: (start > start_height).then(|| start - 1),
+        })
+        .collect()
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ClaimRequest {
+    pub worker: String,
+}
+
+/// Coordinator's answer to a claim
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum Claim {
+    /// Renew the lease (`/chunks/{id}/heartbeat`) well within `lease_secs`
+    Assigned { chunk: ChunkAssignment, lease_secs: u64 },
+    /// Nothing pending, but leased chunks may still come back
+    Wait { retry_secs: u64 },
+    Done,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct HeartbeatRequest {
+    pub worker: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum ChunkOutcome {
+    Finished(ChunkRecord),
+    /// The chunk failed on the worker even after its local retries
+    Failed(String),
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChunkReport {
+    pub worker: String,
+    pub outcome: ChunkOutcome,
+}
+
+/// A chunk given up on after `max_attempts`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FailedChunk {
+    pub chunk: ChunkAssignment,
+    pub error: String,
+}
+
+struct Lease {
+    chunk: ChunkAssignment,
+    worker: String,
+    expires: Instant,
+}
+
+/// Chunk bookkeeping: pending, leased to a worker, finished or failed
+struct ChunkQueue {
+    pending: VecDeque<ChunkAssignment>,
+    leased: HashMap<usize, Lease>,
+    attempts: HashMap<usize, usize>,
+    finished: Vec<(String, ChunkRecord)>,
+    failed: Vec<FailedChunk>,
+    lease: Duration,
+    max_attempts: usize,
+}
+
+impl ChunkQueue {
+    fn new(chunks: Vec<ChunkAssignment>, lease: Duration, max_attempts: usize) -> Self {
+        Self {
+            pending: chunks.into(),
+            leased: HashMap::new(),
+            attempts: HashMap::new(),
+            finished: Vec::new(),
+            failed: Vec::new(),
+            lease,
+            max_attempts,
+        }
+    }
+
+    fn claim(&mut self, worker: &str, now: Instant) -> Claim {
+        self.expire(now);
+        match self.pending.pop_front() {
+            Some(chunk) => {
+                *self.attempts.entry(chunk.id).or_default() += 1;
+                self.leased.insert(
+                    chunk.id,
+                    Lease {
+                        chunk: chunk.clone(),
+                        worker: worker.to_string(),
+                        expires: now + self.lease,
+                    },
+                );
+                Claim::Assigned { chunk, lease_secs: self.lease.as_secs() }
+            }
+            None if self.leased.is_empty() => Claim::Done,
+            None => Claim::Wait { retry_secs: WAIT_SECS },
+        }
+    }
+
+    /// Extend `worker`'s lease; false if the chunk isn't (or is no longer) leased to it
+    fn renew(&mut self, id: usize, worker: &str, now: Instant) -> bool {
+        match self.leased.get_mut(&id) {
+            Some(lease) if lease.worker == worker => {
+                lease.expires = now + self.lease;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Record a report; false if the chunk was already settled
+    ///
+    /// A finished record is taken from whichever worker sends it first, even one
+    /// whose lease expired; a failure only counts from the current leaseholder.
+    fn report(&mut self, id: usize, report: ChunkReport) -> bool {
+        let current = self.leased.get(&id).is_some_and(|lease| lease.worker == report.worker);
+        match report.outcome {
+            ChunkOutcome::Finished(record) => {
+                let unsettled = self.leased.remove(&id).is_some() || {
+                    let before = self.pending.len();
+                    self.pending.retain(|chunk| chunk.id != id);
+                    self.pending.len() < before
+                };
+                if unsettled {
+                    self.finished.push((report.worker, record));
+                }
+                unsettled
+            }
+            ChunkOutcome::Failed(error) if current => {
+                let lease = self.leased.remove(&id).expect("current lease");
+                eprintln!("⚠️  Chunk [{}-{}] failed on {}: {}", lease.chunk.start_height, lease.chunk.end_height, report.worker, error);
+                self.requeue_or_fail(lease.chunk, error);
+                true
+            }
+            ChunkOutcome::Failed(_) => false,
+        }
+    }
+
+    /// Take back chunks whose worker stopped renewing its lease
+    fn expire(&mut self, now: Instant) {
+        let expired: Vec<usize> = self.leased.iter().filter(|(_, lease)| lease.expires <= now).map(|(id, _)| *id).collect();
+        for id in expired {
+            let lease = self.leased.remove(&id).expect("expired lease");
+            eprintln!("⏰ Lease on chunk [{}-{}] held by {} expired", lease.chunk.start_height, lease.chunk.end_height, lease.worker);
+            self.requeue_or_fail(lease.chunk, format!("lease expired on {}", lease.worker));
+        }
+    }
+
+    fn requeue_or_fail(&mut self, chunk: ChunkAssignment, error: String) {
+        if self.attempts.get(&chunk.id).copied().unwrap_or_default() < self.max_attempts {
+            self.pending.push_back(chunk);
+        } else {
+            self.failed.push(FailedChunk { chunk, error });
+        }
+    }
+
+    fn is_done(&self) -> bool {
+        self.pending.is_empty() && self.leased.is_empty()
+    }
+}
+
+/// Where and how the coordinator hands out chunks
+#[derive(Debug, Clone)]
+pub struct CoordinatorConfig {
+    pub listen: SocketAddr,
+    pub lease: Duration,
+    pub max_attempts: usize,
+}
+
+impl Default for CoordinatorConfig {
+    fn default() -> Self {
+        Self {
+            listen: SocketAddr::from(([0, 0, 0, 0], DEFAULT_PORT)),
+            lease: DEFAULT_LEASE,
+            max_attempts: DEFAULT_MAX_ATTEMPTS,
+        }
+    }
+}
+
+/// Merged result of a distributed run
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DistributedRun {
+    pub record: RunRecord,
+    /// Chunks finished per worker
+    pub chunks_by_worker: BTreeMap<String, usize>,
+    pub failed: Vec<FailedChunk>,
+}
+
+impl DistributedRun {
+    pub fn save(&self, path: &std::path::Path) -> Result<()> {
+        std::fs::write(path, serde_json::to_string_pretty(self)?)
+            .with_context(|| format!("Failed to write {}", path.display()))
+    }
+}
+
+struct Coordinator {
+    queue: Mutex<ChunkQueue>,
+    checkpoints: Arc<Mutex<CheckpointStore>>,
+    started_at: SystemTime,
+    done: tokio::sync::Notify,
+}
+
+impl Coordinator {
+    fn check_done(&self, queue: &ChunkQueue) {
+        if queue.is_done() {
+            self.done.notify_one();
+        }
+    }
+}
+
+type Shared = axum::extract::State<Arc<Coordinator>>;
+type HttpError = (axum::http::StatusCode, String);
+
+async fn handle_claim(
+    axum::extract::State(coordinator): Shared,
+    axum::Json(request): axum::Json<ClaimRequest>,
+) -> axum::Json<Claim> {
+    let mut queue = coordinator.queue.lock().expect("chunk queue poisoned");
+    let claim = queue.claim(&request.worker, Instant::now());
+    if let Claim::Assigned { chunk, .. } = &claim {
+        println!("📤 Chunk [{}-{}] -> {}", chunk.start_height, chunk.end_height, request.worker);
+    }
+    coordinator.check_done(&queue);
+    axum::Json(claim)
+}
+
+async fn handle_checkpoint(
+    axum::extract::State(coordinator): Shared,
+    axum::extract::Path(height): axum::extract::Path<u64>,
+) -> Result<Vec<u8>, HttpError> {
+    let checkpoints = coordinator.checkpoints.clone();
+    let encoded = tokio::task::spawn_blocking(move || -> Result<Vec<u8>> {
+        let utxo_set = checkpoints.lock().expect("checkpoint store poisoned").load(height)?;
+        Ok(zstd::encode_all(crate::reproducer::encode_utxo_set(&utxo_set).as_slice(), 3)?)
+    })
+    .await
+    .map_err(|e| (axum::http::StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    encoded.map_err(|e| (axum::http::StatusCode::NOT_FOUND, format!("{:#}", e)))
+}
+
+async fn handle_heartbeat(
+    axum::extract::State(coordinator): Shared,
+    axum::extract::Path(id): axum::extract::Path<usize>,
+    axum::Json(request): axum::Json<HeartbeatRequest>,
+) -> axum::http::StatusCode {
+    let mut queue = coordinator.queue.lock().expect("chunk queue poisoned");
+    if queue.renew(id, &request.worker, Instant::now()) {
+        axum::http::StatusCode::NO_CONTENT
+    } else {
+        // Lease lost: the worker should stop and claim again
+        axum::http::StatusCode::GONE
+    }
+}
+
+async fn handle_report(
+    axum::extract::State(coordinator): Shared,
+    axum::extract::Path(id): axum::extract::Path<usize>,
+    axum::Json(mut report): axum::Json<ChunkReport>,
+) -> axum::http::StatusCode {
+    if let ChunkOutcome::Finished(record) = &mut report.outcome {
+        record.finished_after_secs = coordinator.started_at.elapsed().map(|d| d.as_secs_f64()).unwrap_or_default();
+        println!(
+            "✅ Chunk [{}-{}] from {}: {} blocks, {} divergences, {:.1}s",
+            record.start_height, record.end_height, report.worker, record.tested, record.divergences.len(), record.duration_secs
+        );
+    }
+    let mut queue = coordinator.queue.lock().expect("chunk queue poisoned");
+    let accepted = queue.report(id, report);
+    coordinator.check_done(&queue);
+    if accepted {
+        axum::http::StatusCode::NO_CONTENT
+    } else {
+        axum::http::StatusCode::CONFLICT
+    }
+}
+
+/// Coordinate a differential run over `start_height..=end_height` across remote workers
+///
+/// `block_source` is only used to generate checkpoints; blocks are validated by the workers.
+pub async fn run_coordinator(
+    start_height: u64,
+    end_height: u64,
+    config: &ParallelConfig,
+    coordinator_config: CoordinatorConfig,
+    block_source: Arc<BlockDataSource>,
+) -> Result<DistributedRun> {
+    if config.sample.is_some() {
+        anyhow::bail!("Sample mode isn't supported by the coordinator; run it on a single machine");
+    }
+    if config.optimistic.is_some() {
+        anyhow::bail!("Optimistic chunks aren't supported by the coordinator; it hands out stored checkpoints");
+    }
+    if config.verify_node.is_some() {
+        anyhow::bail!("A verify node needs blocks in order; it can't be used with distributed chunks");
+    }
+    let chain_height = crate::parallel_differential::source_tip(block_source.as_ref(), end_height).await?;
+    let actual_end = end_height.min(chain_height);
+    let started_at = SystemTime::now();
+
+    println!("🛰️  Starting distributed differential coordinator");
+    println!("   Range: {} to {}", start_height, actual_end);
+    println!("   Chunk size: {}", config.chunk_size);
+    println!("   Lease: {}s, {} attempts per chunk", coordinator_config.lease.as_secs(), coordinator_config.max_attempts);
+
+    let checkpoint_store =
+        crate::parallel_differential::prepare_checkpoints(start_height, actual_end, config, true, &block_source).await?;
+    let chunks = plan_chunks(start_height, actual_end, config.chunk_size, &config.chunk_sizing);
+    println!("\n📦 Created {} chunks for remote workers", chunks.len());
+
+    let coordinator = Arc::new(Coordinator {
+        queue: Mutex::new(ChunkQueue::new(chunks, coordinator_config.lease, coordinator_config.max_attempts)),
+        checkpoints: Arc::new(Mutex::new(checkpoint_store)),
+        started_at,
+        done: tokio::sync::Notify::new(),
+    });
+    let app = axum::Router::new()
+        .route("/claim", axum::routing::post(handle_claim))
+        .route("/checkpoints/:height", axum::routing::get(handle_checkpoint))
+        .route("/chunks/:id/heartbeat", axum::routing::post(handle_heartbeat))
+        .route("/chunks/:id/report", axum::routing::post(handle_report))
+        .with_state(coordinator.clone());
+    let listener = tokio::net::TcpListener::bind(coordinator_config.listen)
+        .await
+        .with_context(|| format!("Failed to listen on {}", coordinator_config.listen))?;
+    println!("\n⚡ Phase 2: Serving chunks on http://{}", coordinator_config.listen);
+
+    let waiter = coordinator.clone();
+    let lease = coordinator_config.lease;
+    axum::serve(listener, app)
+        .with_graceful_shutdown(async move {
+            loop {
+                tokio::select! {
+                    _ = waiter.done.notified() => break,
+                    // Nobody may be polling if every worker died; expire leases anyway
+                    _ = tokio::time::sleep(lease) => {
+                        let done = {
+                            let mut queue = waiter.queue.lock().expect("chunk queue poisoned");
+                            queue.expire(Instant::now());
+                            queue.is_done()
+                        };
+                        if done {
+                            break;
+                        }
+                    }
+                }
+            }
+            tokio::time::sleep(DONE_GRACE).await;
+        })
+        .await
+        .context("Coordinator server failed")?;
+
+    let mut queue = coordinator.queue.lock().expect("chunk queue poisoned");
+    let mut chunks_by_worker = BTreeMap::new();
+    let mut records = Vec::new();
+    for (worker, record) in queue.finished.drain(..) {
+        *chunks_by_worker.entry(worker).or_default() += 1;
+        records.push(record);
+    }
+    records.sort_by_key(|r| r.start_height);
+    let run = DistributedRun {
+        record: RunRecord {
+            started_at: started_at.duration_since(std::time::UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or_default(),
+            start_height,
+            end_height: actual_end,
+            chunk_size: config.chunk_size,
+            num_workers: chunks_by_worker.len(),
+            utxo_backend: config.utxo_backend.name().to_string(),
+            sample_seed: None,
+            chunks: records,
+        },
+        chunks_by_worker,
+        failed: std::mem::take(&mut queue.failed),
+    };
+    run.print_summary();
+    Ok(run)
+}
+
+impl DistributedRun {
+    pub fn total_divergences(&self) -> usize {
+        self.record.chunks.iter().map(|c| c.divergences.len()).sum()
+    }
+
+    /// Divergences no waiver covers (the ones that fail a run)
+    pub fn unwaived_divergences(&self) -> usize {
+        self.record.unwaived_divergences()
+    }
+
+    fn print_summary(&self) {
+        let tested: usize = self.record.chunks.iter().map(|c| c.tested).sum();
+        let matched: usize = self.record.chunks.iter().map(|c| c.matched).sum();
+        let wall_secs = self.record.chunks.iter().map(|c| c.finished_after_secs).fold(0.0, f64::max);
+        println!("\n📊 Distributed Differential Test Summary:");
+        println!("   Total blocks tested: {}", tested);
+        println!("   Matched: {}", matched);
+        println!("   Divergences: {}", self.total_divergences());
+        for (worker, chunks) in &self.chunks_by_worker {
+            println!("   {}: {} chunks", worker, chunks);
+        }
+        if !self.failed.is_empty() {
+            println!("   Failed chunks: {}", self.failed.len());
+            for failed in &self.failed {
+                println!("      [{}-{}]: {}", failed.chunk.start_height, failed.chunk.end_height, failed.error);
+            }
+        }
+        println!("   Wall time: {:.1}s ({:.1} blocks/sec)", wall_secs, tested as f64 / wall_secs.max(f64::EPSILON));
+        for chunk in &self.record.chunks {
+            for divergence in &chunk.divergences {
+                match &divergence.waiver {
+                    Some(reason) => println!(
+                        "   📝 Height {}: BLVM={}, Core={} (waived: {})",
+                        divergence.height, divergence.blvm_result, divergence.core_result, reason
+                    ),
+                    None => println!("   ❌ Height {}: BLVM={}, Core={}", divergence.height, divergence.blvm_result, divergence.core_result),
+                }
+            }
+        }
+    }
+}
+
+/// HTTP client a worker uses to talk to the coordinator
+#[derive(Clone)]
+struct CoordinatorClient {
+    http: reqwest::Client,
+    base_url: String,
+    worker: String,
+}
+
+impl CoordinatorClient {
+    /// Send a request, retrying connection failures with a growing delay
+    async fn send(&self, build: impl Fn() -> reqwest::RequestBuilder) -> Result<reqwest::Response> {
+        let mut attempt = 1;
+        loop {
+            match build().send().await {
+                Ok(response) => return Ok(response),
+                Err(e) if (e.is_connect() || e.is_timeout()) && attempt < REQUEST_ATTEMPTS => {
+                    eprintln!("⚠️  Coordinator {} unreachable ({}), retrying", self.base_url, e);
+                    tokio::time::sleep(Duration::from_secs(2u64.pow(attempt))).await;
+                    attempt += 1;
+                }
+                Err(e) => return Err(e).with_context(|| format!("Coordinator {} unreachable", self.base_url)),
+            }
+        }
+    }
+
+    async fn post<T: Serialize, R: DeserializeOwned>(&self, path: &str, body: &T) -> Result<R> {
+        let url = format!("{}{}", self.base_url, path);
+        let response = self.send(|| self.http.post(&url).json(body)).await?;
+        Ok(response.error_for_status()?.json().await?)
+    }
+
+    async fn claim(&self) -> Result<Claim> {
+        self.post("/claim", &ClaimRequest { worker: self.worker.clone() }).await
+    }
+
+    async fn checkpoint(&self, height: u64) -> Result<UtxoSet> {
+        let url = format!("{}/checkpoints/{}", self.base_url, height);
+        let response = self.send(|| self.http.get(&url)).await?;
+        let status = response.status();
+        if !status.is_success() {
+            anyhow::bail!("Checkpoint {} unavailable ({}): {}", height, status, response.text().await.unwrap_or_default());
+        }
+        let compressed = response.bytes().await?;
+        let encoded = zstd::decode_all(compressed.as_ref()).context("Failed to decompress checkpoint")?;
+        crate::reproducer::decode_utxo_set(&encoded)
+    }
+
+    /// False once the coordinator has handed the chunk to someone else
+    async fn heartbeat(&self, id: usize) -> Result<bool> {
+        let url = format!("{}/chunks/{}/heartbeat", self.base_url, id);
+        let body = HeartbeatRequest { worker: self.worker.clone() };
+        let response = self.send(|| self.http.post(&url).json(&body)).await?;
+        Ok(response.status() != reqwest::StatusCode::GONE)
+    }
+
+    async fn report(&self, id: usize, outcome: ChunkOutcome) -> Result<bool> {
+        let url = format!("{}/chunks/{}/report", self.base_url, id);
+        let body = ChunkReport { worker: self.worker.clone(), outcome };
+        let response = self.send(|| self.http.post(&url).json(&body)).await?;
+        Ok(response.status() != reqwest::StatusCode::CONFLICT)
+    }
+}
+
+/// Claim and validate chunks from the coordinator at `coordinator_url` until it says it's done
+///
+/// Runs `config.num_workers` chunks at a time against `block_source` (with the
+/// usual retries and fallbacks) and returns how many chunks this worker finished.
+pub async fn run_worker(
+    coordinator_url: &str,
+    worker_name: String,
+    config: ParallelConfig,
+    block_source: Arc<BlockDataSource>,
+) -> Result<usize> {
+    let client = CoordinatorClient {
+        http: reqwest::Client::new(),
+        base_url: coordinator_url.trim_end_matches('/').to_string(),
+        worker: worker_name,
+    };
+    println!("🛰️  Worker {} for coordinator {}", client.worker, client.base_url);
+    println!("   Slots: {}", config.num_workers);
+    println!("   Block source: {}", block_source.name());
+
+    let config = Arc::new(config);
+    let mut handles = Vec::new();
+    for _ in 0..config.num_workers.max(1) {
+        let (client, config, block_source) = (client.clone(), config.clone(), block_source.clone());
+        handles.push(tokio::spawn(async move { worker_slot(client, config, block_source).await }));
+    }
+    let mut finished = 0;
+    for handle in handles {
+        finished += handle.await.context("Worker slot panicked")??;
+    }
+    println!("\n✅ Worker {} done: {} chunks", client.worker, finished);
+    Ok(finished)
+}
+
+async fn worker_slot(
+    client: CoordinatorClient,
+    config: Arc<ParallelConfig>,
+    block_source: Arc<BlockDataSource>,
+) -> Result<usize> {
+    let mut finished = 0;
+    loop {
+        let (chunk, lease) = match client.claim().await? {
+            Claim::Assigned { chunk, lease_secs } => (chunk, Duration::from_secs(lease_secs)),
+            Claim::Wait { retry_secs } => {
+                tokio::time::sleep(Duration::from_secs(retry_secs)).await;
+                continue;
+            }
+            Claim::Done => return Ok(finished),
+        };
+        println!("📥 Chunk [{}-{}]", chunk.start_height, chunk.end_height);
+
+        let heartbeat = {
+            let client = client.clone();
+            let id = chunk.id;
+            tokio::spawn(async move {
+                loop {
+                    tokio::time::sleep(lease / 3).await;
+                    match client.heartbeat(id).await {
+                        Ok(true) => {}
+                        Ok(false) => {
+                            eprintln!("⚠️  Lost the lease on chunk {}; its result may be discarded", id);
+                            break;
+                        }
+                        Err(e) => eprintln!("⚠️  Heartbeat for chunk {} failed: {:#}", id, e),
+                    }
+                }
+            })
+        };
+        let outcome = match validate_assignment(&client, &config, &chunk, block_source.clone()).await {
+            Ok(record) => ChunkOutcome::Finished(record),
+            Err(e) => {
+                eprintln!("❌ Chunk [{}-{}] failed: {:#}", chunk.start_height, chunk.end_height, e);
+                ChunkOutcome::Failed(format!("{:#}", e))
+            }
+        };
+        heartbeat.abort();
+        let succeeded = matches!(outcome, ChunkOutcome::Finished(_));
+        if !client.report(chunk.id, outcome).await? {
+            println!("   Chunk [{}-{}] was already settled by another worker", chunk.start_height, chunk.end_height);
+        } else if succeeded {
+            finished += 1;
+        }
+    }
+}
+
+/// Fetch the chunk's checkpoint and validate it locally
+async fn validate_assignment(
+    client: &CoordinatorClient,
+    config: &ParallelConfig,
+    chunk: &ChunkAssignment,
+    block_source: Arc<BlockDataSource>,
+) -> Result<ChunkRecord> {
+    let checkpoint_utxo = match chunk.checkpoint_height {
+        Some(height) => client.checkpoint(height).await?,
+        None => UtxoSet::new(),
+    };
+    let progress = crate::progress::RunProgress::new(chunk.end_height - chunk.start_height + 1);
+    let block_chunk = config.chunk(chunk.start_height, chunk.end_height, Some(checkpoint_utxo), progress.clone());
+    let result = crate::parallel_differential::validate_chunk_with_retry(
+        block_chunk,
+        block_source,
+        &config.fallback_sources,
+        config.chunk_retries,
+    )
+    .await;
+    progress.finish();
+    let result = result?;
+    if let Some(block_panic) = &result.poisoned {
+        eprintln!("☣️  Chunk [{}-{}] poisoned after {} blocks: {}", chunk.start_height, chunk.end_height, result.tested, block_panic);
+        crate::parallel_differential::quarantine_chunk(&config.quarantine_dir, chunk.start_height, chunk.end_height, block_panic.clone());
+    }
+    // The coordinator fills in finished_after_secs against its own start time
+    Ok(ChunkRecord::from_result(&result, result.finished_at))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn finished(worker: &str, chunk: &ChunkAssignment) -> ChunkReport {
+        ChunkReport {
+            worker: worker.to_string(),
+            outcome: ChunkOutcome::Finished(ChunkRecord {
+                start_height: chunk.start_height,
+                end_height: chunk.end_height,
+                tested: (chunk.end_height - chunk.start_height + 1) as usize,
+                matched: (chunk.end_height - chunk.start_height + 1) as usize,
+                duration_secs: 1.0,
+                finished_after_secs: 0.0,
+                utxo_count: 0,
+                divergences: Vec::new(),
+                poisoned: None,
+                memory: Default::default(),
+            }),
+        }
+    }
+
+    fn failed(worker: &str) -> ChunkReport {
+        ChunkReport {
+            worker: worker.to_string(),
+            outcome: ChunkOutcome::Failed("boom".to_string()),
+        }
+    }
+
+    #[test]
+    fn test_plan_chunks_reference_previous_checkpoint() {
+        let chunks = plan_chunks(0, 250, 100, &ChunkSizing::Equal);
+        let ranges: Vec<_> = chunks.iter().map(|c| (c.start_height, c.end_height, c.checkpoint_height)).collect();
+        assert_eq!(ranges, vec![(0, 99, None), (100, 199, Some(99)), (200, 250, Some(199))]);
+        assert_eq!(chunks.iter().map(|c| c.id).collect::<Vec<_>>(), vec![0, 1, 2]);
+    }
+
+    #[test]
+    fn test_queue_expires_retries_and_gives_up() {
+        let lease = Duration::from_secs(60);
+        let mut queue = ChunkQueue::new(plan_chunks(0, 199, 100, &ChunkSizing::Equal), lease, 2);
+        let now = Instant::now();
+
+        let Claim::Assigned { chunk: first, lease_secs } = queue.claim("a", now) else { panic!("expected a chunk") };
+        let Claim::Assigned { chunk: second, .. } = queue.claim("b", now) else { panic!("expected a chunk") };
+        assert_eq!(lease_secs, 60);
+        assert_eq!(queue.claim("c", now), Claim::Wait { retry_secs: WAIT_SECS });
+
+        // "a" keeps its lease alive; "b" goes silent and its chunk moves to "c"
+        assert!(queue.renew(first.id, "a", now + lease / 2));
+        let later = now + lease + Duration::from_secs(1);
+        assert_eq!(queue.claim("c", later), Claim::Assigned { chunk: second.clone(), lease_secs });
+        assert!(!queue.renew(second.id, "b", later));
+
+        // A late finish from "b" still counts; "c"'s duplicate is rejected
+        assert!(queue.report(second.id, finished("b", &second)));
+        assert!(!queue.report(second.id, finished("c", &second)));
+
+        // A failure from someone else's lease is ignored; the holder's own is retried once
+        assert!(!queue.report(first.id, failed("c")));
+        assert!(queue.report(first.id, failed("a")));
+        assert_eq!(queue.claim("a", later), Claim::Assigned { chunk: first.clone(), lease_secs });
+        assert!(queue.report(first.id, failed("a")));
+        assert_eq!(queue.claim("a", later), Claim::Done);
+
+        assert!(queue.is_done());
+        assert_eq!(queue.finished.len(), 1);
+        assert_eq!(queue.failed.len(), 1);
+        assert_eq!(queue.failed[0].chunk, first);
+    }
+}
@@ -0,0 +1,237 @@
+//! Divergence Minimization
+//!
+//! A divergent block can carry thousands of transactions, only one of which
+//! trips the bug. When BLVM rejects a block Core accepted, the minimizer
+//! bisects the block's transaction list (delta debugging): it rebuilds
+//! synthetic blocks from subsets - coinbase plus the chosen transactions and
+//! every in-block parent they spend, recommitted - and re-runs BLVM on each
+//! against the same pre-state, keeping any smaller subset that is still
+//! rejected for the same reason. The result is a 1-minimal transaction set:
+//! dropping any single transaction from it makes the rejection go away.
+//!
+//! Synthetic blocks keep the original header apart from the merkle root, and
+//! their coinbase claims nothing so dropped fees can't make it overpay.
+//! Rejection reasons are compared with digits removed, since they often embed
+//! transaction indices that shift as the block shrinks. Core can't judge
+//! synthetic blocks off its tip, so divergences where BLVM is the lenient
+//! side aren't minimized.
+
+use anyhow::{Context, Result};
+use blvm_consensus::types::Network;
+use blvm_consensus::UtxoSet;
+use std::collections::HashMap;
+use std::path::Path;
+
+use crate::mutation_differential::{blvm_verdict, raw_transactions, subset_block};
+
+/// Most BLVM re-runs spent minimizing one block
+pub const MAX_TESTS: usize = 256;
+
+/// The smallest transaction set found that still reproduces a rejection
+#[derive(Debug, Clone)]
+pub struct MinimizedCase {
+    pub height: u64,
+    /// Transactions in the original block (coinbase included)
+    pub original_txs: usize,
+    /// Indices in the original block of the kept transactions (the coinbase, always kept, isn't listed)
+    pub kept: Vec<usize>,
+    /// Display-order txids of the kept transactions
+    pub txids: Vec<String>,
+    /// The synthetic block: coinbase plus the kept transactions
+    pub block_bytes: Vec<u8>,
+    /// BLVM's rejection of the synthetic block
+    pub blvm_rejection: String,
+    /// BLVM runs spent
+    pub tests_run: usize,
+}
+
+impl MinimizedCase {
+    /// Write `minimized_block.bin` and `minimized.json` into `dir` (e.g. the reproducer bundle)
+    pub fn write(&self, dir: &Path) -> Result<()> {
+        std::fs::write(dir.join("minimized_block.bin"), &self.block_bytes)
+            .with_context(|| format!("Failed to write minimized block in {}", dir.display()))?;
+        let meta = serde_json::json!({
+            "height": self.height,
+            "original_txs": self.original_txs,
+            "kept_tx_indices": self.kept,
+            "kept_txids": self.txids,
+            "blvm_rejection": self.blvm_rejection,
+            "tests_run": self.tests_run,
+            "complete": self.tests_run < MAX_TESTS,
+        });
+        std::fs::write(dir.join("minimized.json"), serde_json::to_string_pretty(&meta)?)?;
+        Ok(())
+    }
+}
+
+/// Rejection reason with digits removed (indices shift as transactions are dropped)
+fn reason_signature(reason: &str) -> String {
+    reason.chars().filter(|c| !c.is_ascii_digit()).collect()
+}
+
+/// `set` plus every in-block ancestor of its transactions, sorted
+fn with_ancestors(set: &[usize], parents: &[Vec<usize>]) -> Vec<usize> {
+    let mut included = vec![false; parents.len()];
+    let mut stack = set.to_vec();
+    while let Some(idx) = stack.pop() {
+        if !std::mem::replace(&mut included[idx], true) {
+            stack.extend(&parents[idx]);
+        }
+    }
+    (0..parents.len()).filter(|&idx| included[idx]).collect()
+}
+
+/// Delta debugging: shrink `items` while `test` holds, within `budget` tests
+///
+/// `test(items)` must hold on entry. Returns the reduced set and the tests run.
+fn ddmin(mut items: Vec<usize>, budget: usize, mut test: impl FnMut(&[usize]) -> bool) -> (Vec<usize>, usize) {
+    let mut tests = 0;
+    let mut granularity = 2;
+    while items.len() >= 2 && tests < budget {
+        let chunk_len = items.len().div_ceil(granularity);
+        let chunks: Vec<Vec<usize>> = items.chunks(chunk_len).map(|c| c.to_vec()).collect();
+
+        // A single chunk that still fails: bisect into it
+        let mut reduced = None;
+        for chunk in &chunks {
+            if tests >= budget {
+                break;
+            }
+            tests += 1;
+            if test(chunk) {
+                reduced = Some((chunk.clone(), 2));
+                break;
+            }
+        }
+        // Otherwise try dropping one chunk at a time
+        if reduced.is_none() && chunks.len() > 2 {
+            for (skip, _) in chunks.iter().enumerate() {
+                if tests >= budget {
+                    break;
+                }
+                let complement: Vec<usize> = chunks
+                    .iter()
+                    .enumerate()
+                    .filter(|(idx, _)| *idx != skip)
+                    .flat_map(|(_, chunk)| chunk.iter().copied())
+                    .collect();
+                tests += 1;
+                if test(&complement) {
+                    reduced = Some((complement, (granularity - 1).max(2)));
+                    break;
+                }
+            }
+        }
+        match reduced {
+            Some((smaller, next_granularity)) => {
+                items = smaller;
+                granularity = next_granularity;
+            }
+            None if granularity >= items.len() => break,
+            None => granularity = (granularity * 2).min(items.len()),
+        }
+    }
+    (items, tests)
+}
+
+/// Find the smallest set of `block_bytes`'s transactions that BLVM still rejects with `blvm_rejection`
+///
+/// None if a synthetic block with every transaction isn't rejected the same
+/// way (the rejection depends on something the rebuild changes).
+pub fn minimize_divergence(
+    block_bytes: &[u8],
+    pre_state: &UtxoSet,
+    height: u64,
+    blvm_rejection: &str,
+) -> Result<Option<MinimizedCase>> {
+    let txs = raw_transactions(block_bytes)?;
+    let txids: Vec<[u8; 32]> = txs.iter().map(|tx| tx.txid()).collect();
+    let index_of: HashMap<[u8; 32], usize> = txids.iter().enumerate().map(|(idx, txid)| (*txid, idx)).collect();
+    let parents: Vec<Vec<usize>> = txs
+        .iter()
+        .enumerate()
+        .map(|(idx, tx)| {
+            tx.inputs
+                .iter()
+                .filter_map(|input| index_of.get(&input.prevout[..32]).copied())
+                .filter(|&parent| parent != 0 && parent < idx)
+                .collect()
+        })
+        .collect();
+
+    let target = reason_signature(blvm_rejection);
+    let mut last_rejection = String::new();
+    let mut test = |set: &[usize]| -> bool {
+        let keep = with_ancestors(set, &parents);
+        let Ok(candidate) = subset_block(block_bytes, &keep) else {
+            return false;
+        };
+        match blvm_verdict(&candidate, pre_state, height, Network::Mainnet) {
+            Err(reason) if reason_signature(&reason) == target => {
+                last_rejection = reason;
+                true
+            }
+            _ => false,
+        }
+    };
+
+    let all: Vec<usize> = (1..txs.len()).collect();
+    if !test(&all) {
+        return Ok(None);
+    }
+    // The coinbase alone may be enough
+    let (kept, tests_run) = if test(&[]) {
+        (Vec::new(), 2)
+    } else {
+        let (reduced, tests) = ddmin(all, MAX_TESTS - 2, &mut test);
+        // Re-run on the final set so the recorded rejection is its own
+        test(&reduced);
+        (reduced, tests + 3)
+    };
+    let kept = with_ancestors(&kept, &parents);
+    let block = subset_block(block_bytes, &kept)?;
+
+    Ok(Some(MinimizedCase {
+        height,
+        original_txs: txs.len(),
+        txids: kept
+            .iter()
+            .map(|&idx| {
+                let mut txid = txids[idx];
+                txid.reverse();
+                hex::encode(txid)
+            })
+            .collect(),
+        kept,
+        block_bytes: block,
+        blvm_rejection: last_rejection,
+        tests_run,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ddmin_finds_a_minimal_pair() {
+        // "Fails" whenever both 13 and 71 are present
+        let (kept, tests) = ddmin((1..100).collect(), MAX_TESTS, |set| set.contains(&13) && set.contains(&71));
+        assert_eq!(kept, vec![13, 71]);
+        assert!(tests < 100, "{} tests", tests);
+
+        // Budget exhausted: still a failing (if not minimal) set
+        let (kept, tests) = ddmin((1..100).collect(), 3, |set| set.contains(&13) && set.contains(&71));
+        assert!(kept.contains(&13) && kept.contains(&71));
+        assert_eq!(tests, 3);
+    }
+
+    #[test]
+    fn test_ancestors_and_reason_signature() {
+        // 3 spends 1, 4 spends 3
+        let parents = vec![vec![], vec![], vec![], vec![1], vec![3]];
+        assert_eq!(with_ancestors(&[4], &parents), vec![1, 3, 4]);
+        assert_eq!(with_ancestors(&[2], &parents), vec![2]);
+        assert_eq!(reason_signature("tx 41 input 0: script failed"), reason_signature("tx 2 input 0: script failed"));
+    }
+}
@@ -0,0 +1,298 @@
+//! Setup Doctor
+//!
+//! Verifies a configured environment end-to-end before a long run: block data
+//! source, oracle RPC, chunked cache, checkpoint/UTXO backend config and
+//! resource headroom. Each check reports pass/warn/fail with a short detail.
+
+use anyhow::{Context, Result};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use crate::block_file_reader::Network as BlockFileNetwork;
+use crate::block_hash::block_hash_hex;
+use crate::core_rpc_client::{BitcoinNetwork, CoreRpcClient, RpcConfig};
+use crate::block_source::BlockSource;
+use crate::parallel_differential::create_block_data_source;
+
+/// Minimum free memory before warning (bytes)
+const MIN_MEMORY_BYTES: u64 = 8 * 1024 * 1024 * 1024;
+/// Minimum free disk space at the cache location before warning (bytes)
+const MIN_DISK_BYTES: u64 = 50 * 1024 * 1024 * 1024;
+
+/// Outcome of a single check
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CheckStatus {
+    Pass,
+    Warn,
+    Fail,
+}
+
+/// Result of a single check
+#[derive(Debug, Clone)]
+pub struct CheckResult {
+    pub name: &'static str,
+    pub status: CheckStatus,
+    pub detail: String,
+}
+
+impl CheckResult {
+    fn pass(name: &'static str, detail: impl Into<String>) -> Self {
+        Self { name, status: CheckStatus::Pass, detail: detail.into() }
+    }
+
+    fn warn(name: &'static str, detail: impl Into<String>) -> Self {
+        Self { name, status: CheckStatus::Warn, detail: detail.into() }
+    }
+
+    fn fail(name: &'static str, detail: impl Into<String>) -> Self {
+        Self { name, status: CheckStatus::Fail, detail: detail.into() }
+    }
+}
+
+/// Doctor options
+#[derive(Debug, Clone, Default)]
+pub struct DoctorConfig {
+    /// Shared block cache directory (optional)
+    pub cache_dir: Option<PathBuf>,
+    /// Skip the oracle RPC check (e.g. P2P-only setups)
+    pub skip_rpc: bool,
+}
+
+/// Run all checks and print a summary. Returns the individual results.
+pub async fn run_doctor(config: &DoctorConfig) -> Result<Vec<CheckResult>> {
+    println!("🩺 Checking blvm-bench setup...\n");
+
+    let mut results = Vec::new();
+
+    let rpc_client = if config.skip_rpc {
+        results.push(CheckResult::warn("oracle rpc", "skipped (--skip-rpc)"));
+        None
+    } else {
+        let (result, client) = check_rpc().await;
+        results.push(result);
+        client
+    };
+
+    results.push(check_data_source(config.cache_dir.as_deref(), rpc_client).await);
+    results.push(check_chunked_cache());
+    results.push(check_utxo_backend());
+    results.push(check_memory());
+    let disk_path = config
+        .cache_dir
+        .clone()
+        .or_else(crate::chunked_cache::get_chunks_dir)
+        .unwrap_or_else(|| PathBuf::from("."));
+    results.push(check_disk(&disk_path));
+
+    for result in &results {
+        let icon = match result.status {
+            CheckStatus::Pass => "✅",
+            CheckStatus::Warn => "⚠️ ",
+            CheckStatus::Fail => "❌",
+        };
+        println!("{} {:<14} {}", icon, result.name, result.detail);
+    }
+
+    let failed = results.iter().filter(|r| r.status == CheckStatus::Fail).count();
+    let warned = results.iter().filter(|r| r.status == CheckStatus::Warn).count();
+    println!();
+    if failed == 0 {
+        println!("✅ Setup looks good ({} warning(s))", warned);
+    } else {
+        println!("❌ {} check(s) failed, {} warning(s)", failed, warned);
+    }
+
+    Ok(results)
+}
+
+fn mainnet_genesis() -> &'static str {
+    crate::p2p_client::genesis_hex(&BlockFileNetwork::Mainnet)
+}
+
+/// Oracle RPC: reachable, authenticated, on mainnet
+async fn check_rpc() -> (CheckResult, Option<Arc<CoreRpcClient>>) {
+    const NAME: &str = "oracle rpc";
+    let config = RpcConfig::from_env();
+    let url = config.url.clone();
+    let client = CoreRpcClient::new(config);
+
+    let network = match client.detect_network().await {
+        Ok(network) => network,
+        Err(e) => {
+            return (
+                CheckResult::fail(NAME, format!("{}: {} (check BITCOIN_RPC_* credentials)", url, e)),
+                None,
+            )
+        }
+    };
+    if network != BitcoinNetwork::Mainnet {
+        return (
+            CheckResult::fail(NAME, format!("{} is on {}, differential runs expect mainnet", url, network.as_str())),
+            None,
+        );
+    }
+
+    let detail = match (client.getblockcount().await, client.get_pruning_info().await) {
+        (Ok(height), Ok((true, prune_height))) => {
+            return (
+                CheckResult::warn(
+                    NAME,
+                    format!("{} mainnet, height {}, pruned below {:?}", url, height, prune_height),
+                ),
+                Some(Arc::new(client)),
+            )
+        }
+        (Ok(height), _) => format!("{} mainnet, height {}", url, height),
+        (Err(e), _) => return (CheckResult::fail(NAME, format!("{}: {}", url, e)), None),
+    };
+    (CheckResult::pass(NAME, detail), Some(Arc::new(client)))
+}
+
+/// Block data source: selected source serves the mainnet genesis block
+async fn check_data_source(
+    cache_dir: Option<&Path>,
+    rpc_client: Option<Arc<CoreRpcClient>>,
+) -> CheckResult {
+    const NAME: &str = "data source";
+    let source = match create_block_data_source(BlockFileNetwork::Mainnet, cache_dir, rpc_client) {
+        Ok(source) => source,
+        Err(e) => return CheckResult::fail(NAME, e.to_string()),
+    };
+
+    let kind = source.name().to_lowercase();
+    let genesis = source.get_block(0).await;
+
+    match genesis.map(|bytes| block_hash_hex(&bytes)) {
+        Ok(Some(hash)) if hash == mainnet_genesis() => {
+            CheckResult::pass(NAME, format!("{} serves mainnet genesis", kind))
+        }
+        Ok(Some(hash)) => CheckResult::fail(NAME, format!("{} returned genesis {} (wrong network?)", kind, hash)),
+        Ok(None) => CheckResult::fail(NAME, format!("{} returned a truncated genesis block", kind)),
+        Err(e) => CheckResult::fail(NAME, format!("{}: failed to read genesis: {}", kind, e)),
+    }
+}
+
+/// Chunked cache: metadata parses, chunk files exist, first block is genesis
+fn check_chunked_cache() -> CheckResult {
+    use std::io::Read;
+    const NAME: &str = "chunked cache";
+
+    let Some(chunks_dir) = crate::chunked_cache::get_chunks_dir() else {
+        return CheckResult::warn(NAME, "no cache directory configured");
+    };
+    let meta = match crate::chunked_cache::load_chunk_metadata(&chunks_dir) {
+        Ok(Some(meta)) => meta,
+        Ok(None) => return CheckResult::warn(NAME, format!("no valid chunks.meta in {}", chunks_dir.display())),
+        Err(e) => return CheckResult::fail(NAME, format!("unreadable chunks.meta: {}", e)),
+    };
+
+    let missing: Vec<usize> = (0..meta.num_chunks)
+        .filter(|i| !chunks_dir.join(format!("chunk_{}.bin.zst", i)).exists())
+        .collect();
+    if !missing.is_empty() {
+        return CheckResult::fail(NAME, format!("{} of {} chunk files missing (first: chunk_{})", missing.len(), meta.num_chunks, missing[0]));
+    }
+
+    // Decode just the first block of chunk 0
+    let first_block = (|| -> Result<Vec<u8>> {
+        let mut child = crate::chunked_cache::decompress_chunk_streaming(&chunks_dir.join("chunk_0.bin.zst"))?;
+        let stdout = child.stdout.as_mut().context("zstd produced no stdout")?;
+        let mut len_buf = [0u8; 4];
+        stdout.read_exact(&mut len_buf)?;
+        let mut block = vec![0u8; u32::from_le_bytes(len_buf) as usize];
+        stdout.read_exact(&mut block)?;
+        let _ = child.kill();
+        let _ = child.wait();
+        Ok(block)
+    })();
+
+    match first_block.map(|bytes| block_hash_hex(&bytes)) {
+        Ok(Some(hash)) if hash == mainnet_genesis() => CheckResult::pass(
+            NAME,
+            format!("{} blocks in {} chunks ({})", meta.total_blocks, meta.num_chunks, meta.compression),
+        ),
+        Ok(_) => CheckResult::fail(NAME, "first cached block is not mainnet genesis"),
+        Err(e) => CheckResult::fail(NAME, format!("failed to decode chunk_0: {}", e)),
+    }
+}
+
+/// Checkpoints: configured UTXO backend parses and its storage and the
+/// checkpoint directory are writable
+fn check_utxo_backend() -> CheckResult {
+    use crate::utxo_backend::UtxoBackend;
+    const NAME: &str = "checkpoints";
+
+    let backend = match UtxoBackend::from_env() {
+        Ok(backend) => backend,
+        Err(e) => return CheckResult::fail(NAME, e.to_string()),
+    };
+    if let UtxoBackend::Disk(dir) = &backend {
+        let probe = dir.join(".doctor-probe");
+        let writable = std::fs::create_dir_all(dir).and_then(|_| std::fs::write(&probe, b"ok"));
+        let _ = std::fs::remove_file(&probe);
+        if let Err(e) = writable {
+            return CheckResult::fail(NAME, format!("disk UTXO dir {} not writable: {}", dir.display(), e));
+        }
+    }
+    let checkpoint_dir = crate::checkpoint_store::default_checkpoint_dir();
+    let probe = checkpoint_dir.join(".doctor-probe");
+    let writable = std::fs::create_dir_all(&checkpoint_dir).and_then(|_| std::fs::write(&probe, b"ok"));
+    let _ = std::fs::remove_file(&probe);
+    if let Err(e) = writable {
+        return CheckResult::fail(NAME, format!("checkpoint dir {} not writable: {}", checkpoint_dir.display(), e));
+    }
+    CheckResult::pass(NAME, format!("UTXO backend '{}', checkpoints in {}", backend.name(), checkpoint_dir.display()))
+}
+
+/// Memory headroom from /proc/meminfo
+fn check_memory() -> CheckResult {
+    const NAME: &str = "memory";
+    let available = std::fs::read_to_string("/proc/meminfo").ok().and_then(|meminfo| {
+        meminfo
+            .lines()
+            .find(|l| l.starts_with("MemAvailable:"))
+            .and_then(|l| l.split_whitespace().nth(1))
+            .and_then(|kb| kb.parse::<u64>().ok())
+            .map(|kb| kb * 1024)
+    });
+
+    match available {
+        Some(bytes) if bytes >= MIN_MEMORY_BYTES => CheckResult::pass(NAME, format!("{} available", format_gib(bytes))),
+        Some(bytes) => CheckResult::warn(NAME, format!("only {} available (recommend {})", format_gib(bytes), format_gib(MIN_MEMORY_BYTES))),
+        None => CheckResult::warn(NAME, "could not read /proc/meminfo"),
+    }
+}
+
+/// Disk headroom via `df` at the cache location
+fn check_disk(path: &Path) -> CheckResult {
+    const NAME: &str = "disk";
+    let available = std::process::Command::new("df")
+        .arg("-Pk")
+        .arg(path)
+        .output()
+        .ok()
+        .filter(|out| out.status.success())
+        .and_then(|out| {
+            String::from_utf8_lossy(&out.stdout)
+                .lines()
+                .nth(1)
+                .and_then(|l| l.split_whitespace().nth(3))
+                .and_then(|kb| kb.parse::<u64>().ok())
+                .map(|kb| kb * 1024)
+        });
+
+    match available {
+        Some(bytes) if bytes >= MIN_DISK_BYTES => {
+            CheckResult::pass(NAME, format!("{} free at {}", format_gib(bytes), path.display()))
+        }
+        Some(bytes) => CheckResult::warn(
+            NAME,
+            format!("only {} free at {} (recommend {})", format_gib(bytes), path.display(), format_gib(MIN_DISK_BYTES)),
+        ),
+        None => CheckResult::warn(NAME, format!("could not determine free space at {}", path.display())),
+    }
+}
+
+fn format_gib(bytes: u64) -> String {
+    format!("{:.1} GiB", bytes as f64 / (1024.0 * 1024.0 * 1024.0))
+}
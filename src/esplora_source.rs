@@ -0,0 +1,219 @@
+//! Esplora Block Source
+//!
+//! Reads blocks from an Esplora-compatible HTTP API (blockstream.info,
+//! mempool.space or a self-hosted electrs) for users without any local node.
+//! Public instances are shared infrastructure, so requests are paced through an
+//! `RpcLimiter` (a few per second, backing off when the server answers 429 or
+//! 503), and every block is cached on disk by hash: blocks never change, so a
+//! re-run of the same range only costs the height lookups. Meant for small-range
+//! differential runs and corpus extraction, not full syncs.
+//!
+//! Configuration:
+//! - `BLVM_ESPLORA_URL` - API base URL, or `blockstream` for the public instance
+//!   of the selected network (enables the source)
+//! - `BLVM_ESPLORA_CACHE` - block cache directory (default: `~/.cache/blvm-bench/esplora`)
+//! - `BLVM_ESPLORA_MAX_PER_SEC` - request rate (default: 4)
+
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use crate::block_file_reader::Network;
+use crate::block_hash::block_hash_hex;
+use crate::block_source::BlockSource;
+use crate::rpc_limiter::{RpcLimiter, RpcLimits};
+
+/// Requests per second by default (polite for public instances)
+pub const DEFAULT_MAX_PER_SEC: f64 = 4.0;
+
+/// Attempts per request before giving up on a rate-limited or unavailable server
+const MAX_ATTEMPTS: u32 = 6;
+
+/// Esplora source configuration
+#[derive(Debug, Clone)]
+pub struct EsploraConfig {
+    /// API base URL without trailing slash (e.g. "https://blockstream.info/api")
+    pub base_url: String,
+    /// Where fetched blocks are kept (None = no disk cache)
+    pub cache_dir: Option<PathBuf>,
+    pub max_per_sec: f64,
+}
+
+impl EsploraConfig {
+    pub fn new(base_url: &str, network: Network) -> Self {
+        Self {
+            base_url: resolve_url(base_url, network),
+            cache_dir: dirs::cache_dir().map(|d| d.join("blvm-bench/esplora")),
+            max_per_sec: DEFAULT_MAX_PER_SEC,
+        }
+    }
+
+    /// Create from environment variables (None unless `BLVM_ESPLORA_URL` is set)
+    pub fn from_env(network: Network) -> Result<Option<Self>> {
+        let Ok(url) = std::env::var("BLVM_ESPLORA_URL") else {
+            return Ok(None);
+        };
+        let mut config = Self::new(&url, network);
+        if let Ok(dir) = std::env::var("BLVM_ESPLORA_CACHE") {
+            config.cache_dir = Some(PathBuf::from(dir));
+        }
+        if let Ok(rate) = std::env::var("BLVM_ESPLORA_MAX_PER_SEC") {
+            config.max_per_sec = rate.parse().with_context(|| format!("Invalid BLVM_ESPLORA_MAX_PER_SEC '{}'", rate))?;
+            if !(config.max_per_sec > 0.0 && config.max_per_sec.is_finite()) {
+                anyhow::bail!("BLVM_ESPLORA_MAX_PER_SEC must be positive, got {}", rate);
+            }
+        }
+        Ok(Some(config))
+    }
+}
+
+/// `blockstream` expands to the public instance for `network`; URLs lose their trailing slash
+fn resolve_url(url: &str, network: Network) -> String {
+    match (url.trim(), network) {
+        ("blockstream", Network::Mainnet) => "https://blockstream.info/api".to_string(),
+        ("blockstream", Network::Testnet) => "https://blockstream.info/testnet/api".to_string(),
+        (url, _) => url.trim_end_matches('/').to_string(),
+    }
+}
+
+/// Seconds from a `Retry-After` header, if it holds a number
+fn retry_after(response: &reqwest::Response) -> Option<Duration> {
+    let secs: u64 = response.headers().get(reqwest::header::RETRY_AFTER)?.to_str().ok()?.trim().parse().ok()?;
+    Some(Duration::from_secs(secs.min(60)))
+}
+
+/// Blocks from an Esplora HTTP API
+pub struct EsploraSource {
+    config: EsploraConfig,
+    http: reqwest::Client,
+    limiter: RpcLimiter,
+    /// Height -> hash lookups already made this run
+    hashes: Mutex<HashMap<u64, String>>,
+}
+
+impl EsploraSource {
+    pub fn new(config: EsploraConfig) -> Result<Self> {
+        if let Some(dir) = &config.cache_dir {
+            std::fs::create_dir_all(dir).with_context(|| format!("Failed to create Esplora cache {}", dir.display()))?;
+        }
+        let limiter = RpcLimiter::new(RpcLimits {
+            max_in_flight: 2,
+            max_per_sec: Some(config.max_per_sec),
+        });
+        Ok(Self {
+            http: reqwest::Client::builder()
+                .timeout(Duration::from_secs(60))
+                .user_agent(concat!("blvm-bench/", env!("CARGO_PKG_VERSION")))
+                .build()?,
+            config,
+            limiter,
+            hashes: Mutex::new(HashMap::new()),
+        })
+    }
+
+    pub fn base_url(&self) -> &str {
+        &self.config.base_url
+    }
+
+    /// GET `path`, paced, retrying while the server says it's rate limited or unavailable
+    async fn get(&self, path: &str) -> Result<reqwest::Response> {
+        let url = format!("{}{}", self.config.base_url, path);
+        for attempt in 1..=MAX_ATTEMPTS {
+            let permit = self.limiter.acquire().await;
+            let response = match self.http.get(&url).send().await {
+                Ok(response) => response,
+                Err(e) => {
+                    permit.finish(false);
+                    return Err(e).with_context(|| format!("GET {} failed", url));
+                }
+            };
+            let status = response.status();
+            let pushed_back = status == reqwest::StatusCode::TOO_MANY_REQUESTS
+                || status == reqwest::StatusCode::SERVICE_UNAVAILABLE;
+            permit.finish(pushed_back);
+            if status.is_success() {
+                return Ok(response);
+            }
+            if !pushed_back || attempt == MAX_ATTEMPTS {
+                let body = response.text().await.unwrap_or_default();
+                anyhow::bail!("GET {} returned {}: {}", url, status, body.trim());
+            }
+            let delay = retry_after(&response).unwrap_or(Duration::from_secs(1 << attempt.min(5)));
+            tokio::time::sleep(delay).await;
+        }
+        unreachable!("the last attempt returns")
+    }
+
+    async fn get_text(&self, path: &str) -> Result<String> {
+        Ok(self.get(path).await?.text().await?.trim().to_string())
+    }
+
+    pub async fn get_block_hash(&self, height: u64) -> Result<String> {
+        if let Some(hash) = self.hashes.lock().expect("Esplora hash cache poisoned").get(&height) {
+            return Ok(hash.clone());
+        }
+        let hash = self.get_text(&format!("/block-height/{}", height)).await?;
+        if hash.len() != 64 || !hash.bytes().all(|b| b.is_ascii_hexdigit()) {
+            anyhow::bail!("Unexpected block hash for height {}: {:.100}", height, hash);
+        }
+        self.hashes.lock().expect("Esplora hash cache poisoned").insert(height, hash.clone());
+        Ok(hash)
+    }
+
+    /// Raw block by hash, from the disk cache when possible
+    pub async fn get_block_by_hash(&self, hash: &str) -> Result<Vec<u8>> {
+        let cached = self.config.cache_dir.as_ref().map(|dir| dir.join(format!("{}.blk", hash)));
+        if let Some(path) = &cached {
+            if let Ok(bytes) = std::fs::read(path) {
+                // A torn or corrupted file is simply fetched again
+                if block_hash_hex(&bytes).as_deref() == Some(hash) {
+                    return Ok(bytes);
+                }
+            }
+        }
+        let bytes = self.get(&format!("/block/{}/raw", hash)).await?.bytes().await?.to_vec();
+        if block_hash_hex(&bytes).as_deref() != Some(hash) {
+            anyhow::bail!("{} served a block that doesn't hash to {}", self.config.base_url, hash);
+        }
+        if let Some(path) = &cached {
+            let tmp = path.with_extension("tmp");
+            if let Err(e) = std::fs::write(&tmp, &bytes).and_then(|_| std::fs::rename(&tmp, path)) {
+                eprintln!("⚠️  Failed to cache block {} in {}: {}", hash, path.display(), e);
+            }
+        }
+        Ok(bytes)
+    }
+}
+
+#[async_trait]
+impl BlockSource for EsploraSource {
+    fn name(&self) -> &str {
+        "Esplora"
+    }
+
+    async fn get_block(&self, height: u64) -> Result<Vec<u8>> {
+        let hash = self.get_block_hash(height).await?;
+        self.get_block_by_hash(&hash).await
+    }
+
+    async fn get_tip_height(&self) -> Result<Option<u64>> {
+        let tip = self.get_text("/blocks/tip/height").await?;
+        Ok(Some(tip.parse().with_context(|| format!("Unexpected tip height: {:.100}", tip))?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resolve_url() {
+        assert_eq!(resolve_url("blockstream", Network::Mainnet), "https://blockstream.info/api");
+        assert_eq!(resolve_url("blockstream", Network::Testnet), "https://blockstream.info/testnet/api");
+        assert_eq!(resolve_url("http://electrs.local:3002/", Network::Regtest), "http://electrs.local:3002");
+        assert_eq!(resolve_url(" https://mempool.space/api ", Network::Mainnet), "https://mempool.space/api");
+    }
+}
@@ -0,0 +1,151 @@
+//! Fee and Subsidy Cross-Check
+//!
+//! Two engines can agree a block is valid while disagreeing on what it's
+//! worth: a coin with a drifted value in BLVM's UTXO set or an overflow in fee
+//! summation only becomes a validity divergence if a coinbase happens to claim
+//! the difference. With `BLVM_CHECK_FEES=1` every block both sides accept also
+//! has its total fees (from BLVM's pre-block coins) and subsidy compared with
+//! Core's `getblockstats` (`totalfee`, `subsidy`); a mismatch is recorded as a
+//! divergence. Core is reached via the usual RPC settings (`RpcConfig::from_env`)
+//! and needs block undo data, so pruned heights can't be checked.
+
+use anyhow::{Context, Result};
+use blvm_consensus::block::calculate_tx_id;
+use blvm_consensus::serialization::block::deserialize_block_with_witnesses;
+use blvm_consensus::{Block, OutPoint, UtxoSet};
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use crate::core_rpc_client::{CoreRpcClient, RpcConfig};
+
+/// Initial block subsidy in satoshis
+const INITIAL_SUBSIDY: i64 = 50 * 100_000_000;
+const HALVING_INTERVAL: u64 = 210_000;
+
+/// What a block pays out, in satoshis
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BlockValue {
+    pub total_fee: i64,
+    pub subsidy: i64,
+}
+
+impl std::fmt::Display for BlockValue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "fees={} subsidy={}", self.total_fee, self.subsidy)
+    }
+}
+
+/// Mainnet subsidy at `height`
+pub fn block_subsidy(height: u64) -> i64 {
+    let halvings = height / HALVING_INTERVAL;
+    if halvings >= 64 {
+        0
+    } else {
+        INITIAL_SUBSIDY >> halvings
+    }
+}
+
+/// Fees and subsidy of a block from the coins it spends
+///
+/// `pre_state` holds the coins the block read before it was connected; outputs
+/// spent later in the same block are tracked here. Sums are checked, so an
+/// overflow is an error rather than a wrapped total.
+pub fn block_value(block: &Block, pre_state: &UtxoSet, height: u64) -> Result<BlockValue> {
+    let mut created: HashMap<OutPoint, i64> = HashMap::new();
+    let mut total_fee: i64 = 0;
+    for (tx_index, tx) in block.transactions.iter().enumerate() {
+        if tx_index > 0 {
+            let mut value_in: i64 = 0;
+            for input in tx.inputs.iter() {
+                let value = match created.remove(&input.prevout) {
+                    Some(value) => value,
+                    None => pre_state
+                        .get(&input.prevout)
+                        .map(|utxo| utxo.value as i64)
+                        .with_context(|| format!("tx {} spends a coin missing from the pre-state", tx_index))?,
+                };
+                value_in = value_in.checked_add(value).context("input value overflow")?;
+            }
+            let value_out = tx
+                .outputs
+                .iter()
+                .try_fold(0i64, |sum, output| sum.checked_add(output.value as i64))
+                .context("output value overflow")?;
+            let fee = value_in.checked_sub(value_out).context("fee underflow")?;
+            total_fee = total_fee.checked_add(fee).context("fee total overflow")?;
+        }
+        let txid = calculate_tx_id(tx);
+        for (index, output) in tx.outputs.iter().enumerate() {
+            created.insert(OutPoint { hash: txid, index: index as _ }, output.value as i64);
+        }
+    }
+    Ok(BlockValue {
+        total_fee,
+        subsidy: block_subsidy(height),
+    })
+}
+
+/// Core node asked for `getblockstats`
+#[derive(Clone)]
+pub struct FeeCheck {
+    client: Arc<CoreRpcClient>,
+}
+
+impl std::fmt::Debug for FeeCheck {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("FeeCheck").field("url", &self.client.url()).finish()
+    }
+}
+
+impl FeeCheck {
+    /// Enabled by `BLVM_CHECK_FEES=1`
+    pub fn from_env() -> Option<Self> {
+        match std::env::var("BLVM_CHECK_FEES").as_deref() {
+            Ok("1") | Ok("true") => Some(Self {
+                client: Arc::new(CoreRpcClient::new(RpcConfig::from_env())),
+            }),
+            _ => None,
+        }
+    }
+
+    /// Core's fees and subsidy for the block at `height`
+    pub async fn core_value(&self, height: u64) -> Result<BlockValue> {
+        let stats = self.client.getblockstats(height, &["totalfee", "subsidy"]).await?;
+        let field = |key: &str| {
+            stats
+                .get(key)
+                .and_then(|v| v.as_i64())
+                .with_context(|| format!("getblockstats missing {}", key))
+        };
+        Ok(BlockValue {
+            total_fee: field("totalfee")?,
+            subsidy: field("subsidy")?,
+        })
+    }
+
+    /// (BLVM, Core) descriptions if the block's value differs between the two
+    pub async fn compare(&self, height: u64, block_bytes: &[u8], pre_state: &UtxoSet) -> Result<Option<(String, String)>> {
+        let (block, _) = deserialize_block_with_witnesses(block_bytes)
+            .map_err(|e| anyhow::anyhow!("Failed to deserialize block {}: {}", height, e))?;
+        let blvm = match block_value(&block, pre_state, height) {
+            Ok(value) => value.to_string(),
+            Err(e) => format!("error: {}", e),
+        };
+        let core = self.core_value(height).await?.to_string();
+        Ok((blvm != core).then(|| (format!("Valid({})", blvm), format!("Valid({})", core))))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_block_subsidy() {
+        assert_eq!(block_subsidy(0), 5_000_000_000);
+        assert_eq!(block_subsidy(209_999), 5_000_000_000);
+        assert_eq!(block_subsidy(210_000), 2_500_000_000);
+        assert_eq!(block_subsidy(840_000), 312_500_000);
+        assert_eq!(block_subsidy(64 * 210_000), 0);
+    }
+}
@@ -0,0 +1,325 @@
+//! BIP158 Block Filter Differential
+//!
+//! A compact block filter commits to every output script a block creates and
+//! every script its inputs spend. Computing one from BLVM's view of a block
+//! (its parsed outputs, and the prevouts its own UTXO state resolves) and
+//! comparing it with Core's `getblockfilter` catches divergences in script
+//! extraction and in filter construction (element hashing, Golomb-Rice coding,
+//! serialization) that leave every validation verdict unchanged.
+//!
+//! BLVM's state starts from the nearest genesis-based checkpoint below the
+//! first block (see `checkpoint_store`), or empty at genesis. Core must run
+//! with `-blockfilterindex=1`. Each block's filter header is chained from
+//! Core's header of the block before, so one bad filter doesn't make every
+//! later header mismatch.
+
+use anyhow::{Context, Result};
+use blvm_consensus::block::calculate_tx_id;
+use blvm_consensus::serialization::block::deserialize_block_with_witnesses;
+use blvm_consensus::transaction::is_coinbase;
+use blvm_consensus::types::{Network, ValidationResult};
+use blvm_consensus::{Block, OutPoint};
+use std::collections::{BTreeSet, HashMap};
+use std::hash::Hasher;
+use std::time::Instant;
+
+use crate::bench_fixtures::{sha256d, write_compact_size};
+use crate::block_hash::{display_hex, from_display_hex};
+use crate::checkpoint_store::{utxo_store_before, CheckpointStore};
+use crate::core_rpc_client::CoreRpcClient;
+use crate::parallel_differential::{get_block_data, BlockDataSource};
+use crate::utxo_backend::{UtxoBackend, UtxoStore};
+
+/// Golomb-Rice parameter of the basic filter
+pub const BASIC_FILTER_P: u8 = 19;
+/// False-positive rate inverse of the basic filter
+pub const BASIC_FILTER_M: u64 = 784_931;
+/// Stop collecting mismatch details after this many
+const MAX_REPORTED_MISMATCHES: usize = 100;
+
+/// Basic filter elements: non-empty, non-OP_RETURN output scripts and every non-empty spent script
+pub fn basic_filter_elements(block: &Block, prevout_scripts: &[Vec<u8>]) -> BTreeSet<Vec<u8>> {
+    let mut elements = BTreeSet::new();
+    for tx in block.transactions.iter() {
+        for output in tx.outputs.iter() {
+            let script: &[u8] = &output.script_pubkey;
+            if !script.is_empty() && script[0] != 0x6a {
+                elements.insert(script.to_vec());
+            }
+        }
+    }
+    elements.extend(prevout_scripts.iter().filter(|s| !s.is_empty()).cloned());
+    elements
+}
+
+/// Serialized filter: CompactSize(N) followed by the Golomb-Rice coded set
+///
+/// `block_hash` is in internal byte order; its first 16 bytes key SipHash.
+pub fn build_basic_filter(block_hash: &[u8; 32], elements: &BTreeSet<Vec<u8>>) -> Vec<u8> {
+    let k0 = u64::from_le_bytes(block_hash[0..8].try_into().expect("8 bytes"));
+    let k1 = u64::from_le_bytes(block_hash[8..16].try_into().expect("8 bytes"));
+    let range = elements.len() as u64 * BASIC_FILTER_M;
+    let mut values: Vec<u64> = elements
+        .iter()
+        .map(|element| {
+            let mut hasher = siphasher::sip::SipHasher24::new_with_keys(k0, k1);
+            hasher.write(element);
+            ((hasher.finish() as u128 * range as u128) >> 64) as u64
+        })
+        .collect();
+    values.sort_unstable();
+
+    let mut filter = Vec::new();
+    write_compact_size(&mut filter, elements.len() as u64);
+    let mut bits = BitWriter::default();
+    let mut last = 0;
+    for value in values {
+        let delta = value - last;
+        last = value;
+        for _ in 0..delta >> BASIC_FILTER_P {
+            bits.push(true);
+        }
+        bits.push(false);
+        bits.write(delta, BASIC_FILTER_P);
+    }
+    filter.extend_from_slice(&bits.bytes);
+    filter
+}
+
+/// Filter header: double-SHA256 of the filter's hash and the previous header (internal byte order)
+pub fn filter_header(filter: &[u8], prev_header: &[u8; 32]) -> [u8; 32] {
+    let filter_hash = sha256d(filter);
+    sha256d(&[&filter_hash[..], prev_header].concat())
+}
+
+/// Element count a serialized filter starts with
+pub fn filter_element_count(filter: &[u8]) -> Option<u64> {
+    Some(match *filter.first()? {
+        0xfd => u16::from_le_bytes(filter.get(1..3)?.try_into().ok()?) as u64,
+        0xfe => u32::from_le_bytes(filter.get(1..5)?.try_into().ok()?) as u64,
+        0xff => u64::from_le_bytes(filter.get(1..9)?.try_into().ok()?),
+        n => n as u64,
+    })
+}
+
+/// MSB-first bit stream
+#[derive(Default)]
+struct BitWriter {
+    bytes: Vec<u8>,
+    used: u8,
+}
+
+impl BitWriter {
+    fn push(&mut self, bit: bool) {
+        if self.used == 0 {
+            self.bytes.push(0);
+        }
+        if bit {
+            *self.bytes.last_mut().expect("byte pushed above") |= 0x80 >> self.used;
+        }
+        self.used = (self.used + 1) % 8;
+    }
+
+    /// Low `n` bits of `value`, most significant first
+    fn write(&mut self, value: u64, n: u8) {
+        for i in (0..n).rev() {
+            self.push((value >> i) & 1 == 1);
+        }
+    }
+}
+
+/// A block whose BLVM filter or header differs from Core's
+#[derive(Debug, Clone)]
+pub struct FilterMismatch {
+    pub height: u64,
+    pub block_hash: String,
+    /// `filter` or `header`
+    pub field: &'static str,
+    pub blvm: String,
+    pub core: String,
+}
+
+/// Result of a filter differential run
+#[derive(Debug, Default)]
+pub struct FilterReport {
+    pub blocks_checked: u64,
+    pub mismatch_count: u64,
+    /// First `MAX_REPORTED_MISMATCHES` mismatches, in height order
+    pub mismatches: Vec<FilterMismatch>,
+    pub elapsed_secs: f64,
+}
+
+/// Scripts the block's inputs spend, from BLVM's state and the block's own earlier outputs
+fn prevout_scripts(block: &Block, utxo_store: &dyn UtxoStore, height: u64) -> Result<Vec<Vec<u8>>> {
+    let mut created: HashMap<OutPoint, Vec<u8>> = HashMap::new();
+    let mut scripts = Vec::new();
+    for tx in block.transactions.iter() {
+        if !is_coinbase(tx) {
+            for input in tx.inputs.iter() {
+                let script = match created.get(&input.prevout) {
+                    Some(script) => script.clone(),
+                    None => {
+                        let coin = utxo_store.get(&input.prevout)?.with_context(|| {
+                            format!(
+                                "Block {} spends {}:{}, which BLVM's UTXO set doesn't have",
+                                height,
+                                display_hex(&input.prevout.hash),
+                                input.prevout.index
+                            )
+                        })?;
+                        let script: &[u8] = &coin.script_pubkey;
+                        script.to_vec()
+                    }
+                };
+                scripts.push(script);
+            }
+        }
+        let txid = calculate_tx_id(tx);
+        for (index, output) in tx.outputs.iter().enumerate() {
+            let script: &[u8] = &output.script_pubkey;
+            created.insert(
+                OutPoint {
+                    hash: txid,
+                    index: index as _,
+                },
+                script.to_vec(),
+            );
+        }
+    }
+    Ok(scripts)
+}
+
+/// Core's filter and header for a block (header in internal byte order)
+async fn core_filter(client: &CoreRpcClient, block_hash: &str) -> Result<(Vec<u8>, [u8; 32])> {
+    let result = client
+        .getblockfilter(block_hash)
+        .await
+        .context("getblockfilter failed (is Core running with -blockfilterindex=1?)")?;
+    let filter = hex::decode(result["filter"].as_str().context("getblockfilter returned no filter")?)?;
+    let header = from_display_hex(result["header"].as_str().context("getblockfilter returned no header")?)?;
+    Ok((filter, header))
+}
+
+/// Compare filters for blocks `start_height..=end_height` (end defaults to Core's tip)
+pub async fn run_filter_differential(
+    client: &CoreRpcClient,
+    block_source: &BlockDataSource,
+    checkpoints: &CheckpointStore,
+    utxo_backend: &UtxoBackend,
+    start_height: u64,
+    end_height: Option<u64>,
+) -> Result<FilterReport> {
+    let tip = client.getblockcount().await?;
+    let end_height = end_height.unwrap_or(tip).min(tip);
+    println!("🧮 BIP158 filter differential: blocks {}..={}", start_height, end_height);
+
+    let (mut utxo_store, mut prev_header) = if start_height == 0 {
+        (utxo_backend.create(Default::default())?, [0u8; 32])
+    } else {
+        let (utxo_store, _) = utxo_store_before(start_height, block_source, checkpoints, utxo_backend)
+            .await?
+            .with_context(|| {
+                format!(
+                    "No genesis-based checkpoint below height {}; generate checkpoints or start at 0",
+                    start_height
+                )
+            })?;
+        let prev_hash = client.getblockhash(start_height - 1).await?;
+        (utxo_store, core_filter(client, &prev_hash).await?.1)
+    };
+
+    let start = Instant::now();
+    let mut report = FilterReport::default();
+    for height in start_height..=end_height {
+        let block_bytes = get_block_data(block_source, height).await?;
+        let hash = crate::block_hash::block_hash(&block_bytes)
+            .with_context(|| format!("Block {} is shorter than a header", height))?;
+        let hash_hex = display_hex(&hash);
+        let (block, witnesses) = deserialize_block_with_witnesses(&block_bytes)
+            .map_err(|e| anyhow::anyhow!("Failed to deserialize block {}: {}", height, e))?;
+
+        let spent = prevout_scripts(&block, utxo_store.as_ref(), height)?;
+        if let ValidationResult::Invalid(msg) = utxo_store.connect_block(&block, &witnesses, height, Network::Mainnet)? {
+            anyhow::bail!("BLVM rejected block {}: {}", height, msg);
+        }
+        let filter = build_basic_filter(&hash, &basic_filter_elements(&block, &spent));
+        let header = filter_header(&filter, &prev_header);
+
+        let (core_filter, core_header) = core_filter(client, &hash_hex).await?;
+        let mut mismatches = Vec::new();
+        if filter != core_filter {
+            let count = |f: &[u8]| filter_element_count(f).map_or("?".to_string(), |n| n.to_string());
+            mismatches.push(FilterMismatch {
+                height,
+                block_hash: hash_hex.clone(),
+                field: "filter",
+                blvm: format!("{} elements, {}", count(&filter), hex::encode(&filter)),
+                core: format!("{} elements, {}", count(&core_filter), hex::encode(&core_filter)),
+            });
+        } else if header != core_header {
+            mismatches.push(FilterMismatch {
+                height,
+                block_hash: hash_hex.clone(),
+                field: "header",
+                blvm: display_hex(&header),
+                core: display_hex(&core_header),
+            });
+        }
+        for mismatch in mismatches {
+            report.mismatch_count += 1;
+            if report.mismatches.len() < MAX_REPORTED_MISMATCHES {
+                eprintln!(
+                    "❌ Block {} {}: BLVM={:.80}, Core={:.80}",
+                    height, mismatch.field, mismatch.blvm, mismatch.core
+                );
+                report.mismatches.push(mismatch);
+            }
+        }
+        prev_header = core_header;
+
+        report.blocks_checked += 1;
+        if report.blocks_checked % 10_000 == 0 {
+            println!(
+                "   {} blocks checked ({:.0}/s)",
+                report.blocks_checked,
+                report.blocks_checked as f64 / start.elapsed().as_secs_f64()
+            );
+        }
+    }
+
+    report.elapsed_secs = start.elapsed().as_secs_f64();
+    println!(
+        "{} {} blocks checked in {:.1}s, {} filter mismatches",
+        if report.mismatch_count == 0 { "✅" } else { "❌" },
+        report.blocks_checked,
+        report.elapsed_secs,
+        report.mismatch_count
+    );
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_genesis_filter_matches_bip158_vector() {
+        // BIP158 test vector for (testnet3) block 0: one element, the coinbase's P2PK script
+        let genesis_hash = from_display_hex("000000000933ea01ad0ee984209779baaec3ced90fa3f408719526f8d77f4943").unwrap();
+        let script = hex::decode(concat!(
+            "4104678afdb0fe5548271967f1a67130b7105cd6a828e03909a67962e0ea1f61deb6",
+            "49f6bc3f4cef38c4f35504e51ec112de5c384df7ba0b8d578a4c702b6bf11d5fac"
+        ))
+        .unwrap();
+        let filter = build_basic_filter(&genesis_hash, &BTreeSet::from([script]));
+        assert_eq!(hex::encode(&filter), "019dfca8");
+        assert_eq!(filter_element_count(&filter), Some(1));
+        assert_eq!(
+            display_hex(&filter_header(&filter, &[0; 32])),
+            "21584579b7eb08997773e5aeff3a7f932700042d0ed2a6129012b7d7ae81b750"
+        );
+
+        // An empty set is just its count
+        assert_eq!(build_basic_filter(&genesis_hash, &BTreeSet::new()), vec![0x00]);
+    }
+}
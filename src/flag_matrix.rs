@@ -0,0 +1,256 @@
+//! Script Flag Matrix
+//!
+//! Block-level runs verify every script under the one flag set its height
+//! calls for, so a rule that only misbehaves in combination with another
+//! (say NULLDUMMY without WITNESS) is never exercised. This re-verifies the
+//! transactions of the historical block fixtures under every combination of
+//! the consensus flags libbitcoinconsensus accepts and compares BLVM's verdict
+//! with libbitcoinconsensus (Core's interpreter) under the same flags.
+//!
+//! The result is a matrix of 128 flag combinations with agreement counts;
+//! any cell where the engines disagree is a flag-dependence bug on one side.
+
+use anyhow::Result;
+use blvm_consensus::block::calculate_tx_id;
+use blvm_consensus::{OutPoint, TransactionOutput, UtxoSet};
+use serde::Serialize;
+use std::collections::HashMap;
+
+use crate::test_vectors::{parse_flags, parse_transaction, verify_transaction};
+
+/// Consensus flags libbitcoinconsensus accepts (any other bit is rejected as invalid flags)
+const MATRIX_FLAGS: [&str; 7] = [
+    "P2SH",
+    "DERSIG",
+    "NULLDUMMY",
+    "CHECKLOCKTIMEVERIFY",
+    "CHECKSEQUENCEVERIFY",
+    "WITNESS",
+    "TAPROOT",
+];
+/// Transactions with more inputs are skipped (128 passes of a quadratic-sighash monster take hours)
+const MAX_INPUTS: usize = 500;
+/// Example txids kept per disagreeing cell
+const MAX_EXAMPLES: usize = 5;
+
+/// Flags of combination `mask` (bit i selects `MATRIX_FLAGS[i]`)
+fn combo_flags(mask: usize) -> u32 {
+    MATRIX_FLAGS
+        .iter()
+        .enumerate()
+        .filter(|(i, _)| mask & (1 << i) != 0)
+        .map(|(_, name)| parse_flags(name).expect("known flag"))
+        .fold(0, |acc, bit| acc | bit)
+}
+
+fn combo_name(mask: usize) -> String {
+    let names: Vec<&str> = MATRIX_FLAGS
+        .iter()
+        .enumerate()
+        .filter(|(i, _)| mask & (1 << i) != 0)
+        .map(|(_, name)| *name)
+        .collect();
+    if names.is_empty() {
+        "NONE".to_string()
+    } else {
+        names.join(",")
+    }
+}
+
+/// Agreement counts for one flag combination
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct MatrixCell {
+    pub flags: String,
+    pub flag_bits: u32,
+    pub both_valid: usize,
+    pub both_invalid: usize,
+    /// BLVM accepts, libbitcoinconsensus rejects (BLVM too lenient)
+    pub blvm_only_valid: usize,
+    /// libbitcoinconsensus accepts, BLVM rejects (BLVM too strict)
+    pub core_only_valid: usize,
+    /// First few disagreeing transactions: "<fixture>:<txid>: <BLVM reason | Core reason>"
+    pub examples: Vec<String>,
+}
+
+impl MatrixCell {
+    pub fn disagreements(&self) -> usize {
+        self.blvm_only_valid + self.core_only_valid
+    }
+}
+
+/// The full matrix over a corpus
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct FlagMatrix {
+    pub txs_checked: usize,
+    /// Transactions skipped (unknown prevouts or more than `MAX_INPUTS` inputs)
+    pub txs_skipped: usize,
+    pub cells: Vec<MatrixCell>,
+}
+
+impl FlagMatrix {
+    pub fn new() -> Self {
+        Self {
+            cells: (0..1 << MATRIX_FLAGS.len())
+                .map(|mask| MatrixCell {
+                    flags: combo_name(mask),
+                    flag_bits: combo_flags(mask),
+                    ..Default::default()
+                })
+                .collect(),
+            ..Default::default()
+        }
+    }
+
+    pub fn disagreements(&self) -> usize {
+        self.cells.iter().map(MatrixCell::disagreements).sum()
+    }
+
+    /// Add every transaction of a block, resolving prevouts from `pre_state` and the block itself
+    pub fn add_block(&mut self, name: &str, block_bytes: &[u8], pre_state: &UtxoSet, max_txs: usize) -> Result<()> {
+        let mut created: HashMap<OutPoint, TransactionOutput> = HashMap::new();
+        let txs = crate::mutation_differential::block_transactions(block_bytes)?;
+        for (index, tx_bytes) in txs.iter().enumerate() {
+            let (tx, witnesses) = parse_transaction(tx_bytes)?;
+            let txid = calculate_tx_id(&tx);
+            if index > 0 && (max_txs == 0 || self.txs_checked < max_txs) {
+                let prevouts: Option<Vec<TransactionOutput>> = tx
+                    .inputs
+                    .iter()
+                    .map(|input| {
+                        created.get(&input.prevout).cloned().or_else(|| {
+                            pre_state.get(&input.prevout).map(|utxo| TransactionOutput {
+                                value: utxo.value as _,
+                                script_pubkey: utxo.script_pubkey.to_vec(),
+                            })
+                        })
+                    })
+                    .collect();
+                match prevouts {
+                    Some(prevouts) if tx.inputs.len() <= MAX_INPUTS => {
+                        let label = format!("{}:{}", name, display_txid(txid));
+                        self.add_transaction(&label, tx_bytes, &tx, &witnesses, &prevouts);
+                    }
+                    _ => self.txs_skipped += 1,
+                }
+            }
+            for (vout, output) in tx.outputs.iter().enumerate() {
+                created.insert(OutPoint { hash: txid, index: vout as _ }, output.clone());
+            }
+        }
+        Ok(())
+    }
+
+    fn add_transaction(
+        &mut self,
+        label: &str,
+        tx_bytes: &[u8],
+        tx: &blvm_consensus::Transaction,
+        witnesses: &[blvm_consensus::segwit::Witness],
+        prevouts: &[TransactionOutput],
+    ) {
+        self.txs_checked += 1;
+        for cell in &mut self.cells {
+            let blvm = verify_transaction(tx, witnesses, prevouts, cell.flag_bits);
+            let core = libconsensus_verify(tx_bytes, prevouts, cell.flag_bits);
+            match (&blvm, &core) {
+                (Ok(()), Ok(())) => cell.both_valid += 1,
+                (Err(_), Err(_)) => cell.both_invalid += 1,
+                (Ok(()), Err(_)) => cell.blvm_only_valid += 1,
+                (Err(_), Ok(())) => cell.core_only_valid += 1,
+            }
+            if blvm.is_ok() != core.is_ok() && cell.examples.len() < MAX_EXAMPLES {
+                let reason = |r: &std::result::Result<(), String>| r.clone().err().unwrap_or_else(|| "valid".to_string());
+                cell.examples.push(format!("{}: BLVM {} | Core {}", label, reason(&blvm), reason(&core)));
+            }
+        }
+    }
+
+    pub fn print(&self) {
+        println!(
+            "🧮 Flag matrix: {} transactions x {} combinations ({} skipped)",
+            self.txs_checked,
+            self.cells.len(),
+            self.txs_skipped
+        );
+        let agreeing = self.cells.iter().filter(|c| c.disagreements() == 0).count();
+        println!("   {}/{} combinations agree on every transaction", agreeing, self.cells.len());
+        for cell in self.cells.iter().filter(|c| c.disagreements() > 0) {
+            println!(
+                "❌ {:<60} agree {} valid + {} invalid, BLVM-only valid {}, Core-only valid {}",
+                cell.flags, cell.both_valid, cell.both_invalid, cell.blvm_only_valid, cell.core_only_valid
+            );
+            for example in &cell.examples {
+                println!("      {}", example);
+            }
+        }
+    }
+}
+
+/// libbitcoinconsensus verdict on every input of a transaction
+fn libconsensus_verify(tx_bytes: &[u8], prevouts: &[TransactionOutput], flags: u32) -> std::result::Result<(), String> {
+    let spent: Vec<bitcoinconsensus::Utxo> = prevouts
+        .iter()
+        .map(|prevout| bitcoinconsensus::Utxo {
+            script_pubkey: prevout.script_pubkey.as_ptr(),
+            script_pubkey_len: prevout.script_pubkey.len() as _,
+            value: prevout.value as _,
+        })
+        .collect();
+    for (index, prevout) in prevouts.iter().enumerate() {
+        bitcoinconsensus::verify_with_flags(
+            &prevout.script_pubkey,
+            prevout.value as u64,
+            tx_bytes,
+            Some(&spent),
+            index,
+            flags,
+        )
+        .map_err(|e| format!("input {}: {:?}", index, e))?;
+    }
+    Ok(())
+}
+
+fn display_txid(mut txid: [u8; 32]) -> String {
+    txid.reverse();
+    hex::encode(txid)
+}
+
+/// Run the matrix over every block fixture found in `dir` (`max_txs` 0 = all)
+pub fn run_flag_matrix(dir: &std::path::Path, max_txs: usize) -> Result<FlagMatrix> {
+    use crate::block_fixtures::{load_fixture, NOTABLE_BLOCKS};
+
+    let mut matrix = FlagMatrix::new();
+    for (name, height) in NOTABLE_BLOCKS {
+        match load_fixture(dir, name, *height)? {
+            Some(fixture) => {
+                println!("🧮 Fixture {} (height {})", name, height);
+                matrix.add_block(name, &fixture.block_bytes, &fixture.pre_state, max_txs)?;
+            }
+            None => eprintln!("⚠️  Skipping {}: no fixture in {}", name, dir.display()),
+        }
+    }
+    if matrix.txs_checked == 0 {
+        anyhow::bail!("No transactions to check (build fixtures with `blvm-bench build-fixtures`)");
+    }
+    matrix.print();
+    Ok(matrix)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_combinations() {
+        assert_eq!(combo_flags(0), 0);
+        assert_eq!(combo_name(0), "NONE");
+        // P2SH | WITNESS
+        let mask = 1 | (1 << 5);
+        assert_eq!(combo_flags(mask), (1 << 0) | (1 << 11));
+        assert_eq!(combo_name(mask), "P2SH,WITNESS");
+        let matrix = FlagMatrix::new();
+        assert_eq!(matrix.cells.len(), 128);
+        let all = parse_flags("P2SH,DERSIG,NULLDUMMY,CHECKLOCKTIMEVERIFY,CHECKSEQUENCEVERIFY,WITNESS,TAPROOT").unwrap();
+        assert_eq!(matrix.cells[127].flag_bits, all);
+    }
+}
@@ -0,0 +1,173 @@
+//! Per-Block Flamegraphs
+//!
+//! The slowest-blocks leaderboard says which blocks are pathological, not why.
+//! With `BLVM_FLAMEGRAPH_MS` set, any block whose `connect_block` takes at
+//! least that long is connected again against the coins it spent, under
+//! `pprof` sampling, and a flamegraph SVG is written per block
+//! (`block_<height>.svg`) - showing which script ops or hash paths dominate.
+//!
+//! The re-run happens on a dedicated thread and only that thread's samples
+//! are kept, so other workers don't pollute the graph. Short blocks are
+//! connected repeatedly until `MIN_PROFILE_TIME` of samples are collected.
+//! Profiling needs the `flamegraph` feature.
+//!
+//! Graphs go to `BLVM_FLAMEGRAPH_DIR`, else `flamegraphs/` next to
+//! `BLVM_HTML_REPORT`, else `./flamegraphs`; at most `BLVM_FLAMEGRAPH_MAX`
+//! per run (default 20).
+
+use anyhow::{Context, Result};
+use blvm_consensus::UtxoSet;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Flamegraphs written per run by default
+pub const DEFAULT_MAX_FLAMEGRAPHS: usize = 20;
+
+/// Sampling frequency (Hz); not a multiple of common timer ticks
+#[cfg(feature = "flamegraph")]
+const SAMPLE_FREQUENCY: i32 = 997;
+
+/// Minimum time spent re-connecting a block under the profiler
+#[cfg(feature = "flamegraph")]
+const MIN_PROFILE_TIME: Duration = Duration::from_secs(2);
+
+/// Name of the profiling thread (fits Linux's 15-character limit)
+#[cfg(feature = "flamegraph")]
+const PROFILE_THREAD: &str = "blvm-flamegraph";
+
+/// Default output directory (see module docs)
+pub fn default_flamegraph_dir() -> PathBuf {
+    if let Ok(dir) = std::env::var("BLVM_FLAMEGRAPH_DIR") {
+        return PathBuf::from(dir);
+    }
+    std::env::var("BLVM_HTML_REPORT")
+        .ok()
+        .and_then(|report| PathBuf::from(report).parent().map(|dir| dir.join("flamegraphs")))
+        .unwrap_or_else(|| PathBuf::from("flamegraphs"))
+}
+
+/// Flamegraph settings, shared by every chunk of a run
+#[derive(Debug, Clone)]
+pub struct FlamegraphConfig {
+    /// Profile blocks whose connect time reaches this
+    pub threshold: Duration,
+    pub out_dir: PathBuf,
+    pub max_graphs: usize,
+    /// Graphs claimed so far across all chunks
+    claimed: Arc<AtomicUsize>,
+}
+
+impl FlamegraphConfig {
+    pub fn new(threshold: Duration, out_dir: PathBuf, max_graphs: usize) -> Self {
+        Self {
+            threshold,
+            out_dir,
+            max_graphs,
+            claimed: Arc::new(AtomicUsize::new(0)),
+        }
+    }
+
+    /// From `BLVM_FLAMEGRAPH_MS` (None = disabled), `BLVM_FLAMEGRAPH_DIR` and `BLVM_FLAMEGRAPH_MAX`
+    pub fn from_env() -> Result<Option<Self>> {
+        let Ok(ms) = std::env::var("BLVM_FLAMEGRAPH_MS") else {
+            return Ok(None);
+        };
+        if !cfg!(feature = "flamegraph") {
+            anyhow::bail!("BLVM_FLAMEGRAPH_MS is set but blvm-bench was built without the `flamegraph` feature");
+        }
+        let threshold = Duration::from_millis(ms.parse().with_context(|| format!("Invalid BLVM_FLAMEGRAPH_MS '{}'", ms))?);
+        let max_graphs = match std::env::var("BLVM_FLAMEGRAPH_MAX") {
+            Ok(max) => max.parse().with_context(|| format!("Invalid BLVM_FLAMEGRAPH_MAX '{}'", max))?,
+            Err(_) => DEFAULT_MAX_FLAMEGRAPHS,
+        };
+        Ok(Some(Self::new(threshold, default_flamegraph_dir(), max_graphs)))
+    }
+
+    /// Whether a block that took `connect_secs` should be profiled (claims one of the run's graphs)
+    pub fn should_profile(&self, connect_secs: f64) -> bool {
+        connect_secs >= self.threshold.as_secs_f64()
+            && self
+                .claimed
+                .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |n| (n < self.max_graphs).then_some(n + 1))
+                .is_ok()
+    }
+
+    /// Re-connect the block under the profiler and write `block_<height>.svg`
+    #[cfg(feature = "flamegraph")]
+    pub fn profile(&self, height: u64, block_bytes: &[u8], pre_state: &UtxoSet) -> Result<PathBuf> {
+        use blvm_consensus::block::connect_block;
+        use blvm_consensus::serialization::block::deserialize_block_with_witnesses;
+        use blvm_consensus::types::Network;
+
+        // pprof allows one profiler per process; chunks take turns
+        static PROFILER: std::sync::Mutex<()> = std::sync::Mutex::new(());
+        let _turn = PROFILER.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+
+        let (block, witnesses) = deserialize_block_with_witnesses(block_bytes)
+            .map_err(|e| anyhow::anyhow!("Failed to deserialize block {}: {}", height, e))?;
+        let pre_state = pre_state.clone();
+        let guard = pprof::ProfilerGuardBuilder::default()
+            .frequency(SAMPLE_FREQUENCY)
+            .blocklist(&["libc", "libgcc", "pthread", "vdso"])
+            .build()
+            .context("Failed to start the profiler")?;
+
+        let runs = std::thread::Builder::new()
+            .name(PROFILE_THREAD.to_string())
+            .spawn(move || {
+                let started = std::time::Instant::now();
+                let mut runs = 0u32;
+                while runs == 0 || started.elapsed() < MIN_PROFILE_TIME {
+                    let _ = std::hint::black_box(connect_block(
+                        &block,
+                        &witnesses,
+                        pre_state.clone(),
+                        height,
+                        None,
+                        Network::Mainnet,
+                    ));
+                    runs += 1;
+                }
+                runs
+            })?
+            .join()
+            .map_err(|_| anyhow::anyhow!("connect_block panicked while profiling block {}", height))?;
+
+        let mut report = guard.report().build().context("Failed to build the profile")?;
+        report.data.retain(|frames, _| frames.thread_name == PROFILE_THREAD);
+
+        std::fs::create_dir_all(&self.out_dir)
+            .with_context(|| format!("Failed to create flamegraph directory {}", self.out_dir.display()))?;
+        let path = self.out_dir.join(format!("block_{}.svg", height));
+        let file = std::fs::File::create(&path).with_context(|| format!("Failed to create {}", path.display()))?;
+        let mut options = pprof::flamegraph::Options::default();
+        options.title = format!("Block {} - connect_block x{}", height, runs);
+        report
+            .flamegraph_with_options(file, &mut options)
+            .with_context(|| format!("Failed to write {}", path.display()))?;
+        Ok(path)
+    }
+
+    #[cfg(not(feature = "flamegraph"))]
+    pub fn profile(&self, _height: u64, _block_bytes: &[u8], _pre_state: &UtxoSet) -> Result<PathBuf> {
+        anyhow::bail!("blvm-bench was built without the `flamegraph` feature")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_graphs_are_capped_across_clones() {
+        let config = FlamegraphConfig::new(Duration::from_millis(500), PathBuf::from("unused"), 2);
+        let other_chunk = config.clone();
+        assert!(!config.should_profile(0.1));
+        assert!(config.should_profile(0.5));
+        assert!(other_chunk.should_profile(3.0));
+        assert!(!config.should_profile(3.0));
+        assert!(!other_chunk.should_profile(3.0));
+    }
+}
@@ -0,0 +1,103 @@
+//! GitHub Actions Annotations
+//!
+//! Prints `::error` / `::warning` workflow commands so divergences and
+//! regressions show up inline on the job summary and PR checks instead of
+//! being buried in the log. On when `BLVM_GITHUB_ANNOTATIONS=1` (or the
+//! global `--github-annotations` flag); when unset, on inside GitHub Actions
+//! (`GITHUB_ACTIONS=true`). `BLVM_GITHUB_ANNOTATIONS=0` turns it off there.
+
+/// Annotation severity
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Level {
+    Error,
+    Warning,
+    Notice,
+}
+
+impl Level {
+    fn command(&self) -> &'static str {
+        match self {
+            Level::Error => "error",
+            Level::Warning => "warning",
+            Level::Notice => "notice",
+        }
+    }
+}
+
+/// One workflow command
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Annotation {
+    pub level: Level,
+    pub title: String,
+    pub message: String,
+    /// File the annotation points at (e.g. a reproducer's meta.json)
+    pub file: Option<String>,
+}
+
+impl Annotation {
+    pub fn new(level: Level, title: impl Into<String>, message: impl Into<String>) -> Self {
+        Self {
+            level,
+            title: title.into(),
+            message: message.into(),
+            file: None,
+        }
+    }
+
+    pub fn with_file(mut self, file: impl Into<String>) -> Self {
+        self.file = Some(file.into());
+        self
+    }
+
+    /// The `::level file=...,title=...::message` line
+    pub fn command(&self) -> String {
+        let mut properties = Vec::new();
+        if let Some(file) = &self.file {
+            properties.push(format!("file={}", escape_property(file)));
+        }
+        properties.push(format!("title={}", escape_property(&self.title)));
+        format!("::{} {}::{}", self.level.command(), properties.join(","), escape_data(&self.message))
+    }
+}
+
+/// Whether annotations should be printed (see module docs)
+pub fn enabled() -> bool {
+    match std::env::var("BLVM_GITHUB_ANNOTATIONS") {
+        Ok(value) => matches!(value.as_str(), "1" | "true" | "yes"),
+        Err(_) => std::env::var("GITHUB_ACTIONS").is_ok_and(|value| value == "true"),
+    }
+}
+
+/// Print an annotation if annotations are on
+pub fn emit(annotation: &Annotation) {
+    if enabled() {
+        println!("{}", annotation.command());
+    }
+}
+
+fn escape_data(text: &str) -> String {
+    text.replace('%', "%25").replace('\r', "%0D").replace('\n', "%0A")
+}
+
+fn escape_property(text: &str) -> String {
+    escape_data(text).replace(':', "%3A").replace(',', "%2C")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_command_escaping() {
+        let annotation = Annotation::new(Level::Error, "Divergence at height 150", "BLVM=Invalid(a: b)\nCore=Valid 100%")
+            .with_file("reproducers/height_150/meta.json");
+        assert_eq!(
+            annotation.command(),
+            "::error file=reproducers/height_150/meta.json,title=Divergence at height 150::BLVM=Invalid(a: b)%0ACore=Valid 100%25"
+        );
+        assert_eq!(
+            Annotation::new(Level::Warning, "a, b: c", "x").command(),
+            "::warning title=a%2C b%3A c::x"
+        );
+    }
+}
@@ -0,0 +1,338 @@
+//! Header Chain Differential Validation
+//!
+//! Walks the mainnet header chain via Core's `getblockheader` and checks every
+//! header independently: hash, proof of work, difficulty retargeting, median
+//! time past and the BIP34/66/65 minimum versions. No block bodies or UTXOs are
+//! involved, so the full chain runs in minutes - a fast smoke test before
+//! committing to a block-level run.
+
+use anyhow::{Context, Result};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use std::collections::VecDeque;
+use std::time::Instant;
+
+use crate::core_rpc_client::CoreRpcClient;
+
+/// Mainnet difficulty adjustment interval
+const RETARGET_INTERVAL: u64 = 2016;
+/// Two weeks, in seconds
+const TARGET_TIMESPAN: u32 = 14 * 24 * 60 * 60;
+/// Mainnet proof-of-work limit
+const POW_LIMIT_BITS: u32 = 0x1d00ffff;
+/// Number of previous timestamps in the median time past
+const MTP_WINDOW: usize = 11;
+/// (activation height, minimum version): BIP34, BIP66, BIP65
+const MIN_VERSIONS: [(u64, i32); 3] = [(227_931, 2), (363_725, 3), (388_381, 4)];
+/// Stop collecting failure details after this many
+const MAX_REPORTED_FAILURES: usize = 100;
+
+/// 256-bit unsigned integer, little-endian bytes (same order as a block hash)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct U256([u8; 32]);
+
+impl U256 {
+    fn from_compact(bits: u32) -> Self {
+        let size = (bits >> 24) as usize;
+        let mut word = bits & 0x007f_ffff;
+        let mut bytes = [0u8; 32];
+        if size <= 3 {
+            word >>= 8 * (3 - size);
+            bytes[..4].copy_from_slice(&word.to_le_bytes());
+        } else {
+            for (i, b) in word.to_le_bytes()[..3].iter().enumerate() {
+                if let Some(slot) = bytes.get_mut(size - 3 + i) {
+                    *slot = *b;
+                }
+            }
+        }
+        U256(bytes)
+    }
+
+    fn to_compact(self) -> u32 {
+        let size = self.0.iter().rposition(|b| *b != 0).map(|i| i + 1).unwrap_or(0);
+        let mut compact = if size <= 3 {
+            let mut low = [0u8; 4];
+            low[..3].copy_from_slice(&self.0[..3]);
+            u32::from_le_bytes(low) << (8 * (3 - size))
+        } else {
+            u32::from_le_bytes([self.0[size - 3], self.0[size - 2], self.0[size - 1], 0])
+        };
+        let mut size = size as u32;
+        // The mantissa is signed; push the high bit into the exponent
+        if compact & 0x0080_0000 != 0 {
+            compact >>= 8;
+            size += 1;
+        }
+        compact | (size << 24)
+    }
+
+    fn mul_u32(self, m: u32) -> Self {
+        let mut out = [0u8; 32];
+        let mut carry = 0u64;
+        for (o, b) in out.iter_mut().zip(self.0.iter()) {
+            let v = *b as u64 * m as u64 + carry;
+            *o = v as u8;
+            carry = v >> 8;
+        }
+        U256(out)
+    }
+
+    fn div_u32(self, d: u32) -> Self {
+        let mut out = [0u8; 32];
+        let mut rem = 0u64;
+        for i in (0..32).rev() {
+            rem = (rem << 8) | self.0[i] as u64;
+            out[i] = (rem / d as u64) as u8;
+            rem %= d as u64;
+        }
+        U256(out)
+    }
+}
+
+impl PartialOrd for U256 {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for U256 {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.0.iter().rev().cmp(other.0.iter().rev())
+    }
+}
+
+/// Expected `bits` for the first block of a new period
+fn retarget(prev_bits: u32, period_start_time: u32, prev_time: u32) -> u32 {
+    let actual = prev_time
+        .saturating_sub(period_start_time)
+        .clamp(TARGET_TIMESPAN / 4, TARGET_TIMESPAN * 4);
+    let target = U256::from_compact(prev_bits).mul_u32(actual).div_u32(TARGET_TIMESPAN);
+    let limit = U256::from_compact(POW_LIMIT_BITS);
+    if target > limit {
+        limit.to_compact()
+    } else {
+        target.to_compact()
+    }
+}
+
+fn median(times: impl Iterator<Item = u32>) -> u32 {
+    let mut sorted: Vec<u32> = times.collect();
+    sorted.sort_unstable();
+    sorted[sorted.len() / 2]
+}
+
+/// Header fields as reported by Core
+#[derive(Debug, Clone)]
+struct CoreHeader {
+    hash: String,
+    version: i32,
+    prev_hash: Option<String>,
+    merkle_root: String,
+    time: u32,
+    bits: u32,
+    nonce: u32,
+    median_time: u32,
+    next_hash: Option<String>,
+}
+
+impl CoreHeader {
+    fn parse(v: &Value) -> Result<Self> {
+        let str_field = |k: &str| v.get(k).and_then(|x| x.as_str()).map(String::from);
+        let u64_field = |k: &str| v.get(k).and_then(|x| x.as_u64()).with_context(|| format!("header missing {}", k));
+        Ok(Self {
+            hash: str_field("hash").context("header missing hash")?,
+            version: v.get("version").and_then(|x| x.as_i64()).context("header missing version")? as i32,
+            prev_hash: str_field("previousblockhash"),
+            merkle_root: str_field("merkleroot").context("header missing merkleroot")?,
+            time: u64_field("time")? as u32,
+            bits: u32::from_str_radix(&str_field("bits").context("header missing bits")?, 16)?,
+            nonce: u64_field("nonce")? as u32,
+            median_time: u64_field("mediantime")? as u32,
+            next_hash: str_field("nextblockhash"),
+        })
+    }
+
+    /// Re-serialize the 80-byte header and hash it (internal byte order)
+    fn compute_hash(&self) -> Result<[u8; 32]> {
+        let internal = |display: &str| -> Result<Vec<u8>> {
+            let mut bytes = hex::decode(display)?;
+            bytes.reverse();
+            Ok(bytes)
+        };
+        let mut raw = Vec::with_capacity(80);
+        raw.extend_from_slice(&self.version.to_le_bytes());
+        match &self.prev_hash {
+            Some(prev) => raw.extend_from_slice(&internal(prev)?),
+            None => raw.extend_from_slice(&[0u8; 32]),
+        }
+        raw.extend_from_slice(&internal(&self.merkle_root)?);
+        raw.extend_from_slice(&self.time.to_le_bytes());
+        raw.extend_from_slice(&self.bits.to_le_bytes());
+        raw.extend_from_slice(&self.nonce.to_le_bytes());
+        Ok(Sha256::digest(Sha256::digest(&raw)).into())
+    }
+}
+
+/// A header that failed a rule
+#[derive(Debug, Clone)]
+pub struct HeaderFailure {
+    pub height: u64,
+    pub rule: &'static str,
+    pub detail: String,
+}
+
+/// Result of a header chain run
+#[derive(Debug, Default)]
+pub struct HeaderDiffReport {
+    pub headers_checked: u64,
+    pub failure_count: u64,
+    /// First `MAX_REPORTED_FAILURES` failures, in height order
+    pub failures: Vec<HeaderFailure>,
+    pub elapsed_secs: f64,
+}
+
+fn fail(report: &mut HeaderDiffReport, height: u64, rule: &'static str, detail: String) {
+    report.failure_count += 1;
+    if report.failures.len() < MAX_REPORTED_FAILURES {
+        eprintln!("❌ Header {} failed {}: {}", height, rule, detail);
+        report.failures.push(HeaderFailure { height, rule, detail });
+    }
+}
+
+/// Validate headers from genesis to `end_height` (Core's tip if None)
+pub async fn run_header_differential(client: &CoreRpcClient, end_height: Option<u64>) -> Result<HeaderDiffReport> {
+    let tip = client.getblockcount().await?;
+    let end_height = end_height.unwrap_or(tip).min(tip);
+    println!("🧱 Header differential: validating headers 0..={}", end_height);
+
+    let start = Instant::now();
+    let mut report = HeaderDiffReport::default();
+    let mut recent_times: VecDeque<u32> = VecDeque::with_capacity(MTP_WINDOW);
+    let mut period_start_time = 0u32;
+    let mut prev: Option<CoreHeader> = None;
+    let mut next_hash = Some(client.getblockhash(0).await?);
+
+    for height in 0..=end_height {
+        let hash = next_hash.take().context("Core header chain ended early")?;
+        let header = CoreHeader::parse(&client.getblockheader(&hash).await?)?;
+
+        // Hash: our serialization must reproduce Core's block hash
+        let mut computed = header.compute_hash()?;
+        let pow_value = U256(computed);
+        computed.reverse();
+        if hex::encode(computed) != header.hash {
+            fail(&mut report, height, "hash", format!("computed {} vs Core {}", hex::encode(computed), header.hash));
+        }
+
+        // Proof of work against the claimed target
+        let target = U256::from_compact(header.bits);
+        if pow_value > target || target > U256::from_compact(POW_LIMIT_BITS) {
+            fail(&mut report, height, "pow", format!("hash above target for bits {:08x}", header.bits));
+        }
+
+        if let Some(prev) = &prev {
+            // Difficulty: retarget at period boundaries, otherwise unchanged
+            let expected_bits = if height % RETARGET_INTERVAL == 0 {
+                retarget(prev.bits, period_start_time, prev.time)
+            } else {
+                prev.bits
+            };
+            if header.bits != expected_bits {
+                fail(
+                    &mut report,
+                    height,
+                    "difficulty",
+                    format!("bits {:08x}, expected {:08x}", header.bits, expected_bits),
+                );
+            }
+
+            // Timestamp must exceed the median of the previous 11
+            let mtp = median(recent_times.iter().copied());
+            if header.time <= mtp {
+                fail(&mut report, height, "timestamp", format!("time {} <= median time past {}", header.time, mtp));
+            }
+        }
+
+        // Core's mediantime includes this header
+        if recent_times.len() == MTP_WINDOW {
+            recent_times.pop_front();
+        }
+        recent_times.push_back(header.time);
+        let own_mtp = median(recent_times.iter().copied());
+        if own_mtp != header.median_time {
+            fail(
+                &mut report,
+                height,
+                "mediantime",
+                format!("computed {} vs Core {}", own_mtp, header.median_time),
+            );
+        }
+
+        // Version: BIP34/66/65 minimums once activated
+        if let Some((_, min_version)) = MIN_VERSIONS.iter().rev().find(|(h, _)| height >= *h) {
+            if header.version < *min_version {
+                fail(
+                    &mut report,
+                    height,
+                    "version",
+                    format!("version {} below minimum {}", header.version, min_version),
+                );
+            }
+        }
+
+        if height % RETARGET_INTERVAL == 0 {
+            period_start_time = header.time;
+        }
+        report.headers_checked += 1;
+        if height > 0 && height % 50_000 == 0 {
+            println!(
+                "   {} headers checked ({:.0}/s)",
+                height,
+                height as f64 / start.elapsed().as_secs_f64()
+            );
+        }
+
+        next_hash = header.next_hash.clone();
+        prev = Some(header);
+    }
+
+    report.elapsed_secs = start.elapsed().as_secs_f64();
+    println!(
+        "{} {} headers checked in {:.1}s, {} failures",
+        if report.failure_count == 0 { "✅" } else { "❌" },
+        report.headers_checked,
+        report.elapsed_secs,
+        report.failure_count
+    );
+    if let Some(first) = report.failures.first() {
+        println!("   First failure: height {} ({}) - {}", first.height, first.rule, first.detail);
+    }
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_compact_roundtrip() {
+        for bits in [0x1d00ffff, 0x1b0404cb, 0x170331db, 0x1a05db8b] {
+            assert_eq!(U256::from_compact(bits).to_compact(), bits);
+        }
+    }
+
+    #[test]
+    fn test_retarget() {
+        // Exact two weeks keeps the target; anything slower is capped at the limit
+        assert_eq!(retarget(0x1b0404cb, 0, TARGET_TIMESPAN), 0x1b0404cb);
+        assert_eq!(retarget(0x1d00ffff, 0, TARGET_TIMESPAN * 10), POW_LIMIT_BITS);
+        // Four times faster (clamped) divides the target by four
+        assert_eq!(retarget(0x1d00ffff, 0, 1), 0x1c3fffc0);
+    }
+
+    #[test]
+    fn test_median() {
+        assert_eq!(median([5, 1, 3].into_iter()), 3);
+    }
+}
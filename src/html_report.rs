@@ -0,0 +1,461 @@
+//! HTML Run Report
+//!
+//! Turns a parallel differential run into one self-contained HTML page
+//! (inline SVG charts, no external assets): throughput over time, per-chunk
+//! durations, a divergence table with expandable detail and the UTXO set
+//! growth curve - suitable for attaching to blvm_consensus release notes.
+//!
+//! `run_parallel_differential` saves a `RunRecord` as JSON to
+//! `BLVM_RUN_RECORD` and renders HTML to `BLVM_HTML_REPORT` when set; the
+//! `html-report` subcommand renders a saved record.
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::fmt::Write as _;
+use std::path::Path;
+
+use crate::parallel_differential::{ChunkResult, ParallelConfig};
+
+/// One divergence in a chunk
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DivergenceRecord {
+    pub height: u64,
+    pub blvm_result: String,
+    pub core_result: String,
+    /// Waiver covering the divergence (it doesn't fail the run)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub waiver: Option<String>,
+}
+
+/// Serializable view of a `ChunkResult`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChunkRecord {
+    pub start_height: u64,
+    pub end_height: u64,
+    pub tested: usize,
+    pub matched: usize,
+    pub duration_secs: f64,
+    /// Seconds from run start until the chunk finished
+    pub finished_after_secs: f64,
+    pub utxo_count: usize,
+    pub divergences: Vec<DivergenceRecord>,
+    pub poisoned: Option<String>,
+    /// Absent in records saved before memory profiling
+    #[serde(default)]
+    pub memory: crate::mem_profile::ChunkMemory,
+}
+
+impl ChunkRecord {
+    pub fn from_result(result: &ChunkResult, started_at: std::time::SystemTime) -> Self {
+        Self {
+            start_height: result.start_height,
+            end_height: result.end_height,
+            tested: result.tested,
+            matched: result.matched,
+            duration_secs: result.duration_secs,
+            finished_after_secs: result
+                .finished_at
+                .duration_since(started_at)
+                .map(|d| d.as_secs_f64())
+                .unwrap_or_default(),
+            utxo_count: result.utxo_count,
+            divergences: result
+                .divergences
+                .iter()
+                .map(|(height, blvm, core)| DivergenceRecord {
+                    height: *height,
+                    blvm_result: blvm.clone(),
+                    core_result: core.clone(),
+                    waiver: result
+                        .waived
+                        .iter()
+                        .find(|(waived, _)| waived == height)
+                        .map(|(_, reason)| reason.clone()),
+                })
+                .collect(),
+            poisoned: result.poisoned.as_ref().map(|p| p.to_string()),
+            memory: result.memory,
+        }
+    }
+}
+
+/// Everything the report needs about a run
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RunRecord {
+    /// Unix seconds
+    pub started_at: u64,
+    pub start_height: u64,
+    pub end_height: u64,
+    pub chunk_size: u64,
+    pub num_workers: usize,
+    pub utxo_backend: String,
+    /// Seed of a sample-mode run (reproduces the same heights)
+    #[serde(default)]
+    pub sample_seed: Option<u64>,
+    pub chunks: Vec<ChunkRecord>,
+}
+
+impl RunRecord {
+    pub fn from_results(
+        start_height: u64,
+        end_height: u64,
+        config: &ParallelConfig,
+        started_at: std::time::SystemTime,
+        results: &[ChunkResult],
+    ) -> Self {
+        let mut chunks: Vec<ChunkRecord> = results.iter().map(|r| ChunkRecord::from_result(r, started_at)).collect();
+        chunks.sort_by_key(|c| c.start_height);
+
+        Self {
+            started_at: started_at
+                .duration_since(std::time::UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or_default(),
+            start_height,
+            end_height,
+            chunk_size: config.chunk_size,
+            num_workers: config.num_workers,
+            utxo_backend: config.utxo_backend.name().to_string(),
+            sample_seed: config.sample.filter(|s| !s.is_exhaustive()).map(|s| s.seed),
+            chunks,
+        }
+    }
+
+    pub fn load(path: &Path) -> Result<Self> {
+        let content = std::fs::read_to_string(path).with_context(|| format!("Failed to read {}", path.display()))?;
+        serde_json::from_str(&content).with_context(|| format!("Failed to parse run record {}", path.display()))
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        std::fs::write(path, serde_json::to_string_pretty(self)?)
+            .with_context(|| format!("Failed to write {}", path.display()))
+    }
+
+    fn total_tested(&self) -> usize {
+        self.chunks.iter().map(|c| c.tested).sum()
+    }
+
+    fn total_divergences(&self) -> usize {
+        self.chunks.iter().map(|c| c.divergences.len()).sum()
+    }
+
+    /// Divergences no waiver covers
+    pub fn unwaived_divergences(&self) -> usize {
+        self.chunks
+            .iter()
+            .flat_map(|c| &c.divergences)
+            .filter(|d| d.waiver.is_none())
+            .count()
+    }
+
+    /// (seconds since start, cumulative blocks/sec) at each chunk completion
+    fn throughput_curve(&self) -> Vec<(f64, f64)> {
+        let mut by_time: Vec<&ChunkRecord> = self.chunks.iter().collect();
+        by_time.sort_by(|a, b| a.finished_after_secs.total_cmp(&b.finished_after_secs));
+        let mut blocks = 0usize;
+        by_time
+            .into_iter()
+            .filter(|c| c.finished_after_secs > 0.0)
+            .map(|c| {
+                blocks += c.tested;
+                (c.finished_after_secs, blocks as f64 / c.finished_after_secs)
+            })
+            .collect()
+    }
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+const CHART_W: f64 = 720.0;
+const CHART_H: f64 = 260.0;
+const MARGIN: f64 = 50.0;
+
+/// Axis frame with min/max labels; returns the SVG prefix and a point mapper
+fn chart_frame(title: &str, x_max: f64, y_max: f64, x_label: &str) -> (String, impl Fn(f64, f64) -> (f64, f64)) {
+    let x_max = if x_max > 0.0 { x_max } else { 1.0 };
+    let y_max = if y_max > 0.0 { y_max } else { 1.0 };
+    let mut svg = String::new();
+    let _ = write!(
+        svg,
+        r#"<h2>{title}</h2><svg viewBox="0 0 {w} {h}" width="{w}" height="{h}" role="img">
+<line x1="{m}" y1="{b}" x2="{r}" y2="{b}" class="axis"/><line x1="{m}" y1="{t}" x2="{m}" y2="{b}" class="axis"/>
+<text x="{m}" y="{lb}">0</text><text x="{r}" y="{lb}" text-anchor="end">{x_max:.0} {x_label}</text>
+<text x="{lm}" y="{tt}" text-anchor="end">{y_max:.0}</text>
+"#,
+        title = escape(title),
+        w = CHART_W,
+        h = CHART_H,
+        m = MARGIN,
+        r = CHART_W - 10.0,
+        t = 10.0,
+        b = CHART_H - MARGIN,
+        lb = CHART_H - MARGIN + 16.0,
+        lm = MARGIN - 4.0,
+        tt = 16.0,
+    );
+    let map = move |x: f64, y: f64| {
+        (
+            MARGIN + x / x_max * (CHART_W - MARGIN - 10.0),
+            CHART_H - MARGIN - y / y_max * (CHART_H - MARGIN - 10.0),
+        )
+    };
+    (svg, map)
+}
+
+fn line_chart(title: &str, points: &[(f64, f64)], x_label: &str) -> String {
+    if points.is_empty() {
+        return format!("<h2>{}</h2><p>No data.</p>", escape(title));
+    }
+    let x_max = points.iter().map(|p| p.0).fold(0.0, f64::max);
+    let y_max = points.iter().map(|p| p.1).fold(0.0, f64::max);
+    let (mut svg, map) = chart_frame(title, x_max, y_max, x_label);
+    let path: Vec<String> = points
+        .iter()
+        .map(|(x, y)| {
+            let (px, py) = map(*x, *y);
+            format!("{:.1},{:.1}", px, py)
+        })
+        .collect();
+    let _ = write!(svg, r#"<polyline points="{}" class="line"/></svg>"#, path.join(" "));
+    svg
+}
+
+fn bar_chart(title: &str, bars: &[(String, f64, bool)]) -> String {
+    if bars.is_empty() {
+        return format!("<h2>{}</h2><p>No data.</p>", escape(title));
+    }
+    let y_max = bars.iter().map(|b| b.1).fold(0.0, f64::max);
+    let (mut svg, map) = chart_frame(title, bars.len() as f64, y_max, "chunks");
+    let width = (CHART_W - MARGIN - 10.0) / bars.len() as f64;
+    for (i, (label, value, flagged)) in bars.iter().enumerate() {
+        let (x, y) = map(i as f64, *value);
+        let (_, base) = map(0.0, 0.0);
+        let _ = write!(
+            svg,
+            r#"<rect x="{:.1}" y="{:.1}" width="{:.1}" height="{:.1}" class="{}"><title>{}: {:.1}s</title></rect>"#,
+            x,
+            y,
+            (width - 1.0).max(1.0),
+            base - y,
+            if *flagged { "bar bad" } else { "bar" },
+            escape(label),
+            value
+        );
+    }
+    svg.push_str("</svg>");
+    svg
+}
+
+/// Render the full report page
+pub fn render_html(record: &RunRecord) -> String {
+    let tested = record.total_tested();
+    let divergences = record.total_divergences();
+    let unwaived = record.unwaived_divergences();
+    let wall_secs = record
+        .chunks
+        .iter()
+        .map(|c| c.finished_after_secs)
+        .fold(0.0, f64::max);
+
+    let mut html = String::new();
+    let _ = write!(
+        html,
+        r#"<!DOCTYPE html>
+<html lang="en"><head><meta charset="utf-8"><title>BLVM differential run {start}-{end}</title>
+<style>
+body {{ font-family: system-ui, sans-serif; margin: 2em; color: #222; }}
+table {{ border-collapse: collapse; }} td, th {{ border: 1px solid #ccc; padding: 4px 8px; text-align: left; }}
+.axis {{ stroke: #888; }} .line {{ fill: none; stroke: #1f77b4; stroke-width: 2; }}
+.bar {{ fill: #1f77b4; }} .bad {{ fill: #d62728; }} svg text {{ font-size: 11px; fill: #555; }}
+.ok {{ color: #2ca02c; }} .fail {{ color: #d62728; }}
+</style></head><body>
+<h1>BLVM differential run: heights {start}-{end}</h1>
+<table>
+<tr><th>Result</th><td class="{status_class}">{status}</td></tr>
+<tr><th>Blocks tested</th><td>{tested}</td></tr>
+<tr><th>Divergences</th><td>{divergences}</td></tr>
+<tr><th>Wall time</th><td>{wall:.1}s</td></tr>
+<tr><th>Chunks</th><td>{chunks} &times; {chunk_size} blocks, {workers} workers, {backend} UTXO backend</td></tr>
+{sample_row}{memory_row}</table>
+"#,
+        start = record.start_height,
+        end = record.end_height,
+        status_class = if unwaived == 0 { "ok" } else { "fail" },
+        status = match (divergences, unwaived) {
+            (0, _) => "✅ No divergences",
+            (_, 0) => "✅ Only waived divergences",
+            _ => "❌ Divergences found",
+        },
+        wall = wall_secs,
+        chunks = record.chunks.len(),
+        chunk_size = record.chunk_size,
+        workers = record.num_workers,
+        backend = escape(&record.utxo_backend),
+        sample_row = record
+            .sample_seed
+            .map(|seed| format!("<tr><th>Sample seed</th><td>{}</td></tr>\n", seed))
+            .unwrap_or_default(),
+        memory_row = record
+            .chunks
+            .iter()
+            .filter_map(|c| c.memory.peak_rss_bytes)
+            .max()
+            .map(|peak| format!(
+                "<tr><th>Peak RSS</th><td>{}</td></tr>\n",
+                crate::mem_profile::format_bytes(peak)
+            ))
+            .unwrap_or_default(),
+    );
+
+    html.push_str(&line_chart("Throughput over time (blocks/sec)", &record.throughput_curve(), "s"));
+
+    let bars: Vec<(String, f64, bool)> = record
+        .chunks
+        .iter()
+        .map(|c| {
+            (
+                format!("{}-{}", c.start_height, c.end_height),
+                c.duration_secs,
+                !c.divergences.is_empty() || c.poisoned.is_some(),
+            )
+        })
+        .collect();
+    html.push_str(&bar_chart("Per-chunk duration (s)", &bars));
+
+    let utxo_curve: Vec<(f64, f64)> = record
+        .chunks
+        .iter()
+        .filter(|c| c.utxo_count > 0)
+        .map(|c| (c.end_height as f64, c.utxo_count as f64))
+        .collect();
+    html.push_str(&line_chart("UTXO set size", &utxo_curve, "height"));
+
+    let rss_curve: Vec<(f64, f64)> = record
+        .chunks
+        .iter()
+        .filter_map(|c| c.memory.rss_bytes.map(|rss| (c.finished_after_secs, rss as f64 / (1024.0 * 1024.0))))
+        .collect();
+    if !rss_curve.is_empty() {
+        html.push_str(&line_chart("RSS over time (MiB)", &rss_curve, "s"));
+    }
+
+    html.push_str("<h2>Divergences</h2>");
+    if divergences == 0 {
+        html.push_str("<p class=\"ok\">None.</p>");
+    } else {
+        html.push_str("<table><tr><th>Height</th><th>Chunk</th><th>BLVM</th><th>Core</th></tr>");
+        for chunk in &record.chunks {
+            for d in &chunk.divergences {
+                let short = |s: &str| s.chars().take(60).collect::<String>();
+                let _ = write!(
+                    html,
+                    "<tr><td>{}{}</td><td>{}-{}</td><td><details><summary>{}</summary><pre>{}</pre></details></td>\
+                     <td><details><summary>{}</summary><pre>{}</pre></details></td></tr>",
+                    d.height,
+                    d.waiver
+                        .as_deref()
+                        .map(|reason| format!(" <small>(waived: {})</small>", escape(reason)))
+                        .unwrap_or_default(),
+                    chunk.start_height,
+                    chunk.end_height,
+                    escape(&short(&d.blvm_result)),
+                    escape(&d.blvm_result),
+                    escape(&short(&d.core_result)),
+                    escape(&d.core_result),
+                );
+            }
+        }
+        html.push_str("</table>");
+    }
+
+    let poisoned: Vec<&ChunkRecord> = record.chunks.iter().filter(|c| c.poisoned.is_some()).collect();
+    if !poisoned.is_empty() {
+        html.push_str("<h2>Poisoned chunks</h2><ul>");
+        for chunk in poisoned {
+            let _ = write!(
+                html,
+                "<li>{}-{}: {}</li>",
+                chunk.start_height,
+                chunk.end_height,
+                escape(chunk.poisoned.as_deref().unwrap_or_default())
+            );
+        }
+        html.push_str("</ul>");
+    }
+
+    html.push_str("</body></html>\n");
+    html
+}
+
+/// Render a record to `path`
+pub fn write_html_report(record: &RunRecord, path: &Path) -> Result<()> {
+    std::fs::write(path, render_html(record)).with_context(|| format!("Failed to write {}", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record() -> RunRecord {
+        let chunk = |start: u64, secs: f64, divergences: Vec<DivergenceRecord>| ChunkRecord {
+            start_height: start,
+            end_height: start + 99,
+            tested: 100,
+            matched: 100 - divergences.len(),
+            duration_secs: secs,
+            finished_after_secs: secs,
+            utxo_count: start as usize * 10,
+            divergences,
+            poisoned: None,
+            memory: Default::default(),
+        };
+        RunRecord {
+            started_at: 0,
+            start_height: 0,
+            end_height: 199,
+            chunk_size: 100,
+            num_workers: 2,
+            utxo_backend: "memory".to_string(),
+            sample_seed: None,
+            chunks: vec![
+                chunk(0, 10.0, Vec::new()),
+                chunk(
+                    100,
+                    20.0,
+                    vec![DivergenceRecord {
+                        height: 150,
+                        blvm_result: "Invalid(<bad-txns>)".to_string(),
+                        core_result: "Valid".to_string(),
+                        waiver: None,
+                    }],
+                ),
+            ],
+        }
+    }
+
+    #[test]
+    fn test_throughput_curve() {
+        assert_eq!(record().throughput_curve(), vec![(10.0, 10.0), (20.0, 10.0)]);
+    }
+
+    #[test]
+    fn test_render_escapes_and_lists_divergences() {
+        let html = render_html(&record());
+        assert!(html.contains("<td>150</td>"));
+        assert!(html.contains("Invalid(&lt;bad-txns&gt;)"));
+        assert!(!html.contains("<bad-txns>"));
+        assert!(html.contains("Divergences found"));
+
+        let mut waived = record();
+        waived.chunks[1].divergences[0].waiver = Some("known <issue>".to_string());
+        assert_eq!(waived.unwaived_divergences(), 0);
+        let html = render_html(&waived);
+        assert!(html.contains("Only waived divergences"));
+        assert!(html.contains("(waived: known &lt;issue&gt;)"));
+    }
+}
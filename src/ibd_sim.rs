@@ -0,0 +1,278 @@
+//! IBD Throughput Simulation
+//!
+//! A macro-benchmark that approximates initial block download: replay the
+//! chain from genesis through full BLVM validation (`connect_block` on a real,
+//! growing UTXO set) as fast as the block source allows, with no Core
+//! comparison, and report blocks/sec and tx/sec per consensus era.
+//!
+//! Sequential sources (block files, chunked caches) are read in one pass with
+//! blocks deserialized ahead of validation on the rayon pool
+//! (`BLVM_DECODE_AHEAD`). Reports can be saved as JSON and passed back as a
+//! baseline, so two releases of blvm-consensus can be compared on the same
+//! range and machine.
+
+use anyhow::{Context, Result};
+use blvm_consensus::types::{Network, ValidationResult};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::time::Instant;
+
+use crate::block_source::BlockSource;
+use crate::decode_pipeline::{decode_block, DecodePipeline, DecodedBlock};
+use crate::sampler::Era;
+use crate::utxo_backend::UtxoBackend;
+
+/// Blocks between progress lines
+const PROGRESS_INTERVAL: u64 = 10_000;
+
+/// Throughput over the part of one era that was replayed
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EraThroughput {
+    pub era: String,
+    pub first_height: u64,
+    pub last_height: u64,
+    pub blocks: u64,
+    pub transactions: u64,
+    /// Wall time spent in the era (reading, decoding and connecting)
+    pub wall_secs: f64,
+}
+
+impl EraThroughput {
+    pub fn blocks_per_sec(&self) -> f64 {
+        self.blocks as f64 / self.wall_secs.max(f64::EPSILON)
+    }
+
+    pub fn tx_per_sec(&self) -> f64 {
+        self.transactions as f64 / self.wall_secs.max(f64::EPSILON)
+    }
+}
+
+/// Result of one simulation run
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct IbdReport {
+    pub start_height: u64,
+    pub end_height: u64,
+    pub utxo_backend: String,
+    pub eras: Vec<EraThroughput>,
+    pub wall_secs: f64,
+    /// UTXO set size after the last block
+    pub utxo_count: usize,
+}
+
+impl IbdReport {
+    pub fn blocks(&self) -> u64 {
+        self.eras.iter().map(|era| era.blocks).sum()
+    }
+
+    pub fn transactions(&self) -> u64 {
+        self.eras.iter().map(|era| era.transactions).sum()
+    }
+
+    pub fn print(&self) {
+        println!("\n📊 IBD simulation: heights {}-{} ({} UTXO backend)", self.start_height, self.end_height, self.utxo_backend);
+        println!("   {:<12} {:>17} {:>10} {:>12} {:>12}", "era", "heights", "blocks", "blocks/sec", "tx/sec");
+        for era in &self.eras {
+            println!(
+                "   {:<12} {:>17} {:>10} {:>12.1} {:>12.1}",
+                era.era,
+                format!("{}-{}", era.first_height, era.last_height),
+                era.blocks,
+                era.blocks_per_sec(),
+                era.tx_per_sec()
+            );
+        }
+        let wall = self.wall_secs.max(f64::EPSILON);
+        println!(
+            "   {:<12} {:>17} {:>10} {:>12.1} {:>12.1}",
+            "total",
+            format!("{}-{}", self.start_height, self.end_height),
+            self.blocks(),
+            self.blocks() as f64 / wall,
+            self.transactions() as f64 / wall
+        );
+        println!("   {:.1}s wall clock, {} coins in the final UTXO set", self.wall_secs, self.utxo_count);
+    }
+
+    /// Per-era blocks/sec change against `baseline` (same era names), as percentages
+    pub fn compare(&self, baseline: &IbdReport) -> Vec<(String, f64)> {
+        self.eras
+            .iter()
+            .filter_map(|era| {
+                let base = baseline.eras.iter().find(|b| b.era == era.era)?;
+                Some((era.era.clone(), 100.0 * (era.blocks_per_sec() / base.blocks_per_sec().max(f64::EPSILON) - 1.0)))
+            })
+            .collect()
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        std::fs::write(path, serde_json::to_string_pretty(self)?)
+            .with_context(|| format!("Failed to write {}", path.display()))
+    }
+
+    pub fn load(path: &Path) -> Result<Self> {
+        let data = std::fs::read(path).with_context(|| format!("Failed to read {}", path.display()))?;
+        serde_json::from_slice(&data).with_context(|| format!("{} is not an IBD simulation report", path.display()))
+    }
+}
+
+/// Splits the replay into per-era throughput as blocks are connected
+struct EraMeter {
+    eras: Vec<EraThroughput>,
+    era_started: Instant,
+}
+
+impl EraMeter {
+    fn new() -> Self {
+        Self { eras: Vec::new(), era_started: Instant::now() }
+    }
+
+    fn record(&mut self, height: u64, transactions: usize) {
+        let era = Era::of(height).name();
+        if self.eras.last().map(|current| current.era.as_str()) != Some(era) {
+            self.close_era();
+            self.eras.push(EraThroughput {
+                era: era.to_string(),
+                first_height: height,
+                last_height: height,
+                blocks: 0,
+                transactions: 0,
+                wall_secs: 0.0,
+            });
+        }
+        let current = self.eras.last_mut().expect("era pushed above");
+        current.last_height = height;
+        current.blocks += 1;
+        current.transactions += transactions as u64;
+    }
+
+    fn close_era(&mut self) {
+        if let Some(current) = self.eras.last_mut() {
+            current.wall_secs = self.era_started.elapsed().as_secs_f64();
+        }
+        self.era_started = Instant::now();
+    }
+
+    fn finish(mut self) -> Vec<EraThroughput> {
+        self.close_era();
+        self.eras
+    }
+}
+
+/// Replay `start..=end` from `source` through BLVM, starting from an empty UTXO set at `start`
+///
+/// `start` is normally 0; a later start only makes sense for benchmarking
+/// blocks that don't spend earlier coins. Any rejected block aborts the run.
+pub async fn run_ibd_simulation(
+    source: &dyn BlockSource,
+    start: u64,
+    end: u64,
+    utxo_backend: &UtxoBackend,
+    decode_ahead: usize,
+) -> Result<IbdReport> {
+    if end < start {
+        anyhow::bail!("Nothing to replay: end height {} is below start height {}", end, start);
+    }
+    println!(
+        "🏁 IBD simulation: replaying heights {}-{} from {} ({} UTXO backend, no Core comparison)",
+        start,
+        end,
+        source.name(),
+        utxo_backend.name()
+    );
+    let mut store = utxo_backend.create(blvm_consensus::UtxoSet::new())?;
+    let mut meter = EraMeter::new();
+    let started = Instant::now();
+
+    let mut connect = |height: u64, decoded: DecodedBlock| -> Result<()> {
+        let (block, witnesses) = decoded;
+        match store.connect_block(&block, &witnesses, height, Network::Mainnet)? {
+            ValidationResult::Valid => {}
+            ValidationResult::Invalid(msg) => anyhow::bail!("BLVM rejected block {}: {}", height, msg),
+        }
+        meter.record(height, block.transactions.len());
+        if height % PROGRESS_INTERVAL == 0 {
+            let done = height - start + 1;
+            println!(
+                "   📊 Height {} ({:.1} blocks/sec, {} coins)",
+                height,
+                done as f64 / started.elapsed().as_secs_f64().max(f64::EPSILON),
+                store.len()
+            );
+        }
+        Ok(())
+    };
+
+    let count = (end - start + 1) as usize;
+    let mut next_height = start;
+    match source.iter_sequential(start, count)? {
+        Some(blocks) => {
+            for block in DecodePipeline::new(blocks, start, decode_ahead) {
+                let block = block?;
+                connect(block.height, block.decoded)?;
+                next_height = block.height + 1;
+            }
+        }
+        None => {
+            for height in start..=end {
+                let bytes = source.get_block(height).await.with_context(|| format!("Failed to fetch block {}", height))?;
+                connect(height, decode_block(&bytes, height)?)?;
+                next_height = height + 1;
+            }
+        }
+    }
+    if next_height <= end {
+        anyhow::bail!("{} ended at height {} before {}", source.name(), next_height.saturating_sub(1), end);
+    }
+
+    Ok(IbdReport {
+        start_height: start,
+        end_height: end,
+        utxo_backend: utxo_backend.name().to_string(),
+        eras: meter.finish(),
+        wall_secs: started.elapsed().as_secs_f64(),
+        utxo_count: store.len(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_era_meter_splits_at_activation_heights() {
+        let (segwit_start, _) = Era::Segwit.heights();
+        let mut meter = EraMeter::new();
+        for height in segwit_start - 3..segwit_start + 2 {
+            meter.record(height, 2);
+        }
+        let eras = meter.finish();
+        assert_eq!(eras.len(), 2);
+        assert_eq!((eras[0].era.as_str(), eras[0].blocks, eras[0].transactions), ("pre-segwit", 3, 6));
+        assert_eq!((eras[1].era.as_str(), eras[1].first_height, eras[1].blocks), ("segwit", segwit_start, 2));
+    }
+
+    #[test]
+    fn test_compare_against_baseline() {
+        let era = |name: &str, blocks, wall_secs| EraThroughput {
+            era: name.to_string(),
+            first_height: 0,
+            last_height: 0,
+            blocks,
+            transactions: 0,
+            wall_secs,
+        };
+        let report = |eras| IbdReport {
+            start_height: 0,
+            end_height: 0,
+            utxo_backend: "memory".to_string(),
+            eras,
+            wall_secs: 1.0,
+            utxo_count: 0,
+        };
+        let baseline = report(vec![era("pre-bip34", 1000, 1.0)]);
+        let current = report(vec![era("pre-bip34", 900, 1.0), era("pre-segwit", 10, 1.0)]);
+        let deltas = current.compare(&baseline);
+        assert_eq!(deltas.len(), 1);
+        assert!((deltas[0].1 + 10.0).abs() < 1e-9);
+    }
+}
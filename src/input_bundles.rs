@@ -0,0 +1,128 @@
+//! Recorded Input Bundles
+//!
+//! A differential run already knows exactly which coins each block spends. With
+//! `BLVM_INPUT_BUNDLES` set, every block BLVM accepts is written out with that
+//! UTXO subset as a compact "input bundle", one file per block:
+//!
+//! ```text
+//! <dir>/block_<height>.bundle
+//!   magic "BLVMIB01" | height (u64) | block_len (u32) | block | utxos
+//! ```
+//!
+//! `utxos` uses the same encoding as reproducer `utxos.bin`. A bundle holds
+//! everything `connect_block` reads, so the `connect_block_bundles` bench can
+//! replay any recorded range offline and repeatably - no node, no cache, no
+//! UTXO set to rebuild.
+
+use anyhow::{Context, Result};
+use blvm_consensus::UtxoSet;
+use std::path::{Path, PathBuf};
+
+use crate::reproducer::{decode_utxo_set, encode_utxo_set};
+
+const MAGIC: &[u8; 8] = b"BLVMIB01";
+
+/// Bundle directory from `BLVM_INPUT_BUNDLES` (None = don't record)
+pub fn input_bundle_dir_from_env() -> Option<PathBuf> {
+    std::env::var("BLVM_INPUT_BUNDLES").ok().map(PathBuf::from)
+}
+
+/// One block and the coins it spends
+pub struct InputBundle {
+    pub height: u64,
+    pub block_bytes: Vec<u8>,
+    pub pre_state: UtxoSet,
+}
+
+/// Path of the bundle for `height` in `dir`
+pub fn bundle_path(dir: &Path, height: u64) -> PathBuf {
+    dir.join(format!("block_{}.bundle", height))
+}
+
+/// Write the bundle for `height` (temporary file, then rename)
+pub fn write_bundle(dir: &Path, height: u64, block_bytes: &[u8], pre_state: &UtxoSet) -> Result<PathBuf> {
+    std::fs::create_dir_all(dir)
+        .with_context(|| format!("Failed to create bundle directory {}", dir.display()))?;
+
+    let utxos = encode_utxo_set(pre_state);
+    let mut buf = Vec::with_capacity(20 + block_bytes.len() + utxos.len());
+    buf.extend_from_slice(MAGIC);
+    buf.extend_from_slice(&height.to_le_bytes());
+    buf.extend_from_slice(&(block_bytes.len() as u32).to_le_bytes());
+    buf.extend_from_slice(block_bytes);
+    buf.extend_from_slice(&utxos);
+
+    let path = bundle_path(dir, height);
+    let tmp = path.with_extension("bundle.tmp");
+    std::fs::write(&tmp, &buf).with_context(|| format!("Failed to write {}", tmp.display()))?;
+    std::fs::rename(&tmp, &path).with_context(|| format!("Failed to write {}", path.display()))?;
+    Ok(path)
+}
+
+/// Read one bundle file
+pub fn load_bundle(path: &Path) -> Result<InputBundle> {
+    let buf = std::fs::read(path).with_context(|| format!("Failed to read {}", path.display()))?;
+    if buf.get(..8) != Some(&MAGIC[..]) {
+        anyhow::bail!("{} is not an input bundle", path.display());
+    }
+    let truncated = || format!("{} is truncated", path.display());
+    let height = u64::from_le_bytes(buf.get(8..16).with_context(truncated)?.try_into()?);
+    let block_len = u32::from_le_bytes(buf.get(16..20).with_context(truncated)?.try_into()?) as usize;
+    let block_bytes = buf.get(20..20 + block_len).with_context(truncated)?.to_vec();
+    let pre_state = decode_utxo_set(&buf[20 + block_len..])
+        .with_context(|| format!("Bad UTXO subset in {}", path.display()))?;
+    Ok(InputBundle { height, block_bytes, pre_state })
+}
+
+/// Heights and paths of the bundles in `dir`, in height order
+pub fn list_bundles(dir: &Path) -> Result<Vec<(u64, PathBuf)>> {
+    let mut bundles = Vec::new();
+    for entry in std::fs::read_dir(dir).with_context(|| format!("Failed to list {}", dir.display()))? {
+        let path = entry?.path();
+        let height = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .and_then(|name| name.strip_prefix("block_")?.strip_suffix(".bundle"))
+            .and_then(|height| height.parse().ok());
+        if let Some(height) = height {
+            bundles.push((height, path));
+        }
+    }
+    bundles.sort_unstable_by_key(|(height, _)| *height);
+    Ok(bundles)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use blvm_consensus::{OutPoint, UTXO};
+
+    #[test]
+    fn test_bundle_roundtrip_and_listing() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut pre_state = UtxoSet::new();
+        pre_state.insert(
+            OutPoint { hash: [3u8; 32], index: 1 },
+            UTXO {
+                value: 1_000,
+                script_pubkey: vec![0x51].into(),
+                height: 99,
+                is_coinbase: false,
+            },
+        );
+
+        write_bundle(dir.path(), 120, &[1, 2, 3], &pre_state).unwrap();
+        write_bundle(dir.path(), 7, &[], &UtxoSet::new()).unwrap();
+        std::fs::write(dir.path().join("notes.txt"), "not a bundle").unwrap();
+
+        let listed = list_bundles(dir.path()).unwrap();
+        assert_eq!(listed.iter().map(|(height, _)| *height).collect::<Vec<_>>(), vec![7, 120]);
+
+        let bundle = load_bundle(&listed[1].1).unwrap();
+        assert_eq!(bundle.height, 120);
+        assert_eq!(bundle.block_bytes, vec![1, 2, 3]);
+        assert_eq!(bundle.pre_state.get(&OutPoint { hash: [3u8; 32], index: 1 }).unwrap().value, 1_000);
+
+        assert!(load_bundle(&dir.path().join("notes.txt")).is_err());
+    }
+}
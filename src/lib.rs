@@ -8,6 +8,24 @@ pub mod deep_analysis;
 /// Benchmark utilities and helpers
 pub mod utils;
 
+/// Block header hashing in internal and display byte order
+pub mod block_hash;
+
+/// Signed transaction/block fixtures for benchmarks
+pub mod bench_fixtures;
+
+/// Criterion result export and regression gate
+pub mod bench_report;
+
+/// Bench group selection and sample/corpus sizes from a TOML file or env
+pub mod bench_config;
+
+/// Comparison against Bitcoin Core's bench_bitcoin output
+pub mod core_bench_compare;
+
+/// GitHub Actions workflow-command annotations
+pub mod gh_annotations;
+
 /// Shell benchmark runner
 pub mod shell;
 
@@ -24,13 +42,157 @@ pub mod regtest_node;
 #[cfg(feature = "differential")]
 pub mod parallel_differential;
 #[cfg(feature = "differential")]
+pub mod block_source;
+#[cfg(feature = "differential")]
+pub mod rpc_limiter;
+#[cfg(feature = "differential")]
+pub mod decode_pipeline;
+#[cfg(feature = "differential")]
+pub mod prefetch;
+#[cfg(feature = "differential")]
 pub mod block_file_reader;
 #[cfg(feature = "differential")]
+pub mod datadir;
+#[cfg(feature = "differential")]
+pub mod best_chain;
+#[cfg(feature = "differential")]
+pub mod block_reorder;
+#[cfg(feature = "differential")]
+pub mod blk_tail;
+#[cfg(feature = "differential")]
 pub mod start9_rpc_client;
 #[cfg(feature = "differential")]
+pub mod start9_session;
+#[cfg(feature = "differential")]
+pub mod container_rpc_client;
+#[cfg(feature = "differential")]
+pub mod pruned_source;
+#[cfg(feature = "differential")]
 pub mod chunked_cache;
 #[cfg(feature = "differential")]
+pub mod cache_archive;
+#[cfg(feature = "differential")]
+pub mod cache_manifest;
+#[cfg(feature = "differential")]
+pub mod cache_warm;
+#[cfg(feature = "differential")]
+pub mod mmap_cache;
+#[cfg(feature = "differential")]
 pub mod collect_only;
+#[cfg(feature = "differential")]
+pub mod p2p_client;
+#[cfg(feature = "differential")]
+pub mod compact_block;
+#[cfg(feature = "differential")]
+pub mod esplora_source;
+#[cfg(feature = "differential")]
+pub mod utxo_backend;
+#[cfg(feature = "differential")]
+pub mod block_debug;
+#[cfg(feature = "differential")]
+pub mod block_inspect;
+#[cfg(feature = "differential")]
+pub mod checkpoint_store;
+#[cfg(feature = "differential")]
+pub mod checkpoint_merge;
+#[cfg(feature = "differential")]
+pub mod utxo_snapshot;
+#[cfg(feature = "differential")]
+pub mod coin_stats;
+#[cfg(feature = "differential")]
+pub mod deferred_utxo;
+#[cfg(feature = "differential")]
+pub mod reproducer;
+#[cfg(feature = "differential")]
+pub mod divergence_minimizer;
+#[cfg(feature = "differential")]
+pub mod waivers;
+#[cfg(feature = "differential")]
+pub mod input_bundles;
+#[cfg(feature = "differential")]
+pub mod doctor;
+#[cfg(feature = "differential")]
+pub mod quarantine;
+#[cfg(feature = "differential")]
+pub mod block_fixtures;
+#[cfg(feature = "differential")]
+pub mod mempool_corpus;
+#[cfg(feature = "differential")]
+pub mod reorg_differential;
+#[cfg(feature = "differential")]
+pub mod header_differential;
+#[cfg(feature = "differential")]
+pub mod accounting_differential;
+#[cfg(feature = "differential")]
+pub mod filter_differential;
+#[cfg(feature = "differential")]
+pub mod txindex_differential;
+#[cfg(feature = "differential")]
+pub mod locktime_differential;
+#[cfg(feature = "differential")]
+pub mod taproot_differential;
+#[cfg(feature = "differential")]
+pub mod package_differential;
+#[cfg(feature = "differential")]
+pub mod rbf_differential;
+#[cfg(feature = "differential")]
+pub mod standardness_differential;
+#[cfg(feature = "differential")]
+pub mod activation_check;
+#[cfg(feature = "differential")]
+pub mod core_versions;
+#[cfg(feature = "differential")]
+pub mod submit_verifier;
+#[cfg(feature = "differential")]
+pub mod fee_check;
+#[cfg(feature = "differential")]
+pub mod alerts;
+#[cfg(feature = "differential")]
+pub mod html_report;
+#[cfg(feature = "differential")]
+pub mod ci_report;
+#[cfg(feature = "differential")]
+pub mod progress;
+#[cfg(feature = "differential")]
+pub mod slow_blocks;
+#[cfg(feature = "differential")]
+pub mod mem_profile;
+#[cfg(feature = "differential")]
+pub mod flamegraph;
+#[cfg(feature = "differential")]
+pub mod sampler;
+#[cfg(feature = "differential")]
+pub mod chunk_sizing;
+#[cfg(feature = "differential")]
+pub mod run_plan;
+#[cfg(feature = "differential")]
+pub mod multi_network;
+#[cfg(feature = "differential")]
+pub mod result_stream;
+#[cfg(feature = "differential")]
+pub mod ibd_sim;
+#[cfg(feature = "differential")]
+pub mod presets;
+#[cfg(feature = "differential")]
+pub mod block_corpus;
+#[cfg(feature = "differential")]
+pub mod mutation_differential;
+#[cfg(feature = "differential")]
+pub mod tx_roundtrip;
+#[cfg(feature = "differential")]
+pub mod test_vectors;
+#[cfg(feature = "libconsensus")]
+pub mod flag_matrix;
+#[cfg(feature = "live")]
+pub mod live_differential;
+#[cfg(feature = "chainstate")]
+pub mod chainstate_reader;
+#[cfg(feature = "chainstate")]
+pub mod utxo_diff;
+#[cfg(feature = "results-db")]
+pub mod results_db;
+#[cfg(feature = "distributed")]
+pub mod distributed;
 
 use anyhow::Result;
 
@@ -0,0 +1,380 @@
+//! Live Tip-Following Differential
+//!
+//! Long-running consensus watchdog: catches BLVM up to Core's tip, then
+//! subscribes to Core's `rawblock` ZMQ notifications and validates every new
+//! block as it arrives, keeping the UTXO set in memory between blocks.
+//!
+//! Core only announces blocks it has connected, so any block BLVM rejects is
+//! a divergence; the run alerts (see `alerts`) and exits non-zero.
+//!
+//! Blocks are connected `confirmations` behind Core's tip. The UTXO stores
+//! can't disconnect blocks, so a reorg deeper than that lag stops the run.
+//! With the default of 0 every block is checked the moment it arrives.
+//!
+//! With `chainstate` set (needs the `chainstate` feature) the UTXO set is
+//! loaded from a copy of Core's chainstate and the run starts at its tip
+//! instead of replaying from genesis.
+//!
+//! Requires Core to run with `-zmqpubrawblock=tcp://127.0.0.1:28332`.
+//!
+//! `run_blk_follow_differential` needs neither ZMQ nor RPC: it reads new blocks
+//! straight from the blk files as Core writes them (see `blk_tail`). Core stores
+//! blocks before it has fully validated them, so a rejection there is only a
+//! divergence once the block is buried; run it with a few `confirmations`.
+//!
+//! `run_p2p_follow_differential` needs no local Core at all: it follows a P2P
+//! peer's block announcements and fetches new blocks as compact blocks, rebuilt
+//! from the transactions the peer relays (see `compact_block`), so a watchdog
+//! left running for weeks costs little more bandwidth than the peer's mempool.
+
+use anyhow::{Context, Result};
+use blvm_consensus::serialization::block::deserialize_block_with_witnesses;
+use blvm_consensus::types::{Network, ValidationResult};
+use blvm_consensus::UtxoSet;
+use sha2::{Digest, Sha256};
+use std::sync::Arc;
+use zeromq::{Socket, SocketRecv, SubSocket};
+
+use crate::alerts::AlertConfig;
+use crate::blk_tail::TailEvent;
+use crate::block_file_reader::BlockFileReader;
+use crate::block_source::BlockSource;
+use crate::core_rpc_client::CoreRpcClient;
+use crate::p2p_client::P2pClient;
+use crate::parallel_differential::{get_block_data, BlockDataSource};
+use crate::utxo_backend::{UtxoBackend, UtxoStore};
+
+/// Live mode configuration
+#[derive(Debug, Clone)]
+pub struct LiveConfig {
+    /// Core's `zmqpubrawblock` endpoint
+    pub zmq_endpoint: String,
+    /// How far behind Core's tip to connect blocks (reorg safety margin)
+    pub confirmations: u64,
+    /// Webhook notified on divergence
+    pub alerts: Option<AlertConfig>,
+    pub utxo_backend: UtxoBackend,
+    /// Copy of Core's chainstate directory to start from (None = genesis)
+    pub chainstate: Option<std::path::PathBuf>,
+}
+
+impl Default for LiveConfig {
+    fn default() -> Self {
+        Self {
+            zmq_endpoint: std::env::var("BLVM_ZMQ_RAWBLOCK").unwrap_or_else(|_| "tcp://127.0.0.1:28332".to_string()),
+            confirmations: 0,
+            alerts: AlertConfig::from_env().ok().flatten(),
+            utxo_backend: UtxoBackend::from_env().unwrap_or_default(),
+            chainstate: None,
+        }
+    }
+}
+
+/// A block Core connected but BLVM rejected
+#[derive(Debug, Clone)]
+pub struct LiveDivergence {
+    pub height: u64,
+    pub hash: String,
+    pub blvm_result: String,
+    /// Header of the rejected block, for the alert
+    pub header: Vec<u8>,
+}
+
+/// BLVM's view of the chain
+struct LiveState {
+    store: Box<dyn UtxoStore>,
+    /// Height of the last connected block (None before genesis)
+    height: Option<u64>,
+    /// Hash of the last connected block, internal byte order
+    tip_hash: [u8; 32],
+}
+
+impl LiveState {
+    fn next_height(&self) -> u64 {
+        self.height.map_or(0, |h| h + 1)
+    }
+
+    /// Connect the next block; Err on a broken chain, Ok(Some) on divergence
+    fn connect(&mut self, block_bytes: &[u8]) -> Result<Option<LiveDivergence>> {
+        let height = self.next_height();
+        if block_bytes.len() < 80 {
+            anyhow::bail!("Block at height {} is too short", height);
+        }
+        if block_bytes[4..36] != self.tip_hash {
+            anyhow::bail!(
+                "Block at height {} doesn't extend BLVM's tip: reorg deeper than the confirmation lag \
+                 (restart with a higher --confirmations)",
+                height
+            );
+        }
+        let mut hash: [u8; 32] = Sha256::digest(Sha256::digest(&block_bytes[..80])).into();
+
+        let (block, witnesses) = deserialize_block_with_witnesses(block_bytes)
+            .map_err(|e| anyhow::anyhow!("Failed to deserialize block at height {}: {}", height, e))?;
+        let result = self.store.connect_block(&block, &witnesses, height, Network::Mainnet);
+        let rejection = match result {
+            Ok(ValidationResult::Valid) => None,
+            Ok(ValidationResult::Invalid(msg)) => Some(format!("Invalid({})", msg)),
+            Err(e) => Some(format!("Invalid({:?})", e)),
+        };
+
+        if let Some(blvm_result) = rejection {
+            hash.reverse();
+            return Ok(Some(LiveDivergence {
+                height,
+                hash: hex::encode(hash),
+                blvm_result,
+                header: block_bytes[..80].to_vec(),
+            }));
+        }
+        self.height = Some(height);
+        self.tip_hash = hash;
+        Ok(None)
+    }
+}
+
+/// Report a divergence and turn it into the run's error
+async fn diverged(config: &LiveConfig, divergence: LiveDivergence) -> anyhow::Error {
+    eprintln!(
+        "❌ DIVERGENCE at height {} ({}): BLVM={}, Core=Valid",
+        divergence.height, divergence.hash, divergence.blvm_result
+    );
+    crate::alerts::notify_divergence(
+        config.alerts.as_ref(),
+        divergence.height,
+        &divergence.header,
+        &divergence.blvm_result,
+        "Valid",
+    )
+    .await;
+    anyhow::anyhow!("Consensus divergence at height {}", divergence.height)
+}
+
+/// Connect blocks by height from Core up to `target`
+async fn catch_up_rpc(client: &CoreRpcClient, state: &mut LiveState, target: u64, config: &LiveConfig) -> Result<()> {
+    while state.next_height() <= target {
+        let height = state.next_height();
+        let hash = client.getblockhash(height).await?;
+        let block_bytes = hex::decode(client.getblock_raw(&hash).await?)?;
+        if let Some(divergence) = state.connect(&block_bytes)? {
+            return Err(diverged(config, divergence).await);
+        }
+        println!("✅ Block {} ({}) matches Core", height, hash);
+    }
+    Ok(())
+}
+
+/// Start at the tip of a chainstate copy instead of genesis
+#[cfg(feature = "chainstate")]
+async fn state_from_chainstate(client: &CoreRpcClient, dir: &std::path::Path, config: &LiveConfig) -> Result<LiveState> {
+    println!("📥 Loading UTXO set from chainstate {}", dir.display());
+    let mut reader = crate::chainstate_reader::ChainstateReader::open(dir)?;
+    let tip_hash = reader.best_block()?;
+    let utxo_set = reader.read_utxo_set()?;
+
+    let mut display_hash = tip_hash;
+    display_hash.reverse();
+    let header = client.getblockheader(&hex::encode(display_hash)).await?;
+    let height = header["height"]
+        .as_u64()
+        .context("Core doesn't know the chainstate's best block")?;
+    println!("   ✅ {} coins at height {} ({})", utxo_set.len(), height, hex::encode(display_hash));
+    Ok(LiveState {
+        store: config.utxo_backend.create(utxo_set)?,
+        height: Some(height),
+        tip_hash,
+    })
+}
+
+#[cfg(not(feature = "chainstate"))]
+async fn state_from_chainstate(_client: &CoreRpcClient, _dir: &std::path::Path, _config: &LiveConfig) -> Result<LiveState> {
+    anyhow::bail!("Starting from a chainstate requires building with --features chainstate")
+}
+
+/// Initial sync from the (fast) block data source, genesis to `target`
+async fn sync_from_genesis(state: &mut LiveState, source: &BlockDataSource, target: u64, config: &LiveConfig) -> Result<()> {
+    println!("⏩ Catching up to height {} before following the tip", target);
+    let mut sync_block = |block_bytes: &[u8]| -> Result<Option<LiveDivergence>> {
+        let divergence = state.connect(block_bytes)?;
+        if let Some(height) = state.height.filter(|h| h % 10_000 == 0) {
+            println!("   📊 Height {} ({} coins)", height, state.store.len());
+        }
+        Ok(divergence)
+    };
+    let mut divergence = None;
+    match source.iter_sequential(0, target as usize + 1)? {
+        Some(blocks) => {
+            for block in blocks {
+                divergence = sync_block(&block?)?;
+                if divergence.is_some() {
+                    break;
+                }
+            }
+        }
+        None => {
+            for height in 0..=target {
+                divergence = sync_block(&get_block_data(source, height).await?)?;
+                if divergence.is_some() {
+                    break;
+                }
+            }
+        }
+    }
+    match divergence {
+        Some(divergence) => Err(diverged(config, divergence).await),
+        None => Ok(()),
+    }
+}
+
+/// Follow Core's tip forever; returns only on divergence or error
+pub async fn run_live_differential(
+    client: Arc<CoreRpcClient>,
+    source: &BlockDataSource,
+    config: LiveConfig,
+) -> Result<()> {
+    // Subscribe first so nothing mined during the initial sync is missed
+    let mut socket = SubSocket::new();
+    socket
+        .connect(&config.zmq_endpoint)
+        .await
+        .with_context(|| format!("Failed to connect to ZMQ endpoint {} (is -zmqpubrawblock set?)", config.zmq_endpoint))?;
+    socket.subscribe("rawblock").await?;
+    println!("📡 Subscribed to rawblock at {}", config.zmq_endpoint);
+
+    let mut state = match &config.chainstate {
+        Some(dir) => state_from_chainstate(&client, dir, &config).await?,
+        None => LiveState {
+            store: config.utxo_backend.create(UtxoSet::new())?,
+            height: None,
+            tip_hash: [0u8; 32],
+        },
+    };
+
+    // Initial sync from the (fast) block data source
+    let target = client.getblockcount().await?.saturating_sub(config.confirmations);
+    if let Some(height) = state.height {
+        // Seeded from a chainstate: only the blocks Core connected since
+        println!("⏩ Catching up from height {} to {} before following the tip", height, target);
+        catch_up_rpc(&client, &mut state, target, &config).await?;
+    } else {
+        sync_from_genesis(&mut state, source, target, &config).await?;
+    }
+    println!("✅ Caught up at height {}; following the tip", target);
+
+    loop {
+        let message = socket.recv().await.context("ZMQ receive failed")?;
+        let parts = message.into_vec();
+        if parts.len() < 2 || parts[0].as_ref() != b"rawblock" {
+            continue;
+        }
+        let block_bytes = parts[1].as_ref();
+
+        let target = client.getblockcount().await?.saturating_sub(config.confirmations);
+        // Fast path: the announced block is exactly the next one - use it as delivered
+        if config.confirmations == 0 && block_bytes.len() >= 80 && block_bytes[4..36] == state.tip_hash {
+            let height = state.next_height();
+            if let Some(divergence) = state.connect(block_bytes)? {
+                return Err(diverged(&config, divergence).await);
+            }
+            println!("✅ Block {} matches Core ({} coins)", height, state.store.len());
+        }
+        // Anything missed (or lagging by `confirmations`) comes from RPC by height
+        catch_up_rpc(&client, &mut state, target, &config).await?;
+    }
+}
+
+/// Follow the blk files instead of ZMQ and RPC: validate the best chain from
+/// genesis, then every block Core stores, `confirmations` behind the tip
+pub async fn run_blk_follow_differential(reader: &BlockFileReader, config: LiveConfig) -> Result<()> {
+    if config.chainstate.is_some() {
+        anyhow::bail!("Starting from a chainstate needs RPC to find its height; drop --chainstate to follow blk files");
+    }
+    let mut state = LiveState {
+        store: config.utxo_backend.create(UtxoSet::new())?,
+        height: None,
+        tip_hash: [0u8; 32],
+    };
+
+    // Blocks above this were stored after the run started: report each one
+    let live_from = reader.tip_height()?;
+    // The follower blocks between polls, so it runs on its own thread
+    let followed = reader.follow(0)?;
+    let (tx, mut rx) = tokio::sync::mpsc::channel::<Result<TailEvent>>(64);
+    std::thread::spawn(move || {
+        for event in followed {
+            let failed = event.is_err();
+            if tx.blocking_send(event).is_err() || failed {
+                break;
+            }
+        }
+    });
+    println!("⏩ Validating the blk files' best chain, then following new blocks ({} confirmation(s) behind)", config.confirmations);
+
+    // Blocks not yet buried deep enough to connect
+    let mut pending: std::collections::VecDeque<(u64, Vec<u8>)> = std::collections::VecDeque::new();
+    while let Some(event) = rx.recv().await {
+        match event? {
+            TailEvent::Block { height, bytes } => pending.push_back((height, bytes)),
+            TailEvent::Reorg { fork_height } => {
+                if state.height.is_some_and(|h| h > fork_height) {
+                    anyhow::bail!(
+                        "Reorg to height {} is deeper than the confirmation lag (restart with a higher --confirmations)",
+                        fork_height
+                    );
+                }
+                pending.retain(|(height, _)| *height <= fork_height);
+                continue;
+            }
+        }
+        while pending.len() as u64 > config.confirmations {
+            let (height, bytes) = pending.pop_front().expect("pending is non-empty");
+            if let Some(divergence) = state.connect(&bytes)? {
+                return Err(diverged(&config, divergence).await);
+            }
+            if height > live_from {
+                println!("✅ Block {} valid ({} coins)", height, state.store.len());
+            } else if height % 10_000 == 0 {
+                println!("   📊 Height {} ({} coins)", height, state.store.len());
+            }
+        }
+    }
+    anyhow::bail!("Block file follower stopped")
+}
+
+/// Follow a P2P peer's tip: sync from `source`, then fetch every block the
+/// peer announces, `confirmations` behind its tip (compact blocks when the
+/// client has `compact_blocks` set)
+pub async fn run_p2p_follow_differential(client: &P2pClient, source: &BlockDataSource, config: LiveConfig) -> Result<()> {
+    /// How long to wait for an announcement before asking for headers anyway
+    const POLL: std::time::Duration = std::time::Duration::from_secs(60);
+
+    if config.chainstate.is_some() {
+        anyhow::bail!("Starting from a chainstate needs RPC to find its height; drop --chainstate to follow a P2P peer");
+    }
+    let mut state = LiveState {
+        store: config.utxo_backend.create(UtxoSet::new())?,
+        height: None,
+        tip_hash: [0u8; 32],
+    };
+
+    let target = client.sync_headers().await?.saturating_sub(config.confirmations);
+    sync_from_genesis(&mut state, source, target, &config).await?;
+    println!("✅ Caught up at height {}; following peer announcements", target);
+
+    loop {
+        if let Err(e) = client.wait_for_announcement(POLL).await {
+            // The next header sync reconnects
+            eprintln!("⚠️  Lost the peer ({:#}); reconnecting", e);
+            tokio::time::sleep(std::time::Duration::from_secs(5)).await;
+        }
+        let target = client.sync_headers().await?.saturating_sub(config.confirmations);
+        while state.next_height() <= target {
+            let height = state.next_height();
+            let block_bytes = client.get_block(height).await?;
+            if let Some(divergence) = state.connect(&block_bytes)? {
+                return Err(diverged(&config, divergence).await);
+            }
+            println!("✅ Block {} valid ({} coins)", height, state.store.len());
+        }
+    }
+}
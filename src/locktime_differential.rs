@@ -0,0 +1,562 @@
+//! Median-Time-Past and Lock-Time Differential
+//!
+//! BIP113 made nLockTime compare against the median of the previous 11 block
+//! times instead of the block's own timestamp, and BIP68 added relative locks
+//! measured from each spent coin's height or MTP. Off-by-one errors at those
+//! boundaries (`<` vs `<=`, which block's MTP, the 512-second granularity) are
+//! a classic consensus trap that a replay of the main chain only hits by luck.
+//!
+//! The mainnet walk checks every block:
+//! - BLVM's MTP of the previous 11 headers against Core's parent `mediantime`
+//! - every transaction's nLockTime and sequence locks, evaluated with Core's
+//!   rules on Core's values (parent `mediantime`, prevout heights from
+//!   `getblock` verbosity 3), counting locks that sit right on a boundary
+//! - BLVM's `connect_block` verdict, given the recent headers, on every block
+//!   with a lock in force
+//!
+//! The regtest scenarios sign transactions exactly at and one past each
+//! boundary, add each to a freshly mined block and compare BLVM's verdict with
+//! Core's proposal-mode verdict.
+
+use anyhow::{Context, Result};
+use blvm_consensus::bip113::get_median_time_past;
+use blvm_consensus::block::connect_block;
+use blvm_consensus::constants::CSV_ACTIVATION_MAINNET;
+use blvm_consensus::serialization::block::deserialize_block_with_witnesses;
+use blvm_consensus::types::{Network, ValidationResult};
+use blvm_consensus::{BlockHeader, OutPoint, UtxoSet, UTXO};
+use std::collections::{HashMap, VecDeque};
+use std::time::Instant;
+
+use crate::core_rpc_client::{BitcoinNetwork, CoreRpcClient};
+use crate::mutation_differential::{append_tx, core_verdict, rewind_to_parent, CoreVerdict, RawTx};
+
+/// nLockTime values below this are heights, above are UNIX times
+const LOCKTIME_THRESHOLD: u32 = 500_000_000;
+const SEQUENCE_FINAL: u32 = 0xffff_ffff;
+/// BIP68: relative lock disabled for this input
+const SEQUENCE_LOCKTIME_DISABLE_FLAG: u32 = 1 << 31;
+/// BIP68: relative lock is in units of 512 seconds rather than blocks
+const SEQUENCE_LOCKTIME_TYPE_FLAG: u32 = 1 << 22;
+const SEQUENCE_LOCKTIME_MASK: u32 = 0x0000_ffff;
+const SEQUENCE_LOCKTIME_GRANULARITY: u32 = 9;
+/// Number of previous timestamps in the median time past
+const MTP_WINDOW: usize = 11;
+/// A lock with at most this much slack counts as on the boundary
+const BOUNDARY_BLOCKS: i64 = 1;
+const BOUNDARY_SECS: i64 = 600;
+/// Stop collecting failure and boundary details after this many
+const MAX_REPORTED: usize = 100;
+
+/// Unit a lock is measured in
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LockKind {
+    Height,
+    Time,
+}
+
+/// A lock in force, and how much it could tighten and still be met (negative = unmet)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LockCheck {
+    pub kind: LockKind,
+    pub slack: i64,
+}
+
+impl LockCheck {
+    pub fn satisfied(&self) -> bool {
+        self.slack >= 0
+    }
+
+    pub fn on_boundary(&self) -> bool {
+        let limit = match self.kind {
+            LockKind::Height => BOUNDARY_BLOCKS,
+            LockKind::Time => BOUNDARY_SECS,
+        };
+        self.satisfied() && self.slack < limit
+    }
+}
+
+/// nLockTime of a transaction in the block at `height` (None if no lock is in force)
+///
+/// `cutoff_time` is the parent's MTP once BIP113 is active, the block's own time before.
+pub fn absolute_lock(lock_time: u32, sequences: &[u32], height: u64, cutoff_time: u64) -> Option<LockCheck> {
+    if lock_time == 0 || sequences.iter().all(|s| *s == SEQUENCE_FINAL) {
+        return None;
+    }
+    // Final iff lock_time < height (or < cutoff_time for time locks)
+    Some(if lock_time < LOCKTIME_THRESHOLD {
+        LockCheck {
+            kind: LockKind::Height,
+            slack: height as i64 - 1 - lock_time as i64,
+        }
+    } else {
+        LockCheck {
+            kind: LockKind::Time,
+            slack: cutoff_time as i64 - 1 - lock_time as i64,
+        }
+    })
+}
+
+/// BIP68 lock of one input (None if disabled); only applies to version >= 2 transactions
+///
+/// `coin_mtp` is the MTP of the block before the one that created the coin and
+/// `prev_mtp` the MTP of the spending block's parent.
+pub fn relative_lock(sequence: u32, coin_height: u64, coin_mtp: u64, height: u64, prev_mtp: u64) -> Option<LockCheck> {
+    if sequence & SEQUENCE_LOCKTIME_DISABLE_FLAG != 0 {
+        return None;
+    }
+    let value = (sequence & SEQUENCE_LOCKTIME_MASK) as i64;
+    Some(if sequence & SEQUENCE_LOCKTIME_TYPE_FLAG != 0 {
+        // Met iff coin_mtp + (value << 9) - 1 < prev_mtp
+        LockCheck {
+            kind: LockKind::Time,
+            slack: prev_mtp as i64 - coin_mtp as i64 - (value << SEQUENCE_LOCKTIME_GRANULARITY),
+        }
+    } else {
+        // Met iff coin_height + value - 1 < height
+        LockCheck {
+            kind: LockKind::Height,
+            slack: height as i64 - coin_height as i64 - value,
+        }
+    })
+}
+
+/// A rule that failed at some height
+#[derive(Debug, Clone)]
+pub struct LocktimeFailure {
+    pub height: u64,
+    pub rule: &'static str,
+    pub detail: String,
+}
+
+/// A lock met with (almost) no slack
+#[derive(Debug, Clone)]
+pub struct BoundaryLock {
+    pub height: u64,
+    pub txid: String,
+    pub rule: &'static str,
+    pub kind: LockKind,
+    pub slack: i64,
+}
+
+/// Result of a mainnet lock-time run
+#[derive(Debug, Default)]
+pub struct LocktimeDiffReport {
+    pub blocks_checked: u64,
+    pub txs_checked: u64,
+    pub absolute_locks: u64,
+    pub relative_locks: u64,
+    /// Blocks BLVM connected because a lock was in force
+    pub blocks_connected: u64,
+    pub boundary_count: u64,
+    /// First `MAX_REPORTED` boundary locks, in height order
+    pub boundary: Vec<BoundaryLock>,
+    pub failure_count: u64,
+    /// First `MAX_REPORTED` failures, in height order
+    pub failures: Vec<LocktimeFailure>,
+    pub elapsed_secs: f64,
+}
+
+impl LocktimeDiffReport {
+    fn fail(&mut self, height: u64, rule: &'static str, detail: String) {
+        self.failure_count += 1;
+        if self.failures.len() < MAX_REPORTED {
+            eprintln!("❌ Block {} failed {}: {}", height, rule, detail);
+            self.failures.push(LocktimeFailure { height, rule, detail });
+        }
+    }
+
+    fn record(&mut self, height: u64, txid: &str, rule: &'static str, check: LockCheck) {
+        if !check.satisfied() {
+            self.fail(
+                height,
+                rule,
+                format!("tx {} is in Core's chain but its {:?} lock is unmet by {}", txid, check.kind, -check.slack),
+            );
+        } else if check.on_boundary() {
+            self.boundary_count += 1;
+            if self.boundary.len() < MAX_REPORTED {
+                self.boundary.push(BoundaryLock {
+                    height,
+                    txid: txid.to_string(),
+                    rule,
+                    kind: check.kind,
+                    slack: check.slack,
+                });
+            }
+        }
+    }
+}
+
+/// Header of the block at `height`, as BLVM decodes it
+async fn fetch_header(client: &CoreRpcClient, height: u64) -> Result<BlockHeader> {
+    let hash = client.getblockhash(height).await?;
+    let bytes = hex::decode(client.getblock_raw(&hash).await?)?;
+    let (block, _) = deserialize_block_with_witnesses(&bytes)
+        .map_err(|e| anyhow::anyhow!("Failed to deserialize block {}: {}", height, e))?;
+    Ok(block.header)
+}
+
+/// Core's `mediantime` of the block at `height`, cached
+async fn core_mtp(client: &CoreRpcClient, height: u64, cache: &mut HashMap<u64, u64>) -> Result<u64> {
+    if let Some(mtp) = cache.get(&height) {
+        return Ok(*mtp);
+    }
+    let header = client.getblockheader(&client.getblockhash(height).await?).await?;
+    let mtp = header
+        .get("mediantime")
+        .and_then(|m| m.as_u64())
+        .context("header missing mediantime")?;
+    cache.insert(height, mtp);
+    Ok(mtp)
+}
+
+/// BLVM's verdict on a block, with the recent headers it needs for MTP
+fn blvm_verdict(
+    block_bytes: &[u8],
+    pre_state: UtxoSet,
+    height: u64,
+    recent_headers: &[BlockHeader],
+    network: Network,
+) -> Result<(), String> {
+    let (block, witnesses) =
+        deserialize_block_with_witnesses(block_bytes).map_err(|e| format!("deserialize: {}", e))?;
+    match connect_block(&block, &witnesses, pre_state, height, Some(recent_headers), network) {
+        Ok((ValidationResult::Valid, _, _)) => Ok(()),
+        Ok((ValidationResult::Invalid(msg), _, _)) => Err(msg),
+        Err(e) => Err(format!("{:?}", e)),
+    }
+}
+
+/// Check MTP and every transaction's locks from `start_height` to `end_height` (Core's tip if None)
+pub async fn run_locktime_differential(
+    client: &CoreRpcClient,
+    start_height: u64,
+    end_height: Option<u64>,
+) -> Result<LocktimeDiffReport> {
+    let tip = client.getblockcount().await?;
+    let end_height = end_height.unwrap_or(tip).min(tip);
+    println!("⏳ Lock-time differential: blocks {}..={}", start_height, end_height);
+
+    let start = Instant::now();
+    let mut report = LocktimeDiffReport::default();
+    let mut mtp_cache: HashMap<u64, u64> = HashMap::new();
+    let mut window: VecDeque<BlockHeader> = VecDeque::with_capacity(MTP_WINDOW);
+    for height in start_height.saturating_sub(MTP_WINDOW as u64)..start_height {
+        window.push_back(fetch_header(client, height).await?);
+    }
+    let mut prev_mtp = match start_height.checked_sub(1) {
+        Some(parent) => Some(core_mtp(client, parent, &mut mtp_cache).await?),
+        None => None,
+    };
+
+    for height in start_height..=end_height {
+        let hash = client.getblockhash(height).await?;
+        let block_bytes = hex::decode(client.getblock_raw(&hash).await?)?;
+        let verbose = client
+            .getblock(&hash, 3)
+            .await
+            .context("getblock verbosity 3 requires Bitcoin Core 23.0+")?;
+        let (block, _) = deserialize_block_with_witnesses(&block_bytes)
+            .map_err(|e| anyhow::anyhow!("Failed to deserialize block {}: {}", height, e))?;
+
+        // MTP of the parent: BLVM over the last 11 headers vs Core
+        if let Some(core) = prev_mtp.filter(|_| !window.is_empty()) {
+            let blvm = get_median_time_past(window.make_contiguous());
+            if blvm != core {
+                report.fail(height, "mtp", format!("BLVM {} vs Core {}", blvm, core));
+            }
+        }
+
+        let block_time = verbose.get("time").and_then(|t| t.as_u64()).context("block missing time")?;
+        let bip113 = height >= CSV_ACTIVATION_MAINNET;
+        let cutoff_time = if bip113 { prev_mtp.unwrap_or(block_time) } else { block_time };
+        let txs = verbose.get("tx").and_then(|t| t.as_array()).context("block missing tx array")?;
+        let mut lock_in_force = false;
+        for tx in txs.iter().skip(1) {
+            let txid = tx.get("txid").and_then(|t| t.as_str()).unwrap_or("?");
+            let vins = tx.get("vin").and_then(|v| v.as_array()).context("tx missing vin")?;
+            let sequences: Vec<u32> = vins
+                .iter()
+                .map(|vin| vin.get("sequence").and_then(|s| s.as_u64()).unwrap_or(0) as u32)
+                .collect();
+            let lock_time = tx.get("locktime").and_then(|l| l.as_u64()).unwrap_or(0) as u32;
+            report.txs_checked += 1;
+
+            if let Some(check) = absolute_lock(lock_time, &sequences, height, cutoff_time) {
+                lock_in_force = true;
+                report.absolute_locks += 1;
+                report.record(height, txid, "locktime", check);
+            }
+
+            let version = tx.get("version").and_then(|v| v.as_i64()).unwrap_or(1) as u32;
+            if !bip113 || version < 2 {
+                continue;
+            }
+            for (vin, sequence) in vins.iter().zip(&sequences) {
+                if sequence & SEQUENCE_LOCKTIME_DISABLE_FLAG != 0 {
+                    continue;
+                }
+                let coin_height = vin
+                    .get("prevout")
+                    .and_then(|p| p.get("height"))
+                    .and_then(|h| h.as_u64())
+                    .context("vin missing prevout height (need verbosity 3)")?;
+                let coin_mtp = if sequence & SEQUENCE_LOCKTIME_TYPE_FLAG != 0 {
+                    core_mtp(client, coin_height.saturating_sub(1), &mut mtp_cache).await?
+                } else {
+                    0
+                };
+                let parent_mtp = prev_mtp.unwrap_or(block_time);
+                if let Some(check) = relative_lock(*sequence, coin_height, coin_mtp, height, parent_mtp) {
+                    lock_in_force = true;
+                    report.relative_locks += 1;
+                    report.record(height, txid, "sequence", check);
+                }
+            }
+        }
+
+        // Core connected this block; with a lock in force BLVM must too
+        if lock_in_force {
+            let pre_state = crate::block_fixtures::pre_state_from_verbose(&verbose)?;
+            if let Err(reason) =
+                blvm_verdict(&block_bytes, pre_state, height, window.make_contiguous(), Network::Mainnet)
+            {
+                report.fail(height, "connect_block", format!("BLVM rejects Core's block: {}", reason));
+            }
+            report.blocks_connected += 1;
+        }
+
+        if window.len() == MTP_WINDOW {
+            window.pop_front();
+        }
+        window.push_back(block.header);
+        let mediantime = verbose.get("mediantime").and_then(|m| m.as_u64()).context("block missing mediantime")?;
+        mtp_cache.insert(height, mediantime);
+        prev_mtp = Some(mediantime);
+        report.blocks_checked += 1;
+        if report.blocks_checked % 10_000 == 0 {
+            println!(
+                "   {} blocks checked ({:.0}/s), {} locks on a boundary",
+                report.blocks_checked,
+                report.blocks_checked as f64 / start.elapsed().as_secs_f64(),
+                report.boundary_count
+            );
+        }
+    }
+
+    report.elapsed_secs = start.elapsed().as_secs_f64();
+    println!(
+        "{} {} blocks / {} txs checked in {:.1}s: {} absolute and {} relative locks, {} on a boundary, {} failures",
+        if report.failure_count == 0 { "✅" } else { "❌" },
+        report.blocks_checked,
+        report.txs_checked,
+        report.elapsed_secs,
+        report.absolute_locks,
+        report.relative_locks,
+        report.boundary_count,
+        report.failure_count
+    );
+    for lock in report.boundary.iter().take(10) {
+        println!(
+            "   🎯 {} tx {} ({} {:?}, slack {})",
+            lock.height, lock.txid, lock.rule, lock.kind, lock.slack
+        );
+    }
+    if let Some(first) = report.failures.first() {
+        println!("   First failure: height {} ({}) - {}", first.height, first.rule, first.detail);
+    }
+    Ok(report)
+}
+
+// ---------------------------------------------------------------------------
+// Regtest boundary scenarios
+// ---------------------------------------------------------------------------
+
+/// One boundary transaction and both verdicts on a block containing it
+#[derive(Debug, Clone)]
+pub struct ScenarioOutcome {
+    pub name: &'static str,
+    pub expect_valid: bool,
+    /// BLVM's rejection reason (None = accepted)
+    pub blvm_rejection: Option<String>,
+    pub core: CoreVerdict,
+}
+
+impl ScenarioOutcome {
+    /// Both sides gave the verdict the rules call for
+    pub fn consistent(&self) -> bool {
+        let core_valid = matches!(self.core, CoreVerdict::Accepted);
+        core_valid == self.expect_valid && self.blvm_rejection.is_none() == self.expect_valid
+    }
+
+    fn print(&self) {
+        let blvm = self.blvm_rejection.as_deref().unwrap_or("accepted");
+        let core = match &self.core {
+            CoreVerdict::Accepted => "accepted",
+            CoreVerdict::Rejected(reason) => reason,
+            CoreVerdict::NotChecked => "not checked",
+        };
+        let icon = if self.consistent() { "✅" } else { "❌" };
+        let expected = if self.expect_valid { "valid" } else { "invalid" };
+        println!("{} {:<28} expected {}: BLVM={}, Core={}", icon, self.name, expected, blvm, core);
+    }
+}
+
+/// Result of the regtest scenarios
+#[derive(Debug)]
+pub struct ScenarioReport {
+    /// MTP of the block the scenarios build on
+    pub blvm_mtp: u64,
+    pub core_mtp: u64,
+    pub outcomes: Vec<ScenarioOutcome>,
+}
+
+impl ScenarioReport {
+    pub fn failures(&self) -> usize {
+        self.outcomes.iter().filter(|o| !o.consistent()).count() + usize::from(self.blvm_mtp != self.core_mtp)
+    }
+}
+
+/// Sign transactions at and one past every lock boundary and diff blocks containing them
+pub async fn run_locktime_scenarios(client: &CoreRpcClient) -> Result<ScenarioReport> {
+    if client.detect_network().await? != BitcoinNetwork::Regtest {
+        anyhow::bail!("Lock-time scenarios need a regtest node (they mine and invalidate blocks)");
+    }
+
+    let address = client.getnewaddress().await?;
+    if client.getblockcount().await? < 101 {
+        client.generatetoaddress(101, &address).await?;
+    }
+
+    // One confirmed 1 BTC coin, spent by every scenario (none of them is mined)
+    let funding_txid = client.sendtoaddress(&address, 1.0).await?;
+    let funding = RawTx::from_bytes(&hex::decode(client.getrawtransaction(&funding_txid).await?)?)?;
+    let (vout, script_pubkey) = funding
+        .outputs
+        .iter()
+        .enumerate()
+        .find(|(_, (value, _))| *value == 100_000_000)
+        .map(|(vout, (_, script))| (vout as u32, script.clone()))
+        .context("Funding transaction has no 1 BTC output")?;
+    client.generatetoaddress(1, &address).await?;
+    let coin_height = client.getblockcount().await?;
+    // A few confirmations so relative height locks have room on both sides
+    client.generatetoaddress(5, &address).await?;
+
+    // The block every scenario transaction is added to; its parent becomes the tip
+    client.generatetoaddress(1, &address).await?;
+    let height = client.getblockcount().await?;
+    let host_bytes = hex::decode(client.getblock_raw(&client.getblockhash(height).await?).await?)?;
+    let rewound = rewind_to_parent(client, &host_bytes).await?;
+
+    let mut mtp_cache = HashMap::new();
+    let core_mtp_tip = core_mtp(client, height - 1, &mut mtp_cache).await?;
+    let coin_mtp = core_mtp(client, coin_height - 1, &mut mtp_cache).await?;
+    let mut headers = Vec::with_capacity(MTP_WINDOW);
+    for h in height.saturating_sub(MTP_WINDOW as u64)..height {
+        headers.push(fetch_header(client, h).await?);
+    }
+    let blvm_mtp = get_median_time_past(&headers);
+    if blvm_mtp != core_mtp_tip {
+        eprintln!("❌ MTP of block {}: BLVM {} vs Core {}", height - 1, blvm_mtp, core_mtp_tip);
+    }
+
+    let mut funding_hash: [u8; 32] = hex::decode(&funding_txid)?
+        .try_into()
+        .map_err(|_| anyhow::anyhow!("Invalid txid length"))?;
+    funding_hash.reverse();
+    let mut pre_state = UtxoSet::new();
+    pre_state.insert(
+        OutPoint {
+            hash: funding_hash,
+            index: vout as _,
+        },
+        UTXO {
+            value: 100_000_000,
+            script_pubkey: script_pubkey.into(),
+            height: coin_height as _,
+            is_coinbase: false,
+        },
+    );
+
+    let height_age = (height - coin_height) as u32;
+    let time_units = (core_mtp_tip.saturating_sub(coin_mtp) >> SEQUENCE_LOCKTIME_GRANULARITY) as u32;
+    let non_final = SEQUENCE_FINAL - 1;
+    let mtp = core_mtp_tip as u32;
+    // (name, nLockTime, nSequence, expected valid)
+    let scenarios: Vec<(&'static str, u32, u32, bool)> = vec![
+        ("height-lock-at-boundary", height as u32 - 1, non_final, true),
+        ("height-lock-past-boundary", height as u32, non_final, false),
+        ("time-lock-below-mtp", mtp - 1, non_final, true),
+        // Pre-BIP113 rules (block time) would accept this one
+        ("time-lock-equal-mtp", mtp, non_final, false),
+        ("time-lock-final-sequence", mtp, SEQUENCE_FINAL, true),
+        ("relative-height-at-boundary", 0, height_age, true),
+        ("relative-height-past-boundary", 0, height_age + 1, false),
+        ("relative-time-at-boundary", 0, SEQUENCE_LOCKTIME_TYPE_FLAG | time_units, true),
+        ("relative-time-past-boundary", 0, SEQUENCE_LOCKTIME_TYPE_FLAG | (time_units + 1), false),
+    ];
+
+    let mut outcomes = Vec::new();
+    for (name, lock_time, sequence, expect_valid) in scenarios {
+        let unsigned = client
+            .createrawtransaction(&[(&funding_txid, vout, sequence)], &[(&address, 0.9999)], lock_time)
+            .await?;
+        let tx_bytes = hex::decode(client.signrawtransactionwithwallet(&unsigned).await?)?;
+        if name.starts_with("relative") && RawTx::from_bytes(&tx_bytes)?.version < 2 {
+            println!("⏭️  {}: wallet builds version 1 transactions (no BIP68)", name);
+            continue;
+        }
+        let block_bytes = append_tx(&host_bytes, &tx_bytes, true)?;
+        let outcome = ScenarioOutcome {
+            name,
+            expect_valid,
+            blvm_rejection: blvm_verdict(&block_bytes, pre_state.clone(), height, &headers, Network::Regtest).err(),
+            core: core_verdict(client, &block_bytes).await?,
+        };
+        outcome.print();
+        outcomes.push(outcome);
+    }
+
+    if let Some(hash) = rewound {
+        client.reconsiderblock(&hash).await?;
+    }
+    Ok(ScenarioReport {
+        blvm_mtp,
+        core_mtp: core_mtp_tip,
+        outcomes,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_absolute_lock_boundaries() {
+        let non_final = [SEQUENCE_FINAL - 1];
+        // Height locks: lock_time < height
+        assert_eq!(absolute_lock(99, &non_final, 100, 0).unwrap().slack, 0);
+        assert!(!absolute_lock(100, &non_final, 100, 0).unwrap().satisfied());
+        // Time locks compare against the cutoff (MTP), strictly
+        let t = 1_600_000_000;
+        assert!(absolute_lock(t - 1, &non_final, 100, t as u64).unwrap().on_boundary());
+        assert!(!absolute_lock(t, &non_final, 100, t as u64).unwrap().satisfied());
+        // No lock in force
+        assert_eq!(absolute_lock(t, &[SEQUENCE_FINAL], 100, 0), None);
+        assert_eq!(absolute_lock(0, &non_final, 100, 0), None);
+    }
+
+    #[test]
+    fn test_relative_lock_boundaries() {
+        // Coin at 100, 10-block lock: spendable from height 110
+        assert_eq!(relative_lock(10, 100, 0, 110, 0).unwrap().slack, 0);
+        assert!(!relative_lock(10, 100, 0, 109, 0).unwrap().satisfied());
+        // 2 units of 512s: spendable once the parent's MTP reaches coin_mtp + 1024
+        let time = SEQUENCE_LOCKTIME_TYPE_FLAG | 2;
+        assert_eq!(relative_lock(time, 100, 5_000, 200, 6_024).unwrap().slack, 0);
+        assert!(!relative_lock(time, 100, 5_000, 200, 6_023).unwrap().satisfied());
+        assert_eq!(relative_lock(SEQUENCE_LOCKTIME_DISABLE_FLAG | 10, 100, 0, 101, 0), None);
+    }
+}
@@ -0,0 +1,199 @@
+//! Memory Profiling for Differential Runs
+//!
+//! OOM kills leave nothing behind, so every chunk records the process's
+//! current and peak RSS (from `/proc/self/status`) when it finishes. With the
+//! `mem-profile` feature a tracking global allocator is also installed: it
+//! keeps the live/peak heap size and per-thread allocation counters, which
+//! `AllocMeter` uses to attribute the bytes and allocations made inside
+//! `connect_block` to the chunk that ran it.
+//!
+//! Without the feature the allocator fields stay `None` and the default
+//! system allocator is untouched.
+
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// Value of a `kB` line in /proc/self/status, in bytes
+fn proc_status_bytes(field: &str) -> Option<u64> {
+    let status = std::fs::read_to_string("/proc/self/status").ok()?;
+    status
+        .lines()
+        .find(|l| l.starts_with(field))
+        .and_then(|l| l.split_whitespace().nth(1))
+        .and_then(|kb| kb.parse::<u64>().ok())
+        .map(|kb| kb * 1024)
+}
+
+/// Resident set size now (Linux only)
+pub fn rss_bytes() -> Option<u64> {
+    proc_status_bytes("VmRSS:")
+}
+
+/// Highest resident set size so far (Linux only)
+pub fn peak_rss_bytes() -> Option<u64> {
+    proc_status_bytes("VmHWM:")
+}
+
+#[cfg(feature = "mem-profile")]
+mod tracking {
+    use std::alloc::{GlobalAlloc, Layout, System};
+    use std::cell::Cell;
+    use std::sync::atomic::{AtomicU64, Ordering};
+
+    pub static LIVE: AtomicU64 = AtomicU64::new(0);
+    pub static PEAK: AtomicU64 = AtomicU64::new(0);
+
+    thread_local! {
+        pub static THREAD_BYTES: Cell<u64> = const { Cell::new(0) };
+        pub static THREAD_ALLOCS: Cell<u64> = const { Cell::new(0) };
+    }
+
+    /// System allocator plus live/peak and per-thread counters
+    pub struct TrackingAllocator;
+
+    fn on_alloc(size: u64) {
+        let live = LIVE.fetch_add(size, Ordering::Relaxed) + size;
+        PEAK.fetch_max(live, Ordering::Relaxed);
+        let _ = THREAD_BYTES.try_with(|b| b.set(b.get() + size));
+        let _ = THREAD_ALLOCS.try_with(|c| c.set(c.get() + 1));
+    }
+
+    unsafe impl GlobalAlloc for TrackingAllocator {
+        unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+            let ptr = System.alloc(layout);
+            if !ptr.is_null() {
+                on_alloc(layout.size() as u64);
+            }
+            ptr
+        }
+
+        unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+            let ptr = System.alloc_zeroed(layout);
+            if !ptr.is_null() {
+                on_alloc(layout.size() as u64);
+            }
+            ptr
+        }
+
+        unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+            System.dealloc(ptr, layout);
+            LIVE.fetch_sub(layout.size() as u64, Ordering::Relaxed);
+        }
+
+        unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+            let new_ptr = System.realloc(ptr, layout, new_size);
+            if !new_ptr.is_null() {
+                LIVE.fetch_sub(layout.size() as u64, Ordering::Relaxed);
+                on_alloc(new_size as u64);
+            }
+            new_ptr
+        }
+    }
+
+    #[global_allocator]
+    static GLOBAL: TrackingAllocator = TrackingAllocator;
+}
+
+/// Highest live heap size so far (`mem-profile` feature)
+pub fn heap_peak_bytes() -> Option<u64> {
+    #[cfg(feature = "mem-profile")]
+    return Some(tracking::PEAK.load(std::sync::atomic::Ordering::Relaxed));
+    #[cfg(not(feature = "mem-profile"))]
+    None
+}
+
+/// (bytes, allocations) made on this thread so far (`mem-profile` feature)
+fn thread_allocations() -> Option<(u64, u64)> {
+    #[cfg(feature = "mem-profile")]
+    return Some((
+        tracking::THREAD_BYTES.with(|b| b.get()),
+        tracking::THREAD_ALLOCS.with(|c| c.get()),
+    ));
+    #[cfg(not(feature = "mem-profile"))]
+    None
+}
+
+/// Sums allocations made by synchronous sections on the current thread
+#[derive(Debug, Clone, Copy, Default)]
+pub struct AllocMeter {
+    pub bytes: u64,
+    pub allocations: u64,
+}
+
+impl AllocMeter {
+    /// Run `f` and add the allocations it made to the meter
+    pub fn measure<T>(&mut self, f: impl FnOnce() -> T) -> T {
+        let before = thread_allocations();
+        let result = f();
+        if let (Some((bytes, allocs)), Some((bytes_after, allocs_after))) = (before, thread_allocations()) {
+            self.bytes += bytes_after - bytes;
+            self.allocations += allocs_after - allocs;
+        }
+        result
+    }
+}
+
+/// Memory picture of a chunk when it finished
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct ChunkMemory {
+    /// Process RSS when the chunk finished
+    pub rss_bytes: Option<u64>,
+    /// Process peak RSS up to then (all chunks running so far)
+    pub peak_rss_bytes: Option<u64>,
+    /// Process peak live heap up to then (`mem-profile`)
+    pub heap_peak_bytes: Option<u64>,
+    /// Bytes allocated inside this chunk's `connect_block` calls (`mem-profile`)
+    pub connect_alloc_bytes: Option<u64>,
+    pub connect_allocations: Option<u64>,
+    /// `connect_alloc_bytes` per second of chunk wall time
+    pub alloc_bytes_per_sec: Option<f64>,
+}
+
+impl ChunkMemory {
+    pub fn capture(meter: &AllocMeter, duration: Duration) -> Self {
+        let tracked = cfg!(feature = "mem-profile");
+        let secs = duration.as_secs_f64();
+        Self {
+            rss_bytes: rss_bytes(),
+            peak_rss_bytes: peak_rss_bytes(),
+            heap_peak_bytes: heap_peak_bytes(),
+            connect_alloc_bytes: tracked.then_some(meter.bytes),
+            connect_allocations: tracked.then_some(meter.allocations),
+            alloc_bytes_per_sec: (tracked && secs > 0.0).then(|| meter.bytes as f64 / secs),
+        }
+    }
+}
+
+/// Bytes as MiB/GiB for logs
+pub fn format_bytes(bytes: u64) -> String {
+    const MIB: f64 = 1024.0 * 1024.0;
+    let mib = bytes as f64 / MIB;
+    if mib >= 1024.0 {
+        format!("{:.2} GiB", mib / 1024.0)
+    } else {
+        format!("{:.1} MiB", mib)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_meter_and_capture() {
+        let mut meter = AllocMeter::default();
+        let v = meter.measure(|| vec![0u8; 4096]);
+        assert_eq!(v.len(), 4096);
+        if cfg!(feature = "mem-profile") {
+            assert!(meter.bytes >= 4096);
+            assert!(meter.allocations >= 1);
+        } else {
+            assert_eq!(meter.bytes, 0);
+        }
+        let memory = ChunkMemory::capture(&meter, Duration::from_secs(1));
+        if cfg!(target_os = "linux") {
+            assert!(memory.peak_rss_bytes.unwrap() >= memory.rss_bytes.unwrap());
+        }
+        assert_eq!(format_bytes(3 * 1024 * 1024 * 1024), "3.00 GiB");
+    }
+}
@@ -0,0 +1,377 @@
+//! Mempool Policy Differential (Corpus Replay)
+//!
+//! Captures Core's live mempool (`getrawmempool true` + `getrawtransaction`,
+//! plus the confirmed coins each transaction spends) into a corpus file, then
+//! replays every transaction through `accept_to_memory_pool` and diffs
+//! acceptance and fees against what Core recorded. Block-level differential
+//! runs never exercise standardness policy; this does.
+
+use anyhow::{Context, Result};
+use blvm_consensus::block::calculate_tx_id;
+use blvm_consensus::mempool::{accept_to_memory_pool, Mempool, MempoolResult};
+use blvm_consensus::segwit::Witness;
+use blvm_consensus::serialization::block::deserialize_block_with_witnesses;
+use blvm_consensus::{OutPoint, Transaction, UtxoSet, UTXO};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+
+use crate::core_rpc_client::CoreRpcClient;
+
+/// A confirmed coin spent by a corpus transaction
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CorpusCoin {
+    pub txid: String,
+    pub vout: u32,
+    pub value: i64,
+    pub script_pubkey: String,
+    pub height: u64,
+    pub is_coinbase: bool,
+}
+
+/// One mempool transaction with Core's recorded state
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CorpusEntry {
+    pub txid: String,
+    pub hex: String,
+    /// Base fee in satoshis as reported by Core
+    pub fee: i64,
+    pub vsize: u64,
+    /// In-mempool parents (must be replayed first)
+    pub depends: Vec<String>,
+}
+
+/// Snapshot of a node's mempool
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MempoolCorpus {
+    pub captured_at: u64,
+    pub tip_height: u64,
+    /// Parents before children
+    pub entries: Vec<CorpusEntry>,
+    pub coins: Vec<CorpusCoin>,
+}
+
+impl MempoolCorpus {
+    pub fn load(path: &Path) -> Result<Self> {
+        let contents =
+            std::fs::read_to_string(path).with_context(|| format!("Failed to read corpus {}", path.display()))?;
+        serde_json::from_str(&contents).with_context(|| format!("Failed to parse corpus {}", path.display()))
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        std::fs::write(path, serde_json::to_string(self)?)
+            .with_context(|| format!("Failed to write corpus {}", path.display()))
+    }
+}
+
+/// Capture up to `max_txs` mempool transactions (0 = all) from Core
+pub async fn capture_mempool(client: &CoreRpcClient, max_txs: usize) -> Result<MempoolCorpus> {
+    let tip_height = client.getblockcount().await?;
+    let mempool = client.getrawmempool(true).await?;
+    let mempool = mempool.as_object().context("getrawmempool did not return an object")?;
+    println!("📥 Capturing {} mempool transactions at height {}", mempool.len(), tip_height);
+
+    let mut entries = Vec::new();
+    for (txid, info) in mempool {
+        // Fetch by txid; a transaction may leave the mempool while we iterate
+        let Ok(hex) = client.getrawtransaction(txid).await else {
+            continue;
+        };
+        let fee_btc = info
+            .get("fees")
+            .and_then(|f| f.get("base"))
+            .or_else(|| info.get("fee"))
+            .and_then(|f| f.as_f64())
+            .unwrap_or(0.0);
+        entries.push(CorpusEntry {
+            txid: txid.clone(),
+            hex,
+            fee: btc_to_sats(fee_btc),
+            vsize: info.get("vsize").and_then(|v| v.as_u64()).unwrap_or(0),
+            depends: info
+                .get("depends")
+                .and_then(|d| d.as_array())
+                .map(|d| d.iter().filter_map(|t| t.as_str().map(String::from)).collect())
+                .unwrap_or_default(),
+        });
+    }
+
+    let mut entries = topo_sort(entries);
+    if max_txs > 0 && entries.len() > max_txs {
+        // Truncating a topological order keeps every kept child's parents
+        entries.truncate(max_txs);
+    }
+
+    // Confirmed coins spent by the corpus (in-mempool parents are replayed instead)
+    let in_corpus: HashSet<&str> = entries.iter().map(|e| e.txid.as_str()).collect();
+    let mut coins = Vec::new();
+    for entry in &entries {
+        let (tx, _) = decode_tx(&entry.hex)?;
+        for input in tx.inputs.iter() {
+            let txid = txid_hex(&input.prevout.hash);
+            if in_corpus.contains(txid.as_str()) {
+                continue;
+            }
+            let vout = input.prevout.index as u32;
+            let Some(coin) = client.gettxout(&txid, vout, false).await? else {
+                continue;
+            };
+            let confirmations = coin.get("confirmations").and_then(|c| c.as_u64()).unwrap_or(1);
+            coins.push(CorpusCoin {
+                txid,
+                vout,
+                value: btc_to_sats(coin.get("value").and_then(|v| v.as_f64()).unwrap_or(0.0)),
+                script_pubkey: coin
+                    .get("scriptPubKey")
+                    .and_then(|s| s.get("hex"))
+                    .and_then(|h| h.as_str())
+                    .unwrap_or_default()
+                    .to_string(),
+                height: (tip_height + 1).saturating_sub(confirmations),
+                is_coinbase: coin.get("coinbase").and_then(|c| c.as_bool()).unwrap_or(false),
+            });
+        }
+    }
+
+    Ok(MempoolCorpus {
+        captured_at: std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0),
+        tip_height,
+        entries,
+        coins,
+    })
+}
+
+/// A transaction where BLVM's policy disagrees with Core's
+#[derive(Debug, Clone, Serialize)]
+pub struct PolicyDivergence {
+    pub txid: String,
+    pub kind: DivergenceKind,
+    pub detail: String,
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+pub enum DivergenceKind {
+    /// Core accepted the transaction, BLVM rejected it
+    Rejected,
+    /// BLVM computed a different fee than Core recorded
+    FeeMismatch,
+    /// Transaction could not be decoded or its inputs are unknown
+    Unreplayable,
+}
+
+/// Outcome of replaying a corpus
+#[derive(Debug, Default, Serialize)]
+pub struct ReplayReport {
+    pub total: usize,
+    pub accepted: usize,
+    pub divergences: Vec<PolicyDivergence>,
+    /// Rejection reason -> count, for spotting systematic policy gaps
+    pub rejection_reasons: HashMap<String, usize>,
+}
+
+/// Replay every corpus transaction through BLVM's mempool acceptance
+pub fn replay_corpus(corpus: &MempoolCorpus) -> Result<ReplayReport> {
+    let mut utxo_set = UtxoSet::new();
+    for coin in &corpus.coins {
+        utxo_set.insert(
+            OutPoint {
+                hash: txid_bytes(&coin.txid)?,
+                index: coin.vout as _,
+            },
+            UTXO {
+                value: coin.value as _,
+                script_pubkey: hex::decode(&coin.script_pubkey)?.into(),
+                height: coin.height as _,
+                is_coinbase: coin.is_coinbase,
+            },
+        );
+    }
+
+    let height = corpus.tip_height + 1;
+    let mut mempool: Mempool = HashSet::new();
+    let mut report = ReplayReport {
+        total: corpus.entries.len(),
+        ..Default::default()
+    };
+
+    for entry in &corpus.entries {
+        let (tx, witness) = match decode_tx(&entry.hex) {
+            Ok(decoded) => decoded,
+            Err(e) => {
+                report.divergences.push(PolicyDivergence {
+                    txid: entry.txid.clone(),
+                    kind: DivergenceKind::Unreplayable,
+                    detail: format!("decode failed: {}", e),
+                });
+                continue;
+            }
+        };
+
+        let input_value: Option<i64> = tx
+            .inputs
+            .iter()
+            .map(|i| utxo_set.get(&i.prevout).map(|u| u.value as i64))
+            .sum();
+        let Some(input_value) = input_value else {
+            report.divergences.push(PolicyDivergence {
+                txid: entry.txid.clone(),
+                kind: DivergenceKind::Unreplayable,
+                detail: "spends a coin missing from the corpus".to_string(),
+            });
+            continue;
+        };
+
+        match accept_to_memory_pool(&tx, Some(&witness), &utxo_set, &mempool, height) {
+            Ok(MempoolResult::Accepted) => {
+                report.accepted += 1;
+                let output_value: i64 = tx.outputs.iter().map(|o| o.value as i64).sum();
+                let fee = input_value - output_value;
+                if fee != entry.fee {
+                    report.divergences.push(PolicyDivergence {
+                        txid: entry.txid.clone(),
+                        kind: DivergenceKind::FeeMismatch,
+                        detail: format!("BLVM fee {} sats, Core fee {} sats", fee, entry.fee),
+                    });
+                }
+
+                // Make outputs spendable by in-mempool children
+                let txid = calculate_tx_id(&tx);
+                mempool.insert(txid);
+                for (index, output) in tx.outputs.iter().enumerate() {
+                    utxo_set.insert(
+                        OutPoint { hash: txid, index: index as _ },
+                        UTXO {
+                            value: output.value,
+                            script_pubkey: output.script_pubkey.clone(),
+                            height: height as _,
+                            is_coinbase: false,
+                        },
+                    );
+                }
+            }
+            other => {
+                let reason = match other {
+                    Ok(result) => format!("{:?}", result),
+                    Err(e) => format!("error: {}", e),
+                };
+                *report.rejection_reasons.entry(reason.clone()).or_insert(0) += 1;
+                report.divergences.push(PolicyDivergence {
+                    txid: entry.txid.clone(),
+                    kind: DivergenceKind::Rejected,
+                    detail: reason,
+                });
+            }
+        }
+    }
+
+    Ok(report)
+}
+
+/// Print a replay summary
+pub fn print_report(report: &ReplayReport) {
+    println!("📊 Mempool replay: {}/{} accepted by BLVM", report.accepted, report.total);
+    let mut reasons: Vec<_> = report.rejection_reasons.iter().collect();
+    reasons.sort_by(|a, b| b.1.cmp(a.1));
+    for (reason, count) in reasons.iter().take(10) {
+        println!("   ❌ {} x {}", count, reason);
+    }
+    let fee_mismatches = report
+        .divergences
+        .iter()
+        .filter(|d| d.kind == DivergenceKind::FeeMismatch)
+        .count();
+    if fee_mismatches > 0 {
+        println!("   ⚠️  {} fee mismatches", fee_mismatches);
+    }
+}
+
+/// Order entries so every parent precedes its children
+fn topo_sort(entries: Vec<CorpusEntry>) -> Vec<CorpusEntry> {
+    let mut pending: HashMap<String, CorpusEntry> = entries.into_iter().map(|e| (e.txid.clone(), e)).collect();
+    let mut ordered = Vec::with_capacity(pending.len());
+    let mut done: HashSet<String> = HashSet::new();
+
+    while !pending.is_empty() {
+        let mut ready: Vec<String> = pending
+            .values()
+            .filter(|e| e.depends.iter().all(|p| done.contains(p) || !pending.contains_key(p)))
+            .map(|e| e.txid.clone())
+            .collect();
+        if ready.is_empty() {
+            // Dependency cycle can't happen in a real mempool; emit the rest as-is
+            ready = pending.keys().cloned().collect();
+        }
+        ready.sort();
+        for txid in ready {
+            if let Some(entry) = pending.remove(&txid) {
+                done.insert(txid);
+                ordered.push(entry);
+            }
+        }
+    }
+    ordered
+}
+
+/// Decode a standalone transaction (with witness) by wrapping it in a one-tx block
+pub(crate) fn decode_tx(tx_hex: &str) -> Result<(Transaction, Witness)> {
+    let mut bytes = vec![0u8; 80];
+    bytes.push(1);
+    bytes.extend_from_slice(&hex::decode(tx_hex)?);
+    let (block, mut witnesses) = deserialize_block_with_witnesses(&bytes)?;
+    let tx = block.transactions.first().cloned().context("no transaction decoded")?;
+    let witness = if witnesses.is_empty() { Witness::default() } else { witnesses.swap_remove(0) };
+    Ok((tx, witness))
+}
+
+pub(crate) fn btc_to_sats(btc: f64) -> i64 {
+    (btc * 100_000_000.0).round() as i64
+}
+
+pub(crate) fn txid_hex(hash: &[u8; 32]) -> String {
+    let mut display = *hash;
+    display.reverse();
+    hex::encode(display)
+}
+
+fn txid_bytes(txid: &str) -> Result<[u8; 32]> {
+    let mut hash: [u8; 32] = hex::decode(txid)?
+        .try_into()
+        .map_err(|_| anyhow::anyhow!("Invalid txid length"))?;
+    hash.reverse();
+    Ok(hash)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(txid: &str, depends: &[&str]) -> CorpusEntry {
+        CorpusEntry {
+            txid: txid.to_string(),
+            hex: String::new(),
+            fee: 0,
+            vsize: 0,
+            depends: depends.iter().map(|d| d.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn test_topo_sort_parents_first() {
+        let sorted = topo_sort(vec![entry("c", &["b"]), entry("b", &["a"]), entry("a", &[]), entry("x", &["gone"])]);
+        let order: Vec<&str> = sorted.iter().map(|e| e.txid.as_str()).collect();
+        let pos = |t: &str| order.iter().position(|o| *o == t).unwrap();
+        assert!(pos("a") < pos("b") && pos("b") < pos("c"));
+        assert_eq!(order.len(), 4);
+    }
+
+    #[test]
+    fn test_txid_roundtrip() {
+        let txid = "00000000000000000000000000000000000000000000000000000000000000ff";
+        let bytes = txid_bytes(txid).unwrap();
+        assert_eq!(bytes[0], 0xff);
+        assert_eq!(txid_hex(&bytes), txid);
+    }
+}
@@ -0,0 +1,232 @@
+//! Memory-Mapped Block Cache
+//!
+//! A contiguous block cache that parallel chunk workers read through a single
+//! shared mapping: `get(height)` returns a `&[u8]` slice into the mapping, so
+//! there is no per-block allocation or copy (unlike `SharedBlockCache`, which
+//! reads one file per block).
+//!
+//! ```text
+//! <dir>/blocks.dat   raw blocks, back to back
+//! <dir>/blocks.idx   "BLVMMMAP" | version (u32) | start_height (u64) | count (u64)
+//!                    then per block: offset (u64) | len (u32)
+//! ```
+
+use anyhow::{Context, Result};
+use memmap2::{Mmap, MmapOptions};
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+use crate::block_source::BlockSource;
+use crate::parallel_differential::{get_block_data, BlockDataSource};
+
+const INDEX_MAGIC: &[u8; 8] = b"BLVMMMAP";
+const INDEX_VERSION: u32 = 1;
+const INDEX_HEADER_LEN: usize = 8 + 4 + 8 + 8;
+const INDEX_ENTRY_LEN: usize = 8 + 4;
+
+/// Default mmap cache directory: `BLVM_MMAP_CACHE` or `<cache_dir>/mmap`
+pub fn default_mmap_dir(cache_dir: Option<&Path>) -> Option<PathBuf> {
+    std::env::var("BLVM_MMAP_CACHE")
+        .map(PathBuf::from)
+        .ok()
+        .or_else(|| cache_dir.map(|d| d.join("mmap")))
+}
+
+/// Read-only, shareable view of an mmap cache
+pub struct MmapBlockCache {
+    dir: PathBuf,
+    data: Mmap,
+    start_height: u64,
+    /// (offset, len) per height, starting at `start_height`
+    index: Vec<(u64, u32)>,
+}
+
+impl MmapBlockCache {
+    /// True if `dir` contains an mmap cache index
+    pub fn exists(dir: &Path) -> bool {
+        dir.join("blocks.idx").exists() && dir.join("blocks.dat").exists()
+    }
+
+    pub fn open(dir: impl AsRef<Path>) -> Result<Self> {
+        let dir = dir.as_ref().to_path_buf();
+        let index_bytes = std::fs::read(dir.join("blocks.idx"))
+            .with_context(|| format!("Failed to read mmap cache index in {}", dir.display()))?;
+        let (start_height, index) = parse_index(&index_bytes)?;
+
+        let file = File::open(dir.join("blocks.dat"))
+            .with_context(|| format!("Failed to open mmap cache data in {}", dir.display()))?;
+        // Safety: the cache is only appended to by `MmapCacheWriter`, never while mapped
+        let data = unsafe { MmapOptions::new().map(&file)? };
+
+        if let Some((offset, len)) = index.last() {
+            if offset + *len as u64 > data.len() as u64 {
+                anyhow::bail!("mmap cache index points past the end of blocks.dat (truncated cache?)");
+            }
+        }
+        Ok(Self {
+            dir,
+            data,
+            start_height,
+            index,
+        })
+    }
+
+    /// Zero-copy block bytes for `height`
+    pub fn get(&self, height: u64) -> Option<&[u8]> {
+        let idx = height.checked_sub(self.start_height)? as usize;
+        let (offset, len) = *self.index.get(idx)?;
+        self.data.get(offset as usize..offset as usize + len as usize)
+    }
+
+    pub fn start_height(&self) -> u64 {
+        self.start_height
+    }
+
+    /// Highest cached height (start - 1 if empty)
+    pub fn end_height(&self) -> u64 {
+        (self.start_height + self.index.len() as u64).saturating_sub(1)
+    }
+
+    pub fn len(&self) -> usize {
+        self.index.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.index.is_empty()
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+}
+
+fn parse_index(buf: &[u8]) -> Result<(u64, Vec<(u64, u32)>)> {
+    if buf.len() < INDEX_HEADER_LEN || &buf[0..8] != INDEX_MAGIC {
+        anyhow::bail!("Not an mmap cache index (bad magic)");
+    }
+    let version = u32::from_le_bytes(buf[8..12].try_into()?);
+    if version != INDEX_VERSION {
+        anyhow::bail!("Unsupported mmap cache index version {}", version);
+    }
+    let start_height = u64::from_le_bytes(buf[12..20].try_into()?);
+    let count = u64::from_le_bytes(buf[20..28].try_into()?) as usize;
+    let entries = &buf[INDEX_HEADER_LEN..];
+    if entries.len() < count * INDEX_ENTRY_LEN {
+        anyhow::bail!("mmap cache index truncated: {} entries declared", count);
+    }
+
+    let index = entries
+        .chunks_exact(INDEX_ENTRY_LEN)
+        .take(count)
+        .map(|e| {
+            (
+                u64::from_le_bytes(e[0..8].try_into().expect("8 bytes")),
+                u32::from_le_bytes(e[8..12].try_into().expect("4 bytes")),
+            )
+        })
+        .collect();
+    Ok((start_height, index))
+}
+
+/// Sequential writer for a new mmap cache
+pub struct MmapCacheWriter {
+    dir: PathBuf,
+    data: BufWriter<File>,
+    start_height: u64,
+    offset: u64,
+    index: Vec<(u64, u32)>,
+}
+
+impl MmapCacheWriter {
+    pub fn create(dir: impl AsRef<Path>, start_height: u64) -> Result<Self> {
+        let dir = dir.as_ref().to_path_buf();
+        std::fs::create_dir_all(&dir)
+            .with_context(|| format!("Failed to create mmap cache directory {}", dir.display()))?;
+        let data = File::create(dir.join("blocks.dat"))?;
+        Ok(Self {
+            dir,
+            data: BufWriter::new(data),
+            start_height,
+            offset: 0,
+            index: Vec::new(),
+        })
+    }
+
+    /// Append the block at the next height
+    pub fn append(&mut self, block_bytes: &[u8]) -> Result<()> {
+        self.data.write_all(block_bytes)?;
+        self.index.push((self.offset, block_bytes.len() as u32));
+        self.offset += block_bytes.len() as u64;
+        Ok(())
+    }
+
+    /// Flush data and write the index (the cache isn't readable until this runs)
+    pub fn finish(mut self) -> Result<PathBuf> {
+        self.data.flush()?;
+        let mut buf = Vec::with_capacity(INDEX_HEADER_LEN + self.index.len() * INDEX_ENTRY_LEN);
+        buf.extend_from_slice(INDEX_MAGIC);
+        buf.extend_from_slice(&INDEX_VERSION.to_le_bytes());
+        buf.extend_from_slice(&self.start_height.to_le_bytes());
+        buf.extend_from_slice(&(self.index.len() as u64).to_le_bytes());
+        for (offset, len) in &self.index {
+            buf.extend_from_slice(&offset.to_le_bytes());
+            buf.extend_from_slice(&len.to_le_bytes());
+        }
+        // Write-then-rename so a crash never leaves a half-written index behind
+        let tmp = self.dir.join("blocks.idx.tmp");
+        std::fs::write(&tmp, buf)?;
+        std::fs::rename(&tmp, self.dir.join("blocks.idx"))?;
+        Ok(self.dir)
+    }
+}
+
+/// Build an mmap cache for `start..=end` from any block data source
+pub async fn build_mmap_cache(dir: &Path, source: &BlockDataSource, start_height: u64, end_height: u64) -> Result<()> {
+    println!("📦 Building mmap block cache {}-{} in {}", start_height, end_height, dir.display());
+    let mut writer = MmapCacheWriter::create(dir, start_height)?;
+
+    match source.iter_sequential(start_height, (end_height - start_height + 1) as usize)? {
+        Some(blocks) => {
+            for (idx, block) in blocks.enumerate() {
+                writer.append(&block?)?;
+                if (idx + 1) % 10_000 == 0 {
+                    println!("   📊 {} blocks written (height {})", idx + 1, start_height + idx as u64);
+                }
+            }
+        }
+        None => {
+            for height in start_height..=end_height {
+                writer.append(&get_block_data(source, height).await?)?;
+                if height % 10_000 == 0 {
+                    println!("   📊 Height {} written", height);
+                }
+            }
+        }
+    }
+
+    writer.finish()?;
+    println!("✅ mmap cache ready");
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_write_and_read_back() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut writer = MmapCacheWriter::create(dir.path(), 100).unwrap();
+        writer.append(&[1, 2, 3]).unwrap();
+        writer.append(&[4; 90]).unwrap();
+        writer.finish().unwrap();
+
+        let cache = MmapBlockCache::open(dir.path()).unwrap();
+        assert_eq!(cache.get(100), Some(&[1u8, 2, 3][..]));
+        assert_eq!(cache.get(101).map(|b| b.len()), Some(90));
+        assert_eq!(cache.get(99), None);
+        assert_eq!(cache.get(102), None);
+        assert_eq!(cache.end_height(), 101);
+    }
+}
@@ -0,0 +1,283 @@
+//! Multi-Network Runs
+//!
+//! A consensus change has to hold on more than one chain before a release.
+//! `run_multi_network` runs the parallel differential on several networks from
+//! one invocation, at the same time: each network gets its own Core RPC
+//! (`RpcConfig::for_network`), block source, checkpoint and quarantine
+//! directories and reports, while the chunks of all of them take turns on one
+//! pool of `num_workers` slots, so two chains don't oversubscribe the machine.
+//!
+//! Reports configured by path (`BLVM_RUN_RECORD`, `BLVM_HTML_REPORT`,
+//! `BLVM_JUNIT_XML`, `BLVM_RESULTS_JSONL`, `--summary`) get the network's name
+//! inserted before the extension for every network but mainnet
+//! (`report.testnet.html`).
+
+use anyhow::{Context, Result};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tokio::sync::Semaphore;
+
+use crate::ci_report::RunSummary;
+use crate::core_rpc_client::{BitcoinNetwork, CoreRpcClient, RpcConfig};
+use crate::parallel_differential::{
+    create_block_data_source, fallback_sources, run_parallel_differential, BlockDataSource, BlockFileNetwork,
+    ParallelConfig,
+};
+
+pub fn network_name(network: BlockFileNetwork) -> &'static str {
+    match network {
+        BlockFileNetwork::Mainnet => "mainnet",
+        BlockFileNetwork::Testnet => "testnet",
+        BlockFileNetwork::Regtest => "regtest",
+        BlockFileNetwork::Signet => "signet",
+    }
+}
+
+pub fn parse_network(name: &str) -> Result<BlockFileNetwork> {
+    match name.trim() {
+        "mainnet" | "main" => Ok(BlockFileNetwork::Mainnet),
+        "testnet" | "test" | "testnet3" => Ok(BlockFileNetwork::Testnet),
+        "regtest" => Ok(BlockFileNetwork::Regtest),
+        "signet" => Ok(BlockFileNetwork::Signet),
+        other => anyhow::bail!("Unknown network '{}' (expected mainnet, testnet, signet or regtest)", other),
+    }
+}
+
+pub fn rpc_network(network: BlockFileNetwork) -> BitcoinNetwork {
+    match network {
+        BlockFileNetwork::Mainnet => BitcoinNetwork::Mainnet,
+        BlockFileNetwork::Testnet => BitcoinNetwork::Testnet,
+        BlockFileNetwork::Regtest => BitcoinNetwork::Regtest,
+        BlockFileNetwork::Signet => BitcoinNetwork::Signet,
+    }
+}
+
+/// One network of a run: `testnet`, or `testnet:0-200000` for its own height range
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NetworkSpec {
+    pub network: BlockFileNetwork,
+    /// Heights to validate (None = the run's `--start`/`--end`)
+    pub range: Option<(u64, u64)>,
+}
+
+impl NetworkSpec {
+    pub fn parse(spec: &str) -> Result<Self> {
+        let (name, range) = match spec.split_once(':') {
+            Some((name, range)) => {
+                let (start, end) = range
+                    .split_once('-')
+                    .with_context(|| format!("Invalid height range '{}' (expected start-end)", range))?;
+                let start: u64 = start.trim().parse().with_context(|| format!("Invalid start height in '{}'", spec))?;
+                let end: u64 = end.trim().parse().with_context(|| format!("Invalid end height in '{}'", spec))?;
+                if end < start {
+                    anyhow::bail!("Height range '{}' ends before it starts", range);
+                }
+                (name, Some((start, end)))
+            }
+            None => (spec, None),
+        };
+        Ok(Self {
+            network: parse_network(name)?,
+            range,
+        })
+    }
+}
+
+/// `path` for `network`'s copy of a report: unchanged for mainnet, otherwise
+/// with the network's name before the extension
+pub fn network_path(path: &Path, network: BlockFileNetwork) -> PathBuf {
+    if network == BlockFileNetwork::Mainnet {
+        return path.to_path_buf();
+    }
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    let name = match path.extension() {
+        Some(extension) => format!("{}.{}.{}", stem, network_name(network), extension.to_string_lossy()),
+        None => format!("{}.{}", stem, network_name(network)),
+    };
+    path.with_file_name(name)
+}
+
+/// `network`'s own subdirectory of `dir` (checkpoints, quarantine, caches); `dir` itself for mainnet
+pub fn network_dir(dir: &Path, network: BlockFileNetwork) -> PathBuf {
+    match network {
+        BlockFileNetwork::Mainnet => dir.to_path_buf(),
+        _ => dir.join(network_name(network)),
+    }
+}
+
+/// Report path from environment variable `var`, for `network` (see `network_path`)
+pub fn report_path(var: &str, network: BlockFileNetwork) -> Option<String> {
+    let path = std::env::var(var).ok()?;
+    Some(network_path(Path::new(&path), network).to_string_lossy().into_owned())
+}
+
+/// `base` for one network: its own checkpoints, quarantine and RPC limits
+///
+/// Reproducer bundles, input bundles and flamegraphs replay blocks under
+/// mainnet rules, and waivers, extra Core versions, the verify node, fee checks
+/// and optimistic chunks name mainnet heights and nodes, so other networks run
+/// without them.
+pub fn config_for_network(base: &ParallelConfig, network: BlockFileNetwork) -> ParallelConfig {
+    let mut config = base.clone();
+    config.network = network;
+    config.rpc_limiter = Arc::new(crate::rpc_limiter::RpcLimiter::new(base.rpc_limiter.limits()));
+    if network == BlockFileNetwork::Mainnet {
+        return config;
+    }
+    config.checkpoint_dir = network_dir(&base.checkpoint_dir, network);
+    config.quarantine_dir = network_dir(&base.quarantine_dir, network);
+    config.reproducer_dir = None;
+    config.input_bundle_dir = None;
+    config.flamegraphs = None;
+    config.waivers = None;
+    config.core_endpoints = Vec::new();
+    config.verify_node = None;
+    config.fee_check = None;
+    config.optimistic = None;
+    config
+}
+
+/// Everything one network's run needs
+pub struct NetworkRun {
+    pub network: BlockFileNetwork,
+    pub start_height: u64,
+    pub end_height: u64,
+    pub config: ParallelConfig,
+    pub source: Arc<BlockDataSource>,
+}
+
+impl NetworkRun {
+    /// Core's RPC for `spec`'s network and the fastest block source next to it
+    ///
+    /// Mainnet picks its source as a single-network run does. The others use
+    /// their blk files, then `cache_dir/<network>`, then RPC: the environment's
+    /// other sources (mmap and chunked caches, Start9, P2P, Esplora) describe
+    /// mainnet.
+    pub fn prepare(
+        spec: &NetworkSpec,
+        default_range: (u64, u64),
+        base: &ParallelConfig,
+        cache_dir: Option<&Path>,
+    ) -> Result<Self> {
+        let network = spec.network;
+        let client = Arc::new(CoreRpcClient::new(RpcConfig::for_network(rpc_network(network))));
+        let cache_dir = cache_dir.map(|dir| network_dir(dir, network));
+        let source = match network {
+            BlockFileNetwork::Mainnet => create_block_data_source(network, cache_dir.as_ref(), Some(client.clone()))?,
+            _ => match crate::block_file_reader::BlockFileReader::auto_detect(network) {
+                Ok(reader) => BlockDataSource::DirectFile(reader),
+                Err(e) => {
+                    println!("📂 No {} block files ({:#}), falling back to RPC", network_name(network), e);
+                    match &cache_dir {
+                        Some(dir) => BlockDataSource::SharedCache(
+                            crate::block_file_reader::SharedBlockCache::new(dir)?,
+                            Some(client.clone()),
+                        ),
+                        None => BlockDataSource::Rpc(client.clone()),
+                    }
+                }
+            },
+        };
+        let source = Arc::new(source);
+        let mut config = config_for_network(base, network);
+        config.fallback_sources = fallback_sources(&source, cache_dir.as_deref(), Some(client));
+        let (start_height, end_height) = spec.range.unwrap_or(default_range);
+        Ok(Self {
+            network,
+            start_height,
+            end_height,
+            config,
+            source,
+        })
+    }
+}
+
+/// Run every network at once, sharing `num_workers` chunk slots; one summary per network, in order
+pub async fn run_multi_network(runs: Vec<NetworkRun>, num_workers: usize) -> Result<Vec<(BlockFileNetwork, RunSummary)>> {
+    let pool = Arc::new(Semaphore::new(num_workers.max(1)));
+    let names: Vec<&str> = runs.iter().map(|run| network_name(run.network)).collect();
+    println!("🌐 Validating {} at once ({} shared workers)", names.join(", "), num_workers.max(1));
+
+    let mut handles = Vec::new();
+    for mut run in runs {
+        run.config.worker_pool = Some(pool.clone());
+        let network = run.network;
+        let handle = tokio::spawn(async move {
+            let started = std::time::Instant::now();
+            let outcome = run_parallel_differential(run.start_height, run.end_height, run.config, run.source).await;
+            let wall_secs = started.elapsed().as_secs_f64();
+            match outcome {
+                Ok(results) => RunSummary::from_results(run.start_height, run.end_height, &results, wall_secs),
+                Err(e) => RunSummary::failed(run.start_height, run.end_height, &e, wall_secs),
+            }
+        });
+        handles.push((network, handle));
+    }
+
+    let mut summaries = Vec::new();
+    for (network, handle) in handles {
+        let summary = handle
+            .await
+            .with_context(|| format!("The {} run panicked", network_name(network)))?;
+        summaries.push((network, summary));
+    }
+
+    println!("\n🌐 Multi-network summary:");
+    for (network, summary) in &summaries {
+        match &summary.error {
+            Some(error) => println!("   ❌ {}: {}", network_name(*network), error),
+            None => println!(
+                "   {} {}: heights {}-{}, {} blocks, {} unwaived divergence(s), {} poisoned chunk(s), {:.1} blocks/sec",
+                if summary.exit_code == crate::ci_report::EXIT_MATCH { "✅" } else { "❌" },
+                network_name(*network),
+                summary.start_height,
+                summary.end_height,
+                summary.blocks_tested,
+                summary.divergent_heights.len(),
+                summary.poisoned_chunks,
+                summary.blocks_per_sec
+            ),
+        }
+    }
+    Ok(summaries)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_network_spec_parse() {
+        assert_eq!(
+            NetworkSpec::parse("testnet:0-200000").unwrap(),
+            NetworkSpec {
+                network: BlockFileNetwork::Testnet,
+                range: Some((0, 200_000)),
+            }
+        );
+        assert_eq!(NetworkSpec::parse("main").unwrap().range, None);
+        assert!(NetworkSpec::parse("testnet:5-1").is_err());
+        assert!(NetworkSpec::parse("testnet:100").is_err());
+        assert!(NetworkSpec::parse("litecoin").is_err());
+        assert_eq!(NetworkSpec::parse("signet:0-1000").unwrap().network, BlockFileNetwork::Signet);
+    }
+
+    #[test]
+    fn test_signet_end_to_end_settings() {
+        assert_eq!(rpc_network(BlockFileNetwork::Signet).default_rpc_port(), 38332);
+        assert_eq!(BlockFileNetwork::Signet.magic_bytes(), &[0x0a, 0x03, 0xcf, 0x40]);
+        assert_eq!(network_path(Path::new("report.html"), BlockFileNetwork::Signet), Path::new("report.signet.html"));
+        // BLVM has no signet parameters, so a signet run must refuse rather than validate under other rules
+        let error = crate::parallel_differential::consensus_network(BlockFileNetwork::Signet).unwrap_err();
+        assert!(error.to_string().contains("signet not supported by blvm-consensus"));
+        assert!(crate::parallel_differential::consensus_network(BlockFileNetwork::Testnet).is_ok());
+    }
+
+    #[test]
+    fn test_network_path() {
+        let path = Path::new("/tmp/report.html");
+        assert_eq!(network_path(path, BlockFileNetwork::Mainnet), path);
+        assert_eq!(network_path(path, BlockFileNetwork::Testnet), Path::new("/tmp/report.testnet.html"));
+        assert_eq!(network_path(Path::new("summary"), BlockFileNetwork::Regtest), Path::new("summary.regtest"));
+    }
+}
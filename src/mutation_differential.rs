@@ -0,0 +1,665 @@
+//! Fuzz-Mutation Differential
+//!
+//! Takes valid blocks, applies structured mutations that each break one
+//! consensus rule (flip a signature byte, truncate a witness, tweak a
+//! locktime, duplicate an input) and checks that BLVM rejects them just like
+//! Core - catching cases where BLVM is too lenient, which a replay of the
+//! (all-valid) main chain can never find.
+//!
+//! Mutations work on the raw wire format with their own parser, so a bug in
+//! BLVM's serializer can't hide one. The merkle root and witness commitment
+//! are recomputed after every mutation so the block fails for the intended
+//! reason rather than a trivial commitment mismatch.
+//!
+//! Core's verdict comes from `getblocktemplate` proposal mode, which runs
+//! Core's full block validation without touching its chainstate (and without
+//! checking proof of work). On regtest the run mines a block of wallet spends,
+//! invalidates it so its parent is the tip again, proposes each mutated copy
+//! and finally reconsiders the original.
+//!
+//! Mainnet block fixtures get a Core verdict the same way when a proposal
+//! node is given whose tip is the fixture's parent (or the fixture itself,
+//! which is then invalidated and reconsidered) - e.g. the isolated verify node
+//! of `submit_verifier` stopped at the right height. Core only evaluates
+//! proposals that build on its tip, so without such a node only BLVM's side
+//! is checked and any mutation BLVM accepts is reported as a leniency candidate.
+
+use anyhow::{Context, Result};
+use blvm_consensus::block::connect_block;
+use blvm_consensus::serialization::block::deserialize_block_with_witnesses;
+use blvm_consensus::types::{Network, ValidationResult};
+use blvm_consensus::UtxoSet;
+
+use crate::bench_fixtures::{merkle_root, sha256d, write_compact_size};
+use crate::block_hash::compact_target;
+use crate::core_rpc_client::{BitcoinNetwork, CoreRpcClient};
+
+/// Wallet spends mined into the regtest block that gets mutated
+const REGTEST_SPENDS: usize = 4;
+
+/// A structured, rule-breaking block mutation
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mutation {
+    /// Flip a byte inside the first signature found (scriptSig or witness)
+    FlipSigByte,
+    /// Drop the last item of the first non-empty witness stack
+    TruncateWitness,
+    /// Change a transaction's nLockTime (invalidates its signatures)
+    TweakLocktime,
+    /// Spend the same outpoint twice in one transaction
+    DuplicateInput,
+}
+
+impl Mutation {
+    pub const ALL: [Mutation; 4] = [
+        Mutation::FlipSigByte,
+        Mutation::TruncateWitness,
+        Mutation::TweakLocktime,
+        Mutation::DuplicateInput,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Mutation::FlipSigByte => "flip-sig-byte",
+            Mutation::TruncateWitness => "truncate-witness",
+            Mutation::TweakLocktime => "tweak-locktime",
+            Mutation::DuplicateInput => "duplicate-input",
+        }
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Raw wire format
+// ---------------------------------------------------------------------------
+
+struct Reader<'a> {
+    buf: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, n: usize) -> Result<&'a [u8]> {
+        let bytes = self
+            .pos
+            .checked_add(n)
+            .and_then(|end| self.buf.get(self.pos..end))
+            .with_context(|| format!("Unexpected end of data at offset {}", self.pos))?;
+        self.pos += n;
+        Ok(bytes)
+    }
+
+    fn u32_le(&mut self) -> Result<u32> {
+        Ok(u32::from_le_bytes(self.take(4)?.try_into()?))
+    }
+
+    fn compact_size(&mut self) -> Result<u64> {
+        Ok(match self.take(1)?[0] {
+            0xfd => u16::from_le_bytes(self.take(2)?.try_into()?) as u64,
+            0xfe => u32::from_le_bytes(self.take(4)?.try_into()?) as u64,
+            0xff => u64::from_le_bytes(self.take(8)?.try_into()?),
+            n => n as u64,
+        })
+    }
+
+    fn var_bytes(&mut self) -> Result<Vec<u8>> {
+        let len = self.compact_size()? as usize;
+        Ok(self.take(len)?.to_vec())
+    }
+}
+
+fn write_var_bytes(buf: &mut Vec<u8>, data: &[u8]) {
+    write_compact_size(buf, data.len() as u64);
+    buf.extend_from_slice(data);
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct RawInput {
+    /// txid + vout, as serialized
+    pub(crate) prevout: [u8; 36],
+    pub(crate) script_sig: Vec<u8>,
+    pub(crate) sequence: u32,
+    pub(crate) witness: Vec<Vec<u8>>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct RawTx {
+    pub(crate) version: u32,
+    pub(crate) inputs: Vec<RawInput>,
+    /// (value, scriptPubKey)
+    pub(crate) outputs: Vec<(u64, Vec<u8>)>,
+    pub(crate) lock_time: u32,
+}
+
+impl RawTx {
+    /// Parse a standalone serialized transaction (no trailing bytes allowed)
+    pub(crate) fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        let mut r = Reader { buf: bytes, pos: 0 };
+        let tx = Self::parse(&mut r)?;
+        if r.pos != bytes.len() {
+            anyhow::bail!("{} trailing bytes after transaction", bytes.len() - r.pos);
+        }
+        Ok(tx)
+    }
+
+    fn parse(r: &mut Reader<'_>) -> Result<Self> {
+        let version = r.u32_le()?;
+        let segwit = r.buf.get(r.pos..r.pos + 2) == Some(&[0x00, 0x01][..]);
+        if segwit {
+            r.take(2)?;
+        }
+        let mut inputs = Vec::new();
+        for _ in 0..r.compact_size()? {
+            inputs.push(RawInput {
+                prevout: r.take(36)?.try_into()?,
+                script_sig: r.var_bytes()?,
+                sequence: r.u32_le()?,
+                witness: Vec::new(),
+            });
+        }
+        let mut outputs = Vec::new();
+        for _ in 0..r.compact_size()? {
+            let value = u64::from_le_bytes(r.take(8)?.try_into()?);
+            outputs.push((value, r.var_bytes()?));
+        }
+        if segwit {
+            for input in &mut inputs {
+                for _ in 0..r.compact_size()? {
+                    input.witness.push(r.var_bytes()?);
+                }
+            }
+        }
+        let lock_time = r.u32_le()?;
+        Ok(Self { version, inputs, outputs, lock_time })
+    }
+
+    /// Transaction id (internal byte order)
+    pub(crate) fn txid(&self) -> [u8; 32] {
+        sha256d(&self.serialize(false))
+    }
+
+    /// Serialized with witness data (if any), as relayed
+    pub(crate) fn to_bytes(&self) -> Vec<u8> {
+        self.serialize(true)
+    }
+
+    /// BIP141 virtual size: (3 * base size + total size) / 4, rounded up
+    pub(crate) fn vsize(&self) -> u64 {
+        let weight = 3 * self.serialize(false).len() + self.serialize(true).len();
+        weight.div_ceil(4) as u64
+    }
+
+    fn has_witness(&self) -> bool {
+        self.inputs.iter().any(|i| !i.witness.is_empty())
+    }
+
+    /// Serialized with (if any, and `with_witness`) or without witness data
+    pub(crate) fn serialize(&self, with_witness: bool) -> Vec<u8> {
+        let with_witness = with_witness && self.has_witness();
+        let mut buf = Vec::new();
+        buf.extend_from_slice(&self.version.to_le_bytes());
+        if with_witness {
+            buf.extend_from_slice(&[0x00, 0x01]);
+        }
+        write_compact_size(&mut buf, self.inputs.len() as u64);
+        for input in &self.inputs {
+            buf.extend_from_slice(&input.prevout);
+            write_var_bytes(&mut buf, &input.script_sig);
+            buf.extend_from_slice(&input.sequence.to_le_bytes());
+        }
+        write_compact_size(&mut buf, self.outputs.len() as u64);
+        for (value, script) in &self.outputs {
+            buf.extend_from_slice(&value.to_le_bytes());
+            write_var_bytes(&mut buf, script);
+        }
+        if with_witness {
+            for input in &self.inputs {
+                write_compact_size(&mut buf, input.witness.len() as u64);
+                for item in &input.witness {
+                    write_var_bytes(&mut buf, item);
+                }
+            }
+        }
+        buf.extend_from_slice(&self.lock_time.to_le_bytes());
+        buf
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct RawBlock {
+    header: [u8; 80],
+    txs: Vec<RawTx>,
+}
+
+/// BIP141 commitment output prefix: OP_RETURN, push 36, 0xaa21a9ed
+const WITNESS_COMMITMENT_PREFIX: [u8; 6] = [0x6a, 0x24, 0xaa, 0x21, 0xa9, 0xed];
+
+impl RawBlock {
+    fn parse(bytes: &[u8]) -> Result<Self> {
+        let mut r = Reader { buf: bytes, pos: 0 };
+        let header = r.take(80)?.try_into()?;
+        let mut txs = Vec::new();
+        for _ in 0..r.compact_size()? {
+            txs.push(RawTx::parse(&mut r)?);
+        }
+        if r.pos != bytes.len() {
+            anyhow::bail!("{} trailing bytes after last transaction", bytes.len() - r.pos);
+        }
+        Ok(Self { header, txs })
+    }
+
+    fn serialize(&self) -> Vec<u8> {
+        let mut buf = self.header.to_vec();
+        write_compact_size(&mut buf, self.txs.len() as u64);
+        for tx in &self.txs {
+            buf.extend_from_slice(&tx.serialize(true));
+        }
+        buf
+    }
+
+    /// Recompute the witness commitment (if the block has one) and the merkle root
+    fn recommit(&mut self) {
+        let wtxids: Vec<[u8; 32]> = self
+            .txs
+            .iter()
+            .enumerate()
+            .map(|(idx, tx)| if idx == 0 { [0; 32] } else { sha256d(&tx.serialize(true)) })
+            .collect();
+        let witness_root = merkle_root(wtxids);
+        if let Some(coinbase) = self.txs.first_mut() {
+            let reserved = coinbase
+                .inputs
+                .first()
+                .and_then(|i| i.witness.first())
+                .cloned()
+                .unwrap_or_else(|| vec![0; 32]);
+            let commitment = sha256d(&[&witness_root[..], &reserved].concat());
+            if let Some((_, script)) = coinbase
+                .outputs
+                .iter_mut()
+                .rev()
+                .find(|(_, script)| script.len() >= 38 && script.starts_with(&WITNESS_COMMITMENT_PREFIX))
+            {
+                script[6..38].copy_from_slice(&commitment);
+            }
+        }
+        let txids: Vec<[u8; 32]> = self.txs.iter().map(|tx| sha256d(&tx.serialize(false))).collect();
+        self.header[36..68].copy_from_slice(&merkle_root(txids));
+    }
+}
+
+/// Grind the nonce until the header meets its own `bits` (only practical on regtest)
+fn grind_pow(header: &mut [u8; 80]) -> Result<()> {
+    let bits = u32::from_le_bytes(header[72..76].try_into().expect("4 bytes"));
+    let target = compact_target(bits).with_context(|| format!("Header has invalid nBits {:#010x}", bits))?;
+    for nonce in 0..=u32::MAX {
+        header[76..80].copy_from_slice(&nonce.to_le_bytes());
+        let mut hash = sha256d(header);
+        hash.reverse();
+        if hash <= target {
+            return Ok(());
+        }
+    }
+    anyhow::bail!("No nonce meets nBits {:#010x}", bits)
+}
+
+/// Whether a pushed item looks like a signature (DER + sighash byte, or Schnorr)
+fn looks_like_signature(item: &[u8]) -> bool {
+    (item.len() >= 9 && item.len() <= 73 && item[0] == 0x30) || item.len() == 64 || item.len() == 65
+}
+
+/// Apply `mutation` to the first non-coinbase transaction it fits
+fn apply(block: &mut RawBlock, mutation: Mutation) -> Option<usize> {
+    let mut txs = block.txs.iter_mut().enumerate().skip(1);
+    match mutation {
+        Mutation::FlipSigByte => {
+            for (idx, tx) in txs {
+                for input in &mut tx.inputs {
+                    if let Some(item) = input.witness.iter_mut().find(|item| looks_like_signature(item)) {
+                        item[5] ^= 0x01;
+                        return Some(idx);
+                    }
+                    // scriptSig starting with a direct push of a DER signature
+                    let push = input.script_sig.first().copied().unwrap_or(0) as usize;
+                    if (9..=73).contains(&push) && input.script_sig.get(1) == Some(&0x30) {
+                        input.script_sig[1 + 5] ^= 0x01;
+                        return Some(idx);
+                    }
+                }
+            }
+            None
+        }
+        Mutation::TruncateWitness => {
+            for (idx, tx) in txs {
+                if let Some(input) = tx.inputs.iter_mut().find(|i| !i.witness.is_empty()) {
+                    input.witness.pop();
+                    return Some(idx);
+                }
+            }
+            None
+        }
+        Mutation::TweakLocktime => {
+            let (idx, tx) = txs.next()?;
+            tx.lock_time ^= 1;
+            Some(idx)
+        }
+        Mutation::DuplicateInput => {
+            let (idx, tx) = txs.next()?;
+            let first = tx.inputs.first()?.clone();
+            tx.inputs.push(first);
+            Some(idx)
+        }
+    }
+}
+
+/// Mutate a serialized block; None if no transaction fits the mutation.
+/// Returns the mutated block and the index of the mutated transaction.
+pub fn mutate_block(block_bytes: &[u8], mutation: Mutation, regrind_pow: bool) -> Result<Option<(Vec<u8>, usize)>> {
+    let mut block = RawBlock::parse(block_bytes)?;
+    let Some(tx_index) = apply(&mut block, mutation) else {
+        return Ok(None);
+    };
+    block.recommit();
+    if regrind_pow {
+        grind_pow(&mut block.header)?;
+    }
+    Ok(Some((block.serialize(), tx_index)))
+}
+
+/// Parsed transactions of a block, in block order
+pub(crate) fn raw_transactions(block_bytes: &[u8]) -> Result<Vec<RawTx>> {
+    Ok(RawBlock::parse(block_bytes)?.txs)
+}
+
+/// Serialized transactions (with witnesses) of a block, in block order
+pub(crate) fn block_transactions(block_bytes: &[u8]) -> Result<Vec<Vec<u8>>> {
+    Ok(raw_transactions(block_bytes)?.iter().map(|tx| tx.serialize(true)).collect())
+}
+
+/// Append a serialized transaction to a block, recommitting (and regrinding) it
+pub(crate) fn append_tx(block_bytes: &[u8], tx_bytes: &[u8], regrind_pow: bool) -> Result<Vec<u8>> {
+    let mut block = RawBlock::parse(block_bytes)?;
+    block.txs.push(RawTx::from_bytes(tx_bytes)?);
+    block.recommit();
+    if regrind_pow {
+        grind_pow(&mut block.header)?;
+    }
+    Ok(block.serialize())
+}
+
+/// The block with only its coinbase and the transactions at `keep` (sorted indices), recommitted
+///
+/// The coinbase's outputs are zeroed, so fees of dropped transactions can't
+/// leave it claiming more than allowed.
+pub(crate) fn subset_block(block_bytes: &[u8], keep: &[usize]) -> Result<Vec<u8>> {
+    let mut block = RawBlock::parse(block_bytes)?;
+    let mut txs = std::mem::take(&mut block.txs).into_iter().enumerate();
+    let (_, mut coinbase) = txs.next().context("Block has no coinbase")?;
+    for (value, _) in &mut coinbase.outputs {
+        *value = 0;
+    }
+    block.txs.push(coinbase);
+    block.txs.extend(txs.filter(|(idx, _)| keep.binary_search(idx).is_ok()).map(|(_, tx)| tx));
+    block.recommit();
+    Ok(block.serialize())
+}
+
+// ---------------------------------------------------------------------------
+// Differential
+// ---------------------------------------------------------------------------
+
+/// BLVM's verdict on a (possibly mutated) block
+pub(crate) fn blvm_verdict(block_bytes: &[u8], pre_state: &UtxoSet, height: u64, network: Network) -> Result<(), String> {
+    let (block, witnesses) =
+        deserialize_block_with_witnesses(block_bytes).map_err(|e| format!("deserialize: {}", e))?;
+    match connect_block(&block, &witnesses, pre_state.clone(), height, None, network) {
+        Ok((ValidationResult::Valid, _, _)) => Ok(()),
+        Ok((ValidationResult::Invalid(msg), _, _)) => Err(msg),
+        Err(e) => Err(format!("{:?}", e)),
+    }
+}
+
+/// Core's verdict on a mutant
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CoreVerdict {
+    Accepted,
+    Rejected(String),
+    /// Mainnet fixtures: Core can't evaluate a block off its tip
+    NotChecked,
+}
+
+/// One mutated block and both verdicts
+#[derive(Debug, Clone)]
+pub struct MutationOutcome {
+    pub mutation: Mutation,
+    pub tx_index: usize,
+    /// BLVM's rejection reason (None = accepted)
+    pub blvm_rejection: Option<String>,
+    pub core: CoreVerdict,
+}
+
+impl MutationOutcome {
+    /// Both sides agree (without Core: BLVM rejected the mutant)
+    pub fn consistent(&self) -> bool {
+        match &self.core {
+            CoreVerdict::Accepted => self.blvm_rejection.is_none(),
+            CoreVerdict::Rejected(_) | CoreVerdict::NotChecked => self.blvm_rejection.is_some(),
+        }
+    }
+
+    fn print(&self) {
+        let blvm = self.blvm_rejection.as_deref().unwrap_or("accepted");
+        let core = match &self.core {
+            CoreVerdict::Accepted => "accepted",
+            CoreVerdict::Rejected(reason) => reason,
+            CoreVerdict::NotChecked => "not checked",
+        };
+        let icon = if self.consistent() { "✅" } else { "❌" };
+        println!("{} {:<17} tx {}: BLVM={}, Core={}", icon, self.mutation.name(), self.tx_index, blvm, core);
+    }
+}
+
+/// Mutate a mainnet block fixture every way that applies
+///
+/// Core's verdict comes from `proposal_node` when its tip allows it; otherwise BLVM-only.
+pub async fn check_fixture_mutations(
+    fixture: &crate::block_fixtures::BlockFixture,
+    proposal_node: Option<&CoreRpcClient>,
+) -> Result<Vec<MutationOutcome>> {
+    if let Some(client) = proposal_node {
+        match rewind_to_parent(client, &fixture.block_bytes).await {
+            Ok(rewound) => {
+                let outcomes =
+                    mutate_against_core(client, &fixture.block_bytes, &fixture.pre_state, fixture.height, Network::Mainnet)
+                        .await;
+                if let Some(hash) = rewound {
+                    client.reconsiderblock(&hash).await?;
+                }
+                return outcomes;
+            }
+            Err(e) => eprintln!("⚠️  No Core verdict for {}: {}", fixture.name, e),
+        }
+    }
+    if let Err(reason) = blvm_verdict(&fixture.block_bytes, &fixture.pre_state, fixture.height, Network::Mainnet) {
+        anyhow::bail!("Fixture {} is rejected unmutated: {}", fixture.name, reason);
+    }
+    let mut outcomes = Vec::new();
+    for mutation in Mutation::ALL {
+        let Some((mutated, tx_index)) = mutate_block(&fixture.block_bytes, mutation, false)? else {
+            continue;
+        };
+        let outcome = MutationOutcome {
+            mutation,
+            tx_index,
+            blvm_rejection: blvm_verdict(&mutated, &fixture.pre_state, fixture.height, Network::Mainnet).err(),
+            core: CoreVerdict::NotChecked,
+        };
+        outcome.print();
+        outcomes.push(outcome);
+    }
+    Ok(outcomes)
+}
+
+/// Core's verdict via proposal mode
+pub(crate) async fn core_verdict(client: &CoreRpcClient, block_bytes: &[u8]) -> Result<CoreVerdict> {
+    let result = client.propose_block(&hex::encode(block_bytes)).await?;
+    Ok(if result.accepted || result.error.as_deref() == Some("duplicate") {
+        CoreVerdict::Accepted
+    } else {
+        CoreVerdict::Rejected(result.error.unwrap_or_else(|| "rejected".to_string()))
+    })
+}
+
+/// Display-order hex of a header hash
+fn display_hash(mut hash: [u8; 32]) -> String {
+    hash.reverse();
+    hex::encode(hash)
+}
+
+/// Make the block's parent Core's tip so proposals of its mutants are evaluated
+///
+/// A node already at the parent is used as is; if the tip is the block itself
+/// it is invalidated and its hash returned for `reconsiderblock` afterwards.
+pub(crate) async fn rewind_to_parent(client: &CoreRpcClient, block_bytes: &[u8]) -> Result<Option<String>> {
+    let header = block_bytes.get(..80).context("Block is shorter than a header")?;
+    let parent = display_hash(header[4..36].try_into()?);
+    let hash = display_hash(sha256d(header));
+    let tip = client.getbestblockhash().await?;
+    if tip == parent {
+        Ok(None)
+    } else if tip == hash {
+        client.invalidateblock(&hash).await?;
+        Ok(Some(hash))
+    } else {
+        anyhow::bail!("Core's tip {} is neither block {} nor its parent {}", tip, hash, parent)
+    }
+}
+
+/// Mine a block of wallet spends on regtest and diff every mutation of it
+pub async fn run_mutation_differential(client: &CoreRpcClient) -> Result<Vec<MutationOutcome>> {
+    if client.detect_network().await? != BitcoinNetwork::Regtest {
+        anyhow::bail!("Mutation differential needs a regtest node (it mines and invalidates blocks)");
+    }
+
+    let address = client.getnewaddress().await?;
+    if client.getblockcount().await? < 101 {
+        client.generatetoaddress(101, &address).await?;
+    }
+    for _ in 0..REGTEST_SPENDS {
+        client.sendtoaddress(&client.getnewaddress().await?, 0.1).await?;
+    }
+    client.generatetoaddress(1, &address).await?;
+    let height = client.getblockcount().await?;
+    let (hash, block_bytes, pre_state) = crate::block_fixtures::fetch_block_with_pre_state(client, height).await?;
+    println!("🧬 Mutating regtest block {} ({}, {} bytes)", height, hash, block_bytes.len());
+
+    let rewound = rewind_to_parent(client, &block_bytes).await?;
+    let outcomes = mutate_against_core(client, &block_bytes, &pre_state, height, Network::Regtest).await;
+    if let Some(hash) = rewound {
+        client.reconsiderblock(&hash).await?;
+    }
+    outcomes
+}
+
+async fn mutate_against_core(
+    client: &CoreRpcClient,
+    block_bytes: &[u8],
+    pre_state: &UtxoSet,
+    height: u64,
+    network: Network,
+) -> Result<Vec<MutationOutcome>> {
+    // Control: the untouched block must pass both, or the harness itself is broken
+    // (Core reports a block `rewind_to_parent` invalidated as duplicate-invalid)
+    if let CoreVerdict::Rejected(reason) = core_verdict(client, block_bytes).await? {
+        if reason != "duplicate-invalid" {
+            anyhow::bail!("Core rejects the unmutated block proposal: {}", reason);
+        }
+    }
+    if let Err(reason) = blvm_verdict(block_bytes, pre_state, height, network) {
+        anyhow::bail!("BLVM rejects the unmutated block: {}", reason);
+    }
+
+    // Regtest mutants are reground so they stay submittable; mainnet ones can't be
+    // (proposals skip the PoW check anyway)
+    let regrind_pow = matches!(network, Network::Regtest);
+    let mut outcomes = Vec::new();
+    for mutation in Mutation::ALL {
+        let Some((mutated, tx_index)) = mutate_block(block_bytes, mutation, regrind_pow)? else {
+            println!("⏭️  {}: no applicable transaction", mutation.name());
+            continue;
+        };
+        let outcome = MutationOutcome {
+            mutation,
+            tx_index,
+            blvm_rejection: blvm_verdict(&mutated, pre_state, height, network).err(),
+            core: core_verdict(client, &mutated).await?,
+        };
+        outcome.print();
+        outcomes.push(outcome);
+    }
+    Ok(outcomes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Coinbase-only block plus one P2WPKH-style spend with a DER signature
+    fn sample_block() -> Vec<u8> {
+        let coinbase = RawTx {
+            version: 1,
+            inputs: vec![RawInput {
+                prevout: [[0u8; 32].as_slice(), &[0xff; 4]].concat().try_into().unwrap(),
+                script_sig: vec![0x01, 0x01],
+                sequence: 0xffff_ffff,
+                witness: vec![vec![0; 32]],
+            }],
+            outputs: vec![(50, vec![0x51]), (0, [&WITNESS_COMMITMENT_PREFIX[..], &[0u8; 32]].concat())],
+            lock_time: 0,
+        };
+        let mut der = vec![0x30, 0x44, 0x02, 0x20];
+        der.extend_from_slice(&[7u8; 68]);
+        let spend = RawTx {
+            version: 2,
+            inputs: vec![RawInput {
+                prevout: [9u8; 36],
+                script_sig: Vec::new(),
+                sequence: 0xffff_fffe,
+                witness: vec![der, vec![2u8; 33]],
+            }],
+            outputs: vec![(10, vec![0x00, 0x14, 1, 2, 3])],
+            lock_time: 0,
+        };
+        let mut block = RawBlock { header: [0u8; 80], txs: vec![coinbase, spend] };
+        block.header[72..76].copy_from_slice(&0x207f_ffffu32.to_le_bytes());
+        block.recommit();
+        block.serialize()
+    }
+
+    #[test]
+    fn test_raw_block_roundtrip() {
+        let bytes = sample_block();
+        assert_eq!(RawBlock::parse(&bytes).unwrap().serialize(), bytes);
+        assert!(RawBlock::parse(&bytes[..bytes.len() - 1]).is_err());
+    }
+
+    #[test]
+    fn test_mutations_recommit() {
+        let original = RawBlock::parse(&sample_block()).unwrap();
+        for mutation in Mutation::ALL {
+            let (bytes, tx_index) = mutate_block(&sample_block(), mutation, true).unwrap().unwrap();
+            assert_eq!(tx_index, 1);
+            let mut mutated = RawBlock::parse(&bytes).unwrap();
+            assert_ne!(mutated.txs[1], original.txs[1], "{}", mutation.name());
+            // Commitments already match the mutated contents
+            let header = mutated.header;
+            mutated.recommit();
+            assert_eq!(mutated.header[36..68], header[36..68]);
+            // Regtest PoW was re-ground
+            let mut hash = sha256d(&header);
+            hash.reverse();
+            assert!(hash <= compact_target(0x207f_ffff).unwrap());
+        }
+        let truncated = RawBlock::parse(&mutate_block(&sample_block(), Mutation::TruncateWitness, false).unwrap().unwrap().0).unwrap();
+        assert_eq!(truncated.txs[1].inputs[0].witness.len(), 1);
+    }
+}
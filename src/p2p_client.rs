@@ -0,0 +1,895 @@
+//! Minimal P2P Block Client
+//!
+//! Connects to a single Bitcoin peer, performs headers-first sync and downloads
+//! blocks with `getdata`. This lets differential runs work with no local Core
+//! installation at all - only a reachable node speaking the P2P protocol.
+//!
+//! With `compact_blocks` the client also asks for transaction relay and keeps
+//! what the peer relays in a bounded pool; blocks near the tip are then
+//! fetched as BIP152 compact blocks and rebuilt from that pool (see
+//! `compact_block`), which is what keeps a long-running tip follower cheap.
+
+use crate::bench_fixtures::{sha256d, write_compact_size};
+use crate::block_file_reader::Network;
+use crate::block_hash::compact_target;
+use crate::compact_block::{getblocktxn_payload, parse_blocktxn, CompactBlock, MAX_CMPCTBLOCK_DEPTH, MSG_CMPCT_BLOCK};
+use anyhow::{Context, Result};
+use num_bigint::BigUint;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+use tokio::sync::{Mutex, RwLock};
+
+/// Protocol version we announce (witness + sendheaders capable)
+const PROTOCOL_VERSION: i32 = 70016;
+
+/// Inventory type for blocks with witness data
+const MSG_WITNESS_BLOCK: u32 = 0x4000_0002;
+
+/// Inventory type for transactions with witness data
+const MSG_WITNESS_TX: u32 = 0x4000_0001;
+
+/// Inventory types without the witness flag
+const MSG_TX: u32 = 1;
+const MSG_BLOCK: u32 = 2;
+const MSG_WTX: u32 = 5;
+const MSG_WITNESS_FLAG: u32 = 0x4000_0000;
+
+/// Relayed transactions kept for compact block reconstruction (oldest dropped first)
+const MAX_POOL_BYTES: usize = 64 * 1024 * 1024;
+
+/// Peers send at most 2000 headers per `headers` message
+const MAX_HEADERS_PER_MSG: usize = 2000;
+
+/// Largest payload we accept from a peer (Core's MAX_PROTOCOL_MESSAGE_LENGTH is 4MB,
+/// blocks can be up to 4MB weight, so allow some headroom)
+const MAX_PAYLOAD_SIZE: usize = 32 * 1024 * 1024;
+
+/// P2P client configuration
+#[derive(Debug, Clone)]
+pub struct P2pConfig {
+    /// Peer address (e.g., "127.0.0.1:8333")
+    pub peer: String,
+    /// Network (selects message magic and genesis hash)
+    pub network: Network,
+    /// Timeout for connect and for each message exchange
+    pub timeout: Duration,
+    /// Take transaction relay and fetch blocks near the tip as compact blocks
+    pub compact_blocks: bool,
+}
+
+impl P2pConfig {
+    /// Create config for a peer on the given network
+    pub fn new(peer: impl Into<String>, network: Network) -> Self {
+        Self {
+            peer: peer.into(),
+            network,
+            timeout: Duration::from_secs(60),
+            compact_blocks: false,
+        }
+    }
+
+    /// Create from environment variables
+    ///
+    /// Environment variables:
+    /// - `BITCOIN_P2P_PEER` (required, e.g. "192.168.1.10:8333")
+    /// - `BITCOIN_P2P_COMPACT` (optional, `1` enables compact blocks)
+    pub fn from_env(network: Network) -> Option<Self> {
+        let compact_blocks = std::env::var("BITCOIN_P2P_COMPACT").is_ok_and(|v| v == "1");
+        std::env::var("BITCOIN_P2P_PEER").ok().map(|peer| Self {
+            compact_blocks,
+            ..Self::new(peer, network)
+        })
+    }
+}
+
+/// Transactions the peer relayed, by wtxid
+#[derive(Default)]
+struct TxPool {
+    txs: HashMap<[u8; 32], Vec<u8>>,
+    txids: HashSet<[u8; 32]>,
+    /// (txid, wtxid), oldest first
+    order: VecDeque<([u8; 32], [u8; 32])>,
+    bytes: usize,
+}
+
+impl TxPool {
+    fn insert(&mut self, tx: Vec<u8>) -> Result<()> {
+        let info = crate::compact_block::parse_tx(&tx, 0)?;
+        if info.len != tx.len() || !self.txids.insert(info.txid) {
+            return Ok(());
+        }
+        self.bytes += tx.len();
+        self.txs.insert(info.wtxid, tx);
+        self.order.push_back((info.txid, info.wtxid));
+        while self.bytes > MAX_POOL_BYTES {
+            let Some((txid, wtxid)) = self.order.pop_front() else {
+                break;
+            };
+            self.txids.remove(&txid);
+            if let Some(tx) = self.txs.remove(&wtxid) {
+                self.bytes -= tx.len();
+            }
+        }
+        Ok(())
+    }
+}
+
+/// P2P block client (headers-first)
+pub struct P2pClient {
+    config: P2pConfig,
+    stream: Mutex<Option<TcpStream>>,
+    /// Most-work header chain, plus any competing branch seen
+    headers: RwLock<HeaderChain>,
+    /// Relayed transactions (only fed with `compact_blocks`)
+    pool: Mutex<TxPool>,
+}
+
+impl P2pClient {
+    /// Create a new client (connection is established lazily)
+    pub fn new(config: P2pConfig) -> Self {
+        let genesis = genesis_hash(genesis_hex(&config.network));
+        Self {
+            config,
+            stream: Mutex::new(None),
+            headers: RwLock::new(HeaderChain::new(genesis)),
+            pool: Mutex::new(TxPool::default()),
+        }
+    }
+
+    /// Connect, handshake, and sync the full header chain from the peer
+    pub async fn connect_and_sync(config: P2pConfig) -> Result<Self> {
+        let client = Self::new(config);
+        client.sync_headers().await?;
+        Ok(client)
+    }
+
+    /// Height of the best header we know about
+    pub async fn tip_height(&self) -> u64 {
+        (self.headers.read().await.hashes.len() - 1) as u64
+    }
+
+    /// Block hash at height (internal byte order), if the header is known
+    pub async fn block_hash(&self, height: u64) -> Option<[u8; 32]> {
+        self.headers.read().await.hashes.get(height as usize).copied()
+    }
+
+    /// Download headers from the peer until it has no more to give
+    pub async fn sync_headers(&self) -> Result<u64> {
+        let mut guard = self.stream.lock().await;
+        let stream = self.ensure_connected(&mut *guard).await?;
+
+        loop {
+            let locator = self.headers.read().await.locator();
+
+            let mut payload = Vec::with_capacity(4 + 9 + locator.len() * 32 + 32);
+            payload.extend_from_slice(&(PROTOCOL_VERSION as u32).to_le_bytes());
+            write_compact_size(&mut payload, locator.len() as u64);
+            for hash in &locator {
+                payload.extend_from_slice(hash);
+            }
+            payload.extend_from_slice(&[0u8; 32]); // hash_stop: as many as possible
+            self.send_message(stream, "getheaders", &payload).await?;
+
+            let response = self.wait_for(stream, "headers").await?;
+            let new_headers = parse_headers(&response)?;
+            let received = new_headers.len();
+
+            let progressed = {
+                let mut headers = self.headers.write().await;
+                let old_tip = headers.hashes.len() - 1;
+                let progressed = headers.connect(&new_headers, &self.config.network)?;
+                if let Some(fork) = headers.last_reorg.take() {
+                    println!("   🔀 Switched to a branch with more work, forking at height {} (tip was {})", fork, old_tip);
+                }
+                if received > 0 {
+                    println!("   📥 Synced headers to height {}", headers.hashes.len() - 1);
+                }
+                progressed
+            };
+
+            // A peer repeating headers we already have won't tell us more by being asked again
+            if received < MAX_HEADERS_PER_MSG || !progressed {
+                break;
+            }
+        }
+
+        let tip = self.tip_height().await;
+        println!("✅ Header sync complete: tip height {}", tip);
+        Ok(tip)
+    }
+
+    /// Download a full block (with witness data) by height
+    pub async fn get_block(&self, height: u64) -> Result<Vec<u8>> {
+        let hash = match self.block_hash(height).await {
+            Some(h) => h,
+            None => {
+                // Peer may have advanced since last sync
+                self.sync_headers().await?;
+                self.block_hash(height)
+                    .await
+                    .with_context(|| format!("Peer has no header at height {}", height))?
+            }
+        };
+
+        // Peers only serve compact blocks near their tip
+        let compact = self.config.compact_blocks && height + MAX_CMPCTBLOCK_DEPTH >= self.tip_height().await;
+
+        let mut guard = self.stream.lock().await;
+        let stream = self.ensure_connected(&mut *guard).await?;
+
+        if compact {
+            match self.get_compact_block(stream, &hash, height).await {
+                Ok(block) => return Ok(block),
+                Err(e) => eprintln!("⚠️  Block {} not rebuilt from a compact block ({:#}); fetching it in full", height, e),
+            }
+        }
+
+        self.send_message(stream, "getdata", &getdata_payload(&[(MSG_WITNESS_BLOCK, hash)])).await?;
+
+        let block = self.wait_for(stream, "block").await?;
+        if block.len() < 80 || sha256d(&block[..80]) != hash {
+            // Drop the connection - the stream is out of sync with our requests
+            *guard = None;
+            anyhow::bail!("Peer returned unexpected block for height {}", height);
+        }
+        Ok(block)
+    }
+
+    /// Fetch a block as a compact block, rebuilding it from the pool plus at most one `getblocktxn`
+    async fn get_compact_block(&self, stream: &mut TcpStream, hash: &[u8; 32], height: u64) -> Result<Vec<u8>> {
+        self.send_message(stream, "getdata", &getdata_payload(&[(MSG_CMPCT_BLOCK, *hash)])).await?;
+        let (command, payload) = self.wait_for_any(stream, &["cmpctblock", "block"]).await?;
+        if command == "block" {
+            // The peer's tip moved on and the block is too deep to send compactly
+            if payload.len() < 80 || sha256d(&payload[..80]) != *hash {
+                anyhow::bail!("peer returned an unexpected block");
+            }
+            return Ok(payload);
+        }
+
+        let compact = CompactBlock::parse(&payload)?;
+        if compact.block_hash() != *hash {
+            anyhow::bail!("peer returned a compact block for another block");
+        }
+        let mut partial = compact.reconstruct(&self.pool.lock().await.txs)?;
+        let missing = partial.missing();
+        let mut received = payload.len();
+        if !missing.is_empty() {
+            self.send_message(stream, "getblocktxn", &getblocktxn_payload(hash, &missing)).await?;
+            let response = self.wait_for(stream, "blocktxn").await?;
+            received += response.len();
+            let (block_hash, txs) = parse_blocktxn(&response)?;
+            if block_hash != *hash {
+                anyhow::bail!("peer returned transactions for another block");
+            }
+            partial.fill(txs)?;
+        }
+
+        let from_pool = partial.from_pool;
+        let block = partial.into_block()?;
+        println!(
+            "   🧩 Block {}: {} of {} txs from relayed transactions, {} fetched, {} bytes saved",
+            height,
+            from_pool,
+            compact.tx_count(),
+            missing.len(),
+            block.len().saturating_sub(received)
+        );
+        Ok(block)
+    }
+
+    /// Wait up to `timeout` for the peer to announce a block, feeding the
+    /// transaction pool meanwhile; false when nothing was announced
+    pub async fn wait_for_announcement(&self, timeout: Duration) -> Result<bool> {
+        let deadline = tokio::time::Instant::now() + timeout;
+        let mut guard = self.stream.lock().await;
+        let stream = self.ensure_connected(&mut *guard).await?;
+        loop {
+            // peek() consumes nothing, so hitting the deadline can't cut a message in half
+            let mut byte = [0u8; 1];
+            let ready = match tokio::time::timeout_at(deadline, stream.peek(&mut byte)).await {
+                Err(_) => return Ok(false),
+                Ok(ready) => ready,
+            };
+            let message = match ready {
+                Ok(0) => Err(anyhow::anyhow!("Peer {} closed the connection", self.config.peer)),
+                Ok(_) => self.read_message(stream).await,
+                Err(e) => Err(e).context("Failed to read from peer"),
+            };
+            let announced = match message {
+                Ok((command, payload)) => self.handle_unsolicited(stream, &command, &payload).await,
+                Err(e) => Err(e),
+            };
+            match announced {
+                Ok(true) => return Ok(true),
+                Ok(false) => {}
+                Err(e) => {
+                    // Reconnect on the next call
+                    *guard = None;
+                    return Err(e);
+                }
+            }
+        }
+    }
+
+    /// Return the open connection, connecting and handshaking if needed
+    async fn ensure_connected<'a>(
+        &self,
+        guard: &'a mut Option<TcpStream>,
+    ) -> Result<&'a mut TcpStream> {
+        if guard.is_none() {
+            let stream = tokio::time::timeout(
+                self.config.timeout,
+                TcpStream::connect(&self.config.peer),
+            )
+            .await
+            .with_context(|| format!("Timed out connecting to peer {}", self.config.peer))?
+            .with_context(|| format!("Failed to connect to peer {}", self.config.peer))?;
+            stream.set_nodelay(true)?;
+            *guard = Some(stream);
+
+            let stream = guard.as_mut().expect("just connected");
+            if let Err(e) = self.handshake(stream).await {
+                *guard = None;
+                return Err(e);
+            }
+            println!("🔗 Connected to P2P peer {}", self.config.peer);
+        }
+        Ok(guard.as_mut().expect("connected"))
+    }
+
+    /// version/verack handshake
+    async fn handshake(&self, stream: &mut TcpStream) -> Result<()> {
+        let version = build_version_payload(self.config.compact_blocks);
+        self.send_message(stream, "version", &version).await?;
+
+        let mut got_version = false;
+        let mut got_verack = false;
+        while !(got_version && got_verack) {
+            let (command, _payload) = self.read_message(stream).await?;
+            match command.as_str() {
+                "version" => {
+                    got_version = true;
+                    self.send_message(stream, "verack", &[]).await?;
+                }
+                "verack" => got_verack = true,
+                _ => {}
+            }
+        }
+        if self.config.compact_blocks {
+            // Low-bandwidth mode (announce = 0), version 2 (wtxid short IDs)
+            let mut sendcmpct = vec![0u8];
+            sendcmpct.extend_from_slice(&2u64.to_le_bytes());
+            self.send_message(stream, "sendcmpct", &sendcmpct).await?;
+        }
+        Ok(())
+    }
+
+    /// Read messages until one with the given command arrives, answering pings meanwhile
+    async fn wait_for(&self, stream: &mut TcpStream, wanted: &str) -> Result<Vec<u8>> {
+        Ok(self.wait_for_any(stream, &[wanted]).await?.1)
+    }
+
+    /// Read messages until one with any of the given commands arrives
+    async fn wait_for_any(&self, stream: &mut TcpStream, wanted: &[&str]) -> Result<(String, Vec<u8>)> {
+        loop {
+            let (command, payload) = self.read_message(stream).await?;
+            if wanted.contains(&command.as_str()) {
+                return Ok((command, payload));
+            }
+            // Relayed transactions are often mined or evicted before we ask for them
+            if command == "notfound" && !parse_inv(&payload)?.iter().all(|(kind, _)| is_tx_inv(*kind)) {
+                anyhow::bail!("Peer responded notfound while waiting for {}", wanted.join("/"));
+            }
+            self.handle_unsolicited(stream, &command, &payload).await?;
+        }
+    }
+
+    /// Answer pings and feed relayed transactions into the pool; true if the message announces a block
+    async fn handle_unsolicited(&self, stream: &mut TcpStream, command: &str, payload: &[u8]) -> Result<bool> {
+        match command {
+            "ping" => self.send_message(stream, "pong", payload).await?,
+            "inv" => {
+                let inv = parse_inv(payload)?;
+                if self.config.compact_blocks {
+                    let wanted: Vec<(u32, [u8; 32])> = {
+                        let pool = self.pool.lock().await;
+                        inv.iter()
+                            .filter(|(kind, txid)| *kind & !MSG_WITNESS_FLAG == MSG_TX && !pool.txids.contains(txid))
+                            .map(|(_, txid)| (MSG_WITNESS_TX, *txid))
+                            .collect()
+                    };
+                    if !wanted.is_empty() {
+                        self.send_message(stream, "getdata", &getdata_payload(&wanted)).await?;
+                    }
+                }
+                return Ok(inv.iter().any(|(kind, _)| *kind & !MSG_WITNESS_FLAG == MSG_BLOCK));
+            }
+            "headers" => return Ok(parse_headers(payload).is_ok_and(|headers| !headers.is_empty())),
+            "tx" if self.config.compact_blocks => {
+                // A transaction we can't parse just won't help reconstruction
+                let _ = self.pool.lock().await.insert(payload.to_vec());
+            }
+            _ => {} // addr, sendcmpct, feefilter, ... - not interesting
+        }
+        Ok(false)
+    }
+
+    async fn send_message(&self, stream: &mut TcpStream, command: &str, payload: &[u8]) -> Result<()> {
+        let mut message = Vec::with_capacity(24 + payload.len());
+        message.extend_from_slice(self.config.network.magic_bytes());
+        let mut command_bytes = [0u8; 12];
+        command_bytes[..command.len()].copy_from_slice(command.as_bytes());
+        message.extend_from_slice(&command_bytes);
+        message.extend_from_slice(&(payload.len() as u32).to_le_bytes());
+        message.extend_from_slice(&sha256d(payload)[..4]);
+        message.extend_from_slice(payload);
+
+        tokio::time::timeout(self.config.timeout, stream.write_all(&message))
+            .await
+            .context("Timed out sending P2P message")?
+            .context("Failed to send P2P message")?;
+        Ok(())
+    }
+
+    async fn read_message(&self, stream: &mut TcpStream) -> Result<(String, Vec<u8>)> {
+        let mut header = [0u8; 24];
+        tokio::time::timeout(self.config.timeout, stream.read_exact(&mut header))
+            .await
+            .context("Timed out waiting for P2P message")?
+            .context("Peer closed connection")?;
+
+        if &header[0..4] != self.config.network.magic_bytes() {
+            anyhow::bail!("Peer sent message with wrong network magic: {}", hex::encode(&header[0..4]));
+        }
+        let command = String::from_utf8_lossy(&header[4..16])
+            .trim_end_matches('\0')
+            .to_string();
+        let length = u32::from_le_bytes([header[16], header[17], header[18], header[19]]) as usize;
+        if length > MAX_PAYLOAD_SIZE {
+            anyhow::bail!("Peer sent oversized {} message: {} bytes", command, length);
+        }
+
+        let mut payload = vec![0u8; length];
+        tokio::time::timeout(self.config.timeout, stream.read_exact(&mut payload))
+            .await
+            .context("Timed out reading P2P payload")?
+            .context("Peer closed connection mid-message")?;
+
+        if sha256d(&payload)[..4] != header[20..24] {
+            anyhow::bail!("Checksum mismatch in {} message", command);
+        }
+        Ok((command, payload))
+    }
+}
+
+/// Genesis block hash in display (big-endian) hex for each network
+pub(crate) fn genesis_hex(network: &Network) -> &'static str {
+    match network {
+        Network::Mainnet => "000000000019d6689c085ae165831e934ff763ae46a2a6c172b3f1b60a8ce26f",
+        Network::Testnet => "000000000933ea01ad0ee984209779baaec3ced90fa3f408719526f8d77f4943",
+        Network::Regtest => "0f9188f13cb7b2c71f2a335e3a4fc328bf5beb436012afca590b1a11466e2206",
+        Network::Signet => "00000008819873e925422c1ff0f99f7cc9bbb232af63a077a480a3633bee1ef6",
+    }
+}
+
+/// Convert display-order hex to internal byte order
+fn genesis_hash(display_hex: &str) -> [u8; 32] {
+    let mut bytes: [u8; 32] = hex::decode(display_hex)
+        .expect("valid genesis hex")
+        .try_into()
+        .expect("32-byte genesis hash");
+    bytes.reverse();
+    bytes
+}
+
+/// Block locator: last 10 hashes, then exponentially sparser back to genesis
+fn build_locator(headers: &[[u8; 32]]) -> Vec<[u8; 32]> {
+    let mut locator = Vec::new();
+    let mut step = 1usize;
+    let mut idx = headers.len() as i64 - 1;
+    while idx > 0 {
+        locator.push(headers[idx as usize]);
+        if locator.len() >= 10 {
+            step *= 2;
+        }
+        idx -= step as i64;
+    }
+    locator.push(headers[0]);
+    locator
+}
+
+fn build_version_payload(relay: bool) -> Vec<u8> {
+    let mut payload = Vec::with_capacity(110);
+    let timestamp = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or(0);
+
+    payload.extend_from_slice(&PROTOCOL_VERSION.to_le_bytes());
+    payload.extend_from_slice(&0u64.to_le_bytes()); // services: none (we serve nothing)
+    payload.extend_from_slice(&timestamp.to_le_bytes());
+    // addr_recv and addr_from: services + IPv6-mapped address + port, all zero
+    payload.extend_from_slice(&[0u8; 26]);
+    payload.extend_from_slice(&[0u8; 26]);
+    payload.extend_from_slice(&rand::random::<u64>().to_le_bytes()); // nonce
+    let user_agent = b"/blvm-bench:0.1.0/";
+    write_compact_size(&mut payload, user_agent.len() as u64);
+    payload.extend_from_slice(user_agent);
+    payload.extend_from_slice(&0i32.to_le_bytes()); // start_height
+    payload.push(relay as u8); // relay: only wanted for compact block reconstruction
+    payload
+}
+
+fn getdata_payload(items: &[(u32, [u8; 32])]) -> Vec<u8> {
+    let mut payload = Vec::with_capacity(9 + items.len() * 36);
+    write_compact_size(&mut payload, items.len() as u64);
+    for (kind, hash) in items {
+        payload.extend_from_slice(&kind.to_le_bytes());
+        payload.extend_from_slice(hash);
+    }
+    payload
+}
+
+/// Parse an `inv`/`notfound` payload into (type, hash) entries
+fn parse_inv(payload: &[u8]) -> Result<Vec<(u32, [u8; 32])>> {
+    let (count, mut offset) = read_varint(payload, 0)?;
+    let mut items = Vec::new();
+    for _ in 0..count {
+        let entry = payload.get(offset..offset + 36).context("Truncated inv message")?;
+        let kind = u32::from_le_bytes(entry[..4].try_into().expect("4-byte slice"));
+        items.push((kind, entry[4..].try_into().expect("32-byte slice")));
+        offset += 36;
+    }
+    Ok(items)
+}
+
+fn is_tx_inv(kind: u32) -> bool {
+    matches!(kind & !MSG_WITNESS_FLAG, MSG_TX | MSG_WTX)
+}
+
+/// Parse a `headers` payload into raw 80-byte headers
+fn parse_headers(payload: &[u8]) -> Result<Vec<[u8; 80]>> {
+    let (count, mut offset) = read_varint(payload, 0)?;
+    if count > MAX_HEADERS_PER_MSG as u64 {
+        anyhow::bail!("Peer sent {} headers in one message (limit {})", count, MAX_HEADERS_PER_MSG);
+    }
+    let mut headers = Vec::with_capacity(count as usize);
+    for _ in 0..count {
+        let header: [u8; 80] = payload
+            .get(offset..offset + 80)
+            .context("Truncated headers message")?
+            .try_into()
+            .expect("80-byte slice");
+        offset += 80;
+        // Each header is followed by a transaction count, which must be zero
+        let (tx_count, next) = read_varint(payload, offset)?;
+        if tx_count != 0 {
+            anyhow::bail!("Peer sent a header with a non-zero transaction count ({})", tx_count);
+        }
+        offset = next;
+        headers.push(header);
+    }
+    Ok(headers)
+}
+
+/// Easiest target a header may claim (compact `nBits`)
+fn pow_limit_bits(network: &Network) -> u32 {
+    match network {
+        Network::Mainnet | Network::Testnet => 0x1d00_ffff,
+        Network::Regtest => 0x207f_ffff,
+        Network::Signet => 0x1e03_77ae,
+    }
+}
+
+/// Whether a header's hash meets the target it claims, and that target is within the network's limit
+///
+/// Only checks the header on its own; retargeting (whether `nBits` is the difficulty the chain requires)
+/// is left to the peer, whose chain the differential run compares against Core anyway.
+fn check_header_pow(header: &[u8; 80], network: &Network) -> bool {
+    let bits = u32::from_le_bytes(header[72..76].try_into().expect("4-byte slice"));
+    let (Some(target), Some(limit)) = (compact_target(bits), compact_target(pow_limit_bits(network))) else {
+        return false;
+    };
+    let mut hash = sha256d(header);
+    hash.reverse();
+    target <= limit && hash <= target
+}
+
+/// Work a header with compact `nBits` proves: 2^256 / (target + 1)
+fn header_work(bits: u32) -> BigUint {
+    match compact_target(bits) {
+        Some(target) => (BigUint::from(1u8) << 256u32) / (BigUint::from_bytes_be(&target) + 1u8),
+        None => BigUint::default(),
+    }
+}
+
+/// Headers forking off the active chain that don't have more work than it (yet)
+#[derive(Debug, Clone)]
+struct Branch {
+    /// Height of the last block shared with the active chain
+    fork_height: usize,
+    /// Hashes from `fork_height + 1` on
+    hashes: Vec<[u8; 32]>,
+    /// Cumulative work through each of `hashes`
+    work: Vec<BigUint>,
+}
+
+/// Header chain with the most cumulative work, by height (internal byte order)
+///
+/// A header building on an older block (the peer answered from an earlier
+/// locator entry after a reorg) starts a competing branch. The active chain
+/// only switches to it once the branch has strictly more work, so a cheap
+/// fork from a buggy or hostile peer can't rewrite the heights a running
+/// differential is fetching.
+#[derive(Debug, Clone)]
+struct HeaderChain {
+    hashes: Vec<[u8; 32]>,
+    /// Cumulative work through each height (genesis counts as zero; every branch shares it)
+    work: Vec<BigUint>,
+    branch: Option<Branch>,
+    /// Fork height of the last switch to a branch, until reported
+    last_reorg: Option<usize>,
+}
+
+impl HeaderChain {
+    fn new(genesis: [u8; 32]) -> Self {
+        Self {
+            hashes: vec![genesis],
+            work: vec![BigUint::default()],
+            branch: None,
+            last_reorg: None,
+        }
+    }
+
+    fn tip_work(&self) -> &BigUint {
+        self.work.last().expect("genesis always present")
+    }
+
+    /// Locator for `getheaders`, from the branch tip while one is being downloaded
+    fn locator(&self) -> Vec<[u8; 32]> {
+        match &self.branch {
+            Some(branch) => {
+                let mut hashes = self.hashes[..=branch.fork_height].to_vec();
+                hashes.extend_from_slice(&branch.hashes);
+                build_locator(&hashes)
+            }
+            None => build_locator(&self.hashes),
+        }
+    }
+
+    /// Add the headers of one `headers` message; returns whether any was new
+    ///
+    /// Each header must carry valid proof of work and build on a block we know.
+    fn connect(&mut self, new_headers: &[[u8; 80]], network: &Network) -> Result<bool> {
+        let mut progressed = false;
+        for header in new_headers {
+            let prev: [u8; 32] = header[4..36].try_into().expect("32-byte slice");
+            let hash = sha256d(header);
+            let height = match self.hashes.iter().rposition(|h| *h == prev) {
+                Some(pos) if self.hashes.get(pos + 1) == Some(&hash) => {
+                    // Already on the active chain: the peer follows it, not our branch
+                    if self.branch.as_ref().is_some_and(|b| b.fork_height <= pos) {
+                        self.branch = None;
+                        progressed = true;
+                    }
+                    continue;
+                }
+                Some(pos) => pos + 1,
+                None => {
+                    let branch_pos = self.branch.as_ref().and_then(|b| b.hashes.iter().rposition(|h| *h == prev));
+                    match (branch_pos, &self.branch) {
+                        (Some(pos), Some(branch)) if branch.hashes.get(pos + 1) == Some(&hash) => continue,
+                        (Some(pos), Some(branch)) => branch.fork_height + pos + 2,
+                        _ => anyhow::bail!(
+                            "Peer sent header that does not connect to our chain (prev {})",
+                            hex::encode(prev.iter().rev().copied().collect::<Vec<_>>())
+                        ),
+                    }
+                }
+            };
+            if !check_header_pow(header, network) {
+                anyhow::bail!("Peer sent header at height {} with invalid proof of work", height);
+            }
+            let bits = u32::from_le_bytes(header[72..76].try_into().expect("4-byte slice"));
+            progressed = true;
+
+            if height == self.hashes.len() && prev == self.hashes[height - 1] {
+                let work = self.tip_work() + header_work(bits);
+                self.hashes.push(hash);
+                self.work.push(work);
+                continue;
+            }
+
+            // Off the active chain: start or extend the competing branch
+            let on_branch = self.branch.as_ref().is_some_and(|b| {
+                height > b.fork_height + 1 && b.hashes.get(height - b.fork_height - 2) == Some(&prev)
+            });
+            let mut branch = match (on_branch, self.branch.take()) {
+                (true, Some(mut branch)) => {
+                    branch.hashes.truncate(height - branch.fork_height - 1);
+                    branch.work.truncate(height - branch.fork_height - 1);
+                    branch
+                }
+                _ => Branch {
+                    fork_height: height - 1,
+                    hashes: Vec::new(),
+                    work: Vec::new(),
+                },
+            };
+            let parent_work = branch.work.last().unwrap_or(&self.work[branch.fork_height]).clone();
+            branch.hashes.push(hash);
+            branch.work.push(parent_work + header_work(bits));
+
+            if branch.work.last().expect("just pushed") > self.tip_work() {
+                self.hashes.truncate(branch.fork_height + 1);
+                self.work.truncate(branch.fork_height + 1);
+                self.hashes.extend(branch.hashes);
+                self.work.extend(branch.work);
+                self.last_reorg = Some(branch.fork_height);
+            } else {
+                self.branch = Some(branch);
+            }
+        }
+        Ok(progressed)
+    }
+}
+
+fn read_varint(buf: &[u8], offset: usize) -> Result<(u64, usize)> {
+    let first = *buf.get(offset).context("Truncated varint")?;
+    let (len, value) = match first {
+        0xfd => (2, buf.get(offset + 1..offset + 3).map(|b| u16::from_le_bytes([b[0], b[1]]) as u64)),
+        0xfe => (4, buf.get(offset + 1..offset + 5).map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]) as u64)),
+        0xff => (8, buf.get(offset + 1..offset + 9).map(|b| u64::from_le_bytes(b.try_into().unwrap()))),
+        n => return Ok((n as u64, offset + 1)),
+    };
+    let value = value.context("Truncated varint")?;
+    Ok((value, offset + 1 + len))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_varint_roundtrip() {
+        for value in [0u64, 0xfc, 0xfd, 0xffff, 0x10000, 0xffff_ffff, 0x1_0000_0000] {
+            let mut buf = Vec::new();
+            write_compact_size(&mut buf, value);
+            let (decoded, next) = read_varint(&buf, 0).unwrap();
+            assert_eq!(decoded, value);
+            assert_eq!(next, buf.len());
+        }
+    }
+
+    #[test]
+    fn test_locator_ends_at_genesis() {
+        let headers: Vec<[u8; 32]> = (0..100u8).map(|i| [i; 32]).collect();
+        let locator = build_locator(&headers);
+        assert_eq!(locator.first(), Some(&[99u8; 32]));
+        assert_eq!(locator.last(), Some(&[0u8; 32]));
+        assert!(locator.len() < 20);
+    }
+
+    #[test]
+    fn test_inv_roundtrip() {
+        let items = [(MSG_WITNESS_TX, [1u8; 32]), (MSG_BLOCK, [2u8; 32]), (MSG_WTX, [3u8; 32])];
+        let parsed = parse_inv(&getdata_payload(&items)).unwrap();
+        assert_eq!(parsed, items);
+        assert!(is_tx_inv(parsed[0].0) && !is_tx_inv(parsed[1].0) && is_tx_inv(parsed[2].0));
+        assert!(parse_inv(&getdata_payload(&items)[..40]).is_err());
+    }
+
+    /// A regtest header on `prev`, ground until it meets the easiest target
+    fn mined_header(prev: [u8; 32], salt: u8) -> [u8; 80] {
+        mined_header_with_bits(prev, salt, pow_limit_bits(&Network::Regtest))
+    }
+
+    fn mined_header_with_bits(prev: [u8; 32], salt: u8, bits: u32) -> [u8; 80] {
+        let mut header = [0u8; 80];
+        header[4..36].copy_from_slice(&prev);
+        header[36] = salt;
+        header[72..76].copy_from_slice(&bits.to_le_bytes());
+        for nonce in 0u32.. {
+            header[76..].copy_from_slice(&nonce.to_le_bytes());
+            if check_header_pow(&header, &Network::Regtest) {
+                break;
+            }
+        }
+        header
+    }
+
+    fn headers_payload(headers: &[[u8; 80]]) -> Vec<u8> {
+        let mut payload = Vec::new();
+        write_compact_size(&mut payload, headers.len() as u64);
+        for header in headers {
+            payload.extend_from_slice(header);
+            payload.push(0);
+        }
+        payload
+    }
+
+    #[test]
+    fn test_parse_headers_rejects_malformed_messages() {
+        let header = mined_header([0; 32], 0);
+        let payload = headers_payload(&[header, header]);
+        assert_eq!(parse_headers(&payload).unwrap(), vec![header, header]);
+        assert!(parse_headers(&payload[..payload.len() - 1]).is_err());
+        assert!(parse_headers(&payload[..100]).is_err());
+
+        // A huge count is rejected before anything is allocated for it
+        let mut oversized = Vec::new();
+        write_compact_size(&mut oversized, u64::MAX);
+        assert!(parse_headers(&oversized).is_err());
+        let mut too_many = Vec::new();
+        write_compact_size(&mut too_many, MAX_HEADERS_PER_MSG as u64 + 1);
+        assert!(parse_headers(&too_many).is_err());
+
+        let mut with_txs = headers_payload(&[header]);
+        *with_txs.last_mut().unwrap() = 1;
+        assert!(parse_headers(&with_txs).is_err());
+    }
+
+    #[test]
+    fn test_connect_headers_checks_pow_and_linkage() {
+        let genesis = [0xaa; 32];
+        let a1 = mined_header(genesis, 1);
+        let a2 = mined_header(sha256d(&a1), 1);
+        let mut chain = HeaderChain::new(genesis);
+        assert!(chain.connect(&[a1, a2], &Network::Regtest).unwrap());
+        assert_eq!(chain.hashes, vec![genesis, sha256d(&a1), sha256d(&a2)]);
+        // Headers we already have are no progress
+        assert!(!chain.connect(&[a1, a2], &Network::Regtest).unwrap());
+
+        // A competing branch from a1 replaces a2 once it has more work, not before
+        let b2 = mined_header(sha256d(&a1), 2);
+        let b3 = mined_header(sha256d(&b2), 2);
+        chain.connect(&[b2], &Network::Regtest).unwrap();
+        assert_eq!(chain.hashes, vec![genesis, sha256d(&a1), sha256d(&a2)]);
+        chain.connect(&[b3], &Network::Regtest).unwrap();
+        assert_eq!(chain.hashes, vec![genesis, sha256d(&a1), sha256d(&b2), sha256d(&b3)]);
+        assert_eq!(chain.last_reorg, Some(1));
+
+        // Unknown parent
+        let orphan = mined_header([0xbb; 32], 3);
+        assert!(chain.clone().connect(&[orphan], &Network::Regtest).is_err());
+
+        // Linked, but the hash misses the target it claims
+        let mut weak = mined_header(sha256d(&b3), 4);
+        weak[72..76].copy_from_slice(&0x1d00_ffffu32.to_le_bytes());
+        assert!(chain.clone().connect(&[weak], &Network::Regtest).is_err());
+        // A target easier than the network allows
+        assert!(!check_header_pow(&mined_header(sha256d(&b3), 5), &Network::Mainnet));
+    }
+
+    #[test]
+    fn test_low_work_fork_is_ignored() {
+        let genesis = [0xaa; 32];
+        let mut main = vec![mined_header(genesis, 1)];
+        for _ in 0..3 {
+            main.push(mined_header(sha256d(main.last().unwrap()), 1));
+        }
+        let mut chain = HeaderChain::new(genesis);
+        chain.connect(&main, &Network::Regtest).unwrap();
+        let synced = chain.hashes.clone();
+
+        // A minimum-difficulty fork off height 1 doesn't touch the synced heights
+        let fork = mined_header(sha256d(&main[0]), 9);
+        chain.connect(&[fork], &Network::Regtest).unwrap();
+        assert_eq!(chain.hashes, synced);
+        assert!(chain.branch.is_some());
+
+        // The active chain keeps growing while the branch waits
+        let next = mined_header(sha256d(&main[3]), 1);
+        chain.connect(&[next], &Network::Regtest).unwrap();
+        assert_eq!(chain.hashes.len(), 6);
+
+        // Work, not length, decides: one header at a much harder target outweighs the five easy ones
+        let hard = mined_header_with_bits(genesis, 9, 0x2000_ffff);
+        chain.connect(&[hard], &Network::Regtest).unwrap();
+        assert_eq!(chain.hashes, vec![genesis, sha256d(&hard)]);
+        assert!(chain.branch.is_none());
+    }
+}
@@ -0,0 +1,595 @@
+//! Package Relay Differential (Parent + Child)
+//!
+//! Builds parent+child packages on regtest - plain CPFP, a below-min-fee
+//! parent, several parents, and the malformed shapes package relay has to
+//! refuse (unsorted, duplicates, in-package conflicts, grandparents, too many
+//! transactions) - submits each with Core's `submitpackage`, and runs the same
+//! package through BLVM.
+//!
+//! blvm-consensus has no package entry point, so the package layer is
+//! evaluated here the way Core does it: the well-formedness and
+//! child-with-parents topology rules, then `accept_to_memory_pool` on each
+//! member against a view that includes the earlier members' outputs, then the
+//! aggregate fee-rate. Divergences are reported per scenario: verdict,
+//! per-transaction and aggregate fees, and topology rule disagreements.
+//!
+//! All coins are coinbases paying P2WSH(OP_TRUE), so no wallet is needed and
+//! every spend is standard.
+
+use anyhow::{Context, Result};
+use blvm_consensus::mempool::{accept_to_memory_pool, Mempool, MempoolResult};
+use blvm_consensus::{OutPoint, UtxoSet, UTXO};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, HashSet};
+
+use crate::core_rpc_client::{BitcoinNetwork, CoreRpcClient};
+use crate::mempool_corpus::{btc_to_sats, decode_tx, txid_hex};
+use crate::mutation_differential::{raw_transactions, RawInput, RawTx};
+
+/// Core's MAX_PACKAGE_COUNT
+const MAX_PACKAGE_COUNT: usize = 25;
+/// Core's MAX_PACKAGE_WEIGHT
+const MAX_PACKAGE_WEIGHT: u64 = 404_000;
+/// Core's default -minrelaytxfee (1000 sat/kvB)
+const MIN_RELAY_FEE_RATE: u64 = 1;
+const COINBASE_MATURITY: u64 = 100;
+
+/// OP_TRUE as a P2WSH witness script
+const OP_TRUE_SCRIPT: [u8; 1] = [0x51];
+
+/// OP_0 <sha256(OP_TRUE)>
+pub(crate) fn op_true_p2wsh() -> Vec<u8> {
+    let mut script = vec![0x00, 0x20];
+    script.extend_from_slice(&Sha256::digest(OP_TRUE_SCRIPT));
+    script
+}
+
+/// A spendable P2WSH(OP_TRUE) coin
+#[derive(Debug, Clone)]
+pub(crate) struct Coin {
+    /// txid + vout, as serialized
+    pub(crate) prevout: [u8; 36],
+    pub(crate) value: u64,
+    pub(crate) height: u64,
+    pub(crate) is_coinbase: bool,
+}
+
+impl Coin {
+    pub(crate) fn outpoint(&self) -> OutPoint {
+        let mut hash = [0u8; 32];
+        hash.copy_from_slice(&self.prevout[..32]);
+        OutPoint {
+            hash,
+            index: u32::from_le_bytes(self.prevout[32..].try_into().expect("4 bytes")) as _,
+        }
+    }
+
+    /// Output `vout` of an unconfirmed transaction
+    pub(crate) fn unconfirmed(tx: &RawTx, vout: usize) -> Self {
+        let mut prevout = [0u8; 36];
+        prevout[..32].copy_from_slice(&tx.txid());
+        prevout[32..].copy_from_slice(&(vout as u32).to_le_bytes());
+        Coin {
+            prevout,
+            value: tx.outputs[vout].0,
+            height: 0,
+            is_coinbase: false,
+        }
+    }
+}
+
+/// Mine `count` coinbases paying P2WSH(OP_TRUE) and bury them past coinbase maturity
+pub(crate) async fn fund_op_true_coins(client: &CoreRpcClient, count: usize) -> Result<Vec<Coin>> {
+    let descriptor = format!("raw({})", hex::encode(op_true_p2wsh()));
+    let hashes = client.generatetodescriptor(count as u64, &descriptor).await?;
+    let first_height = client.getblockcount().await? + 1 - hashes.len() as u64;
+    let mut coins = Vec::with_capacity(hashes.len());
+    for (i, hash) in hashes.iter().enumerate() {
+        let block_bytes = hex::decode(client.getblock_raw(hash).await?)?;
+        let coinbase = raw_transactions(&block_bytes)?.into_iter().next().context("Block has no coinbase")?;
+        coins.push(Coin {
+            is_coinbase: true,
+            height: first_height + i as u64,
+            ..Coin::unconfirmed(&coinbase, 0)
+        });
+    }
+    // OP_TRUE: no wallet needed to mine filler blocks
+    client.generatetodescriptor(COINBASE_MATURITY, "raw(51)").await?;
+    Ok(coins)
+}
+
+/// Spend `inputs` into `outputs` equal P2WSH(OP_TRUE) outputs at `fee_rate` sat/vB
+pub(crate) fn op_true_spend(inputs: &[Coin], outputs: usize, fee_rate: u64) -> RawTx {
+    op_true_spend_with(inputs, outputs, |vsize| fee_rate * vsize, 0xffff_fffd)
+}
+
+/// Spend with the fee computed from the final vsize and an explicit nSequence
+pub(crate) fn op_true_spend_with(inputs: &[Coin], outputs: usize, fee: impl Fn(u64) -> u64, sequence: u32) -> RawTx {
+    let mut tx = RawTx {
+        version: 2,
+        inputs: inputs
+            .iter()
+            .map(|coin| RawInput {
+                prevout: coin.prevout,
+                script_sig: Vec::new(),
+                sequence,
+                witness: vec![OP_TRUE_SCRIPT.to_vec()],
+            })
+            .collect(),
+        outputs: vec![(0, op_true_p2wsh()); outputs],
+        lock_time: 0,
+    };
+    // Output values don't change the size, so the fee can be set from the final vsize
+    let input_value: u64 = inputs.iter().map(|coin| coin.value).sum();
+    let each = input_value.saturating_sub(fee(tx.vsize())) / outputs as u64;
+    for output in &mut tx.outputs {
+        output.0 = each;
+    }
+    tx
+}
+
+/// Package shapes under test
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum PackageScenario {
+    /// Parent and child both above the minimum relay fee
+    ParentChild,
+    /// Zero-fee parent paid for by its child (CPFP)
+    CpfpZeroFeeParent,
+    /// Two independent parents, one child spending both
+    TwoParents,
+    /// A lone transaction submitted as a package
+    SingleTx,
+    /// Grandparent, parent, child: not child-with-parents
+    Grandparent,
+    /// Child listed before its parent
+    Unsorted,
+    /// The same parent listed twice
+    Duplicate,
+    /// Two parents spending the same coin
+    ConflictingParents,
+    /// Last transaction doesn't spend the first
+    Unrelated,
+    /// 25 parents plus a child (one over MAX_PACKAGE_COUNT)
+    TooManyTransactions,
+    /// Parent and child both paying nothing
+    ZeroFeePackage,
+}
+
+impl PackageScenario {
+    pub const ALL: [PackageScenario; 11] = [
+        PackageScenario::ParentChild,
+        PackageScenario::CpfpZeroFeeParent,
+        PackageScenario::TwoParents,
+        PackageScenario::SingleTx,
+        PackageScenario::Grandparent,
+        PackageScenario::Unsorted,
+        PackageScenario::Duplicate,
+        PackageScenario::ConflictingParents,
+        PackageScenario::Unrelated,
+        PackageScenario::TooManyTransactions,
+        PackageScenario::ZeroFeePackage,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            PackageScenario::ParentChild => "parent-child",
+            PackageScenario::CpfpZeroFeeParent => "cpfp-zero-fee-parent",
+            PackageScenario::TwoParents => "two-parents",
+            PackageScenario::SingleTx => "single-tx",
+            PackageScenario::Grandparent => "grandparent",
+            PackageScenario::Unsorted => "unsorted",
+            PackageScenario::Duplicate => "duplicate",
+            PackageScenario::ConflictingParents => "conflicting-parents",
+            PackageScenario::Unrelated => "unrelated",
+            PackageScenario::TooManyTransactions => "too-many-transactions",
+            PackageScenario::ZeroFeePackage => "zero-fee-package",
+        }
+    }
+
+    /// Confirmed coins the scenario spends
+    fn coins_needed(&self) -> usize {
+        match self {
+            PackageScenario::TwoParents | PackageScenario::Unrelated => 2,
+            PackageScenario::TooManyTransactions => MAX_PACKAGE_COUNT,
+            _ => 1,
+        }
+    }
+
+    /// The package, in submission order
+    fn build(&self, coins: &[Coin]) -> Vec<RawTx> {
+        let parent_child = |parent_rate: u64, child_rate: u64| {
+            let parent = op_true_spend(&coins[..1], 1, parent_rate);
+            let child = op_true_spend(&[Coin::unconfirmed(&parent, 0)], 1, child_rate);
+            (parent, child)
+        };
+        match self {
+            PackageScenario::ParentChild => {
+                let (parent, child) = parent_child(2, 5);
+                vec![parent, child]
+            }
+            PackageScenario::CpfpZeroFeeParent => {
+                let (parent, child) = parent_child(0, 50);
+                vec![parent, child]
+            }
+            PackageScenario::TwoParents => {
+                let parents: Vec<RawTx> = coins.iter().map(|coin| op_true_spend(std::slice::from_ref(coin), 1, 2)).collect();
+                let spends: Vec<Coin> = parents.iter().map(|p| Coin::unconfirmed(p, 0)).collect();
+                let child = op_true_spend(&spends, 1, 5);
+                parents.into_iter().chain([child]).collect()
+            }
+            PackageScenario::SingleTx => vec![op_true_spend(coins, 1, 2)],
+            PackageScenario::Grandparent => {
+                let (grandparent, parent) = parent_child(2, 2);
+                let child = op_true_spend(&[Coin::unconfirmed(&parent, 0)], 1, 5);
+                vec![grandparent, parent, child]
+            }
+            PackageScenario::Unsorted => {
+                let (parent, child) = parent_child(2, 5);
+                vec![child, parent]
+            }
+            PackageScenario::Duplicate => {
+                let (parent, child) = parent_child(2, 5);
+                vec![parent.clone(), parent, child]
+            }
+            PackageScenario::ConflictingParents => {
+                // Different output counts make two distinct transactions spending one coin
+                let first = op_true_spend(coins, 1, 2);
+                let second = op_true_spend(coins, 2, 3);
+                let child = op_true_spend(&[Coin::unconfirmed(&first, 0), Coin::unconfirmed(&second, 0)], 1, 5);
+                vec![first, second, child]
+            }
+            PackageScenario::Unrelated => coins.iter().map(|coin| op_true_spend(std::slice::from_ref(coin), 1, 2)).collect(),
+            PackageScenario::TooManyTransactions => {
+                let parents: Vec<RawTx> = coins.iter().map(|coin| op_true_spend(std::slice::from_ref(coin), 1, 2)).collect();
+                let spends: Vec<Coin> = parents.iter().map(|p| Coin::unconfirmed(p, 0)).collect();
+                let child = op_true_spend(&spends, 1, 5);
+                parents.into_iter().chain([child]).collect()
+            }
+            PackageScenario::ZeroFeePackage => {
+                let (parent, child) = parent_child(0, 0);
+                vec![parent, child]
+            }
+        }
+    }
+}
+
+/// Package topology rules, Core's order: well-formedness, then child-with-parents tree
+fn check_package_topology(package: &[RawTx]) -> Result<(), &'static str> {
+    if package.len() > MAX_PACKAGE_COUNT {
+        return Err("package-too-many-transactions");
+    }
+    let weight: u64 = package.iter().map(|tx| tx.vsize() * 4).sum();
+    if package.len() > 1 && weight > MAX_PACKAGE_WEIGHT {
+        return Err("package-too-large");
+    }
+    let txids: Vec<[u8; 32]> = package.iter().map(RawTx::txid).collect();
+    if txids.iter().collect::<HashSet<_>>().len() != txids.len() {
+        return Err("package-contains-duplicates");
+    }
+    // Sorted: nothing spends a transaction that comes after it
+    for (i, tx) in package.iter().enumerate() {
+        let later: HashSet<&[u8]> = txids[i + 1..].iter().map(|txid| &txid[..]).collect();
+        if tx.inputs.iter().any(|input| later.contains(&input.prevout[..32])) {
+            return Err("package-not-sorted");
+        }
+    }
+    let mut spent = HashSet::new();
+    if !package.iter().flat_map(|tx| &tx.inputs).all(|input| spent.insert(input.prevout)) {
+        return Err("conflict-in-package");
+    }
+    if package.len() == 1 {
+        return Ok(());
+    }
+
+    // Every other transaction is a parent of the last, and parents don't spend each other
+    let child = package.last().expect("non-empty");
+    let child_spends: HashSet<&[u8]> = child.inputs.iter().map(|input| &input.prevout[..32]).collect();
+    if !txids[..txids.len() - 1].iter().all(|txid| child_spends.contains(&txid[..])) {
+        return Err("package-not-child-with-parents");
+    }
+    let parent_txids: HashSet<&[u8]> = txids[..txids.len() - 1].iter().map(|txid| &txid[..]).collect();
+    let parents = &package[..package.len() - 1];
+    if parents.iter().flat_map(|tx| &tx.inputs).any(|input| parent_txids.contains(&input.prevout[..32])) {
+        return Err("package-not-child-with-parents-tree");
+    }
+    Ok(())
+}
+
+/// One side's verdict on a package
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct PackageVerdict {
+    pub accepted: bool,
+    pub reason: Option<String>,
+    /// True when the rejection is a package topology/well-formedness rule
+    pub topology_rejection: bool,
+    /// Base fee per txid (sats) for the transactions that were evaluated
+    pub fees: BTreeMap<String, i64>,
+}
+
+impl PackageVerdict {
+    pub fn package_fee(&self) -> i64 {
+        self.fees.values().sum()
+    }
+}
+
+/// BLVM's verdict: topology rules, each member through `accept_to_memory_pool`, then the aggregate fee-rate
+fn blvm_package_verdict(package: &[RawTx], coins: &[Coin], height: u64) -> PackageVerdict {
+    if let Err(rule) = check_package_topology(package) {
+        return PackageVerdict {
+            reason: Some(rule.to_string()),
+            topology_rejection: true,
+            ..Default::default()
+        };
+    }
+
+    let mut view = UtxoSet::new();
+    for coin in coins {
+        view.insert(
+            coin.outpoint(),
+            UTXO {
+                value: coin.value as _,
+                script_pubkey: op_true_p2wsh().into(),
+                height: coin.height as _,
+                is_coinbase: coin.is_coinbase,
+            },
+        );
+    }
+    let mempool: Mempool = HashSet::new();
+    let mut verdict = PackageVerdict::default();
+    let mut package_vsize = 0;
+    for raw in package {
+        let txid = txid_hex(&raw.txid());
+        let (tx, witness) = match decode_tx(&hex::encode(raw.to_bytes())) {
+            Ok(decoded) => decoded,
+            Err(e) => {
+                verdict.reason = Some(format!("{}: decode failed: {}", txid, e));
+                return verdict;
+            }
+        };
+        let input_value: Option<u64> = tx.inputs.iter().map(|i| view.get(&i.prevout).map(|u| u.value as u64)).sum();
+        let Some(input_value) = input_value else {
+            verdict.reason = Some(format!("{}: missing inputs", txid));
+            return verdict;
+        };
+        match accept_to_memory_pool(&tx, Some(&witness), &view, &mempool, height) {
+            Ok(MempoolResult::Accepted) => {}
+            Ok(result) => {
+                verdict.reason = Some(format!("{}: {:?}", txid, result));
+                return verdict;
+            }
+            Err(e) => {
+                verdict.reason = Some(format!("{}: error: {}", txid, e));
+                return verdict;
+            }
+        }
+        let output_value: u64 = raw.outputs.iter().map(|(value, _)| value).sum();
+        verdict.fees.insert(txid, input_value as i64 - output_value as i64);
+        package_vsize += raw.vsize();
+        for (vout, (value, script_pubkey)) in raw.outputs.iter().enumerate() {
+            view.insert(
+                Coin::unconfirmed(raw, vout).outpoint(),
+                UTXO {
+                    value: *value as _,
+                    script_pubkey: script_pubkey.clone().into(),
+                    height: height as _,
+                    is_coinbase: false,
+                },
+            );
+        }
+    }
+
+    // CPFP: only the package as a whole has to clear the minimum relay fee-rate
+    if (verdict.package_fee() as u64) < MIN_RELAY_FEE_RATE * package_vsize {
+        verdict.reason = Some(format!(
+            "package fee {} sats below {} sat/vB over {} vB",
+            verdict.package_fee(),
+            MIN_RELAY_FEE_RATE,
+            package_vsize
+        ));
+        return verdict;
+    }
+    verdict.accepted = true;
+    verdict
+}
+
+/// Core's verdict from `submitpackage` (an RPC error is a rejection of the whole package)
+async fn core_package_verdict(client: &CoreRpcClient, package: &[RawTx]) -> PackageVerdict {
+    let hexes: Vec<String> = package.iter().map(|tx| hex::encode(tx.to_bytes())).collect();
+    let result = match client.submitpackage(&hexes).await {
+        Ok(result) => result,
+        Err(e) => {
+            let reason = format!("{:#}", e);
+            return PackageVerdict {
+                topology_rejection: is_core_topology_rejection(&reason),
+                reason: Some(reason),
+                ..Default::default()
+            };
+        }
+    };
+
+    let message = result.get("package_msg").and_then(|m| m.as_str()).unwrap_or("").to_string();
+    let mut verdict = PackageVerdict {
+        accepted: message == "success",
+        ..Default::default()
+    };
+    let mut tx_errors = Vec::new();
+    if let Some(results) = result.get("tx-results").and_then(|r| r.as_object()) {
+        for entry in results.values() {
+            let txid = entry.get("txid").and_then(|t| t.as_str()).unwrap_or_default().to_string();
+            if let Some(error) = entry.get("error").and_then(|e| e.as_str()) {
+                tx_errors.push(format!("{}: {}", txid, error));
+            } else if let Some(fee) = entry.get("fees").and_then(|f| f.get("base")).and_then(|b| b.as_f64()) {
+                verdict.fees.insert(txid, btc_to_sats(fee));
+            }
+        }
+    }
+    if !verdict.accepted {
+        let reason = std::iter::once(message).chain(tx_errors).filter(|s| !s.is_empty()).collect::<Vec<_>>().join("; ");
+        verdict.topology_rejection = is_core_topology_rejection(&reason);
+        verdict.reason = Some(reason);
+    }
+    verdict
+}
+
+/// Core words package-shape rejections as "package-*" results, "conflict-in-package", or a topology RPC error
+fn is_core_topology_rejection(reason: &str) -> bool {
+    ["package-too-many", "package-too-large", "package-contains-duplicates", "package-not-", "conflict-in-package", "topology"]
+        .iter()
+        .any(|token| reason.contains(token))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum PackageDivergenceKind {
+    /// One side accepted the package, the other rejected it
+    Verdict,
+    /// One side rejected on a topology rule, the other didn't
+    Topology,
+    /// Both accepted but a member's base fee differs
+    TxFee,
+    /// Both accepted but the package's total fee differs
+    AggregateFee,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PackageOutcome {
+    pub scenario: PackageScenario,
+    pub blvm: PackageVerdict,
+    pub core: PackageVerdict,
+    pub divergences: Vec<(PackageDivergenceKind, String)>,
+}
+
+impl PackageOutcome {
+    fn new(scenario: PackageScenario, blvm: PackageVerdict, core: PackageVerdict) -> Self {
+        let mut divergences = Vec::new();
+        if blvm.topology_rejection != core.topology_rejection {
+            divergences.push((
+                PackageDivergenceKind::Topology,
+                format!("BLVM {} | Core {}", describe(&blvm), describe(&core)),
+            ));
+        } else if blvm.accepted != core.accepted {
+            divergences.push((
+                PackageDivergenceKind::Verdict,
+                format!("BLVM {} | Core {}", describe(&blvm), describe(&core)),
+            ));
+        }
+        if blvm.accepted && core.accepted {
+            for (txid, core_fee) in &core.fees {
+                match blvm.fees.get(txid) {
+                    Some(fee) if fee == core_fee => {}
+                    fee => divergences.push((
+                        PackageDivergenceKind::TxFee,
+                        format!("{}: BLVM {:?} sats, Core {} sats", txid, fee, core_fee),
+                    )),
+                }
+            }
+            if blvm.package_fee() != core.package_fee() {
+                divergences.push((
+                    PackageDivergenceKind::AggregateFee,
+                    format!("BLVM {} sats, Core {} sats", blvm.package_fee(), core.package_fee()),
+                ));
+            }
+        }
+        Self { scenario, blvm, core, divergences }
+    }
+
+    pub fn consistent(&self) -> bool {
+        self.divergences.is_empty()
+    }
+
+    fn print(&self) {
+        let icon = if self.consistent() { "✅" } else { "❌" };
+        println!(
+            "{} {:<24} BLVM={}, Core={}",
+            icon,
+            self.scenario.name(),
+            describe(&self.blvm),
+            describe(&self.core)
+        );
+        for (kind, detail) in &self.divergences {
+            println!("      {:?}: {}", kind, detail);
+        }
+    }
+}
+
+fn describe(verdict: &PackageVerdict) -> String {
+    if verdict.accepted {
+        format!("accepted ({} sats)", verdict.package_fee())
+    } else {
+        format!("rejected ({})", verdict.reason.as_deref().unwrap_or("no reason"))
+    }
+}
+
+/// Fund every scenario, then submit each package to Core and evaluate it with BLVM
+pub async fn run_package_differential(client: &CoreRpcClient) -> Result<Vec<PackageOutcome>> {
+    if client.detect_network().await? != BitcoinNetwork::Regtest {
+        anyhow::bail!("Package scenarios need a regtest node (they mine blocks and fill the mempool)");
+    }
+    let needed: usize = PackageScenario::ALL.iter().map(PackageScenario::coins_needed).sum();
+    let mut coins = fund_op_true_coins(client, needed).await?.into_iter();
+    let height = client.getblockcount().await? + 1;
+
+    let mut outcomes = Vec::new();
+    for scenario in PackageScenario::ALL {
+        let scenario_coins: Vec<Coin> = coins.by_ref().take(scenario.coins_needed()).collect();
+        let package = scenario.build(&scenario_coins);
+        // BLVM first: Core's verdict changes the mempool
+        let blvm = blvm_package_verdict(&package, &scenario_coins, height);
+        let core = core_package_verdict(client, &package).await;
+        let outcome = PackageOutcome::new(scenario, blvm, core);
+        outcome.print();
+        outcomes.push(outcome);
+    }
+
+    // Confirm whatever Core accepted so reruns start from an empty mempool
+    client.generatetodescriptor(1, "raw(51)").await?;
+    let diverged = outcomes.iter().filter(|o| !o.consistent()).count();
+    println!("📦 Package differential: {}/{} scenarios agree", outcomes.len() - diverged, outcomes.len());
+    Ok(outcomes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn coins(n: usize) -> Vec<Coin> {
+        (0..n)
+            .map(|i| Coin {
+                prevout: [i as u8 + 1; 36],
+                value: 50_000_000,
+                height: 1,
+                is_coinbase: true,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_topology_rules() {
+        let expected = [
+            (PackageScenario::ParentChild, Ok(())),
+            (PackageScenario::CpfpZeroFeeParent, Ok(())),
+            (PackageScenario::TwoParents, Ok(())),
+            (PackageScenario::SingleTx, Ok(())),
+            (PackageScenario::Grandparent, Err("package-not-child-with-parents")),
+            (PackageScenario::Unsorted, Err("package-not-sorted")),
+            (PackageScenario::Duplicate, Err("package-contains-duplicates")),
+            (PackageScenario::ConflictingParents, Err("conflict-in-package")),
+            (PackageScenario::Unrelated, Err("package-not-child-with-parents")),
+            (PackageScenario::TooManyTransactions, Err("package-too-many-transactions")),
+            (PackageScenario::ZeroFeePackage, Ok(())),
+        ];
+        for (scenario, rule) in expected {
+            let package = scenario.build(&coins(scenario.coins_needed()));
+            assert_eq!(check_package_topology(&package), rule, "{}", scenario.name());
+        }
+    }
+
+    #[test]
+    fn test_op_true_spend_fee() {
+        let tx = op_true_spend(&coins(1), 1, 5);
+        assert_eq!(tx.outputs[0].0, 50_000_000 - 5 * tx.vsize());
+        assert_eq!(tx.outputs[0].1, op_true_p2wsh());
+        // 1-in 1-out P2WSH(OP_TRUE) spend: 4 + 1 + 41 + 1 + 43 + 4 base, 2 + 1 + 2 witness
+        assert_eq!(tx.vsize(), 96);
+    }
+}
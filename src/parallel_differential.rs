@@ -7,6 +7,9 @@
 use anyhow::{Context, Result};
 use blvm_consensus::UtxoSet;
 use std::sync::Arc;
+use crate::block_hash::CachedBlockHash;
+use crate::block_source::BlockSource;
+use crate::utxo_backend::{UtxoBackend, UtxoStore};
 use tokio::sync::Semaphore;
 
 // Re-export block file reader for convenience
@@ -20,8 +23,62 @@ pub enum BlockDataSource {
     SharedCache(SharedBlockCache, Option<Arc<crate::core_rpc_client::CoreRpcClient>>),
     /// RPC fallback (slowest but always works)
     Rpc(Arc<crate::core_rpc_client::CoreRpcClient>),
-    /// Start9 RPC via nsenter (works when files are encrypted)
-    Start9Rpc(Arc<crate::start9_rpc_client::Start9RpcClient>),
+    /// Start9 RPC through one persistent nsenter or container exec shell (works when files are encrypted)
+    Start9Rpc(Arc<crate::start9_session::Start9Session>),
+    /// P2P peer via headers-first sync (no local Core installation needed)
+    P2p(Arc<crate::p2p_client::P2pClient>),
+    /// Memory-mapped cache shared by all workers (zero-copy reads)
+    MmapCache(crate::mmap_cache::MmapBlockCache),
+    /// Any other `BlockSource` implementation (S3, Esplora, custom archives)
+    Custom(Arc<dyn crate::block_source::BlockSource>),
+}
+
+impl BlockDataSource {
+    /// Short name for logs
+    pub fn name(&self) -> &str {
+        match self {
+            BlockDataSource::DirectFile(_) => "direct file",
+            BlockDataSource::SharedCache(..) => "shared cache",
+            BlockDataSource::Rpc(_) => "RPC",
+            BlockDataSource::Start9Rpc(_) => "Start9 RPC",
+            BlockDataSource::P2p(_) => "P2P",
+            BlockDataSource::MmapCache(_) => "mmap cache",
+            BlockDataSource::Custom(source) => source.name(),
+        }
+    }
+}
+
+impl std::fmt::Debug for BlockDataSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// Slower sources a failing chunk can be retried from, in the order to try them
+///
+/// DirectFile and the mmap cache fall back to the shared cache (if `cache_dir` is
+/// set) and then RPC; the shared cache falls back to RPC. RPC, Start9 and P2P
+/// sources have nothing slower and are simply retried.
+pub fn fallback_sources(
+    primary: &BlockDataSource,
+    cache_dir: Option<&std::path::Path>,
+    rpc_client: Option<Arc<crate::core_rpc_client::CoreRpcClient>>,
+) -> Vec<Arc<BlockDataSource>> {
+    let mut fallbacks = Vec::new();
+    if let (BlockDataSource::DirectFile(_) | BlockDataSource::MmapCache(_), Some(dir)) = (primary, cache_dir) {
+        match SharedBlockCache::new(dir) {
+            Ok(cache) => fallbacks.push(Arc::new(BlockDataSource::SharedCache(cache, rpc_client.clone()))),
+            Err(e) => eprintln!("⚠️  Shared cache {} unavailable as a retry source: {}", dir.display(), e),
+        }
+    }
+    let has_slower = matches!(
+        primary,
+        BlockDataSource::DirectFile(_) | BlockDataSource::MmapCache(_) | BlockDataSource::SharedCache(..)
+    );
+    if let (true, Some(client)) = (has_slower, rpc_client) {
+        fallbacks.push(Arc::new(BlockDataSource::Rpc(client)));
+    }
+    fallbacks
 }
 
 /// Configuration for parallel differential testing
@@ -31,8 +88,64 @@ pub struct ParallelConfig {
     pub num_workers: usize,
     /// Chunk size (blocks per chunk)
     pub chunk_size: u64,
-    /// Whether to use UTXO checkpoints (requires sequential pass first)
+    /// Equal-height chunks, or boundaries placed by estimated per-era cost
+    pub chunk_sizing: crate::chunk_sizing::ChunkSizing,
+    /// Whether to use UTXO checkpoints (requires sequential pass first); without
+    /// them every chunk after the first starts from an empty UTXO set
+    ///
+    /// Filling a block cache without validating is `cache_warm::warm_cache`.
     pub use_checkpoints: bool,
+    /// UTXO set implementation used for checkpoint generation and chunk validation
+    pub utxo_backend: UtxoBackend,
+    /// Where to write divergence reproducer bundles (None = disabled)
+    pub reproducer_dir: Option<std::path::PathBuf>,
+    /// Where to record accepted blocks with the coins they spend, for offline replay (None = disabled)
+    pub input_bundle_dir: Option<std::path::PathBuf>,
+    /// Where to quarantine blocks that panic BLVM (and the poisoned-chunk manifest)
+    pub quarantine_dir: std::path::PathBuf,
+    /// Extra Core versions every block is also checked against
+    pub core_endpoints: Vec<crate::core_versions::CoreEndpoint>,
+    /// Webhook to notify as soon as a divergence is recorded
+    pub alerts: Option<crate::alerts::AlertConfig>,
+    /// Validate only a seeded, per-era sample of heights (block-local checkpoints)
+    pub sample: Option<crate::sampler::SampleConfig>,
+    /// Blocks deserialized ahead of validation on the rayon pool (0 = inline)
+    pub decode_ahead: usize,
+    /// Blocks fetched ahead of validation for per-block sources like RPC (0 = inline)
+    pub prefetch_blocks: usize,
+    /// Where delta-encoded UTXO checkpoints are kept between runs
+    pub checkpoint_dir: std::path::PathBuf,
+    /// Sequential BLVM pass, or per-segment deltas scanned in parallel and merged
+    pub checkpoint_generation: crate::checkpoint_merge::CheckpointGeneration,
+    /// Skip checkpoints: chunks start from Core's undo data, checked against BLVM's state afterwards
+    pub optimistic: Option<crate::deferred_utxo::OptimisticChunks>,
+    /// Record per-stage timings of blocks slower than this (None = aggregates only)
+    pub slow_block_threshold: Option<std::time::Duration>,
+    /// Size of the slowest-blocks leaderboard (0 = off)
+    pub slowest_blocks: usize,
+    /// Profile blocks slower than a threshold and write per-block flamegraphs (None = off)
+    pub flamegraphs: Option<crate::flamegraph::FlamegraphConfig>,
+    /// Validation-only Core node whose submitblock verdict replaces the chain-membership check
+    pub verify_node: Option<crate::submit_verifier::SubmitVerifier>,
+    /// Compare fees and subsidy of accepted blocks with Core's getblockstats
+    pub fee_check: Option<crate::fee_check::FeeCheck>,
+    /// Triaged divergences that are recorded but don't fail the run
+    pub waivers: Option<Arc<crate::waivers::Waivers>>,
+    /// Times a chunk that fails part-way is resumed before giving up
+    pub chunk_retries: usize,
+    /// Sources to downgrade to on each retry (see `fallback_sources`)
+    pub fallback_sources: Vec<Arc<BlockDataSource>>,
+    /// In-flight cap and pacing for calls to the node behind `Rpc`/`Start9Rpc` sources,
+    /// shared by every chunk (and every clone of this config)
+    pub rpc_limiter: Arc<crate::rpc_limiter::RpcLimiter>,
+    /// Chain the blocks belong to (consensus rules BLVM validates them under)
+    pub network: BlockFileNetwork,
+    /// Chunk slots shared with runs on other networks (None = `num_workers` slots of its own;
+    /// see `multi_network`)
+    pub worker_pool: Option<Arc<Semaphore>>,
+    /// Append-only JSONL of chunk results and divergences as they happen
+    /// (opened from `BLVM_RESULTS_JSONL` when the run starts; see `result_stream`)
+    pub result_stream: Option<Arc<crate::result_stream::ResultStream>>,
 }
 
 impl Default for ParallelConfig {
@@ -40,18 +153,266 @@ impl Default for ParallelConfig {
         Self {
             num_workers: num_cpus::get(),
             chunk_size: 100_000, // 100k blocks per chunk
+            chunk_sizing: crate::chunk_sizing::ChunkSizing::from_env().unwrap_or_else(|e| {
+                eprintln!("⚠️  {}, using equal-height chunks", e);
+                crate::chunk_sizing::ChunkSizing::Equal
+            }),
             use_checkpoints: true,
+            utxo_backend: UtxoBackend::from_env().unwrap_or_default(),
+            reproducer_dir: Some(crate::reproducer::default_reproducer_dir()),
+            input_bundle_dir: crate::input_bundles::input_bundle_dir_from_env(),
+            quarantine_dir: crate::quarantine::default_quarantine_dir(),
+            core_endpoints: crate::core_versions::endpoints_from_env().unwrap_or_else(|e| {
+                eprintln!("⚠️  Ignoring BLVM_CORE_ENDPOINTS: {}", e);
+                Vec::new()
+            }),
+            alerts: crate::alerts::AlertConfig::from_env().unwrap_or_else(|e| {
+                eprintln!("⚠️  Alerts disabled: {}", e);
+                None
+            }),
+            sample: crate::sampler::SampleConfig::from_env().unwrap_or_else(|e| {
+                eprintln!("⚠️  Sample mode disabled: {}", e);
+                None
+            }),
+            decode_ahead: crate::decode_pipeline::decode_ahead_from_env().unwrap_or_else(|e| {
+                eprintln!("⚠️  {}, decoding inline", e);
+                0
+            }),
+            prefetch_blocks: crate::prefetch::prefetch_blocks_from_env().unwrap_or_else(|e| {
+                eprintln!("⚠️  {}, fetching inline", e);
+                0
+            }),
+            checkpoint_dir: crate::checkpoint_store::default_checkpoint_dir(),
+            checkpoint_generation: crate::checkpoint_merge::CheckpointGeneration::from_env().unwrap_or_else(|e| {
+                eprintln!("⚠️  {}, generating checkpoints sequentially", e);
+                crate::checkpoint_merge::CheckpointGeneration::Sequential
+            }),
+            optimistic: crate::deferred_utxo::OptimisticChunks::from_env(),
+            slow_block_threshold: slow_block_threshold_from_env().unwrap_or_else(|e| {
+                eprintln!("⚠️  {}, not recording slow blocks", e);
+                None
+            }),
+            slowest_blocks: crate::slow_blocks::leaderboard_size_from_env().unwrap_or_else(|e| {
+                eprintln!("⚠️  {}, keeping {} slowest blocks", e, crate::slow_blocks::DEFAULT_LEADERBOARD_SIZE);
+                crate::slow_blocks::DEFAULT_LEADERBOARD_SIZE
+            }),
+            flamegraphs: crate::flamegraph::FlamegraphConfig::from_env().unwrap_or_else(|e| {
+                eprintln!("⚠️  Flamegraphs disabled: {}", e);
+                None
+            }),
+            verify_node: crate::submit_verifier::SubmitVerifier::from_env().unwrap_or_else(|e| {
+                eprintln!("⚠️  Ignoring verify node: {}", e);
+                None
+            }),
+            fee_check: crate::fee_check::FeeCheck::from_env(),
+            waivers: crate::waivers::Waivers::from_env().unwrap_or_else(|e| {
+                eprintln!("⚠️  Ignoring waivers: {}", e);
+                None
+            }).map(Arc::new),
+            chunk_retries: chunk_retries_from_env().unwrap_or_else(|e| {
+                eprintln!("⚠️  {}, retrying failed chunks {} times", e, DEFAULT_CHUNK_RETRIES);
+                DEFAULT_CHUNK_RETRIES
+            }),
+            fallback_sources: Vec::new(),
+            rpc_limiter: Arc::new(crate::rpc_limiter::RpcLimiter::new(
+                crate::rpc_limiter::RpcLimits::from_env().unwrap_or_else(|e| {
+                    eprintln!("⚠️  {}, using default RPC limits", e);
+                    crate::rpc_limiter::RpcLimits::default()
+                }),
+            )),
+            network: BlockFileNetwork::Mainnet,
+            worker_pool: None,
+            result_stream: None,
         }
     }
 }
 
+impl ParallelConfig {
+    /// Chunk over `start_height..=end_height` carrying this run's settings
+    pub(crate) fn chunk(
+        &self,
+        start_height: u64,
+        end_height: u64,
+        checkpoint_utxo: Option<UtxoSet>,
+        progress: Arc<crate::progress::RunProgress>,
+    ) -> BlockChunk {
+        BlockChunk {
+            start_height,
+            end_height,
+            checkpoint_utxo,
+            utxo_backend: self.utxo_backend.clone(),
+            reproducer_dir: self.reproducer_dir.clone(),
+            input_bundle_dir: self.input_bundle_dir.clone(),
+            quarantine_dir: self.quarantine_dir.clone(),
+            core_endpoints: self.core_endpoints.clone(),
+            alerts: self.alerts.clone(),
+            progress,
+            decode_ahead: self.decode_ahead,
+            prefetch_blocks: self.prefetch_blocks,
+            slow_block_threshold: self.slow_block_threshold,
+            slowest_blocks: self.slowest_blocks,
+            flamegraphs: self.flamegraphs.clone(),
+            verify_node: self.verify_node.clone(),
+            fee_check: self.fee_check.clone(),
+            waivers: self.waivers.clone(),
+            rpc_limiter: self.rpc_limiter.clone(),
+            optimistic: self.optimistic.clone(),
+            network: self.network,
+            result_stream: self.result_stream.clone(),
+        }
+    }
+}
+
+/// BLVM's consensus parameters for blocks of `network`
+pub fn consensus_network(network: BlockFileNetwork) -> Result<blvm_consensus::types::Network> {
+    match network {
+        BlockFileNetwork::Mainnet => Ok(blvm_consensus::types::Network::Mainnet),
+        BlockFileNetwork::Testnet => Ok(blvm_consensus::types::Network::Testnet),
+        BlockFileNetwork::Regtest => Ok(blvm_consensus::types::Network::Regtest),
+        BlockFileNetwork::Signet => anyhow::bail!("signet not supported by blvm-consensus (it has no signet parameters)"),
+    }
+}
+
+/// Default number of retries for a chunk that fails part-way
+pub const DEFAULT_CHUNK_RETRIES: usize = 2;
+
+/// Chunk retries from `BLVM_CHUNK_RETRIES`
+pub fn chunk_retries_from_env() -> Result<usize> {
+    match std::env::var("BLVM_CHUNK_RETRIES") {
+        Ok(n) => n.parse().with_context(|| format!("Invalid BLVM_CHUNK_RETRIES '{}'", n)),
+        Err(_) => Ok(DEFAULT_CHUNK_RETRIES),
+    }
+}
+
+/// Slow-block threshold from `BLVM_SLOW_BLOCK_MS`
+pub fn slow_block_threshold_from_env() -> Result<Option<std::time::Duration>> {
+    match std::env::var("BLVM_SLOW_BLOCK_MS") {
+        Ok(ms) => Ok(Some(std::time::Duration::from_millis(
+            ms.parse().with_context(|| format!("Invalid BLVM_SLOW_BLOCK_MS '{}'", ms))?,
+        ))),
+        Err(_) => Ok(None),
+    }
+}
+
+/// Time spent in each stage of block processing, in seconds
+///
+/// With decode-ahead, deserialization runs on the rayon pool alongside
+/// validation, so the stages can add up to more than the chunk's wall time.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct StageTimings {
+    /// Fetching raw block bytes (files, cache, RPC or peer)
+    pub read_secs: f64,
+    pub deserialize_secs: f64,
+    /// BLVM `connect_block`
+    pub connect_secs: f64,
+    /// Asking Core whether the block is in its chain (RPC sources)
+    pub core_check_secs: f64,
+}
+
+impl StageTimings {
+    pub fn add(&mut self, other: &StageTimings) {
+        self.read_secs += other.read_secs;
+        self.deserialize_secs += other.deserialize_secs;
+        self.connect_secs += other.connect_secs;
+        self.core_check_secs += other.core_check_secs;
+    }
+
+    pub fn total_secs(&self) -> f64 {
+        self.read_secs + self.deserialize_secs + self.connect_secs + self.core_check_secs
+    }
+
+    /// One-line breakdown with each stage's share of the total
+    pub fn summary(&self) -> String {
+        let total = self.total_secs().max(f64::EPSILON);
+        [
+            ("read", self.read_secs),
+            ("deserialize", self.deserialize_secs),
+            ("connect", self.connect_secs),
+            ("Core check", self.core_check_secs),
+        ]
+        .iter()
+        .map(|(stage, secs)| format!("{} {:.1}s ({:.0}%)", stage, secs, 100.0 * secs / total))
+        .collect::<Vec<_>>()
+        .join(", ")
+    }
+}
+
+/// Blocks processed and the time spent on them, per consensus era
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct EraTimings {
+    /// (blocks, seconds) indexed like `Era::ALL`
+    per_era: [(u64, f64); 4],
+}
+
+impl EraTimings {
+    pub fn record(&mut self, height: u64, secs: f64) {
+        let era = crate::sampler::Era::of(height);
+        let slot = &mut self.per_era[crate::sampler::Era::ALL.iter().position(|e| *e == era).unwrap_or(0)];
+        slot.0 += 1;
+        slot.1 += secs;
+    }
+
+    pub fn add(&mut self, other: &EraTimings) {
+        for (slot, (blocks, secs)) in self.per_era.iter_mut().zip(other.per_era) {
+            slot.0 += blocks;
+            slot.1 += secs;
+        }
+    }
+
+    /// (era, blocks, seconds) for eras with at least one block
+    pub fn eras(&self) -> impl Iterator<Item = (crate::sampler::Era, u64, f64)> + '_ {
+        crate::sampler::Era::ALL
+            .into_iter()
+            .zip(self.per_era)
+            .filter(|(_, (blocks, _))| *blocks > 0)
+            .map(|(era, (blocks, secs))| (era, blocks, secs))
+    }
+}
+
+/// Stage timings of a block slower than the configured threshold
+#[derive(Debug, Clone)]
+pub struct BlockTiming {
+    pub height: u64,
+    pub stages: StageTimings,
+}
+
 /// Chunk of blocks to validate
 #[derive(Debug, Clone)]
 pub struct BlockChunk {
     pub start_height: u64,
     pub end_height: u64,
     pub checkpoint_utxo: Option<UtxoSet>,
-    pub skip_validation: bool, // If true, just read blocks for cache building, don't validate
+    pub utxo_backend: UtxoBackend,
+    pub reproducer_dir: Option<std::path::PathBuf>,
+    pub input_bundle_dir: Option<std::path::PathBuf>,
+    pub quarantine_dir: std::path::PathBuf,
+    pub core_endpoints: Vec<crate::core_versions::CoreEndpoint>,
+    pub alerts: Option<crate::alerts::AlertConfig>,
+    pub progress: Arc<crate::progress::RunProgress>,
+    pub decode_ahead: usize,
+    pub prefetch_blocks: usize,
+    pub slow_block_threshold: Option<std::time::Duration>,
+    pub slowest_blocks: usize,
+    pub flamegraphs: Option<crate::flamegraph::FlamegraphConfig>,
+    pub verify_node: Option<crate::submit_verifier::SubmitVerifier>,
+    pub fee_check: Option<crate::fee_check::FeeCheck>,
+    pub waivers: Option<Arc<crate::waivers::Waivers>>,
+    pub rpc_limiter: Arc<crate::rpc_limiter::RpcLimiter>,
+    /// Start from Core's undo data instead of `checkpoint_utxo` and keep the end set
+    pub optimistic: Option<crate::deferred_utxo::OptimisticChunks>,
+    pub network: BlockFileNetwork,
+    pub result_stream: Option<Arc<crate::result_stream::ResultStream>>,
+}
+
+impl BlockChunk {
+    /// Whether blocks' spent coins must be collected before connecting them
+    /// (reproducers, input bundles, fee checks and flamegraph re-runs need them)
+    fn captures_pre_state(&self) -> bool {
+        self.reproducer_dir.is_some()
+            || self.input_bundle_dir.is_some()
+            || self.fee_check.is_some()
+            || self.flamegraphs.is_some()
+    }
 }
 
 /// Result from validating a chunk
@@ -62,7 +423,142 @@ pub struct ChunkResult {
     pub tested: usize,
     pub matched: usize,
     pub divergences: Vec<(u64, String, String)>, // (height, blvm_result, core_result)
+    /// Divergences covered by a waiver: (height, waiver description)
+    pub waived: Vec<(u64, String)>,
+    /// Blocks where BLVM and the configured Core versions don't all agree
+    pub version_divergences: Vec<crate::core_versions::VersionDivergence>,
     pub duration_secs: f64,
+    /// UTXO set size after the chunk's last block
+    pub utxo_count: usize,
+    /// When the chunk finished (for throughput-over-time reporting)
+    pub finished_at: std::time::SystemTime,
+    /// Set if BLVM panicked; the chunk stopped at that block
+    pub poisoned: Option<crate::quarantine::BlockPanic>,
+    /// Time spent per stage across the chunk
+    pub stage_timings: StageTimings,
+    /// Blocks and per-block processing time by consensus era
+    pub era_timings: EraTimings,
+    /// Blocks slower than `slow_block_threshold`, in height order
+    pub slow_blocks: Vec<BlockTiming>,
+    /// The chunk's slowest blocks by connect time
+    pub slowest: crate::slow_blocks::Leaderboard,
+    /// RSS and `connect_block` allocations when the chunk finished
+    pub memory: crate::mem_profile::ChunkMemory,
+    /// Times the chunk was resumed after failing part-way
+    pub retries: usize,
+    /// Optimistic mode: the undo-derived set the chunk started from
+    pub assumed_utxo: Option<UtxoSet>,
+    /// Optimistic mode: BLVM's UTXO set after the chunk's last block
+    pub end_utxo: Option<UtxoSet>,
+    /// Set if the chunk failed or was cancelled before comparing all its blocks
+    pub failed: Option<String>,
+    /// Heights a failed chunk never compared (all of them if it produced nothing)
+    pub untested: Option<(u64, u64)>,
+}
+
+impl ChunkResult {
+    /// A chunk that never produced a result (failed, cancelled, or panicked outside a block)
+    pub fn incomplete(start_height: u64, end_height: u64, failed: Option<String>, poisoned: Option<crate::quarantine::BlockPanic>) -> Self {
+        Self {
+            start_height,
+            end_height,
+            tested: 0,
+            matched: 0,
+            divergences: Vec::new(),
+            waived: Vec::new(),
+            version_divergences: Vec::new(),
+            duration_secs: 0.0,
+            utxo_count: 0,
+            finished_at: std::time::SystemTime::now(),
+            poisoned,
+            stage_timings: StageTimings::default(),
+            era_timings: EraTimings::default(),
+            slow_blocks: Vec::new(),
+            slowest: Default::default(),
+            memory: Default::default(),
+            retries: 0,
+            assumed_utxo: None,
+            end_utxo: None,
+            untested: failed.as_ref().map(|_| (start_height, end_height)),
+            failed,
+        }
+    }
+
+    /// Fold in the result of the next attempt at the same chunk (which resumed where this one stopped)
+    fn absorb(&mut self, later: ChunkResult) {
+        self.end_height = later.end_height;
+        self.tested += later.tested;
+        self.matched += later.matched;
+        self.divergences.extend(later.divergences);
+        self.waived.extend(later.waived);
+        self.version_divergences.extend(later.version_divergences);
+        self.duration_secs += later.duration_secs;
+        self.utxo_count = later.utxo_count;
+        self.finished_at = later.finished_at;
+        self.poisoned = later.poisoned;
+        self.stage_timings.add(&later.stage_timings);
+        self.era_timings.add(&later.era_timings);
+        self.slow_blocks.extend(later.slow_blocks);
+        self.slowest.merge(&later.slowest);
+        self.memory = later.memory;
+        self.end_utxo = later.end_utxo;
+        self.failed = later.failed;
+        self.untested = later.untested;
+    }
+
+    /// Divergences no waiver covers (the ones that fail a run)
+    pub fn unwaived_divergences(&self) -> impl Iterator<Item = &(u64, String, String)> {
+        self.divergences
+            .iter()
+            .filter(|(height, _, _)| !self.waived.iter().any(|(waived, _)| waived == height))
+    }
+}
+
+/// Print GitHub Actions annotations for a run's divergences, panics and expired waivers
+fn annotate_results(config: &ParallelConfig, results: &[ChunkResult]) {
+    use crate::gh_annotations::{emit, Annotation, Level};
+
+    for result in results {
+        for (height, blvm, core) in &result.divergences {
+            let waiver = result.waived.iter().find(|(waived, _)| waived == height);
+            let mut annotation = match waiver {
+                Some((_, reason)) => Annotation::new(
+                    Level::Warning,
+                    format!("Waived divergence at height {}", height),
+                    format!("BLVM={}, Core={} (waived: {})", blvm, core, reason),
+                ),
+                None => Annotation::new(
+                    Level::Error,
+                    format!("Consensus divergence at height {}", height),
+                    format!("BLVM={}, Core={}", blvm, core),
+                ),
+            };
+            if let Some(dir) = &config.reproducer_dir {
+                let meta = dir.join(format!("height_{}", height)).join("meta.json");
+                if meta.exists() {
+                    annotation = annotation.with_file(meta.display().to_string());
+                }
+            }
+            emit(&annotation);
+        }
+        for divergence in &result.version_divergences {
+            emit(&Annotation::new(Level::Warning, "Core version divergence", divergence.to_string()));
+        }
+        if let Some(panic) = &result.poisoned {
+            let annotation = Annotation::new(
+                Level::Error,
+                format!("BLVM panicked in chunk {}-{}", result.start_height, result.end_height),
+                panic.to_string(),
+            );
+            emit(&match &panic.artifact_dir {
+                Some(dir) => annotation.with_file(dir.display().to_string()),
+                None => annotation,
+            });
+        }
+    }
+    for waiver in config.waivers.iter().flat_map(|waivers| waivers.expired()) {
+        emit(&Annotation::new(Level::Warning, "Expired divergence waiver", waiver.describe()));
+    }
 }
 
 /// Create optimized block data source
@@ -74,44 +570,76 @@ pub fn create_block_data_source(
     cache_dir: Option<impl AsRef<std::path::Path>>,
     rpc_client: Option<Arc<crate::core_rpc_client::CoreRpcClient>>,
 ) -> Result<BlockDataSource> {
-    // Try direct file reading first (fastest - 10-50x faster than RPC)
-    // Check common locations - standard Bitcoin Core paths first, Start9 as fallback
-    let possible_dirs = vec![
-        dirs::home_dir().map(|h| h.join(".bitcoin")), // Standard local Bitcoin Core (default)
-        Some(std::path::PathBuf::from("/root/.bitcoin")),
-        Some(std::path::PathBuf::from("/var/lib/bitcoind")),
-        // Start9 paths (fallback for local testing only)
-        dirs::home_dir().map(|h| h.join("mnt/bitcoin-start9")),
-        Some(std::path::PathBuf::from("/mnt/bitcoin-start9")),
-    ];
+    // A prebuilt mmap cache beats everything: random access with zero-copy reads
+    let cache_dir = cache_dir.map(|d| d.as_ref().to_path_buf());
+    if let Some(mmap_dir) = crate::mmap_cache::default_mmap_dir(cache_dir.as_deref()) {
+        if crate::mmap_cache::MmapBlockCache::exists(&mmap_dir) {
+            let cache = crate::mmap_cache::MmapBlockCache::open(&mmap_dir)?;
+            println!("✅ Using mmap block cache {} (heights {}-{}, zero-copy)", mmap_dir.display(), cache.start_height(), cache.end_height());
+            return Ok(BlockDataSource::MmapCache(cache));
+        }
+    }
     
-    // Try direct file reading first (including Start9 mount - fixing XOR decryption!)
-    for dir in possible_dirs.into_iter().flatten() {
-        if dir.join("blocks").exists() {
-            // Try to create reader - may fail due to permissions or format issues
-            match BlockFileReader::new(&dir, network) {
-                Ok(reader) => {
-                    let is_start9 = dir.to_string_lossy().contains("bitcoin-start9");
-                    if is_start9 {
-                        println!("✅ Using direct block file reading from Start9 mount {} (10-50x faster than RPC, XOR decryption enabled)", dir.display());
-                    } else {
-                        println!("✅ Using direct block file reading from {} (10-50x faster than RPC)", dir.display());
-                    }
-                    return Ok(BlockDataSource::DirectFile(reader));
+    // A pruned node's blk files and RPC stop at its prune height: with a fallback configured,
+    // serve what the node has over RPC and the rest from the fallback
+    if let Some(client) = &rpc_client {
+        if let Some(fallback) = crate::pruned_source::fallback_from_env(network, cache_dir.as_deref())? {
+            println!("✅ Using RPC with {} for pruned heights (BLVM_PRUNED_FALLBACK)", fallback.name());
+            let source = crate::pruned_source::PrunedSource::new(client.clone(), fallback);
+            return Ok(BlockDataSource::Custom(Arc::new(source)));
+        }
+    }
+    
+    // Try direct file reading first (fastest - 10-50x faster than RPC)
+    // Override, running bitcoind, bitcoin.conf, then standard paths (Start9 mounts last)
+    let mut attempts = Vec::new();
+    for candidate in crate::datadir::candidates(network, None) {
+        let dir = candidate.dir.clone();
+        if !dir.join("blocks").exists() {
+            attempts.push((candidate, "no blocks/ directory".to_string()));
+            continue;
+        }
+        // Try to create reader - may fail due to permissions or format issues
+        let is_start9 = dir.to_string_lossy().contains("bitcoin-start9");
+        match BlockFileReader::new(&dir, network) {
+            Ok(reader) => {
+                if is_start9 {
+                    println!("✅ Using direct block file reading from Start9 mount {} (10-50x faster than RPC, XOR decryption enabled)", dir.display());
+                } else {
+                    println!("✅ Using direct block file reading from {} via {} (10-50x faster than RPC)", dir.display(), candidate.origin);
                 }
-                Err(e) => {
-                    // Log but continue trying other locations
-                    let is_start9 = dir.to_string_lossy().contains("bitcoin-start9");
-                    if is_start9 {
-                        println!("⚠️  Direct file reading from Start9 mount failed: {}. Will try RPC fallback.", e);
-                    } else {
-                        eprintln!("⚠️  Direct file reading from {} failed: {}. Will try other options.", dir.display(), e);
-                    }
-                    continue;
+                return Ok(BlockDataSource::DirectFile(reader));
+            }
+            Err(e) => {
+                // Log but continue trying other locations
+                if is_start9 {
+                    println!("⚠️  Direct file reading from Start9 mount failed: {}. Will try RPC fallback.", e);
+                } else {
+                    eprintln!("⚠️  Direct file reading from {} failed: {}. Will try other options.", dir.display(), e);
                 }
+                attempts.push((candidate, e.to_string()));
             }
         }
     }
+    let tried = crate::datadir::describe_attempts(&attempts);
+    if crate::datadir::datadir_override().is_some() {
+        // An explicitly chosen datadir that doesn't work is a mistake, not a reason to fall back to RPC
+        anyhow::bail!("No readable block files in the configured data directory. Tried:\n{}", tried);
+    }
+    if !attempts.is_empty() {
+        println!("📂 No readable block files found. Tried:\n{}", tried);
+    }
+    
+    // A chunked cache (built locally or pulled from a cache archive) needs no node
+    if let Some(chunks_dir) = crate::chunked_cache::get_chunks_dir() {
+        if let Some(cache) = crate::chunked_cache::ChunkedCacheSource::open(&chunks_dir)? {
+            // With trusted keys configured, only a cache whose files hash to a signed manifest is used
+            let trusted = crate::cache_manifest::trusted_keys_from_env()?;
+            crate::cache_manifest::verify_trusted_cache(&chunks_dir, &trusted)?;
+            println!("✅ Using chunked block cache {} ({} blocks)", chunks_dir.display(), cache.metadata().total_blocks);
+            return Ok(BlockDataSource::Custom(Arc::new(cache)));
+        }
+    }
     
     // If Start9 mount exists but direct reading failed, try Start9 RPC as fallback
     let start9_mount = dirs::home_dir().map(|h| h.join("mnt/bitcoin-start9"));
@@ -119,10 +647,32 @@ pub fn create_block_data_source(
         .map(|p| p.exists())
         .unwrap_or(false);
     
+    // An explicitly named container is reached with podman/docker exec (no host privileges needed)
+    if crate::container_rpc_client::ContainerTarget::configured() {
+        let client = crate::container_rpc_client::ContainerRpcClient::from_env()?;
+        println!("✅ Using RPC via `{}`, {} blocks per batch", client.target().exec_shell().join(" "), client.session().batch_size());
+        return Ok(BlockDataSource::Start9Rpc(client.session()));
+    }
+    
     if is_start9 {
-        let start9_client = Arc::new(crate::start9_rpc_client::Start9RpcClient::new());
-        println!("✅ Using Start9 RPC via nsenter (fallback - direct file reading unavailable)");
-        return Ok(BlockDataSource::Start9Rpc(start9_client));
+        // Prefer exec into the container; nsenter needs root (or an explicit BLVM_START9_SHELL/PID)
+        let nsenter_configured = std::env::var("BLVM_START9_SHELL").is_ok() || std::env::var("BLVM_START9_PID").is_ok();
+        if !nsenter_configured {
+            match crate::container_rpc_client::ContainerRpcClient::from_env() {
+                Ok(client) => {
+                    println!("✅ Using Start9 RPC via `{}`, {} blocks per batch (fallback - direct file reading unavailable)", client.target().exec_shell().join(" "), client.session().batch_size());
+                    return Ok(BlockDataSource::Start9Rpc(client.session()));
+                }
+                Err(e) => eprintln!("⚠️  No container exec for Start9 RPC ({:#}), trying nsenter", e),
+            }
+        }
+        match crate::start9_session::Start9Session::from_env() {
+            Ok(session) => {
+                println!("✅ Using Start9 RPC via a persistent nsenter session, {} blocks per batch (fallback - direct file reading unavailable)", session.batch_size());
+                return Ok(BlockDataSource::Start9Rpc(Arc::new(session)));
+            }
+            Err(e) => eprintln!("⚠️  Start9 mount found but no RPC session: {:#}", e),
+        }
     }
     
     // Try shared cache (fast on subsequent runs, can use DirectFile or RPC to populate)
@@ -139,87 +689,152 @@ pub fn create_block_data_source(
         return Ok(BlockDataSource::Rpc(client));
     }
     
-    anyhow::bail!("No block data source available. Need Core data directory, cache directory, or RPC client.")
+    // Last resort: a P2P peer (no local Core needed at all)
+    if let Some(p2p_config) = crate::p2p_client::P2pConfig::from_env(network) {
+        println!("✅ Using P2P peer {} (headers-first sync, no local Core required)", p2p_config.peer);
+        let client = crate::p2p_client::P2pClient::new(p2p_config);
+        return Ok(BlockDataSource::P2p(Arc::new(client)));
+    }
+    
+    // Or a public/self-hosted Esplora API (no node at all; small ranges only)
+    if let Some(esplora_config) = crate::esplora_source::EsploraConfig::from_env(network)? {
+        let source = crate::esplora_source::EsploraSource::new(esplora_config)?;
+        println!("✅ Using Esplora API {} (paced HTTP requests - best for small ranges)", source.base_url());
+        return Ok(BlockDataSource::Custom(Arc::new(source)));
+    }
+    
+    anyhow::bail!(
+        "No block data source available. Need Core data directory (--datadir or BITCOIN_DATA_DIR), cache directory, RPC client, BLVM_CONTAINER, BITCOIN_P2P_PEER, or BLVM_ESPLORA_URL.\nData directories tried:\n{}",
+        tried
+    )
 }
 
 /// Get block data from optimized source
 pub async fn get_block_data(
     source: &BlockDataSource,
     height: u64,
+) -> Result<Vec<u8>> {
+    source.get_block(height).await
+}
+
+/// Run one call to `source`'s node through the shared RPC limiter
+///
+/// The call counts as overloaded if Core turned any call away for a full work
+/// queue meanwhile (the client retries those itself) or if it failed with one.
+async fn rpc_limited<T>(
+    limiter: &crate::rpc_limiter::RpcLimiter,
+    source: &BlockDataSource,
+    call: impl std::future::Future<Output = Result<T>>,
+) -> Result<T> {
+    let rejections = || match source {
+        BlockDataSource::Rpc(client) | BlockDataSource::SharedCache(_, Some(client)) => client.work_queue_rejections(),
+        _ => 0,
+    };
+    let permit = limiter.acquire().await;
+    let before = rejections();
+    let result = call.await;
+    let overloaded = rejections() > before
+        || result.as_ref().err().is_some_and(crate::rpc_limiter::is_work_queue_error);
+    permit.finish(overloaded);
+    result
+}
+
+/// `get_block_data`, through the shared RPC limiter for sources backed by a node's RPC
+pub(crate) async fn fetch_block(
+    source: &BlockDataSource,
+    height: u64,
+    limiter: &crate::rpc_limiter::RpcLimiter,
 ) -> Result<Vec<u8>> {
     match source {
-        BlockDataSource::DirectFile(reader) => {
-            // For direct file reading, we need to iterate sequentially
-            // This is a limitation - we'll need to cache blocks or use index
-            // For now, fall back to RPC for random access
-            anyhow::bail!("Direct file reading requires sequential access. Use generate_checkpoints_sequential or provide RPC client for random access.")
-        }
-        BlockDataSource::SharedCache(cache, rpc_client) => {
-            cache.get_or_fetch_block(height, rpc_client.as_deref()).await
-        }
-        BlockDataSource::Rpc(client) => {
-            let block_hash = client.getblockhash(height).await?;
-            let block_hex = client.getblock_raw(&block_hash).await?;
-            Ok(hex::decode(&block_hex)?)
-        }
-        BlockDataSource::Start9Rpc(client) => {
-            let block_hash = client.get_block_hash(height).await?;
-            let block_hex = client.get_block_hex(&block_hash).await?;
-            Ok(hex::decode(&block_hex)?)
+        BlockDataSource::Rpc(_) | BlockDataSource::Start9Rpc(_) => {
+            rpc_limited(limiter, source, get_block_data(source, height)).await
         }
+        other => get_block_data(other, height).await,
     }
 }
 
 /// Generate UTXO checkpoints at chunk boundaries
 /// 
 /// This runs sequentially to build up UTXO state, then saves checkpoints
-/// at chunk boundaries for parallel execution.
+/// at chunk boundaries into `store` for parallel execution. Returns the
+/// checkpoint heights.
 /// 
 /// Uses optimized block data source (direct file reading if available).
 pub async fn generate_checkpoints(
     start_height: u64,
     end_height: u64,
-    chunk_size: u64,
+    checkpoint_heights: &[u64],
     block_source: &BlockDataSource,
-) -> Result<Vec<(u64, UtxoSet)>> {
-    use blvm_consensus::block::connect_block;
-    use blvm_consensus::segwit::Witness;
+    utxo_backend: &UtxoBackend,
+    network: BlockFileNetwork,
+    store: &mut crate::checkpoint_store::CheckpointStore,
+) -> Result<Vec<u64>> {
     use blvm_consensus::serialization::block::deserialize_block_with_witnesses;
-    use blvm_consensus::types::Network;
 
-    // OPTIMIZATION: Pre-allocate checkpoints vector (estimate: ~10 checkpoints for 1M blocks)
-    let estimated_checkpoints = ((end_height - start_height) / chunk_size + 1) as usize;
-    let mut checkpoints = Vec::with_capacity(estimated_checkpoints.min(100));
-    let mut utxo_set = UtxoSet::new();
+    let mut checkpoints = Vec::with_capacity(checkpoint_heights.len() + 1);
+    let mut utxo_store = utxo_backend.create(UtxoSet::new())?;
+    store.reset(start_height)?;
     let mut previous_block_hash: Option<[u8; 32]> = None; // Track previous block hash for verification
     
     // If starting from height 0, we start with empty UTXO set
     // Otherwise, we'd need to load from a previous checkpoint
     
-    // Get chain height (need RPC for this)
-    let chain_height = match block_source {
-        BlockDataSource::Rpc(client) => client.getblockcount().await?,
-        BlockDataSource::Start9Rpc(client) => client.get_block_count().await?,
-        BlockDataSource::SharedCache(_, Some(client)) => client.getblockcount().await?,
-        _ => {
-            // For direct file reading, we don't know chain height
-            // Use end_height as estimate
-            end_height
-        }
-    };
+    // Get chain height
+    // Cache without RPC doesn't know the chain height; use end_height as estimate
+    let chain_height = source_tip(block_source, end_height).await?;
     let actual_end = end_height.min(chain_height);
     
-    println!("🔧 Generating UTXO checkpoints from {} to {} (chunk size: {})", 
-             start_height, actual_end, chunk_size);
+    println!("🔧 Generating {} UTXO checkpoints from {} to {} (UTXO backend: {})", 
+             checkpoint_heights.len(), start_height, actual_end, utxo_store.name());
     
-    let mut next_checkpoint = start_height + chunk_size;
+    let is_checkpoint = |height: u64| height == actual_end || checkpoint_heights.binary_search(&height).is_ok();
+    
+    // Validate a block with BLVM, then save a checkpoint if one falls at its height
+    let mut connect = |height: u64, block_bytes: &[u8], block: &blvm_consensus::Block, witnesses: &[blvm_consensus::segwit::Witness]| -> Result<()> {
+        if crate::block_debug::enabled(height) {
+            crate::block_debug::dump_block(height, block_bytes, block, utxo_store.as_ref());
+        }
+        
+        // Validate with BLVM
+        let result = utxo_store.connect_block(block, witnesses, height, consensus_network(network)?)?;
+        
+        if !matches!(result, blvm_consensus::types::ValidationResult::Valid) {
+            // OPTIMIZATION: Use string reference instead of clone
+            let error_msg = match &result {
+                blvm_consensus::types::ValidationResult::Invalid(msg) => msg.as_str(),
+                _ => "Unknown error",
+            };
+            eprintln!("❌ Block {} validation failed: {}", height, error_msg);
+            anyhow::bail!("Block {} failed validation during checkpoint generation: {}", height, error_msg);
+        }
+        
+        // Save checkpoint at chunk boundaries
+        // CRITICAL: Save checkpoint at the END of each chunk (before the next chunk starts)
+        // For chunk 0-169, save at height 169 (after processing block 169)
+        // For chunk 170-339, save at height 339 (after processing block 339)
+        // This ensures the checkpoint contains UTXOs from blocks 0-169, not 0-170
+        if is_checkpoint(height) {
+            println!("✅ Checkpoint at height {} (UTXO count: {})", height, utxo_store.len());
+            // NOTE: Must materialize here because we continue processing after checkpoint
+            // The checkpoint is saved for parallel validation later
+            store.save(height, utxo_store.to_utxo_set()?)?;
+            checkpoints.push(height);
+        }
+        
+        // Progress indicator
+        if height % 10_000 == 0 {
+            println!("📊 Checkpoint generation: {}/{} ({:.1}%)", 
+                     height - start_height, actual_end - start_height,
+                     100.0 * (height - start_height) as f64 / (actual_end - start_height) as f64);
+        }
+        Ok(())
+    };
     
     // Use optimized block reading for sequential access
-    match block_source {
-        BlockDataSource::DirectFile(reader) => {
-            // Direct file reading - sequential iterator (fastest!)
-            println!("📂 Using direct file reading for checkpoint generation");
-            let iterator = reader.read_blocks_sequential(Some(start_height), Some((actual_end - start_height + 1) as usize))?;
+    match block_source.iter_sequential(start_height, (actual_end - start_height + 1) as usize)? {
+        Some(iterator) => {
+            // Sequential reader, e.g. direct file reading (fastest!)
+            println!("📂 Using {} sequential reads for checkpoint generation", block_source.name());
             
             for (idx, block_result) in iterator.enumerate() {
                 let height = start_height + idx as u64;
@@ -227,7 +842,7 @@ pub async fn generate_checkpoints(
                     Ok(bytes) => bytes,
                     Err(e) => {
                         eprintln!("❌ Failed to read block at height {}: {}", height, e);
-                        return Err(e.into());
+                        return Err(e);
                     }
                 };
                 
@@ -236,12 +851,6 @@ pub async fn generate_checkpoints(
                     anyhow::bail!("Block {} too small: {} bytes (minimum 80 for header)", height, block_bytes.len());
                 }
                 
-                // Verify previous block hash matches (if not genesis) - this helps detect block boundary issues
-                if height > 0 {
-                    let prev_hash_in_header = &block_bytes[4..36]; // Previous block hash is at bytes 4-36 (little-endian)
-                    // We'll verify this after parsing the block
-                }
-                
                 let (block, witnesses) = match deserialize_block_with_witnesses(&block_bytes) {
                     Ok(result) => result,
                     Err(e) => {
@@ -258,124 +867,10 @@ pub async fn generate_checkpoints(
                     }
                 };
                 
-                // Debug: Check if previous blocks had non-coinbase transactions
-                if height <= 16 {
-                    let non_coinbase_count = block.transactions.iter().filter(|tx| !blvm_consensus::transaction::is_coinbase(tx)).count();
-                    if non_coinbase_count > 0 {
-                        eprintln!("🔍 Block {}: {} non-coinbase transactions", height, non_coinbase_count);
-                        // For each non-coinbase transaction, show what it's spending
-                        for (tx_idx, tx) in block.transactions.iter().enumerate() {
-                            if !blvm_consensus::transaction::is_coinbase(tx) {
-                                use blvm_consensus::block::calculate_tx_id;
-                                let txid = calculate_tx_id(tx);
-                                let txid_str: String = txid.iter().take(8).map(|b| format!("{:02x}", b)).collect();
-                                eprintln!("   TX {} (non-coinbase): {} inputs, {} outputs, TXID: {}...", 
-                                         tx_idx, tx.inputs.len(), tx.outputs.len(), txid_str);
-                                if !tx.inputs.is_empty() {
-                                    let hash_str: String = tx.inputs[0].prevout.hash.iter().take(8).map(|b| format!("{:02x}", b)).collect();
-                                    eprintln!("      Spending: {}:{}", hash_str, tx.inputs[0].prevout.index);
-                                }
-                            }
-                        }
-                    }
-                }
-                
-                // Debug: Check UTXO set after each block to see if outputs are being added
-                if height <= 16 {
-                    let non_coinbase_utxos: Vec<_> = utxo_set.iter()
-                        .filter(|(_, utxo)| !utxo.is_coinbase)
-                        .collect();
-                    if !non_coinbase_utxos.is_empty() {
-                        eprintln!("🔍 After block {}: {} non-coinbase UTXOs in set", height, non_coinbase_utxos.len());
-                        for (outpoint, utxo) in non_coinbase_utxos.iter().take(3) {
-                            let hash_str: String = outpoint.hash.iter().take(8).map(|b| format!("{:02x}", b)).collect();
-                            eprintln!("   Non-coinbase UTXO: {}:{} (value={}, height={})", 
-                                     hash_str, outpoint.index, utxo.value, utxo.height);
-                        }
-                    }
-                }
-                
-                // Debug: Print transaction details for block 15
-                if height == 15 {
-                    eprintln!("🔍 DEBUG Block 15: {} transactions", block.transactions.len());
-                    eprintln!("   UTXO set size: {}", utxo_set.len());
-                    // List all UTXOs in the set
-                    eprintln!("   All UTXOs in set:");
-                    for (outpoint, utxo) in utxo_set.iter() {
-                        let hash_str: String = outpoint.hash.iter().take(8).map(|b| format!("{:02x}", b)).collect();
-                        eprintln!("      {}:{} (value={}, height={}, coinbase={})", 
-                                 hash_str, outpoint.index, utxo.value, utxo.height, utxo.is_coinbase);
-                    }
-                    for (tx_idx, tx) in block.transactions.iter().enumerate() {
-                        eprintln!("   TX {}: {} inputs, {} outputs", tx_idx, tx.inputs.len(), tx.outputs.len());
-                        if !tx.inputs.is_empty() {
-                            let hash_str: String = tx.inputs[0].prevout.hash.iter().take(8).map(|b| format!("{:02x}", b)).collect();
-                            eprintln!("      First input prevout: {}:{}", hash_str, tx.inputs[0].prevout.index);
-                            // Check if UTXO exists
-                            if let Some(utxo) = utxo_set.get(&tx.inputs[0].prevout) {
-                                eprintln!("      UTXO exists: value={}, height={}, coinbase={}", 
-                                         utxo.value, utxo.height, utxo.is_coinbase);
-                            } else {
-                                eprintln!("      UTXO MISSING!");
-                                // The prevout hash should be a transaction ID from a previous block
-                                // Let's check if we can find it in the UTXO set by searching for matching txids
-                                eprintln!("      Looking for TX that created this UTXO...");
-                                let target_hash = tx.inputs[0].prevout.hash;
-                                let target_index = tx.inputs[0].prevout.index;
-                                let mut found_match = false;
-                                for (outpoint, utxo) in utxo_set.iter() {
-                                    if outpoint.hash == target_hash {
-                                        found_match = true;
-                                        let hash_str: String = outpoint.hash.iter().take(8).map(|b| format!("{:02x}", b)).collect();
-                                        eprintln!("      Found matching TX ID in UTXO set: {}:{} (target index: {})", 
-                                                 hash_str, outpoint.index, target_index);
-                                        eprintln!("      UTXO details: value={}, height={}, coinbase={}", 
-                                                 utxo.value, utxo.height, utxo.is_coinbase);
-                                    }
-                                }
-                                if !found_match {
-                                    let hash_str: String = target_hash.iter().take(8).map(|b| format!("{:02x}", b)).collect();
-                                    eprintln!("      No UTXO found with TX ID: {} (index: {})", hash_str, target_index);
-                                    eprintln!("      This UTXO should have been created in a previous block");
-                                    // Check if this TX ID matches any coinbase TX ID in the UTXO set
-                                    eprintln!("      Checking if this matches any coinbase TX ID...");
-                                    let mut found_coinbase_match = false;
-                                    for (outpoint, utxo) in utxo_set.iter() {
-                                        if utxo.is_coinbase {
-                                            let outpoint_hash_str: String = outpoint.hash.iter().take(8).map(|b| format!("{:02x}", b)).collect();
-                                            if outpoint.hash == target_hash {
-                                                found_coinbase_match = true;
-                                                eprintln!("      ✅ Found matching coinbase TX ID: {}:{} (but index {} doesn't match)", 
-                                                         outpoint_hash_str, outpoint.index, target_index);
-                                                eprintln!("      This suggests the transaction is trying to spend the wrong output index");
-                                                break;
-                                            }
-                                        }
-                                    }
-                                    if !found_coinbase_match {
-                                        eprintln!("      ❌ No matching coinbase TX ID found - this UTXO was never created");
-                                    }
-                                }
-                            }
-                        }
-                        // Calculate TX ID
-                        use blvm_consensus::block::calculate_tx_id;
-                        let txid = calculate_tx_id(tx);
-                        let txid_str: String = txid.iter().take(8).map(|b| format!("{:02x}", b)).collect();
-                        eprintln!("      TX ID: {}...", txid_str);
-                    }
-                }
-                
-                // Calculate this block's hash for next block verification
-                // OPTIMIZATION: Cache hash calculation (only compute once per block)
-                use sha2::{Digest, Sha256};
-                let header = &block_bytes[0..80];
-                let first_hash = Sha256::digest(header);
-                let second_hash = Sha256::digest(&first_hash);
-                // OPTIMIZATION: Use array directly instead of Vec allocation
-                let mut current_block_hash: [u8; 32] = second_hash.as_slice().try_into()
-                    .map_err(|_| anyhow::anyhow!("Invalid hash length"))?;
-                current_block_hash.reverse(); // Convert to big-endian
+                // Calculate this block's hash (display order) for next block verification
+                let current_block_hash = crate::block_hash::block_hash(&block_bytes)
+                    .map(crate::block_hash::to_display_order)
+                    .ok_or_else(|| anyhow::anyhow!("Block {} is shorter than a header", height))?;
                 
                 // Verify previous block hash matches (if not genesis)
                 // For Start9 encrypted files, if prev hash doesn't match, the block boundary detection
@@ -399,457 +894,924 @@ pub async fn generate_checkpoints(
                 // Update previous block hash for next iteration
                 previous_block_hash = Some(current_block_hash);
                 
-                // Debug: Check transaction count and verify block hash for problematic blocks
-                if height == 15 || height == 10 {
-                    let block_hash_hex = hex::encode(&current_block_hash[..8]);
-                    eprintln!("DEBUG Block {}: Parsed {} transactions, block hash (first 8 bytes) = {}, block size = {} bytes", 
-                             height, block.transactions.len(), block_hash_hex, block_bytes.len());
-                    for (i, tx) in block.transactions.iter().enumerate() {
-                        eprintln!("DEBUG Block {}: TX {} has {} inputs, {} outputs", height, i, tx.inputs.len(), tx.outputs.len());
-                    }
-                }
-                
-                // Debug: Verify we're calculating the correct coinbase txid
-                #[cfg(debug_assertions)]
-                if height <= 2 {
-                    use blvm_consensus::block::calculate_tx_id;
-                    if let Some(coinbase) = block.transactions.first() {
-                        let txid = calculate_tx_id(coinbase);
-                        eprintln!("DEBUG Block {}: coinbase txid = {}", height, hex::encode(txid));
-                        eprintln!("DEBUG Block {}: UTXO set size = {}", height, utxo_set.len());
-                        // List all coinbase UTXOs in the set
-                        let mut coinbase_utxos = Vec::new();
-                        for (outpoint, utxo) in utxo_set.iter() {
-                            if utxo.is_coinbase {
-                                coinbase_utxos.push((hex::encode(outpoint.hash), utxo.height));
-                            }
-                        }
-                        if !coinbase_utxos.is_empty() {
-                            eprintln!("DEBUG Block {}: Coinbase UTXOs in set: {:?}", height, coinbase_utxos);
-                        }
-                    }
-                }
-                
-                // Validate with BLVM
-                let (result, new_utxo_set, _undo_log) = connect_block(
-                    &block,
-                    &witnesses,
-                    utxo_set.clone(),
-                    height,
-                    None,
-                    Network::Mainnet,
-                )?;
-                
-                if matches!(result, blvm_consensus::types::ValidationResult::Valid) {
-                    utxo_set = new_utxo_set;
-                } else {
-                    // OPTIMIZATION: Use string reference instead of clone
-                    let error_msg = match &result {
-                        blvm_consensus::types::ValidationResult::Invalid(msg) => msg.as_str(),
-                        _ => "Unknown error",
-                    };
-                    eprintln!("❌ Block {} validation failed: {}", height, error_msg);
-                    anyhow::bail!("Block {} failed validation during checkpoint generation: {}", height, error_msg);
-                }
-                
-                // Save checkpoint at chunk boundaries
-                // CRITICAL: Save checkpoint at the END of each chunk (before the next chunk starts)
-                // For chunk 0-169, save at height 169 (after processing block 169)
-                // For chunk 170-339, save at height 339 (after processing block 339)
-                // This ensures the checkpoint contains UTXOs from blocks 0-169, not 0-170
-                if height == next_checkpoint - 1 || height == actual_end {
-                    println!("✅ Checkpoint at height {} (UTXO count: {})", height, utxo_set.len());
-                    // NOTE: Must clone here because we continue processing after checkpoint
-                    checkpoints.push((height, utxo_set.clone()));
-                    next_checkpoint += chunk_size;
-                }
-                
-                // Progress indicator
-                if height % 10_000 == 0 {
-                    println!("📊 Checkpoint generation: {}/{} ({:.1}%)", 
-                             height - start_height, actual_end - start_height,
-                             100.0 * (height - start_height) as f64 / (actual_end - start_height) as f64);
-                }
+                connect(height, &block_bytes, &block, &witnesses)?;
             }
         }
-        _ => {
+        None => {
             // For cache/RPC, fetch blocks sequentially (async)
             for height in start_height..=actual_end {
                 let block_bytes = get_block_data(block_source, height).await?;
                 
                 let (block, witnesses) = deserialize_block_with_witnesses(&block_bytes)?;
                 
-                // Debug: Verify coinbase txid and block data for problematic blocks
-                #[cfg(debug_assertions)]
-                if height == 16 || height == 2 || height <= 1 {
-                    use blvm_consensus::block::calculate_tx_id;
-                    use sha2::{Digest, Sha256};
-                    
-                    // Verify block hash matches expected
-                    if block_bytes.len() >= 80 {
-                        let header = &block_bytes[0..80];
-                        let block_hash = hex::encode(Sha256::digest(&Sha256::digest(header)));
-                        eprintln!("DEBUG Block {}: block hash (calculated) = {}", height, block_hash);
-                    }
-                    
-                    if let Some(coinbase) = block.transactions.first() {
-                        let txid = calculate_tx_id(coinbase);
-                        eprintln!("DEBUG Block {}: coinbase txid = {}", height, hex::encode(txid));
-                        eprintln!("DEBUG Block {}: coinbase script_sig len = {}", height, coinbase.inputs[0].script_sig.len());
-                        eprintln!("DEBUG Block {}: coinbase script_sig (first 20) = {}", height, 
-                                 hex::encode(&coinbase.inputs[0].script_sig[..coinbase.inputs[0].script_sig.len().min(20)]));
-                        eprintln!("DEBUG Block {}: block_bytes len = {}", height, block_bytes.len());
-                        eprintln!("DEBUG Block {}: UTXO set size = {}", height, utxo_set.len());
-                        
-                        // Check for matching UTXOs
-                        let mut matches = Vec::new();
-                        for (outpoint, utxo) in utxo_set.iter() {
-                            if outpoint.hash == txid && utxo.is_coinbase {
-                                matches.push(utxo.height);
-                            }
-                        }
-                        if !matches.is_empty() {
-                            eprintln!("DEBUG Block {}: Found {} UTXO(s) with matching txid at heights: {:?}", height, matches.len(), matches);
-                        }
-                    }
-                }
-                
-                // Validate with BLVM
-                let (result, new_utxo_set, _undo_log) = connect_block(
-                    &block,
-                    &witnesses,
-                    utxo_set.clone(),
-                    height,
-                    None,
-                    Network::Mainnet,
-                )?;
-                
-                if matches!(result, blvm_consensus::types::ValidationResult::Valid) {
-                    utxo_set = new_utxo_set;
-                } else {
-                    // OPTIMIZATION: Use string reference instead of clone
-                    let error_msg = match &result {
-                        blvm_consensus::types::ValidationResult::Invalid(msg) => msg.as_str(),
-                        _ => "Unknown error",
-                    };
-                    eprintln!("❌ Block {} validation failed: {}", height, error_msg);
-                    anyhow::bail!("Block {} failed validation during checkpoint generation: {}", height, error_msg);
-                }
-                
-                // Save checkpoint at chunk boundaries
-                // CRITICAL: Save checkpoint at the END of each chunk (before the next chunk starts)
-                // For chunk 0-169, save at height 169 (after processing block 169)
-                // For chunk 170-339, save at height 339 (after processing block 339)
-                // This ensures the checkpoint contains UTXOs from blocks 0-169, not 0-170
-                if height == next_checkpoint - 1 || height == actual_end {
-                    println!("✅ Checkpoint at height {} (UTXO count: {})", height, utxo_set.len());
-                    // NOTE: Must clone here because we continue processing after checkpoint
-                    // The checkpoint is saved for parallel validation later
-                    checkpoints.push((height, utxo_set.clone()));
-                    next_checkpoint += chunk_size;
-                }
-                
-                // Progress indicator
-                if height % 10_000 == 0 {
-                    println!("📊 Checkpoint generation: {}/{} ({:.1}%)", 
-                             height - start_height, actual_end - start_height,
-                             100.0 * (height - start_height) as f64 / (actual_end - start_height) as f64);
-                }
+                connect(height, &block_bytes, &block, &witnesses)?;
             }
         }
     }
     
+    let bases = store.entries().iter().filter(|e| e.kind == crate::checkpoint_store::CheckpointKind::Base).count();
+    println!("💾 Checkpoints: {:.1} MB on disk ({} base, {} delta)",
+             store.disk_usage() as f64 / (1024.0 * 1024.0), bases, checkpoints.len() - bases);
     Ok(checkpoints)
 }
 
 /// Process a single block (validate with BLVM and Core)
+///
+/// When `capture_pre_state` is set, also returns the coins the block read before
+/// it was connected (used for reproducer bundles). A panic inside BLVM is caught,
+/// quarantined under `quarantine_dir` and returned as a `BlockPanic` error.
+/// Connect and Core check times are recorded into `timings`, allocations made
+/// by `connect_block` into `allocations`. With a `verify_node`, Core's verdict
+/// comes from submitting the block to it instead of checking chain membership.
+#[allow(clippy::too_many_arguments)]
 async fn process_block(
     block_bytes: &[u8],
+    decoded: crate::decode_pipeline::DecodedBlock,
     height: u64,
-    utxo_set: &mut UtxoSet,
+    utxo_store: &mut dyn UtxoStore,
     block_source: &BlockDataSource,
-) -> Result<(crate::differential::ValidationResult, crate::differential::CoreValidationResult)> {
+    capture_pre_state: bool,
+    quarantine_dir: &std::path::Path,
+    timings: &mut StageTimings,
+    allocations: &mut crate::mem_profile::AllocMeter,
+    verify_node: Option<&crate::submit_verifier::SubmitVerifier>,
+    rpc_limiter: &crate::rpc_limiter::RpcLimiter,
+    network: BlockFileNetwork,
+) -> Result<(
+    crate::differential::ValidationResult,
+    crate::differential::CoreValidationResult,
+    Option<UtxoSet>,
+)> {
     use crate::differential::{CoreValidationResult, ValidationResult};
-    use blvm_consensus::block::connect_block;
-    use blvm_consensus::segwit::Witness;
-    use blvm_consensus::serialization::block::deserialize_block_with_witnesses;
-    use blvm_consensus::types::Network;
     
-    let (block, witnesses) = match deserialize_block_with_witnesses(block_bytes) {
-        Ok((b, w)) => (b, w),
-        Err(e) => {
-            anyhow::bail!("Failed to deserialize block at height {}: {}", height, e);
-        }
+    let (block, witnesses) = decoded;
+    if crate::block_debug::enabled(height) {
+        crate::block_debug::dump_block(height, block_bytes, &block, &*utxo_store);
+    }
+    
+    let pre_state = if capture_pre_state {
+        Some(crate::reproducer::collect_pre_state(&block, utxo_store)?)
+    } else {
+        None
     };
     
-    // Validate with BLVM
-    let blvm_result = match connect_block(
-        &block,
-        &witnesses,
-        utxo_set.clone(),
-        height,
-        None,
-        Network::Mainnet,
-    ) {
-        Ok((result, new_utxo_set, _undo_log)) => {
-            *utxo_set = new_utxo_set;
-            match result {
-                blvm_consensus::types::ValidationResult::Valid => ValidationResult::Valid,
-                blvm_consensus::types::ValidationResult::Invalid(msg) => {
-                    ValidationResult::Invalid(msg)
-                }
+    // A verify node's verdict is fetched before connecting: if that RPC fails the store
+    // is still untouched, so a chunk retry can resume at this block
+    let verdict_started = std::time::Instant::now();
+    let verified = match verify_node {
+        Some(verifier) => Some(verifier.verdict(height, block_bytes).await?),
+        None => None,
+    };
+    let verdict_secs = verdict_started.elapsed().as_secs_f64();
+    
+    // Validate with BLVM (panics are bugs in BLVM - quarantine the block instead of losing the worker)
+    let consensus = consensus_network(network)?;
+    let connect_started = std::time::Instant::now();
+    let connect_result = allocations.measure(|| {
+        std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            utxo_store.connect_block(&block, &witnesses, height, consensus)
+        }))
+    });
+    timings.connect_secs = connect_started.elapsed().as_secs_f64();
+    let connect_result = match connect_result {
+        Ok(result) => result,
+        Err(payload) => {
+            let message = crate::quarantine::panic_message(payload.as_ref());
+            eprintln!("💥 BLVM panicked at height {}: {}", height, message);
+            // Stores only mutate after connect_block returns, so this is still the pre-state
+            let artifact_dir = crate::reproducer::collect_pre_state(&block, utxo_store)
+                .and_then(|pre_state| {
+                    crate::reproducer::Reproducer {
+                        height,
+                        block_bytes,
+                        pre_state: &pre_state,
+                        blvm_result: &format!("Panic({})", message),
+                        core_result: "Unknown",
+                        utxo_backend: utxo_store.name(),
+                    }
+                    .write(quarantine_dir)
+                })
+                .map_err(|e| eprintln!("   ⚠️  Failed to quarantine block {}: {}", height, e))
+                .ok();
+            return Err(crate::quarantine::BlockPanic {
+                height: Some(height),
+                message,
+                artifact_dir,
             }
+            .into());
         }
+    };
+    let blvm_result = match connect_result {
+        Ok(blvm_consensus::types::ValidationResult::Valid) => ValidationResult::Valid,
+        Ok(blvm_consensus::types::ValidationResult::Invalid(msg)) => ValidationResult::Invalid(msg),
         Err(e) => ValidationResult::Invalid(format!("{:?}", e)),
     };
     
     // Validate with Core
-    let core_result = match block_source {
-        BlockDataSource::DirectFile(_) => {
-            // Blocks from Core's files are assumed valid
-            CoreValidationResult::Valid
-        }
-        BlockDataSource::SharedCache(_, Some(client)) | BlockDataSource::Rpc(client) => {
-            // Calculate block hash to check with Core
-            // OPTIMIZATION: Use fixed-size array instead of Vec allocation
-            // OPTIMIZATION: Cache hash calculation if called multiple times
-            use sha2::{Digest, Sha256};
-            if block_bytes.len() >= 80 {
-                let header = &block_bytes[0..80];
-                let first_hash = Sha256::digest(header);
-                let second_hash = Sha256::digest(&first_hash);
-                // Reverse bytes for Core RPC (Core displays hashes in reverse)
-                // OPTIMIZATION: Use array instead of Vec to avoid allocation
-                let mut hash_bytes: [u8; 32] = second_hash.as_slice().try_into()
-                    .unwrap_or_else(|_| {
-                        // Fallback: create zero array if conversion fails
-                        [0u8; 32]
-                    });
-                hash_bytes.reverse();
-                let block_hash = hex::encode(hash_bytes);
-                
-                match client.getblock(&block_hash, 1).await {
-                    Ok(_) => CoreValidationResult::Valid,
-                    Err(_) => CoreValidationResult::Invalid("Block not in chain".to_string()),
+    let core_check_started = std::time::Instant::now();
+    let core_result = if let Some(verdict) = verified {
+        // Core's own verdict on the block, not just whether it's in Core's chain
+        verdict
+    } else {
+        match block_source {
+            BlockDataSource::DirectFile(_) | BlockDataSource::P2p(_) | BlockDataSource::MmapCache(_) => {
+                // Blocks from Core's files (or served by a peer from its active chain) are assumed valid
+                CoreValidationResult::Valid
+            }
+            BlockDataSource::SharedCache(_, Some(client)) | BlockDataSource::Rpc(client) => {
+                // Calculate block hash (display order) to check with Core
+                if let Some(block_hash) = crate::block_hash::block_hash_hex(block_bytes) {
+                    match rpc_limited(rpc_limiter, block_source, client.getblock(&block_hash, 1)).await {
+                        Ok(_) => CoreValidationResult::Valid,
+                        Err(_) => CoreValidationResult::Invalid("Block not in chain".to_string()),
+                    }
+                } else {
+                    CoreValidationResult::Invalid("Block too short".to_string())
                 }
-            } else {
-                CoreValidationResult::Invalid("Block too short".to_string())
             }
-        }
-        BlockDataSource::Start9Rpc(client) => {
-            // Calculate block hash to check with Core
-            // OPTIMIZATION: Use fixed-size array instead of Vec allocation
-            use sha2::{Digest, Sha256};
-            if block_bytes.len() >= 80 {
-                let header = &block_bytes[0..80];
-                let first_hash = Sha256::digest(header);
-                let second_hash = Sha256::digest(&first_hash);
-                // Reverse bytes for Core RPC (Core displays hashes in reverse)
-                // OPTIMIZATION: Use array instead of Vec to avoid allocation
-                let mut hash_bytes: [u8; 32] = second_hash.as_slice().try_into()
-                    .map_err(|_| anyhow::anyhow!("Invalid hash length"))?;
-                hash_bytes.reverse();
-                let block_hash = hex::encode(hash_bytes);
-                
-                // Start9 RPC - just check if we can get the block
-                match client.get_block_hex(&block_hash).await {
-                    Ok(_) => CoreValidationResult::Valid,
-                    Err(_) => CoreValidationResult::Invalid("Block not in chain".to_string()),
+            BlockDataSource::Start9Rpc(client) => {
+                // Calculate block hash (display order) to check with Core
+                if let Some(block_hash) = crate::block_hash::block_hash_hex(block_bytes) {
+                    // Start9 RPC - the header says whether it's in the active chain
+                    match rpc_limited(rpc_limiter, block_source, client.block_in_chain(&block_hash)).await {
+                        Ok(true) => CoreValidationResult::Valid,
+                        Ok(false) | Err(_) => CoreValidationResult::Invalid("Block not in chain".to_string()),
+                    }
+                } else {
+                    CoreValidationResult::Invalid("Block too short".to_string())
                 }
-            } else {
-                CoreValidationResult::Invalid("Block too short".to_string())
             }
-        }
-        _ => {
-            // No RPC client available, assume valid for direct file reading
-            CoreValidationResult::Valid
+            _ => {
+                // No RPC client available, assume valid for direct file reading
+                CoreValidationResult::Valid
+            }
         }
     };
+    timings.core_check_secs = verdict_secs + core_check_started.elapsed().as_secs_f64();
     
-    Ok((blvm_result, core_result))
+    Ok((blvm_result, core_result, pre_state))
+}
+
+/// Write a divergence reproducer bundle and return its directory (failures are logged, not fatal)
+fn write_reproducer(
+    dir: &std::path::Path,
+    height: u64,
+    block_bytes: &[u8],
+    pre_state: &UtxoSet,
+    blvm_result: &str,
+    core_result: &str,
+    utxo_backend: &UtxoBackend,
+) -> Option<std::path::PathBuf> {
+    let reproducer = crate::reproducer::Reproducer {
+        height,
+        block_bytes,
+        pre_state,
+        blvm_result,
+        core_result,
+        utxo_backend: utxo_backend.name(),
+    };
+    match reproducer.write(dir) {
+        Ok(path) => {
+            eprintln!("   📦 Reproducer written to {}", path.display());
+            Some(path)
+        }
+        Err(e) => {
+            eprintln!("   ⚠️  Failed to write reproducer for height {}: {}", height, e);
+            None
+        }
+    }
+}
+
+/// Shrink a block BLVM rejected to the fewest transactions that still trigger
+/// the rejection and add the result to its reproducer bundle (failures are logged, not fatal)
+fn attach_minimized(bundle: &std::path::Path, height: u64, block_bytes: &[u8], pre_state: &UtxoSet, rejection: &str) {
+    let minimized = crate::divergence_minimizer::minimize_divergence(block_bytes, pre_state, height, rejection)
+        .and_then(|case| case.map(|case| case.write(bundle).map(|()| case)).transpose());
+    match minimized {
+        Ok(Some(case)) => eprintln!(
+            "   ✂️  Minimized to {} of {} transactions ({} BLVM runs)",
+            case.kept.len(),
+            case.original_txs.saturating_sub(1),
+            case.tests_run
+        ),
+        Ok(None) => eprintln!("   ⚠️  Block {} couldn't be minimized: a rebuilt block isn't rejected the same way", height),
+        Err(e) => eprintln!("   ⚠️  Failed to minimize block {}: {}", height, e),
+    }
+}
+
+/// Description of the waiver covering a divergence at `height`, if any
+fn find_waiver(waivers: &crate::waivers::Waivers, height: u64, block_bytes: &[u8]) -> Option<String> {
+    let txids: Vec<String> = if waivers.needs_txids() {
+        crate::mutation_differential::raw_transactions(block_bytes)
+            .map(|txs| {
+                txs.iter()
+                    .map(|tx| {
+                        let mut txid = tx.txid();
+                        txid.reverse();
+                        hex::encode(txid)
+                    })
+                    .collect()
+            })
+            .unwrap_or_default()
+    } else {
+        Vec::new()
+    };
+    waivers.find(height, &txids).map(|waiver| waiver.describe())
+}
+
+/// Record an accepted block's input bundle (failures are logged, not fatal)
+fn record_input_bundle(dir: &std::path::Path, height: u64, block_bytes: &[u8], pre_state: &UtxoSet) {
+    if let Err(e) = crate::input_bundles::write_bundle(dir, height, block_bytes, pre_state) {
+        eprintln!("   ⚠️  Failed to write input bundle for height {}: {}", height, e);
+    }
+}
+
+/// Profile a slow block and write its flamegraph (failures are logged, not fatal)
+fn record_flamegraph(
+    flamegraphs: &crate::flamegraph::FlamegraphConfig,
+    height: u64,
+    block_bytes: &[u8],
+    pre_state: &UtxoSet,
+) {
+    match flamegraphs.profile(height, block_bytes, pre_state) {
+        Ok(path) => eprintln!("   🔥 Flamegraph for block {} written to {}", height, path.display()),
+        Err(e) => eprintln!("   ⚠️  Failed to profile block {}: {}", height, e),
+    }
+}
+
+/// Record a poisoned chunk in the quarantine manifest (failures are logged, not fatal)
+pub(crate) fn quarantine_chunk(
+    quarantine_dir: &std::path::Path,
+    start_height: u64,
+    end_height: u64,
+    panic: crate::quarantine::BlockPanic,
+) {
+    let entry = crate::quarantine::PoisonedChunk { start_height, end_height, panic };
+    if let Err(e) = crate::quarantine::record_poisoned_chunk(quarantine_dir, &entry) {
+        eprintln!("   ⚠️  Failed to update quarantine manifest: {}", e);
+    }
+}
+
+/// (transaction count, input count) of a block, for the leaderboard
+fn block_shape(block: &blvm_consensus::Block) -> (usize, usize) {
+    let inputs = block.transactions.iter().map(|tx| tx.inputs.len()).sum();
+    (block.transactions.len(), inputs)
 }
 
 /// Validate a single chunk of blocks
 /// 
 /// Uses optimized block data source (direct file reading if available).
 pub async fn validate_chunk(
-    chunk: BlockChunk,
+    mut chunk: BlockChunk,
     block_source: Arc<BlockDataSource>,
 ) -> Result<ChunkResult> {
-    use crate::differential::{CoreValidationResult, ValidationResult};
+    let checkpoint_utxo = chunk.checkpoint_utxo.take().unwrap_or_else(UtxoSet::new);
+    validate_chunk_from(&chunk, chunk.start_height, checkpoint_utxo, block_source)
+        .await
+        .map_err(|interrupted| interrupted.error)
+}
+
+/// A chunk that stopped on an error part-way, with what's needed to resume it
+struct ChunkInterrupted {
+    error: anyhow::Error,
+    /// Results for the blocks validated before the error (None if there were none)
+    partial: Option<ChunkResult>,
+    /// First height not yet compared with Core
+    resume_height: u64,
+    /// UTXO set from before `resume_height` (None if it can't be recovered, which fails the chunk)
+    resume_utxo: Option<UtxoSet>,
+}
+
+/// Tip height as seen by `source` (`fallback_end` if it can't tell)
+pub(crate) async fn source_tip(source: &BlockDataSource, fallback_end: u64) -> Result<u64> {
+    Ok(source.get_tip_height().await?.unwrap_or(fallback_end))
+}
+
+/// Per-block results of one chunk attempt, built up by `record_block`
+struct ChunkTally {
+    divergences: Vec<(u64, String, String)>,
+    waived: Vec<(u64, String)>,
+    version_divergences: Vec<crate::core_versions::VersionDivergence>,
+    tested: usize,
+    matched: usize,
+    poisoned: Option<crate::quarantine::BlockPanic>,
+    stage_timings: StageTimings,
+    era_timings: EraTimings,
+    slow_blocks: Vec<BlockTiming>,
+    slowest: crate::slow_blocks::Leaderboard,
+    /// Last block fully compared, and last block applied to the UTXO set (they differ
+    /// only if an error hit between connecting a block and recording its result)
+    validated_through: Option<u64>,
+    connected_through: Option<u64>,
+    /// Set if that error hit: the uncompared block and the UTXO set from before it
+    rollback: Option<(u64, UtxoSet)>,
+}
+
+impl ChunkTally {
+    fn new(slowest_blocks: usize) -> Self {
+        Self {
+            // OPTIMIZATION: Pre-allocate divergences vector (most tests have 0-10 divergences)
+            divergences: Vec::with_capacity(10),
+            waived: Vec::new(),
+            version_divergences: Vec::new(),
+            tested: 0,
+            matched: 0,
+            poisoned: None,
+            stage_timings: StageTimings::default(),
+            era_timings: EraTimings::default(),
+            slow_blocks: Vec::new(),
+            slowest: crate::slow_blocks::Leaderboard::new(slowest_blocks),
+            validated_through: None,
+            connected_through: None,
+            rollback: None,
+        }
+    }
+
+    fn record_timing(
+        &mut self,
+        chunk: &BlockChunk,
+        height: u64,
+        block: &StageTimings,
+        block_hash: &CachedBlockHash,
+        (tx_count, input_count): (usize, usize),
+    ) {
+        self.stage_timings.add(block);
+        self.era_timings.record(height, block.total_secs());
+        if chunk.slow_block_threshold.is_some_and(|t| block.total_secs() >= t.as_secs_f64()) {
+            self.slow_blocks.push(BlockTiming { height, stages: *block });
+        }
+        self.slowest.record(crate::slow_blocks::SlowBlock {
+            height,
+            hash: block_hash.display_hex().unwrap_or_default(),
+            connect_secs: block.connect_secs,
+            tx_count,
+            input_count,
+        });
+    }
+
+    /// Record a block `process_block` handled: its timings, then its comparison with Core
+    /// (version and fee checks, waivers, reproducers, the result stream)
+    ///
+    /// Returns false if BLVM panicked on it and the chunk must stop there.
+    #[allow(clippy::too_many_arguments)]
+    async fn record_block(
+        &mut self,
+        chunk: &BlockChunk,
+        height: u64,
+        block_bytes: &[u8],
+        processed: Result<(
+            crate::differential::ValidationResult,
+            crate::differential::CoreValidationResult,
+            Option<UtxoSet>,
+        )>,
+        timings: &StageTimings,
+        shape: (usize, usize),
+        progress: &mut crate::progress::ChunkProgress,
+    ) -> Result<bool> {
+        use crate::differential::{CoreValidationResult, ValidationResult};
+
+        let block_hash = CachedBlockHash::new(block_bytes);
+        self.record_timing(chunk, height, timings, &block_hash, shape);
+        let (blvm_result, core_result, pre_state) = match processed {
+            Ok(results) => results,
+            Err(e) => match e.downcast::<crate::quarantine::BlockPanic>() {
+                Ok(block_panic) => {
+                    self.poisoned = Some(block_panic);
+                    return Ok(false);
+                }
+                Err(e) => return Err(e),
+            },
+        };
+        self.connected_through = Some(height);
+
+        if let (Some(dir), Some(pre_state), ValidationResult::Valid) = (&chunk.input_bundle_dir, &pre_state, &blvm_result) {
+            record_input_bundle(dir, height, block_bytes, pre_state);
+        }
+        if let (Some(flamegraphs), Some(pre_state)) = (&chunk.flamegraphs, &pre_state) {
+            if flamegraphs.should_profile(timings.connect_secs) {
+                record_flamegraph(flamegraphs, height, block_bytes, pre_state);
+            }
+        }
+    
+        if let Some(divergence) = crate::core_versions::compare_versions(
+            &chunk.core_endpoints,
+            height,
+            block_bytes,
+            &blvm_result,
+            &core_result,
+        ).await {
+            self.version_divergences.push(divergence);
+        }
+    
+        // Blocks both sides accept must also agree on fees and subsidy
+        let value_mismatch = match (&chunk.fee_check, &pre_state, &blvm_result, &core_result) {
+            (Some(fee_check), Some(pre_state), ValidationResult::Valid, CoreValidationResult::Valid) => {
+                match fee_check.compare(height, block_bytes, pre_state).await {
+                    Ok(mismatch) => mismatch,
+                    Err(e) => {
+                        // The block is already connected; a retry starts over from its pre-state
+                        self.rollback = Some((height, pre_state.clone()));
+                        return Err(e);
+                    }
+                }
+            }
+            _ => None,
+        };
+    
+        // Compare and record results
+        let matches = value_mismatch.is_none() && matches!(
+            (&blvm_result, &core_result),
+            (ValidationResult::Valid, CoreValidationResult::Valid)
+                | (
+                    ValidationResult::Invalid(_),
+                    CoreValidationResult::Invalid(_)
+                )
+        );
+    
+        if !matches {
+            // OPTIMIZATION: Use format! directly instead of intermediate strings
+            let (blvm_str, core_str) = value_mismatch.unwrap_or_else(|| {
+                let blvm_str = match &blvm_result {
+                    ValidationResult::Valid => "Valid".to_string(),
+                    ValidationResult::Invalid(msg) => format!("Invalid({})", msg),
+                };
+                let core_str = match &core_result {
+                    CoreValidationResult::Valid => "Valid".to_string(),
+                    CoreValidationResult::Invalid(msg) => format!("Invalid({})", msg),
+                };
+                (blvm_str, core_str)
+            });
+            self.divergences.push((height, blvm_str.clone(), core_str.clone()));
+            eprintln!("❌ DIVERGENCE at height {}: BLVM={}, Core={}", 
+                     height, blvm_str, core_str);
+            match chunk.waivers.as_deref().and_then(|waivers| find_waiver(waivers, height, block_bytes)) {
+                Some(reason) => {
+                    eprintln!("   📝 Waived: {}", reason);
+                    self.waived.push((height, reason));
+                }
+                None => crate::alerts::notify_divergence(chunk.alerts.as_ref(), height, block_bytes, &blvm_str, &core_str).await,
+            }
+            if let Some(stream) = &chunk.result_stream {
+                let waiver = self.waived.last().filter(|(waived, _)| *waived == height).map(|(_, reason)| reason.as_str());
+                stream.divergence(height, &blvm_str, &core_str, waiver);
+            }
+        
+            if let (Some(dir), Some(pre_state)) = (&chunk.reproducer_dir, &pre_state) {
+                let bundle = write_reproducer(dir, height, block_bytes, pre_state, &blvm_str, &core_str, &chunk.utxo_backend);
+                // Only BLVM's rejections can be checked on synthetic blocks
+                if let (Some(bundle), ValidationResult::Invalid(rejection), CoreValidationResult::Valid) = (bundle, &blvm_result, &core_result) {
+                    attach_minimized(&bundle, height, block_bytes, pre_state, rejection);
+                }
+            }
+        
+            // Log first few divergences with more detail
+            if self.divergences.len() <= 5 {
+                if let Some(hash) = block_hash.display() {
+                    eprintln!("   Block hash (first 8 bytes): {}", hex::encode(&hash[..8]));
+                }
+            }
+        } else {
+            self.matched += 1;
+        }
+    
+        self.tested += 1;
+    
+        progress.block_done(!matches);
+        self.validated_through = Some(height);
+        Ok(true)
+    }
+}
+
+/// Validate `chunk` from `start_height` (its start, or a resume point inside it) on top of `checkpoint_utxo`
+async fn validate_chunk_from(
+    chunk: &BlockChunk,
+    start_height: u64,
+    checkpoint_utxo: UtxoSet,
+    block_source: Arc<BlockDataSource>,
+) -> std::result::Result<ChunkResult, Box<ChunkInterrupted>> {
     use std::time::Instant;
     
     let start_time = Instant::now();
-    let mut utxo_set = chunk.checkpoint_utxo.unwrap_or_else(UtxoSet::new);
-    // OPTIMIZATION: Pre-allocate divergences vector (most tests have 0-10 divergences)
-    let mut divergences = Vec::with_capacity(10);
-    let mut tested = 0;
-    let mut matched = 0;
+    let mut utxo_store = match chunk.utxo_backend.create(checkpoint_utxo) {
+        Ok(store) => store,
+        Err(error) => {
+            return Err(Box::new(ChunkInterrupted {
+                error,
+                partial: None,
+                resume_height: start_height,
+                resume_utxo: None,
+            }))
+        }
+    };
+    let mut tally = ChunkTally::new(chunk.slowest_blocks);
+    let mut allocations = crate::mem_profile::AllocMeter::default();
     
     // Get chain height
-    let chain_height = match block_source.as_ref() {
-        BlockDataSource::Rpc(client) => client.getblockcount().await?,
-        BlockDataSource::Start9Rpc(client) => client.get_block_count().await?,
-        BlockDataSource::SharedCache(_, Some(client)) => client.getblockcount().await?,
-        BlockDataSource::DirectFile(_) => chunk.end_height, // Don't know exact height
-        BlockDataSource::SharedCache(_, None) => chunk.end_height, // Don't know exact height
+    let chain_height = match source_tip(block_source.as_ref(), chunk.end_height).await {
+        Ok(height) => height,
+        Err(error) => {
+            return Err(Box::new(ChunkInterrupted {
+                error,
+                partial: None,
+                resume_height: start_height,
+                resume_utxo: utxo_store.to_utxo_set().ok(),
+            }))
+        }
     };
     let actual_end = chunk.end_height.min(chain_height);
+    let mut progress = chunk.progress.chunk(start_height, actual_end);
     
     // Process blocks based on data source
-    match block_source.as_ref() {
-        BlockDataSource::DirectFile(reader) => {
-            // Direct file reading - sequential iterator (fastest!)
-            let iterator = reader.read_blocks_sequential(
-                Some(chunk.start_height),
-                Some((actual_end - chunk.start_height + 1) as usize)
-            )?;
+    let outcome: Result<()> = async {
+        match block_source.iter_sequential(start_height, (actual_end - start_height + 1) as usize)? {
+            Some(iterator) => {
+                // Sequential reader (e.g. direct file reading - fastest!), deserialized
+                // on the rayon pool ahead of validation
+                let pipeline = crate::decode_pipeline::DecodePipeline::new(iterator, start_height, chunk.decode_ahead);
             
-            for (idx, block_result) in iterator.enumerate() {
-                let height = chunk.start_height + idx as u64;
-                let block_bytes = block_result?;
-                
-                // Process block (same logic for both paths)
-                let (blvm_result, core_result) = process_block(
-                    &block_bytes,
-                    height,
-                    &mut utxo_set,
-                    block_source.as_ref(),
-                ).await?;
-                
-                // Compare and record results
-                let matches = matches!(
-                    (&blvm_result, &core_result),
-                    (ValidationResult::Valid, CoreValidationResult::Valid)
-                        | (
-                            ValidationResult::Invalid(_),
-                            CoreValidationResult::Invalid(_)
-                        )
-                );
-                
-                if !matches {
-                    // OPTIMIZATION: Use format! directly instead of intermediate strings
-                    let blvm_str = match &blvm_result {
-                        ValidationResult::Valid => "Valid".to_string(),
-                        ValidationResult::Invalid(msg) => format!("Invalid({})", msg),
-                    };
-                    let core_str = match &core_result {
-                        CoreValidationResult::Valid => "Valid".to_string(),
-                        CoreValidationResult::Invalid(msg) => format!("Invalid({})", msg),
+                for block_result in pipeline {
+                    let crate::decode_pipeline::PipelinedBlock { height, bytes: block_bytes, decoded, read_time, decode_time } = block_result?;
+                    let mut timings = StageTimings {
+                        read_secs: read_time.as_secs_f64(),
+                        deserialize_secs: decode_time.as_secs_f64(),
+                        ..Default::default()
                     };
-                    divergences.push((height, blvm_str.clone(), core_str.clone()));
-                    eprintln!("❌ DIVERGENCE at height {}: BLVM={}, Core={}", 
-                             height, blvm_str, core_str);
-                    
-                    // Log first few divergences with more detail
-                    if divergences.len() <= 5 {
-                        use sha2::{Digest, Sha256};
-                        if block_bytes.len() >= 80 {
-                            let header = &block_bytes[0..80];
-                            let first_hash = Sha256::digest(header);
-                            let second_hash = Sha256::digest(&first_hash);
-                            let mut hash_bytes = second_hash.as_slice().to_vec();
-                            hash_bytes.reverse();
-                            let block_hash = hex::encode(&hash_bytes[..8]);
-                            eprintln!("   Block hash (first 8 bytes): {}", block_hash);
-                        }
-                    }
-                } else {
-                    matched += 1;
-                }
+                    let shape = block_shape(&decoded.0);
                 
-                tested += 1;
-                
-                // Progress indicator every 100 blocks (more frequent for better feedback)
-                if tested % 100 == 0 || tested == 1 {
-                    let total = actual_end - chunk.start_height + 1;
-                    let pct = 100.0 * tested as f64 / total as f64;
-                    let elapsed = start_time.elapsed().as_secs_f64();
-                    let rate = tested as f64 / elapsed;
-                    println!("📊 Chunk [{}-{}]: {}/{} blocks ({:.1}%) @ {:.1} blocks/sec", 
-                             chunk.start_height, actual_end, tested, total, pct, rate);
+                    // Process block (same logic for both paths)
+                    let processed = process_block(
+                        &block_bytes,
+                        decoded,
+                        height,
+                        utxo_store.as_mut(),
+                        block_source.as_ref(),
+                        chunk.captures_pre_state(),
+                        &chunk.quarantine_dir,
+                        &mut timings,
+                        &mut allocations,
+                        chunk.verify_node.as_ref(),
+                        &chunk.rpc_limiter,
+                        chunk.network,
+                    ).await;
+                    if !tally.record_block(chunk, height, &block_bytes, processed, &timings, shape, &mut progress).await? {
+                        break;
+                    }
                 }
             }
-        }
-        _ => {
-            // For cache/RPC, fetch blocks sequentially (async)
-            for height in chunk.start_height..=actual_end {
-                let block_bytes = get_block_data(block_source.as_ref(), height).await?;
-                
-                // Process block (same logic)
-                let (blvm_result, core_result) = process_block(
-                    &block_bytes,
-                    height,
-                    &mut utxo_set,
-                    block_source.as_ref(),
-                ).await?;
-                
-                // Compare and record results
-                let matches = matches!(
-                    (&blvm_result, &core_result),
-                    (ValidationResult::Valid, CoreValidationResult::Valid)
-                        | (
-                            ValidationResult::Invalid(_),
-                            CoreValidationResult::Invalid(_)
-                        )
-                );
-                
-                if !matches {
-                    // OPTIMIZATION: Use format! directly instead of intermediate strings
-                    let blvm_str = match &blvm_result {
-                        ValidationResult::Valid => "Valid".to_string(),
-                        ValidationResult::Invalid(msg) => format!("Invalid({})", msg),
-                    };
-                    let core_str = match &core_result {
-                        CoreValidationResult::Valid => "Valid".to_string(),
-                        CoreValidationResult::Invalid(msg) => format!("Invalid({})", msg),
-                    };
-                    divergences.push((height, blvm_str.clone(), core_str.clone()));
-                    eprintln!("❌ DIVERGENCE at height {}: BLVM={}, Core={}", 
-                             height, blvm_str, core_str);
-                    
-                    // Log first few divergences with more detail
-                    if divergences.len() <= 5 {
-                        use sha2::{Digest, Sha256};
-                        if block_bytes.len() >= 80 {
-                            let header = &block_bytes[0..80];
-                            let first_hash = Sha256::digest(header);
-                            let second_hash = Sha256::digest(&first_hash);
-                            let mut hash_bytes = second_hash.as_slice().to_vec();
-                            hash_bytes.reverse();
-                            let block_hash = hex::encode(&hash_bytes[..8]);
-                            eprintln!("   Block hash (first 8 bytes): {}", block_hash);
+            None => {
+                // For cache/RPC, fetch blocks in height order, prefetching ahead of
+                // validation where each block is its own round trip
+                let mut batched = std::collections::VecDeque::new();
+                let mut prefetcher = match block_source.as_ref() {
+                    BlockDataSource::MmapCache(_) | BlockDataSource::Start9Rpc(_) => None,
+                    _ if chunk.prefetch_blocks > 0 => Some(crate::prefetch::BlockPrefetcher::new(
+                        block_source.clone(),
+                        chunk.rpc_limiter.clone(),
+                        start_height,
+                        actual_end,
+                        chunk.prefetch_blocks,
+                    )),
+                    _ => None,
+                };
+                for height in start_height..=actual_end {
+                    let read_started = std::time::Instant::now();
+                    let fetched;
+                    let block_bytes: &[u8] = match block_source.as_ref() {
+                        // Zero-copy: borrow straight from the shared mapping
+                        BlockDataSource::MmapCache(cache) => cache
+                            .get(height)
+                            .with_context(|| format!("Block {} not in mmap cache", height))?,
+                        // One session round trip per batch instead of per block
+                        BlockDataSource::Start9Rpc(session) => {
+                            if batched.is_empty() {
+                                let batch_end = (height + session.batch_size() as u64 - 1).min(actual_end);
+                                let blocks = rpc_limited(&chunk.rpc_limiter, block_source.as_ref(), session.get_blocks(height, batch_end)).await?;
+                                batched.extend(blocks);
+                            }
+                            fetched = batched.pop_front().with_context(|| format!("Start9 batch ended before block {}", height))?;
+                            &fetched
                         }
-                    }
-                } else {
-                    matched += 1;
-                }
+                        // With prefetching, read time is the wait for a block not yet fetched
+                        other => {
+                            fetched = match &mut prefetcher {
+                                Some(prefetcher) => prefetcher.next(height).await?,
+                                None => fetch_block(other, height, &chunk.rpc_limiter).await?,
+                            };
+                            &fetched
+                        }
+                    };
                 
-                tested += 1;
+                    let decode_started = std::time::Instant::now();
+                    let decoded = crate::decode_pipeline::decode_block(block_bytes, height)?;
+                    let mut timings = StageTimings {
+                        read_secs: (decode_started - read_started).as_secs_f64(),
+                        deserialize_secs: decode_started.elapsed().as_secs_f64(),
+                        ..Default::default()
+                    };
+                    let shape = block_shape(&decoded.0);
                 
-                // Progress indicator every 100 blocks (more frequent for better feedback)
-                if tested % 100 == 0 || tested == 1 {
-                    let total = actual_end - chunk.start_height + 1;
-                    let pct = 100.0 * tested as f64 / total as f64;
-                    let elapsed = start_time.elapsed().as_secs_f64();
-                    let rate = tested as f64 / elapsed;
-                    println!("📊 Chunk [{}-{}]: {}/{} blocks ({:.1}%) @ {:.1} blocks/sec", 
-                             chunk.start_height, actual_end, tested, total, pct, rate);
+                    // Process block (same logic)
+                    let processed = process_block(
+                        &block_bytes,
+                        decoded,
+                        height,
+                        utxo_store.as_mut(),
+                        block_source.as_ref(),
+                        chunk.captures_pre_state(),
+                        &chunk.quarantine_dir,
+                        &mut timings,
+                        &mut allocations,
+                        chunk.verify_node.as_ref(),
+                        &chunk.rpc_limiter,
+                        chunk.network,
+                    ).await;
+                    if !tally.record_block(chunk, height, &block_bytes, processed, &timings, shape, &mut progress).await? {
+                        break;
+                    }
                 }
             }
         }
-    }
     
-    let duration = start_time.elapsed().as_secs_f64();
+        Ok(())
+    }.await;
     
-    Ok(ChunkResult {
-        start_height: chunk.start_height,
-        end_height: actual_end,
+    let elapsed = start_time.elapsed();
+    let duration = elapsed.as_secs_f64();
+    progress.finish();
+    
+    let ChunkTally {
+        divergences,
+        waived,
+        version_divergences,
+        tested,
+        matched,
+        poisoned,
+        stage_timings,
+        era_timings,
+        slow_blocks,
+        slowest,
+        validated_through,
+        connected_through,
+        rollback,
+    } = tally;
+    let result = ChunkResult {
+        start_height,
+        end_height: match outcome {
+            Ok(()) => actual_end,
+            Err(_) => validated_through.unwrap_or(start_height),
+        },
         tested,
         matched,
         divergences,
+        waived,
+        version_divergences,
         duration_secs: duration,
-    })
+        utxo_count: utxo_store.len(),
+        finished_at: std::time::SystemTime::now(),
+        poisoned,
+        stage_timings,
+        era_timings,
+        slow_blocks,
+        slowest,
+        memory: crate::mem_profile::ChunkMemory::capture(&allocations, elapsed),
+        retries: 0,
+        assumed_utxo: None,
+        end_utxo: chunk.optimistic.as_ref().and_then(|_| utxo_store.to_utxo_set().ok()),
+        failed: None,
+        untested: None,
+    };
+    match outcome {
+        Ok(()) => Ok(result),
+        Err(error) => {
+            let (resume_height, resume_utxo) = match (rollback, connected_through) {
+                // Resume at the block whose comparison failed, so it's compared after all
+                (Some((height, pre_state)), _) => (height, Some(pre_state)),
+                // Connected but neither compared nor recoverable: the chunk can't resume without skipping it
+                (None, Some(height)) if Some(height) != validated_through => (height, None),
+                (None, connected_through) => (connected_through.map_or(start_height, |h| h + 1), utxo_store.to_utxo_set().ok()),
+            };
+            Err(Box::new(ChunkInterrupted {
+                error,
+                partial: validated_through.map(|_| result),
+                resume_height,
+                resume_utxo,
+            }))
+        }
+    }
+}
+
+/// `validate_chunk`, retrying a chunk that fails part-way (RPC hiccup, bad cache block)
+///
+/// A retry resumes after the last compared block rather than starting the chunk
+/// over, and each one moves a step down `fallbacks` (DirectFile → cache → RPC)
+/// while there is one left. Results from every attempt are merged; once the
+/// retries run out, whatever was compared is still returned, marked failed.
+pub async fn validate_chunk_with_retry(
+    mut chunk: BlockChunk,
+    block_source: Arc<BlockDataSource>,
+    fallbacks: &[Arc<BlockDataSource>],
+    max_retries: usize,
+) -> Result<ChunkResult> {
+    let mut utxo = match (chunk.checkpoint_utxo.take(), &chunk.optimistic) {
+        (Some(utxo), _) => utxo,
+        (None, Some(optimistic)) => optimistic.start_set(chunk.start_height, chunk.end_height).await.with_context(|| {
+            format!("Failed to fetch the start set of chunk [{}-{}] from Core", chunk.start_height, chunk.end_height)
+        })?,
+        (None, None) => UtxoSet::new(),
+    };
+    let assumed_utxo = chunk.optimistic.as_ref().map(|_| utxo.clone());
+    let mut start_height = chunk.start_height;
+    let mut source = block_source;
+    let mut fallbacks = fallbacks.iter();
+    let mut merged: Option<ChunkResult> = None;
+    let mut retries = 0;
+    loop {
+        let interrupted = match validate_chunk_from(&chunk, start_height, utxo, source.clone()).await {
+            Ok(result) => {
+                let mut result = match merged {
+                    Some(mut earlier) => {
+                        earlier.absorb(result);
+                        earlier
+                    }
+                    None => result,
+                };
+                result.start_height = chunk.start_height;
+                result.retries = retries;
+                result.assumed_utxo = assumed_utxo;
+                return Ok(result);
+            }
+            Err(interrupted) => *interrupted,
+        };
+        let ChunkInterrupted { error, partial, resume_height, resume_utxo } = interrupted;
+        if let Some(partial) = partial {
+            match &mut merged {
+                Some(earlier) => earlier.absorb(partial),
+                None => merged = Some(partial),
+            }
+        }
+        if resume_height > chunk.end_height {
+            // Every block was compared before the error
+            if let Some(mut result) = merged {
+                result.retries = retries;
+                result.assumed_utxo = assumed_utxo;
+                return Ok(result);
+            }
+        }
+        let resume_utxo = match resume_utxo {
+            Some(utxo) if retries < max_retries && resume_height <= chunk.end_height => utxo,
+            _ => {
+                let error = match retries {
+                    0 => error,
+                    _ => error.context(format!(
+                        "Chunk [{}-{}] still failing after {} retries",
+                        chunk.start_height, chunk.end_height, retries
+                    )),
+                };
+                return give_up(merged, chunk.start_height, chunk.end_height, resume_height, retries, error);
+            }
+        };
+        retries += 1;
+        if let Some(next) = fallbacks.next() {
+            source = next.clone();
+        }
+        let delay = std::time::Duration::from_secs(retries as u64);
+        eprintln!("🔁 Chunk [{}-{}] failed at height {}: {:#}", chunk.start_height, chunk.end_height, resume_height, error);
+        eprintln!("   Retry {}/{} in {}s, resuming at height {} via {}", retries, max_retries, delay.as_secs(), resume_height, source.name());
+        tokio::time::sleep(delay).await;
+        start_height = resume_height;
+        utxo = resume_utxo;
+    }
+}
+
+/// The result of a chunk that ran out of retries at `resume_height`
+///
+/// Blocks compared before the failure keep their matches and divergences; only
+/// `resume_height..=end_height` is reported untested. With nothing compared
+/// there's no result to keep and the error is returned.
+pub(crate) fn give_up(
+    merged: Option<ChunkResult>,
+    start_height: u64,
+    end_height: u64,
+    resume_height: u64,
+    retries: usize,
+    error: anyhow::Error,
+) -> Result<ChunkResult> {
+    let Some(mut result) = merged else {
+        return Err(error);
+    };
+    result.start_height = start_height;
+    result.retries = retries;
+    // A partial end set can't be reconciled against the next chunk
+    result.end_utxo = None;
+    result.failed = Some(format!("{:#}", error));
+    result.untested = Some((resume_height, end_height));
+    Ok(result)
+}
+
+/// Deferred UTXO check of an optimistic chunk; assumed coins BLVM's own state disagrees with become divergences
+fn reconcile_chunk(
+    reconciler: &mut crate::deferred_utxo::Reconciler,
+    result: &mut ChunkResult,
+    stream: Option<&crate::result_stream::ResultStream>,
+) {
+    match (&result.poisoned, result.assumed_utxo.take(), result.end_utxo.take()) {
+        (None, Some(assumed), Some(end)) => {
+            for mismatch in reconciler.fold(result.start_height, result.end_height, &assumed, end) {
+                let (blvm_str, core_str) = mismatch.describe();
+                eprintln!("❌ DEFERRED UTXO CHECK failed for chunk at {}: BLVM={}, Core={}", mismatch.chunk_start, blvm_str, core_str);
+                if let Some(stream) = stream {
+                    stream.divergence(mismatch.chunk_start, &blvm_str, &core_str, None);
+                }
+                result.divergences.push((mismatch.chunk_start, blvm_str, core_str));
+            }
+        }
+        _ => reconciler.gap(result.start_height),
+    }
+}
+
+/// Print peak RSS/heap and the `connect_block` allocation rate across chunks
+fn print_memory_summary(results: &[ChunkResult]) {
+    use crate::mem_profile::format_bytes;
+    let peak_rss = results.iter().filter_map(|r| r.memory.peak_rss_bytes).max();
+    let heap_peak = results.iter().filter_map(|r| r.memory.heap_peak_bytes).max();
+    let mut line = Vec::new();
+    if let Some(bytes) = peak_rss {
+        line.push(format!("peak RSS {}", format_bytes(bytes)));
+    }
+    if let Some(bytes) = heap_peak {
+        line.push(format!("peak heap {}", format_bytes(bytes)));
+    }
+    let alloc_bytes: u64 = results.iter().filter_map(|r| r.memory.connect_alloc_bytes).sum();
+    let allocations: u64 = results.iter().filter_map(|r| r.memory.connect_allocations).sum();
+    if heap_peak.is_some() {
+        let secs: f64 = results.iter().map(|r| r.duration_secs).sum::<f64>().max(f64::EPSILON);
+        line.push(format!(
+            "connect_block allocated {} in {} allocations ({}/s per chunk-second)",
+            format_bytes(alloc_bytes),
+            allocations,
+            format_bytes((alloc_bytes as f64 / secs) as u64)
+        ));
+    }
+    if !line.is_empty() {
+        println!("   Memory: {}", line.join(", "));
+    }
+}
+
+/// Open the checkpoint store and, if `generate`, make sure it has a checkpoint before every chunk
+///
+/// Checkpoints from a previous run over the same range are reused.
+pub(crate) async fn prepare_checkpoints(
+    start_height: u64,
+    end_height: u64,
+    config: &ParallelConfig,
+    generate: bool,
+    block_source: &Arc<BlockDataSource>,
+) -> Result<crate::checkpoint_store::CheckpointStore> {
+    let base_interval = crate::checkpoint_store::base_interval_from_env().unwrap_or_else(|e| {
+        eprintln!("⚠️  {}, using {}", e, crate::checkpoint_store::DEFAULT_BASE_INTERVAL);
+        crate::checkpoint_store::DEFAULT_BASE_INTERVAL
+    });
+    let mut checkpoint_store = crate::checkpoint_store::CheckpointStore::open(&config.checkpoint_dir, base_interval)?;
+    if generate {
+        // Each chunk after the first starts from the state one block before it
+        let needed = crate::chunk_sizing::checkpoint_heights(&crate::chunk_sizing::chunk_ranges(
+            start_height,
+            end_height,
+            config.chunk_size,
+            &config.chunk_sizing,
+        ));
+        if checkpoint_store.covers(start_height, &needed) {
+            println!("\n♻️  Phase 1: Reusing {} UTXO checkpoints from {}", needed.len(), config.checkpoint_dir.display());
+        } else {
+            println!("\n📌 Phase 1: Generating UTXO checkpoints ({})...", config.checkpoint_generation.name());
+            match config.checkpoint_generation {
+                crate::checkpoint_merge::CheckpointGeneration::Sequential => {
+                    generate_checkpoints(
+                        start_height,
+                        end_height,
+                        &needed,
+                        block_source,
+                        &config.utxo_backend,
+                        config.network,
+                        &mut checkpoint_store,
+                    )
+                    .await?;
+                }
+                crate::checkpoint_merge::CheckpointGeneration::Merged => {
+                    crate::checkpoint_merge::generate_checkpoints_merged(
+                        start_height,
+                        end_height,
+                        &needed,
+                        block_source,
+                        config.num_workers,
+                        &mut checkpoint_store,
+                    )
+                    .await?;
+                }
+            }
+        }
+    }
+    Ok(checkpoint_store)
 }
 
 /// Run parallel differential tests
@@ -858,177 +1820,417 @@ pub async fn validate_chunk(
 pub async fn run_parallel_differential(
     start_height: u64,
     end_height: u64,
-    config: ParallelConfig,
+    mut config: ParallelConfig,
     block_source: Arc<BlockDataSource>,
 ) -> Result<Vec<ChunkResult>> {
+    // Fail before any work if BLVM can't validate this network's blocks
+    consensus_network(config.network)?;
+
     // Get chain height
-    let chain_height = match block_source.as_ref() {
-        BlockDataSource::Rpc(client) => client.getblockcount().await?,
-        BlockDataSource::Start9Rpc(client) => client.get_block_count().await?,
-        BlockDataSource::SharedCache(_, Some(client)) => client.getblockcount().await?,
-        BlockDataSource::DirectFile(_) => {
-            // For direct file reading, we don't know exact height
-            // Use end_height as estimate
-            end_height
-        }
-        _ => end_height,
-    };
+    let chain_height = source_tip(&block_source, end_height).await?;
+    if let BlockDataSource::DirectFile(_) = block_source.as_ref() {
+        println!("📂 Block files end at height {}", chain_height);
+    }
     let actual_end = end_height.min(chain_height);
+    let run_started = std::time::SystemTime::now();
     
     println!("🚀 Starting parallel differential test");
+    if config.network != BlockFileNetwork::Mainnet {
+        println!("   Network: {}", crate::multi_network::network_name(config.network));
+    }
     println!("   Range: {} to {}", start_height, actual_end);
-    println!("   Chunk size: {}", config.chunk_size);
+    println!("   Chunk size: {} ({})", config.chunk_size, config.chunk_sizing.describe());
     println!("   Workers: {}", config.num_workers);
     println!("   Use checkpoints: {}", config.use_checkpoints);
+    println!("   UTXO backend: {}", config.utxo_backend.name());
+    if let Some(alerts) = &config.alerts {
+        println!("   Divergence alerts: {:?} webhook (run {})", alerts.format, alerts.run_id);
+    }
+    if !config.core_endpoints.is_empty() {
+        let labels: Vec<&str> = config.core_endpoints.iter().map(|e| e.label.as_str()).collect();
+        println!("   Extra Core versions: {}", labels.join(", "));
+    }
+    let num_workers = match &config.verify_node {
+        Some(verifier) => {
+            if config.sample.is_some() {
+                anyhow::bail!("A verify node needs blocks in order; it can't be used with sample mode");
+            }
+            verifier.check_tip(start_height).await?;
+            println!("   Core verdicts: submitblock to {} (chunks run one at a time)", verifier.url());
+            1
+        }
+        None => config.num_workers,
+    };
+    if config.fee_check.is_some() {
+        println!("   Fee/subsidy check: getblockstats for every accepted block");
+    }
+    if let Some(optimistic) = &config.optimistic {
+        if config.sample.is_some() {
+            anyhow::bail!("Sample mode already starts every block from Core's coins; it can't be combined with optimistic chunks");
+        }
+        println!("   Optimistic chunks: start sets from Core's undo data via {}, deferred UTXO check", optimistic.url());
+    }
+    if let Some(flamegraphs) = &config.flamegraphs {
+        println!(
+            "   Flamegraphs: blocks connecting in >= {}ms, up to {} in {}",
+            flamegraphs.threshold.as_millis(),
+            flamegraphs.max_graphs,
+            flamegraphs.out_dir.display()
+        );
+    }
+    if let Some(dir) = &config.input_bundle_dir {
+        println!("   Input bundles: recording accepted blocks to {}", dir.display());
+    }
+    if let Some(waivers) = &config.waivers {
+        println!("   Waivers: {}", waivers.len());
+        for waiver in waivers.expired() {
+            eprintln!("⚠️  Expired waiver no longer applies: {}", waiver.describe());
+        }
+    }
+    if matches!(block_source.as_ref(), BlockDataSource::Rpc(_) | BlockDataSource::Start9Rpc(_) | BlockDataSource::SharedCache(_, Some(_))) {
+        println!("   RPC limits: {}", config.rpc_limiter.limits().summary());
+    }
+    if let Some(path) = crate::multi_network::report_path("BLVM_RESULTS_JSONL", config.network) {
+        let stream = crate::result_stream::ResultStream::open(std::path::Path::new(&path), run_started)?;
+        stream.run_started(start_height, actual_end, &config);
+        println!("   Streaming results to {}", path);
+        config.result_stream = Some(Arc::new(stream));
+    }
+    let per_block_source = !matches!(
+        block_source.as_ref(),
+        BlockDataSource::DirectFile(_) | BlockDataSource::MmapCache(_) | BlockDataSource::Start9Rpc(_)
+    );
+    if per_block_source && config.prefetch_blocks > 0 {
+        println!("   Prefetch: {} blocks ahead of validation", config.prefetch_blocks);
+    }
     
-    // Generate checkpoints if enabled
-    let checkpoints = if config.use_checkpoints {
-        println!("\n📌 Phase 1: Generating UTXO checkpoints...");
-        generate_checkpoints(start_height, actual_end, config.chunk_size, block_source.as_ref()).await?
-    } else {
-        Vec::new()
+    // Sample mode: a seeded handful of blocks per era, each with the coins it spends
+    let sampled = match &config.sample {
+        Some(sample) => {
+            let heights = crate::sampler::sample_heights(start_height, actual_end, sample);
+            if sample.is_exhaustive() {
+                println!("\n🎯 Validating all {} blocks from block-local checkpoints", heights.len());
+            } else {
+                println!("\n🎲 Sample mode: {} blocks ({} per era, seed {})", heights.len(), sample.blocks_per_era, sample.seed);
+            }
+            let client = match block_source.as_ref() {
+                BlockDataSource::Rpc(client) | BlockDataSource::SharedCache(_, Some(client)) => client.clone(),
+                _ => Arc::new(crate::core_rpc_client::CoreRpcClient::new(crate::core_rpc_client::RpcConfig::from_env())),
+            };
+            Some(crate::sampler::sample_checkpoints(&client, &heights).await?)
+        }
+        None => None,
     };
     
+    // Generate checkpoints if enabled (or reuse a previous run's)
+    let checkpoint_store = prepare_checkpoints(
+        start_height,
+        actual_end,
+        &config,
+        config.use_checkpoints && sampled.is_none() && config.optimistic.is_none(),
+        &block_source,
+    )
+    .await?;
+    let mut checkpoints = checkpoint_store.replay();
+    
     // Create chunks
+    let total_blocks = match &sampled {
+        Some(samples) => samples.len() as u64,
+        None => actual_end.saturating_sub(start_height) + 1,
+    };
+    let progress = crate::progress::RunProgress::new(total_blocks);
     let mut chunks = Vec::new();
-    let mut current_start = start_height;
-    let mut checkpoint_idx = 0;
     
-    while current_start <= actual_end {
-        let chunk_end = (current_start + config.chunk_size - 1).min(actual_end);
-        
-        // Find checkpoint UTXO for this chunk
-        let checkpoint_utxo = if config.use_checkpoints && checkpoint_idx > 0 {
-            // Use previous checkpoint as starting UTXO
-            checkpoints.get(checkpoint_idx - 1).map(|(_, utxo)| utxo.clone())
-        } else if current_start == start_height {
-            // First chunk starts with empty UTXO set
-            Some(UtxoSet::new())
-        } else {
-            None
-        };
-        
-        chunks.push(BlockChunk {
-            start_height: current_start,
-            end_height: chunk_end,
-            checkpoint_utxo,
-            skip_validation: !config.use_checkpoints, // Skip validation if checkpoints disabled
-        });
+    if let Some(samples) = sampled {
+        // One single-block chunk per sampled height
+        for (height, pre_state) in samples {
+            chunks.push(config.chunk(height, height, Some(pre_state), progress.clone()));
+        }
+    } else {
+        for (current_start, chunk_end) in crate::chunk_sizing::chunk_ranges(start_height, actual_end, config.chunk_size, &config.chunk_sizing) {
+            // Find checkpoint UTXO for this chunk
+            let checkpoint_utxo = if config.optimistic.is_some() {
+                // Fetched from Core's undo data by the chunk's worker
+                None
+            } else if config.use_checkpoints && current_start > start_height {
+                // Use the checkpoint at the end of the previous chunk as starting UTXO
+                Some(checkpoints.advance_to(current_start - 1)?)
+            } else if current_start == start_height {
+                // First chunk starts with empty UTXO set
+                Some(UtxoSet::new())
+            } else {
+                None
+            };
         
-        current_start = chunk_end + 1;
-        if current_start <= actual_end && checkpoint_idx < checkpoints.len() {
-            checkpoint_idx += 1;
+            chunks.push(config.chunk(current_start, chunk_end, checkpoint_utxo, progress.clone()));
         }
     }
     
     println!("\n📦 Created {} chunks for parallel execution", chunks.len());
     
-    // If checkpoints disabled, just build cache by reading blocks (no validation)
-    if !config.use_checkpoints {
-        println!("\n📦 Cache building mode: Reading blocks in parallel to build cache (no validation)...");
-        println!("   This will populate the cache file for future use");
-        
-        // For cache building, we just need to trigger block reading
-        // The cache is built automatically when blocks are read from files
-        match block_source.as_ref() {
-            BlockDataSource::DirectFile(reader) => {
-                // Trigger cache building by reading blocks sequentially
-                // This will use parallel file reading internally
-                println!("   🚀 Starting parallel block reading to build cache...");
-                let iterator = reader.read_blocks_sequential(Some(start_height), Some((actual_end - start_height + 1) as usize))?;
-                
-                let mut blocks_read = 0;
-                for (idx, block_result) in iterator.enumerate() {
-                    let height = start_height + idx as u64;
-                    match block_result {
-                        Ok(_) => {
-                            blocks_read += 1;
-                            if blocks_read % 10000 == 0 {
-                                println!("   📊 Read {} blocks (at height {})", blocks_read, height);
-                            }
-                        }
-                        Err(e) => {
-                            eprintln!("   ⚠️  Failed to read block at height {}: {}", height, e);
-                            // Continue reading - don't fail on individual block errors
-                        }
-                    }
-                }
-                
-                println!("   ✅ Cache building complete: {} blocks read", blocks_read);
-                
-                // Return empty results since we're not validating
-                return Ok(Vec::new());
-            }
-            BlockDataSource::Start9Rpc(_) | BlockDataSource::Rpc(_) | BlockDataSource::SharedCache(_, _) => {
-                // For RPC sources, we can't build cache efficiently in parallel
-                // The cache building happens in block_file_reader when using DirectFile
-                println!("   ⚠️  Cache building requires DirectFile source (currently using RPC)");
-                println!("   💡 Cache will be built when blocks are read, but it's slower via RPC");
-                println!("   📦 Proceeding with cache building via current source...");
-                // Fall through - let it process chunks but skip validation
-            }
-        }
-    }
-    
     // Run chunks in parallel with semaphore to limit concurrency
-    let semaphore = Arc::new(Semaphore::new(config.num_workers));
+    let semaphore = match (&config.worker_pool, &config.verify_node) {
+        (Some(pool), None) => pool.clone(),
+        _ => Arc::new(Semaphore::new(num_workers)),
+    };
     let mut handles = Vec::new();
     
     for chunk in chunks {
+        let chunk_range = (chunk.start_height, chunk.end_height);
         let permit = semaphore.clone().acquire_owned().await?;
         let block_source_clone = block_source.clone();
+        let fallbacks = config.fallback_sources.clone();
+        let max_retries = config.chunk_retries;
         
         let handle = tokio::spawn(async move {
             let _permit = permit;
-            let result = validate_chunk(chunk, block_source_clone).await;
-            result
+            let stream = chunk.result_stream.clone();
+            // Optimistic chunks are streamed once the deferred UTXO check has run
+            let deferred = chunk.optimistic.is_some();
+            let outcome = validate_chunk_with_retry(chunk, block_source_clone, &fallbacks, max_retries).await;
+            if let Some(stream) = &stream {
+                match &outcome {
+                    Ok(result) => {
+                        if !deferred || result.failed.is_some() {
+                            stream.chunk_finished(result);
+                        }
+                        if let (Some(error), Some((from, to))) = (&result.failed, result.untested) {
+                            stream.chunk_failed(from, to, &anyhow::anyhow!("{}", error));
+                        }
+                    }
+                    Err(e) => stream.chunk_failed(chunk_range.0, chunk_range.1, e),
+                }
+            }
+            outcome
         });
         
-        handles.push(handle);
+        handles.push((chunk_range, handle));
     }
     
     // Collect results
     println!("\n⚡ Phase 2: Running chunks in parallel...");
     let mut results = Vec::new();
-    for (idx, handle) in handles.into_iter().enumerate() {
-        match handle.await {
-            Ok(Ok(result)) => {
-                println!("✅ Chunk {} [{}-{}]: {} blocks, {} divergences, {:.1}s", 
-                         idx + 1, result.start_height, result.end_height,
-                         result.tested, result.divergences.len(), result.duration_secs);
+    let mut reconciler = config.optimistic.as_ref().map(|_| crate::deferred_utxo::Reconciler::new(start_height));
+    for (idx, ((chunk_start, chunk_end), handle)) in handles.into_iter().enumerate() {
+        let outcome = handle.await;
+        if let (Some(reconciler), false) = (&mut reconciler, matches!(&outcome, Ok(Ok(result)) if result.failed.is_none())) {
+            reconciler.gap(chunk_start);
+        }
+        match outcome {
+            // Failed after comparing part of the chunk: keep what was compared, the rest is untested
+            Ok(Ok(result)) if result.failed.is_some() => {
+                eprintln!(
+                    "❌ Chunk {} [{}-{}] failed after {} blocks ({} divergences): {}",
+                    idx + 1,
+                    chunk_start,
+                    chunk_end,
+                    result.tested,
+                    result.divergences.len(),
+                    result.failed.as_deref().unwrap_or_default()
+                );
+                results.push(result);
+            }
+            Ok(Ok(mut result)) => {
+                if let Some(reconciler) = &mut reconciler {
+                    reconcile_chunk(reconciler, &mut result, config.result_stream.as_deref());
+                    if let Some(stream) = &config.result_stream {
+                        stream.chunk_finished(&result);
+                    }
+                }
+                if let Some(block_panic) = &result.poisoned {
+                    eprintln!("☣️  Chunk {} [{}-{}] poisoned after {} blocks: {}", 
+                             idx + 1, chunk_start, chunk_end, result.tested, block_panic);
+                    quarantine_chunk(&config.quarantine_dir, chunk_start, chunk_end, block_panic.clone());
+                } else {
+                    let rss = result.memory.rss_bytes
+                        .map(|bytes| format!(", RSS {}", crate::mem_profile::format_bytes(bytes)))
+                        .unwrap_or_default();
+                    println!("✅ Chunk {} [{}-{}]: {} blocks, {} divergences, {:.1}s{}", 
+                             idx + 1, result.start_height, result.end_height,
+                             result.tested, result.divergences.len(), result.duration_secs, rss);
+                }
                 results.push(result);
             }
+            // A chunk without a result still gets one, so the summary knows its heights went untested
             Ok(Err(e)) => {
                 eprintln!("❌ Chunk {} failed: {}", idx + 1, e);
+                results.push(ChunkResult::incomplete(chunk_start, chunk_end, Some(format!("{:#}", e)), None));
+            }
+            Err(e) if e.is_panic() => {
+                // Panicked outside block processing - height unknown, but still record it
+                let message = crate::quarantine::panic_message(e.into_panic().as_ref());
+                eprintln!("❌ Chunk {} panicked: {}", idx + 1, message);
+                if let Some(stream) = &config.result_stream {
+                    stream.chunk_failed(chunk_start, chunk_end, &anyhow::anyhow!("panicked: {}", message));
+                }
+                let block_panic = crate::quarantine::BlockPanic {
+                    height: None,
+                    message,
+                    artifact_dir: None,
+                };
+                quarantine_chunk(&config.quarantine_dir, chunk_start, chunk_end, block_panic.clone());
+                results.push(ChunkResult::incomplete(chunk_start, chunk_end, None, Some(block_panic)));
             }
             Err(e) => {
-                eprintln!("❌ Chunk {} panicked: {}", idx + 1, e);
+                eprintln!("❌ Chunk {} was cancelled: {}", idx + 1, e);
+                let error = anyhow::anyhow!("cancelled: {}", e);
+                if let Some(stream) = &config.result_stream {
+                    stream.chunk_failed(chunk_start, chunk_end, &error);
+                }
+                results.push(ChunkResult::incomplete(chunk_start, chunk_end, Some(format!("{:#}", error)), None));
             }
         }
     }
     
+    progress.finish();
+    if let Some(reconciler) = &reconciler {
+        match reconciler.stopped_at() {
+            None => println!(
+                "\n🔎 Deferred UTXO check: {} assumed coins checked against BLVM's sequential state, {} mismatched",
+                reconciler.checked(),
+                reconciler.mismatched()
+            ),
+            Some(height) => eprintln!(
+                "\n⚠️  Deferred UTXO check stopped at height {} (a chunk there didn't finish); {} coins checked before it, {} mismatched",
+                height,
+                reconciler.checked(),
+                reconciler.mismatched()
+            ),
+        }
+    }
+    
     // Summary
     let total_tested: usize = results.iter().map(|r| r.tested).sum();
     let total_matched: usize = results.iter().map(|r| r.matched).sum();
     let total_divergences: usize = results.iter().map(|r| r.divergences.len()).sum();
+    let total_version_divergences: usize = results.iter().map(|r| r.version_divergences.len()).sum();
+    let total_poisoned = results.iter().filter(|r| r.poisoned.is_some()).count();
+    let total_retries: usize = results.iter().map(|r| r.retries).sum();
     let total_duration: f64 = results.iter().map(|r| r.duration_secs).sum();
     
     println!("\n📊 Parallel Differential Test Summary:");
     println!("   Total blocks tested: {}", total_tested);
     println!("   Matched: {}", total_matched);
     println!("   Divergences: {}", total_divergences);
+    let total_waived: usize = results.iter().map(|r| r.waived.len()).sum();
+    if total_waived > 0 {
+        println!("   Waived divergences: {} (recorded, not failing the run)", total_waived);
+    }
+    if !config.core_endpoints.is_empty() {
+        println!("   Version divergences: {}", total_version_divergences);
+    }
+    if total_poisoned > 0 {
+        println!("   Poisoned chunks: {} (see {})", total_poisoned, config.quarantine_dir.join("manifest.json").display());
+    }
+    if total_retries > 0 {
+        println!("   Chunk retries: {}", total_retries);
+    }
+    let failed: Vec<String> = results
+        .iter()
+        .filter_map(|r| r.untested)
+        .map(|(from, to)| format!("{}-{}", from, to))
+        .collect();
+    if !failed.is_empty() {
+        println!("   Failed chunks: {} (heights {} untested)", failed.len(), failed.join(", "));
+    }
+    let overloads = config.rpc_limiter.overloads();
+    if overloads > 0 {
+        println!("   RPC work-queue rejections: {} (lower BLVM_RPC_MAX_IN_FLIGHT or raise Core's -rpcworkqueue)", overloads);
+    }
     println!("   Total duration: {:.1}s ({:.1} minutes)", total_duration, total_duration / 60.0);
     println!("   Throughput: {:.1} blocks/sec", total_tested as f64 / total_duration);
+    let mut total_stages = StageTimings::default();
+    for result in &results {
+        total_stages.add(&result.stage_timings);
+    }
+    println!("   Stages: {}", total_stages.summary());
+    let slow_blocks: usize = results.iter().map(|r| r.slow_blocks.len()).sum();
+    if let Some(threshold) = config.slow_block_threshold {
+        println!("   Slow blocks (>= {}ms): {}", threshold.as_millis(), slow_blocks);
+    }
+    let mut slowest = crate::slow_blocks::Leaderboard::new(config.slowest_blocks);
+    for result in &results {
+        slowest.merge(&result.slowest);
+    }
+    slowest.print_report();
+    print_memory_summary(&results);
+    if let Some(sample) = config.sample.filter(|s| !s.is_exhaustive()) {
+        println!("   Sample seed: {} (reproduce with BLVM_SAMPLE_BLOCKS={} BLVM_SAMPLE_SEED={})",
+                 sample.seed, sample.blocks_per_era, sample.seed);
+    }
+    
+    if let Some(stream) = &config.result_stream {
+        stream.append(&crate::result_stream::StreamEvent::RunFinished {
+            tested: total_tested,
+            divergences: total_divergences,
+            wall_secs: run_started.elapsed().map(|d| d.as_secs_f64()).unwrap_or_default(),
+        });
+        println!("   Results streamed to {}", stream.path().display());
+    }
+    
+    #[cfg(feature = "results-db")]
+    if let Ok(db_path) = std::env::var("BLVM_RESULTS_DB") {
+        let recorded = crate::results_db::ResultsDb::open(&db_path).and_then(|mut db| {
+            let revisions = crate::results_db::Revisions::detect();
+            let wall_secs = run_started.elapsed().map(|d| d.as_secs_f64()).unwrap_or_default();
+            db.record_run(start_height, actual_end, &config, &revisions, &results, wall_secs)
+        });
+        match recorded {
+            Ok(run_id) => println!("   Recorded as run #{} in {}", run_id, db_path),
+            Err(e) => eprintln!("⚠️  Failed to record run in {}: {}", db_path, e),
+        }
+    }
+    
+    let run_record = crate::multi_network::report_path("BLVM_RUN_RECORD", config.network);
+    let html_report = crate::multi_network::report_path("BLVM_HTML_REPORT", config.network);
+    let junit_xml = crate::multi_network::report_path("BLVM_JUNIT_XML", config.network);
+    if run_record.is_some() || html_report.is_some() || junit_xml.is_some() {
+        let record = crate::html_report::RunRecord::from_results(start_height, actual_end, &config, run_started, &results);
+        if let Some(path) = run_record {
+            match record.save(std::path::Path::new(&path)) {
+                Ok(()) => println!("   Run record saved to {}", path),
+                Err(e) => eprintln!("⚠️  {}", e),
+            }
+        }
+        if let Some(path) = html_report {
+            match crate::html_report::write_html_report(&record, std::path::Path::new(&path)) {
+                Ok(()) => println!("   HTML report written to {}", path),
+                Err(e) => eprintln!("⚠️  {}", e),
+            }
+        }
+        if let Some(path) = junit_xml {
+            match crate::ci_report::write_junit(&record, std::path::Path::new(&path)) {
+                Ok(()) => println!("   JUnit report written to {}", path),
+                Err(e) => eprintln!("⚠️  {}", e),
+            }
+        }
+    }
     
     if total_divergences > 0 {
         println!("\n❌ Divergences found:");
         for result in &results {
             for (height, blvm, core) in &result.divergences {
-                println!("   Height {}: BLVM={}, Core={}", height, blvm, core);
+                match result.waived.iter().find(|(waived, _)| waived == height) {
+                    Some((_, reason)) => println!("   Height {}: BLVM={}, Core={} (waived: {})", height, blvm, core, reason),
+                    None => println!("   Height {}: BLVM={}, Core={}", height, blvm, core),
+                }
             }
         }
     }
     
+    if total_version_divergences > 0 {
+        println!("\n❌ Version divergences found:");
+        for result in &results {
+            for divergence in &result.version_divergences {
+                println!("   {}", divergence);
+            }
+        }
+    }
+    
+    if crate::gh_annotations::enabled() {
+        annotate_results(&config, &results);
+    }
+    
     Ok(results)
 }
 
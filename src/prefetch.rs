@@ -0,0 +1,155 @@
+//! Block Prefetching
+//!
+//! For sources read block by block (RPC, shared cache, P2P, custom sources),
+//! `validate_chunk` used to fetch a block, validate it, then fetch the next, so
+//! every network round trip sat idle next to `connect_block`. `BlockPrefetcher`
+//! keeps up to `depth` fetches in flight on the tokio runtime ahead of the
+//! consumer and hands blocks back in height order, overlapping fetch latency
+//! with validation.
+//!
+//! At most `depth` blocks are fetched or held at once, so a slow validator
+//! stops the fetching instead of buffering the chain in memory. Calls to a
+//! node still go through the shared `RpcLimiter`, which backs off when Core's
+//! work queue fills. Depth 0 fetches inline (the old serial behaviour).
+//! Configured with `BLVM_PREFETCH_BLOCKS`.
+
+use anyhow::{Context, Result};
+use std::collections::VecDeque;
+use std::sync::Arc;
+use tokio::task::JoinHandle;
+
+use crate::parallel_differential::{fetch_block, BlockDataSource};
+use crate::rpc_limiter::RpcLimiter;
+
+/// Blocks fetched ahead of validation by default
+pub const DEFAULT_PREFETCH_BLOCKS: usize = 8;
+
+/// Prefetch depth from `BLVM_PREFETCH_BLOCKS` (default `DEFAULT_PREFETCH_BLOCKS`)
+pub fn prefetch_blocks_from_env() -> Result<usize> {
+    match std::env::var("BLVM_PREFETCH_BLOCKS") {
+        Ok(depth) => depth
+            .parse()
+            .with_context(|| format!("Invalid BLVM_PREFETCH_BLOCKS '{}'", depth)),
+        Err(_) => Ok(DEFAULT_PREFETCH_BLOCKS),
+    }
+}
+
+/// Fetches `start..=end` up to `depth` blocks ahead of the consumer
+pub struct BlockPrefetcher {
+    source: Arc<BlockDataSource>,
+    limiter: Arc<RpcLimiter>,
+    depth: usize,
+    /// Next height to start fetching
+    next_spawn: u64,
+    end: u64,
+    in_flight: VecDeque<(u64, JoinHandle<Result<Vec<u8>>>)>,
+}
+
+impl BlockPrefetcher {
+    /// Start fetching from `start`; must run inside a tokio runtime
+    pub fn new(source: Arc<BlockDataSource>, limiter: Arc<RpcLimiter>, start: u64, end: u64, depth: usize) -> Self {
+        let mut prefetcher = Self {
+            source,
+            limiter,
+            depth: depth.max(1),
+            next_spawn: start,
+            end,
+            in_flight: VecDeque::new(),
+        };
+        prefetcher.top_up();
+        prefetcher
+    }
+
+    fn top_up(&mut self) {
+        while self.in_flight.len() < self.depth && self.next_spawn <= self.end {
+            let height = self.next_spawn;
+            let source = self.source.clone();
+            let limiter = self.limiter.clone();
+            let handle = tokio::spawn(async move { fetch_block(&source, height, &limiter).await });
+            self.in_flight.push_back((height, handle));
+            self.next_spawn += 1;
+        }
+    }
+
+    /// The block at `height`, which must be the next one in order
+    pub async fn next(&mut self, height: u64) -> Result<Vec<u8>> {
+        let (fetched_height, handle) = self
+            .in_flight
+            .pop_front()
+            .with_context(|| format!("Prefetcher has nothing left for block {}", height))?;
+        if fetched_height != height {
+            anyhow::bail!("Prefetcher out of step: asked for block {}, next is {}", height, fetched_height);
+        }
+        let result = match handle.await {
+            Ok(result) => result,
+            Err(e) if e.is_panic() => std::panic::resume_unwind(e.into_panic()),
+            Err(e) => Err(anyhow::anyhow!("Fetch of block {} was cancelled: {}", height, e)),
+        };
+        // Refill once this block is out, so `depth` bounds what's fetched or held
+        self.top_up();
+        result
+    }
+}
+
+impl Drop for BlockPrefetcher {
+    fn drop(&mut self) {
+        // A chunk that stops early (error, poisoned block) shouldn't keep fetching
+        for (_, handle) in &self.in_flight {
+            handle.abort();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::block_source::BlockSource;
+    use async_trait::async_trait;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Duration;
+
+    /// One-byte blocks that take 20ms each to "download"
+    struct SlowSource {
+        in_flight: AtomicUsize,
+        max_in_flight: AtomicUsize,
+    }
+
+    #[async_trait]
+    impl BlockSource for SlowSource {
+        fn name(&self) -> &str {
+            "slow"
+        }
+
+        async fn get_block(&self, height: u64) -> Result<Vec<u8>> {
+            let now = self.in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+            self.max_in_flight.fetch_max(now, Ordering::SeqCst);
+            tokio::time::sleep(Duration::from_millis(20)).await;
+            self.in_flight.fetch_sub(1, Ordering::SeqCst);
+            Ok(vec![height as u8])
+        }
+
+        async fn get_tip_height(&self) -> Result<Option<u64>> {
+            Ok(None)
+        }
+    }
+
+    #[test]
+    fn test_prefetch_in_order_and_bounded() {
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let slow = Arc::new(SlowSource { in_flight: AtomicUsize::new(0), max_in_flight: AtomicUsize::new(0) });
+        let source = Arc::new(BlockDataSource::Custom(slow.clone()));
+        let limiter = Arc::new(RpcLimiter::new(crate::rpc_limiter::RpcLimits::default()));
+
+        runtime.block_on(async {
+            let started = std::time::Instant::now();
+            let mut prefetcher = BlockPrefetcher::new(source, limiter, 10, 29, 4);
+            for height in 10..=29 {
+                assert_eq!(prefetcher.next(height).await.unwrap(), vec![height as u8]);
+            }
+            // 20 blocks of 20ms, four at a time
+            assert!(started.elapsed() < Duration::from_millis(300), "took {:?}", started.elapsed());
+            assert!(prefetcher.next(30).await.is_err());
+        });
+        assert_eq!(slow.max_in_flight.load(Ordering::SeqCst), 4);
+    }
+}
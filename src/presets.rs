@@ -0,0 +1,77 @@
+//! Per-Era Differential Presets
+//!
+//! Named height windows around historically tricky parts of mainnet, so a
+//! developer can re-validate a known-risky era with `--preset <name>` instead
+//! of looking the heights up.
+//!
+//! A window doesn't start at genesis, so preset runs validate every block from
+//! a block-local checkpoint (see `sampler`) rather than replaying the chain.
+
+use anyhow::Result;
+
+/// A named height window
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Preset {
+    pub name: &'static str,
+    pub description: &'static str,
+    pub start: u64,
+    /// Inclusive
+    pub end: u64,
+}
+
+pub const PRESETS: &[Preset] = &[
+    Preset {
+        name: "bip30-duplicates",
+        description: "duplicate coinbase txids at 91722/91880 overwritten by 91812/91842 (BIP30 exceptions)",
+        start: 91_700,
+        end: 91_900,
+    },
+    Preset {
+        name: "bip66-fork",
+        description: "BIP66 strict-DER enforcement and the July 2015 fork at 363731",
+        start: 363_700,
+        end: 363_800,
+    },
+    Preset {
+        name: "segwit-activation",
+        description: "segwit activation at 481824",
+        start: 481_800,
+        end: 481_900,
+    },
+    Preset {
+        name: "taproot-activation",
+        description: "taproot activation at 709632",
+        start: 709_600,
+        end: 709_700,
+    },
+];
+
+impl Preset {
+    /// Look up a preset by name
+    pub fn find(name: &str) -> Result<&'static Preset> {
+        PRESETS.iter().find(|p| p.name == name).ok_or_else(|| {
+            let names: Vec<&str> = PRESETS.iter().map(|p| p.name).collect();
+            anyhow::anyhow!("Unknown preset '{}' (available: {})", name, names.join(", "))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_presets_cover_their_heights() {
+        for (name, height) in [
+            ("bip30-duplicates", 91_722),
+            ("bip30-duplicates", 91_880),
+            ("bip66-fork", 363_731),
+            ("segwit-activation", 481_824),
+            ("taproot-activation", 709_632),
+        ] {
+            let preset = Preset::find(name).unwrap();
+            assert!((preset.start..=preset.end).contains(&height), "{} misses {}", name, height);
+        }
+        assert!(Preset::find("nope").unwrap_err().to_string().contains("segwit-activation"));
+    }
+}
@@ -0,0 +1,202 @@
+//! Run Progress Reporting
+//!
+//! Per-chunk progress for parallel differential runs. With the `tui` feature
+//! and an interactive terminal, each running chunk gets its own progress bar
+//! under an aggregate bar showing blocks/sec, ETA, resident memory and a live
+//! divergence counter. Otherwise (or with `BLVM_PROGRESS=plain`) the original
+//! println lines are printed every 100 blocks.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Instant;
+
+/// Blocks between plain-text progress lines
+const PLAIN_INTERVAL: u64 = 100;
+
+/// Resident set size of this process (Linux only)
+pub fn current_rss_bytes() -> Option<u64> {
+    let status = std::fs::read_to_string("/proc/self/status").ok()?;
+    let line = status.lines().find(|l| l.starts_with("VmRSS:"))?;
+    let kb: u64 = line.split_whitespace().nth(1)?.parse().ok()?;
+    Some(kb * 1024)
+}
+
+/// Progress shared by every chunk of a run
+pub struct RunProgress {
+    total_blocks: u64,
+    started: Instant,
+    tested: AtomicU64,
+    divergences: AtomicU64,
+    #[cfg(feature = "tui")]
+    tui: Option<tui::Bars>,
+}
+
+impl std::fmt::Debug for RunProgress {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RunProgress")
+            .field("total_blocks", &self.total_blocks)
+            .field("tested", &self.tested.load(Ordering::Relaxed))
+            .finish()
+    }
+}
+
+impl RunProgress {
+    /// Progress for a run over `total_blocks`; uses bars when available
+    pub fn new(total_blocks: u64) -> Arc<Self> {
+        Arc::new(Self {
+            total_blocks,
+            started: Instant::now(),
+            tested: AtomicU64::new(0),
+            divergences: AtomicU64::new(0),
+            #[cfg(feature = "tui")]
+            tui: tui::Bars::new(total_blocks),
+        })
+    }
+
+    /// Start tracking a chunk
+    pub fn chunk(self: &Arc<Self>, start_height: u64, end_height: u64) -> ChunkProgress {
+        ChunkProgress {
+            run: self.clone(),
+            start_height,
+            end_height,
+            started: Instant::now(),
+            tested: 0,
+            #[cfg(feature = "tui")]
+            bar: self.tui.as_ref().map(|t| t.add_chunk(start_height, end_height)),
+        }
+    }
+
+    /// Aggregate line: blocks/sec, ETA, memory, divergences
+    fn aggregate_message(&self) -> String {
+        let tested = self.tested.load(Ordering::Relaxed);
+        let rate = tested as f64 / self.started.elapsed().as_secs_f64().max(1e-9);
+        let eta = if rate > 0.0 {
+            format!("{:.0}m", self.total_blocks.saturating_sub(tested) as f64 / rate / 60.0)
+        } else {
+            "?".to_string()
+        };
+        let rss = current_rss_bytes()
+            .map(|b| format!("{:.1} GiB", b as f64 / (1u64 << 30) as f64))
+            .unwrap_or_else(|| "n/a".to_string());
+        format!(
+            "{:.1} blocks/sec | ETA {} | RSS {} | divergences {}",
+            rate,
+            eta,
+            rss,
+            self.divergences.load(Ordering::Relaxed)
+        )
+    }
+
+    /// Clear the bars and print a final aggregate line
+    pub fn finish(&self) {
+        #[cfg(feature = "tui")]
+        if let Some(tui) = &self.tui {
+            tui.finish(&self.aggregate_message());
+            return;
+        }
+        println!("📊 {}", self.aggregate_message());
+    }
+}
+
+/// One chunk's progress
+pub struct ChunkProgress {
+    run: Arc<RunProgress>,
+    start_height: u64,
+    end_height: u64,
+    started: Instant,
+    tested: u64,
+    #[cfg(feature = "tui")]
+    bar: Option<indicatif::ProgressBar>,
+}
+
+impl ChunkProgress {
+    /// Record one processed block
+    pub fn block_done(&mut self, diverged: bool) {
+        self.tested += 1;
+        self.run.tested.fetch_add(1, Ordering::Relaxed);
+        if diverged {
+            self.run.divergences.fetch_add(1, Ordering::Relaxed);
+        }
+
+        #[cfg(feature = "tui")]
+        if let Some(bar) = &self.bar {
+            bar.inc(1);
+            if let Some(tui) = &self.run.tui {
+                tui.update(self.run.tested.load(Ordering::Relaxed), || self.run.aggregate_message());
+            }
+            return;
+        }
+
+        if self.tested % PLAIN_INTERVAL == 0 || self.tested == 1 {
+            let total = self.end_height - self.start_height + 1;
+            let pct = 100.0 * self.tested as f64 / total as f64;
+            let rate = self.tested as f64 / self.started.elapsed().as_secs_f64();
+            println!(
+                "📊 Chunk [{}-{}]: {}/{} blocks ({:.1}%) @ {:.1} blocks/sec",
+                self.start_height, self.end_height, self.tested, total, pct, rate
+            );
+        }
+    }
+
+    /// Remove the chunk's bar
+    pub fn finish(self) {
+        #[cfg(feature = "tui")]
+        if let Some(bar) = self.bar {
+            bar.finish_and_clear();
+        }
+    }
+}
+
+#[cfg(feature = "tui")]
+mod tui {
+    use indicatif::{MultiProgress, ProgressBar, ProgressDrawTarget, ProgressStyle};
+    use std::io::IsTerminal;
+
+    /// Refresh the aggregate message every this many blocks
+    const AGGREGATE_INTERVAL: u64 = 50;
+
+    pub(super) struct Bars {
+        multi: MultiProgress,
+        aggregate: ProgressBar,
+    }
+
+    impl Bars {
+        /// None when output isn't a terminal or `BLVM_PROGRESS=plain`
+        pub(super) fn new(total_blocks: u64) -> Option<Self> {
+            if std::env::var("BLVM_PROGRESS").as_deref() == Ok("plain") || !std::io::stderr().is_terminal() {
+                return None;
+            }
+            let multi = MultiProgress::with_draw_target(ProgressDrawTarget::stderr());
+            let aggregate = multi.add(ProgressBar::new(total_blocks));
+            aggregate.set_style(
+                ProgressStyle::with_template("{prefix:>12} [{bar:40.green}] {pos}/{len} {msg}")
+                    .expect("valid template")
+                    .progress_chars("=> "),
+            );
+            aggregate.set_prefix("total");
+            Some(Self { multi, aggregate })
+        }
+
+        pub(super) fn add_chunk(&self, start_height: u64, end_height: u64) -> ProgressBar {
+            let bar = self.multi.add(ProgressBar::new(end_height - start_height + 1));
+            bar.set_style(
+                ProgressStyle::with_template("{prefix:>12} [{bar:40.cyan}] {pos}/{len} {per_sec} eta {eta}")
+                    .expect("valid template")
+                    .progress_chars("=> "),
+            );
+            bar.set_prefix(format!("{}", start_height));
+            bar
+        }
+
+        pub(super) fn update(&self, tested: u64, message: impl FnOnce() -> String) {
+            self.aggregate.set_position(tested);
+            if tested % AGGREGATE_INTERVAL == 0 {
+                self.aggregate.set_message(message());
+            }
+        }
+
+        pub(super) fn finish(&self, message: &str) {
+            self.aggregate.finish_with_message(message.to_string());
+        }
+    }
+}
@@ -0,0 +1,228 @@
+//! Pruned Node Hybrid Source
+//!
+//! A pruned Core node only keeps recent blocks: RPC and its blk files can't
+//! serve anything below its prune height. `PrunedSource` reads heights the node
+//! still has through its RPC and transparently fetches the pruned ones from a
+//! fallback (a P2P peer, an Esplora API or a prefilled shared cache). The prune
+//! height is asked for on first use and again whenever the node turns a block
+//! away (it keeps pruning while a run goes on), and every height served is
+//! recorded per source, so a run ends with which source served which heights.
+//!
+//! Configuration:
+//! - `BLVM_PRUNED_FALLBACK` - `p2p` (uses `BITCOIN_P2P_PEER`), `esplora` (uses
+//!   `BLVM_ESPLORA_URL`) or `cache` (the `--cache-dir` shared cache, read-only);
+//!   enables the source whenever Core's RPC is configured
+
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+
+use crate::block_file_reader::{Network, SharedBlockCache};
+use crate::block_source::BlockSource;
+use crate::core_rpc_client::CoreRpcClient;
+use crate::parallel_differential::BlockDataSource;
+
+/// Prune height not asked for yet
+const UNKNOWN: u64 = u64::MAX;
+
+/// Name the local node's heights are reported under
+const LOCAL: &str = "local node (RPC)";
+
+/// The fallback for pruned heights chosen by `BLVM_PRUNED_FALLBACK`, if set
+pub fn fallback_from_env(network: Network, cache_dir: Option<&Path>) -> Result<Option<Arc<BlockDataSource>>> {
+    let Ok(kind) = std::env::var("BLVM_PRUNED_FALLBACK") else {
+        return Ok(None);
+    };
+    let source = match kind.trim() {
+        "p2p" => {
+            let config = crate::p2p_client::P2pConfig::from_env(network)
+                .context("BLVM_PRUNED_FALLBACK=p2p needs BITCOIN_P2P_PEER")?;
+            BlockDataSource::P2p(Arc::new(crate::p2p_client::P2pClient::new(config)))
+        }
+        "esplora" => {
+            let config = crate::esplora_source::EsploraConfig::from_env(network)?
+                .context("BLVM_PRUNED_FALLBACK=esplora needs BLVM_ESPLORA_URL")?;
+            BlockDataSource::Custom(Arc::new(crate::esplora_source::EsploraSource::new(config)?))
+        }
+        "cache" => {
+            let dir = cache_dir.context("BLVM_PRUNED_FALLBACK=cache needs --cache-dir")?;
+            // No RPC behind it: the node can't fill in what it has pruned
+            BlockDataSource::SharedCache(SharedBlockCache::new(dir)?, None)
+        }
+        other => anyhow::bail!("Unknown BLVM_PRUNED_FALLBACK '{}' (expected p2p, esplora or cache)", other),
+    };
+    Ok(Some(Arc::new(source)))
+}
+
+/// Heights as sorted, non-adjacent inclusive ranges
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct HeightRanges(BTreeMap<u64, u64>);
+
+impl HeightRanges {
+    pub fn insert(&mut self, height: u64) {
+        let mut start = height;
+        let mut end = height;
+        if let Some((&s, &e)) = self.0.range(..=height).next_back() {
+            if e >= height {
+                return;
+            }
+            if e + 1 == height {
+                start = s;
+            }
+        }
+        if let Some(next_end) = height.checked_add(1).and_then(|next| self.0.remove(&next)) {
+            end = next_end;
+        }
+        self.0.insert(start, end);
+    }
+
+    pub fn count(&self) -> u64 {
+        self.0.iter().map(|(start, end)| end - start + 1).sum()
+    }
+
+    /// `a-b, c-d` (the first few ranges, then how many more)
+    pub fn describe(&self) -> String {
+        const SHOWN: usize = 5;
+        let mut parts: Vec<String> = self
+            .0
+            .iter()
+            .take(SHOWN)
+            .map(|(start, end)| if start == end { start.to_string() } else { format!("{}-{}", start, end) })
+            .collect();
+        if self.0.len() > SHOWN {
+            parts.push(format!("{} more ranges", self.0.len() - SHOWN));
+        }
+        parts.join(", ")
+    }
+}
+
+/// Core's RPC for the heights it still has, a fallback for the pruned ones
+pub struct PrunedSource {
+    local: Arc<CoreRpcClient>,
+    fallback: Arc<BlockDataSource>,
+    name: String,
+    /// Lowest height the node still stores (`UNKNOWN` until first asked)
+    prune_height: AtomicU64,
+    /// Heights served, per source name
+    served: Mutex<BTreeMap<String, HeightRanges>>,
+}
+
+impl PrunedSource {
+    pub fn new(local: Arc<CoreRpcClient>, fallback: Arc<BlockDataSource>) -> Self {
+        Self {
+            name: format!("pruned RPC + {}", fallback.name()),
+            local,
+            fallback,
+            prune_height: AtomicU64::new(UNKNOWN),
+            served: Mutex::new(BTreeMap::new()),
+        }
+    }
+
+    /// Ask the node again; 0 when it isn't pruned
+    async fn refresh_prune_height(&self) -> Result<u64> {
+        let (pruned, prune_height) = self.local.get_pruning_info().await?;
+        let height = if pruned { prune_height.unwrap_or(0) } else { 0 };
+        if self.prune_height.swap(height, Ordering::Relaxed) != height && pruned {
+            println!("✂️  Core is pruned below height {}; older blocks come from {}", height, self.fallback.name());
+        }
+        Ok(height)
+    }
+
+    async fn prune_height(&self) -> Result<u64> {
+        match self.prune_height.load(Ordering::Relaxed) {
+            UNKNOWN => self.refresh_prune_height().await,
+            height => Ok(height),
+        }
+    }
+
+    fn record(&self, source: &str, height: u64) {
+        if let Ok(mut served) = self.served.lock() {
+            served.entry(source.to_string()).or_default().insert(height);
+        }
+    }
+
+    /// Heights served so far, per source
+    pub fn served(&self) -> BTreeMap<String, HeightRanges> {
+        self.served.lock().map(|served| served.clone()).unwrap_or_default()
+    }
+
+    /// One line per source: which heights it served
+    pub fn summary(&self) -> String {
+        self.served()
+            .iter()
+            .map(|(source, ranges)| format!("   {}: {} blocks ({})", source, ranges.count(), ranges.describe()))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    async fn from_fallback(&self, height: u64) -> Result<Vec<u8>> {
+        let block = self
+            .fallback
+            .get_block(height)
+            .await
+            .with_context(|| format!("Block {} is pruned and {} couldn't serve it", height, self.fallback.name()))?;
+        self.record(self.fallback.name(), height);
+        Ok(block)
+    }
+}
+
+#[async_trait]
+impl BlockSource for PrunedSource {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    async fn get_block(&self, height: u64) -> Result<Vec<u8>> {
+        if height < self.prune_height().await? {
+            return self.from_fallback(height).await;
+        }
+        match self.local.get_block(height).await {
+            Ok(block) => {
+                self.record(LOCAL, height);
+                Ok(block)
+            }
+            // The node may have pruned further since we last asked
+            Err(e) if height < self.refresh_prune_height().await? => {
+                eprintln!("⚠️  Block {} was pruned meanwhile ({:#}), fetching from {}", height, e, self.fallback.name());
+                self.from_fallback(height).await
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    async fn get_tip_height(&self) -> Result<Option<u64>> {
+        self.local.get_tip_height().await
+    }
+}
+
+impl Drop for PrunedSource {
+    fn drop(&mut self) {
+        let summary = self.summary();
+        if !summary.is_empty() {
+            println!("📦 Blocks served by source:\n{}", summary);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_height_ranges_merge_out_of_order() {
+        let mut ranges = HeightRanges::default();
+        for height in [5, 7, 3, 6, 4, 4, 20, 0] {
+            ranges.insert(height);
+        }
+        assert_eq!(ranges.count(), 7);
+        assert_eq!(ranges.describe(), "0, 3-7, 20");
+
+        for height in (30..50).step_by(2) {
+            ranges.insert(height);
+        }
+        assert_eq!(ranges.describe(), "0, 3-7, 20, 30, 32, 8 more ranges");
+    }
+}
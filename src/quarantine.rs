@@ -0,0 +1,79 @@
+//! Poisoned Chunk Quarantine
+//!
+//! A panic inside `connect_block` is a consensus-library bug, not a harness
+//! failure. The panic is caught per block, the block bytes and UTXO context are
+//! written as a reproducer artifact, and the chunk is recorded as poisoned in
+//! `<quarantine_dir>/manifest.json` while other workers keep running.
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::any::Any;
+use std::path::{Path, PathBuf};
+
+/// Default quarantine root: `BLVM_QUARANTINE_DIR` or `./quarantine`
+pub fn default_quarantine_dir() -> PathBuf {
+    std::env::var("BLVM_QUARANTINE_DIR")
+        .map(PathBuf::from)
+        .unwrap_or_else(|_| PathBuf::from("quarantine"))
+}
+
+/// A block that panicked during validation
+#[derive(Debug, Clone, Serialize, Deserialize, thiserror::Error)]
+#[error("BLVM panicked at height {height}: {message}")]
+pub struct BlockPanic {
+    /// Height being processed (None if the panic happened outside block processing)
+    pub height: Option<u64>,
+    /// Panic payload
+    pub message: String,
+    /// Quarantined block bytes + UTXO context
+    pub artifact_dir: Option<PathBuf>,
+}
+
+/// Manifest entry for a poisoned chunk
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PoisonedChunk {
+    pub start_height: u64,
+    pub end_height: u64,
+    #[serde(flatten)]
+    pub panic: BlockPanic,
+}
+
+/// Extract a readable message from a panic payload
+pub fn panic_message(payload: &(dyn Any + Send)) -> String {
+    if let Some(s) = payload.downcast_ref::<&str>() {
+        s.to_string()
+    } else if let Some(s) = payload.downcast_ref::<String>() {
+        s.clone()
+    } else {
+        "non-string panic payload".to_string()
+    }
+}
+
+/// Append a poisoned chunk to the quarantine manifest
+pub fn record_poisoned_chunk(quarantine_dir: &Path, chunk: &PoisonedChunk) -> Result<()> {
+    std::fs::create_dir_all(quarantine_dir)
+        .with_context(|| format!("Failed to create quarantine directory {}", quarantine_dir.display()))?;
+    let manifest_path = quarantine_dir.join("manifest.json");
+
+    let mut entries: Vec<PoisonedChunk> = match std::fs::read_to_string(&manifest_path) {
+        Ok(content) => serde_json::from_str(&content)
+            .with_context(|| format!("Corrupt quarantine manifest {}", manifest_path.display()))?,
+        Err(_) => Vec::new(),
+    };
+    entries.retain(|e| (e.start_height, e.end_height) != (chunk.start_height, chunk.end_height));
+    entries.push(chunk.clone());
+    entries.sort_by_key(|e| e.start_height);
+
+    std::fs::write(&manifest_path, serde_json::to_string_pretty(&entries)?)?;
+    Ok(())
+}
+
+/// Load the quarantine manifest (empty if none)
+pub fn load_manifest(quarantine_dir: &Path) -> Result<Vec<PoisonedChunk>> {
+    let manifest_path = quarantine_dir.join("manifest.json");
+    if !manifest_path.exists() {
+        return Ok(Vec::new());
+    }
+    let content = std::fs::read_to_string(&manifest_path)?;
+    Ok(serde_json::from_str(&content)?)
+}
@@ -0,0 +1,443 @@
+//! Replace-By-Fee (BIP125) Differential
+//!
+//! `replacement_checks` only had microbenchmarks. This builds targeted
+//! replacement scenarios on regtest - explicit and inherited signaling, the
+//! absolute-fee (rule 3) and incremental-fee (rule 4) boundaries, replacements
+//! that must also out-pay the originals' descendants, the 100-eviction cap
+//! (rule 5) on either side of the limit, and a replacement adding a new
+//! unconfirmed input - broadcasts the originals to Core, then sends the
+//! replacement and records whether Core took it and how many mempool
+//! transactions it evicted.
+//!
+//! BLVM's verdict is `replacement_checks` against every directly conflicting
+//! original plus `accept_to_memory_pool` for the replacement itself. The
+//! eviction count BLVM would apply (conflicts and all their in-mempool
+//! descendants) is compared with what Core actually removed.
+//!
+//! Core 28+ defaults to `-mempoolfullrbf=1`, so the signaling scenarios show
+//! whichever policy the node runs; BIP125's answer is printed alongside.
+
+use anyhow::Result;
+use blvm_consensus::mempool::{accept_to_memory_pool, replacement_checks, Mempool, MempoolResult};
+use blvm_consensus::{UtxoSet, UTXO};
+use serde::Serialize;
+use std::collections::HashSet;
+
+use crate::core_rpc_client::{BitcoinNetwork, CoreRpcClient};
+use crate::mempool_corpus::{decode_tx, txid_hex};
+use crate::mutation_differential::RawTx;
+use crate::package_differential::{fund_op_true_coins, op_true_p2wsh, op_true_spend_with, Coin};
+
+/// nSequence that opts in to replacement (BIP125)
+const SEQUENCE_RBF: u32 = 0xffff_fffd;
+/// Highest nSequence that doesn't signal (lock time still enabled)
+const SEQUENCE_NO_RBF: u32 = 0xffff_fffe;
+/// Core's MAX_REPLACEMENT_CANDIDATES (rule 5)
+const MAX_REPLACEMENT_CANDIDATES: usize = 100;
+/// Fee-rate (sat/vB) of the originals
+const ORIGINAL_FEE_RATE: u64 = 2;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum RbfScenario {
+    /// Original signals explicitly, replacement pays more
+    ExplicitSignal,
+    /// Original doesn't signal (BIP125 rule 1 rejects; full-RBF accepts)
+    NoSignal,
+    /// Non-signaling child of a signaling parent is replaced (inherited signaling)
+    InheritedSignal,
+    /// Higher fee-rate but lower absolute fee than the original (rule 3)
+    LowerAbsoluteFee,
+    /// Pays one sat less than the incremental relay fee over the original (rule 4)
+    BelowIncrementalFee,
+    /// Pays exactly the incremental relay fee over the original (rule 4 boundary)
+    ExactIncrementalFee,
+    /// Out-pays the original but not the original plus its child (rule 3 with descendants)
+    UnderpaysDescendants,
+    /// Evicts 105 transactions (rule 5)
+    TooManyEvictions,
+    /// Evicts exactly 100 transactions (rule 5 boundary)
+    MaxEvictions,
+    /// Adds an input spending another unconfirmed transaction (rule 2)
+    NewUnconfirmedInput,
+}
+
+impl RbfScenario {
+    pub const ALL: [RbfScenario; 10] = [
+        RbfScenario::ExplicitSignal,
+        RbfScenario::NoSignal,
+        RbfScenario::InheritedSignal,
+        RbfScenario::LowerAbsoluteFee,
+        RbfScenario::BelowIncrementalFee,
+        RbfScenario::ExactIncrementalFee,
+        RbfScenario::UnderpaysDescendants,
+        RbfScenario::TooManyEvictions,
+        RbfScenario::MaxEvictions,
+        RbfScenario::NewUnconfirmedInput,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            RbfScenario::ExplicitSignal => "explicit-signal",
+            RbfScenario::NoSignal => "no-signal",
+            RbfScenario::InheritedSignal => "inherited-signal",
+            RbfScenario::LowerAbsoluteFee => "rule3-lower-absolute-fee",
+            RbfScenario::BelowIncrementalFee => "rule4-below-increment",
+            RbfScenario::ExactIncrementalFee => "rule4-exact-increment",
+            RbfScenario::UnderpaysDescendants => "rule3-underpays-descendants",
+            RbfScenario::TooManyEvictions => "rule5-too-many-evictions",
+            RbfScenario::MaxEvictions => "rule5-max-evictions",
+            RbfScenario::NewUnconfirmedInput => "rule2-new-unconfirmed-input",
+        }
+    }
+
+    /// Whether BIP125 (opt-in RBF, inherited signaling included) allows the replacement
+    pub fn bip125_allows(&self) -> bool {
+        matches!(
+            self,
+            RbfScenario::ExplicitSignal
+                | RbfScenario::InheritedSignal
+                | RbfScenario::ExactIncrementalFee
+                | RbfScenario::MaxEvictions
+        )
+    }
+
+    fn coins_needed(&self) -> usize {
+        match self {
+            RbfScenario::TooManyEvictions | RbfScenario::MaxEvictions => 5,
+            RbfScenario::NewUnconfirmedInput => 2,
+            _ => 1,
+        }
+    }
+
+    fn build(&self, coins: &[Coin]) -> RbfCase {
+        let spend = |inputs: &[Coin], outputs: usize, fee: &dyn Fn(u64) -> u64, sequence: u32| {
+            op_true_spend_with(inputs, outputs, fee, sequence)
+        };
+        let at_rate = |rate: u64| move |vsize: u64| rate * vsize;
+        let original = || spend(&coins[..1], 1, &at_rate(ORIGINAL_FEE_RATE), SEQUENCE_RBF);
+
+        match self {
+            RbfScenario::ExplicitSignal | RbfScenario::NoSignal => {
+                let sequence = if *self == RbfScenario::ExplicitSignal { SEQUENCE_RBF } else { SEQUENCE_NO_RBF };
+                let original = spend(&coins[..1], 1, &at_rate(ORIGINAL_FEE_RATE), sequence);
+                let replacement = spend(&coins[..1], 1, &at_rate(10), sequence);
+                RbfCase::new(coins, vec![original], replacement)
+            }
+            RbfScenario::InheritedSignal => {
+                let parent = original();
+                let output = Coin::unconfirmed(&parent, 0);
+                let child = spend(std::slice::from_ref(&output), 1, &at_rate(ORIGINAL_FEE_RATE), SEQUENCE_NO_RBF);
+                let replacement = spend(&[output], 1, &at_rate(10), SEQUENCE_NO_RBF);
+                RbfCase::new(coins, vec![parent, child], replacement)
+            }
+            RbfScenario::LowerAbsoluteFee => {
+                // Ten outputs make the original big, so a smaller fee is still a higher rate
+                let original = spend(&coins[..1], 10, &at_rate(ORIGINAL_FEE_RATE), SEQUENCE_RBF);
+                let original_fee = fee_of(&original, coins);
+                let replacement = spend(&coins[..1], 1, &|_| original_fee - 1, SEQUENCE_RBF);
+                RbfCase::new(coins, vec![original], replacement)
+            }
+            RbfScenario::BelowIncrementalFee | RbfScenario::ExactIncrementalFee => {
+                let original = original();
+                let original_fee = fee_of(&original, coins);
+                let shortfall = u64::from(*self == RbfScenario::BelowIncrementalFee);
+                // Incremental relay fee is 1 sat/vB of the replacement's own size
+                let replacement = spend(&coins[..1], 1, &|vsize| original_fee + vsize - shortfall, SEQUENCE_RBF);
+                RbfCase::new(coins, vec![original], replacement)
+            }
+            RbfScenario::UnderpaysDescendants => {
+                let parent = original();
+                let child = spend(&[Coin::unconfirmed(&parent, 0)], 1, &at_rate(20), SEQUENCE_RBF);
+                let parent_fee = fee_of(&parent, coins);
+                let replacement = spend(&coins[..1], 1, &|vsize| parent_fee + 5 * vsize, SEQUENCE_RBF);
+                RbfCase::new(coins, vec![parent, child], replacement)
+            }
+            RbfScenario::TooManyEvictions | RbfScenario::MaxEvictions => {
+                // Five conflicts each with a fan-out of children (descendant limit is 25)
+                let children = if *self == RbfScenario::TooManyEvictions { 20 } else { 19 };
+                let mut originals = Vec::new();
+                for coin in coins {
+                    let parent = spend(std::slice::from_ref(coin), children, &at_rate(ORIGINAL_FEE_RATE), SEQUENCE_RBF);
+                    let outputs: Vec<Coin> = (0..children).map(|vout| Coin::unconfirmed(&parent, vout)).collect();
+                    originals.push(parent);
+                    for output in outputs {
+                        originals.push(spend(&[output], 1, &at_rate(ORIGINAL_FEE_RATE), SEQUENCE_RBF));
+                    }
+                }
+                // Generously out-pays everything it evicts
+                let replacement = spend(coins, 1, &at_rate(200), SEQUENCE_RBF);
+                RbfCase::new(coins, originals, replacement)
+            }
+            RbfScenario::NewUnconfirmedInput => {
+                let original = original();
+                let other = spend(&coins[1..], 1, &at_rate(ORIGINAL_FEE_RATE), SEQUENCE_RBF);
+                let inputs = [coins[0].clone(), Coin::unconfirmed(&other, 0)];
+                let replacement = spend(&inputs, 1, &at_rate(20), SEQUENCE_RBF);
+                RbfCase::new(coins, vec![original, other], replacement)
+            }
+        }
+    }
+}
+
+/// Fee of a transaction spending only `coins` and earlier unconfirmed outputs it can see in `coins`
+fn fee_of(tx: &RawTx, coins: &[Coin]) -> u64 {
+    let input_value: u64 = tx
+        .inputs
+        .iter()
+        .filter_map(|input| coins.iter().find(|coin| coin.prevout == input.prevout))
+        .map(|coin| coin.value)
+        .sum();
+    input_value - tx.outputs.iter().map(|(value, _)| value).sum::<u64>()
+}
+
+/// Mempool contents before the replacement, and the replacement
+#[derive(Debug, Clone)]
+struct RbfCase {
+    coins: Vec<Coin>,
+    /// Broadcast in order (parents first)
+    originals: Vec<RawTx>,
+    replacement: RawTx,
+}
+
+impl RbfCase {
+    fn new(coins: &[Coin], originals: Vec<RawTx>, replacement: RawTx) -> Self {
+        Self {
+            coins: coins.to_vec(),
+            originals,
+            replacement,
+        }
+    }
+
+    /// Originals spending an input the replacement also spends
+    fn conflicts(&self) -> Vec<&RawTx> {
+        let spent: HashSet<[u8; 36]> = self.replacement.inputs.iter().map(|input| input.prevout).collect();
+        self.originals
+            .iter()
+            .filter(|tx| tx.inputs.iter().any(|input| spent.contains(&input.prevout)))
+            .collect()
+    }
+
+    /// Txids the replacement evicts: its conflicts and all their in-mempool descendants
+    fn evicted(&self) -> HashSet<[u8; 32]> {
+        let mut evicted: HashSet<[u8; 32]> = self.conflicts().iter().map(|tx| tx.txid()).collect();
+        // Originals are in topological order, so one pass finds every descendant
+        for tx in &self.originals {
+            if tx.inputs.iter().any(|input| evicted.contains(&input.prevout[..32])) {
+                evicted.insert(tx.txid());
+            }
+        }
+        evicted
+    }
+}
+
+/// One side's verdict on a replacement
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct RbfVerdict {
+    pub replaced: bool,
+    pub reason: Option<String>,
+    /// Mempool transactions removed by the replacement
+    pub evicted: usize,
+}
+
+/// `replacement_checks` against every direct conflict, then `accept_to_memory_pool` on the replacement
+fn blvm_rbf_verdict(case: &RbfCase, height: u64) -> RbfVerdict {
+    let mut utxo_set = UtxoSet::new();
+    let mut mempool: Mempool = HashSet::new();
+    for coin in &case.coins {
+        utxo_set.insert(coin.outpoint(), coin_utxo(coin, op_true_p2wsh()));
+    }
+    for tx in &case.originals {
+        mempool.insert(tx.txid());
+        for vout in 0..tx.outputs.len() {
+            let coin = Coin::unconfirmed(tx, vout);
+            utxo_set.insert(coin.outpoint(), coin_utxo(&coin, tx.outputs[vout].1.clone()));
+        }
+    }
+
+    let rejected = |reason: String| RbfVerdict {
+        reason: Some(reason),
+        ..Default::default()
+    };
+    let decode = |tx: &RawTx| decode_tx(&hex::encode(tx.to_bytes()));
+    let (replacement, witness) = match decode(&case.replacement) {
+        Ok(decoded) => decoded,
+        Err(e) => return rejected(format!("decode failed: {}", e)),
+    };
+    for conflict in case.conflicts() {
+        let (existing, _) = match decode(conflict) {
+            Ok(decoded) => decoded,
+            Err(e) => return rejected(format!("decode failed: {}", e)),
+        };
+        match replacement_checks(&replacement, &existing, &utxo_set, &mempool) {
+            Ok(true) => {}
+            Ok(false) => return rejected(format!("replacement_checks refused vs {}", txid_hex(&conflict.txid()))),
+            Err(e) => return rejected(format!("replacement_checks error vs {}: {}", txid_hex(&conflict.txid()), e)),
+        }
+    }
+    match accept_to_memory_pool(&replacement, Some(&witness), &utxo_set, &mempool, height) {
+        Ok(MempoolResult::Accepted) => RbfVerdict {
+            replaced: true,
+            reason: None,
+            evicted: case.evicted().len(),
+        },
+        Ok(result) => rejected(format!("{:?}", result)),
+        Err(e) => rejected(format!("error: {}", e)),
+    }
+}
+
+fn coin_utxo(coin: &Coin, script_pubkey: Vec<u8>) -> UTXO {
+    UTXO {
+        value: coin.value as _,
+        script_pubkey: script_pubkey.into(),
+        height: coin.height as _,
+        is_coinbase: coin.is_coinbase,
+    }
+}
+
+/// Broadcast the originals, send the replacement, and count what left the mempool
+async fn core_rbf_verdict(client: &CoreRpcClient, case: &RbfCase) -> Result<RbfVerdict> {
+    for tx in &case.originals {
+        client.sendrawtransaction(&hex::encode(tx.to_bytes())).await.map_err(|e| {
+            e.context(format!("Core refused original {} (scenario setup)", txid_hex(&tx.txid())))
+        })?;
+    }
+    let verdict = match client.sendrawtransaction(&hex::encode(case.replacement.to_bytes())).await {
+        Ok(_) => {
+            let mempool = client.getrawmempool(false).await?;
+            let remaining: HashSet<&str> = mempool.as_array().into_iter().flatten().filter_map(|t| t.as_str()).collect();
+            RbfVerdict {
+                replaced: true,
+                reason: None,
+                evicted: case
+                    .originals
+                    .iter()
+                    .filter(|tx| !remaining.contains(txid_hex(&tx.txid()).as_str()))
+                    .count(),
+            }
+        }
+        Err(e) => RbfVerdict {
+            replaced: false,
+            reason: Some(format!("{:#}", e)),
+            evicted: 0,
+        },
+    };
+    Ok(verdict)
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RbfOutcome {
+    pub scenario: RbfScenario,
+    pub bip125_allows: bool,
+    pub blvm: RbfVerdict,
+    pub core: RbfVerdict,
+}
+
+impl RbfOutcome {
+    pub fn verdicts_agree(&self) -> bool {
+        self.blvm.replaced == self.core.replaced
+    }
+
+    /// Both replaced but removed a different number of mempool transactions
+    pub fn eviction_mismatch(&self) -> bool {
+        self.blvm.replaced && self.core.replaced && self.blvm.evicted != self.core.evicted
+    }
+
+    pub fn consistent(&self) -> bool {
+        self.verdicts_agree() && !self.eviction_mismatch()
+    }
+
+    fn print(&self) {
+        let describe = |v: &RbfVerdict| {
+            if v.replaced {
+                format!("replaced, evicted {}", v.evicted)
+            } else {
+                format!("rejected ({})", v.reason.as_deref().unwrap_or("no reason"))
+            }
+        };
+        let icon = if self.consistent() { "✅" } else { "❌" };
+        let bip125 = if self.bip125_allows { "allows" } else { "forbids" };
+        println!(
+            "{} {:<30} BIP125 {}: BLVM={}, Core={}",
+            icon,
+            self.scenario.name(),
+            bip125,
+            describe(&self.blvm),
+            describe(&self.core)
+        );
+        if self.blvm.replaced && self.blvm.evicted > MAX_REPLACEMENT_CANDIDATES {
+            println!(
+                "      BLVM evicts {} transactions, over rule 5's cap of {}",
+                self.blvm.evicted, MAX_REPLACEMENT_CANDIDATES
+            );
+        }
+    }
+}
+
+/// Run every BIP125 scenario against a regtest node
+pub async fn run_rbf_differential(client: &CoreRpcClient) -> Result<Vec<RbfOutcome>> {
+    if client.detect_network().await? != BitcoinNetwork::Regtest {
+        anyhow::bail!("RBF scenarios need a regtest node (they mine blocks and fill the mempool)");
+    }
+    let needed: usize = RbfScenario::ALL.iter().map(RbfScenario::coins_needed).sum();
+    let mut coins = fund_op_true_coins(client, needed).await?.into_iter();
+    let height = client.getblockcount().await? + 1;
+
+    let mut outcomes = Vec::new();
+    for scenario in RbfScenario::ALL {
+        let scenario_coins: Vec<Coin> = coins.by_ref().take(scenario.coins_needed()).collect();
+        let case = scenario.build(&scenario_coins);
+        let outcome = RbfOutcome {
+            scenario,
+            bip125_allows: scenario.bip125_allows(),
+            blvm: blvm_rbf_verdict(&case, height),
+            core: core_rbf_verdict(client, &case).await?,
+        };
+        outcome.print();
+        outcomes.push(outcome);
+    }
+
+    client.generatetodescriptor(1, "raw(51)").await?;
+    let diverged = outcomes.iter().filter(|o| !o.consistent()).count();
+    println!("🔁 RBF differential: {}/{} scenarios agree", outcomes.len() - diverged, outcomes.len());
+    Ok(outcomes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn coins(n: usize) -> Vec<Coin> {
+        (0..n)
+            .map(|i| Coin {
+                prevout: [i as u8 + 1; 36],
+                value: 50_000_000,
+                height: 1,
+                is_coinbase: true,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_eviction_sets() {
+        let evicted = |scenario: RbfScenario| scenario.build(&coins(scenario.coins_needed())).evicted().len();
+        assert_eq!(evicted(RbfScenario::ExplicitSignal), 1);
+        assert_eq!(evicted(RbfScenario::InheritedSignal), 1);
+        assert_eq!(evicted(RbfScenario::UnderpaysDescendants), 2);
+        assert_eq!(evicted(RbfScenario::TooManyEvictions), 105);
+        assert_eq!(evicted(RbfScenario::MaxEvictions), MAX_REPLACEMENT_CANDIDATES);
+        assert_eq!(evicted(RbfScenario::NewUnconfirmedInput), 1);
+    }
+
+    #[test]
+    fn test_fee_boundaries() {
+        let c = coins(1);
+        let case = RbfScenario::ExactIncrementalFee.build(&c);
+        let increment = fee_of(&case.replacement, &c) - fee_of(&case.originals[0], &c);
+        assert_eq!(increment, case.replacement.vsize());
+        let case = RbfScenario::BelowIncrementalFee.build(&c);
+        let increment = fee_of(&case.replacement, &c) - fee_of(&case.originals[0], &c);
+        assert_eq!(increment, case.replacement.vsize() - 1);
+        let case = RbfScenario::LowerAbsoluteFee.build(&c);
+        assert_eq!(fee_of(&case.replacement, &c) + 1, fee_of(&case.originals[0], &c));
+    }
+}
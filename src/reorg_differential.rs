@@ -0,0 +1,244 @@
+//! Reorg Differential Testing
+//!
+//! Drives disconnect/reconnect sequences on a regtest node and checks BLVM's
+//! undo log against Core's `invalidateblock`/`reconsiderblock`:
+//!
+//! 1. Connect the whole chain in BLVM, snapshotting the UTXO set at the fork point
+//! 2. Disconnect the last `depth` blocks with their undo logs; Core invalidates the
+//!    same blocks. The undone set must equal the snapshot and match Core's stats
+//! 3. Optionally mine a heavier competing branch in Core and connect it in BLVM
+//! 4. Reconsider the original branch and check both sides agree on the tip again
+
+use anyhow::{Context, Result};
+use blvm_consensus::block::connect_block;
+use blvm_consensus::reorganization::disconnect_block;
+use blvm_consensus::segwit::Witness;
+use blvm_consensus::serialization::block::deserialize_block_with_witnesses;
+use blvm_consensus::types::{Network, ValidationResult};
+use blvm_consensus::{Block, UtxoSet};
+
+use crate::core_rpc_client::{BitcoinNetwork, CoreRpcClient};
+
+/// Reorg test configuration
+#[derive(Debug, Clone)]
+pub struct ReorgConfig {
+    /// Number of blocks to disconnect
+    pub depth: u64,
+    /// Mine a competing branch of `depth + 1` blocks after invalidation
+    pub mine_fork: bool,
+}
+
+impl Default for ReorgConfig {
+    fn default() -> Self {
+        Self {
+            depth: 3,
+            mine_fork: true,
+        }
+    }
+}
+
+/// Outcome of one reorg stage
+#[derive(Debug, Clone)]
+pub struct StageResult {
+    pub stage: &'static str,
+    pub height: u64,
+    pub matches: bool,
+    pub detail: String,
+}
+
+/// Coin count and total value, comparable with Core's `gettxoutsetinfo`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct UtxoStats {
+    txouts: u64,
+    total_sats: i64,
+}
+
+fn utxo_stats(utxo_set: &UtxoSet) -> UtxoStats {
+    let mut stats = UtxoStats { txouts: 0, total_sats: 0 };
+    for (_, utxo) in utxo_set.iter() {
+        // Core never adds provably unspendable outputs to its chainstate
+        if utxo.script_pubkey.first() == Some(&0x6a) {
+            continue;
+        }
+        stats.txouts += 1;
+        stats.total_sats += utxo.value as i64;
+    }
+    stats
+}
+
+async fn core_stats(client: &CoreRpcClient) -> Result<(UtxoStats, u64)> {
+    let info = client.gettxoutsetinfo().await?;
+    let txouts = info.get("txouts").and_then(|v| v.as_u64()).context("gettxoutsetinfo missing txouts")?;
+    let total = info
+        .get("total_amount")
+        .and_then(|v| v.as_f64())
+        .context("gettxoutsetinfo missing total_amount")?;
+    let height = info.get("height").and_then(|v| v.as_u64()).context("gettxoutsetinfo missing height")?;
+    Ok((
+        UtxoStats {
+            txouts,
+            total_sats: (total * 100_000_000.0).round() as i64,
+        },
+        height,
+    ))
+}
+
+/// Exact comparison of two UTXO sets (undo must restore every coin bit-for-bit)
+fn utxo_sets_equal(a: &UtxoSet, b: &UtxoSet) -> bool {
+    a.len() == b.len()
+        && a.iter().all(|(outpoint, utxo)| {
+            b.get(outpoint).is_some_and(|other| {
+                other.value == utxo.value
+                    && other.script_pubkey == utxo.script_pubkey
+                    && other.height == utxo.height
+                    && other.is_coinbase == utxo.is_coinbase
+            })
+        })
+}
+
+async fn fetch_block(client: &CoreRpcClient, height: u64) -> Result<(String, Block, Vec<Witness>)> {
+    let hash = client.getblockhash(height).await?;
+    let bytes = hex::decode(client.getblock_raw(&hash).await?)?;
+    let (block, witnesses) = deserialize_block_with_witnesses(&bytes)
+        .with_context(|| format!("Failed to deserialize block {}", height))?;
+    Ok((hash, block, witnesses))
+}
+
+async fn compare_with_core(
+    client: &CoreRpcClient,
+    stage: &'static str,
+    utxo_set: &UtxoSet,
+    expected_height: u64,
+) -> Result<StageResult> {
+    let (core, core_height) = core_stats(client).await?;
+    let blvm = utxo_stats(utxo_set);
+    let matches = core == blvm && core_height == expected_height;
+    Ok(StageResult {
+        stage,
+        height: expected_height,
+        matches,
+        detail: format!(
+            "Core height {} ({} coins, {} sats) vs BLVM height {} ({} coins, {} sats)",
+            core_height, core.txouts, core.total_sats, expected_height, blvm.txouts, blvm.total_sats
+        ),
+    })
+}
+
+/// Run the reorg sequence against a regtest node
+pub async fn run_reorg_differential(client: &CoreRpcClient, config: &ReorgConfig) -> Result<Vec<StageResult>> {
+    if client.detect_network().await? != BitcoinNetwork::Regtest {
+        anyhow::bail!("Reorg differential invalidates blocks and must run against a regtest node");
+    }
+    let network = Network::Regtest;
+    let tip = client.getblockcount().await?;
+    if tip <= config.depth {
+        anyhow::bail!("Chain height {} too short for reorg depth {}", tip, config.depth);
+    }
+    let fork_height = tip - config.depth;
+    println!("🔀 Reorg differential: tip {}, disconnecting {} blocks to {}", tip, config.depth, fork_height);
+
+    let mut results = Vec::new();
+
+    // 1. Forward pass, keeping undo logs for the blocks we will disconnect
+    let mut utxo_set = UtxoSet::new();
+    let mut fork_snapshot = None;
+    let mut tail = Vec::new();
+    for height in 1..=tip {
+        let (hash, block, witnesses) = fetch_block(client, height).await?;
+        let (result, new_set, undo_log) = connect_block(&block, &witnesses, utxo_set, height, None, network)?;
+        if !matches!(result, ValidationResult::Valid) {
+            anyhow::bail!("BLVM rejected block {} on the forward pass: {:?}", height, result);
+        }
+        utxo_set = new_set;
+        if height == fork_height {
+            fork_snapshot = Some(utxo_set.clone());
+        }
+        if height > fork_height {
+            tail.push((height, hash, block, witnesses, undo_log));
+        }
+    }
+    let tip_snapshot = utxo_set.clone();
+    results.push(compare_with_core(client, "forward", &utxo_set, tip).await?);
+
+    // 2. Disconnect: BLVM via undo logs, Core via invalidateblock
+    for (height, _, block, _, undo_log) in tail.iter().rev() {
+        utxo_set = disconnect_block(block, undo_log, utxo_set)
+            .with_context(|| format!("BLVM failed to disconnect block {}", height))?;
+    }
+    let first_disconnected = tail.first().map(|(_, hash, _, _, _)| hash.clone()).context("empty reorg tail")?;
+    client.invalidateblock(&first_disconnected).await?;
+
+    let fork_snapshot = fork_snapshot.context("fork height never reached")?;
+    let undo_exact = utxo_sets_equal(&utxo_set, &fork_snapshot);
+    results.push(StageResult {
+        stage: "undo_exact",
+        height: fork_height,
+        matches: undo_exact,
+        detail: if undo_exact {
+            "undo restored the fork-point UTXO set exactly".to_string()
+        } else {
+            format!(
+                "undone set has {} coins, fork-point snapshot has {}",
+                utxo_set.len(),
+                fork_snapshot.len()
+            )
+        },
+    });
+    results.push(compare_with_core(client, "disconnect", &utxo_set, fork_height).await?);
+
+    // 3. Competing branch: one block longer so Core keeps it after reconsider
+    let mut final_height = tip;
+    if config.mine_fork {
+        let address = client.getnewaddress().await?;
+        client.generatetoaddress(config.depth + 1, &address).await?;
+        let fork_tip = fork_height + config.depth + 1;
+        for height in fork_height + 1..=fork_tip {
+            let (_, block, witnesses) = fetch_block(client, height).await?;
+            let (result, new_set, _undo_log) = connect_block(&block, &witnesses, utxo_set, height, None, network)?;
+            if !matches!(result, ValidationResult::Valid) {
+                anyhow::bail!("BLVM rejected fork block {}: {:?}", height, result);
+            }
+            utxo_set = new_set;
+        }
+        final_height = fork_tip;
+        results.push(compare_with_core(client, "fork", &utxo_set, fork_tip).await?);
+    }
+
+    // 4. Reconsider the original branch
+    client.reconsiderblock(&first_disconnected).await?;
+    if !config.mine_fork {
+        // Original branch wins again: reconnect it and expect the forward-pass set
+        for (height, _, block, witnesses, _) in &tail {
+            let (result, new_set, _undo_log) = connect_block(block, witnesses, utxo_set, *height, None, network)?;
+            if !matches!(result, ValidationResult::Valid) {
+                anyhow::bail!("BLVM rejected block {} on reconnect: {:?}", height, result);
+            }
+            utxo_set = new_set;
+        }
+        let reconnect_exact = utxo_sets_equal(&utxo_set, &tip_snapshot);
+        results.push(StageResult {
+            stage: "reconnect_exact",
+            height: tip,
+            matches: reconnect_exact,
+            detail: if reconnect_exact {
+                "reconnect reproduced the forward-pass UTXO set exactly".to_string()
+            } else {
+                format!("reconnected set has {} coins, forward pass had {}", utxo_set.len(), tip_snapshot.len())
+            },
+        });
+    }
+    results.push(compare_with_core(client, "reconsider", &utxo_set, final_height).await?);
+    let core_tip = client.getblockcount().await?;
+    results.push(StageResult {
+        stage: "tip",
+        height: final_height,
+        matches: core_tip == final_height,
+        detail: format!("Core tip {} vs expected {}", core_tip, final_height),
+    });
+
+    for r in &results {
+        let icon = if r.matches { "✅" } else { "❌" };
+        println!("   {} {:<16} {}", icon, r.stage, r.detail);
+    }
+    Ok(results)
+}
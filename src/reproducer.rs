@@ -0,0 +1,211 @@
+//! Divergence Reproducer Bundles
+//!
+//! When BLVM and Core disagree on a block, write a self-contained directory that
+//! replays just that block, so the divergence can be debugged without re-running
+//! the full pipeline:
+//!
+//! ```text
+//! <root>/height_<h>/
+//!   block.bin        raw block bytes (as served by the data source)
+//!   utxos.bin        pre-state UTXO subset touched by the block
+//!   meta.json        height, network, block hash, both results
+//!   replay_test.rs   `#[test]` that runs connect_block on the inputs above
+//! ```
+
+use anyhow::{Context, Result};
+use blvm_consensus::{Block, OutPoint, UtxoSet};
+use std::path::{Path, PathBuf};
+
+use crate::block_hash::block_hash_hex;
+use crate::utxo_backend::{decode_utxo, encode_utxo, touched_outpoints, UtxoStore};
+
+/// Default reproducer root: `BLVM_REPRODUCER_DIR` or `./reproducers`
+pub fn default_reproducer_dir() -> PathBuf {
+    std::env::var("BLVM_REPRODUCER_DIR")
+        .map(PathBuf::from)
+        .unwrap_or_else(|_| PathBuf::from("reproducers"))
+}
+
+/// Capture the coins a block reads from the store before it is connected
+pub fn collect_pre_state(block: &Block, utxo_store: &dyn UtxoStore) -> Result<UtxoSet> {
+    let mut pre_state = UtxoSet::new();
+    for outpoint in touched_outpoints(block) {
+        if let Some(utxo) = utxo_store.get(&outpoint)? {
+            pre_state.insert(outpoint, utxo);
+        }
+    }
+    Ok(pre_state)
+}
+
+/// Everything needed to replay one divergent block
+pub struct Reproducer<'a> {
+    pub height: u64,
+    pub block_bytes: &'a [u8],
+    pub pre_state: &'a UtxoSet,
+    pub blvm_result: &'a str,
+    pub core_result: &'a str,
+    pub utxo_backend: &'a str,
+}
+
+impl Reproducer<'_> {
+    /// Write the bundle under `root` and return its directory
+    pub fn write(&self, root: &Path) -> Result<PathBuf> {
+        let dir = root.join(format!("height_{}", self.height));
+        std::fs::create_dir_all(&dir)
+            .with_context(|| format!("Failed to create reproducer directory {}", dir.display()))?;
+
+        std::fs::write(dir.join("block.bin"), self.block_bytes)?;
+        std::fs::write(dir.join("utxos.bin"), encode_utxo_set(self.pre_state))?;
+
+        let meta = serde_json::json!({
+            "height": self.height,
+            "network": "mainnet",
+            "block_hash": block_hash_hex(self.block_bytes).unwrap_or_default(),
+            "block_size": self.block_bytes.len(),
+            "pre_state_utxos": self.pre_state.len(),
+            "blvm_result": self.blvm_result,
+            "core_result": self.core_result,
+            "utxo_backend": self.utxo_backend,
+            // Script flags are derived by connect_block from (height, network)
+            "flags": "derived from height + network",
+        });
+        std::fs::write(dir.join("meta.json"), serde_json::to_string_pretty(&meta)?)?;
+
+        let core_valid = self.core_result == "Valid";
+        std::fs::write(
+            dir.join("replay_test.rs"),
+            REPLAY_TEST_TEMPLATE
+                .replace("{HEIGHT}", &self.height.to_string())
+                .replace("{CORE_RESULT}", self.core_result)
+                .replace("{EXPECT_VALID}", &core_valid.to_string()),
+        )?;
+
+        Ok(dir)
+    }
+}
+
+/// Load a bundle's block bytes and pre-state (for replaying from tooling)
+pub fn load_reproducer(dir: &Path) -> Result<(Vec<u8>, UtxoSet)> {
+    let block_bytes = std::fs::read(dir.join("block.bin"))
+        .with_context(|| format!("Failed to read block.bin in {}", dir.display()))?;
+    let utxo_bytes = std::fs::read(dir.join("utxos.bin"))
+        .with_context(|| format!("Failed to read utxos.bin in {}", dir.display()))?;
+    Ok((block_bytes, decode_utxo_set(&utxo_bytes)?))
+}
+
+/// Layout: count (u32) then per coin: txid (32) | index (u32) | record_len (u32) | record
+pub(crate) fn encode_utxo_set(utxo_set: &UtxoSet) -> Vec<u8> {
+    let mut buf = Vec::new();
+    buf.extend_from_slice(&(utxo_set.len() as u32).to_le_bytes());
+    for (outpoint, utxo) in utxo_set.iter() {
+        let record = encode_utxo(utxo);
+        buf.extend_from_slice(&outpoint.hash);
+        buf.extend_from_slice(&(outpoint.index as u32).to_le_bytes());
+        buf.extend_from_slice(&(record.len() as u32).to_le_bytes());
+        buf.extend_from_slice(&record);
+    }
+    buf
+}
+
+pub(crate) fn decode_utxo_set(buf: &[u8]) -> Result<UtxoSet> {
+    let read_u32 = |pos: usize| -> Result<u32> {
+        let bytes = buf.get(pos..pos + 4).context("utxos.bin truncated")?;
+        Ok(u32::from_le_bytes(bytes.try_into()?))
+    };
+
+    let count = read_u32(0)? as usize;
+    let mut pos = 4;
+    let mut utxo_set = UtxoSet::new();
+    for _ in 0..count {
+        let hash: [u8; 32] = buf.get(pos..pos + 32).context("utxos.bin truncated")?.try_into()?;
+        let index = read_u32(pos + 32)?;
+        let record_len = read_u32(pos + 36)? as usize;
+        pos += 40;
+        let record = buf.get(pos..pos + record_len).context("utxos.bin truncated")?;
+        pos += record_len;
+        utxo_set.insert(OutPoint { hash, index: index as _ }, decode_utxo(record)?);
+    }
+    Ok(utxo_set)
+}
+
+/// Generated replay test; copy into `tests/` of a crate depending on blvm-consensus
+const REPLAY_TEST_TEMPLATE: &str = r#"//! Reproducer for divergence at height {HEIGHT}
+//! Core result: {CORE_RESULT}
+//!
+//! Generated by blvm-bench. Inputs are embedded from the files next to this one.
+
+use blvm_consensus::block::connect_block;
+use blvm_consensus::serialization::block::deserialize_block_with_witnesses;
+use blvm_consensus::types::{Network, ValidationResult};
+use blvm_consensus::{OutPoint, UtxoSet, UTXO};
+
+const HEIGHT: u64 = {HEIGHT};
+const CORE_VALID: bool = {EXPECT_VALID};
+const BLOCK: &[u8] = include_bytes!("block.bin");
+const UTXOS: &[u8] = include_bytes!("utxos.bin");
+
+fn u32_at(buf: &[u8], pos: usize) -> u32 {
+    u32::from_le_bytes(buf[pos..pos + 4].try_into().unwrap())
+}
+
+fn load_utxos() -> UtxoSet {
+    let mut utxo_set = UtxoSet::new();
+    let mut pos = 4;
+    for _ in 0..u32_at(UTXOS, 0) {
+        let hash: [u8; 32] = UTXOS[pos..pos + 32].try_into().unwrap();
+        let index = u32_at(UTXOS, pos + 32);
+        let len = u32_at(UTXOS, pos + 36) as usize;
+        let rec = &UTXOS[pos + 40..pos + 40 + len];
+        pos += 40 + len;
+        // value (i64) | height (u64) | is_coinbase (u8) | script_len (u32) | script
+        let script_len = u32_at(rec, 17) as usize;
+        utxo_set.insert(
+            OutPoint { hash, index: index as _ },
+            UTXO {
+                value: i64::from_le_bytes(rec[0..8].try_into().unwrap()) as _,
+                height: u64::from_le_bytes(rec[8..16].try_into().unwrap()) as _,
+                is_coinbase: rec[16] != 0,
+                script_pubkey: rec[21..21 + script_len].to_vec().into(),
+            },
+        );
+    }
+    utxo_set
+}
+
+#[test]
+fn replay_divergence() {
+    let (block, witnesses) = deserialize_block_with_witnesses(BLOCK).expect("block deserializes");
+    let result = connect_block(&block, &witnesses, load_utxos(), HEIGHT, None, Network::Mainnet)
+        .map(|(result, _, _)| result);
+
+    let blvm_valid = matches!(result, Ok(ValidationResult::Valid));
+    assert_eq!(blvm_valid, CORE_VALID, "BLVM result {:?} disagrees with Core at height {}", result, HEIGHT);
+}
+"#;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use blvm_consensus::UTXO;
+
+    #[test]
+    fn test_utxo_set_roundtrip() {
+        let mut utxo_set = UtxoSet::new();
+        utxo_set.insert(
+            OutPoint { hash: [7u8; 32], index: 3 },
+            UTXO {
+                value: 50_000,
+                script_pubkey: vec![0x51, 0x52].into(),
+                height: 170,
+                is_coinbase: true,
+            },
+        );
+
+        let decoded = decode_utxo_set(&encode_utxo_set(&utxo_set)).unwrap();
+        let utxo = decoded.get(&OutPoint { hash: [7u8; 32], index: 3 }).unwrap();
+        assert_eq!(decoded.len(), 1);
+        assert_eq!(utxo.value, 50_000);
+        assert_eq!(utxo.height, 170);
+        assert!(utxo.is_coinbase);
+    }
+}
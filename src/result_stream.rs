@@ -0,0 +1,252 @@
+//! Streaming Run Results
+//!
+//! `run_parallel_differential` only summarizes when every chunk is done, so a
+//! crash late in a long run used to lose all of it. With `BLVM_RESULTS_JSONL`
+//! set, every event is appended to that file as one JSON line the moment it
+//! happens: the run's parameters, each divergence as the worker finds it, and
+//! each chunk's `ChunkRecord` as it finishes (or the error it failed with).
+//! Lines are written whole and flushed, so after a crash at most the line
+//! being written is lost; `load_run` rebuilds a `RunRecord` of the last run in
+//! the file from what made it to disk, and `html-report` accepts the file.
+//!
+//! The file is only ever appended to: runs pointed at the same file follow
+//! one another, each starting with a `run_started` line.
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use crate::html_report::{ChunkRecord, DivergenceRecord, RunRecord};
+use crate::parallel_differential::{ChunkResult, ParallelConfig};
+
+/// One line of the stream
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum StreamEvent {
+    RunStarted {
+        /// Unix seconds
+        started_at: u64,
+        network: String,
+        start_height: u64,
+        end_height: u64,
+        chunk_size: u64,
+        num_workers: usize,
+        utxo_backend: String,
+        #[serde(default)]
+        sample_seed: Option<u64>,
+    },
+    /// Written by the worker as soon as it sees the divergence
+    Divergence(DivergenceRecord),
+    /// A chunk finished (possibly poisoned)
+    Chunk(ChunkRecord),
+    /// A chunk gave up after its retries
+    ChunkFailed { start_height: u64, end_height: u64, error: String },
+    RunFinished { tested: usize, divergences: usize, wall_secs: f64 },
+}
+
+/// Append-only JSONL file of a run's events
+pub struct ResultStream {
+    path: PathBuf,
+    file: Mutex<std::fs::File>,
+    started_at: std::time::SystemTime,
+}
+
+impl ResultStream {
+    /// Open `path` for appending (created if missing)
+    pub fn open(path: &Path, started_at: std::time::SystemTime) -> Result<Self> {
+        let file = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .with_context(|| format!("Failed to open result stream {}", path.display()))?;
+        Ok(Self {
+            path: path.to_path_buf(),
+            file: Mutex::new(file),
+            started_at,
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Write one event; a failing disk is reported but never stops the run
+    pub fn append(&self, event: &StreamEvent) {
+        let written = serde_json::to_string(event).map_err(anyhow::Error::from).and_then(|mut line| {
+            line.push('\n');
+            let mut file = self.file.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+            // One write per line: concurrent chunks never interleave within a line
+            file.write_all(line.as_bytes())?;
+            file.flush()?;
+            Ok(())
+        });
+        if let Err(e) = written {
+            eprintln!("⚠️  Failed to append to {}: {:#}", self.path.display(), e);
+        }
+    }
+
+    pub fn run_started(&self, start_height: u64, end_height: u64, config: &ParallelConfig) {
+        self.append(&StreamEvent::RunStarted {
+            started_at: self
+                .started_at
+                .duration_since(std::time::UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or_default(),
+            network: crate::multi_network::network_name(config.network).to_string(),
+            start_height,
+            end_height,
+            chunk_size: config.chunk_size,
+            num_workers: config.num_workers,
+            utxo_backend: config.utxo_backend.name().to_string(),
+            sample_seed: config.sample.filter(|s| !s.is_exhaustive()).map(|s| s.seed),
+        });
+    }
+
+    pub fn divergence(&self, height: u64, blvm_result: &str, core_result: &str, waiver: Option<&str>) {
+        self.append(&StreamEvent::Divergence(DivergenceRecord {
+            height,
+            blvm_result: blvm_result.to_string(),
+            core_result: core_result.to_string(),
+            waiver: waiver.map(str::to_string),
+        }));
+    }
+
+    pub fn chunk_finished(&self, result: &ChunkResult) {
+        self.append(&StreamEvent::Chunk(ChunkRecord::from_result(result, self.started_at)));
+    }
+
+    pub fn chunk_failed(&self, start_height: u64, end_height: u64, error: &anyhow::Error) {
+        self.append(&StreamEvent::ChunkFailed {
+            start_height,
+            end_height,
+            error: format!("{:#}", error),
+        });
+    }
+}
+
+/// The last run in a stream file, as far as it got
+///
+/// A torn final line (the process died mid-write) is skipped; anything else
+/// that doesn't parse is an error.
+pub fn load_run(path: &Path) -> Result<RunRecord> {
+    let content = std::fs::read_to_string(path).with_context(|| format!("Failed to read {}", path.display()))?;
+    let lines: Vec<&str> = content.lines().filter(|line| !line.trim().is_empty()).collect();
+    let mut run: Option<RunRecord> = None;
+    let mut finished = false;
+    for (index, line) in lines.iter().enumerate() {
+        let event: StreamEvent = match serde_json::from_str(line) {
+            Ok(event) => event,
+            Err(_) if index + 1 == lines.len() && !content.ends_with('\n') => {
+                eprintln!("⚠️  Ignoring the torn last line of {}", path.display());
+                break;
+            }
+            Err(e) => return Err(e).with_context(|| format!("Invalid event on line {} of {}", index + 1, path.display())),
+        };
+        match event {
+            StreamEvent::RunStarted {
+                started_at,
+                start_height,
+                end_height,
+                chunk_size,
+                num_workers,
+                utxo_backend,
+                sample_seed,
+                ..
+            } => {
+                finished = false;
+                run = Some(RunRecord {
+                    started_at,
+                    start_height,
+                    end_height,
+                    chunk_size,
+                    num_workers,
+                    utxo_backend,
+                    sample_seed,
+                    chunks: Vec::new(),
+                });
+            }
+            StreamEvent::Chunk(chunk) => {
+                if let Some(run) = &mut run {
+                    run.chunks.push(chunk);
+                }
+            }
+            StreamEvent::RunFinished { .. } => finished = true,
+            StreamEvent::Divergence(_) | StreamEvent::ChunkFailed { .. } => {}
+        }
+    }
+    let mut run = run.with_context(|| format!("No run_started event in {}", path.display()))?;
+    if !finished {
+        eprintln!(
+            "⚠️  The last run in {} didn't finish; reporting the {} chunk(s) it completed",
+            path.display(),
+            run.chunks.len()
+        );
+    }
+    run.chunks.sort_by_key(|chunk| chunk.start_height);
+    Ok(run)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chunk(start_height: u64, end_height: u64) -> ChunkRecord {
+        ChunkRecord {
+            start_height,
+            end_height,
+            tested: (end_height - start_height + 1) as usize,
+            matched: (end_height - start_height + 1) as usize,
+            duration_secs: 1.0,
+            finished_after_secs: 1.0,
+            utxo_count: 0,
+            divergences: Vec::new(),
+            poisoned: None,
+            memory: Default::default(),
+        }
+    }
+
+    #[test]
+    fn test_load_run_after_crash() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("results.jsonl");
+        let stream = ResultStream::open(&path, std::time::SystemTime::now()).unwrap();
+        let started = |start_height| StreamEvent::RunStarted {
+            started_at: 0,
+            network: "mainnet".to_string(),
+            start_height,
+            end_height: 299,
+            chunk_size: 100,
+            num_workers: 2,
+            utxo_backend: "memory".to_string(),
+            sample_seed: None,
+        };
+        // An earlier, finished run in the same file
+        stream.append(&started(0));
+        stream.append(&StreamEvent::Chunk(chunk(0, 299)));
+        stream.append(&StreamEvent::RunFinished { tested: 300, divergences: 0, wall_secs: 1.0 });
+
+        stream.append(&started(100));
+        stream.append(&StreamEvent::Chunk(chunk(200, 299)));
+        stream.divergence(150, "Valid", "Invalid(bad-txns)", None);
+        stream.append(&StreamEvent::Chunk(chunk(100, 199)));
+        drop(stream);
+        // Killed half-way through the next line
+        std::fs::OpenOptions::new()
+            .append(true)
+            .open(&path)
+            .unwrap()
+            .write_all(b"{\"event\":\"chunk\",\"start_he")
+            .unwrap();
+
+        let run = load_run(&path).unwrap();
+        assert_eq!(run.start_height, 100);
+        let starts: Vec<u64> = run.chunks.iter().map(|c| c.start_height).collect();
+        assert_eq!(starts, [100, 200]);
+
+        // A bad line that isn't the last one is corruption, not a crash
+        std::fs::write(&path, "garbage\n{\"event\":\"run_finished\",\"tested\":0,\"divergences\":0,\"wall_secs\":0.0}\n").unwrap();
+        assert!(load_run(&path).is_err());
+    }
+}
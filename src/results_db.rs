@@ -0,0 +1,544 @@
+//! Run History Database
+//!
+//! Records every parallel differential run (parameters, revisions of
+//! blvm-consensus and Core, per-chunk timings, divergences) into SQLite, with
+//! query helpers for questions like "when did height X first diverge" and
+//! "has throughput regressed since commit Y".
+//!
+//! Each run also stores its wall-clock time and blocks/sec per consensus era,
+//! so `perf_history` can line up blvm-consensus revisions in the order they
+//! were first run and flag the ones where throughput dropped - overall or in
+//! a single era - against the revision before.
+//!
+//! Enabled with the `results-db` feature. `run_parallel_differential` records
+//! into the database at `BLVM_RESULTS_DB` when set.
+
+use anyhow::{Context, Result};
+use rusqlite::{params, Connection, OptionalExtension};
+use std::path::Path;
+
+use crate::parallel_differential::{ChunkResult, EraTimings, ParallelConfig};
+
+const SCHEMA: &str = r#"
+CREATE TABLE IF NOT EXISTS runs (
+    id                  INTEGER PRIMARY KEY AUTOINCREMENT,
+    started_at          INTEGER NOT NULL,
+    start_height        INTEGER NOT NULL,
+    end_height          INTEGER NOT NULL,
+    chunk_size          INTEGER NOT NULL,
+    num_workers         INTEGER NOT NULL,
+    utxo_backend        TEXT NOT NULL,
+    blvm_consensus_rev  TEXT,
+    core_version        TEXT,
+    blocks_tested       INTEGER NOT NULL,
+    divergences         INTEGER NOT NULL,
+    duration_secs       REAL NOT NULL,
+    throughput          REAL NOT NULL,
+    wall_secs           REAL
+);
+CREATE TABLE IF NOT EXISTS chunks (
+    run_id          INTEGER NOT NULL REFERENCES runs(id),
+    start_height    INTEGER NOT NULL,
+    end_height      INTEGER NOT NULL,
+    tested          INTEGER NOT NULL,
+    matched         INTEGER NOT NULL,
+    duration_secs   REAL NOT NULL,
+    poisoned        TEXT
+);
+CREATE TABLE IF NOT EXISTS divergences (
+    run_id       INTEGER NOT NULL REFERENCES runs(id),
+    height       INTEGER NOT NULL,
+    blvm_result  TEXT NOT NULL,
+    core_result  TEXT NOT NULL
+);
+CREATE INDEX IF NOT EXISTS idx_divergences_height ON divergences(height);
+CREATE TABLE IF NOT EXISTS era_throughput (
+    run_id          INTEGER NOT NULL REFERENCES runs(id),
+    era             TEXT NOT NULL,
+    blocks          INTEGER NOT NULL,
+    duration_secs   REAL NOT NULL
+);
+"#;
+
+/// Revisions under test
+#[derive(Debug, Clone, Default)]
+pub struct Revisions {
+    /// blvm-consensus git revision
+    pub blvm_consensus: Option<String>,
+    /// Core version string (e.g. from `getnetworkinfo.subversion`)
+    pub core: Option<String>,
+}
+
+impl Revisions {
+    /// Detect revisions: `BLVM_CONSENSUS_REV`, else `git rev-parse` in ../blvm-consensus;
+    /// Core from `BITCOIN_CORE_VERSION`
+    pub fn detect() -> Self {
+        let blvm_consensus = std::env::var("BLVM_CONSENSUS_REV").ok().or_else(|| {
+            let manifest_dir = Path::new(env!("CARGO_MANIFEST_DIR"));
+            std::process::Command::new("git")
+                .arg("-C")
+                .arg(manifest_dir.join("../blvm-consensus"))
+                .args(["rev-parse", "HEAD"])
+                .output()
+                .ok()
+                .filter(|out| out.status.success())
+                .map(|out| String::from_utf8_lossy(&out.stdout).trim().to_string())
+        });
+        Self {
+            blvm_consensus,
+            core: std::env::var("BITCOIN_CORE_VERSION").ok(),
+        }
+    }
+}
+
+/// Summary row for a recorded run
+#[derive(Debug, Clone)]
+pub struct RunSummary {
+    pub id: i64,
+    pub started_at: i64,
+    pub start_height: u64,
+    pub end_height: u64,
+    pub blvm_consensus_rev: Option<String>,
+    pub core_version: Option<String>,
+    pub blocks_tested: u64,
+    pub divergences: u64,
+    pub throughput: f64,
+}
+
+/// Throughput comparison against a baseline revision
+#[derive(Debug, Clone)]
+pub struct ThroughputComparison {
+    /// Mean blocks/sec over runs at the baseline revision
+    pub baseline: f64,
+    /// Blocks/sec of the most recent run
+    pub latest: f64,
+    /// (latest - baseline) / baseline
+    pub change: f64,
+}
+
+impl ThroughputComparison {
+    /// Whether throughput dropped by more than `tolerance` (e.g. 0.05 = 5%)
+    pub fn regressed(&self, tolerance: f64) -> bool {
+        self.change < -tolerance
+    }
+}
+
+/// Throughput of one blvm-consensus revision, averaged over its runs
+#[derive(Debug, Clone)]
+pub struct RevisionPerf {
+    pub revision: String,
+    pub runs: u64,
+    /// When the revision was first run (unix seconds); history is ordered by this
+    pub first_run_at: i64,
+    /// Mean blocks/sec
+    pub throughput: f64,
+    /// Mean run wall-clock time (None for runs recorded before it was tracked)
+    pub wall_secs: Option<f64>,
+    /// (era, blocks/sec) over all of the revision's runs
+    pub eras: Vec<(String, f64)>,
+}
+
+/// A revision slower than the revision run before it
+#[derive(Debug, Clone, PartialEq)]
+pub struct PerfRegression {
+    pub revision: String,
+    pub previous: String,
+    /// "overall" or an era name
+    pub scope: String,
+    /// (revision - previous) / previous blocks/sec
+    pub change: f64,
+}
+
+/// Consecutive revisions in `history` whose blocks/sec dropped by more than `tolerance` (e.g. 0.05 = 5%)
+pub fn perf_regressions(history: &[RevisionPerf], tolerance: f64) -> Vec<PerfRegression> {
+    let mut regressions = Vec::new();
+    for pair in history.windows(2) {
+        let (previous, current) = (&pair[0], &pair[1]);
+        let overall = std::iter::once(("overall", previous.throughput, current.throughput));
+        let eras = current.eras.iter().filter_map(|(era, bps)| {
+            let (_, previous_bps) = previous.eras.iter().find(|(e, _)| e == era)?;
+            Some((era.as_str(), *previous_bps, *bps))
+        });
+        for (scope, before, after) in overall.chain(eras) {
+            if before <= 0.0 {
+                continue;
+            }
+            let change = (after - before) / before;
+            if change < -tolerance {
+                regressions.push(PerfRegression {
+                    revision: current.revision.clone(),
+                    previous: previous.revision.clone(),
+                    scope: scope.to_string(),
+                    change,
+                });
+            }
+        }
+    }
+    regressions
+}
+
+/// Print the revision history table and any regressions
+pub fn print_perf_history(history: &[RevisionPerf], regressions: &[PerfRegression]) {
+    println!("📈 Throughput by blvm-consensus revision (oldest first)");
+    for perf in history {
+        let flagged = regressions.iter().any(|r| r.revision == perf.revision);
+        let eras = perf
+            .eras
+            .iter()
+            .map(|(era, bps)| format!("{} {:.1}", era, bps))
+            .collect::<Vec<_>>()
+            .join(", ");
+        println!(
+            "   {} {:<12} {:>3} run(s) {:>9.1} blocks/sec  wall {:>9}  [{}]",
+            if flagged { "❌" } else { "  " },
+            &perf.revision[..perf.revision.len().min(12)],
+            perf.runs,
+            perf.throughput,
+            perf.wall_secs.map_or("-".to_string(), |secs| format!("{:.0}s", secs)),
+            eras
+        );
+    }
+    for regression in regressions {
+        println!(
+            "⚠️  {} is {:.1}% slower than {} ({})",
+            &regression.revision[..regression.revision.len().min(12)],
+            -100.0 * regression.change,
+            &regression.previous[..regression.previous.len().min(12)],
+            regression.scope
+        );
+    }
+}
+
+/// SQLite run history
+pub struct ResultsDb {
+    conn: Connection,
+}
+
+impl ResultsDb {
+    /// Open (or create) the database at `path`
+    pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let conn = Connection::open(path)
+            .with_context(|| format!("Failed to open results database {}", path.display()))?;
+        Self::init(conn)
+    }
+
+    /// In-memory database (tests, dry runs)
+    pub fn open_in_memory() -> Result<Self> {
+        Self::init(Connection::open_in_memory()?)
+    }
+
+    fn init(conn: Connection) -> Result<Self> {
+        conn.execute_batch(SCHEMA).context("Failed to create results schema")?;
+        // Databases created before wall-clock tracking lack the column
+        let has_wall_secs = conn
+            .prepare("SELECT 1 FROM pragma_table_info('runs') WHERE name = 'wall_secs'")?
+            .exists([])?;
+        if !has_wall_secs {
+            conn.execute_batch("ALTER TABLE runs ADD COLUMN wall_secs REAL")
+                .context("Failed to upgrade results schema")?;
+        }
+        Ok(Self { conn })
+    }
+
+    /// Record a finished run (`wall_secs`: the whole run, start to finish); returns its id
+    pub fn record_run(
+        &mut self,
+        start_height: u64,
+        end_height: u64,
+        config: &ParallelConfig,
+        revisions: &Revisions,
+        results: &[ChunkResult],
+        wall_secs: f64,
+    ) -> Result<i64> {
+        let started_at = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_secs() as i64)
+            .unwrap_or(0);
+        let blocks_tested: usize = results.iter().map(|r| r.tested).sum();
+        let divergences: usize = results.iter().map(|r| r.divergences.len()).sum();
+        let duration_secs: f64 = results.iter().map(|r| r.duration_secs).sum();
+        let throughput = if duration_secs > 0.0 {
+            blocks_tested as f64 / duration_secs
+        } else {
+            0.0
+        };
+
+        let tx = self.conn.transaction()?;
+        tx.execute(
+            "INSERT INTO runs (started_at, start_height, end_height, chunk_size, num_workers, utxo_backend,
+                               blvm_consensus_rev, core_version, blocks_tested, divergences, duration_secs, throughput,
+                               wall_secs)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13)",
+            params![
+                started_at,
+                start_height as i64,
+                end_height as i64,
+                config.chunk_size as i64,
+                config.num_workers as i64,
+                config.utxo_backend.name(),
+                revisions.blvm_consensus,
+                revisions.core,
+                blocks_tested as i64,
+                divergences as i64,
+                duration_secs,
+                throughput,
+                wall_secs,
+            ],
+        )?;
+        let run_id = tx.last_insert_rowid();
+
+        let mut era_timings = EraTimings::default();
+        for result in results {
+            era_timings.add(&result.era_timings);
+        }
+        for (era, blocks, secs) in era_timings.eras() {
+            tx.execute(
+                "INSERT INTO era_throughput (run_id, era, blocks, duration_secs) VALUES (?1, ?2, ?3, ?4)",
+                params![run_id, era.name(), blocks as i64, secs],
+            )?;
+        }
+
+        for result in results {
+            tx.execute(
+                "INSERT INTO chunks (run_id, start_height, end_height, tested, matched, duration_secs, poisoned)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+                params![
+                    run_id,
+                    result.start_height as i64,
+                    result.end_height as i64,
+                    result.tested as i64,
+                    result.matched as i64,
+                    result.duration_secs,
+                    result.poisoned.as_ref().map(|p| p.to_string()),
+                ],
+            )?;
+            for (height, blvm, core) in &result.divergences {
+                tx.execute(
+                    "INSERT INTO divergences (run_id, height, blvm_result, core_result) VALUES (?1, ?2, ?3, ?4)",
+                    params![run_id, *height as i64, blvm, core],
+                )?;
+            }
+        }
+        tx.commit()?;
+
+        Ok(run_id)
+    }
+
+    /// Earliest run in which `height` diverged
+    pub fn first_divergence(&self, height: u64) -> Result<Option<RunSummary>> {
+        self.conn
+            .query_row(
+                &format!(
+                    "SELECT {} FROM runs r JOIN divergences d ON d.run_id = r.id
+                     WHERE d.height = ?1 ORDER BY r.started_at, r.id LIMIT 1",
+                    RUN_COLUMNS
+                ),
+                params![height as i64],
+                run_from_row,
+            )
+            .optional()
+            .map_err(Into::into)
+    }
+
+    /// Most recent runs, newest first
+    pub fn recent_runs(&self, limit: usize) -> Result<Vec<RunSummary>> {
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT {} FROM runs r ORDER BY r.started_at DESC, r.id DESC LIMIT ?1",
+            RUN_COLUMNS
+        ))?;
+        let rows = stmt.query_map(params![limit as i64], run_from_row)?;
+        Ok(rows.collect::<rusqlite::Result<Vec<_>>>()?)
+    }
+
+    /// Compare the latest run's throughput against runs at `baseline_rev`
+    ///
+    /// Returns None if there are no runs at the baseline revision.
+    pub fn throughput_since(&self, baseline_rev: &str) -> Result<Option<ThroughputComparison>> {
+        let baseline: Option<f64> = self.conn.query_row(
+            "SELECT AVG(throughput) FROM runs WHERE blvm_consensus_rev LIKE ?1 || '%'",
+            params![baseline_rev],
+            |row| row.get(0),
+        )?;
+        let Some(baseline) = baseline else {
+            return Ok(None);
+        };
+        let Some(latest) = self.recent_runs(1)?.into_iter().next() else {
+            return Ok(None);
+        };
+        let change = if baseline > 0.0 {
+            (latest.throughput - baseline) / baseline
+        } else {
+            0.0
+        };
+        Ok(Some(ThroughputComparison {
+            baseline,
+            latest: latest.throughput,
+            change,
+        }))
+    }
+
+    /// Per-revision throughput in the order revisions were first run
+    ///
+    /// With `range`, only runs over exactly that (start, end) height range count,
+    /// so revisions are compared on the same blocks.
+    pub fn perf_history(&self, range: Option<(u64, u64)>) -> Result<Vec<RevisionPerf>> {
+        let (start, end) = match range {
+            Some((start, end)) => (Some(start as i64), Some(end as i64)),
+            None => (None, None),
+        };
+        let mut stmt = self.conn.prepare(
+            "SELECT blvm_consensus_rev, COUNT(*), MIN(started_at), AVG(throughput), AVG(wall_secs)
+             FROM runs
+             WHERE blvm_consensus_rev IS NOT NULL
+               AND (?1 IS NULL OR start_height = ?1) AND (?2 IS NULL OR end_height = ?2)
+             GROUP BY blvm_consensus_rev
+             ORDER BY MIN(started_at), MIN(id)",
+        )?;
+        let mut history = stmt
+            .query_map(params![start, end], |row| {
+                Ok(RevisionPerf {
+                    revision: row.get(0)?,
+                    runs: row.get::<_, i64>(1)? as u64,
+                    first_run_at: row.get(2)?,
+                    throughput: row.get(3)?,
+                    wall_secs: row.get(4)?,
+                    eras: Vec::new(),
+                })
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+
+        let mut stmt = self.conn.prepare(
+            "SELECT r.blvm_consensus_rev, e.era, SUM(e.blocks) / SUM(e.duration_secs)
+             FROM era_throughput e JOIN runs r ON r.id = e.run_id
+             WHERE r.blvm_consensus_rev IS NOT NULL
+               AND (?1 IS NULL OR r.start_height = ?1) AND (?2 IS NULL OR r.end_height = ?2)
+             GROUP BY r.blvm_consensus_rev, e.era
+             HAVING SUM(e.duration_secs) > 0",
+        )?;
+        let eras = stmt
+            .query_map(params![start, end], |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?, row.get::<_, f64>(2)?)))?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        for perf in &mut history {
+            // Keep eras in chain order
+            for era in crate::sampler::Era::ALL {
+                if let Some((_, _, bps)) = eras.iter().find(|(rev, name, _)| *rev == perf.revision && name == era.name()) {
+                    perf.eras.push((era.name().to_string(), *bps));
+                }
+            }
+        }
+        Ok(history)
+    }
+}
+
+const RUN_COLUMNS: &str = "r.id, r.started_at, r.start_height, r.end_height, r.blvm_consensus_rev, \
+                           r.core_version, r.blocks_tested, r.divergences, r.throughput";
+
+fn run_from_row(row: &rusqlite::Row<'_>) -> rusqlite::Result<RunSummary> {
+    Ok(RunSummary {
+        id: row.get(0)?,
+        started_at: row.get(1)?,
+        start_height: row.get::<_, i64>(2)? as u64,
+        end_height: row.get::<_, i64>(3)? as u64,
+        blvm_consensus_rev: row.get(4)?,
+        core_version: row.get(5)?,
+        blocks_tested: row.get::<_, i64>(6)? as u64,
+        divergences: row.get::<_, i64>(7)? as u64,
+        throughput: row.get(8)?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chunk(start: u64, end: u64, secs: f64, divergences: Vec<u64>) -> ChunkResult {
+        let tested = (end - start + 1) as usize;
+        ChunkResult {
+            start_height: start,
+            end_height: end,
+            tested,
+            matched: tested - divergences.len(),
+            divergences: divergences
+                .into_iter()
+                .map(|h| (h, "Invalid(x)".to_string(), "Valid".to_string()))
+                .collect(),
+            waived: Vec::new(),
+            version_divergences: Vec::new(),
+            duration_secs: secs,
+            utxo_count: 0,
+            finished_at: std::time::SystemTime::UNIX_EPOCH,
+            poisoned: None,
+            stage_timings: Default::default(),
+            era_timings: Default::default(),
+            slow_blocks: Vec::new(),
+            slowest: Default::default(),
+            memory: Default::default(),
+            retries: 0,
+            assumed_utxo: None,
+            end_utxo: None,
+            failed: None,
+            untested: None,
+        }
+    }
+
+    fn revs(rev: &str) -> Revisions {
+        Revisions {
+            blvm_consensus: Some(rev.to_string()),
+            core: None,
+        }
+    }
+
+    #[test]
+    fn test_first_divergence_and_regression() {
+        let mut db = ResultsDb::open_in_memory().unwrap();
+        let config = ParallelConfig::default();
+
+        let first = db.record_run(0, 99, &config, &revs("aaaa"), &[chunk(0, 99, 1.0, vec![])], 1.0).unwrap();
+        let second = db.record_run(0, 99, &config, &revs("bbbb"), &[chunk(0, 99, 2.0, vec![42])], 2.0).unwrap();
+        db.record_run(0, 99, &config, &revs("cccc"), &[chunk(0, 99, 2.0, vec![42])], 2.0).unwrap();
+        assert!(second > first);
+
+        let diverged = db.first_divergence(42).unwrap().unwrap();
+        assert_eq!(diverged.id, second);
+        assert!(db.first_divergence(7).unwrap().is_none());
+
+        let comparison = db.throughput_since("aaaa").unwrap().unwrap();
+        assert!((comparison.change + 0.5).abs() < 1e-9);
+        assert!(comparison.regressed(0.05));
+        assert!(db.throughput_since("zzzz").unwrap().is_none());
+    }
+
+    #[test]
+    fn test_perf_history_flags_era_regressions() {
+        let mut db = ResultsDb::open_in_memory().unwrap();
+        let config = ParallelConfig::default();
+        let run = |db: &mut ResultsDb, rev: &str, pre_bip34_secs: f64, segwit_secs: f64, start: u64| {
+            let mut result = chunk(start, start + 199, 10.0, vec![]);
+            for height in 0..100 {
+                result.era_timings.record(height, pre_bip34_secs / 100.0);
+                result.era_timings.record(500_000 + height, segwit_secs / 100.0);
+            }
+            db.record_run(start, start + 199, &config, &revs(rev), &[result], 12.0).unwrap();
+        };
+        run(&mut db, "aaaa", 1.0, 10.0, 0);
+        run(&mut db, "aaaa", 1.0, 10.0, 0);
+        // Same overall throughput, but segwit-era blocks got 25% slower
+        run(&mut db, "bbbb", 1.0, 13.333, 0);
+        // A different range doesn't count when filtering by range
+        run(&mut db, "cccc", 100.0, 100.0, 1_000);
+
+        let history = db.perf_history(Some((0, 199))).unwrap();
+        assert_eq!(history.iter().map(|p| p.revision.as_str()).collect::<Vec<_>>(), vec!["aaaa", "bbbb"]);
+        assert_eq!(history[0].runs, 2);
+        assert_eq!(history[0].wall_secs, Some(12.0));
+        assert_eq!(history[0].eras.iter().map(|(era, _)| era.as_str()).collect::<Vec<_>>(), vec!["pre-bip34", "segwit"]);
+
+        let regressions = perf_regressions(&history, 0.05);
+        assert_eq!(regressions.len(), 1);
+        assert_eq!((regressions[0].revision.as_str(), regressions[0].scope.as_str()), ("bbbb", "segwit"));
+        assert!((regressions[0].change + 0.25).abs() < 1e-3);
+
+        assert_eq!(db.perf_history(None).unwrap().len(), 3);
+    }
+}
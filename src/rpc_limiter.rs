@@ -0,0 +1,249 @@
+//! RPC Rate Limiting
+//!
+//! Every chunk of a parallel run fetches its blocks (and Core's verdicts) from
+//! the same bitcoind. Unchecked, dozens of concurrent `getblock` calls fill
+//! Core's RPC work queue (`-rpcworkqueue`, 16 by default), after which it
+//! answers HTTP 503 "Work queue depth exceeded" and the node starves. One
+//! `RpcLimiter` is shared by all chunks: it caps calls in flight, optionally
+//! spaces them to a fixed rate, and doubles the spacing whenever Core rejects
+//! a call for a full work queue, easing back once calls succeed again.
+
+use anyhow::{Context, Result};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tokio::sync::{Semaphore, SemaphorePermit};
+
+/// Default cap on calls in flight (half of Core's default work queue)
+pub const DEFAULT_MAX_IN_FLIGHT: usize = 8;
+
+/// Spacing after the first work-queue rejection
+const MIN_OVERLOAD_INTERVAL: Duration = Duration::from_millis(20);
+
+/// Upper bound on the spacing after repeated rejections
+const MAX_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Successful calls in a row before the spacing is halved again
+const RECOVERY_SUCCESSES: u32 = 50;
+
+/// Limits for calls to the node behind `Rpc` and `Start9Rpc` sources
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RpcLimits {
+    /// Calls in flight across all chunks (0 = unlimited)
+    pub max_in_flight: usize,
+    /// Calls started per second across all chunks (None = unpaced until Core pushes back)
+    pub max_per_sec: Option<f64>,
+}
+
+impl Default for RpcLimits {
+    fn default() -> Self {
+        Self {
+            max_in_flight: DEFAULT_MAX_IN_FLIGHT,
+            max_per_sec: None,
+        }
+    }
+}
+
+impl RpcLimits {
+    /// Defaults, overridden by `BLVM_RPC_MAX_IN_FLIGHT` / `BLVM_RPC_MAX_PER_SEC`
+    pub fn from_env() -> Result<Self> {
+        let mut limits = Self::default();
+        if let Ok(n) = std::env::var("BLVM_RPC_MAX_IN_FLIGHT") {
+            limits.max_in_flight = n.parse().with_context(|| format!("Invalid BLVM_RPC_MAX_IN_FLIGHT '{}'", n))?;
+        }
+        if let Ok(rate) = std::env::var("BLVM_RPC_MAX_PER_SEC") {
+            let rate: f64 = rate.parse().with_context(|| format!("Invalid BLVM_RPC_MAX_PER_SEC '{}'", rate))?;
+            if !(rate > 0.0 && rate.is_finite()) {
+                anyhow::bail!("BLVM_RPC_MAX_PER_SEC must be positive, got {}", rate);
+            }
+            limits.max_per_sec = Some(rate);
+        }
+        Ok(limits)
+    }
+
+    /// One-line description for run headers
+    pub fn summary(&self) -> String {
+        let in_flight = match self.max_in_flight {
+            0 => "unlimited in flight".to_string(),
+            n => format!("{} in flight", n),
+        };
+        match self.max_per_sec {
+            Some(rate) => format!("{}, {:.0}/s", in_flight, rate),
+            None => in_flight,
+        }
+    }
+
+    fn base_interval(&self) -> Duration {
+        self.max_per_sec.map(|rate| Duration::from_secs_f64(1.0 / rate)).unwrap_or_default()
+    }
+}
+
+/// Spacing between call starts, widened on overload and narrowed on success
+#[derive(Debug)]
+struct Pacing {
+    base: Duration,
+    interval: Duration,
+    next_slot: Instant,
+    successes: u32,
+}
+
+impl Pacing {
+    fn new(base: Duration) -> Self {
+        Self {
+            base,
+            interval: base,
+            next_slot: Instant::now(),
+            successes: 0,
+        }
+    }
+
+    /// Reserve the next start slot; returns how long to wait for it
+    fn reserve(&mut self, now: Instant) -> Duration {
+        let slot = self.next_slot.max(now);
+        self.next_slot = slot + self.interval;
+        slot - now
+    }
+
+    /// Back off after a rejection; returns the new interval if it grew
+    fn slow_down(&mut self) -> Option<Duration> {
+        self.successes = 0;
+        let slower = (self.interval * 2).max(MIN_OVERLOAD_INTERVAL).min(MAX_INTERVAL.max(self.base));
+        (slower > self.interval).then(|| {
+            self.interval = slower;
+            slower
+        })
+    }
+
+    /// Count a success; returns the new interval if it shrank
+    fn record_success(&mut self) -> Option<Duration> {
+        if self.interval <= self.base {
+            return None;
+        }
+        self.successes += 1;
+        if self.successes < RECOVERY_SUCCESSES {
+            return None;
+        }
+        self.successes = 0;
+        let halved = self.interval / 2;
+        self.interval = if halved < MIN_OVERLOAD_INTERVAL { self.base } else { halved.max(self.base) };
+        Some(self.interval)
+    }
+}
+
+/// In-flight cap and adaptive pacing shared by every chunk of a run
+#[derive(Debug)]
+pub struct RpcLimiter {
+    limits: RpcLimits,
+    in_flight: Semaphore,
+    pacing: Mutex<Pacing>,
+    overloads: AtomicU64,
+}
+
+impl RpcLimiter {
+    pub fn new(limits: RpcLimits) -> Self {
+        let permits = match limits.max_in_flight {
+            0 => Semaphore::MAX_PERMITS,
+            n => n,
+        };
+        Self {
+            limits,
+            in_flight: Semaphore::new(permits),
+            pacing: Mutex::new(Pacing::new(limits.base_interval())),
+            overloads: AtomicU64::new(0),
+        }
+    }
+
+    pub fn limits(&self) -> RpcLimits {
+        self.limits
+    }
+
+    /// Calls Core rejected for a full work queue so far
+    pub fn overloads(&self) -> u64 {
+        self.overloads.load(Ordering::Relaxed)
+    }
+
+    /// Wait for an in-flight slot and this call's start time
+    pub async fn acquire(&self) -> RpcPermit<'_> {
+        let permit = self.in_flight.acquire().await.expect("RPC limiter semaphore is never closed");
+        let wait = self.pacing.lock().expect("RPC pacing lock poisoned").reserve(Instant::now());
+        if !wait.is_zero() {
+            tokio::time::sleep(wait).await;
+        }
+        RpcPermit { limiter: self, _permit: permit }
+    }
+}
+
+/// A call in flight; report how it went with `finish`
+pub struct RpcPermit<'a> {
+    limiter: &'a RpcLimiter,
+    _permit: SemaphorePermit<'a>,
+}
+
+impl RpcPermit<'_> {
+    /// Release the slot, slowing everyone down if Core said its work queue was full
+    pub fn finish(self, overloaded: bool) {
+        let mut pacing = self.limiter.pacing.lock().expect("RPC pacing lock poisoned");
+        if overloaded {
+            self.limiter.overloads.fetch_add(1, Ordering::Relaxed);
+            if let Some(interval) = pacing.slow_down() {
+                eprintln!("🐢 Server is turning calls away (work queue full / rate limited); spacing calls {}ms apart", interval.as_millis());
+            }
+        } else if let Some(interval) = pacing.record_success() {
+            println!("🐇 RPC calls recovering; spacing now {}ms", interval.as_millis());
+        }
+    }
+}
+
+/// Whether an RPC error is Core turning the call away for a full work queue
+pub fn is_work_queue_error(error: &anyhow::Error) -> bool {
+    let message = format!("{:#}", error);
+    message.contains("Work queue depth exceeded") || message.contains("503 Service Unavailable")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pacing_backs_off_and_recovers() {
+        let mut pacing = Pacing::new(Duration::ZERO);
+        let now = Instant::now();
+        assert_eq!(pacing.reserve(now), Duration::ZERO);
+        assert_eq!(pacing.record_success(), None);
+
+        assert_eq!(pacing.slow_down(), Some(MIN_OVERLOAD_INTERVAL));
+        assert_eq!(pacing.slow_down(), Some(MIN_OVERLOAD_INTERVAL * 2));
+        for _ in 0..20 {
+            pacing.slow_down();
+        }
+        assert_eq!(pacing.interval, MAX_INTERVAL);
+        assert_eq!(pacing.slow_down(), None);
+
+        // Back-to-back reservations are spaced by the current interval
+        let now = Instant::now();
+        let first = pacing.reserve(now);
+        assert_eq!(pacing.reserve(now), first + MAX_INTERVAL);
+
+        // Each run of successes halves the interval until it drops back to unpaced
+        let mut steps = 0;
+        while pacing.interval > Duration::ZERO {
+            for _ in 0..RECOVERY_SUCCESSES {
+                pacing.record_success();
+            }
+            steps += 1;
+        }
+        assert_eq!(steps, 7);
+    }
+
+    #[test]
+    fn test_pacing_never_recovers_past_configured_rate() {
+        let base = RpcLimits { max_in_flight: 4, max_per_sec: Some(100.0) }.base_interval();
+        let mut pacing = Pacing::new(base);
+        pacing.slow_down();
+        assert_eq!(pacing.interval, MIN_OVERLOAD_INTERVAL);
+        for _ in 0..RECOVERY_SUCCESSES {
+            pacing.record_success();
+        }
+        assert_eq!(pacing.interval, base);
+    }
+}
@@ -0,0 +1,322 @@
+//! Parallel Run Plan
+//!
+//! A full-chain differential run takes hours; `differential --plan` shows
+//! what one would do before committing to it, without validating anything:
+//! the data source and resolved height range, the chunk layout, which chunk
+//! checkpoints would be reused from the checkpoint store and which generated,
+//! a rough peak memory per worker and an estimated wall-clock time.
+//!
+//! Time estimates use the chunk sizing cost model (`chunk_sizing::CostModel`):
+//! measured per-era rates when `BLVM_RESULTS_DB` has history, else built-in
+//! ballpark rates. Memory estimates use the coin counts stored with reused
+//! checkpoints, else a coarse model of mainnet UTXO set growth.
+
+use anyhow::Result;
+use std::sync::Arc;
+
+use crate::chunk_sizing::{chunk_ranges, ChunkSizing, CostModel};
+use crate::parallel_differential::{source_tip, BlockDataSource, ParallelConfig};
+use crate::utxo_backend::UtxoBackend;
+
+/// In-memory cost of one coin (outpoint, output, map overhead), roughly
+const BYTES_PER_COIN: u64 = 120;
+
+/// Mainnet UTXO set size (height, millions of coins), interpolated between points
+const UTXO_GROWTH: &[(u64, f64)] = &[
+    (0, 0.0),
+    (100_000, 0.1),
+    (200_000, 3.0),
+    (250_000, 7.0),
+    (300_000, 14.0),
+    (350_000, 30.0),
+    (400_000, 40.0),
+    (450_000, 50.0),
+    (500_000, 60.0),
+    (550_000, 55.0),
+    (600_000, 63.0),
+    (650_000, 70.0),
+    (700_000, 80.0),
+    (750_000, 85.0),
+    (800_000, 115.0),
+    (850_000, 175.0),
+    (900_000, 170.0),
+];
+
+/// Where a chunk's starting UTXO set comes from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChunkStart {
+    /// First chunk: empty set at the range start
+    Empty,
+    /// Stored checkpoint at the given height, reused
+    Reused(u64),
+    /// Checkpoint at the given height, generated in phase 1
+    Generated(u64),
+    /// Checkpoints disabled: starts from an empty set (spends fail)
+    Unchecked,
+    /// Sample mode: the block's spent coins, fetched from Core
+    BlockLocal,
+    /// Optimistic mode: the coins the chunk spends from before it, from Core's undo data
+    Undo,
+}
+
+impl ChunkStart {
+    fn describe(&self) -> String {
+        match self {
+            ChunkStart::Empty => "empty UTXO set".to_string(),
+            ChunkStart::Reused(height) => format!("reuse checkpoint @{}", height),
+            ChunkStart::Generated(height) => format!("generate checkpoint @{}", height),
+            ChunkStart::Unchecked => "empty UTXO set (no checkpoints)".to_string(),
+            ChunkStart::BlockLocal => "block-local checkpoint from Core".to_string(),
+            ChunkStart::Undo => "spent coins from Core's undo data".to_string(),
+        }
+    }
+}
+
+/// One planned chunk
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChunkPlan {
+    pub start_height: u64,
+    pub end_height: u64,
+    pub starts_from: ChunkStart,
+    /// Estimated validation time on one worker
+    pub est_secs: f64,
+}
+
+/// Everything a run would do, worked out without doing it
+#[derive(Debug, Clone)]
+pub struct RunPlan {
+    pub source: String,
+    pub start_height: u64,
+    pub end_height: u64,
+    pub workers: usize,
+    pub utxo_backend: String,
+    pub chunk_sizing: String,
+    pub chunks: Vec<ChunkPlan>,
+    /// Checkpoint generation pass (sequential), if one is needed
+    pub generation_secs: Option<f64>,
+    /// Peak UTXO set held by one worker (None for the disk backend)
+    pub memory_per_worker: Option<u64>,
+    /// Per-worker blocks/sec used for the estimates
+    pub cost_model: CostModel,
+    pub est_wall_secs: f64,
+}
+
+impl RunPlan {
+    pub fn print(&self) {
+        use crate::mem_profile::format_bytes;
+
+        println!("\n🗺️  Plan (nothing will be validated)");
+        println!("   Source: {}", self.source);
+        println!("   Range: {} to {}", self.start_height, self.end_height);
+        println!("   Workers: {}, UTXO backend: {}", self.workers, self.utxo_backend);
+        println!("   Chunks: {} ({})", self.chunks.len(), self.chunk_sizing);
+        for (idx, chunk) in self.chunks.iter().enumerate().take(50) {
+            println!(
+                "      {:>4}. [{}-{}] {} (~{})",
+                idx + 1,
+                chunk.start_height,
+                chunk.end_height,
+                chunk.starts_from.describe(),
+                format_duration(chunk.est_secs)
+            );
+        }
+        if self.chunks.len() > 50 {
+            println!("      ... {} more", self.chunks.len() - 50);
+        }
+        let reused = self.chunks.iter().filter(|c| matches!(c.starts_from, ChunkStart::Reused(_))).count();
+        let generated = self.chunks.iter().filter(|c| matches!(c.starts_from, ChunkStart::Generated(_))).count();
+        if reused + generated > 0 {
+            println!("   Checkpoints: {} reused, {} generated", reused, generated);
+        }
+        if let Some(secs) = self.generation_secs {
+            println!("   Phase 1 (checkpoint generation, sequential): ~{}", format_duration(secs));
+        }
+        match self.memory_per_worker {
+            Some(bytes) => println!("   Peak UTXO set per worker: ~{}", format_bytes(bytes)),
+            None => println!("   Peak UTXO set per worker: on disk"),
+        }
+        let rates: Vec<String> = self
+            .cost_model
+            .rates
+            .iter()
+            .map(|(era, bps)| format!("{} {:.0}", era.name(), bps))
+            .collect();
+        println!("   Blocks/sec per worker ({}): {}", self.cost_model.source, rates.join(", "));
+        println!("   Estimated wall clock: ~{}", format_duration(self.est_wall_secs));
+    }
+}
+
+fn format_duration(secs: f64) -> String {
+    if secs >= 3_600.0 {
+        format!("{:.1}h", secs / 3_600.0)
+    } else if secs >= 60.0 {
+        format!("{:.0}m", secs / 60.0)
+    } else {
+        format!("{:.0}s", secs)
+    }
+}
+
+/// Modelled mainnet UTXO set size at `height`
+fn modelled_coins(height: u64) -> u64 {
+    let upper = UTXO_GROWTH.iter().position(|(h, _)| *h >= height).unwrap_or(UTXO_GROWTH.len() - 1);
+    let millions = if upper == 0 || UTXO_GROWTH[upper].0 < height {
+        UTXO_GROWTH[upper].1
+    } else {
+        let (h0, c0) = UTXO_GROWTH[upper - 1];
+        let (h1, c1) = UTXO_GROWTH[upper];
+        c0 + (c1 - c0) * (height - h0) as f64 / (h1 - h0) as f64
+    };
+    (millions * 1_000_000.0) as u64
+}
+
+/// Wall time of running `durations` in order on `workers` slots, each job
+/// starting on the first slot to free up (as the run's semaphore does)
+fn makespan(durations: impl IntoIterator<Item = f64>, workers: usize) -> f64 {
+    let mut slots = vec![0.0f64; workers.max(1)];
+    for secs in durations {
+        let free = slots
+            .iter_mut()
+            .min_by(|a, b| a.total_cmp(b))
+            .expect("at least one slot");
+        *free += secs;
+    }
+    slots.into_iter().fold(0.0, f64::max)
+}
+
+/// Where each of `ranges` starts from, given the heights already in the
+/// checkpoint store (`stored` = None if it can't be reused)
+fn layout_chunks(ranges: &[(u64, u64)], use_checkpoints: bool, stored: Option<&[u64]>) -> Vec<(u64, u64, ChunkStart)> {
+    let needed = crate::chunk_sizing::checkpoint_heights(ranges);
+    // The run regenerates everything unless every needed checkpoint is stored
+    let reuse = stored.is_some_and(|stored| needed.iter().all(|h| stored.contains(h)));
+    ranges
+        .iter()
+        .enumerate()
+        .map(|(idx, &(start, end))| {
+            let starts_from = if idx == 0 {
+                ChunkStart::Empty
+            } else if !use_checkpoints {
+                ChunkStart::Unchecked
+            } else if reuse {
+                ChunkStart::Reused(start - 1)
+            } else {
+                ChunkStart::Generated(start - 1)
+            };
+            (start, end, starts_from)
+        })
+        .collect()
+}
+
+/// Work out what `run_parallel_differential` would do over `start_height..=end_height`
+pub async fn plan_parallel_differential(
+    start_height: u64,
+    end_height: u64,
+    config: &ParallelConfig,
+    block_source: &Arc<BlockDataSource>,
+) -> Result<RunPlan> {
+    let actual_end = end_height.min(source_tip(block_source, end_height).await?);
+    if actual_end < start_height {
+        anyhow::bail!("{} ends at height {}, before the range starts at {}", block_source.name(), actual_end, start_height);
+    }
+    let workers = if config.verify_node.is_some() { 1 } else { config.num_workers };
+    let cost_model = match &config.chunk_sizing {
+        ChunkSizing::Weighted(model) => model.clone(),
+        ChunkSizing::Equal => CostModel::best_available(),
+    };
+
+    let store = crate::checkpoint_store::CheckpointStore::open(
+        &config.checkpoint_dir,
+        crate::checkpoint_store::base_interval_from_env().unwrap_or(crate::checkpoint_store::DEFAULT_BASE_INTERVAL),
+    )?;
+    let stored: Option<Vec<u64>> =
+        (store.start_height() == start_height).then(|| store.entries().iter().map(|e| e.height).collect());
+
+    let layout = match &config.sample {
+        Some(sample) => crate::sampler::sample_heights(start_height, actual_end, sample)
+            .into_iter()
+            .map(|height| (height, height, ChunkStart::BlockLocal))
+            .collect(),
+        None if config.optimistic.is_some() => chunk_ranges(start_height, actual_end, config.chunk_size, &config.chunk_sizing)
+            .into_iter()
+            .map(|(start, end)| (start, end, ChunkStart::Undo))
+            .collect(),
+        None => layout_chunks(
+            &chunk_ranges(start_height, actual_end, config.chunk_size, &config.chunk_sizing),
+            config.use_checkpoints,
+            stored.as_deref(),
+        ),
+    };
+    let chunks: Vec<ChunkPlan> = layout
+        .into_iter()
+        .map(|(start, end, starts_from)| ChunkPlan {
+            start_height: start,
+            end_height: end,
+            starts_from,
+            est_secs: cost_model.range_secs(start, end),
+        })
+        .collect();
+
+    let generates = chunks.iter().any(|c| matches!(c.starts_from, ChunkStart::Generated(_)));
+    let generation_secs = generates.then(|| cost_model.range_secs(start_height, actual_end));
+    let coins_at = |height: u64| {
+        store
+            .entries()
+            .iter()
+            .find(|e| e.height == height && stored.is_some())
+            .map(|e| e.utxo_count as u64)
+            .unwrap_or_else(|| modelled_coins(height))
+    };
+    let memory_per_worker = match config.utxo_backend {
+        UtxoBackend::Disk(_) => None,
+        _ if config.sample.is_some() => Some(0),
+        _ => Some(chunks.iter().map(|c| coins_at(c.end_height)).max().unwrap_or_default() * BYTES_PER_COIN),
+    };
+    let est_wall_secs = generation_secs.unwrap_or_default() + makespan(chunks.iter().map(|c| c.est_secs), workers);
+
+    Ok(RunPlan {
+        source: block_source.name().to_string(),
+        start_height,
+        end_height: actual_end,
+        workers,
+        utxo_backend: config.utxo_backend.name().to_string(),
+        chunk_sizing: config.chunk_sizing.describe(),
+        chunks,
+        generation_secs,
+        memory_per_worker,
+        cost_model,
+        est_wall_secs,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_layout_reuses_only_complete_stores() {
+        let ranges = chunk_ranges(0, 249, 100, &ChunkSizing::Equal);
+        let fresh = layout_chunks(&ranges, true, None);
+        assert_eq!(
+            fresh,
+            vec![
+                (0, 99, ChunkStart::Empty),
+                (100, 199, ChunkStart::Generated(99)),
+                (200, 249, ChunkStart::Generated(199)),
+            ]
+        );
+        let reused = layout_chunks(&ranges, true, Some(&[99, 199]));
+        assert_eq!(reused[2], (200, 249, ChunkStart::Reused(199)));
+        // A store missing one checkpoint is regenerated whole
+        assert_eq!(layout_chunks(&ranges, true, Some(&[99]))[1].2, ChunkStart::Generated(99));
+        assert_eq!(layout_chunks(&ranges, false, None)[1].2, ChunkStart::Unchecked);
+    }
+
+    #[test]
+    fn test_estimates() {
+        assert_eq!(makespan([4.0, 1.0, 1.0, 1.0, 1.0], 2), 4.0);
+        assert_eq!(makespan([1.0; 4], 1), 4.0);
+        assert_eq!(modelled_coins(0), 0);
+        assert_eq!(modelled_coins(225_000), 5_000_000);
+        assert_eq!(modelled_coins(2_000_000), 170_000_000);
+    }
+}
@@ -0,0 +1,162 @@
+//! Deterministic Block Sampler
+//!
+//! A full-chain differential run is too slow for pre-merge CI. Sample mode
+//! picks a seedable, stratified subset of heights - the same number from each
+//! consensus era (pre-BIP34, pre-segwit, segwit, taproot) - and validates only
+//! those blocks, each against a block-local checkpoint: the coins it spends,
+//! taken from Core's `getblock` verbosity 3 (Core 23.0+).
+//!
+//! The same seed always selects the same heights, and the seed is printed and
+//! stored in the run record so a failing sample can be reproduced exactly.
+//! Configured with `BLVM_SAMPLE_BLOCKS` (blocks per era) and `BLVM_SAMPLE_SEED`.
+
+use anyhow::{Context, Result};
+use blvm_consensus::constants::{BIP34_ACTIVATION_MAINNET, SEGWIT_ACTIVATION_MAINNET, TAPROOT_ACTIVATION_MAINNET};
+use blvm_consensus::UtxoSet;
+use std::collections::BTreeSet;
+
+use crate::bench_fixtures::SplitMix64;
+use crate::core_rpc_client::CoreRpcClient;
+
+/// Consensus era used to stratify samples
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Era {
+    PreBip34,
+    PreSegwit,
+    Segwit,
+    Taproot,
+}
+
+impl Era {
+    pub const ALL: [Era; 4] = [Era::PreBip34, Era::PreSegwit, Era::Segwit, Era::Taproot];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Era::PreBip34 => "pre-bip34",
+            Era::PreSegwit => "pre-segwit",
+            Era::Segwit => "segwit",
+            Era::Taproot => "taproot",
+        }
+    }
+
+    /// Mainnet heights covered by the era (inclusive)
+    pub fn heights(&self) -> (u64, u64) {
+        match self {
+            Era::PreBip34 => (0, BIP34_ACTIVATION_MAINNET - 1),
+            Era::PreSegwit => (BIP34_ACTIVATION_MAINNET, SEGWIT_ACTIVATION_MAINNET - 1),
+            Era::Segwit => (SEGWIT_ACTIVATION_MAINNET, TAPROOT_ACTIVATION_MAINNET - 1),
+            Era::Taproot => (TAPROOT_ACTIVATION_MAINNET, u64::MAX),
+        }
+    }
+
+    /// Era a mainnet height belongs to
+    pub fn of(height: u64) -> Era {
+        Era::ALL
+            .into_iter()
+            .find(|era| height <= era.heights().1)
+            .unwrap_or(Era::Taproot)
+    }
+}
+
+/// Sample mode settings
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SampleConfig {
+    /// Blocks sampled from each era
+    pub blocks_per_era: usize,
+    pub seed: u64,
+}
+
+impl SampleConfig {
+    /// Every height in the range, each from a block-local checkpoint
+    /// (for ranges that don't start at genesis)
+    pub fn exhaustive() -> Self {
+        Self { blocks_per_era: usize::MAX, seed: 0 }
+    }
+
+    pub fn is_exhaustive(&self) -> bool {
+        self.blocks_per_era == usize::MAX
+    }
+
+    /// From `BLVM_SAMPLE_BLOCKS` / `BLVM_SAMPLE_SEED`; None unless a sample size is set.
+    /// Without a seed one is drawn from the clock (and reported).
+    pub fn from_env() -> Result<Option<Self>> {
+        let Ok(blocks) = std::env::var("BLVM_SAMPLE_BLOCKS") else {
+            return Ok(None);
+        };
+        let blocks_per_era = blocks
+            .parse()
+            .with_context(|| format!("Invalid BLVM_SAMPLE_BLOCKS '{}'", blocks))?;
+        let seed = match std::env::var("BLVM_SAMPLE_SEED") {
+            Ok(seed) => seed.parse().with_context(|| format!("Invalid BLVM_SAMPLE_SEED '{}'", seed))?,
+            Err(_) => std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .map(|d| d.as_nanos() as u64)
+                .unwrap_or_default(),
+        };
+        Ok(Some(Self { blocks_per_era, seed }))
+    }
+}
+
+/// Pick up to `blocks_per_era` distinct heights from each era's overlap with
+/// `start..=end`, sorted ascending
+pub fn sample_heights(start: u64, end: u64, config: &SampleConfig) -> Vec<u64> {
+    let mut heights = BTreeSet::new();
+    for (idx, era) in Era::ALL.iter().enumerate() {
+        let (era_start, era_end) = era.heights();
+        let lo = era_start.max(start);
+        let hi = era_end.min(end);
+        if lo > hi {
+            continue;
+        }
+        let span = hi - lo + 1;
+        if config.blocks_per_era as u64 >= span {
+            heights.extend(lo..=hi);
+            continue;
+        }
+        // Each era gets its own stream so narrowing the range doesn't reshuffle the others
+        let mut rng = SplitMix64(config.seed ^ (idx as u64).wrapping_mul(0xD1B5_4A32_D192_ED03));
+        let mut picked = BTreeSet::new();
+        while picked.len() < config.blocks_per_era {
+            picked.insert(lo + rng.next_u64() % span);
+        }
+        heights.extend(picked);
+    }
+    heights.into_iter().collect()
+}
+
+/// Block-local checkpoint for every sampled height (the coins each block spends)
+pub async fn sample_checkpoints(client: &CoreRpcClient, heights: &[u64]) -> Result<Vec<(u64, UtxoSet)>> {
+    let mut checkpoints = Vec::with_capacity(heights.len());
+    for (idx, &height) in heights.iter().enumerate() {
+        let (_, _, pre_state) = crate::block_fixtures::fetch_block_with_pre_state(client, height)
+            .await
+            .with_context(|| format!("Failed to fetch pre-state for sampled block {}", height))?;
+        checkpoints.push((height, pre_state));
+        if (idx + 1) % 25 == 0 {
+            println!("   📊 Fetched {}/{} sample checkpoints", idx + 1, heights.len());
+        }
+    }
+    Ok(checkpoints)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sample_is_deterministic_and_stratified() {
+        let config = SampleConfig { blocks_per_era: 5, seed: 42 };
+        let heights = sample_heights(0, 900_000, &config);
+        assert_eq!(heights, sample_heights(0, 900_000, &config));
+        assert_eq!(heights.len(), 20);
+        for era in Era::ALL {
+            let (lo, hi) = era.heights();
+            assert_eq!(heights.iter().filter(|h| (lo..=hi).contains(*h)).count(), 5, "{}", era.name());
+        }
+        assert_ne!(heights, sample_heights(0, 900_000, &SampleConfig { seed: 43, ..config }));
+
+        // Small ranges are taken whole; eras outside the range are skipped
+        assert_eq!(sample_heights(10, 12, &config), vec![10, 11, 12]);
+        assert_eq!(sample_heights(500, 1_499, &SampleConfig::exhaustive()).len(), 1_000);
+    }
+}
@@ -0,0 +1,215 @@
+//! Slowest-Blocks Leaderboard
+//!
+//! A handful of pathological blocks (quadratic sighash-era transactions,
+//! huge input counts) can dominate a run's wall-clock time. Every chunk keeps
+//! the N blocks with the longest `connect_block` time plus running statistics
+//! of log(connect time); chunks merge into a run-wide leaderboard.
+//!
+//! Connect times are roughly log-normal, so an outlier is a block more than
+//! `OUTLIER_SIGMAS` standard deviations above the mean in log space.
+//! Leaderboard sizes come from `BLVM_SLOWEST_BLOCKS` (default 20).
+
+use anyhow::{Context, Result};
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+
+/// Blocks kept per leaderboard by default
+pub const DEFAULT_LEADERBOARD_SIZE: usize = 20;
+
+/// Standard deviations (of log connect time) that make a block an outlier
+pub const OUTLIER_SIGMAS: f64 = 3.0;
+
+/// Leaderboard size from `BLVM_SLOWEST_BLOCKS`
+pub fn leaderboard_size_from_env() -> Result<usize> {
+    match std::env::var("BLVM_SLOWEST_BLOCKS") {
+        Ok(size) => size
+            .parse()
+            .with_context(|| format!("Invalid BLVM_SLOWEST_BLOCKS '{}'", size)),
+        Err(_) => Ok(DEFAULT_LEADERBOARD_SIZE),
+    }
+}
+
+/// One block's validation cost
+#[derive(Debug, Clone)]
+pub struct SlowBlock {
+    pub height: u64,
+    /// Display-order block hash
+    pub hash: String,
+    pub connect_secs: f64,
+    pub tx_count: usize,
+    pub input_count: usize,
+}
+
+impl PartialEq for SlowBlock {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for SlowBlock {}
+
+impl PartialOrd for SlowBlock {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Reversed: `BinaryHeap` then pops the fastest block first
+impl Ord for SlowBlock {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other
+            .connect_secs
+            .total_cmp(&self.connect_secs)
+            .then(self.height.cmp(&other.height))
+    }
+}
+
+/// The N slowest blocks seen, plus log-time statistics of all blocks
+#[derive(Debug, Clone, Default)]
+pub struct Leaderboard {
+    capacity: usize,
+    heap: BinaryHeap<SlowBlock>,
+    /// Welford accumulators over ln(connect_secs)
+    count: u64,
+    mean: f64,
+    m2: f64,
+}
+
+impl Leaderboard {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            ..Default::default()
+        }
+    }
+
+    pub fn record(&mut self, block: SlowBlock) {
+        let ln = block.connect_secs.max(1e-9).ln();
+        self.count += 1;
+        let delta = ln - self.mean;
+        self.mean += delta / self.count as f64;
+        self.m2 += delta * (ln - self.mean);
+        self.push(block);
+    }
+
+    fn push(&mut self, block: SlowBlock) {
+        if self.capacity == 0 {
+            return;
+        }
+        self.heap.push(block);
+        if self.heap.len() > self.capacity {
+            self.heap.pop();
+        }
+    }
+
+    /// Fold another chunk's leaderboard into this one
+    pub fn merge(&mut self, other: &Leaderboard) {
+        if other.count > 0 {
+            let count = self.count + other.count;
+            let delta = other.mean - self.mean;
+            self.mean += delta * other.count as f64 / count as f64;
+            self.m2 += other.m2 + delta * delta * (self.count as f64 * other.count as f64) / count as f64;
+            self.count = count;
+        }
+        for block in other.heap.iter() {
+            self.push(block.clone());
+        }
+    }
+
+    /// Slowest first
+    pub fn slowest(&self) -> Vec<SlowBlock> {
+        let mut blocks: Vec<SlowBlock> = self.heap.iter().cloned().collect();
+        blocks.sort();
+        blocks
+    }
+
+    /// Connect time above which a block is an outlier (None until there's a spread)
+    pub fn outlier_threshold_secs(&self) -> Option<f64> {
+        if self.count < 2 {
+            return None;
+        }
+        let std_dev = (self.m2 / (self.count - 1) as f64).sqrt();
+        Some((self.mean + OUTLIER_SIGMAS * std_dev).exp())
+    }
+
+    /// Leaderboard entries beyond the outlier threshold, slowest first
+    pub fn outliers(&self) -> Vec<SlowBlock> {
+        match self.outlier_threshold_secs() {
+            Some(threshold) => self
+                .slowest()
+                .into_iter()
+                .filter(|b| b.connect_secs > threshold)
+                .collect(),
+            None => Vec::new(),
+        }
+    }
+
+    /// Print the leaderboard and outlier report
+    pub fn print_report(&self) {
+        let slowest = self.slowest();
+        if slowest.is_empty() {
+            return;
+        }
+        println!("\n🐢 Slowest {} blocks (connect_block time):", slowest.len());
+        println!("   {:>8}  {:>10}  {:>6}  {:>7}  hash", "height", "time", "txs", "inputs");
+        for block in &slowest {
+            println!(
+                "   {:>8}  {:>9.1}ms  {:>6}  {:>7}  {}",
+                block.height,
+                block.connect_secs * 1000.0,
+                block.tx_count,
+                block.input_count,
+                block.hash
+            );
+        }
+        let outliers = self.outliers();
+        if let Some(threshold) = self.outlier_threshold_secs() {
+            println!(
+                "   Outliers (> {:.1}ms, {}σ above the log-mean of {} blocks): {}",
+                threshold * 1000.0,
+                OUTLIER_SIGMAS,
+                self.count,
+                outliers.len()
+            );
+        }
+        if !outliers.is_empty() {
+            let heights: Vec<String> = outliers.iter().map(|b| b.height.to_string()).collect();
+            println!("   💡 Turn them into benches: bllvm-bench corpus-extract --heights {}", heights.join(","));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn block(height: u64, connect_secs: f64) -> SlowBlock {
+        SlowBlock {
+            height,
+            hash: String::new(),
+            connect_secs,
+            tx_count: 1,
+            input_count: 0,
+        }
+    }
+
+    #[test]
+    fn test_leaderboard_keeps_slowest_and_flags_outliers() {
+        let mut first = Leaderboard::new(3);
+        let mut second = Leaderboard::new(3);
+        for height in 0..500 {
+            let chunk = if height % 2 == 0 { &mut first } else { &mut second };
+            chunk.record(block(height, 0.001 * (1.0 + (height % 7) as f64 / 10.0)));
+        }
+        second.record(block(1000, 2.5));
+        first.record(block(1001, 0.01));
+
+        let mut run = Leaderboard::new(3);
+        run.merge(&first);
+        run.merge(&second);
+        let slowest: Vec<u64> = run.slowest().iter().map(|b| b.height).collect();
+        assert_eq!(slowest[..2], [1000, 1001]);
+        let outliers: Vec<u64> = run.outliers().iter().map(|b| b.height).collect();
+        assert_eq!(outliers, vec![1000, 1001]);
+    }
+}
@@ -0,0 +1,388 @@
+//! Dust and Standardness Rule Differential
+//!
+//! Generates boundary-value transactions for the relay policy rules that
+//! `is_standard_tx` covers: an output one satoshi either side of the dust
+//! threshold for every standard script type, bare multisig with 3 and 4 keys,
+//! OP_RETURN outputs at and over the data-carrier limit (and two of them), and
+//! transaction versions 0 through 4. Each transaction spends the same
+//! P2WSH(OP_TRUE) coin, so Core's `testmempoolaccept` judges only the policy
+//! difference and nothing is broadcast.
+//!
+//! Thresholds follow Core 28's defaults (`-dustrelayfee=3000`,
+//! `-datacarriersize=83`, `TX_MAX_STANDARD_VERSION=3`). Outcomes are grouped
+//! by rule, and every rule where BLVM and Core disagree is reported.
+
+use anyhow::Result;
+use blvm_consensus::mempool::is_standard_tx;
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+use crate::core_rpc_client::{BitcoinNetwork, CoreRpcClient};
+use crate::mempool_corpus::decode_tx;
+use crate::mutation_differential::RawTx;
+use crate::package_differential::{fund_op_true_coins, op_true_spend_with, Coin};
+
+/// Core's DUST_RELAY_TX_FEE (sat/kvB)
+const DUST_RELAY_FEE: u64 = 3000;
+/// Core's MAX_OP_RETURN_RELAY (whole scriptPubKey, in bytes)
+const MAX_OP_RETURN_RELAY: usize = 83;
+/// Fee-rate (sat/vB) paid by every case, comfortably above the minimum relay fee
+const CASE_FEE_RATE: u64 = 2;
+
+const OP_RETURN: u8 = 0x6a;
+const OP_PUSHDATA1: u8 = 0x4c;
+const OP_CHECKMULTISIG: u8 = 0xae;
+
+/// Policy rule a case exercises
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+pub enum StandardnessRule {
+    /// Output value below the dust threshold
+    Dust,
+    /// Bare multisig key count
+    BareMultisig,
+    /// OP_RETURN size and count
+    DataCarrier,
+    /// Transaction version
+    Version,
+}
+
+impl StandardnessRule {
+    pub fn name(&self) -> &'static str {
+        match self {
+            StandardnessRule::Dust => "dust",
+            StandardnessRule::BareMultisig => "bare-multisig",
+            StandardnessRule::DataCarrier => "datacarrier",
+            StandardnessRule::Version => "version",
+        }
+    }
+}
+
+/// Standard output script types and their dust boundary
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ScriptType {
+    P2pkh,
+    P2sh,
+    P2wpkh,
+    P2wsh,
+    P2tr,
+    /// Pay-to-anchor, witness v1 with a 2-byte program
+    P2a,
+}
+
+impl ScriptType {
+    const ALL: [ScriptType; 6] = [
+        ScriptType::P2pkh,
+        ScriptType::P2sh,
+        ScriptType::P2wpkh,
+        ScriptType::P2wsh,
+        ScriptType::P2tr,
+        ScriptType::P2a,
+    ];
+
+    fn name(&self) -> &'static str {
+        match self {
+            ScriptType::P2pkh => "p2pkh",
+            ScriptType::P2sh => "p2sh",
+            ScriptType::P2wpkh => "p2wpkh",
+            ScriptType::P2wsh => "p2wsh",
+            ScriptType::P2tr => "p2tr",
+            ScriptType::P2a => "p2a",
+        }
+    }
+
+    fn script_pubkey(&self) -> Vec<u8> {
+        match self {
+            ScriptType::P2pkh => [&[0x76, 0xa9, 0x14][..], &[0x11; 20], &[0x88, 0xac]].concat(),
+            ScriptType::P2sh => [&[0xa9, 0x14][..], &[0x22; 20], &[0x87]].concat(),
+            ScriptType::P2wpkh => [&[0x00, 0x14][..], &[0x33; 20]].concat(),
+            ScriptType::P2wsh => [&[0x00, 0x20][..], &[0x44; 32]].concat(),
+            ScriptType::P2tr => [&[0x51, 0x20][..], &[0x55; 32]].concat(),
+            ScriptType::P2a => vec![0x51, 0x02, 0x4e, 0x73],
+        }
+    }
+}
+
+/// Core's GetDustThreshold: the cost at `DUST_RELAY_FEE` of creating and later spending the output
+fn dust_threshold(script_pubkey: &[u8]) -> u64 {
+    if script_pubkey.first() == Some(&OP_RETURN) {
+        return 0;
+    }
+    // value + script length (always one byte here) + script
+    let output_size = 8 + 1 + script_pubkey.len() as u64;
+    // prevout + sequence + scriptSig length, plus a 107-byte P2PKH-style scriptSig (witness discounted)
+    let spend_size = if is_witness_program(script_pubkey) {
+        32 + 4 + 1 + 107 / 4 + 4
+    } else {
+        32 + 4 + 1 + 107 + 4
+    };
+    (output_size + spend_size) * DUST_RELAY_FEE / 1000
+}
+
+/// OP_0..OP_16 followed by a single 2-40 byte push
+fn is_witness_program(script: &[u8]) -> bool {
+    let version_ok = script.first().is_some_and(|&op| op == 0x00 || (0x51..=0x60).contains(&op));
+    version_ok && (4..=42).contains(&script.len()) && script[1] as usize + 2 == script.len()
+}
+
+/// Bare `1-of-keys` multisig with compressed keys
+fn bare_multisig(keys: u8) -> Vec<u8> {
+    let mut script = vec![0x51];
+    for i in 0..keys {
+        script.push(33);
+        script.push(0x02);
+        script.extend_from_slice(&[0x60 + i; 32]);
+    }
+    script.push(0x50 + keys);
+    script.push(OP_CHECKMULTISIG);
+    script
+}
+
+/// OP_RETURN scriptPubKey exactly `size` bytes long
+fn op_return(size: usize) -> Vec<u8> {
+    // OP_RETURN <len> <payload>, or OP_RETURN OP_PUSHDATA1 <len> <payload> past a direct push
+    let direct = size - 2 < OP_PUSHDATA1 as usize;
+    let payload = if direct { size - 2 } else { size - 3 };
+    let mut script = vec![OP_RETURN];
+    if !direct {
+        script.push(OP_PUSHDATA1);
+    }
+    script.push(payload as u8);
+    script.extend(std::iter::repeat_n(0xab, payload));
+    debug_assert_eq!(script.len(), size);
+    script
+}
+
+/// One boundary-value transaction
+#[derive(Debug, Clone)]
+struct StandardnessCase {
+    rule: StandardnessRule,
+    name: String,
+    /// Whether Core 28's default policy relays it
+    expected_standard: bool,
+    tx: RawTx,
+}
+
+/// Spend `coin` into a P2WSH(OP_TRUE) change output plus the `extra` outputs under test
+fn probe_tx(coin: &Coin, version: u32, extra: Vec<(u64, Vec<u8>)>) -> RawTx {
+    let mut tx = op_true_spend_with(std::slice::from_ref(coin), 1, |_| 0, 0xffff_fffe);
+    tx.version = version;
+    let extra_value: u64 = extra.iter().map(|(value, _)| value).sum();
+    tx.outputs.extend(extra);
+    // The size doesn't depend on output values, so the change can be set after the fact
+    tx.outputs[0].0 = coin.value - extra_value - CASE_FEE_RATE * tx.vsize();
+    tx
+}
+
+/// Every boundary case, all spending `coin`
+fn generate_cases(coin: &Coin) -> Vec<StandardnessCase> {
+    let mut cases = Vec::new();
+    let mut push = |rule, name: String, expected_standard, tx| {
+        cases.push(StandardnessCase {
+            rule,
+            name,
+            expected_standard,
+            tx,
+        })
+    };
+
+    for script_type in ScriptType::ALL {
+        let script = script_type.script_pubkey();
+        let threshold = dust_threshold(&script);
+        let boundary = [("below", threshold - 1, false), ("at", threshold, true), ("above", threshold + 1, true)];
+        for (label, value, standard) in boundary {
+            let name = format!("{}-{}-dust ({} sat)", script_type.name(), label, value);
+            push(StandardnessRule::Dust, name, standard, probe_tx(coin, 2, vec![(value, script.clone())]));
+        }
+    }
+
+    for keys in [3u8, 4] {
+        let script = bare_multisig(keys);
+        let value = dust_threshold(&script);
+        let name = format!("bare-multisig-1-of-{}", keys);
+        push(StandardnessRule::BareMultisig, name, keys <= 3, probe_tx(coin, 2, vec![(value, script)]));
+    }
+
+    for size in [MAX_OP_RETURN_RELAY, MAX_OP_RETURN_RELAY + 1] {
+        let name = format!("op-return-{}-bytes", size);
+        let standard = size <= MAX_OP_RETURN_RELAY;
+        push(StandardnessRule::DataCarrier, name, standard, probe_tx(coin, 2, vec![(0, op_return(size))]));
+    }
+    let two = vec![(0, op_return(10)), (0, op_return(10))];
+    push(StandardnessRule::DataCarrier, "two-op-returns".to_string(), false, probe_tx(coin, 2, two));
+
+    for version in 0u32..=4 {
+        let name = format!("version-{}", version);
+        push(StandardnessRule::Version, name, (1..=3).contains(&version), probe_tx(coin, version, Vec::new()));
+    }
+    cases
+}
+
+/// One side's standardness verdict
+#[derive(Debug, Clone, Serialize)]
+pub struct StandardnessVerdict {
+    pub standard: bool,
+    pub reason: Option<String>,
+}
+
+fn blvm_standardness_verdict(tx: &RawTx) -> StandardnessVerdict {
+    let nonstandard = |reason: String| StandardnessVerdict {
+        standard: false,
+        reason: Some(reason),
+    };
+    let (tx, _) = match decode_tx(&hex::encode(tx.to_bytes())) {
+        Ok(decoded) => decoded,
+        Err(e) => return nonstandard(format!("decode failed: {}", e)),
+    };
+    match is_standard_tx(&tx) {
+        Ok(true) => StandardnessVerdict {
+            standard: true,
+            reason: None,
+        },
+        Ok(false) => nonstandard("is_standard_tx returned false".to_string()),
+        Err(e) => nonstandard(format!("error: {}", e)),
+    }
+}
+
+async fn core_standardness_verdict(client: &CoreRpcClient, tx: &RawTx) -> Result<StandardnessVerdict> {
+    let result = client.testmempoolaccept(&hex::encode(tx.to_bytes())).await?;
+    Ok(StandardnessVerdict {
+        standard: result.allowed,
+        reason: result.reject_reason,
+    })
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct StandardnessOutcome {
+    pub rule: StandardnessRule,
+    pub case: String,
+    pub expected_standard: bool,
+    pub blvm: StandardnessVerdict,
+    pub core: StandardnessVerdict,
+}
+
+impl StandardnessOutcome {
+    pub fn consistent(&self) -> bool {
+        self.blvm.standard == self.core.standard
+    }
+
+    fn print(&self) {
+        let describe = |v: &StandardnessVerdict| {
+            if v.standard {
+                "standard".to_string()
+            } else {
+                format!("rejected ({})", v.reason.as_deref().unwrap_or("no reason"))
+            }
+        };
+        let icon = if self.consistent() { "✅" } else { "❌" };
+        println!(
+            "{} {:<32} BLVM={}, Core={}",
+            icon,
+            self.case,
+            describe(&self.blvm),
+            describe(&self.core)
+        );
+        if self.core.standard != self.expected_standard {
+            println!("      Core's verdict differs from the default policy this case targets (non-default node flags?)");
+        }
+    }
+}
+
+/// Per-rule tally of cases and disagreements
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct RuleSummary {
+    pub cases: usize,
+    pub diverged: Vec<String>,
+}
+
+/// Group outcomes by rule
+pub fn summarize_by_rule(outcomes: &[StandardnessOutcome]) -> BTreeMap<StandardnessRule, RuleSummary> {
+    let mut summary: BTreeMap<StandardnessRule, RuleSummary> = BTreeMap::new();
+    for outcome in outcomes {
+        let entry = summary.entry(outcome.rule).or_default();
+        entry.cases += 1;
+        if !outcome.consistent() {
+            entry.diverged.push(outcome.case.clone());
+        }
+    }
+    summary
+}
+
+/// Run every boundary case through `is_standard_tx` and Core's `testmempoolaccept`
+pub async fn run_standardness_differential(client: &CoreRpcClient) -> Result<Vec<StandardnessOutcome>> {
+    if client.detect_network().await? != BitcoinNetwork::Regtest {
+        anyhow::bail!("Standardness cases need a regtest node (they mine a coin to spend)");
+    }
+    let coin = fund_op_true_coins(client, 1)
+        .await?
+        .pop()
+        .ok_or_else(|| anyhow::anyhow!("No coin was funded"))?;
+
+    let mut outcomes = Vec::new();
+    for case in generate_cases(&coin) {
+        let outcome = StandardnessOutcome {
+            rule: case.rule,
+            case: case.name,
+            expected_standard: case.expected_standard,
+            blvm: blvm_standardness_verdict(&case.tx),
+            core: core_standardness_verdict(client, &case.tx).await?,
+        };
+        outcome.print();
+        outcomes.push(outcome);
+    }
+
+    println!("\n📏 Standardness differential by rule:");
+    for (rule, summary) in summarize_by_rule(&outcomes) {
+        if summary.diverged.is_empty() {
+            println!("   ✅ {:<14} {} case(s) agree", rule.name(), summary.cases);
+        } else {
+            println!(
+                "   ❌ {:<14} policy differs in {}/{}: {}",
+                rule.name(),
+                summary.diverged.len(),
+                summary.cases,
+                summary.diverged.join(", ")
+            );
+        }
+    }
+    Ok(outcomes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dust_thresholds_match_core() {
+        let threshold = |t: ScriptType| dust_threshold(&t.script_pubkey());
+        assert_eq!(threshold(ScriptType::P2pkh), 546);
+        assert_eq!(threshold(ScriptType::P2sh), 540);
+        assert_eq!(threshold(ScriptType::P2wpkh), 294);
+        assert_eq!(threshold(ScriptType::P2wsh), 330);
+        assert_eq!(threshold(ScriptType::P2tr), 330);
+        assert_eq!(threshold(ScriptType::P2a), 240);
+        assert_eq!(dust_threshold(&op_return(40)), 0);
+    }
+
+    #[test]
+    fn test_generated_cases_hit_boundaries() {
+        let coin = Coin {
+            prevout: [1; 36],
+            value: 50_000_000,
+            height: 1,
+            is_coinbase: true,
+        };
+        let cases = generate_cases(&coin);
+        assert_eq!(cases.iter().filter(|c| c.rule == StandardnessRule::Dust).count(), 18);
+        for case in &cases {
+            let fee = coin.value - case.tx.outputs.iter().map(|(value, _)| value).sum::<u64>();
+            assert_eq!(fee, CASE_FEE_RATE * case.tx.vsize(), "{}", case.name);
+        }
+        let op_returns: Vec<usize> = cases
+            .iter()
+            .filter(|c| c.rule == StandardnessRule::DataCarrier)
+            .map(|c| c.tx.outputs[1].1.len())
+            .collect();
+        assert_eq!(op_returns, vec![83, 84, 10]);
+        assert_eq!(bare_multisig(3).len(), 105);
+    }
+}
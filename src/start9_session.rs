@@ -0,0 +1,253 @@
+//! Start9 RPC Session
+//!
+//! On Start9 the node's RPC port is only reachable inside its container, so
+//! `Start9RpcClient` enters the container's namespaces with `nsenter` for every
+//! call - one privileged process spawn per block, which caps throughput at a
+//! few blocks per second. A `Start9Session` spawns a single long-lived shell
+//! inside the namespaces instead and pipes JSON-RPC batches through it (curl to
+//! the in-container RPC port), so a batch of blocks costs two round trips
+//! (`getblockhash` then `getblock`) and no extra `nsenter`.
+//!
+//! Configuration:
+//! - `BLVM_START9_SHELL` - command that opens the shell (default: `nsenter --target <pid> --mount --net -- sh`)
+//! - `BLVM_START9_PID` - bitcoind's host PID for the default shell (default: `pidof bitcoind`)
+//! - `BLVM_START9_RPC_URL` - RPC URL inside the container (default: `http://127.0.0.1:8332/`)
+//! - `BLVM_START9_COOKIE` - cookie file inside the container (default: `/root/.bitcoin/.cookie`)
+//! - `BLVM_START9_BATCH` - blocks per batch (default: 16)
+//!
+//! `nsenter` needs root on the host; `container_rpc_client` opens the same
+//! shell with `podman exec`/`docker exec` instead.
+
+use anyhow::{Context, Result};
+use serde_json::Value;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::process::{Child, ChildStdin, ChildStdout, Command};
+
+/// Blocks fetched per batch by default
+pub const DEFAULT_BATCH_SIZE: usize = 16;
+
+/// How to reach the node inside the Start9 container
+#[derive(Debug, Clone)]
+pub struct Start9Config {
+    /// Program and arguments that open a shell in the container's namespaces
+    pub shell: Vec<String>,
+    pub rpc_url: String,
+    pub cookie_file: String,
+    pub batch_size: usize,
+}
+
+impl Start9Config {
+    pub fn from_env() -> Result<Self> {
+        let shell = match std::env::var("BLVM_START9_SHELL") {
+            Ok(command) => command.split_whitespace().map(String::from).collect(),
+            Err(_) => {
+                let pid = match std::env::var("BLVM_START9_PID") {
+                    Ok(pid) => pid,
+                    Err(_) => bitcoind_pid()?,
+                };
+                ["nsenter", "--target", &pid, "--mount", "--net", "--", "sh"].map(String::from).to_vec()
+            }
+        };
+        Self::with_shell(shell)
+    }
+
+    /// Configuration from the environment, opening the shell with `shell` (see `container_rpc_client`)
+    pub fn with_shell(shell: Vec<String>) -> Result<Self> {
+        let batch_size = match std::env::var("BLVM_START9_BATCH") {
+            Ok(n) => n.parse().with_context(|| format!("Invalid BLVM_START9_BATCH '{}'", n))?,
+            Err(_) => DEFAULT_BATCH_SIZE,
+        };
+        let config = Self {
+            shell,
+            rpc_url: std::env::var("BLVM_START9_RPC_URL").unwrap_or_else(|_| "http://127.0.0.1:8332/".to_string()),
+            cookie_file: std::env::var("BLVM_START9_COOKIE").unwrap_or_else(|_| "/root/.bitcoin/.cookie".to_string()),
+            batch_size: batch_size.max(1),
+        };
+        if config.shell.is_empty() {
+            anyhow::bail!("BLVM_START9_SHELL is empty");
+        }
+        // Both are interpolated into single-quoted shell words
+        if config.rpc_url.contains('\'') || config.cookie_file.contains('\'') {
+            anyhow::bail!("BLVM_START9_RPC_URL and BLVM_START9_COOKIE can't contain single quotes");
+        }
+        Ok(config)
+    }
+}
+
+/// Host PID of the container's bitcoind
+fn bitcoind_pid() -> Result<String> {
+    let output = std::process::Command::new("pidof")
+        .arg("bitcoind")
+        .output()
+        .context("Failed to run pidof (set BLVM_START9_PID)")?;
+    String::from_utf8_lossy(&output.stdout)
+        .split_whitespace()
+        .next()
+        .map(String::from)
+        .context("bitcoind is not running (set BLVM_START9_PID or BLVM_START9_SHELL)")
+}
+
+/// The long-lived shell inside the container
+struct Shell {
+    child: Child,
+    stdin: ChildStdin,
+    stdout: BufReader<ChildStdout>,
+}
+
+/// JSON-RPC over one persistent shell in the Start9 container
+pub struct Start9Session {
+    config: Start9Config,
+    /// Marks the end of each command's output
+    sentinel: String,
+    /// Spawned on first use, and again after an I/O error
+    shell: tokio::sync::Mutex<Option<Shell>>,
+}
+
+impl Start9Session {
+    pub fn new(config: Start9Config) -> Self {
+        Self {
+            config,
+            sentinel: format!("__BLVM_DONE_{}__", std::process::id()),
+            shell: tokio::sync::Mutex::new(None),
+        }
+    }
+
+    pub fn from_env() -> Result<Self> {
+        Ok(Self::new(Start9Config::from_env()?))
+    }
+
+    pub fn batch_size(&self) -> usize {
+        self.config.batch_size
+    }
+
+    fn spawn_shell(&self) -> Result<Shell> {
+        let mut child = Command::new(&self.config.shell[0])
+            .args(&self.config.shell[1..])
+            .stdin(std::process::Stdio::piped())
+            .stdout(std::process::Stdio::piped())
+            .kill_on_drop(true)
+            .spawn()
+            .with_context(|| format!("Failed to start Start9 shell `{}`", self.config.shell.join(" ")))?;
+        let stdin = child.stdin.take().context("Start9 shell has no stdin")?;
+        let stdout = BufReader::new(child.stdout.take().context("Start9 shell has no stdout")?);
+        Ok(Shell { child, stdin, stdout })
+    }
+
+    /// POST `body` to the node from inside the container; returns the response body
+    async fn post(&self, body: &str) -> Result<String> {
+        let script = format!(
+            "curl -sS --user \"$(cat '{cookie}')\" -H 'content-type: text/plain;' --data-binary @- '{url}' 2>&1 <<'__BLVM_BODY__'\n{body}\n__BLVM_BODY__\nstatus=$?\necho\necho \"{sentinel} $status\"\n",
+            cookie = self.config.cookie_file,
+            url = self.config.rpc_url,
+            body = body,
+            sentinel = self.sentinel,
+        );
+        let mut guard = self.shell.lock().await;
+        if guard.is_none() {
+            *guard = Some(self.spawn_shell()?);
+        }
+        let shell = guard.as_mut().expect("shell just spawned");
+        let result = Self::exchange(shell, &script, &self.sentinel).await;
+        if result.is_err() {
+            // The shell is in an unknown state; start a fresh one next time
+            if let Some(mut shell) = guard.take() {
+                let _ = shell.child.start_kill();
+            }
+        }
+        result
+    }
+
+    async fn exchange(shell: &mut Shell, script: &str, sentinel: &str) -> Result<String> {
+        shell.stdin.write_all(script.as_bytes()).await.context("Start9 shell closed its input")?;
+        shell.stdin.flush().await?;
+        let mut output = String::new();
+        loop {
+            let mut line = String::new();
+            if shell.stdout.read_line(&mut line).await? == 0 {
+                anyhow::bail!("Start9 shell exited (output so far: {})", output.trim());
+            }
+            if let Some(status) = line.strip_prefix(sentinel) {
+                return match status.trim() {
+                    "0" => Ok(output),
+                    code => anyhow::bail!("curl in the Start9 container exited with {}: {}", code, output.trim()),
+                };
+            }
+            output.push_str(&line);
+        }
+    }
+
+    /// Send `calls` as one JSON-RPC batch; results in call order
+    pub async fn call_batch(&self, calls: &[(&str, Value)]) -> Result<Vec<Value>> {
+        if calls.is_empty() {
+            return Ok(Vec::new());
+        }
+        let body: Vec<Value> = calls
+            .iter()
+            .enumerate()
+            .map(|(id, (method, params))| {
+                serde_json::json!({ "jsonrpc": "1.0", "id": id, "method": method, "params": params })
+            })
+            .collect();
+        let response = self.post(&serde_json::to_string(&body)?).await?;
+        let mut replies: Vec<Value> = serde_json::from_str(response.trim())
+            .with_context(|| format!("Unexpected Start9 RPC response: {:.200}", response.trim()))?;
+        if replies.len() != calls.len() {
+            anyhow::bail!("Start9 RPC batch of {} returned {} replies", calls.len(), replies.len());
+        }
+        replies.sort_by_key(|reply| reply.get("id").and_then(Value::as_u64).unwrap_or(u64::MAX));
+        replies
+            .into_iter()
+            .zip(calls)
+            .map(|(mut reply, (method, _))| match reply.get("error").filter(|e| !e.is_null()) {
+                Some(error) => anyhow::bail!("RPC {} failed: {}", method, error),
+                None => Ok(reply["result"].take()),
+            })
+            .collect()
+    }
+
+    async fn call(&self, method: &str, params: Value) -> Result<Value> {
+        let mut results = self.call_batch(&[(method, params)]).await?;
+        Ok(results.remove(0))
+    }
+
+    pub async fn get_block_count(&self) -> Result<u64> {
+        self.call("getblockcount", serde_json::json!([])).await?.as_u64().context("Invalid getblockcount response")
+    }
+
+    pub async fn get_block_hash(&self, height: u64) -> Result<String> {
+        let hash = self.call("getblockhash", serde_json::json!([height])).await?;
+        hash.as_str().map(String::from).context("Invalid getblockhash response")
+    }
+
+    pub async fn get_block_hex(&self, block_hash: &str) -> Result<String> {
+        let block = self.call("getblock", serde_json::json!([block_hash, 0])).await?;
+        block.as_str().map(String::from).context("Invalid getblock response")
+    }
+
+    /// Whether the block is in the node's active chain (header only, no block transfer)
+    pub async fn block_in_chain(&self, block_hash: &str) -> Result<bool> {
+        let header = self.call("getblockheader", serde_json::json!([block_hash, true])).await?;
+        Ok(header.get("confirmations").and_then(Value::as_i64).is_some_and(|c| c >= 0))
+    }
+
+    /// Raw blocks `start_height..=end_height` in two batched round trips
+    pub async fn get_blocks(&self, start_height: u64, end_height: u64) -> Result<Vec<Vec<u8>>> {
+        let hash_calls: Vec<(&str, Value)> = (start_height..=end_height)
+            .map(|height| ("getblockhash", serde_json::json!([height])))
+            .collect();
+        let hashes = self.call_batch(&hash_calls).await?;
+        let block_calls: Vec<(&str, Value)> = hashes
+            .into_iter()
+            .map(|hash| ("getblock", serde_json::json!([hash, 0])))
+            .collect();
+        self.call_batch(&block_calls)
+            .await?
+            .into_iter()
+            .zip(start_height..)
+            .map(|(block, height)| {
+                let hex_block = block.as_str().with_context(|| format!("Invalid getblock response for height {}", height))?;
+                Ok(hex::decode(hex_block)?)
+            })
+            .collect()
+    }
+}
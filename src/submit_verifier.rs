@@ -0,0 +1,120 @@
+//! Core Verification via submitblock
+//!
+//! By default a block is "valid for Core" if Core has it in its chain, which
+//! can't catch blocks Core would reject. In submit mode every block is also
+//! handed to a secondary, validation-only Core node with `submitblock`, and
+//! its verdict (BIP22 result) is what BLVM is compared against.
+//!
+//! The node must be isolated so it only ever sees the blocks we feed it:
+//!
+//! ```text
+//! bitcoind -datadir=/tmp/blvm-verify -connect=0 -listen=0 -dnsseed=0 -rpcport=18832 -rpcuser=u -rpcpassword=p
+//! BLVM_VERIFY_NODE=http://u:p@127.0.0.1:18832
+//! ```
+//!
+//! It needs every block in height order, so a run with a verify node
+//! validates its chunks one at a time and must start at the node's tip + 1.
+
+use anyhow::{Context, Result};
+use std::sync::Arc;
+
+use crate::core_rpc_client::{CoreRpcClient, SubmitBlockResult};
+use crate::differential::CoreValidationResult;
+
+/// A validation-only Core node blocks are submitted to
+#[derive(Clone)]
+pub struct SubmitVerifier {
+    client: Arc<CoreRpcClient>,
+}
+
+impl std::fmt::Debug for SubmitVerifier {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SubmitVerifier").field("url", &self.client.url()).finish()
+    }
+}
+
+impl SubmitVerifier {
+    pub fn new(client: Arc<CoreRpcClient>) -> Self {
+        Self { client }
+    }
+
+    /// Verify node from `BLVM_VERIFY_NODE` (None if unset)
+    pub fn from_env() -> Result<Option<Self>> {
+        let Ok(url) = std::env::var("BLVM_VERIFY_NODE") else {
+            return Ok(None);
+        };
+        let config = crate::core_versions::parse_rpc_url(&url).context("Invalid BLVM_VERIFY_NODE")?;
+        Ok(Some(Self::new(Arc::new(CoreRpcClient::new(config)))))
+    }
+
+    pub fn url(&self) -> &str {
+        self.client.url()
+    }
+
+    /// Fail early unless the node's tip is right below `start_height`
+    pub async fn check_tip(&self, start_height: u64) -> Result<()> {
+        let tip = self
+            .client
+            .getblockcount()
+            .await
+            .with_context(|| format!("Verify node {} is unreachable", self.url()))?;
+        if tip + 1 != start_height {
+            anyhow::bail!(
+                "Verify node is at height {}, but the run starts at {} (start at {} or use a node synced to {})",
+                tip,
+                start_height,
+                tip + 1,
+                start_height.saturating_sub(1)
+            );
+        }
+        Ok(())
+    }
+
+    /// Submit a block and return Core's verdict on it
+    pub async fn verdict(&self, height: u64, block_bytes: &[u8]) -> Result<CoreValidationResult> {
+        let result = self
+            .client
+            .submitblock(&hex::encode(block_bytes))
+            .await
+            .with_context(|| format!("submitblock failed for block {}", height))?;
+        if result.error.as_deref() == Some("prev-blk-not-found") {
+            anyhow::bail!("Verify node doesn't have block {}'s parent (blocks must be submitted in order)", height);
+        }
+        Ok(classify(&result))
+    }
+}
+
+/// Map a BIP22 submitblock result to a validity verdict
+fn classify(result: &SubmitBlockResult) -> CoreValidationResult {
+    match result.error.as_deref() {
+        None if result.accepted => CoreValidationResult::Valid,
+        // Resubmitting a block the node already connected (e.g. a rerun chunk)
+        Some("duplicate") => CoreValidationResult::Valid,
+        // Stored but not connected to the node's best chain
+        Some("inconclusive") | Some("duplicate-inconclusive") => {
+            CoreValidationResult::Invalid("Not connected to the verify node's tip (inconclusive)".to_string())
+        }
+        Some(reason) => CoreValidationResult::Invalid(reason.to_string()),
+        None => CoreValidationResult::Invalid("rejected".to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_classify() {
+        let result = |accepted: bool, error: Option<&str>| SubmitBlockResult {
+            accepted,
+            error: error.map(str::to_string),
+        };
+        assert!(matches!(classify(&result(true, None)), CoreValidationResult::Valid));
+        assert!(matches!(classify(&result(false, Some("duplicate"))), CoreValidationResult::Valid));
+        assert!(matches!(
+            classify(&result(false, Some("bad-txns-inputs-missingorspent"))),
+            CoreValidationResult::Invalid(reason) if reason == "bad-txns-inputs-missingorspent"
+        ));
+        assert!(matches!(classify(&result(false, Some("inconclusive"))), CoreValidationResult::Invalid(_)));
+    }
+}
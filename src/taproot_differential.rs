@@ -0,0 +1,509 @@
+//! Taproot Differential
+//!
+//! Taproot spends only show up in mainnet blocks after activation (709632) and
+//! the generic runs give them no dedicated coverage. This adds:
+//!
+//! - a built-in corpus of taproot-heavy mainnet blocks (first spends,
+//!   inscriptions with huge script-path witnesses, the runes halving block),
+//!   kept as block + pre-state fixtures in `benches/fixtures/taproot` and
+//!   connected with BLVM, with every P2TR input classified (key path, script
+//!   path, annex, control block depth)
+//! - synthetic regtest spends for the edges mainnet barely has: annexes, a
+//!   control block at the 128-deep maximum and one past it, a wrong parity
+//!   bit. Each spend is added to a freshly mined block and BLVM's verdict is
+//!   compared with Core's proposal-mode verdict.
+
+use anyhow::{Context, Result};
+use blvm_consensus::block::{calculate_tx_id, connect_block};
+use blvm_consensus::serialization::block::deserialize_block_with_witnesses;
+use blvm_consensus::types::{Network, ValidationResult};
+use blvm_consensus::{tx_inputs, OutPoint, Transaction, TransactionInput, TransactionOutput, UtxoSet, UTXO};
+use secp256k1::{Keypair, Secp256k1};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use crate::bench_fixtures::{
+    push_data, schnorr_sig, secret_key, serialize_tx, tagged_hash, tapleaf_hash, taptweak, SighashMidstate,
+};
+use crate::core_rpc_client::{BitcoinNetwork, CoreRpcClient};
+use crate::mutation_differential::{append_tx, core_verdict, rewind_to_parent, CoreVerdict};
+
+/// Taproot-heavy mainnet blocks: (name, height)
+pub const TAPROOT_BLOCKS: &[(&str, u64)] = &[
+    // Activation block
+    ("taproot_activation", 709_632),
+    // First P2TR spends (key path and script path)
+    ("taproot_first_spends", 709_635),
+    // First ordinal inscription - script-path envelope
+    ("inscription_0", 767_430),
+    // ~4MB inscription in a single script-path witness
+    ("taproot_wizards", 774_628),
+    // Runes launch at the fourth halving
+    ("runes_halving", 840_000),
+];
+
+/// Leaf version of BIP342 tapscript
+const TAPSCRIPT_LEAF: u8 = 0xc0;
+/// BIP341: first byte of an annex
+const ANNEX_TAG: u8 = 0x50;
+/// BIP341: deepest allowed script path
+const TAPROOT_CONTROL_MAX_NODE_COUNT: usize = 128;
+/// Fee of each synthetic spend (sats)
+const SPEND_FEE: i64 = 1_000;
+/// Coinbase maturity
+const COINBASE_MATURITY: u64 = 100;
+
+/// Default taproot fixture directory: `BLVM_TAPROOT_FIXTURES` or `benches/fixtures/taproot`
+pub fn default_taproot_dir() -> PathBuf {
+    std::env::var("BLVM_TAPROOT_FIXTURES")
+        .map(PathBuf::from)
+        .unwrap_or_else(|_| PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("benches/fixtures/taproot"))
+}
+
+// ---------------------------------------------------------------------------
+// Mainnet corpus
+// ---------------------------------------------------------------------------
+
+/// P2TR input counts of a block
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TaprootStats {
+    pub key_path: usize,
+    pub script_path: usize,
+    pub with_annex: usize,
+    /// Deepest control block seen (merkle path length)
+    pub max_depth: usize,
+    /// Largest script-path witness script, in bytes
+    pub max_script_len: usize,
+}
+
+impl TaprootStats {
+    /// Classify one P2TR input by its witness stack
+    fn add_input(&mut self, witness: &[Vec<u8>]) {
+        let mut stack = witness;
+        if stack.len() >= 2 && stack.last().and_then(|a| a.first()) == Some(&ANNEX_TAG) {
+            self.with_annex += 1;
+            stack = &stack[..stack.len() - 1];
+        }
+        match stack {
+            [] => {}
+            [_signature] => self.key_path += 1,
+            [.., script, control] => {
+                self.script_path += 1;
+                self.max_depth = self.max_depth.max(control.len().saturating_sub(33) / 32);
+                self.max_script_len = self.max_script_len.max(script.len());
+            }
+        }
+    }
+}
+
+fn is_p2tr(script_pubkey: &[u8]) -> bool {
+    script_pubkey.len() == 34 && script_pubkey[0] == 0x51 && script_pubkey[1] == 0x20
+}
+
+/// Classify every P2TR input of a block (prevouts from `pre_state` or the block itself)
+///
+/// Works on the raw wire format so witness stacks are kept per input.
+pub fn taproot_stats(block_bytes: &[u8], pre_state: &UtxoSet) -> Result<TaprootStats> {
+    let mut created: HashMap<OutPoint, Vec<u8>> = HashMap::new();
+    let mut stats = TaprootStats::default();
+    for tx in crate::mutation_differential::raw_transactions(block_bytes)? {
+        for input in &tx.inputs {
+            let prevout = OutPoint {
+                hash: input.prevout[..32].try_into()?,
+                index: u32::from_le_bytes(input.prevout[32..].try_into()?) as _,
+            };
+            let script = created
+                .get(&prevout)
+                .cloned()
+                .or_else(|| pre_state.get(&prevout).map(|utxo| utxo.script_pubkey.to_vec()));
+            if script.as_deref().is_some_and(is_p2tr) {
+                stats.add_input(&input.witness);
+            }
+        }
+        let txid = tx.txid();
+        for (vout, (_, script_pubkey)) in tx.outputs.iter().enumerate() {
+            created.insert(OutPoint { hash: txid, index: vout as _ }, script_pubkey.clone());
+        }
+    }
+    Ok(stats)
+}
+
+/// One corpus block and BLVM's verdict on it
+#[derive(Debug, Clone)]
+pub struct CorpusOutcome {
+    pub name: &'static str,
+    pub height: u64,
+    pub stats: TaprootStats,
+    /// BLVM's rejection reason (None = connected, as Core did)
+    pub blvm_rejection: Option<String>,
+}
+
+/// Connect every corpus block, building missing fixtures from `client` if given
+pub async fn run_taproot_corpus(dir: &Path, client: Option<&CoreRpcClient>) -> Result<Vec<CorpusOutcome>> {
+    use crate::block_fixtures::{build_fixture, load_fixture};
+
+    let mut outcomes = Vec::new();
+    for (name, height) in TAPROOT_BLOCKS {
+        let fixture = match (load_fixture(dir, name, *height)?, client) {
+            (Some(fixture), _) => fixture,
+            (None, Some(client)) => {
+                build_fixture(client, dir, name, *height).await?;
+                load_fixture(dir, name, *height)?.context("Fixture missing right after building it")?
+            }
+            (None, None) => {
+                eprintln!("⚠️  Skipping {}: no fixture in {} (pass a Core node to build it)", name, dir.display());
+                continue;
+            }
+        };
+        let stats = taproot_stats(&fixture.block_bytes, &fixture.pre_state)?;
+        let blvm_rejection = blvm_verdict(&fixture.block_bytes, fixture.pre_state.clone(), *height, Network::Mainnet).err();
+        println!(
+            "{} {} (height {}): {} key-path, {} script-path, {} annex, depth <= {}, script <= {} bytes{}",
+            if blvm_rejection.is_none() { "✅" } else { "❌" },
+            name,
+            height,
+            stats.key_path,
+            stats.script_path,
+            stats.with_annex,
+            stats.max_depth,
+            stats.max_script_len,
+            blvm_rejection.as_deref().map(|r| format!(" - BLVM rejects: {}", r)).unwrap_or_default()
+        );
+        outcomes.push(CorpusOutcome {
+            name,
+            height: *height,
+            stats,
+            blvm_rejection,
+        });
+    }
+    if outcomes.is_empty() {
+        anyhow::bail!("No taproot fixtures in {} and no Core node to build them", dir.display());
+    }
+    Ok(outcomes)
+}
+
+fn blvm_verdict(block_bytes: &[u8], pre_state: UtxoSet, height: u64, network: Network) -> Result<(), String> {
+    let (block, witnesses) =
+        deserialize_block_with_witnesses(block_bytes).map_err(|e| format!("deserialize: {}", e))?;
+    match connect_block(&block, &witnesses, pre_state, height, None, network) {
+        Ok((ValidationResult::Valid, _, _)) => Ok(()),
+        Ok((ValidationResult::Invalid(msg), _, _)) => Err(msg),
+        Err(e) => Err(format!("{:?}", e)),
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Synthetic regtest spends
+// ---------------------------------------------------------------------------
+
+/// A synthetic taproot spend
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TaprootScenario {
+    KeyPath,
+    /// Key path with an annex the signature commits to
+    KeyPathAnnex,
+    /// Annex present but the signature was made without it
+    KeyPathAnnexUncommitted,
+    ScriptPath,
+    /// Script path with an annex
+    ScriptPathAnnex,
+    /// Leaf at the maximum depth (128 nodes, 4129-byte control block)
+    MaxDepth,
+    /// One node deeper than allowed
+    OverMaxDepth,
+    /// Control block with the output key's parity bit flipped
+    WrongParity,
+}
+
+impl TaprootScenario {
+    pub const ALL: [TaprootScenario; 8] = [
+        TaprootScenario::KeyPath,
+        TaprootScenario::KeyPathAnnex,
+        TaprootScenario::KeyPathAnnexUncommitted,
+        TaprootScenario::ScriptPath,
+        TaprootScenario::ScriptPathAnnex,
+        TaprootScenario::MaxDepth,
+        TaprootScenario::OverMaxDepth,
+        TaprootScenario::WrongParity,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            TaprootScenario::KeyPath => "key-path",
+            TaprootScenario::KeyPathAnnex => "key-path-annex",
+            TaprootScenario::KeyPathAnnexUncommitted => "key-path-annex-uncommitted",
+            TaprootScenario::ScriptPath => "script-path",
+            TaprootScenario::ScriptPathAnnex => "script-path-annex",
+            TaprootScenario::MaxDepth => "script-path-depth-128",
+            TaprootScenario::OverMaxDepth => "script-path-depth-129",
+            TaprootScenario::WrongParity => "script-path-wrong-parity",
+        }
+    }
+
+    pub fn expect_valid(&self) -> bool {
+        !matches!(
+            self,
+            TaprootScenario::KeyPathAnnexUncommitted | TaprootScenario::OverMaxDepth | TaprootScenario::WrongParity
+        )
+    }
+
+    /// Merkle path length of the spent leaf (None = key path)
+    fn depth(&self) -> Option<usize> {
+        match self {
+            TaprootScenario::KeyPath | TaprootScenario::KeyPathAnnex | TaprootScenario::KeyPathAnnexUncommitted => None,
+            TaprootScenario::ScriptPath | TaprootScenario::ScriptPathAnnex | TaprootScenario::WrongParity => Some(1),
+            TaprootScenario::MaxDepth => Some(TAPROOT_CONTROL_MAX_NODE_COUNT),
+            TaprootScenario::OverMaxDepth => Some(TAPROOT_CONTROL_MAX_NODE_COUNT + 1),
+        }
+    }
+
+    fn annex(&self) -> Option<Vec<u8>> {
+        matches!(
+            self,
+            TaprootScenario::KeyPathAnnex | TaprootScenario::KeyPathAnnexUncommitted | TaprootScenario::ScriptPathAnnex
+        )
+        .then(|| vec![ANNEX_TAG, 0x01, 0x02, 0x03])
+    }
+}
+
+/// Keys and tree behind one scenario's output
+struct TaprootOutput {
+    internal: Keypair,
+    leaf_key: Keypair,
+    leaf_script: Vec<u8>,
+    /// Sibling hashes from the leaf up to the root (empty for key path)
+    path: Vec<[u8; 32]>,
+    parity: u8,
+    script_pubkey: Vec<u8>,
+}
+
+fn tap_branch(a: [u8; 32], b: [u8; 32]) -> [u8; 32] {
+    let (lo, hi) = if a <= b { (a, b) } else { (b, a) };
+    tagged_hash("TapBranch", &[lo, hi].concat())
+}
+
+impl TaprootOutput {
+    fn new(scenario: TaprootScenario, seed: u64) -> Self {
+        let secp = Secp256k1::new();
+        let internal = Keypair::from_secret_key(&secp, &secret_key(seed * 2));
+        let leaf_key = Keypair::from_secret_key(&secp, &secret_key(seed * 2 + 1));
+        let mut leaf_script = Vec::new();
+        push_data(&mut leaf_script, &leaf_key.x_only_public_key().0.serialize());
+        leaf_script.push(0xac); // OP_CHECKSIG
+
+        let path: Vec<[u8; 32]> = (0..scenario.depth().unwrap_or(0) as u64)
+            .map(|i| tagged_hash("TapLeaf", &((seed << 16) | i).to_le_bytes()))
+            .collect();
+        let merkle_root = scenario
+            .depth()
+            .map(|_| path.iter().fold(tapleaf_hash(&leaf_script), |node, sibling| tap_branch(node, *sibling)));
+        let (internal_x, _) = internal.x_only_public_key();
+        let (output_key, parity) = internal_x
+            .add_tweak(&secp, &taptweak(&internal_x, merkle_root))
+            .expect("valid taproot tweak");
+        let mut script_pubkey = vec![0x51];
+        push_data(&mut script_pubkey, &output_key.serialize());
+        Self {
+            internal,
+            leaf_key,
+            leaf_script,
+            path,
+            parity: parity.to_u8(),
+            script_pubkey,
+        }
+    }
+
+    /// Sign `tx` (single input spending `spent`) and return its witness stack
+    fn witness(&self, scenario: TaprootScenario, tx: &Transaction, spent: &TransactionOutput) -> Vec<Vec<u8>> {
+        let secp = Secp256k1::new();
+        let midstate = SighashMidstate::new(tx, std::slice::from_ref(spent));
+        let annex = scenario.annex();
+        // The uncommitted case signs as if there were no annex
+        let signed_annex = annex.as_deref().filter(|_| scenario != TaprootScenario::KeyPathAnnexUncommitted);
+        let mut stack = match scenario.depth() {
+            None => {
+                let (internal_x, _) = self.internal.x_only_public_key();
+                let keypair = self
+                    .internal
+                    .add_xonly_tweak(&secp, &taptweak(&internal_x, None))
+                    .expect("valid taproot tweak");
+                let sighash = midstate.bip341_with_annex(tx, 0, None, signed_annex);
+                vec![schnorr_sig(&secp, &sighash, &keypair)]
+            }
+            Some(_) => {
+                let leaf_hash = tapleaf_hash(&self.leaf_script);
+                let sighash = midstate.bip341_with_annex(tx, 0, Some(leaf_hash), signed_annex);
+                let parity = if scenario == TaprootScenario::WrongParity { self.parity ^ 1 } else { self.parity };
+                let mut control = vec![TAPSCRIPT_LEAF | parity];
+                control.extend_from_slice(&self.internal.x_only_public_key().0.serialize());
+                for sibling in &self.path {
+                    control.extend_from_slice(sibling);
+                }
+                vec![schnorr_sig(&secp, &sighash, &self.leaf_key), self.leaf_script.clone(), control]
+            }
+        };
+        stack.extend(annex);
+        stack
+    }
+}
+
+/// One synthetic spend and both verdicts on a block containing it
+#[derive(Debug, Clone)]
+pub struct ScenarioOutcome {
+    pub scenario: TaprootScenario,
+    /// BLVM's rejection reason (None = accepted)
+    pub blvm_rejection: Option<String>,
+    pub core: CoreVerdict,
+}
+
+impl ScenarioOutcome {
+    /// Both sides gave the verdict BIP341/342 call for
+    pub fn consistent(&self) -> bool {
+        let expect_valid = self.scenario.expect_valid();
+        matches!(self.core, CoreVerdict::Accepted) == expect_valid && self.blvm_rejection.is_none() == expect_valid
+    }
+
+    fn print(&self) {
+        let blvm = self.blvm_rejection.as_deref().unwrap_or("accepted");
+        let core = match &self.core {
+            CoreVerdict::Accepted => "accepted",
+            CoreVerdict::Rejected(reason) => reason,
+            CoreVerdict::NotChecked => "not checked",
+        };
+        let icon = if self.consistent() { "✅" } else { "❌" };
+        let expected = if self.scenario.expect_valid() { "valid" } else { "invalid" };
+        println!("{} {:<28} expected {}: BLVM={}, Core={}", icon, self.scenario.name(), expected, blvm, core);
+    }
+}
+
+/// Mine one coinbase per scenario to its taproot output, then diff a block spending each
+pub async fn run_taproot_scenarios(client: &CoreRpcClient) -> Result<Vec<ScenarioOutcome>> {
+    if client.detect_network().await? != BitcoinNetwork::Regtest {
+        anyhow::bail!("Taproot scenarios need a regtest node (they mine and invalidate blocks)");
+    }
+    // OP_TRUE: no wallet needed to mine filler blocks
+    let filler = "raw(51)";
+
+    let mut funded = Vec::new();
+    for (seed, scenario) in TaprootScenario::ALL.into_iter().enumerate() {
+        let output = TaprootOutput::new(scenario, seed as u64 + 1);
+        let descriptor = format!("raw({})", hex::encode(&output.script_pubkey));
+        let hash = client
+            .generatetodescriptor(1, &descriptor)
+            .await?
+            .pop()
+            .context("generatetodescriptor mined no block")?;
+        let bytes = hex::decode(client.getblock_raw(&hash).await?)?;
+        let (block, _) =
+            deserialize_block_with_witnesses(&bytes).map_err(|e| anyhow::anyhow!("Failed to deserialize block: {}", e))?;
+        let coinbase = block.transactions.first().context("Block has no coinbase")?;
+        let coin = OutPoint {
+            hash: calculate_tx_id(coinbase),
+            index: 0,
+        };
+        let spent = coinbase.outputs.first().cloned().context("Coinbase has no outputs")?;
+        funded.push((scenario, output, coin, spent, client.getblockcount().await?));
+    }
+    client.generatetodescriptor(COINBASE_MATURITY, filler).await?;
+
+    // The block every spend is added to; its parent becomes the tip
+    let hash = client
+        .generatetodescriptor(1, filler)
+        .await?
+        .pop()
+        .context("generatetodescriptor mined no block")?;
+    let height = client.getblockcount().await?;
+    let host_bytes = hex::decode(client.getblock_raw(&hash).await?)?;
+    let rewound = rewind_to_parent(client, &host_bytes).await?;
+
+    let mut outcomes = Vec::new();
+    for (scenario, output, coin, spent, coin_height) in funded {
+        let tx = Transaction {
+            version: 2,
+            inputs: tx_inputs![TransactionInput {
+                prevout: coin.clone(),
+                script_sig: Vec::new(),
+                sequence: 0xffff_fffd,
+            }],
+            outputs: vec![TransactionOutput {
+                value: (spent.value as i64 - SPEND_FEE) as _,
+                script_pubkey: vec![0x51],
+            }]
+            .into(),
+            lock_time: 0,
+        };
+        let witness = output.witness(scenario, &tx, &spent);
+        let block_bytes = append_tx(&host_bytes, &serialize_tx(&tx, Some(&witness)), true)?;
+
+        let mut pre_state = UtxoSet::new();
+        pre_state.insert(
+            coin,
+            UTXO {
+                value: spent.value as _,
+                script_pubkey: spent.script_pubkey.clone().into(),
+                height: coin_height as _,
+                is_coinbase: true,
+            },
+        );
+        let outcome = ScenarioOutcome {
+            scenario,
+            blvm_rejection: blvm_verdict(&block_bytes, pre_state, height, Network::Regtest).err(),
+            core: core_verdict(client, &block_bytes).await?,
+        };
+        outcome.print();
+        outcomes.push(outcome);
+    }
+
+    if let Some(hash) = rewound {
+        client.reconsiderblock(&hash).await?;
+    }
+    Ok(outcomes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_classify_witness() {
+        let mut stats = TaprootStats::default();
+        stats.add_input(&[vec![0; 64]]);
+        stats.add_input(&[vec![0; 64], vec![ANNEX_TAG, 1]]);
+        stats.add_input(&[vec![0; 64], vec![0xac; 40], vec![0xc0; 33 + 32 * 3]]);
+        assert_eq!(stats.key_path, 2);
+        assert_eq!(stats.with_annex, 1);
+        assert_eq!(stats.script_path, 1);
+        assert_eq!(stats.max_depth, 3);
+        assert_eq!(stats.max_script_len, 40);
+    }
+
+    #[test]
+    fn test_scenario_witness_shapes() {
+        let spent = |output: &TaprootOutput| TransactionOutput {
+            value: (SPEND_FEE * 50) as _,
+            script_pubkey: output.script_pubkey.clone(),
+        };
+        let tx = Transaction {
+            version: 2,
+            inputs: tx_inputs![TransactionInput {
+                prevout: OutPoint { hash: [1; 32], index: 0 },
+                script_sig: Vec::new(),
+                sequence: 0xffff_fffd,
+            }],
+            outputs: vec![TransactionOutput {
+                value: (SPEND_FEE * 49) as _,
+                script_pubkey: vec![0x51],
+            }]
+            .into(),
+            lock_time: 0,
+        };
+        for scenario in TaprootScenario::ALL {
+            let output = TaprootOutput::new(scenario, 7);
+            let witness = output.witness(scenario, &tx, &spent(&output));
+            let mut stats = TaprootStats::default();
+            stats.add_input(&witness);
+            assert_eq!(stats.with_annex, usize::from(scenario.annex().is_some()), "{}", scenario.name());
+            assert_eq!(stats.max_depth, scenario.depth().unwrap_or(0), "{}", scenario.name());
+        }
+    }
+}
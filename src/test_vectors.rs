@@ -0,0 +1,558 @@
+//! Core Test-Vector Runner
+//!
+//! Runs Bitcoin Core's published JSON test vectors through BLVM:
+//!
+//! - `script_tests.json` - scriptSig/scriptPubKey(/witness) pairs, evaluated in
+//!   Core's crediting/spending transaction pair
+//! - `tx_valid.json` / `tx_invalid.json` - whole transactions with their prevouts
+//! - `sighash.json` - legacy signature hashes
+//!
+//! The vectors are Core's own expected results, so no node is needed: every
+//! vector where BLVM disagrees is reported like a block-level divergence, with
+//! `<file>#<index>` in place of a height. Files are read from
+//! `BLVM_TEST_VECTORS` (default `benches/fixtures/test_vectors`, copied from
+//! Core's `src/test/data`); missing files are skipped.
+
+use anyhow::{Context, Result};
+use blvm_consensus::block::calculate_tx_id;
+use blvm_consensus::script::verify_script_with_context;
+use blvm_consensus::segwit::Witness;
+use blvm_consensus::transaction::check_transaction;
+use blvm_consensus::transaction_hash::{calculate_transaction_sighash, SighashType};
+use blvm_consensus::types::{Network, ValidationResult};
+use blvm_consensus::{tx_inputs, OutPoint, Transaction, TransactionInput, TransactionOutput};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use crate::mutation_differential::RawTx;
+
+/// Default vector directory
+pub fn default_vectors_dir() -> PathBuf {
+    std::env::var("BLVM_TEST_VECTORS")
+        .map(PathBuf::from)
+        .unwrap_or_else(|_| PathBuf::from("benches/fixtures/test_vectors"))
+}
+
+/// One vector where BLVM disagrees with Core's expectation
+#[derive(Debug, Clone, PartialEq)]
+pub struct VectorDivergence {
+    pub file: &'static str,
+    /// Index of the vector in the file's JSON array
+    pub index: usize,
+    pub blvm_result: String,
+    pub core_result: String,
+    /// Comment or script of the vector, to find it in the file
+    pub detail: String,
+}
+
+impl std::fmt::Display for VectorDivergence {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}#{}: BLVM={}, Core={} ({})",
+            self.file, self.index, self.blvm_result, self.core_result, self.detail
+        )
+    }
+}
+
+/// Results for one vector file
+#[derive(Debug, Clone, Default)]
+pub struct VectorFileResult {
+    pub tested: usize,
+    pub matched: usize,
+    /// Vectors that couldn't be parsed (unknown flag or opcode name)
+    pub skipped: usize,
+    pub divergences: Vec<VectorDivergence>,
+}
+
+// ---------------------------------------------------------------------------
+// Script verification flags (Core's bit layout)
+// ---------------------------------------------------------------------------
+
+const SCRIPT_FLAGS: &[(&str, u32)] = &[
+    ("NONE", 0),
+    ("P2SH", 1 << 0),
+    ("STRICTENC", 1 << 1),
+    ("DERSIG", 1 << 2),
+    ("LOW_S", 1 << 3),
+    ("NULLDUMMY", 1 << 4),
+    ("SIGPUSHONLY", 1 << 5),
+    ("MINIMALDATA", 1 << 6),
+    ("DISCOURAGE_UPGRADABLE_NOPS", 1 << 7),
+    ("CLEANSTACK", 1 << 8),
+    ("CHECKLOCKTIMEVERIFY", 1 << 9),
+    ("CHECKSEQUENCEVERIFY", 1 << 10),
+    ("WITNESS", 1 << 11),
+    ("DISCOURAGE_UPGRADABLE_WITNESS_PROGRAM", 1 << 12),
+    ("MINIMALIF", 1 << 13),
+    ("NULLFAIL", 1 << 14),
+    ("WITNESS_PUBKEYTYPE", 1 << 15),
+    ("CONST_SCRIPTCODE", 1 << 16),
+    ("TAPROOT", 1 << 17),
+    ("DISCOURAGE_UPGRADABLE_TAPROOT_VERSION", 1 << 18),
+    ("DISCOURAGE_OP_SUCCESS", 1 << 19),
+    ("DISCOURAGE_UPGRADABLE_PUBKEYTYPE", 1 << 20),
+];
+
+const FLAG_P2SH: u32 = 1 << 0;
+const FLAG_CLEANSTACK: u32 = 1 << 8;
+const FLAG_WITNESS: u32 = 1 << 11;
+const ALL_FLAGS: u32 = (1 << 21) - 1;
+
+/// Parse a comma-separated flag list ("P2SH,WITNESS", "NONE", "")
+pub fn parse_flags(s: &str) -> Result<u32> {
+    s.split(',').filter(|f| !f.is_empty()).try_fold(0, |acc, name| {
+        let (_, bit) = SCRIPT_FLAGS
+            .iter()
+            .find(|(n, _)| *n == name)
+            .with_context(|| format!("Unknown script flag '{}'", name))?;
+        Ok(acc | bit)
+    })
+}
+
+/// Drop flags that are meaningless without others, as Core's tests do
+/// (CLEANSTACK needs P2SH and WITNESS, WITNESS needs P2SH)
+fn trim_flags(mut flags: u32) -> u32 {
+    if flags & FLAG_P2SH == 0 {
+        flags &= !FLAG_WITNESS;
+    }
+    if flags & FLAG_WITNESS == 0 {
+        flags &= !FLAG_CLEANSTACK;
+    }
+    flags
+}
+
+// ---------------------------------------------------------------------------
+// Script assembly (Core's ParseScript)
+// ---------------------------------------------------------------------------
+
+const OPCODE_NAMES: &[(&str, u8)] = &[
+    ("RESERVED", 0x50), ("NOP", 0x61), ("VER", 0x62), ("IF", 0x63), ("NOTIF", 0x64),
+    ("VERIF", 0x65), ("VERNOTIF", 0x66), ("ELSE", 0x67), ("ENDIF", 0x68), ("VERIFY", 0x69),
+    ("RETURN", 0x6a), ("TOALTSTACK", 0x6b), ("FROMALTSTACK", 0x6c), ("2DROP", 0x6d),
+    ("2DUP", 0x6e), ("3DUP", 0x6f), ("2OVER", 0x70), ("2ROT", 0x71), ("2SWAP", 0x72),
+    ("IFDUP", 0x73), ("DEPTH", 0x74), ("DROP", 0x75), ("DUP", 0x76), ("NIP", 0x77),
+    ("OVER", 0x78), ("PICK", 0x79), ("ROLL", 0x7a), ("ROT", 0x7b), ("SWAP", 0x7c),
+    ("TUCK", 0x7d), ("CAT", 0x7e), ("SUBSTR", 0x7f), ("LEFT", 0x80), ("RIGHT", 0x81),
+    ("SIZE", 0x82), ("INVERT", 0x83), ("AND", 0x84), ("OR", 0x85), ("XOR", 0x86),
+    ("EQUAL", 0x87), ("EQUALVERIFY", 0x88), ("RESERVED1", 0x89), ("RESERVED2", 0x8a),
+    ("1ADD", 0x8b), ("1SUB", 0x8c), ("2MUL", 0x8d), ("2DIV", 0x8e), ("NEGATE", 0x8f),
+    ("ABS", 0x90), ("NOT", 0x91), ("0NOTEQUAL", 0x92), ("ADD", 0x93), ("SUB", 0x94),
+    ("MUL", 0x95), ("DIV", 0x96), ("MOD", 0x97), ("LSHIFT", 0x98), ("RSHIFT", 0x99),
+    ("BOOLAND", 0x9a), ("BOOLOR", 0x9b), ("NUMEQUAL", 0x9c), ("NUMEQUALVERIFY", 0x9d),
+    ("NUMNOTEQUAL", 0x9e), ("LESSTHAN", 0x9f), ("GREATERTHAN", 0xa0),
+    ("LESSTHANOREQUAL", 0xa1), ("GREATERTHANOREQUAL", 0xa2), ("MIN", 0xa3), ("MAX", 0xa4),
+    ("WITHIN", 0xa5), ("RIPEMD160", 0xa6), ("SHA1", 0xa7), ("SHA256", 0xa8),
+    ("HASH160", 0xa9), ("HASH256", 0xaa), ("CODESEPARATOR", 0xab), ("CHECKSIG", 0xac),
+    ("CHECKSIGVERIFY", 0xad), ("CHECKMULTISIG", 0xae), ("CHECKMULTISIGVERIFY", 0xaf),
+    ("NOP1", 0xb0), ("CHECKLOCKTIMEVERIFY", 0xb1), ("NOP2", 0xb1),
+    ("CHECKSEQUENCEVERIFY", 0xb2), ("NOP3", 0xb2), ("NOP4", 0xb3), ("NOP5", 0xb4),
+    ("NOP6", 0xb5), ("NOP7", 0xb6), ("NOP8", 0xb7), ("NOP9", 0xb8), ("NOP10", 0xb9),
+    ("CHECKSIGADD", 0xba),
+];
+
+/// Append a data push with the smallest push opcode that fits
+fn push_data(script: &mut Vec<u8>, data: &[u8]) {
+    match data.len() {
+        0..=75 => script.push(data.len() as u8),
+        76..=0xff => script.extend_from_slice(&[0x4c, data.len() as u8]),
+        0x100..=0xffff => {
+            script.push(0x4d);
+            script.extend_from_slice(&(data.len() as u16).to_le_bytes());
+        }
+        _ => {
+            script.push(0x4e);
+            script.extend_from_slice(&(data.len() as u32).to_le_bytes());
+        }
+    }
+    script.extend_from_slice(data);
+}
+
+/// Minimal CScriptNum encoding
+fn script_num(n: i64) -> Vec<u8> {
+    let mut out = Vec::new();
+    let negative = n < 0;
+    let mut abs = n.unsigned_abs();
+    while abs > 0 {
+        out.push((abs & 0xff) as u8);
+        abs >>= 8;
+    }
+    let Some(&last) = out.last() else {
+        return out;
+    };
+    if last & 0x80 != 0 {
+        out.push(if negative { 0x80 } else { 0x00 });
+    } else if negative {
+        *out.last_mut().expect("non-empty") |= 0x80;
+    }
+    out
+}
+
+/// Assemble a script in Core's test notation: decimal numbers, `0x` raw
+/// bytes, `'text'` pushes and opcode names with or without `OP_`
+pub fn parse_script(asm: &str) -> Result<Vec<u8>> {
+    let mut script = Vec::new();
+    for word in asm.split_whitespace() {
+        let digits = word.strip_prefix('-').unwrap_or(word);
+        if !digits.is_empty() && digits.bytes().all(|b| b.is_ascii_digit()) {
+            let n: i64 = word.parse().with_context(|| format!("Bad number '{}'", word))?;
+            if n.unsigned_abs() > 0xffff_ffff {
+                anyhow::bail!("Number {} out of range", n);
+            }
+            match n {
+                0 => script.push(0x00),
+                -1 => script.push(0x4f),
+                1..=16 => script.push(0x50 + n as u8),
+                _ => push_data(&mut script, &script_num(n)),
+            }
+        } else if let Some(hex_str) = word.strip_prefix("0x") {
+            script.extend(hex::decode(hex_str).with_context(|| format!("Bad hex '{}'", word))?);
+        } else if word.len() >= 2 && word.starts_with('\'') && word.ends_with('\'') {
+            push_data(&mut script, word[1..word.len() - 1].as_bytes());
+        } else {
+            let name = word.strip_prefix("OP_").unwrap_or(word);
+            let (_, opcode) = OPCODE_NAMES
+                .iter()
+                .find(|(n, _)| *n == name)
+                .with_context(|| format!("Unknown opcode '{}'", word))?;
+            script.push(*opcode);
+        }
+    }
+    Ok(script)
+}
+
+// ---------------------------------------------------------------------------
+// Transactions
+// ---------------------------------------------------------------------------
+
+
+/// BLVM transaction plus per-input witness stacks from raw bytes
+pub(crate) fn parse_transaction(bytes: &[u8]) -> Result<(Transaction, Vec<Witness>)> {
+    let raw = RawTx::from_bytes(bytes)?;
+    let tx = Transaction {
+        version: raw.version as _,
+        inputs: raw
+            .inputs
+            .iter()
+            .map(|input| TransactionInput {
+                prevout: OutPoint {
+                    hash: input.prevout[..32].try_into().expect("36-byte outpoint"),
+                    index: u32::from_le_bytes(input.prevout[32..].try_into().expect("36-byte outpoint")) as _,
+                },
+                script_sig: input.script_sig.clone(),
+                sequence: input.sequence as _,
+            })
+            .collect::<Vec<_>>()
+            .into(),
+        outputs: raw
+            .outputs
+            .iter()
+            .map(|(value, script_pubkey)| TransactionOutput {
+                value: *value as _,
+                script_pubkey: script_pubkey.clone(),
+            })
+            .collect::<Vec<_>>()
+            .into(),
+        lock_time: raw.lock_time as _,
+    };
+    let witnesses = raw.inputs.into_iter().map(|input| input.witness).collect();
+    Ok((tx, witnesses))
+}
+
+/// Verify every input of `tx`; `prevouts` are in input order
+pub(crate) fn verify_transaction(
+    tx: &Transaction,
+    witnesses: &[Witness],
+    prevouts: &[TransactionOutput],
+    flags: u32,
+) -> std::result::Result<(), String> {
+    for (index, (input, prevout)) in tx.inputs.iter().zip(prevouts).enumerate() {
+        let witness = witnesses.get(index).filter(|w| !w.is_empty());
+        match verify_script_with_context(
+            &input.script_sig,
+            &prevout.script_pubkey,
+            witness,
+            flags,
+            tx,
+            index,
+            prevouts,
+            Network::Mainnet,
+        ) {
+            Ok(true) => {}
+            Ok(false) => return Err(format!("input {}: script evaluated false", index)),
+            Err(e) => return Err(format!("input {}: {}", index, e)),
+        }
+    }
+    Ok(())
+}
+
+fn describe(result: &std::result::Result<(), String>) -> String {
+    match result {
+        Ok(()) => "valid".to_string(),
+        Err(reason) => format!("invalid ({})", reason),
+    }
+}
+
+/// Core's BuildCreditingTransaction / BuildSpendingTransaction pair for a script test
+fn script_test_transactions(
+    script_sig: Vec<u8>,
+    script_pubkey: Vec<u8>,
+    amount: i64,
+) -> (Transaction, TransactionOutput) {
+    let credited = TransactionOutput {
+        value: amount as _,
+        script_pubkey,
+    };
+    let crediting = Transaction {
+        version: 1,
+        inputs: tx_inputs![TransactionInput {
+            prevout: OutPoint {
+                hash: [0; 32],
+                index: 0xffffffff,
+            },
+            script_sig: vec![0x00, 0x00],
+            sequence: 0xffffffff,
+        }],
+        outputs: vec![credited.clone()].into(),
+        lock_time: 0,
+    };
+    let spending = Transaction {
+        version: 1,
+        inputs: tx_inputs![TransactionInput {
+            prevout: OutPoint {
+                hash: calculate_tx_id(&crediting),
+                index: 0,
+            },
+            script_sig,
+            sequence: 0xffffffff,
+        }],
+        outputs: vec![TransactionOutput {
+            value: amount as _,
+            script_pubkey: Vec::new(),
+        }]
+        .into(),
+        lock_time: 0,
+    };
+    (spending, credited)
+}
+
+/// Txid in display (reversed) hex to internal byte order
+fn txid_from_hex(s: &str) -> Result<[u8; 32]> {
+    let mut hash: [u8; 32] = hex::decode(s)
+        .with_context(|| format!("Bad txid '{}'", s))?
+        .try_into()
+        .map_err(|_| anyhow::anyhow!("Txid '{}' is not 32 bytes", s))?;
+    hash.reverse();
+    Ok(hash)
+}
+
+fn str_at<'a>(entry: &'a [Value], index: usize) -> Result<&'a str> {
+    entry
+        .get(index)
+        .and_then(Value::as_str)
+        .with_context(|| format!("Expected a string at position {}", index))
+}
+
+// ---------------------------------------------------------------------------
+// Per-file checks: Ok(None) = BLVM agrees, Ok(Some((blvm, core))) = divergence,
+// Err = vector couldn't be interpreted
+// ---------------------------------------------------------------------------
+
+type Check = Result<Option<(String, String)>>;
+
+/// `[[wit..., amount]?, scriptSig, scriptPubKey, flags, expected_error, comment?]`
+fn check_script_test(entry: &[Value]) -> Check {
+    let (witness, amount, rest) = match entry.first() {
+        Some(Value::Array(items)) => {
+            let (amount, items) = items.split_last().context("Empty witness array")?;
+            let amount = amount.as_f64().context("Witness amount is not a number")?;
+            let witness = items
+                .iter()
+                .map(|item| hex::decode(item.as_str().unwrap_or_default()).context("Bad witness hex"))
+                .collect::<Result<Witness>>()?;
+            (witness, (amount * 100_000_000.0).round() as i64, &entry[1..])
+        }
+        _ => (Witness::new(), 0, entry),
+    };
+    let script_sig = parse_script(str_at(rest, 0)?)?;
+    let script_pubkey = parse_script(str_at(rest, 1)?)?;
+    let flags = parse_flags(str_at(rest, 2)?)?;
+    let expected = str_at(rest, 3)?;
+
+    let (spending, credited) = script_test_transactions(script_sig, script_pubkey, amount);
+    let blvm = verify_transaction(&spending, &[witness], &[credited], flags);
+    Ok((blvm.is_ok() != (expected == "OK")).then(|| (describe(&blvm), expected.to_string())))
+}
+
+/// `[[[prevout_hash, prevout_index, prevout_scriptPubKey, amount?], ...], tx_hex, flags]`
+///
+/// tx_valid lists the flags to *exclude*; tx_invalid the flags to apply, with
+/// `BADTX` meaning the transaction already fails CheckTransaction
+fn check_tx_test(entry: &[Value], expect_valid: bool) -> Check {
+    let mut spent = HashMap::new();
+    for prevout in entry.first().and_then(Value::as_array).context("Missing prevouts")? {
+        let prevout = prevout.as_array().context("Prevout is not an array")?;
+        let hash = txid_from_hex(str_at(prevout, 0)?)?;
+        let index = prevout.get(1).and_then(Value::as_i64).context("Bad prevout index")? as u32;
+        let script_pubkey = parse_script(str_at(prevout, 2)?)?;
+        let value = prevout.get(3).and_then(Value::as_i64).unwrap_or(0);
+        spent.insert((hash, index), TransactionOutput { value: value as _, script_pubkey });
+    }
+    let tx_bytes = hex::decode(str_at(entry, 1)?).context("Bad transaction hex")?;
+    let (tx, witnesses) = parse_transaction(&tx_bytes)?;
+    let flag_names = str_at(entry, 2)?;
+    let listed = parse_flags(
+        &flag_names.split(',').filter(|f| *f != "BADTX").collect::<Vec<_>>().join(","),
+    )?;
+    let flags = if expect_valid { trim_flags(ALL_FLAGS & !listed) } else { listed };
+
+    let prevouts = tx
+        .inputs
+        .iter()
+        .map(|input| {
+            spent
+                .get(&(input.prevout.hash, input.prevout.index as u32))
+                .cloned()
+                .context("Vector has no prevout for an input")
+        })
+        .collect::<Result<Vec<_>>>()?;
+    let blvm = match check_transaction(&tx) {
+        Ok(ValidationResult::Valid) => verify_transaction(&tx, &witnesses, &prevouts, flags),
+        Ok(ValidationResult::Invalid(reason)) => Err(format!("check_transaction: {}", reason)),
+        Err(e) => Err(format!("check_transaction: {}", e)),
+    };
+    let core = if expect_valid { "valid".to_string() } else { format!("invalid ({})", flag_names) };
+    Ok((blvm.is_ok() != expect_valid).then(|| (describe(&blvm), core)))
+}
+
+/// `[raw_tx, script, input_index, hash_type, expected_sighash]`
+///
+/// Signatures only carry one hash-type byte, so vectors whose 32-bit hash type
+/// doesn't fit in a byte (unreachable through consensus) are skipped
+fn check_sighash_test(entry: &[Value]) -> Check {
+    let (tx, _) = parse_transaction(&hex::decode(str_at(entry, 0)?).context("Bad transaction hex")?)?;
+    let script_code = hex::decode(str_at(entry, 1)?).context("Bad script hex")?;
+    let input_index = entry.get(2).and_then(Value::as_u64).context("Bad input index")? as usize;
+    let hash_type = entry.get(3).and_then(Value::as_i64).context("Bad hash type")?;
+    let expected = str_at(entry, 4)?;
+    let hash_type = u8::try_from(hash_type).map_err(|_| anyhow::anyhow!("Hash type {} needs more than a byte", hash_type))?;
+
+    let prevouts: Vec<TransactionOutput> = (0..tx.inputs.len())
+        .map(|i| TransactionOutput {
+            value: 0,
+            script_pubkey: if i == input_index { script_code.clone() } else { Vec::new() },
+        })
+        .collect();
+    let blvm = match calculate_transaction_sighash(&tx, input_index, &prevouts, SighashType::from_byte(hash_type)) {
+        Ok(mut hash) => {
+            hash.reverse();
+            hex::encode(hash)
+        }
+        Err(e) => format!("error ({})", e),
+    };
+    Ok((blvm != expected).then(|| (blvm, expected.to_string())))
+}
+
+// ---------------------------------------------------------------------------
+// Runner
+// ---------------------------------------------------------------------------
+
+/// Vector files, whether an entry is a comment, and the check for real entries
+const FILES: &[(&str, fn(&[Value]) -> bool, fn(&[Value]) -> Check)] = &[
+    ("script_tests.json", |e| e.len() == 1, check_script_test),
+    ("tx_valid.json", |e| !e.first().is_some_and(Value::is_array), |e| check_tx_test(e, true)),
+    ("tx_invalid.json", |e| !e.first().is_some_and(Value::is_array), |e| check_tx_test(e, false)),
+    ("sighash.json", |e| e.len() == 1, check_sighash_test),
+];
+
+/// Run one file's vectors
+fn run_vectors(
+    file: &'static str,
+    vectors: &[Value],
+    is_comment: fn(&[Value]) -> bool,
+    check: fn(&[Value]) -> Check,
+) -> VectorFileResult {
+    let mut result = VectorFileResult::default();
+    for (index, vector) in vectors.iter().enumerate() {
+        let Some(entry) = vector.as_array() else {
+            continue;
+        };
+        if is_comment(entry) {
+            continue;
+        }
+        result.tested += 1;
+        match check(entry) {
+            Ok(None) => result.matched += 1,
+            Ok(Some((blvm_result, core_result))) => {
+                let mut detail = vector.to_string();
+                if detail.chars().count() > 120 {
+                    detail = detail.chars().take(117).chain("...".chars()).collect();
+                }
+                result.divergences.push(VectorDivergence { file, index, blvm_result, core_result, detail });
+            }
+            Err(_) => {
+                result.tested -= 1;
+                result.skipped += 1;
+            }
+        }
+    }
+    result
+}
+
+/// Run every vector file found in `dir`
+pub fn run_test_vectors(dir: &Path) -> Result<Vec<(&'static str, VectorFileResult)>> {
+    let mut results = Vec::new();
+    for (file, is_comment, check) in FILES {
+        let path = dir.join(file);
+        if !path.exists() {
+            eprintln!("⚠️  Skipping {}: not found in {}", file, dir.display());
+            continue;
+        }
+        let content = std::fs::read_to_string(&path).with_context(|| format!("Failed to read {}", path.display()))?;
+        let vectors: Vec<Value> =
+            serde_json::from_str(&content).with_context(|| format!("Failed to parse {}", path.display()))?;
+        let result = run_vectors(file, &vectors, *is_comment, *check);
+        println!(
+            "{} {}: {}/{} vectors match{}",
+            if result.divergences.is_empty() { "✅" } else { "❌" },
+            file,
+            result.matched,
+            result.tested,
+            if result.skipped > 0 { format!(" ({} skipped)", result.skipped) } else { String::new() }
+        );
+        results.push((*file, result));
+    }
+    if results.is_empty() {
+        anyhow::bail!("No test vector files in {}", dir.display());
+    }
+
+    if results.iter().any(|(_, r)| !r.divergences.is_empty()) {
+        println!("\n❌ Divergences found:");
+        for (_, result) in &results {
+            for divergence in &result.divergences {
+                println!("   {}", divergence);
+            }
+        }
+    }
+    Ok(results)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_script_matches_core_notation() {
+        assert_eq!(parse_script("0 -1 1 16 17 -2").unwrap(), vec![0x00, 0x4f, 0x51, 0x60, 0x01, 0x11, 0x01, 0x82]);
+        assert_eq!(parse_script("128 -128 255").unwrap(), vec![0x02, 0x80, 0x00, 0x02, 0x80, 0x80, 0x02, 0xff, 0x00]);
+        assert_eq!(parse_script("DUP OP_HASH160 0x14 0x0102 EQUALVERIFY").unwrap(), vec![0x76, 0xa9, 0x14, 0x01, 0x02, 0x88]);
+        assert_eq!(parse_script("'ab' NOP2 CHECKSIGADD").unwrap(), vec![0x02, b'a', b'b', 0xb1, 0xba]);
+        assert!(parse_script("OP_FOO").is_err());
+
+        assert_eq!(parse_flags("P2SH,WITNESS").unwrap(), (1 << 0) | (1 << 11));
+        assert_eq!(parse_flags("NONE").unwrap(), 0);
+        assert!(parse_flags("P2SH,BOGUS").is_err());
+        assert_eq!(trim_flags(ALL_FLAGS & !FLAG_P2SH) & (FLAG_WITNESS | FLAG_CLEANSTACK), 0);
+    }
+}
@@ -0,0 +1,195 @@
+//! Transaction Serialization Round-Trips
+//!
+//! Every mode of this crate starts by deserializing blocks with BLVM, so a
+//! decoder that accepts an encoding Core rejects, or reads a transaction back
+//! into something that re-encodes differently, changes verdicts everywhere.
+//! `check_roundtrip` decodes one serialized transaction with BLVM and with the
+//! independent wire parser of `mutation_differential` and compares:
+//!
+//! - An encoding the reference parser reads and re-encodes byte for byte is
+//!   canonical: BLVM must decode it, produce the same stripped serialization
+//!   and carry the same witness items.
+//! - Anything else (truncated data, non-minimal compact sizes, a witness marker
+//!   with no witness data, input counts larger than the data) is rejected by
+//!   Core's deserializer and must be rejected by BLVM too.
+//!
+//! BLVM only exposes block deserialization, so the transaction is decoded as
+//! the single transaction of a block with an all-zero header. `weird_encodings`
+//! lists the known edge cases; `fuzz/fuzz_targets/tx_roundtrip.rs` looks for
+//! more.
+
+use anyhow::Result;
+use blvm_consensus::segwit::Witness;
+use blvm_consensus::serialization::block::deserialize_block_with_witnesses;
+use blvm_consensus::serialization::transaction::serialize_transaction;
+use blvm_consensus::Transaction;
+
+use crate::bench_fixtures::write_compact_size;
+use crate::mutation_differential::{RawInput, RawTx};
+
+/// How BLVM and the reference agreed on an encoding
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RoundTrip {
+    /// Canonical, decoded and re-encoded identically
+    Canonical,
+    /// Not a canonical encoding, rejected by both
+    Rejected,
+}
+
+/// `tx_bytes` as the only transaction of a block with an all-zero header
+pub fn wrap_in_block(tx_bytes: &[u8]) -> Vec<u8> {
+    let mut block = vec![0u8; 80];
+    write_compact_size(&mut block, 1);
+    block.extend_from_slice(tx_bytes);
+    block
+}
+
+/// Decode one serialized transaction with BLVM
+pub fn decode_tx(tx_bytes: &[u8]) -> Result<(Transaction, Witness)> {
+    let (block, witnesses) = deserialize_block_with_witnesses(&wrap_in_block(tx_bytes))
+        .map_err(|e| anyhow::anyhow!("Failed to deserialize transaction: {}", e))?;
+    let mut transactions = block.transactions.into_vec();
+    if transactions.len() != 1 {
+        anyhow::bail!("Decoded {} transactions from one", transactions.len());
+    }
+    let witness = witnesses.into_iter().next().unwrap_or_default();
+    Ok((transactions.remove(0), witness))
+}
+
+/// The reference parse of `tx_bytes`, if it is a canonical encoding
+fn canonical(tx_bytes: &[u8]) -> Option<RawTx> {
+    RawTx::from_bytes(tx_bytes)
+        .ok()
+        // Core reads the output count after an empty input list as the segwit flag
+        .filter(|raw| !raw.inputs.is_empty() || raw.outputs.is_empty())
+        .filter(|raw| raw.to_bytes() == tx_bytes)
+}
+
+/// Compare BLVM's decoding of `tx_bytes` with the reference; an error describes the disagreement
+pub fn check_roundtrip(tx_bytes: &[u8]) -> Result<RoundTrip> {
+    match (canonical(tx_bytes), decode_tx(tx_bytes)) {
+        (None, Err(_)) => Ok(RoundTrip::Rejected),
+        (None, Ok(_)) => anyhow::bail!(
+            "BLVM accepted a non-canonical or malformed encoding Core rejects: {}",
+            preview(tx_bytes)
+        ),
+        (Some(_), Err(e)) => anyhow::bail!("BLVM rejected a canonical transaction ({:#}): {}", e, preview(tx_bytes)),
+        (Some(raw), Ok((tx, witness))) => {
+            let expected = raw.serialize(false);
+            let reencoded = serialize_transaction(&tx);
+            if reencoded != expected {
+                anyhow::bail!(
+                    "BLVM re-encodes {} as {} (expected {})",
+                    preview(tx_bytes),
+                    preview(&reencoded),
+                    preview(&expected)
+                );
+            }
+            // BLVM keeps one flat witness stack per transaction
+            let items: Vec<&Vec<u8>> = raw.inputs.iter().flat_map(|input| &input.witness).collect();
+            if witness.iter().collect::<Vec<_>>() != items {
+                anyhow::bail!(
+                    "BLVM decoded {} witness items from {} ({} expected)",
+                    witness.len(),
+                    preview(tx_bytes),
+                    items.len()
+                );
+            }
+            Ok(RoundTrip::Canonical)
+        }
+    }
+}
+
+/// Hex of the first bytes of `bytes`
+fn preview(bytes: &[u8]) -> String {
+    const SHOWN: usize = 64;
+    if bytes.len() <= SHOWN {
+        hex::encode(bytes)
+    } else {
+        format!("{}... ({} bytes)", hex::encode(&bytes[..SHOWN]), bytes.len())
+    }
+}
+
+/// Transaction with `inputs` inputs and two outputs; segwit inputs carry a signature-sized witness
+pub fn sample_tx(inputs: usize, segwit: bool) -> Vec<u8> {
+    RawTx {
+        version: 2,
+        inputs: (0..inputs as u32)
+            .map(|i| {
+                let mut prevout = [0u8; 36];
+                prevout[..4].copy_from_slice(&i.to_le_bytes());
+                prevout[32..].copy_from_slice(&(i % 3).to_le_bytes());
+                RawInput {
+                    prevout,
+                    script_sig: if segwit { Vec::new() } else { vec![0x47; 107] },
+                    sequence: 0xffff_fffd,
+                    witness: if segwit { vec![vec![0x30; 72], vec![0x02; 33]] } else { Vec::new() },
+                }
+            })
+            .collect(),
+        outputs: vec![(50_000, vec![0x00, 0x14].into_iter().chain([0x11; 20]).collect()), (1_000, vec![0x51])],
+        lock_time: 0,
+    }
+    .to_bytes()
+}
+
+/// Legal-but-unusual and almost-legal encodings, with whether each is canonical
+pub fn weird_encodings() -> Vec<(&'static str, Vec<u8>, bool)> {
+    let legacy = sample_tx(1, false);
+    let segwit = sample_tx(2, true);
+    // version (4) then the input count
+    let with_count = |tx: &[u8], count: &[u8]| [&tx[..4], count, &tx[5..]].concat();
+
+    let mut mixed_witness = RawTx::from_bytes(&segwit).expect("sample parses");
+    mixed_witness.inputs[0].witness.clear();
+    // Marker and flag kept, but every input's witness is empty
+    let stripped = RawTx::from_bytes(&segwit).expect("sample parses").serialize(false);
+    let (body, lock_time) = stripped[4..].split_at(stripped.len() - 8);
+    let empty_witnesses = [&stripped[..4], &[0x00, 0x01][..], body, &[0x00, 0x00][..], lock_time].concat();
+    let mut big_script = RawTx::from_bytes(&legacy).expect("sample parses");
+    big_script.inputs[0].script_sig = vec![0x51; 10_000];
+    let mut no_outputs = RawTx::from_bytes(&legacy).expect("sample parses");
+    no_outputs.outputs.clear();
+    let mut no_inputs = RawTx::from_bytes(&legacy).expect("sample parses");
+    no_inputs.inputs.clear();
+
+    vec![
+        ("legacy", legacy.clone(), true),
+        ("segwit", segwit, true),
+        ("segwit_one_empty_witness", mixed_witness.to_bytes(), true),
+        ("many_inputs", sample_tx(3_000, false), true),
+        ("max_script_sig", big_script.to_bytes(), true),
+        ("no_outputs", no_outputs.to_bytes(), true),
+        // An empty input list followed by outputs reads as a witness marker and flag
+        ("no_inputs_ambiguous", no_inputs.to_bytes(), false),
+        ("segwit_all_witnesses_empty", empty_witnesses, false),
+        ("non_minimal_input_count_fd", with_count(&legacy, &[0xfd, 0x01, 0x00]), false),
+        ("non_minimal_input_count_fe", with_count(&legacy, &[0xfe, 0x01, 0x00, 0x00, 0x00]), false),
+        ("huge_input_count_u32", with_count(&legacy, &[0xfe, 0xff, 0xff, 0xff, 0xff]), false),
+        ("huge_input_count_u64", with_count(&legacy, &[0xff; 9]), false),
+        ("truncated", legacy[..legacy.len() - 1].to_vec(), false),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_weird_encodings_classified() {
+        for (name, bytes, is_canonical) in weird_encodings() {
+            assert_eq!(canonical(&bytes).is_some(), is_canonical, "{}", name);
+        }
+    }
+
+    #[test]
+    fn test_blvm_round_trips_weird_encodings() {
+        for (name, bytes, is_canonical) in weird_encodings() {
+            let expected = if is_canonical { RoundTrip::Canonical } else { RoundTrip::Rejected };
+            match check_roundtrip(&bytes) {
+                Ok(outcome) => assert_eq!(outcome, expected, "{}", name),
+                Err(e) => panic!("{}: {:#}", name, e),
+            }
+        }
+    }
+}
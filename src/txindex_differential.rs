@@ -0,0 +1,254 @@
+//! Transaction Serialization Differential (txindex)
+//!
+//! Checks the serialization layer on its own, without any consensus rules:
+//! for a sampled set of transactions, BLVM's txid, wtxid, size, vsize, weight
+//! and raw bytes are compared with Core's `getrawtransaction` (verbose), looked
+//! up by txid alone, so Core must run with `-txindex=1`.
+//!
+//! BLVM's side is what it derives from the parsed block: the txid from
+//! `calculate_tx_id`, the weight from `calculate_transaction_weight` and the
+//! size implied by that weight and BLVM's stripped serialization. Each
+//! transaction's bytes are framed in the raw block by those sizes, so a size
+//! BLVM gets wrong shifts the framing and shows up as a wrong wtxid and hex
+//! for that transaction and the ones after it.
+//!
+//! Blocks are picked with the era-stratified sampler (see `sampler`), then up
+//! to `txs_per_block` transactions spread evenly through each block.
+
+use anyhow::{Context, Result};
+use blvm_consensus::block::calculate_tx_id;
+use blvm_consensus::segwit::calculate_transaction_weight;
+use blvm_consensus::serialization::block::deserialize_block_with_witnesses;
+use blvm_consensus::serialization::transaction::serialize_transaction;
+use serde_json::Value;
+use std::time::Instant;
+
+use crate::bench_fixtures::{sha256d, write_compact_size};
+use crate::block_hash::display_hex;
+use crate::core_rpc_client::CoreRpcClient;
+use crate::sampler::{sample_heights, SampleConfig};
+
+/// Stop collecting mismatch details after this many
+const MAX_REPORTED_MISMATCHES: usize = 100;
+
+/// Identity and size figures of one transaction
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TxFields {
+    /// Display byte order
+    pub txid: String,
+    pub wtxid: String,
+    pub size: u64,
+    pub vsize: u64,
+    pub weight: u64,
+    pub hex: String,
+}
+
+/// BLVM's figures for every transaction of a serialized block
+pub fn blvm_tx_fields(block_bytes: &[u8]) -> Result<Vec<TxFields>> {
+    let (block, witnesses) = deserialize_block_with_witnesses(block_bytes)
+        .map_err(|e| anyhow::anyhow!("Failed to deserialize block: {}", e))?;
+    let mut tx_count = Vec::new();
+    write_compact_size(&mut tx_count, block.transactions.len() as u64);
+    let mut offset = 80 + tx_count.len();
+
+    let mut fields = Vec::with_capacity(block.transactions.len());
+    for (index, (tx, witness)) in block.transactions.iter().zip(witnesses.iter()).enumerate() {
+        let stripped_size = serialize_transaction(tx).len() as u64;
+        let weight = calculate_transaction_weight(tx, Some(witness))
+            .map_err(|e| anyhow::anyhow!("Transaction {} weight: {:?}", index, e))?;
+        // weight = 3 * stripped size + total size
+        let size = weight
+            .checked_sub(3 * stripped_size)
+            .with_context(|| format!("Transaction {} weight {} is below 3x its stripped size", index, weight))?;
+        let bytes = block_bytes
+            .get(offset..offset + size as usize)
+            .with_context(|| format!("Transaction {} runs past the end of the block at BLVM's sizes", index))?;
+        offset += size as usize;
+        fields.push(TxFields {
+            txid: display_hex(&calculate_tx_id(tx)),
+            wtxid: display_hex(&sha256d(bytes)),
+            size,
+            vsize: weight.div_ceil(4),
+            weight,
+            hex: hex::encode(bytes),
+        });
+    }
+    Ok(fields)
+}
+
+/// Core's figures from `getrawtransaction` (verbose)
+pub fn core_tx_fields(tx: &Value) -> Result<TxFields> {
+    let text = |key: &str| {
+        tx.get(key)
+            .and_then(|v| v.as_str())
+            .map(str::to_string)
+            .with_context(|| format!("getrawtransaction missing {}", key))
+    };
+    let number = |key: &str| {
+        tx.get(key)
+            .and_then(|v| v.as_u64())
+            .with_context(|| format!("getrawtransaction missing {}", key))
+    };
+    Ok(TxFields {
+        txid: text("txid")?,
+        wtxid: text("hash")?,
+        size: number("size")?,
+        vsize: number("vsize")?,
+        weight: number("weight")?,
+        hex: text("hex")?,
+    })
+}
+
+/// One figure BLVM and Core disagree on
+#[derive(Debug, Clone)]
+pub struct TxMismatch {
+    pub height: u64,
+    pub index: usize,
+    pub field: &'static str,
+    pub blvm: String,
+    pub core: String,
+}
+
+/// Field-by-field differences for the transaction at `index` of block `height`
+pub fn compare_tx_fields(height: u64, index: usize, blvm: &TxFields, core: &TxFields) -> Vec<TxMismatch> {
+    let mut mismatches = Vec::new();
+    let mut check = |field: &'static str, blvm: String, core: String| {
+        if blvm != core {
+            mismatches.push(TxMismatch {
+                height,
+                index,
+                field,
+                blvm,
+                core,
+            });
+        }
+    };
+    check("txid", blvm.txid.clone(), core.txid.clone());
+    check("wtxid", blvm.wtxid.clone(), core.wtxid.clone());
+    check("size", blvm.size.to_string(), core.size.to_string());
+    check("vsize", blvm.vsize.to_string(), core.vsize.to_string());
+    check("weight", blvm.weight.to_string(), core.weight.to_string());
+    check("hex", blvm.hex.clone(), core.hex.clone());
+    mismatches
+}
+
+/// Up to `n` indices spread evenly over `0..count`, always including the first and last
+pub fn spread_indices(count: usize, n: usize) -> Vec<usize> {
+    match (count, n) {
+        (0, _) | (_, 0) => Vec::new(),
+        _ if n >= count => (0..count).collect(),
+        (_, 1) => vec![0],
+        _ => (0..n).map(|i| i * (count - 1) / (n - 1)).collect(),
+    }
+}
+
+/// Result of a txindex differential run
+#[derive(Debug, Default)]
+pub struct TxIndexReport {
+    pub seed: u64,
+    pub blocks_sampled: u64,
+    pub txs_checked: u64,
+    pub mismatch_count: u64,
+    /// First `MAX_REPORTED_MISMATCHES` mismatches, in height order
+    pub mismatches: Vec<TxMismatch>,
+    pub elapsed_secs: f64,
+}
+
+/// Compare sampled transactions from blocks `start_height..=end_height` (end defaults to Core's tip)
+pub async fn run_txindex_differential(
+    client: &CoreRpcClient,
+    sample: &SampleConfig,
+    txs_per_block: usize,
+    start_height: u64,
+    end_height: Option<u64>,
+) -> Result<TxIndexReport> {
+    let tip = client.getblockcount().await?;
+    let end_height = end_height.unwrap_or(tip).min(tip);
+    // The genesis coinbase isn't in the UTXO set or the txindex
+    let heights = sample_heights(start_height.max(1), end_height, sample);
+    println!(
+        "🔢 Transaction serialization differential: {} blocks sampled from {}..={} (seed {}), up to {} txs each",
+        heights.len(),
+        start_height.max(1),
+        end_height,
+        sample.seed,
+        txs_per_block
+    );
+
+    let start = Instant::now();
+    let mut report = TxIndexReport {
+        seed: sample.seed,
+        ..Default::default()
+    };
+    for height in heights {
+        let hash = client.getblockhash(height).await?;
+        let block_bytes = hex::decode(client.getblock_raw(&hash).await?)?;
+        let blvm = blvm_tx_fields(&block_bytes).with_context(|| format!("BLVM serialization of block {}", height))?;
+
+        for index in spread_indices(blvm.len(), txs_per_block) {
+            let core_tx = client
+                .getrawtransaction_verbose(&blvm[index].txid)
+                .await
+                .with_context(|| {
+                    format!(
+                        "Core has no transaction {} (block {} tx {}); is -txindex=1 set, or is BLVM's txid wrong?",
+                        blvm[index].txid, height, index
+                    )
+                })?;
+            let core = core_tx_fields(&core_tx).with_context(|| format!("Core's view of block {} tx {}", height, index))?;
+            for mismatch in compare_tx_fields(height, index, &blvm[index], &core) {
+                report.mismatch_count += 1;
+                if report.mismatches.len() < MAX_REPORTED_MISMATCHES {
+                    eprintln!(
+                        "❌ Block {} tx {} {}: BLVM={:.80}, Core={:.80}",
+                        height, index, mismatch.field, mismatch.blvm, mismatch.core
+                    );
+                    report.mismatches.push(mismatch);
+                }
+            }
+            report.txs_checked += 1;
+        }
+        report.blocks_sampled += 1;
+    }
+
+    report.elapsed_secs = start.elapsed().as_secs_f64();
+    println!(
+        "{} {} transactions in {} blocks checked in {:.1}s, {} serialization mismatches",
+        if report.mismatch_count == 0 { "✅" } else { "❌" },
+        report.txs_checked,
+        report.blocks_sampled,
+        report.elapsed_secs,
+        report.mismatch_count
+    );
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_spread_indices() {
+        assert_eq!(spread_indices(3, 10), vec![0, 1, 2]);
+        assert_eq!(spread_indices(100, 1), vec![0]);
+        assert_eq!(spread_indices(100, 4), vec![0, 33, 66, 99]);
+        assert!(spread_indices(0, 4).is_empty());
+    }
+
+    #[test]
+    fn test_compare_tx_fields() {
+        let core = core_tx_fields(&json!({
+            "txid": "aa", "hash": "bb", "size": 225, "vsize": 144, "weight": 573, "hex": "0200"
+        }))
+        .unwrap();
+        let mut blvm = core.clone();
+        assert!(compare_tx_fields(800_000, 3, &blvm, &core).is_empty());
+
+        blvm.weight = 572;
+        blvm.wtxid = "cc".to_string();
+        let fields: Vec<&str> = compare_tx_fields(800_000, 3, &blvm, &core).iter().map(|m| m.field).collect();
+        assert_eq!(fields, vec!["wtxid", "weight"]);
+        assert!(core_tx_fields(&json!({ "txid": "aa" })).is_err());
+    }
+}
@@ -0,0 +1,542 @@
+//! UTXO Set Backends
+//!
+//! Runtime-selectable UTXO storage used by checkpoint generation and chunk
+//! validation, so storage-engine experiments can be A/B tested without code edits.
+//!
+//! - `Memory`: the original approach - clone the full `UtxoSet` into every `connect_block`
+//! - `Cow`: immutable base set plus a write overlay; only the coins a block touches
+//!   are handed to `connect_block`
+//! - `Disk`: coins live in an append-only file with an in-memory outpoint index
+
+use anyhow::{Context, Result};
+use blvm_consensus::block::{calculate_tx_id, connect_block};
+use blvm_consensus::segwit::Witness;
+use blvm_consensus::types::{Network, ValidationResult};
+use blvm_consensus::{Block, OutPoint, UtxoSet, UTXO};
+use std::collections::HashMap;
+use std::fs::{File, OpenOptions};
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// Overlay size at which the COW store folds its changes into the base set
+const COW_FLATTEN_THRESHOLD: usize = 1_000_000;
+
+/// Selectable UTXO backend
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum UtxoBackend {
+    /// Full in-memory map, cloned per block (current behaviour)
+    #[default]
+    Memory,
+    /// Copy-on-write: base set + overlay of changes
+    Cow,
+    /// Disk-backed coin storage in the given directory
+    Disk(PathBuf),
+}
+
+impl UtxoBackend {
+    /// Parse from a config string ("memory", "cow", "disk" or "disk:/path")
+    pub fn parse(value: &str) -> Result<Self> {
+        match value.trim() {
+            "memory" | "mem" => Ok(UtxoBackend::Memory),
+            "cow" | "layered" => Ok(UtxoBackend::Cow),
+            "disk" => Ok(UtxoBackend::Disk(default_disk_dir())),
+            other => match other.strip_prefix("disk:") {
+                Some(path) => Ok(UtxoBackend::Disk(PathBuf::from(path))),
+                None => anyhow::bail!("Unknown UTXO backend '{}' (expected memory, cow, or disk[:path])", other),
+            },
+        }
+    }
+
+    /// Read from `BLVM_UTXO_BACKEND`, defaulting to `Memory`
+    pub fn from_env() -> Result<Self> {
+        match std::env::var("BLVM_UTXO_BACKEND") {
+            Ok(value) => Self::parse(&value),
+            Err(_) => Ok(UtxoBackend::Memory),
+        }
+    }
+
+    /// Short name for logs and reports
+    pub fn name(&self) -> &'static str {
+        match self {
+            UtxoBackend::Memory => "memory",
+            UtxoBackend::Cow => "cow",
+            UtxoBackend::Disk(_) => "disk",
+        }
+    }
+
+    /// Create a store seeded with the given UTXO set
+    pub fn create(&self, initial: UtxoSet) -> Result<Box<dyn UtxoStore>> {
+        Ok(match self {
+            UtxoBackend::Memory => Box::new(MemoryUtxoStore { utxo_set: initial }),
+            UtxoBackend::Cow => Box::new(CowUtxoStore::new(initial)),
+            UtxoBackend::Disk(dir) => Box::new(DiskUtxoStore::create(dir, initial)?),
+        })
+    }
+}
+
+fn default_disk_dir() -> PathBuf {
+    dirs::cache_dir()
+        .or_else(|| dirs::home_dir().map(|h| h.join(".cache")))
+        .unwrap_or_else(std::env::temp_dir)
+        .join("blvm-bench")
+        .join("utxo-disk")
+}
+
+/// UTXO storage that can connect blocks
+pub trait UtxoStore: Send {
+    /// Backend name (for logs)
+    fn name(&self) -> &'static str;
+
+    /// Validate and apply a block. State is only updated when the block is valid.
+    fn connect_block(
+        &mut self,
+        block: &Block,
+        witnesses: &[Witness],
+        height: u64,
+        network: Network,
+    ) -> Result<ValidationResult>;
+
+    /// Number of unspent outputs
+    fn len(&self) -> usize;
+
+    /// Whether the set is empty
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Look up a single coin
+    fn get(&self, outpoint: &OutPoint) -> Result<Option<UTXO>>;
+
+    /// Materialize the full set (checkpoints, debugging)
+    fn to_utxo_set(&self) -> Result<UtxoSet>;
+}
+
+/// Original behaviour: clone the whole set into `connect_block`
+pub struct MemoryUtxoStore {
+    utxo_set: UtxoSet,
+}
+
+impl UtxoStore for MemoryUtxoStore {
+    fn name(&self) -> &'static str {
+        "memory"
+    }
+
+    fn connect_block(
+        &mut self,
+        block: &Block,
+        witnesses: &[Witness],
+        height: u64,
+        network: Network,
+    ) -> Result<ValidationResult> {
+        let (result, new_utxo_set, _undo_log) =
+            connect_block(block, witnesses, self.utxo_set.clone(), height, None, network)?;
+        if matches!(result, ValidationResult::Valid) {
+            self.utxo_set = new_utxo_set;
+        }
+        Ok(result)
+    }
+
+    fn len(&self) -> usize {
+        self.utxo_set.len()
+    }
+
+    fn get(&self, outpoint: &OutPoint) -> Result<Option<UTXO>> {
+        Ok(self.utxo_set.get(outpoint).cloned())
+    }
+
+    fn to_utxo_set(&self) -> Result<UtxoSet> {
+        Ok(self.utxo_set.clone())
+    }
+}
+
+/// Outpoints a block may read: every spent prevout, plus every outpoint it would
+/// create (so BIP30 duplicate checks still see existing coins)
+pub(crate) fn touched_outpoints(block: &Block) -> Vec<OutPoint> {
+    let mut touched = Vec::new();
+    for tx in block.transactions.iter() {
+        if !blvm_consensus::transaction::is_coinbase(tx) {
+            for input in tx.inputs.iter() {
+                touched.push(input.prevout.clone());
+            }
+        }
+        let txid = calculate_tx_id(tx);
+        for index in 0..tx.outputs.len() {
+            touched.push(OutPoint {
+                hash: txid,
+                index: index as _,
+            });
+        }
+    }
+    touched
+}
+
+/// Run `connect_block` on only the touched subset and return (result, removed, added)
+fn connect_subset(
+    block: &Block,
+    witnesses: &[Witness],
+    height: u64,
+    network: Network,
+    subset: UtxoSet,
+) -> Result<(ValidationResult, Vec<OutPoint>, Vec<(OutPoint, UTXO)>)> {
+    let before: Vec<OutPoint> = subset.iter().map(|(outpoint, _)| outpoint.clone()).collect();
+    let (result, after, _undo_log) = connect_block(block, witnesses, subset, height, None, network)?;
+    if !matches!(result, ValidationResult::Valid) {
+        return Ok((result, Vec::new(), Vec::new()));
+    }
+    let removed = before
+        .into_iter()
+        .filter(|outpoint| after.get(outpoint).is_none())
+        .collect();
+    let added = after
+        .iter()
+        .map(|(outpoint, utxo)| (outpoint.clone(), utxo.clone()))
+        .collect();
+    Ok((result, removed, added))
+}
+
+/// Copy-on-write store: immutable base plus overlay (`None` = spent)
+pub struct CowUtxoStore {
+    base: UtxoSet,
+    overlay: HashMap<OutPoint, Option<UTXO>>,
+    len: usize,
+}
+
+impl CowUtxoStore {
+    pub fn new(base: UtxoSet) -> Self {
+        let len = base.len();
+        Self {
+            base,
+            overlay: HashMap::new(),
+            len,
+        }
+    }
+
+    fn flatten(&mut self) {
+        for (outpoint, entry) in self.overlay.drain() {
+            match entry {
+                Some(utxo) => {
+                    self.base.insert(outpoint, utxo);
+                }
+                None => {
+                    self.base.remove(&outpoint);
+                }
+            }
+        }
+    }
+}
+
+impl UtxoStore for CowUtxoStore {
+    fn name(&self) -> &'static str {
+        "cow"
+    }
+
+    fn connect_block(
+        &mut self,
+        block: &Block,
+        witnesses: &[Witness],
+        height: u64,
+        network: Network,
+    ) -> Result<ValidationResult> {
+        let mut subset = UtxoSet::new();
+        for outpoint in touched_outpoints(block) {
+            if let Some(utxo) = self.get(&outpoint)? {
+                subset.insert(outpoint, utxo);
+            }
+        }
+
+        let (result, removed, added) = connect_subset(block, witnesses, height, network, subset)?;
+        for outpoint in removed {
+            if self.get(&outpoint)?.is_some() {
+                self.len -= 1;
+            }
+            self.overlay.insert(outpoint, None);
+        }
+        for (outpoint, utxo) in added {
+            if self.get(&outpoint)?.is_none() {
+                self.len += 1;
+            }
+            self.overlay.insert(outpoint, Some(utxo));
+        }
+
+        if self.overlay.len() >= COW_FLATTEN_THRESHOLD {
+            self.flatten();
+        }
+        Ok(result)
+    }
+
+    fn len(&self) -> usize {
+        self.len
+    }
+
+    fn get(&self, outpoint: &OutPoint) -> Result<Option<UTXO>> {
+        match self.overlay.get(outpoint) {
+            Some(entry) => Ok(entry.clone()),
+            None => Ok(self.base.get(outpoint).cloned()),
+        }
+    }
+
+    fn to_utxo_set(&self) -> Result<UtxoSet> {
+        let mut set = self.base.clone();
+        for (outpoint, entry) in &self.overlay {
+            match entry {
+                Some(utxo) => {
+                    set.insert(outpoint.clone(), utxo.clone());
+                }
+                None => {
+                    set.remove(outpoint);
+                }
+            }
+        }
+        Ok(set)
+    }
+}
+
+/// Disk-backed store: coins in an append-only file, index in memory
+///
+/// Spent coins are dropped from the index only; the file is never compacted
+/// because each store lives for a single chunk.
+pub struct DiskUtxoStore {
+    path: PathBuf,
+    file: File,
+    index: HashMap<OutPoint, (u64, u32)>,
+    write_offset: u64,
+}
+
+static DISK_STORE_COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+impl DiskUtxoStore {
+    /// Create a fresh store file in `dir`, seeded with `initial`
+    pub fn create(dir: &std::path::Path, initial: UtxoSet) -> Result<Self> {
+        std::fs::create_dir_all(dir)
+            .with_context(|| format!("Failed to create UTXO directory {}", dir.display()))?;
+        let path = dir.join(format!(
+            "utxo-{}-{}.dat",
+            std::process::id(),
+            DISK_STORE_COUNTER.fetch_add(1, Ordering::Relaxed)
+        ));
+        let file = OpenOptions::new()
+            .create(true)
+            .truncate(true)
+            .read(true)
+            .write(true)
+            .open(&path)
+            .with_context(|| format!("Failed to create UTXO file {}", path.display()))?;
+
+        let mut store = Self {
+            path,
+            file,
+            index: HashMap::with_capacity(initial.len()),
+            write_offset: 0,
+        };
+        for (outpoint, utxo) in initial.iter() {
+            store.put(outpoint.clone(), utxo)?;
+        }
+        Ok(store)
+    }
+
+    fn put(&mut self, outpoint: OutPoint, utxo: &UTXO) -> Result<()> {
+        let record = encode_utxo(utxo);
+        self.file.seek(SeekFrom::Start(self.write_offset))?;
+        self.file.write_all(&record)?;
+        self.index.insert(outpoint, (self.write_offset, record.len() as u32));
+        self.write_offset += record.len() as u64;
+        Ok(())
+    }
+
+    /// Apply a connected block's changes. Every added coin is written, even one whose
+    /// outpoint is already indexed: a BIP30 duplicate coinbase replaces the older coin,
+    /// as it does in the Memory and Cow stores.
+    fn apply(&mut self, removed: Vec<OutPoint>, added: Vec<(OutPoint, UTXO)>) -> Result<()> {
+        for outpoint in removed {
+            self.index.remove(&outpoint);
+        }
+        for (outpoint, utxo) in added {
+            self.put(outpoint, &utxo)?;
+        }
+        Ok(())
+    }
+
+    fn read_at(&self, offset: u64, len: u32) -> Result<UTXO> {
+        let mut buf = vec![0u8; len as usize];
+        let mut file = &self.file;
+        file.seek(SeekFrom::Start(offset))?;
+        file.read_exact(&mut buf)?;
+        decode_utxo(&buf)
+    }
+}
+
+impl Drop for DiskUtxoStore {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+impl UtxoStore for DiskUtxoStore {
+    fn name(&self) -> &'static str {
+        "disk"
+    }
+
+    fn connect_block(
+        &mut self,
+        block: &Block,
+        witnesses: &[Witness],
+        height: u64,
+        network: Network,
+    ) -> Result<ValidationResult> {
+        let mut subset = UtxoSet::new();
+        for outpoint in touched_outpoints(block) {
+            if let Some(utxo) = self.get(&outpoint)? {
+                subset.insert(outpoint, utxo);
+            }
+        }
+
+        let (result, removed, added) = connect_subset(block, witnesses, height, network, subset)?;
+        self.apply(removed, added)?;
+        Ok(result)
+    }
+
+    fn len(&self) -> usize {
+        self.index.len()
+    }
+
+    fn get(&self, outpoint: &OutPoint) -> Result<Option<UTXO>> {
+        match self.index.get(outpoint) {
+            Some(&(offset, len)) => Ok(Some(self.read_at(offset, len)?)),
+            None => Ok(None),
+        }
+    }
+
+    fn to_utxo_set(&self) -> Result<UtxoSet> {
+        let mut set = UtxoSet::new();
+        for (outpoint, &(offset, len)) in &self.index {
+            set.insert(outpoint.clone(), self.read_at(offset, len)?);
+        }
+        Ok(set)
+    }
+}
+
+/// Record layout: value (i64) | height (u64) | is_coinbase (u8) | script_len (u32) | script
+pub(crate) fn encode_utxo(utxo: &UTXO) -> Vec<u8> {
+    let script: &[u8] = &utxo.script_pubkey;
+    let mut buf = Vec::with_capacity(21 + script.len());
+    buf.extend_from_slice(&(utxo.value as i64).to_le_bytes());
+    buf.extend_from_slice(&(utxo.height as u64).to_le_bytes());
+    buf.push(utxo.is_coinbase as u8);
+    buf.extend_from_slice(&(script.len() as u32).to_le_bytes());
+    buf.extend_from_slice(script);
+    buf
+}
+
+pub(crate) fn decode_utxo(buf: &[u8]) -> Result<UTXO> {
+    if buf.len() < 21 {
+        anyhow::bail!("UTXO record too short: {} bytes", buf.len());
+    }
+    let value = i64::from_le_bytes(buf[0..8].try_into()?);
+    let height = u64::from_le_bytes(buf[8..16].try_into()?);
+    let is_coinbase = buf[16] != 0;
+    let script_len = u32::from_le_bytes(buf[17..21].try_into()?) as usize;
+    let script = buf
+        .get(21..21 + script_len)
+        .context("UTXO record script truncated")?
+        .to_vec();
+    Ok(UTXO {
+        value: value as _,
+        script_pubkey: script.into(),
+        height: height as _,
+        is_coinbase,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bench_fixtures::{build_block, signed_spends, SpendKind};
+    use crate::deferred_utxo::same_coin;
+
+    fn coin(script: &[u8], height: u64, is_coinbase: bool) -> UTXO {
+        UTXO {
+            value: 5_000_000_000,
+            script_pubkey: script.to_vec().into(),
+            height: height as _,
+            is_coinbase,
+        }
+    }
+
+    fn temp_dir(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("blvm-utxo-backend-{}-{}", name, std::process::id()))
+    }
+
+    #[test]
+    fn test_utxo_record_roundtrip() {
+        for utxo in [coin(&[], 0, false), coin(&[0x51], 91_842, true), coin(&[0xab; 10_000], u32::MAX as u64, false)] {
+            let record = encode_utxo(&utxo);
+            assert!(same_coin(&decode_utxo(&record).unwrap(), &utxo));
+            assert!(decode_utxo(&record[..record.len() - 1]).is_err());
+        }
+        assert!(decode_utxo(&[0u8; 20]).is_err());
+    }
+
+    #[test]
+    fn test_disk_store_overwrites_duplicate_outpoint() {
+        let outpoint = OutPoint { hash: [7; 32], index: 0 };
+        let mut initial = UtxoSet::new();
+        initial.insert(outpoint.clone(), coin(&[0x51], 91_812, true));
+        let dir = temp_dir("overwrite");
+        let mut store = DiskUtxoStore::create(&dir, initial).unwrap();
+
+        // A duplicate coinbase re-creates the same outpoint at a later height
+        store.apply(Vec::new(), vec![(outpoint.clone(), coin(&[0x51], 91_842, true))]).unwrap();
+        assert!(same_coin(&store.get(&outpoint).unwrap().unwrap(), &coin(&[0x51], 91_842, true)));
+        assert_eq!(store.len(), 1);
+
+        store.apply(vec![outpoint.clone()], Vec::new()).unwrap();
+        assert!(store.get(&outpoint).unwrap().is_none() && store.is_empty());
+        drop(store);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_backends_agree_over_block_sequence() {
+        // Coinbase-only blocks have no witness data, so they are valid before segwit
+        let coinbase_only = |height: u64| {
+            let (block, _, _) = build_block(Vec::new(), height);
+            (block, vec![Vec::new()])
+        };
+        let (spend_block, _, pre_state) = build_block(signed_spends(SpendKind::P2pkh, 2), 91_813);
+        let spend_witnesses = vec![Vec::new(); spend_block.transactions.len()];
+        let (duplicated, duplicated_witnesses) = coinbase_only(91_812);
+        let blocks = [
+            (duplicated.clone(), duplicated_witnesses.clone(), 91_812),
+            (spend_block, spend_witnesses, 91_813),
+            // Same coinbase txid again, as at mainnet height 91842 (BIP30 exception)
+            (duplicated, duplicated_witnesses, 91_842),
+        ];
+
+        let dir = temp_dir("equivalence");
+        let backends = [UtxoBackend::Memory, UtxoBackend::Cow, UtxoBackend::Disk(dir.clone())];
+        let mut stores: Vec<Box<dyn UtxoStore>> =
+            backends.iter().map(|backend| backend.create(pre_state.clone()).unwrap()).collect();
+        for (block, witnesses, height) in &blocks {
+            let results: Vec<String> = stores
+                .iter_mut()
+                .map(|store| match store.connect_block(block, witnesses, *height, Network::Mainnet).unwrap() {
+                    ValidationResult::Valid => "valid".to_string(),
+                    ValidationResult::Invalid(msg) => format!("invalid: {}", msg),
+                })
+                .collect();
+            assert!(results.iter().all(|r| *r == results[0]), "height {}: {:?}", height, results);
+
+            let memory = stores[0].to_utxo_set().unwrap();
+            for store in &stores[1..] {
+                assert_eq!(store.len(), memory.len(), "{} at height {}", store.name(), height);
+                for (outpoint, utxo) in memory.iter() {
+                    let stored = store.get(outpoint).unwrap();
+                    assert!(stored.is_some_and(|stored| same_coin(&stored, utxo)), "{} at height {}", store.name(), height);
+                }
+            }
+        }
+        drop(stores);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,249 @@
+//! UTXO Set Diff Against Core
+//!
+//! An aggregate UTXO hash mismatch says the sets drifted, not where. This
+//! rebuilds BLVM's UTXO set at the height of a Core chainstate (from the
+//! nearest stored checkpoint, see `checkpoint_store`) and compares the two
+//! entry by entry, reporting coins only Core has, coins only BLVM has, and
+//! coins whose value, script, coinbase flag or height differ.
+
+use anyhow::{Context, Result};
+use blvm_consensus::serialization::block::deserialize_block_with_witnesses;
+use blvm_consensus::types::{Network, ValidationResult};
+use blvm_consensus::{OutPoint, UtxoSet, UTXO};
+use sha2::{Digest, Sha256};
+use std::fmt;
+use std::path::Path;
+
+use crate::chainstate_reader::ChainstateReader;
+use crate::block_source::BlockSource;
+use crate::checkpoint_store::CheckpointStore;
+use crate::parallel_differential::{get_block_data, BlockDataSource};
+use crate::utxo_backend::UtxoBackend;
+
+/// Entries printed per category
+const REPORT_LIMIT: usize = 20;
+
+/// A coin present in both sets with different contents
+#[derive(Debug, Clone)]
+pub struct CoinMismatch {
+    pub outpoint: OutPoint,
+    /// Fields that differ (value, script, coinbase, height)
+    pub fields: Vec<&'static str>,
+    pub blvm: UTXO,
+    pub core: UTXO,
+}
+
+/// Entry-by-entry difference between BLVM's and Core's UTXO sets
+#[derive(Debug, Default)]
+pub struct UtxoDiff {
+    pub height: u64,
+    /// In Core's set but not BLVM's
+    pub missing: Vec<(OutPoint, UTXO)>,
+    /// In BLVM's set but not Core's
+    pub extra: Vec<(OutPoint, UTXO)>,
+    pub mismatched: Vec<CoinMismatch>,
+}
+
+impl UtxoDiff {
+    pub fn is_empty(&self) -> bool {
+        self.missing.is_empty() && self.extra.is_empty() && self.mismatched.is_empty()
+    }
+}
+
+/// `txid:vout` with the txid in display byte order
+pub fn format_outpoint(outpoint: &OutPoint) -> String {
+    let mut txid = outpoint.hash;
+    txid.reverse();
+    format!("{}:{}", hex::encode(txid), outpoint.index)
+}
+
+fn format_coin(utxo: &UTXO) -> String {
+    let script: &[u8] = &utxo.script_pubkey;
+    format!(
+        "value={} height={} coinbase={} script={}",
+        utxo.value,
+        utxo.height,
+        utxo.is_coinbase,
+        hex::encode(script)
+    )
+}
+
+impl fmt::Display for UtxoDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "UTXO diff at height {}: {} missing, {} extra, {} mismatched",
+            self.height,
+            self.missing.len(),
+            self.extra.len(),
+            self.mismatched.len()
+        )?;
+        for (outpoint, utxo) in self.missing.iter().take(REPORT_LIMIT) {
+            writeln!(f, "   - missing in BLVM {} ({})", format_outpoint(outpoint), format_coin(utxo))?;
+        }
+        for (outpoint, utxo) in self.extra.iter().take(REPORT_LIMIT) {
+            writeln!(f, "   + extra in BLVM {} ({})", format_outpoint(outpoint), format_coin(utxo))?;
+        }
+        for mismatch in self.mismatched.iter().take(REPORT_LIMIT) {
+            writeln!(f, "   ~ {} differs in {}", format_outpoint(&mismatch.outpoint), mismatch.fields.join(", "))?;
+            writeln!(f, "       BLVM: {}", format_coin(&mismatch.blvm))?;
+            writeln!(f, "       Core: {}", format_coin(&mismatch.core))?;
+        }
+        Ok(())
+    }
+}
+
+/// Compare two UTXO sets coin by coin
+pub fn diff_utxo_sets(blvm: &UtxoSet, core: &UtxoSet, height: u64) -> UtxoDiff {
+    let mut diff = UtxoDiff {
+        height,
+        ..Default::default()
+    };
+    for (outpoint, core_coin) in core.iter() {
+        let Some(blvm_coin) = blvm.get(outpoint) else {
+            diff.missing.push((outpoint.clone(), core_coin.clone()));
+            continue;
+        };
+        let blvm_script: &[u8] = &blvm_coin.script_pubkey;
+        let core_script: &[u8] = &core_coin.script_pubkey;
+        let fields: Vec<&'static str> = [
+            ("value", blvm_coin.value as i64 != core_coin.value as i64),
+            ("script", blvm_script != core_script),
+            ("coinbase", blvm_coin.is_coinbase != core_coin.is_coinbase),
+            ("height", blvm_coin.height as u64 != core_coin.height as u64),
+        ]
+        .into_iter()
+        .filter_map(|(field, differs)| differs.then_some(field))
+        .collect();
+        if !fields.is_empty() {
+            diff.mismatched.push(CoinMismatch {
+                outpoint: outpoint.clone(),
+                fields,
+                blvm: blvm_coin.clone(),
+                core: core_coin.clone(),
+            });
+        }
+    }
+    for (outpoint, blvm_coin) in blvm.iter() {
+        if core.get(outpoint).is_none() {
+            diff.extra.push((outpoint.clone(), blvm_coin.clone()));
+        }
+    }
+    diff
+}
+
+/// Rebuild BLVM's set at the chainstate's height and diff it against Core's
+///
+/// The chainstate height is the highest coin height (the tip's coinbase is
+/// always unspent); the block connected last must hash to the chainstate's
+/// best block, so a chainstate from a different chain is caught.
+pub async fn run_utxo_diff(
+    chainstate_dir: &Path,
+    block_source: &BlockDataSource,
+    checkpoints: &CheckpointStore,
+    utxo_backend: &UtxoBackend,
+) -> Result<UtxoDiff> {
+    println!("📥 Reading Core's UTXO set from {}", chainstate_dir.display());
+    let mut reader = ChainstateReader::open(chainstate_dir)?;
+    let best_block = reader.best_block()?;
+    let core_set = reader.read_utxo_set()?;
+    let height = core_set
+        .iter()
+        .map(|(_, utxo)| utxo.height as u64)
+        .max()
+        .context("Chainstate is empty")?;
+    println!("   ✅ {} coins at height {}", core_set.len(), height);
+
+    // Nearest checkpoint at or below the target, if it was generated from genesis
+    let base = if checkpoints.start_height() == 0 {
+        checkpoints.entries().iter().map(|e| e.height).filter(|&h| h <= height).max()
+    } else {
+        None
+    };
+    let (initial, from) = match base {
+        Some(checkpoint) => {
+            println!("📌 Starting BLVM from checkpoint at height {}", checkpoint);
+            (checkpoints.load(checkpoint)?, checkpoint + 1)
+        }
+        None => (UtxoSet::new(), 0),
+    };
+    let mut store = utxo_backend.create(initial)?;
+
+    println!("🔧 Connecting blocks {} to {} with BLVM", from, height);
+    let mut connect = |block_height: u64, block_bytes: &[u8]| -> Result<[u8; 32]> {
+        let (block, witnesses) = deserialize_block_with_witnesses(block_bytes)
+            .map_err(|e| anyhow::anyhow!("Failed to deserialize block {}: {}", block_height, e))?;
+        match store.connect_block(&block, &witnesses, block_height, Network::Mainnet)? {
+            ValidationResult::Valid => {}
+            ValidationResult::Invalid(msg) => {
+                anyhow::bail!("BLVM rejected block {} while rebuilding the UTXO set: {}", block_height, msg)
+            }
+        }
+        if block_height % 10_000 == 0 {
+            println!("   📊 Height {} ({} coins)", block_height, store.len());
+        }
+        Ok(Sha256::digest(Sha256::digest(&block_bytes[..80.min(block_bytes.len())])).into())
+    };
+    let mut last_hash = None;
+    match block_source.iter_sequential(from, (height + 1 - from) as usize)? {
+        Some(blocks) => {
+            for (offset, block) in blocks.enumerate() {
+                last_hash = Some(connect(from + offset as u64, &block?)?);
+            }
+        }
+        None => {
+            for block_height in from..=height {
+                last_hash = Some(connect(block_height, &get_block_data(block_source, block_height).await?)?);
+            }
+        }
+    }
+    if let Some(hash) = last_hash {
+        if hash != best_block {
+            anyhow::bail!("Block {} doesn't match the chainstate's best block (chainstate is on another chain)", height);
+        }
+    }
+
+    let diff = diff_utxo_sets(&store.to_utxo_set()?, &core_set, height);
+    if diff.is_empty() {
+        println!("✅ UTXO sets match at height {} ({} coins)", height, core_set.len());
+    } else {
+        println!("❌ {}", diff);
+    }
+    Ok(diff)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn coin(value: i64, coinbase: bool) -> UTXO {
+        UTXO {
+            value: value as _,
+            script_pubkey: vec![0x51].into(),
+            height: 7,
+            is_coinbase: coinbase,
+        }
+    }
+
+    #[test]
+    fn test_diff_reports_each_drift() {
+        let outpoint = |n: u8| OutPoint { hash: [n; 32], index: 0 };
+        let mut blvm = UtxoSet::new();
+        let mut core = UtxoSet::new();
+        blvm.insert(outpoint(1), coin(10, false));
+        core.insert(outpoint(1), coin(10, false));
+        blvm.insert(outpoint(2), coin(20, false));
+        core.insert(outpoint(2), coin(21, true));
+        core.insert(outpoint(3), coin(30, false));
+        blvm.insert(outpoint(4), coin(40, false));
+
+        let diff = diff_utxo_sets(&blvm, &core, 7);
+        assert_eq!(diff.missing.len(), 1);
+        assert_eq!(diff.missing[0].0, outpoint(3));
+        assert_eq!(diff.extra.len(), 1);
+        assert_eq!(diff.extra[0].0, outpoint(4));
+        assert_eq!(diff.mismatched.len(), 1);
+        assert_eq!(diff.mismatched[0].fields, vec!["value", "coinbase"]);
+        assert!(diff.to_string().contains("1 missing, 1 extra, 1 mismatched"));
+    }
+}
@@ -0,0 +1,478 @@
+//! Core-Compatible UTXO Snapshots
+//!
+//! Writes a checkpointed `UtxoSet` in the format of Core's `dumptxoutset`
+//! (assumeutxo snapshot version 2, Core 28+), giving an independent way to
+//! cross-check BLVM's state against Core:
+//!
+//! - Coins are written in Core's LevelDB order (txid, then output index),
+//!   leaving out unspendable ones Core never stores, so the file is
+//!   byte-for-byte what `dumptxoutset` writes at the same height when the two
+//!   sets agree: comparing SHA-256 sums is a full-set check.
+//! - The file loads into Core with `loadtxoutset`, as long as the height is
+//!   one of the snapshot heights compiled into Core's chain parameters.
+//!
+//! Layout: `"utxo" 0xff`, version (u16), network magic, base block hash,
+//! coin count (u64), then per transaction its txid, CompactSize coin count and
+//! `CompactSize(vout) | coin` for each unspent output. A coin is
+//! `VARINT(height * 2 + coinbase)` followed by Core's compressed amount and
+//! script, the same encoding as the chainstate database's values.
+
+use anyhow::{Context, Result};
+use blvm_consensus::{OutPoint, UtxoSet, UTXO};
+use sha2::{Digest, Sha256};
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Read, Write};
+use std::path::Path;
+
+use crate::bench_fixtures::write_compact_size;
+use crate::checkpoint_merge::{is_unspendable, MAX_SCRIPT_SIZE};
+use crate::checkpoint_store::CheckpointStore;
+use crate::parallel_differential::{get_block_data, BlockDataSource, BlockFileNetwork};
+
+pub const SNAPSHOT_MAGIC: [u8; 5] = *b"utxo\xff";
+pub const SNAPSHOT_VERSION: u16 = 2;
+
+/// Script encodings below this are special forms (see `compress_script`)
+const SPECIAL_SCRIPTS: u64 = 6;
+
+/// Snapshot header
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SnapshotMetadata {
+    pub message_start: [u8; 4],
+    /// Internal byte order
+    pub base_block_hash: [u8; 32],
+    pub coins_count: u64,
+}
+
+/// Result of `export_snapshot`
+#[derive(Debug, Clone)]
+pub struct SnapshotExport {
+    pub height: u64,
+    /// Display byte order, as `dumptxoutset` reports it
+    pub base_block_hash: String,
+    pub coins_count: u64,
+    pub bytes: u64,
+    /// SHA-256 of the whole file
+    pub sha256: String,
+}
+
+/// Export the checkpoint at `height` of `network`'s store to `path`
+///
+/// The header carries `network`'s message start, which `loadtxoutset` checks
+/// against the node's chain.
+pub async fn export_snapshot(
+    store: &CheckpointStore,
+    height: u64,
+    network: BlockFileNetwork,
+    block_source: &BlockDataSource,
+    path: &Path,
+) -> Result<SnapshotExport> {
+    // A store started mid-chain lacks every coin created before its start
+    if store.start_height() != 0 {
+        anyhow::bail!(
+            "Checkpoints start at height {}, not genesis; their UTXO sets aren't complete",
+            store.start_height()
+        );
+    }
+    let utxo_set = store.load(height)?;
+    let block_bytes = get_block_data(block_source, height).await?;
+    let base_block_hash = crate::block_hash::block_hash(&block_bytes)
+        .with_context(|| format!("Block {} is shorter than a header", height))?;
+
+    let file = File::create(path).with_context(|| format!("Failed to create {}", path.display()))?;
+    let mut writer = HashingWriter::new(BufWriter::new(file));
+    let coins_count = write_snapshot(&mut writer, *network.magic_bytes(), base_block_hash, &utxo_set)?;
+    let (bytes, sha256) = writer.finish()?;
+    Ok(SnapshotExport {
+        height,
+        base_block_hash: crate::block_hash::display_hex(&base_block_hash),
+        coins_count,
+        bytes,
+        sha256,
+    })
+}
+
+/// Write `utxo_set` as a snapshot based on `base_block_hash`; returns the number of coins
+///
+/// Unspendable coins (OP_RETURN, oversized scripts) are skipped, as Core never stores them.
+pub fn write_snapshot(
+    writer: &mut impl Write,
+    message_start: [u8; 4],
+    base_block_hash: [u8; 32],
+    utxo_set: &UtxoSet,
+) -> Result<u64> {
+    let mut outpoints: Vec<&OutPoint> = utxo_set
+        .iter()
+        .filter(|(_, coin)| !is_unspendable(&coin.script_pubkey))
+        .map(|(outpoint, _)| outpoint)
+        .collect();
+    outpoints.sort_unstable_by_key(|o| (o.hash, o.index as u32));
+
+    let mut buf = Vec::with_capacity(1 << 16);
+    buf.extend_from_slice(&SNAPSHOT_MAGIC);
+    buf.extend_from_slice(&SNAPSHOT_VERSION.to_le_bytes());
+    buf.extend_from_slice(&message_start);
+    buf.extend_from_slice(&base_block_hash);
+    buf.extend_from_slice(&(outpoints.len() as u64).to_le_bytes());
+
+    for group in outpoints.chunk_by(|a, b| a.hash == b.hash) {
+        buf.extend_from_slice(&group[0].hash);
+        write_compact_size(&mut buf, group.len() as u64);
+        for outpoint in group {
+            let coin = utxo_set.get(outpoint).context("Outpoint vanished while writing")?;
+            write_compact_size(&mut buf, outpoint.index as u64);
+            encode_coin(&mut buf, coin);
+        }
+        if buf.len() >= 1 << 16 {
+            writer.write_all(&buf)?;
+            buf.clear();
+        }
+    }
+    writer.write_all(&buf)?;
+    writer.flush()?;
+    Ok(outpoints.len() as u64)
+}
+
+/// Read a snapshot (e.g. Core's `dumptxoutset` output) into a `UtxoSet`
+pub fn read_snapshot(path: &Path) -> Result<(SnapshotMetadata, UtxoSet)> {
+    let file = File::open(path).with_context(|| format!("Failed to open {}", path.display()))?;
+    let mut reader = BufReader::new(file);
+    let metadata = read_metadata(&mut reader)?;
+
+    let mut utxo_set = UtxoSet::new();
+    let mut read = 0u64;
+    while read < metadata.coins_count {
+        let hash: [u8; 32] = read_array(&mut reader).context("Snapshot truncated")?;
+        let coins = read_compact_size(&mut reader)?;
+        if coins == 0 || read + coins > metadata.coins_count {
+            anyhow::bail!("Snapshot has a bad coin count for {}", crate::block_hash::display_hex(&hash));
+        }
+        for _ in 0..coins {
+            let index = read_compact_size(&mut reader)?;
+            let coin = decode_coin(&mut reader)
+                .with_context(|| format!("Corrupt coin {}:{}", crate::block_hash::display_hex(&hash), index))?;
+            utxo_set.insert(
+                OutPoint {
+                    hash,
+                    index: index as _,
+                },
+                coin,
+            );
+        }
+        read += coins;
+    }
+    if reader.read(&mut [0u8])? != 0 {
+        anyhow::bail!("Snapshot has data after its {} coins", metadata.coins_count);
+    }
+    Ok((metadata, utxo_set))
+}
+
+/// Read just a snapshot's header
+pub fn read_metadata(reader: &mut impl Read) -> Result<SnapshotMetadata> {
+    let magic: [u8; 5] = read_array(reader).context("Snapshot truncated")?;
+    if magic != SNAPSHOT_MAGIC {
+        anyhow::bail!("Not a UTXO snapshot (bad magic; pre-28.0 dumps have no header and aren't supported)");
+    }
+    let version = u16::from_le_bytes(read_array(reader)?);
+    if version != SNAPSHOT_VERSION {
+        anyhow::bail!("Unsupported snapshot version {} (expected {})", version, SNAPSHOT_VERSION);
+    }
+    Ok(SnapshotMetadata {
+        message_start: read_array(reader)?,
+        base_block_hash: read_array(reader)?,
+        coins_count: u64::from_le_bytes(read_array(reader)?),
+    })
+}
+
+/// SHA-256 of a file, e.g. Core's dump to compare with an export
+pub fn file_sha256(path: &Path) -> Result<String> {
+    let mut file = File::open(path).with_context(|| format!("Failed to open {}", path.display()))?;
+    let mut hasher = Sha256::new();
+    std::io::copy(&mut file, &mut hasher)?;
+    Ok(hex::encode(hasher.finalize()))
+}
+
+/// Hashes and counts everything written through it
+struct HashingWriter<W: Write> {
+    inner: W,
+    hasher: Sha256,
+    bytes: u64,
+}
+
+impl<W: Write> HashingWriter<W> {
+    fn new(inner: W) -> Self {
+        Self {
+            inner,
+            hasher: Sha256::new(),
+            bytes: 0,
+        }
+    }
+
+    fn finish(mut self) -> Result<(u64, String)> {
+        self.inner.flush()?;
+        Ok((self.bytes, hex::encode(self.hasher.finalize())))
+    }
+}
+
+impl<W: Write> Write for HashingWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let written = self.inner.write(buf)?;
+        self.hasher.update(&buf[..written]);
+        self.bytes += written as u64;
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+fn read_array<const N: usize>(reader: &mut impl Read) -> Result<[u8; N]> {
+    let mut bytes = [0u8; N];
+    reader.read_exact(&mut bytes)?;
+    Ok(bytes)
+}
+
+fn read_compact_size(reader: &mut impl Read) -> Result<u64> {
+    let [first] = read_array(reader).context("Snapshot truncated")?;
+    Ok(match first {
+        0xfd => u16::from_le_bytes(read_array(reader)?) as u64,
+        0xfe => u32::from_le_bytes(read_array(reader)?) as u64,
+        0xff => u64::from_le_bytes(read_array(reader)?),
+        n => n as u64,
+    })
+}
+
+/// Core's VARINT (MSB base-128 with the "+1 per continuation" offset)
+pub(crate) fn read_varint(reader: &mut impl Read) -> Result<u64> {
+    let mut n: u64 = 0;
+    loop {
+        let [byte] = read_array(reader).context("VARINT truncated")?;
+        n = n
+            .checked_mul(128)
+            .context("VARINT overflow")?
+            | (byte & 0x7f) as u64;
+        if byte & 0x80 == 0 {
+            return Ok(n);
+        }
+        n = n.checked_add(1).context("VARINT overflow")?;
+    }
+}
+
+pub(crate) fn write_varint(buf: &mut Vec<u8>, mut n: u64) {
+    let mut tmp = [0u8; 10];
+    let mut len = 0;
+    loop {
+        tmp[len] = (n & 0x7f) as u8 | if len > 0 { 0x80 } else { 0x00 };
+        if n <= 0x7f {
+            break;
+        }
+        n = (n >> 7) - 1;
+        len += 1;
+    }
+    buf.extend(tmp[..=len].iter().rev());
+}
+
+/// Core's CompressAmount
+fn compress_amount(mut n: u64) -> u64 {
+    if n == 0 {
+        return 0;
+    }
+    let mut e = 0;
+    while n % 10 == 0 && e < 9 {
+        n /= 10;
+        e += 1;
+    }
+    if e < 9 {
+        let d = n % 10;
+        n /= 10;
+        1 + (n * 9 + d - 1) * 10 + e
+    } else {
+        1 + (n - 1) * 10 + 9
+    }
+}
+
+/// Inverse of Core's CompressAmount
+pub(crate) fn decompress_amount(mut x: u64) -> u64 {
+    if x == 0 {
+        return 0;
+    }
+    x -= 1;
+    let mut e = x % 10;
+    x /= 10;
+    let mut n = if e < 9 {
+        let d = x % 9 + 1;
+        x /= 9;
+        x * 10 + d
+    } else {
+        x + 1
+    };
+    while e > 0 {
+        n *= 10;
+        e -= 1;
+    }
+    n
+}
+
+/// Core's ScriptCompression special forms: P2PKH, P2SH and P2PK (compressed, or uncompressed on the curve)
+fn compress_script(script: &[u8]) -> Option<Vec<u8>> {
+    match script {
+        [0x76, 0xa9, 0x14, hash @ .., 0x88, 0xac] if hash.len() == 20 => Some([&[0x00][..], hash].concat()),
+        [0xa9, 0x14, hash @ .., 0x87] if hash.len() == 20 => Some([&[0x01][..], hash].concat()),
+        [0x21, prefix @ (0x02 | 0x03), x @ .., 0xac] if x.len() == 32 => Some([&[*prefix][..], x].concat()),
+        [0x41, pubkey @ .., 0xac] if pubkey.len() == 65 && pubkey[0] == 0x04 => {
+            secp256k1::PublicKey::from_slice(pubkey).ok()?;
+            Some([&[0x04 | (pubkey[64] & 1)][..], &pubkey[1..33]].concat())
+        }
+        _ => None,
+    }
+}
+
+/// Inverse of Core's ScriptCompression
+fn decompress_script(reader: &mut impl Read) -> Result<Vec<u8>> {
+    let size = read_varint(reader)?;
+    Ok(match size {
+        0 => {
+            let hash: [u8; 20] = read_array(reader).context("Coin truncated")?;
+            [&[0x76, 0xa9, 0x14][..], &hash, &[0x88, 0xac]].concat()
+        }
+        1 => {
+            let hash: [u8; 20] = read_array(reader).context("Coin truncated")?;
+            [&[0xa9, 0x14][..], &hash, &[0x87]].concat()
+        }
+        2 | 3 => {
+            let x: [u8; 32] = read_array(reader).context("Coin truncated")?;
+            [&[0x21, size as u8][..], &x, &[0xac]].concat()
+        }
+        4 | 5 => {
+            let x: [u8; 32] = read_array(reader).context("Coin truncated")?;
+            let compressed = [&[size as u8 - 2][..], &x].concat();
+            let pubkey = secp256k1::PublicKey::from_slice(&compressed).context("Invalid compressed P2PK key")?;
+            [&[0x41][..], &pubkey.serialize_uncompressed(), &[0xac]].concat()
+        }
+        _ => {
+            let len = (size - SPECIAL_SCRIPTS) as usize;
+            if len > MAX_SCRIPT_SIZE {
+                std::io::copy(&mut reader.take(len as u64), &mut std::io::sink())?;
+                vec![0x6a] // OP_RETURN
+            } else {
+                let mut script = vec![0u8; len];
+                reader.read_exact(&mut script).context("Coin truncated")?;
+                script
+            }
+        }
+    })
+}
+
+/// Core's `Coin` serialization
+pub(crate) fn encode_coin(buf: &mut Vec<u8>, coin: &UTXO) {
+    write_varint(buf, (coin.height as u64) * 2 + coin.is_coinbase as u64);
+    write_varint(buf, compress_amount(coin.value as u64));
+    let script: &[u8] = &coin.script_pubkey;
+    match compress_script(script) {
+        Some(compressed) => buf.extend_from_slice(&compressed),
+        None => {
+            write_varint(buf, script.len() as u64 + SPECIAL_SCRIPTS);
+            buf.extend_from_slice(script);
+        }
+    }
+}
+
+/// Decode a coin (the chainstate's deobfuscated value, or one snapshot entry)
+pub(crate) fn decode_coin(reader: &mut impl Read) -> Result<UTXO> {
+    let code = read_varint(reader)?;
+    let amount = decompress_amount(read_varint(reader)?);
+    let script = decompress_script(reader)?;
+    Ok(UTXO {
+        value: amount as _,
+        script_pubkey: script.into(),
+        height: (code >> 1) as _,
+        is_coinbase: code & 1 == 1,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn coin(value: u64, script: Vec<u8>, height: u64, is_coinbase: bool) -> UTXO {
+        UTXO {
+            value: value as _,
+            script_pubkey: script.into(),
+            height: height as _,
+            is_coinbase,
+        }
+    }
+
+    #[test]
+    fn test_coin_encoding_matches_core() {
+        // 50 BTC coinbase P2PKH at height 1: the chainstate bytes Core writes
+        let p2pkh = [&[0x76, 0xa9, 0x14][..], &[0xab; 20], &[0x88, 0xac]].concat();
+        let mut buf = Vec::new();
+        encode_coin(&mut buf, &coin(5_000_000_000, p2pkh, 1, true));
+        assert_eq!(&buf[..3], &[0x03, 0x32, 0x00]);
+        assert_eq!(buf.len(), 23);
+
+        for amount in [0, 1, 9, 10, 546, 100_000_000, 2_099_999_997_690_000, 1_234_567_891] {
+            assert_eq!(decompress_amount(compress_amount(amount)), amount);
+        }
+        for n in [0, 127, 128, 255, 16_511, u32::MAX as u64] {
+            let mut buf = Vec::new();
+            write_varint(&mut buf, n);
+            assert_eq!(read_varint(&mut buf.as_slice()).unwrap(), n);
+        }
+        let mut buf = Vec::new();
+        write_varint(&mut buf, 128);
+        assert_eq!(buf, [0x80, 0x00]);
+    }
+
+    #[test]
+    fn test_snapshot_round_trip() {
+        let genesis_key = hex::decode(concat!(
+            "04678afdb0fe5548271967f1a67130b7105cd6a828e03909a67962e0ea1f61deb6",
+            "49f6bc3f4cef38c4f35504e51ec112de5c384df7ba0b8d578a4c702b6bf11d5f"
+        ))
+        .unwrap();
+        // (script, whether Core stores it in a special form)
+        let scripts = [
+            ([&[0xa9, 0x14][..], &[0x11; 20], &[0x87]].concat(), true),
+            ([&[0x21, 0x02][..], &[0x22; 32], &[0xac]].concat(), true),
+            (vec![0x00, 0x14, 0x33, 0x44], false),
+            // Uncompressed P2PK is only compressed when the key is on the curve
+            ([&[0x41, 0x04][..], &[0x55; 64], &[0xac]].concat(), false),
+            ([&[0x41][..], &genesis_key, &[0xac]].concat(), true),
+        ];
+        let mut utxo_set = UtxoSet::new();
+        for (i, (script, special)) in scripts.into_iter().enumerate() {
+            assert_eq!(compress_script(&script).is_some(), special, "script {}", i);
+            let outpoint = OutPoint {
+                hash: [i as u8 % 2; 32],
+                index: (i * 300) as _,
+            };
+            utxo_set.insert(outpoint, coin(1_000 * i as u64 + 1, script, 700_000 + i as u64, i == 0));
+        }
+        // Core never stores an OP_RETURN output, so neither does the snapshot
+        let op_return = OutPoint { hash: [0x99; 32], index: 0 };
+        utxo_set.insert(op_return.clone(), coin(0, vec![0x6a, 0x04, 0xde, 0xad, 0xbe, 0xef], 700_010, false));
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("utxo.dat");
+        let mut file = File::create(&path).unwrap();
+        let testnet = *BlockFileNetwork::Testnet.magic_bytes();
+        assert_eq!(write_snapshot(&mut file, testnet, [0x77; 32], &utxo_set).unwrap(), 5);
+
+        let (metadata, loaded) = read_snapshot(&path).unwrap();
+        assert_eq!(metadata.base_block_hash, [0x77; 32]);
+        assert_eq!(metadata.message_start, testnet);
+        assert_eq!(metadata.coins_count, 5);
+        assert_eq!(loaded.len(), 5);
+        assert!(loaded.get(&op_return).is_none());
+        for (outpoint, expected) in utxo_set.iter().filter(|(outpoint, _)| **outpoint != op_return) {
+            let got = loaded.get(outpoint).unwrap();
+            let (got_script, expected_script): (&[u8], &[u8]) = (&got.script_pubkey, &expected.script_pubkey);
+            assert_eq!(got_script, expected_script);
+            assert_eq!(got.value as u64, expected.value as u64);
+            assert_eq!((got.height as u64, got.is_coinbase), (expected.height as u64, expected.is_coinbase));
+        }
+    }
+}
@@ -0,0 +1,199 @@
+//! Known-Divergence Waivers
+//!
+//! A divergence that has been triaged but needs a long fix in blvm-consensus
+//! shouldn't fail every CI run until then. A waivers file (`BLVM_WAIVERS`)
+//! lists such divergences by height or by the txid of a transaction in the
+//! block, each with a reason and an optional expiry date:
+//!
+//! ```json
+//! [
+//!   { "height": 91842, "reason": "BIP30 duplicate coinbase, tracked in #123", "expires": "2026-12-31" },
+//!   { "txid": "e3bf3d07...", "reason": "sighash edge case, fix in review" }
+//! ]
+//! ```
+//!
+//! A waived divergence is still recorded everywhere (summary, reproducers,
+//! run record, HTML report) but doesn't fail the run. A waiver holds through
+//! its expiry date (UTC); expired waivers no longer match and are reported so
+//! they get renewed or removed.
+
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use std::path::Path;
+
+/// One triaged divergence
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Waiver {
+    /// Block height the divergence occurs at
+    #[serde(default)]
+    pub height: Option<u64>,
+    /// Display-order txid of a transaction in the divergent block
+    #[serde(default)]
+    pub txid: Option<String>,
+    pub reason: String,
+    /// Last day (`YYYY-MM-DD`, UTC) the waiver applies
+    #[serde(default)]
+    pub expires: Option<String>,
+}
+
+impl Waiver {
+    /// Short description for logs and reports
+    pub fn describe(&self) -> String {
+        match self.expires.as_deref() {
+            Some(expires) => format!("{} (until {})", self.reason, expires),
+            None => self.reason.clone(),
+        }
+    }
+
+    fn matches(&self, height: u64, txids: &[String]) -> bool {
+        self.height.is_none_or(|h| h == height)
+            && self.txid.as_ref().is_none_or(|txid| txids.iter().any(|t| t.eq_ignore_ascii_case(txid)))
+    }
+}
+
+/// The waivers of a run, with each one's expiry as days since the Unix epoch
+#[derive(Debug, Clone, Default)]
+pub struct Waivers {
+    entries: Vec<(Waiver, Option<i64>)>,
+}
+
+impl Waivers {
+    /// Parse a waivers file (a JSON array of waivers)
+    pub fn parse(json: &str) -> Result<Self> {
+        let waivers: Vec<Waiver> = serde_json::from_str(json).context("Waivers must be a JSON array of {height?, txid?, reason, expires?}")?;
+        let mut entries = Vec::with_capacity(waivers.len());
+        for (idx, waiver) in waivers.into_iter().enumerate() {
+            if waiver.height.is_none() && waiver.txid.is_none() {
+                anyhow::bail!("Waiver {} ('{}') has neither a height nor a txid", idx, waiver.reason);
+            }
+            if let Some(txid) = &waiver.txid {
+                if txid.len() != 64 || hex::decode(txid).is_err() {
+                    anyhow::bail!("Waiver {} has an invalid txid '{}'", idx, txid);
+                }
+            }
+            let expires = waiver
+                .expires
+                .as_deref()
+                .map(|date| parse_date(date).with_context(|| format!("Waiver {} has an invalid expiry '{}'", idx, date)))
+                .transpose()?;
+            entries.push((waiver, expires));
+        }
+        Ok(Self { entries })
+    }
+
+    pub fn load(path: &Path) -> Result<Self> {
+        let json = std::fs::read_to_string(path).with_context(|| format!("Failed to read waivers file {}", path.display()))?;
+        Self::parse(&json).with_context(|| format!("Invalid waivers file {}", path.display()))
+    }
+
+    /// From the file named by `BLVM_WAIVERS` (None if unset)
+    pub fn from_env() -> Result<Option<Self>> {
+        match std::env::var("BLVM_WAIVERS") {
+            Ok(path) => Self::load(Path::new(&path)).map(Some),
+            Err(_) => Ok(None),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Whether any waiver matches on txid (so a divergent block's txids must be computed)
+    pub fn needs_txids(&self) -> bool {
+        self.entries.iter().any(|(waiver, _)| waiver.txid.is_some())
+    }
+
+    /// The unexpired waiver covering a divergence at `height` whose block has `txids`
+    pub fn find(&self, height: u64, txids: &[String]) -> Option<&Waiver> {
+        let today = today();
+        self.entries
+            .iter()
+            .find(|(waiver, expires)| expires.is_none_or(|day| day >= today) && waiver.matches(height, txids))
+            .map(|(waiver, _)| waiver)
+    }
+
+    /// Waivers past their expiry date
+    pub fn expired(&self) -> Vec<&Waiver> {
+        let today = today();
+        self.entries
+            .iter()
+            .filter(|(_, expires)| expires.is_some_and(|day| day < today))
+            .map(|(waiver, _)| waiver)
+            .collect()
+    }
+}
+
+/// Days since 1970-01-01 of a `YYYY-MM-DD` date
+fn parse_date(date: &str) -> Result<i64> {
+    let mut parts = date.splitn(3, '-');
+    let mut next = || -> Result<i64> { Ok(parts.next().context("expected YYYY-MM-DD")?.parse()?) };
+    let (year, month, day) = (next()?, next()?, next()?);
+    let days_in_month = match month {
+        1 | 3 | 5 | 7 | 8 | 10 | 12 => 31,
+        4 | 6 | 9 | 11 => 30,
+        2 if year % 4 == 0 && (year % 100 != 0 || year % 400 == 0) => 29,
+        2 => 28,
+        _ => anyhow::bail!("month {} out of range", month),
+    };
+    if !(1..=days_in_month).contains(&day) {
+        anyhow::bail!("day {} out of range", day);
+    }
+    // Howard Hinnant's days_from_civil
+    let y = if month <= 2 { year - 1 } else { year };
+    let era = y.div_euclid(400);
+    let yoe = y - era * 400;
+    let doy = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    Ok(era * 146_097 + doe - 719_468)
+}
+
+/// Today (UTC) as days since 1970-01-01
+fn today() -> i64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| (d.as_secs() / 86_400) as i64)
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TXID: &str = "4a5e1e4baab89f3a32518a88c31bc87f618f76673e2cc77ab2127b7afdeda33b";
+
+    #[test]
+    fn test_parse_date() {
+        assert_eq!(parse_date("1970-01-01").unwrap(), 0);
+        assert_eq!(parse_date("2000-03-01").unwrap(), 11_017);
+        assert_eq!(parse_date("2024-02-29").unwrap(), 19_782);
+        assert!(parse_date("2023-02-29").is_err());
+        assert!(parse_date("2024-13-01").is_err());
+        assert!(parse_date("soon").is_err());
+    }
+
+    #[test]
+    fn test_waivers_match_unexpired_entries() {
+        let json = format!(
+            r#"[
+                {{ "height": 100, "reason": "old", "expires": "2000-01-01" }},
+                {{ "height": 100, "reason": "current", "expires": "9999-12-31" }},
+                {{ "txid": "{}", "reason": "by txid" }}
+            ]"#,
+            TXID.to_uppercase()
+        );
+        let waivers = Waivers::parse(&json).unwrap();
+        assert!(waivers.needs_txids());
+        assert_eq!(waivers.find(100, &[]).map(|w| w.reason.as_str()), Some("current"));
+        assert_eq!(waivers.find(7, &[TXID.to_string()]).map(|w| w.reason.as_str()), Some("by txid"));
+        assert!(waivers.find(7, &[]).is_none());
+        assert_eq!(waivers.expired().len(), 1);
+
+        assert!(Waivers::parse(r#"[{ "reason": "nothing to match" }]"#).is_err());
+        assert!(Waivers::parse(r#"[{ "txid": "abcd", "reason": "short" }]"#).is_err());
+    }
+}